@@ -0,0 +1,180 @@
+use std::{
+    fs::File,
+    io::BufReader,
+    path::{Path, PathBuf},
+};
+
+use fxhash::hash64;
+use image::{
+    codecs::{gif::GifDecoder, webp::WebPDecoder},
+    AnimationDecoder, DynamicImage, ImageFormat, Rgb, RgbImage, RgbaImage,
+};
+use log::error;
+use tokio::task::spawn_blocking;
+
+use crate::{
+    dependencies::{Dependency, SingletonFor},
+    dirs::Dirs,
+    modal::{animation_frame::AnimationFrameModal, manager::ModalManager},
+    photo_manager::PhotoManager,
+    project_settings::ProjectSettingsManager,
+};
+
+pub const ANIMATION_EXTENSIONS: [&str; 2] = ["gif", "webp"];
+
+#[derive(Debug, thiserror::Error)]
+pub enum AnimationStillError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Image error: {0}")]
+    Image(#[from] image::ImageError),
+    #[error("{0:?} has no frame {1}")]
+    MissingFrame(PathBuf, usize),
+    #[error("{0:?} isn't a GIF or WebP file")]
+    Unsupported(PathBuf),
+}
+
+/// The frames of an animated GIF or WebP waiting for one to be picked as its still
+pub struct AnimationFrames {
+    pub source: PathBuf,
+    pub frames: Vec<RgbaImage>,
+}
+
+impl AnimationFrames {
+    /// The frame offered first. Animations often start on a blank or fading frame so the middle
+    /// one is more likely to show what it's about.
+    pub fn default_frame(&self) -> usize {
+        self.frames.len() / 2
+    }
+}
+
+pub fn is_animation(path: &Path) -> bool {
+    path.extension().is_some_and(|extension| {
+        ANIMATION_EXTENSIONS
+            .iter()
+            .any(|animation| extension.eq_ignore_ascii_case(animation))
+    })
+}
+
+/// Every frame of the animation, fully composited. Still WebP files give a single frame.
+pub fn decode_frames(path: &Path) -> Result<Vec<RgbaImage>, AnimationStillError> {
+    let extension = path
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let reader = BufReader::new(File::open(path)?);
+
+    let frames = match extension.as_str() {
+        "gif" => GifDecoder::new(reader)?.into_frames().collect_frames()?,
+        "webp" => {
+            let decoder = WebPDecoder::new(reader)?;
+            if !decoder.has_animation() {
+                return Ok(vec![DynamicImage::from_decoder(decoder)?.to_rgba8()]);
+            }
+            decoder.into_frames().collect_frames()?
+        }
+        _ => return Err(AnimationStillError::Unsupported(path.to_path_buf())),
+    };
+
+    Ok(frames
+        .into_iter()
+        .map(|frame| frame.into_buffer())
+        .collect())
+}
+
+/// Where the still of `frame` is kept. The name is made from the source and the frame so the
+/// same choice always gives the same file.
+pub fn still_path(source: &Path, frame: usize) -> PathBuf {
+    let stem = source
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    Dirs::Stills.path().join(format!(
+        "{}-{}-frame{}.jpg",
+        stem,
+        hash64(&source.to_string_lossy()),
+        frame + 1
+    ))
+}
+
+/// Saves the frame as a JPEG so it goes through the same pipeline as any other photo.
+/// Transparent areas become white, like the page they're placed on.
+pub fn write_still(
+    source: &Path,
+    frame_index: usize,
+    frame: &RgbaImage,
+) -> Result<PathBuf, AnimationStillError> {
+    let path = still_path(source, frame_index);
+
+    let still = RgbImage::from_fn(frame.width(), frame.height(), |x, y| {
+        let [r, g, b, a] = frame.get_pixel(x, y).0;
+        let alpha = a as f32 / 255.0;
+        let over_white = |channel: u8| (channel as f32 * alpha + 255.0 * (1.0 - alpha)) as u8;
+        Rgb([over_white(r), over_white(g), over_white(b)])
+    });
+    still.save_with_format(&path, ImageFormat::Jpeg)?;
+
+    Ok(path)
+}
+
+/// The still of `frame`, extracted again if it's been removed since it was picked
+pub fn extract(source: &Path, frame: usize) -> Result<PathBuf, AnimationStillError> {
+    let path = still_path(source, frame);
+    if path.exists() {
+        return Ok(path);
+    }
+
+    let frames = decode_frames(source)?;
+    let still = frames
+        .get(frame)
+        .ok_or_else(|| AnimationStillError::MissingFrame(source.to_path_buf(), frame))?;
+    write_still(source, frame, still)
+}
+
+/// Adds a still of each animation to the library. Animations a frame was already picked for in
+/// this project use that frame again, the rest are shown in a frame picker. Files with only one
+/// frame are added as they are.
+pub async fn import(sources: Vec<PathBuf>) {
+    let mut stills = Vec::new();
+    let mut to_pick = Vec::new();
+
+    for source in sources {
+        let picked = Dependency::<ProjectSettingsManager>::get().with_lock(|manager| {
+            manager
+                .project_settings
+                .animation_frames
+                .get(&source)
+                .copied()
+        });
+
+        let task_source = source.clone();
+        let result = spawn_blocking(move || match picked {
+            Some(frame) => extract(&task_source, frame).map(Ok),
+            None => decode_frames(&task_source).and_then(|frames| {
+                if frames.len() == 1 {
+                    write_still(&task_source, 0, &frames[0]).map(Ok)
+                } else {
+                    Ok(Err(frames))
+                }
+            }),
+        })
+        .await;
+
+        match result {
+            Ok(Ok(Ok(still))) => stills.push(still),
+            Ok(Ok(Err(frames))) if !frames.is_empty() => {
+                to_pick.push(AnimationFrames { source, frames })
+            }
+            Ok(Ok(Err(_))) => error!("{:?} has no frames", source),
+            Ok(Err(err)) => error!("Failed to read {:?}: {:?}", source, err),
+            Err(err) => error!("Frame extraction for {:?} failed: {:?}", source, err),
+        }
+    }
+
+    if !to_pick.is_empty() {
+        ModalManager::push(AnimationFrameModal::new(to_pick));
+    }
+
+    PhotoManager::import_files(stills).await;
+}
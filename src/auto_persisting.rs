@@ -1,3 +1,8 @@
+use std::{
+    sync::{Mutex, OnceLock},
+    thread::JoinHandle,
+};
+
 pub trait PersistentModifiable<T> {
     type Error;
     type Modification;
@@ -8,27 +13,61 @@ pub trait PersistentModifiable<T> {
 }
 
 pub struct AutoPersisting<T: PersistentModifiable<T>> {
-    value: Option<T>,
+    // Set by the first read, which can happen behind a shared lock
+    value: OnceLock<T>,
+    // A load started ahead of the first read, taken over by whichever read comes first
+    loading: Mutex<Option<JoinHandle<Result<T, T::Error>>>>,
 }
 
 impl<T: PersistentModifiable<T>> AutoPersisting<T> {
     pub fn new() -> Self {
-        Self { value: None }
+        Self {
+            value: OnceLock::new(),
+            loading: Mutex::new(None),
+        }
     }
 
-    pub fn read(&mut self) -> Result<&T, T::Error> {
-        if self.value.is_none() {
-            self.value = Some(T::load()?);
-        }
-        Ok(self.value.as_ref().unwrap())
+    pub fn read(&self) -> Result<&T, T::Error> {
+        self.ensure_loaded()
     }
 
     pub fn modify(&mut self, modification: T::Modification) -> Result<(), T::Error> {
-        if self.value.is_none() {
-            self.value = Some(T::load()?);
-        }
-        self.value.as_mut().unwrap().modify(modification)?;
-        self.value.as_ref().unwrap().save()?;
+        self.ensure_loaded()?;
+        let value = self.value.get_mut().unwrap();
+        value.modify(modification)?;
+        value.save()?;
         Ok(())
     }
+
+    // Waits for a background load if one was started, it's usually done by the time it's needed
+    fn ensure_loaded(&self) -> Result<&T, T::Error> {
+        if let Some(value) = self.value.get() {
+            return Ok(value);
+        }
+
+        let loading = self.loading.lock().unwrap().take();
+        let value = match loading {
+            // Loaded again here if the background load panicked
+            Some(loading) => loading.join().unwrap_or_else(|_| T::load()),
+            None => T::load(),
+        }?;
+
+        // Two reads racing to load both get whichever value was set first
+        Ok(self.value.get_or_init(|| value))
+    }
+}
+
+impl<T> AutoPersisting<T>
+where
+    T: PersistentModifiable<T> + Send + 'static,
+    T::Error: Send + 'static,
+{
+    /// Starts reading the value from disk on a background thread so the first read, usually
+    /// during the first frame, doesn't have to wait on it
+    pub fn load_in_background(&mut self) {
+        let loading = self.loading.get_mut().unwrap();
+        if self.value.get().is_none() && loading.is_none() {
+            *loading = Some(std::thread::spawn(T::load));
+        }
+    }
 }
@@ -0,0 +1,222 @@
+use chrono::{DateTime, Local, Utc};
+use egui::{Color32, FontId, Pos2, Rect};
+use strum_macros::{Display, EnumIter};
+
+use crate::{
+    book_theme::{ThemeColorRole, ThemeFontRole},
+    dependencies::{Dependency, SingletonFor},
+    photo::Photo,
+    photo_manager::PhotoManager,
+    project_settings::ProjectSettingsManager,
+    widget::{
+        canvas::CanvasState,
+        canvas_info::{
+            layers::{
+                CanvasText, Layer, LayerContent, LayerTransformEditState, TextHorizontalAlignment,
+                TextVerticalAlignment,
+            },
+            quick_layout::Layout,
+        },
+    },
+};
+
+// Height of the date header as a fraction of the page height
+const HEADER_HEIGHT: f32 = 0.12;
+const HEADER_FONT_SCALE: f32 = 0.4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Display, EnumIter)]
+pub enum PlacementGrouping {
+    Day,
+    Event,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AutoPlacementOptions {
+    pub grouping: PlacementGrouping,
+    // Photos taken further apart than this start a new event
+    pub event_gap_hours: f32,
+    pub max_photos_per_page: usize,
+    pub date_headers: bool,
+}
+
+impl Default for AutoPlacementOptions {
+    fn default() -> Self {
+        Self {
+            grouping: PlacementGrouping::Day,
+            event_gap_hours: 3.0,
+            max_photos_per_page: 4,
+            date_headers: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PhotoGroup {
+    // None for photos without a capture date, which are collected into a single group at the end
+    pub date: Option<DateTime<Utc>>,
+    pub photos: Vec<Photo>,
+}
+
+impl PhotoGroup {
+    fn header(&self) -> Option<String> {
+        self.date
+            .map(|date| date.with_timezone(&Local).format("%B %-d, %Y").to_string())
+    }
+}
+
+/// A page auto placement will make, before its photos are laid out
+#[derive(Debug, Clone)]
+pub struct PlannedPage {
+    pub header: Option<String>,
+    pub photos: Vec<Photo>,
+}
+
+/// Lays photos out across new pages in the order they were taken
+pub struct AutoPlacement;
+
+impl AutoPlacement {
+    pub fn group(photos: Vec<Photo>, options: &AutoPlacementOptions) -> Vec<PhotoGroup> {
+        let mut dated: Vec<(DateTime<Utc>, Photo)> = Vec::new();
+        let mut undated: Vec<Photo> = Vec::new();
+
+        for photo in photos {
+            match PhotoManager::capture_date(&photo) {
+                Some(date) => dated.push((date, photo)),
+                None => undated.push(photo),
+            }
+        }
+
+        dated.sort_by_key(|(date, _)| *date);
+
+        let event_gap = chrono::Duration::seconds((options.event_gap_hours * 3600.0) as i64);
+
+        let mut groups: Vec<PhotoGroup> = Vec::new();
+        let mut last_date: Option<DateTime<Utc>> = None;
+
+        for (date, photo) in dated {
+            let starts_group = match last_date {
+                None => true,
+                Some(last_date) => match options.grouping {
+                    PlacementGrouping::Day => {
+                        last_date.with_timezone(&Local).date_naive()
+                            != date.with_timezone(&Local).date_naive()
+                    }
+                    PlacementGrouping::Event => date - last_date > event_gap,
+                },
+            };
+
+            if starts_group {
+                groups.push(PhotoGroup {
+                    date: Some(date),
+                    photos: Vec::new(),
+                });
+            }
+
+            if let Some(group) = groups.last_mut() {
+                group.photos.push(photo);
+            }
+
+            last_date = Some(date);
+        }
+
+        if !undated.is_empty() {
+            groups.push(PhotoGroup {
+                date: None,
+                photos: undated,
+            });
+        }
+
+        groups
+    }
+
+    /// Splits each group over as many pages as it needs. Groups never share a page.
+    pub fn plan(groups: &[PhotoGroup], options: &AutoPlacementOptions) -> Vec<PlannedPage> {
+        groups
+            .iter()
+            .flat_map(|group| {
+                group
+                    .photos
+                    .chunks(options.max_photos_per_page.max(1))
+                    .enumerate()
+                    .map(|(index, photos)| PlannedPage {
+                        // Only the first page of a group gets the header
+                        header: if options.date_headers && index == 0 {
+                            group.header()
+                        } else {
+                            None
+                        },
+                        photos: photos.to_vec(),
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    pub fn pages(planned: Vec<PlannedPage>) -> Vec<CanvasState> {
+        planned
+            .into_iter()
+            .map(|planned| Self::page(&planned.photos, planned.header))
+            .collect()
+    }
+
+    fn page(photos: &[Photo], header: Option<String>) -> CanvasState {
+        let mut page = CanvasState::new();
+
+        for photo in photos {
+            page.add_photo(photo.clone());
+        }
+
+        if let Some(layout) = Layout::default_for(page.quick_layout_order.len()) {
+            layout.apply(&mut page);
+            page.last_quick_layout = Some(layout);
+        }
+
+        if let Some(header) = header {
+            Self::add_header(&mut page, header);
+        }
+
+        page
+    }
+
+    fn add_header(page: &mut CanvasState, header: String) {
+        let page_size = page.page.value.size_pixels();
+        let header_height = page_size.y * HEADER_HEIGHT;
+        let margin = page_size * page.quick_layout_parameters.margin;
+
+        // Shrink the photos towards the bottom of the page to make room above them
+        let scale = (page_size.y - header_height) / page_size.y;
+        let anchor = Pos2::new(page_size.x / 2.0, page_size.y);
+        for layer in page.layers.values_mut() {
+            let rect = layer.transform_state.rect;
+            layer.transform_state.rect = Rect::from_min_max(
+                anchor + (rect.min - anchor) * scale,
+                anchor + (rect.max - anchor) * scale,
+            );
+        }
+
+        let mut layer = Layer::new_text_layer();
+        layer.name = "Date".to_string();
+        layer.transform_state.rect = Rect::from_min_max(
+            Pos2::new(margin.x, margin.y),
+            Pos2::new(page_size.x - margin.x, header_height),
+        );
+        layer.transform_edit_state = LayerTransformEditState::from(&layer.transform_state);
+
+        let mut text = CanvasText::new(
+            header,
+            header_height * HEADER_FONT_SCALE,
+            FontId::default(),
+            Color32::BLACK,
+            TextHorizontalAlignment::Center,
+            TextVerticalAlignment::Center,
+        );
+        text.font_role = Some(ThemeFontRole::Heading);
+        text.color_role = Some(ThemeColorRole::Text);
+        Dependency::<ProjectSettingsManager>::get()
+            .with_lock(|manager| manager.project_settings.theme.apply_to_text(&mut text));
+        layer.content = LayerContent::Text(text);
+
+        // Not added to the quick layout order so later layouts only move the photos
+        page.layers.insert(layer.id, layer);
+    }
+}
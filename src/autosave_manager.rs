@@ -1,10 +1,16 @@
-use std::path::PathBuf;
+use std::{
+    path::PathBuf,
+    time::{Duration, Instant},
+};
 
 use log::{error, info};
 
 use crate::{
     auto_persisting::{AutoPersisting, PersistentModifiable},
+    debug::AutoSaveTiming,
     dependencies::{Dependency, Singleton, SingletonFor},
+    error_sink::ErrorSink,
+    photo_database::PhotoDatabase,
     photo_manager::PhotoManager,
     project::v1::{Project, ProjectError},
     scene::organize_edit_scene::OrganizeEditScene,
@@ -33,6 +39,8 @@ pub struct AutoSave {
 pub struct AutoSaveManager {
     last_save_time: Option<std::time::Instant>,
     current_save_task: Option<tokio::task::JoinHandle<()>>,
+    // How long the last save task took to write the project, set by the task when it's done
+    last_save_duration: Option<Duration>,
 }
 
 impl AutoSaveManager {
@@ -40,6 +48,18 @@ impl AutoSaveManager {
         Self {
             last_save_time: None,
             current_save_task: None,
+            last_save_duration: None,
+        }
+    }
+
+    pub fn timing(&self) -> AutoSaveTiming {
+        AutoSaveTiming {
+            since_last_save: self.last_save_time.map(|time| time.elapsed()),
+            last_save_duration: self.last_save_duration,
+            saving: self
+                .current_save_task
+                .as_ref()
+                .is_some_and(|task| !task.is_finished()),
         }
     }
 
@@ -61,13 +81,39 @@ impl AutoSaveManager {
             }
         };
 
-        if let Some(active_project) = auto_save.active_project {
-            Dependency::<Session>::get().with_lock_mut(|session| {
+        // Without a saved project to go back to the restored changes are just kept
+        let should_warn = Dependency::<Session>::get().with_lock_mut(|session| {
+            if let Some(active_project) = auto_save.active_project {
                 session.active_project = Some(active_project);
+            }
+
+            let has_saved_project = match session.mark_active_project_saved() {
+                Ok(()) => session.active_project.is_some(),
+                Err(err) => {
+                    error!("Error reading active project: {:?}", err);
+                    false
+                }
+            };
+
+            has_saved_project && session.has_unsaved_changes(&auto_save.project)
+        });
+
+        // Photos that haven't changed since the project was last saved don't need to be read again
+        if let Some(active_project) =
+            Dependency::<Session>::get().with_lock(|session| session.active_project.clone())
+        {
+            Dependency::<PhotoManager>::get().with_lock_mut(|photo_manager| {
+                photo_manager.set_photo_database(PhotoDatabase::load(&active_project));
             });
         }
 
-        Some(auto_save.project.into())
+        let mut scene: OrganizeEditScene = auto_save.project.clone().into();
+
+        if should_warn {
+            scene.warn_recovered_changes(auto_save.project);
+        }
+
+        Some(scene)
     }
 
     pub fn auto_save_if_needed(
@@ -113,6 +159,7 @@ impl AutoSaveManager {
 fn create_save_task(root_scene: OrganizeEditScene, path: PathBuf) -> tokio::task::JoinHandle<()> {
     tokio::spawn(async move {
         info!("Auto saving project to {}", path.display());
+        let started_at = Instant::now();
 
         let auto_save: AutoSave =
             Dependency::<PhotoManager>::get().with_lock(|photo_manager| AutoSave {
@@ -124,14 +171,18 @@ fn create_save_task(root_scene: OrganizeEditScene, path: PathBuf) -> tokio::task
         let data = match serde_json::to_string_pretty(&auto_save) {
             Ok(data) => data,
             Err(err) => {
-                error!("Error saving auto save: {:?}", err);
+                ErrorSink::report("Couldn't auto save", err);
                 return;
             }
         };
 
         if let Err(e) = std::fs::write(path, data) {
-            error!("Error saving auto save: {:?}", e);
+            ErrorSink::report("Couldn't auto save", e);
         }
+
+        Dependency::<AutoSaveManager>::get().with_lock_mut(|auto_save_manager| {
+            auto_save_manager.last_save_duration = Some(started_at.elapsed());
+        });
     })
 }
 
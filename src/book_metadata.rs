@@ -0,0 +1,103 @@
+use std::borrow::Cow;
+
+use strum::IntoEnumIterator;
+use strum_macros::{Display, EnumIter};
+
+use crate::{
+    dependencies::{Dependency, SingletonFor},
+    project_settings::ProjectSettingsManager,
+};
+
+/// Details about the book that text layers can show through variables, so e.g. the title is
+/// typed once and the cover, spine and title page all follow it
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BookMetadata {
+    pub title: String,
+    pub author: String,
+    pub date_range: String,
+    pub location: String,
+    // Name of the section the page being drawn is in, set per page with `with_section` and
+    // not part of the project settings
+    pub section: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Display, EnumIter)]
+pub enum TextVariable {
+    Title,
+    Author,
+    #[strum(to_string = "Date Range")]
+    DateRange,
+    Location,
+    Section,
+}
+
+impl TextVariable {
+    /// What's typed into a text layer to show the variable
+    pub fn token(&self) -> &'static str {
+        match self {
+            TextVariable::Title => "{title}",
+            TextVariable::Author => "{author}",
+            TextVariable::DateRange => "{date_range}",
+            TextVariable::Location => "{location}",
+            TextVariable::Section => "{section}",
+        }
+    }
+
+    /// Whether the variable is the same on every page and set in the book details. Sections
+    /// are named in the outline instead.
+    pub fn is_book_wide(&self) -> bool {
+        *self != TextVariable::Section
+    }
+}
+
+impl BookMetadata {
+    /// The metadata of the open project
+    pub fn current() -> BookMetadata {
+        Dependency::<ProjectSettingsManager>::get()
+            .with_lock(|manager| manager.project_settings.metadata.clone())
+    }
+
+    /// The metadata for a page in `section`
+    pub fn with_section(mut self, section: Option<&str>) -> BookMetadata {
+        self.section = section.unwrap_or_default().to_string();
+        self
+    }
+
+    pub fn value(&self, variable: TextVariable) -> &str {
+        match variable {
+            TextVariable::Title => &self.title,
+            TextVariable::Author => &self.author,
+            TextVariable::DateRange => &self.date_range,
+            TextVariable::Location => &self.location,
+            TextVariable::Section => &self.section,
+        }
+    }
+
+    pub fn value_mut(&mut self, variable: TextVariable) -> &mut String {
+        match variable {
+            TextVariable::Title => &mut self.title,
+            TextVariable::Author => &mut self.author,
+            TextVariable::DateRange => &mut self.date_range,
+            TextVariable::Location => &mut self.location,
+            TextVariable::Section => &mut self.section,
+        }
+    }
+
+    /// Replaces the variables in `text` with their values. Variables without a value are left
+    /// as they are so they stand out on the page instead of silently disappearing.
+    pub fn resolve<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        if !text.contains('{') {
+            return Cow::Borrowed(text);
+        }
+
+        let mut resolved = Cow::Borrowed(text);
+        for variable in TextVariable::iter() {
+            let value = self.value(variable);
+            if !value.is_empty() && resolved.contains(variable.token()) {
+                resolved = Cow::Owned(resolved.replace(variable.token(), value));
+            }
+        }
+
+        resolved
+    }
+}
@@ -0,0 +1,132 @@
+use std::{collections::HashSet, ops::RangeInclusive, path::Path};
+
+use indexmap::IndexMap;
+
+use crate::{
+    book_vendor::{BookVendor, VendorPricing},
+    id::PageId,
+    utils::csv_field,
+    widget::{canvas::CanvasState, canvas_info::layers::LayerContent},
+};
+
+/// What printing the book with a vendor would cost and whether the vendor can print it as is
+pub struct VendorEstimate {
+    pub vendor: BookVendor,
+    pub cost: f32,
+    // Page numbers past the most pages the vendor prints in a book
+    pub pages_over_limit: Option<RangeInclusive<usize>>,
+    // How many pages short of the vendor's minimum the book is, they're paid for either way
+    pub pages_short: usize,
+}
+
+impl VendorEstimate {
+    fn new(pricing: &VendorPricing, page_count: usize) -> Self {
+        let spec = pricing.vendor.spec();
+
+        Self {
+            vendor: pricing.vendor,
+            cost: pricing.estimate(page_count),
+            pages_over_limit: (page_count > spec.max_pages)
+                .then(|| spec.max_pages + 1..=page_count),
+            pages_short: spec.min_pages.saturating_sub(page_count),
+        }
+    }
+
+    pub fn warning(&self) -> Option<String> {
+        let spec = self.vendor.spec();
+
+        if let Some(pages) = &self.pages_over_limit {
+            let pages = if pages.start() == pages.end() {
+                format!("Page {} is", pages.start())
+            } else {
+                format!("Pages {}–{} are", pages.start(), pages.end())
+            };
+            Some(format!(
+                "{} past {}'s limit of {} pages",
+                pages, self.vendor, spec.max_pages
+            ))
+        } else if self.pages_short > 0 {
+            Some(format!(
+                "{} books have at least {} pages, {} more would be free",
+                self.vendor, spec.min_pages, self.pages_short
+            ))
+        } else {
+            None
+        }
+    }
+}
+
+/// Counts of what's in the book and what printing it would cost with each vendor. Exported as
+/// CSV.
+pub struct BookStatistics {
+    pub page_count: usize,
+    // Every placement, a photo used twice counts twice
+    pub photo_count: usize,
+    pub unique_photo_count: usize,
+    pub text_count: usize,
+    pub empty_page_count: usize,
+    pub estimates: Vec<VendorEstimate>,
+}
+
+impl BookStatistics {
+    pub fn new(pages: &IndexMap<PageId, CanvasState>, pricing: &[VendorPricing]) -> Self {
+        let photo_count = pages.values().map(|page| page.photo_paths().count()).sum();
+        let unique_photo_count = pages
+            .values()
+            .flat_map(|page| page.photo_paths())
+            .collect::<HashSet<_>>()
+            .len();
+        let text_count = pages
+            .values()
+            .flat_map(|page| page.layers.values())
+            .filter(|layer| {
+                matches!(
+                    layer.content,
+                    LayerContent::Text(_) | LayerContent::TemplateText { .. }
+                )
+            })
+            .count();
+        let empty_page_count = pages.values().filter(|page| page.layers.is_empty()).count();
+
+        Self {
+            page_count: pages.len(),
+            photo_count,
+            unique_photo_count,
+            text_count,
+            empty_page_count,
+            estimates: pricing
+                .iter()
+                .map(|pricing| VendorEstimate::new(pricing, pages.len()))
+                .collect(),
+        }
+    }
+
+    pub fn write(&self, path: &Path) -> std::io::Result<()> {
+        std::fs::write(path, self.csv())
+    }
+
+    fn csv(&self) -> String {
+        let mut csv = String::from("Statistic,Value\n");
+        for (name, value) in [
+            ("Pages", self.page_count),
+            ("Photos Placed", self.photo_count),
+            ("Different Photos", self.unique_photo_count),
+            ("Text Layers", self.text_count),
+            ("Empty Pages", self.empty_page_count),
+        ] {
+            csv.push_str(&format!("{},{}\n", name, value));
+        }
+
+        csv.push_str("\nVendor,Estimated Cost,Warning\n");
+        for estimate in &self.estimates {
+            csv.push_str(&format!(
+                "{},{:.2},{}\n",
+                csv_field(&estimate.vendor.to_string()),
+                estimate.cost,
+                csv_field(&estimate.warning().unwrap_or_default())
+            ));
+        }
+
+        csv
+    }
+}
@@ -0,0 +1,279 @@
+use std::path::Path;
+
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    id::{LayerId, PageId},
+    utils::csv_field,
+    widget::{canvas::CanvasState, canvas_info::layers::LayerContent},
+};
+
+#[derive(Error, Debug)]
+pub enum BookTextError {
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+
+    #[error("Serde error: {0}")]
+    SerdeError(#[from] serde_json::Error),
+
+    #[error("CSV error on line {line}: {message}")]
+    CsvError { line: usize, message: String },
+}
+
+/// One text layer of the book. The page number is for the proofreader, the layer id is what finds
+/// the layer again when the text comes back.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BookTextEntry {
+    // Starting at 1
+    pub page: usize,
+    pub layer_id: LayerId,
+    pub layer: String,
+    pub text: String,
+}
+
+/// A corrected text that differs from what's in the book
+#[derive(Debug, Clone, PartialEq)]
+pub struct BookTextChange {
+    pub page_id: PageId,
+    pub page: usize,
+    pub layer_id: LayerId,
+    pub layer: String,
+    pub before: String,
+    pub after: String,
+}
+
+/// The text of every text layer in the book, for proofreading outside the app. Written and read
+/// as CSV when the file has a `.csv` extension and as JSON otherwise.
+#[derive(Debug, Clone, Default)]
+pub struct BookText {
+    pub entries: Vec<BookTextEntry>,
+}
+
+impl BookText {
+    pub fn new(pages: &IndexMap<PageId, CanvasState>) -> Self {
+        let entries = pages
+            .values()
+            .enumerate()
+            .flat_map(|(page_index, page)| {
+                page.layers.values().filter_map(move |layer| {
+                    Self::layer_text(&layer.content).map(|text| BookTextEntry {
+                        page: page_index + 1,
+                        layer_id: layer.id,
+                        layer: layer.name.clone(),
+                        text: text.to_string(),
+                    })
+                })
+            })
+            .collect();
+
+        Self { entries }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), BookTextError> {
+        let contents = if Self::is_csv(path) {
+            self.csv()
+        } else {
+            serde_json::to_string_pretty(&self.entries)?
+        };
+
+        std::fs::write(path, contents)?;
+        Ok(())
+    }
+
+    pub fn read(path: &Path) -> Result<Self, BookTextError> {
+        let contents = std::fs::read_to_string(path)?;
+
+        let entries = if Self::is_csv(path) {
+            Self::parse_csv(&contents)?
+        } else {
+            serde_json::from_str(&contents)?
+        };
+
+        Ok(Self { entries })
+    }
+
+    /// The entries whose text differs from the book. A layer is looked for on the page the entry
+    /// names first and anywhere in the book after that, in case pages were moved since the
+    /// export. Also returns how many entries matched no layer.
+    pub fn changes(&self, pages: &IndexMap<PageId, CanvasState>) -> (Vec<BookTextChange>, usize) {
+        let mut changes = Vec::new();
+        let mut unmatched = 0;
+
+        for entry in &self.entries {
+            let on_named_page = entry
+                .page
+                .checked_sub(1)
+                .and_then(|index| pages.get_index(index))
+                .filter(|(_, page)| page.layers.contains_key(&entry.layer_id))
+                .map(|(page_id, _)| *page_id);
+
+            // Duplicated pages share layer ids, only a layer that's unique in the book is safe
+            let found = on_named_page.or_else(|| {
+                let mut matching = pages
+                    .iter()
+                    .filter(|(_, page)| page.layers.contains_key(&entry.layer_id));
+                match (matching.next(), matching.next()) {
+                    (Some((page_id, _)), None) => Some(*page_id),
+                    _ => None,
+                }
+            });
+
+            let Some((page_index, page_id, page)) =
+                found.and_then(|page_id| pages.get_full(&page_id))
+            else {
+                unmatched += 1;
+                continue;
+            };
+
+            let Some(layer) = page.layers.get(&entry.layer_id) else {
+                unmatched += 1;
+                continue;
+            };
+
+            let Some(before) = Self::layer_text(&layer.content) else {
+                unmatched += 1;
+                continue;
+            };
+
+            if before != entry.text {
+                changes.push(BookTextChange {
+                    page_id: *page_id,
+                    page: page_index + 1,
+                    layer_id: layer.id,
+                    layer: layer.name.clone(),
+                    before: before.to_string(),
+                    after: entry.text.clone(),
+                });
+            }
+        }
+
+        (changes, unmatched)
+    }
+
+    fn layer_text(content: &LayerContent) -> Option<&str> {
+        match content {
+            LayerContent::Text(text) | LayerContent::TemplateText { text, .. } => {
+                Some(text.text.as_str())
+            }
+            LayerContent::Photo(_)
+            | LayerContent::TemplatePhoto { .. }
+            | LayerContent::Decoration(_)
+            | LayerContent::Path(_) => None,
+        }
+    }
+
+    fn is_csv(path: &Path) -> bool {
+        path.extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("csv"))
+    }
+
+    fn csv(&self) -> String {
+        let mut csv = String::from("Page,Layer Id,Layer,Text\n");
+        for entry in &self.entries {
+            csv.push_str(&format!(
+                "{},{},{},{}\n",
+                entry.page,
+                entry.layer_id,
+                csv_field(&entry.layer),
+                csv_field(&entry.text)
+            ));
+        }
+        csv
+    }
+
+    fn parse_csv(contents: &str) -> Result<Vec<BookTextEntry>, BookTextError> {
+        let mut entries = Vec::new();
+
+        // The header row is skipped
+        for (line, row) in Self::csv_rows(contents)?.into_iter().skip(1) {
+            if row.iter().all(|field| field.trim().is_empty()) {
+                continue;
+            }
+
+            let [page, layer_id, layer, text] =
+                <[String; 4]>::try_from(row).map_err(|row| BookTextError::CsvError {
+                    line,
+                    message: format!("expected 4 fields, found {}", row.len()),
+                })?;
+
+            let number = |field: &str, name: &str| {
+                field
+                    .trim()
+                    .parse::<usize>()
+                    .map_err(|_| BookTextError::CsvError {
+                        line,
+                        message: format!("{} '{}' isn't a number", name, field),
+                    })
+            };
+
+            entries.push(BookTextEntry {
+                page: number(&page, "page")?,
+                layer_id: number(&layer_id, "layer id")?,
+                layer,
+                text,
+            });
+        }
+
+        Ok(entries)
+    }
+
+    // Splits CSV into rows of fields along with the line each row starts on. Quoted fields may
+    // contain commas, doubled quotes and line breaks.
+    fn csv_rows(contents: &str) -> Result<Vec<(usize, Vec<String>)>, BookTextError> {
+        let mut rows = Vec::new();
+        let mut row = Vec::new();
+        let mut field = String::new();
+        let mut in_quotes = false;
+        let mut line = 1;
+        let mut row_line = 1;
+
+        let mut chars = contents.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '"' if in_quotes => {
+                    if chars.peek() == Some(&'"') {
+                        field.push('"');
+                        chars.next();
+                    } else {
+                        in_quotes = false;
+                    }
+                }
+                '"' if field.is_empty() => in_quotes = true,
+                ',' if !in_quotes => row.push(std::mem::take(&mut field)),
+                '\r' if !in_quotes => {}
+                '\n' if !in_quotes => {
+                    row.push(std::mem::take(&mut field));
+                    rows.push((row_line, std::mem::take(&mut row)));
+                    line += 1;
+                    row_line = line;
+                }
+                _ => {
+                    if c == '\n' {
+                        line += 1;
+                    }
+                    field.push(c);
+                }
+            }
+        }
+
+        if in_quotes {
+            return Err(BookTextError::CsvError {
+                line: row_line,
+                message: "unterminated quoted field".to_string(),
+            });
+        }
+
+        if !field.is_empty() || !row.is_empty() {
+            row.push(field);
+            rows.push((row_line, row));
+        }
+
+        Ok(rows)
+    }
+}
@@ -0,0 +1,200 @@
+use std::sync::Arc;
+
+use egui::{Color32, FontFamily, FontId, Stroke};
+use strum_macros::{Display, EnumIter};
+
+use crate::{
+    dependencies::{Dependency, SingletonFor},
+    font_manager::FontManager,
+    widget::{
+        canvas::CanvasState,
+        canvas_info::layers::{CanvasText, LayerContent},
+    },
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, EnumIter)]
+pub enum ThemeFontRole {
+    Heading,
+    Body,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, EnumIter)]
+pub enum ThemeColorRole {
+    Primary,
+    Secondary,
+    Accent,
+    Text,
+    Background,
+}
+
+/// Default stroke for outlined layers, the color refers to the theme palette so it follows theme changes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ThemeStroke {
+    pub width: f32,
+    pub color: ThemeColorRole,
+}
+
+/// Fonts and colors shared by the whole book. Text layers that reference a role instead of a literal
+/// value are restyled whenever the theme changes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BookTheme {
+    pub name: String,
+    // None uses the default proportional font
+    pub heading_font: Option<String>,
+    pub body_font: Option<String>,
+    pub primary: Color32,
+    pub secondary: Color32,
+    pub accent: Color32,
+    pub text: Color32,
+    pub background: Color32,
+    pub stroke: ThemeStroke,
+}
+
+impl Default for BookTheme {
+    fn default() -> Self {
+        Self::builtin().remove(0)
+    }
+}
+
+impl BookTheme {
+    pub fn builtin() -> Vec<BookTheme> {
+        vec![
+            BookTheme {
+                name: "Classic".to_string(),
+                heading_font: Some("Georgia".to_string()),
+                body_font: None,
+                primary: Color32::from_rgb(34, 34, 34),
+                secondary: Color32::from_rgb(110, 110, 110),
+                accent: Color32::from_rgb(150, 30, 45),
+                text: Color32::BLACK,
+                background: Color32::WHITE,
+                stroke: ThemeStroke {
+                    width: 1.0,
+                    color: ThemeColorRole::Primary,
+                },
+            },
+            BookTheme {
+                name: "Modern".to_string(),
+                heading_font: Some("Helvetica Neue".to_string()),
+                body_font: Some("Helvetica Neue".to_string()),
+                primary: Color32::from_rgb(20, 20, 20),
+                secondary: Color32::from_rgb(140, 140, 140),
+                accent: Color32::from_rgb(0, 120, 215),
+                text: Color32::from_rgb(30, 30, 30),
+                background: Color32::from_rgb(248, 248, 248),
+                stroke: ThemeStroke {
+                    width: 2.0,
+                    color: ThemeColorRole::Accent,
+                },
+            },
+            BookTheme {
+                name: "Warm".to_string(),
+                heading_font: Some("Baskerville".to_string()),
+                body_font: Some("Georgia".to_string()),
+                primary: Color32::from_rgb(94, 60, 40),
+                secondary: Color32::from_rgb(176, 137, 104),
+                accent: Color32::from_rgb(214, 125, 62),
+                text: Color32::from_rgb(60, 40, 30),
+                background: Color32::from_rgb(250, 243, 232),
+                stroke: ThemeStroke {
+                    width: 1.5,
+                    color: ThemeColorRole::Secondary,
+                },
+            },
+            BookTheme {
+                name: "Midnight".to_string(),
+                heading_font: Some("Futura".to_string()),
+                body_font: None,
+                primary: Color32::from_rgb(230, 230, 240),
+                secondary: Color32::from_rgb(140, 150, 175),
+                accent: Color32::from_rgb(240, 190, 80),
+                text: Color32::from_rgb(235, 235, 245),
+                background: Color32::from_rgb(18, 22, 36),
+                stroke: ThemeStroke {
+                    width: 1.0,
+                    color: ThemeColorRole::Accent,
+                },
+            },
+        ]
+    }
+
+    pub fn color(&self, role: ThemeColorRole) -> Color32 {
+        match role {
+            ThemeColorRole::Primary => self.primary,
+            ThemeColorRole::Secondary => self.secondary,
+            ThemeColorRole::Accent => self.accent,
+            ThemeColorRole::Text => self.text,
+            ThemeColorRole::Background => self.background,
+        }
+    }
+
+    pub fn color_mut(&mut self, role: ThemeColorRole) -> &mut Color32 {
+        match role {
+            ThemeColorRole::Primary => &mut self.primary,
+            ThemeColorRole::Secondary => &mut self.secondary,
+            ThemeColorRole::Accent => &mut self.accent,
+            ThemeColorRole::Text => &mut self.text,
+            ThemeColorRole::Background => &mut self.background,
+        }
+    }
+
+    pub fn font(&self, role: ThemeFontRole) -> Option<&String> {
+        match role {
+            ThemeFontRole::Heading => self.heading_font.as_ref(),
+            ThemeFontRole::Body => self.body_font.as_ref(),
+        }
+    }
+
+    pub fn font_mut(&mut self, role: ThemeFontRole) -> &mut Option<String> {
+        match role {
+            ThemeFontRole::Heading => &mut self.heading_font,
+            ThemeFontRole::Body => &mut self.body_font,
+        }
+    }
+
+    // egui panics when laying out text with a family it doesn't know about so fonts that aren't
+    // installed on this machine fall back to the default font
+    pub fn font_family(&self, role: ThemeFontRole) -> FontFamily {
+        let Some(name) = self.font(role) else {
+            return FontFamily::Proportional;
+        };
+
+        let family = FontFamily::Name(Arc::from(name.as_str()));
+        let is_loaded = Dependency::<FontManager>::get()
+            .with_lock(|font_manager| font_manager.is_available(&family));
+
+        if is_loaded {
+            family
+        } else {
+            FontFamily::Proportional
+        }
+    }
+
+    pub fn stroke(&self) -> Stroke {
+        Stroke::new(self.stroke.width, self.color(self.stroke.color))
+    }
+
+    pub fn apply_to_text(&self, text: &mut CanvasText) {
+        if let Some(role) = text.font_role {
+            text.font_id = FontId::new(text.font_id.size, self.font_family(role));
+        }
+
+        if let Some(role) = text.color_role {
+            text.color = self.color(role);
+        }
+    }
+
+    pub fn apply_to_page(&self, page: &mut CanvasState) {
+        for layer in page.layers.values_mut() {
+            match &mut layer.content {
+                LayerContent::Text(text) | LayerContent::TemplateText { text, .. } => {
+                    self.apply_to_text(text);
+                }
+                LayerContent::Photo(_)
+                | LayerContent::TemplatePhoto { .. }
+                | LayerContent::Decoration(_)
+                | LayerContent::Path(_) => {}
+            }
+        }
+    }
+}
@@ -0,0 +1,478 @@
+use egui::{Rect, Vec2};
+use serde::{Deserialize, Serialize};
+use strum_macros::EnumIter;
+
+use crate::{
+    model::{edit_state::EditablePage, page::Page, unit::Unit},
+    template::TemplateRegion,
+    widget::{canvas::CanvasState, canvas_info::layers::LayerContent},
+};
+
+const MM_PER_INCH: f32 = 25.4;
+
+// Sizes are compared in millimeters so small rounding differences between units are allowed
+const PAGE_SIZE_TOLERANCE_MM: f32 = 1.0;
+
+// Layers closer than this to the page edge are treated as touching it
+const EDGE_TOLERANCE_PIXELS: f32 = 1.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, Serialize, Deserialize)]
+pub enum BookVendor {
+    Blurb,
+    Saal,
+    Cewe,
+}
+
+impl std::fmt::Display for BookVendor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BookVendor::Blurb => write!(f, "Blurb"),
+            BookVendor::Saal => write!(f, "Saal Digital"),
+            BookVendor::Cewe => write!(f, "CEWE"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct VendorSpec {
+    // Trim sizes of the supported books in millimeters, width x height
+    pub page_sizes_mm: Vec<Vec2>,
+    pub bleed_mm: f32,
+    // Text closer than this to the trim edge may be cut off
+    pub safe_margin_mm: f32,
+    pub ppi: i32,
+    // Photos printed below this resolution will look soft
+    pub min_photo_ppi: f32,
+    pub min_pages: usize,
+    pub max_pages: usize,
+    pub spine_base_mm: f32,
+    pub spine_mm_per_page: f32,
+}
+
+/// What a vendor charges for a book, used to estimate what printing it will cost
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct VendorPricing {
+    pub vendor: BookVendor,
+    // The price of a book with the vendor's minimum number of pages
+    pub base_price: f32,
+    pub price_per_extra_page: f32,
+}
+
+impl VendorPricing {
+    pub fn estimate(&self, page_count: usize) -> f32 {
+        let extra_pages = page_count.saturating_sub(self.vendor.spec().min_pages);
+        self.base_price + self.price_per_extra_page * extra_pages as f32
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct VendorSpecViolation {
+    // None if the violation applies to the whole book
+    pub page: Option<usize>,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CoverSpec {
+    pub vendor: String,
+    pub page_count: usize,
+    pub spine_width_mm: f32,
+    pub cover_width_mm: f32,
+    pub cover_height_mm: f32,
+    pub bleed_mm: f32,
+}
+
+impl BookVendor {
+    pub fn spec(&self) -> VendorSpec {
+        match self {
+            BookVendor::Blurb => VendorSpec {
+                page_sizes_mm: vec![
+                    inches(7.0, 7.0),
+                    inches(8.0, 10.0),
+                    inches(10.0, 8.0),
+                    inches(12.0, 12.0),
+                    inches(13.0, 11.0),
+                ],
+                bleed_mm: 0.125 * MM_PER_INCH,
+                safe_margin_mm: 0.25 * MM_PER_INCH,
+                ppi: 300,
+                min_photo_ppi: 200.0,
+                min_pages: 20,
+                max_pages: 240,
+                spine_base_mm: 3.2,
+                spine_mm_per_page: 0.0635,
+            },
+            BookVendor::Saal => VendorSpec {
+                page_sizes_mm: vec![
+                    Vec2::new(210.0, 210.0),
+                    Vec2::new(280.0, 190.0),
+                    Vec2::new(190.0, 280.0),
+                    Vec2::new(300.0, 300.0),
+                ],
+                bleed_mm: 3.0,
+                safe_margin_mm: 5.0,
+                ppi: 300,
+                min_photo_ppi: 200.0,
+                min_pages: 26,
+                max_pages: 130,
+                spine_base_mm: 4.0,
+                spine_mm_per_page: 0.1,
+            },
+            BookVendor::Cewe => VendorSpec {
+                page_sizes_mm: vec![
+                    Vec2::new(200.0, 200.0),
+                    Vec2::new(210.0, 280.0),
+                    Vec2::new(280.0, 210.0),
+                    Vec2::new(300.0, 300.0),
+                ],
+                bleed_mm: 3.0,
+                safe_margin_mm: 6.0,
+                ppi: 300,
+                min_photo_ppi: 180.0,
+                min_pages: 26,
+                max_pages: 154,
+                spine_base_mm: 3.5,
+                spine_mm_per_page: 0.08,
+            },
+        }
+    }
+
+    /// Rough list prices to start from. What a book costs depends on its size, paper and cover so
+    /// these are meant to be changed.
+    pub fn default_pricing(&self) -> VendorPricing {
+        let (base_price, price_per_extra_page) = match self {
+            BookVendor::Blurb => (35.0, 0.6),
+            BookVendor::Saal => (40.0, 1.0),
+            BookVendor::Cewe => (35.0, 0.8),
+        };
+
+        VendorPricing {
+            vendor: *self,
+            base_price,
+            price_per_extra_page,
+        }
+    }
+
+    pub fn spine_width_mm(&self, page_count: usize) -> f32 {
+        let spec = self.spec();
+        spec.spine_base_mm + spec.spine_mm_per_page * page_count as f32
+    }
+
+    pub fn cover_spec(&self, pages: &[CanvasState]) -> Option<CoverSpec> {
+        let trim_size = pages.first()?.page.size_mm();
+        let spec = self.spec();
+        let spine_width_mm = self.spine_width_mm(pages.len());
+
+        // The cover wraps around the front, spine and back with bleed on every edge
+        Some(CoverSpec {
+            vendor: self.to_string(),
+            page_count: pages.len(),
+            spine_width_mm,
+            cover_width_mm: trim_size.x * 2.0 + spine_width_mm + spec.bleed_mm * 2.0,
+            cover_height_mm: trim_size.y + spec.bleed_mm * 2.0,
+            bleed_mm: spec.bleed_mm,
+        })
+    }
+
+    pub fn page_file_name(&self, project_name: &str, page_number: usize) -> String {
+        match self {
+            BookVendor::Blurb => format!("{}_{:03}.jpg", project_name, page_number + 1),
+            BookVendor::Saal => format!("page_{:03}.jpg", page_number + 1),
+            BookVendor::Cewe => format!("{}-{:03}.jpg", project_name, page_number + 1),
+        }
+    }
+
+    pub fn validate(&self, pages: &[CanvasState]) -> Vec<VendorSpecViolation> {
+        let spec = self.spec();
+        let mut violations = vec![];
+
+        if pages.len() < spec.min_pages || pages.len() > spec.max_pages {
+            violations.push(VendorSpecViolation {
+                page: None,
+                message: format!(
+                    "{} books need between {} and {} pages, this book has {}",
+                    self,
+                    spec.min_pages,
+                    spec.max_pages,
+                    pages.len()
+                ),
+            });
+        }
+
+        if pages.len() % 2 != 0 {
+            violations.push(VendorSpecViolation {
+                page: None,
+                message: "Books are printed in spreads so the page count must be even".to_string(),
+            });
+        }
+
+        for (index, page) in pages.iter().enumerate() {
+            let size_mm = page.page.size_mm();
+            let supported_size = spec.page_sizes_mm.iter().any(|supported| {
+                (supported.x - size_mm.x).abs() <= PAGE_SIZE_TOLERANCE_MM
+                    && (supported.y - size_mm.y).abs() <= PAGE_SIZE_TOLERANCE_MM
+            });
+            if !supported_size {
+                violations.push(VendorSpecViolation {
+                    page: Some(index),
+                    message: format!(
+                        "Page size {:.0} × {:.0} mm isn't offered by {}",
+                        size_mm.x, size_mm.y, self
+                    ),
+                });
+            }
+
+            let page_ppi = page.page.ppi() as f32;
+            let pixels_per_mm = page_ppi / MM_PER_INCH;
+            let safe_rect = Rect::from_min_size(egui::Pos2::ZERO, page.page.size_pixels())
+                .shrink(spec.safe_margin_mm * pixels_per_mm);
+
+            for layer in page.layers.values().filter(|layer| layer.visible) {
+                let rect = layer.transform_state.rect;
+
+                match &layer.content {
+                    LayerContent::Photo(photo)
+                    | LayerContent::TemplatePhoto {
+                        photo: Some(photo), ..
+                    } => {
+                        let printed_width_inches = rect.width() / page_ppi;
+                        let photo_width_pixels =
+                            photo.oriented_size().x * photo.display_crop().width();
+                        let effective_ppi = photo_width_pixels / printed_width_inches;
+
+                        if effective_ppi < spec.min_photo_ppi {
+                            violations.push(VendorSpecViolation {
+                                page: Some(index),
+                                message: format!(
+                                    "{} prints at {:.0} PPI, {} recommends at least {:.0}",
+                                    layer.name, effective_ppi, self, spec.min_photo_ppi
+                                ),
+                            });
+                        }
+                    }
+                    LayerContent::Text(_) | LayerContent::TemplateText { .. } => {
+                        if !safe_rect.contains_rect(rect) {
+                            violations.push(VendorSpecViolation {
+                                page: Some(index),
+                                message: format!(
+                                    "{} is within {:.0} mm of the page edge and may be trimmed",
+                                    layer.name, spec.safe_margin_mm
+                                ),
+                            });
+                        }
+                    }
+                    LayerContent::TemplatePhoto { photo: None, .. }
+                    | LayerContent::Decoration(_)
+                    | LayerContent::Path(_) => {}
+                }
+            }
+        }
+
+        violations
+    }
+
+    // Resamples the page to the vendor's resolution and adds bleed around it. Photos that touch the
+    // edge of the page are extended into the bleed so there's no white border if the trim is off.
+    pub fn prepare_page(&self, canvas_state: &CanvasState) -> CanvasState {
+        let spec = self.spec();
+        let mut prepared = canvas_state.clone();
+
+        let scale = spec.ppi as f32 / canvas_state.page.ppi() as f32;
+        let bleed_pixels = spec.bleed_mm / MM_PER_INCH * spec.ppi as f32;
+        let trim_size = canvas_state.page.size_pixels() * scale;
+        let bleed_page_size = trim_size + Vec2::splat(bleed_pixels * 2.0);
+
+        // Template regions are relative to the page, so they're moved from the trimmed page onto
+        // the larger page with the bleed around it
+        let to_bleed_page = |region: &mut TemplateRegion, rect: Rect| {
+            let rect = rect.translate(Vec2::splat(bleed_pixels));
+            region.relative_position = (rect.min.to_vec2() / bleed_page_size).to_pos2();
+            region.relative_size = rect.size() / bleed_page_size;
+        };
+
+        for layer in prepared.layers.values_mut() {
+            let rect = layer.transform_state.rect;
+            let mut scaled = Rect::from_min_max(
+                (rect.min.to_vec2() * scale).to_pos2(),
+                (rect.max.to_vec2() * scale).to_pos2(),
+            );
+
+            match &mut layer.content {
+                LayerContent::Photo(photo) if layer.transform_state.rotation == 0.0 => {
+                    // Extended in the space of the photo as it's shown, rotated or flipped photos
+                    // extend the crop in different directions of the stored image
+                    let display_crop = photo.display_crop();
+                    let crop_per_pixel = display_crop.size() / scaled.size();
+                    let bleed_rect = extend_into_bleed(scaled, trim_size, bleed_pixels);
+
+                    // Show more of the photo rather than stretching it. If the crop is already at
+                    // the edge of the photo the bleed is small enough that a slight stretch is fine.
+                    let mut crop = display_crop;
+                    crop.min -= (scaled.min - bleed_rect.min) * crop_per_pixel;
+                    crop.max += (bleed_rect.max - scaled.max) * crop_per_pixel;
+                    photo.crop = photo.display_crop_to_image(crop.intersect(Rect::from_min_max(
+                        egui::Pos2::ZERO,
+                        egui::Pos2::new(1.0, 1.0),
+                    )));
+
+                    scaled = bleed_rect;
+                }
+                LayerContent::TemplatePhoto { region, .. } => {
+                    // The photo is placed in its region, so extending the region lets it run
+                    // into the bleed
+                    let region_rect = Rect::from_min_size(
+                        (region.relative_position.to_vec2() * trim_size).to_pos2(),
+                        region.relative_size * trim_size,
+                    );
+                    to_bleed_page(
+                        region,
+                        extend_into_bleed(region_rect, trim_size, bleed_pixels),
+                    );
+                }
+                LayerContent::TemplateText { region, text } => {
+                    text.font_size *= scale;
+                    let region_rect = Rect::from_min_size(
+                        (region.relative_position.to_vec2() * trim_size).to_pos2(),
+                        region.relative_size * trim_size,
+                    );
+                    to_bleed_page(region, region_rect);
+                }
+                LayerContent::Text(text) => {
+                    text.font_size *= scale;
+                }
+                _ => {}
+            }
+
+            layer.transform_state.rect = scaled.translate(Vec2::splat(bleed_pixels));
+        }
+
+        let unit = canvas_state.page.unit();
+        let mut page = Page::new(bleed_page_size, spec.ppi, Unit::Pixels);
+        page.set_unit(unit);
+        prepared.page = EditablePage::new(page);
+
+        prepared
+    }
+}
+
+// Pushes the edges of `rect` that touch the trimmed page out by the bleed
+fn extend_into_bleed(rect: Rect, trim_size: Vec2, bleed_pixels: f32) -> Rect {
+    let mut extended = rect;
+    if rect.min.x <= EDGE_TOLERANCE_PIXELS {
+        extended.min.x -= bleed_pixels;
+    }
+    if rect.min.y <= EDGE_TOLERANCE_PIXELS {
+        extended.min.y -= bleed_pixels;
+    }
+    if rect.max.x >= trim_size.x - EDGE_TOLERANCE_PIXELS {
+        extended.max.x += bleed_pixels;
+    }
+    if rect.max.y >= trim_size.y - EDGE_TOLERANCE_PIXELS {
+        extended.max.y += bleed_pixels;
+    }
+    extended
+}
+
+fn inches(width: f32, height: f32) -> Vec2 {
+    Vec2::new(width, height) * MM_PER_INCH
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn book(page_count: usize, size_inches: Vec2) -> Vec<CanvasState> {
+        (0..page_count)
+            .map(|_| {
+                let mut page = CanvasState::new();
+                page.page = EditablePage::new(Page::with_size_inches(size_inches));
+                page
+            })
+            .collect()
+    }
+
+    fn approx_eq(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-3
+    }
+
+    #[test]
+    fn spine_grows_with_the_page_count() {
+        assert!(approx_eq(BookVendor::Blurb.spine_width_mm(0), 3.2));
+        assert!(approx_eq(BookVendor::Blurb.spine_width_mm(100), 9.55));
+        assert!(approx_eq(BookVendor::Saal.spine_width_mm(30), 7.0));
+    }
+
+    #[test]
+    fn cover_wraps_both_pages_and_the_spine_with_bleed() {
+        let pages = book(40, Vec2::new(8.0, 10.0));
+        let cover = BookVendor::Blurb.cover_spec(&pages).unwrap();
+        let bleed = 0.125 * MM_PER_INCH;
+        let spine = BookVendor::Blurb.spine_width_mm(40);
+
+        assert_eq!(cover.page_count, 40);
+        assert!(approx_eq(cover.spine_width_mm, spine));
+        assert!(approx_eq(
+            cover.cover_width_mm,
+            8.0 * MM_PER_INCH * 2.0 + spine + bleed * 2.0
+        ));
+        assert!(approx_eq(
+            cover.cover_height_mm,
+            10.0 * MM_PER_INCH + bleed * 2.0
+        ));
+        assert!(BookVendor::Blurb.cover_spec(&[]).is_none());
+    }
+
+    #[test]
+    fn supported_books_pass() {
+        let pages = book(20, Vec2::new(7.0, 7.0));
+        assert!(BookVendor::Blurb.validate(&pages).is_empty());
+    }
+
+    #[test]
+    fn page_counts_are_checked() {
+        let violations = BookVendor::Blurb.validate(&book(19, Vec2::new(7.0, 7.0)));
+        assert_eq!(violations.len(), 2);
+        assert!(violations.iter().all(|violation| violation.page.is_none()));
+
+        let violations = BookVendor::Blurb.validate(&book(242, Vec2::new(7.0, 7.0)));
+        assert_eq!(violations.len(), 1);
+    }
+
+    #[test]
+    fn page_sizes_are_checked_with_some_tolerance() {
+        // 210 mm is within a millimeter of 8.25"
+        assert!(BookVendor::Saal
+            .validate(&book(26, Vec2::new(8.25, 8.25)))
+            .is_empty());
+
+        let violations = BookVendor::Saal.validate(&book(26, Vec2::new(9.0, 9.0)));
+        assert_eq!(violations.len(), 26);
+        assert_eq!(violations[3].page, Some(3));
+    }
+
+    #[test]
+    fn only_edges_touching_the_trim_are_extended() {
+        let trim_size = Vec2::new(1000.0, 800.0);
+
+        let touching =
+            Rect::from_min_max(egui::Pos2::new(0.5, 100.0), egui::Pos2::new(1000.0, 800.0));
+        assert_eq!(
+            extend_into_bleed(touching, trim_size, 36.0),
+            Rect::from_min_max(
+                egui::Pos2::new(-35.5, 100.0),
+                egui::Pos2::new(1036.0, 836.0)
+            )
+        );
+
+        let inside = Rect::from_min_max(egui::Pos2::new(10.0, 10.0), egui::Pos2::new(990.0, 790.0));
+        assert_eq!(extend_into_bleed(inside, trim_size, 36.0), inside);
+    }
+
+    #[test]
+    fn estimates_charge_for_pages_past_the_minimum() {
+        let pricing = BookVendor::Blurb.default_pricing();
+        assert!(approx_eq(pricing.estimate(10), 35.0));
+        assert!(approx_eq(pricing.estimate(20), 35.0));
+        assert!(approx_eq(pricing.estimate(30), 41.0));
+    }
+}
@@ -0,0 +1,386 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufReader, Read},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use chrono::{DateTime, Local};
+use exif::{In, Tag};
+use log::{error, info};
+use tokio::task::spawn_blocking;
+
+use crate::{
+    dependencies::{Dependency, Singleton, SingletonFor},
+    modal::{basic::BasicModal, manager::ModalManager, progress::ProgressModal},
+    photo_manager::PhotoManager,
+};
+
+const DCIM_DIR: &str = "DCIM";
+// Added to the library once they're copied
+const PHOTO_EXTENSIONS: [&str; 2] = ["jpg", "jpeg"];
+// Copied off the card alongside the JPEGs but the library can't show them yet
+const RAW_EXTENSIONS: [&str; 12] = [
+    "cr2", "cr3", "nef", "arw", "dng", "orf", "rw2", "raf", "pef", "srw", "heic", "heif",
+];
+
+// Copies are written under this extension and renamed once they've been verified so a cancelled
+// or failed copy never looks like a finished photo
+const PARTIAL_EXTENSION: &str = "part";
+
+const COMPARE_CHUNK_SIZE: usize = 64 * 1024;
+
+#[derive(Debug, thiserror::Error)]
+pub enum CameraImportError {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+    #[error("The copy of {0} doesn't match the original")]
+    VerificationFailed(PathBuf),
+}
+
+/// A mounted volume with a DCIM folder, like a camera or a memory card
+#[derive(Debug, Clone, PartialEq)]
+pub struct CameraVolume {
+    pub name: String,
+    pub dcim: PathBuf,
+}
+
+#[derive(Debug, Clone)]
+pub struct CameraFile {
+    pub path: PathBuf,
+    pub size: u64,
+    pub modified: Option<DateTime<Local>>,
+    // The small preview cameras embed in the EXIF data. Decoding every full size photo on the
+    // card just to show the list would be far too slow.
+    pub thumbnail: Option<Arc<[u8]>>,
+}
+
+impl CameraFile {
+    /// Whether the file is a RAW or HEIC photo, which is copied but not added to the library
+    pub fn is_raw(&self) -> bool {
+        has_extension(&self.path, &RAW_EXTENSIONS)
+    }
+
+    pub fn file_name(&self) -> String {
+        self.path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default()
+    }
+
+    /// The folder the file is copied into, optionally a subfolder named for the day it was taken
+    pub fn destination_dir(&self, destination: &Path, sort_by_date: bool) -> PathBuf {
+        match (sort_by_date, self.modified) {
+            (true, Some(modified)) => destination.join(modified.format("%Y-%m-%d").to_string()),
+            _ => destination.to_path_buf(),
+        }
+    }
+
+    /// Whether the file has already been copied to the destination by an earlier import. Copies
+    /// that had to be renamed to avoid a name clash aren't recognized.
+    pub fn is_imported(&self, destination: &Path, sort_by_date: bool) -> bool {
+        let target = self
+            .destination_dir(destination, sort_by_date)
+            .join(self.file_name());
+
+        fs::metadata(target)
+            .map(|metadata| metadata.len() == self.size)
+            .unwrap_or(false)
+    }
+}
+
+/// Finds the mounted volumes that look like a camera or memory card
+pub fn detect_volumes() -> Vec<CameraVolume> {
+    volume_roots()
+        .into_iter()
+        .filter_map(|root| {
+            let dcim = find_dcim(&root)?;
+            let name = root
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_else(|| root.display().to_string());
+
+            Some(CameraVolume { name, dcim })
+        })
+        .collect()
+}
+
+#[cfg(target_os = "macos")]
+fn volume_roots() -> Vec<PathBuf> {
+    subdirectories(Path::new("/Volumes"))
+}
+
+#[cfg(target_os = "windows")]
+fn volume_roots() -> Vec<PathBuf> {
+    // A and B are floppy drives and C is almost always the system drive
+    ('D'..='Z')
+        .map(|letter| PathBuf::from(format!("{}:\\", letter)))
+        .filter(|root| root.exists())
+        .collect()
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn volume_roots() -> Vec<PathBuf> {
+    let mut parents = vec![PathBuf::from("/media"), PathBuf::from("/mnt")];
+    if let Ok(user) = std::env::var("USER") {
+        parents.push(Path::new("/media").join(&user));
+        parents.push(Path::new("/run/media").join(&user));
+    }
+
+    parents
+        .iter()
+        .flat_map(|parent| subdirectories(parent))
+        .collect()
+}
+
+fn subdirectories(path: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(path) else {
+        return Vec::new();
+    };
+
+    entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .collect()
+}
+
+/// The DCIM folder directly under `root`, cameras don't agree on its case
+pub fn find_dcim(root: &Path) -> Option<PathBuf> {
+    if root
+        .file_name()
+        .is_some_and(|name| name.eq_ignore_ascii_case(DCIM_DIR))
+    {
+        return Some(root.to_path_buf());
+    }
+
+    subdirectories(root).into_iter().find(|path| {
+        path.file_name()
+            .is_some_and(|name| name.eq_ignore_ascii_case(DCIM_DIR))
+    })
+}
+
+/// Lists the photos under a DCIM folder, oldest first. Reads the EXIF data of every photo for its
+/// thumbnail so this should be kept off the UI thread.
+pub fn scan_dcim(dcim: &Path) -> Vec<CameraFile> {
+    let mut paths = Vec::new();
+    collect_photos(dcim, &mut paths);
+
+    let mut files: Vec<CameraFile> = paths
+        .into_iter()
+        .filter_map(|path| {
+            let metadata = fs::metadata(&path).ok()?;
+
+            Some(CameraFile {
+                size: metadata.len(),
+                modified: metadata.modified().ok().map(DateTime::<Local>::from),
+                thumbnail: exif_thumbnail(&path),
+                path,
+            })
+        })
+        .collect();
+
+    files.sort_by(|a, b| a.modified.cmp(&b.modified).then(a.path.cmp(&b.path)));
+    files
+}
+
+fn collect_photos(dir: &Path, paths: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let path = entry.path();
+
+        // Skips the ._ files macOS leaves on FAT formatted cards
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+
+        if path.is_dir() {
+            collect_photos(&path, paths);
+        } else if has_extension(&path, &PHOTO_EXTENSIONS) || has_extension(&path, &RAW_EXTENSIONS) {
+            paths.push(path);
+        }
+    }
+}
+
+fn has_extension(path: &Path, extensions: &[&str]) -> bool {
+    path.extension().is_some_and(|extension| {
+        extensions
+            .iter()
+            .any(|photo_extension| extension.eq_ignore_ascii_case(photo_extension))
+    })
+}
+
+fn exif_thumbnail(path: &Path) -> Option<Arc<[u8]>> {
+    let file = File::open(path).ok()?;
+    let exif = exif::Reader::new()
+        .read_from_container(&mut BufReader::new(file))
+        .ok()?;
+
+    let offset = exif
+        .get_field(Tag::JPEGInterchangeFormat, In::THUMBNAIL)?
+        .value
+        .get_uint(0)? as usize;
+    let length = exif
+        .get_field(Tag::JPEGInterchangeFormatLength, In::THUMBNAIL)?
+        .value
+        .get_uint(0)? as usize;
+
+    exif.buf().get(offset..offset + length).map(Arc::from)
+}
+
+/// Copies the file into `directory` and checks the copy byte for byte against the original.
+/// Returns the path of the copy, which is the existing file if an identical one is already there.
+pub fn copy_verified(file: &CameraFile, directory: &Path) -> Result<PathBuf, CameraImportError> {
+    fs::create_dir_all(directory)?;
+
+    let stem = file
+        .path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let extension = file
+        .path
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    // Cameras restart their numbering so a different photo with the same name is common
+    let mut target = directory.join(file.file_name());
+    let mut suffix = 1;
+    while target.exists() {
+        if files_match(&file.path, &target)? {
+            return Ok(target);
+        }
+
+        target = directory.join(format!("{}_{}.{}", stem, suffix, extension));
+        suffix += 1;
+    }
+
+    let partial = target.with_extension(format!("{}.{}", extension, PARTIAL_EXTENSION));
+    fs::copy(&file.path, &partial)?;
+
+    if !files_match(&file.path, &partial)? {
+        let _ = fs::remove_file(&partial);
+        return Err(CameraImportError::VerificationFailed(file.path.clone()));
+    }
+
+    fs::rename(&partial, &target)?;
+
+    Ok(target)
+}
+
+fn files_match(a: &Path, b: &Path) -> io::Result<bool> {
+    if fs::metadata(a)?.len() != fs::metadata(b)?.len() {
+        return Ok(false);
+    }
+
+    let mut a = BufReader::new(File::open(a)?);
+    let mut b = BufReader::new(File::open(b)?);
+    let mut a_buffer = vec![0; COMPARE_CHUNK_SIZE];
+    let mut b_buffer = vec![0; COMPARE_CHUNK_SIZE];
+
+    loop {
+        let a_read = read_chunk(&mut a, &mut a_buffer)?;
+        let b_read = read_chunk(&mut b, &mut b_buffer)?;
+
+        if a_read != b_read || a_buffer[..a_read] != b_buffer[..b_read] {
+            return Ok(false);
+        }
+
+        if a_read == 0 {
+            return Ok(true);
+        }
+    }
+}
+
+// Fills as much of the buffer as the reader has left
+fn read_chunk(reader: &mut impl Read, buffer: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buffer.len() {
+        match reader.read(&mut buffer[filled..])? {
+            0 => break,
+            read => filled += read,
+        }
+    }
+
+    Ok(filled)
+}
+
+/// Copies the files off the camera into the destination with progress shown in a modal, then adds
+/// the copied JPEGs to the library
+pub fn import(files: Vec<CameraFile>, destination: PathBuf, sort_by_date: bool) {
+    tokio::spawn(async move {
+        let num_files = files.len();
+        if num_files == 0 {
+            return;
+        }
+
+        let modal_manager: Singleton<ModalManager> = Dependency::get();
+        let modal_id = ModalManager::push(ProgressModal::new(
+            "Copying Photos",
+            format!("Copying {} photos", num_files),
+            "Cancel",
+            0.0,
+        ));
+
+        let mut copied = Vec::new();
+        let mut num_failed: usize = 0;
+
+        for (index, file) in files.into_iter().enumerate() {
+            let source = file.path.clone();
+            let directory = file.destination_dir(&destination, sort_by_date);
+            let is_raw = file.is_raw();
+
+            match spawn_blocking(move || copy_verified(&file, &directory)).await {
+                Ok(Ok(path)) if is_raw => info!("Copied {:?} to {:?}", source, path),
+                Ok(Ok(path)) => copied.push(path),
+                Ok(Err(err)) => {
+                    error!("Failed to copy {:?}: {:?}", source, err);
+                    num_failed += 1;
+                }
+                Err(err) => {
+                    error!("Copy task for {:?} failed: {:?}", source, err);
+                    num_failed += 1;
+                }
+            }
+
+            let cancelled = modal_manager.with_lock(|modal_manager| {
+                if !modal_manager.exists(&modal_id) {
+                    return true;
+                }
+
+                let _ = modal_manager.modify(&modal_id, |progress_modal| {
+                    progress_modal.progress = (index + 1) as f32 / num_files as f32;
+                    progress_modal.message = format!("Copied {}/{} photos", index + 1, num_files);
+                });
+
+                false
+            });
+
+            if cancelled {
+                info!("Camera import cancelled after {} photos", index + 1);
+                break;
+            }
+        }
+
+        modal_manager.with_lock_mut(|modal_manager| {
+            modal_manager.dismiss(modal_id);
+        });
+
+        if num_failed > 0 {
+            ModalManager::push(BasicModal::new(
+                "Import from Camera",
+                format!(
+                    "{} of {} photos couldn't be copied and were left on the camera",
+                    num_failed, num_files
+                ),
+                "OK",
+            ));
+        }
+
+        PhotoManager::import_files(copied).await;
+    });
+}
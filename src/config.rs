@@ -4,9 +4,29 @@ use std::{
     path::PathBuf,
 };
 
+use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
+use strum::IntoEnumIterator;
 
-use crate::{auto_persisting::PersistentModifiable, dirs::Dirs};
+use crate::{
+    auto_persisting::PersistentModifiable,
+    book_vendor::{BookVendor, VendorPricing},
+    dirs::Dirs,
+    layer_naming::{LayerNaming, DEFAULT_PHOTO_LAYER_NAME, DEFAULT_TEXT_LAYER_NAME},
+    model::{
+        nudge::NudgeDistance,
+        page_preset::{PagePreset, MAX_RECENT_PAGE_SIZES},
+    },
+    scene::workspace::WorkspaceLayout,
+    smart_collection::SmartCollection,
+    template::{pack::TemplatePack, Template},
+    theme::{AccentColor, ThemeMode},
+    widget::{
+        crop::CropPreset,
+        crop_overlay::CropOverlay,
+        pan_zoom::{PanZoomSettings, WheelAction, MAX_ZOOM_SENSITIVITY, MIN_ZOOM_SENSITIVITY},
+    },
+};
 
 #[derive(Debug, thiserror::Error)]
 pub enum ConfigError {
@@ -18,10 +38,58 @@ pub enum ConfigError {
     TomlSer(#[from] toml::ser::Error),
 }
 
+pub const DEFAULT_TRASH_RETENTION_DAYS: u32 = 30;
+pub const DEFAULT_HISTORY_MEMORY_LIMIT_MB: u32 = 256;
+pub const DEFAULT_HISTORY_LENGTH: u32 = 200;
+pub const DEFAULT_FIT_PAGE_MARGIN_MM: f32 = 10.0;
+pub const DEFAULT_UI_SCALE: f32 = 1.0;
+pub const MIN_UI_SCALE: f32 = 0.75;
+pub const MAX_UI_SCALE: f32 = 2.0;
+
 #[derive(Debug, Serialize, Deserialize, Default)]
 pub struct Config {
     recent_projects: Option<Vec<PathBuf>>,
     last_project: Option<PathBuf>,
+    trash_retention_days: Option<u32>,
+    workspace_layouts: Option<IndexMap<String, WorkspaceLayout>>,
+    pan_inertia: Option<bool>,
+    page_strip: Option<bool>,
+    crop_presets: Option<Vec<CropPreset>>,
+    crop_overlays: Option<Vec<CropOverlay>>,
+    // Gallery search queries by name
+    saved_searches: Option<IndexMap<String, String>>,
+    page_presets: Option<Vec<PagePreset>>,
+    // Most recent first
+    recent_page_sizes: Option<Vec<PagePreset>>,
+    // Approximate memory the undo history of a page may use before old entries are dropped
+    history_memory_limit_mb: Option<u32>,
+    // Undo steps kept per page before old entries are dropped
+    history_length: Option<u32>,
+    theme_mode: Option<ThemeMode>,
+    accent_color: Option<AccentColor>,
+    // Where photos copied off a camera went last time
+    camera_import_destination: Option<PathBuf>,
+    smart_collections: Option<Vec<SmartCollection>>,
+    // Space left around a photo fitted to the page
+    fit_page_margin_mm: Option<f32>,
+    // Templates made in the template editor, shown after the built in ones
+    templates: Option<Vec<Template>>,
+    wheel_action: Option<WheelAction>,
+    zoom_sensitivity: Option<f32>,
+    invert_zoom: Option<bool>,
+    middle_mouse_pan: Option<bool>,
+    photo_layer_name: Option<String>,
+    text_layer_name: Option<String>,
+    // How far the arrow keys move the selected layers
+    nudge_distance: Option<NudgeDistance>,
+    // Prices set for the book statistics' print cost estimates
+    print_pricing: Option<Vec<VendorPricing>>,
+    // Applied on top of the display's own scaling
+    ui_scale: Option<f32>,
+    // Imported template packs, shown after the templates made here
+    template_packs: Option<Vec<TemplatePack>>,
+    // Crop photos placed into regions of a very different shape around their subject
+    smart_crop: Option<bool>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -33,6 +101,45 @@ struct RecentProject {
 pub enum ConfigModification {
     AddRecentProject(PathBuf),
     SetLastProject(PathBuf),
+    SetTrashRetentionDays(u32),
+    SaveWorkspaceLayout(String, WorkspaceLayout),
+    DeleteWorkspaceLayout(String),
+    SetPanInertia(bool),
+    SetPageStrip(bool),
+    SaveCropPreset(CropPreset),
+    DeleteCropPreset(String),
+    SetCropOverlays(Vec<CropOverlay>),
+    SaveSearch(String, String),
+    DeleteSearch(String),
+    SavePagePreset(PagePreset),
+    DeletePagePreset(String),
+    AddRecentPageSize(PagePreset),
+    SetHistoryMemoryLimit(u32),
+    SetHistoryLength(u32),
+    SetThemeMode(ThemeMode),
+    SetAccentColor(AccentColor),
+    SetCameraImportDestination(PathBuf),
+    // Replaces the collection with the given name, which may differ from the new name when renamed
+    SaveSmartCollection(Option<String>, SmartCollection),
+    DeleteSmartCollection(String),
+    SetFitPageMargin(f32),
+    // Replaces the template with the given name, which may differ from the new name when renamed
+    SaveTemplate(Option<String>, Template),
+    DeleteTemplate(String),
+    SetWheelAction(WheelAction),
+    SetZoomSensitivity(f32),
+    SetInvertZoom(bool),
+    SetMiddleMousePan(bool),
+    SetLayerNaming(LayerNaming),
+    SetNudgeDistance(NudgeDistance),
+    SetPrintPricing(VendorPricing),
+    SetUiScale(f32),
+    // Numbered if a pack with the same name was already added
+    AddTemplatePack(TemplatePack),
+    // Takes the place of the pack with the same name
+    ReplaceTemplatePack(TemplatePack),
+    DeleteTemplatePack(String),
+    SetSmartCrop(bool),
 }
 
 impl Config {
@@ -43,6 +150,140 @@ impl Config {
     pub fn last_project(&self) -> Option<&PathBuf> {
         self.last_project.as_ref()
     }
+
+    pub fn trash_retention_days(&self) -> u32 {
+        self.trash_retention_days
+            .unwrap_or(DEFAULT_TRASH_RETENTION_DAYS)
+    }
+
+    pub fn workspace_layouts(&self) -> Vec<(String, WorkspaceLayout)> {
+        self.workspace_layouts
+            .iter()
+            .flatten()
+            .map(|(name, layout)| (name.clone(), layout.clone()))
+            .collect()
+    }
+
+    pub fn pan_inertia(&self) -> bool {
+        self.pan_inertia.unwrap_or(true)
+    }
+
+    pub fn page_strip(&self) -> bool {
+        self.page_strip.unwrap_or(true)
+    }
+
+    pub fn smart_crop(&self) -> bool {
+        self.smart_crop.unwrap_or(false)
+    }
+
+    pub fn crop_presets(&self) -> &[CropPreset] {
+        self.crop_presets.as_deref().unwrap_or(&[])
+    }
+
+    pub fn crop_overlays(&self) -> Vec<CropOverlay> {
+        self.crop_overlays
+            .clone()
+            .unwrap_or_else(|| vec![CropOverlay::RuleOfThirds])
+    }
+
+    pub fn saved_searches(&self) -> Vec<(String, String)> {
+        self.saved_searches
+            .iter()
+            .flatten()
+            .map(|(name, query)| (name.clone(), query.clone()))
+            .collect()
+    }
+
+    pub fn page_presets(&self) -> &[PagePreset] {
+        self.page_presets.as_deref().unwrap_or(&[])
+    }
+
+    pub fn recent_page_sizes(&self) -> &[PagePreset] {
+        self.recent_page_sizes.as_deref().unwrap_or(&[])
+    }
+
+    pub fn history_memory_limit_mb(&self) -> u32 {
+        self.history_memory_limit_mb
+            .unwrap_or(DEFAULT_HISTORY_MEMORY_LIMIT_MB)
+    }
+
+    pub fn history_length(&self) -> u32 {
+        self.history_length.unwrap_or(DEFAULT_HISTORY_LENGTH)
+    }
+
+    pub fn theme_mode(&self) -> ThemeMode {
+        self.theme_mode.unwrap_or_default()
+    }
+
+    pub fn accent_color(&self) -> AccentColor {
+        self.accent_color.unwrap_or_default()
+    }
+
+    pub fn camera_import_destination(&self) -> Option<&PathBuf> {
+        self.camera_import_destination.as_ref()
+    }
+
+    pub fn smart_collections(&self) -> &[SmartCollection] {
+        self.smart_collections.as_deref().unwrap_or(&[])
+    }
+
+    pub fn fit_page_margin_mm(&self) -> f32 {
+        self.fit_page_margin_mm
+            .unwrap_or(DEFAULT_FIT_PAGE_MARGIN_MM)
+    }
+
+    pub fn templates(&self) -> &[Template] {
+        self.templates.as_deref().unwrap_or(&[])
+    }
+
+    pub fn template_packs(&self) -> &[TemplatePack] {
+        self.template_packs.as_deref().unwrap_or(&[])
+    }
+
+    pub fn pan_zoom_settings(&self) -> PanZoomSettings {
+        let defaults = PanZoomSettings::default();
+        PanZoomSettings {
+            wheel_action: self.wheel_action.unwrap_or(defaults.wheel_action),
+            zoom_sensitivity: self.zoom_sensitivity.unwrap_or(defaults.zoom_sensitivity),
+            invert_zoom: self.invert_zoom.unwrap_or(defaults.invert_zoom),
+            middle_mouse_pan: self.middle_mouse_pan.unwrap_or(defaults.middle_mouse_pan),
+        }
+    }
+
+    pub fn nudge_distance(&self) -> NudgeDistance {
+        self.nudge_distance.unwrap_or_default()
+    }
+
+    pub fn ui_scale(&self) -> f32 {
+        self.ui_scale.unwrap_or(DEFAULT_UI_SCALE)
+    }
+
+    /// The pricing of every vendor, the defaults for vendors that haven't been priced
+    pub fn print_pricing(&self) -> Vec<VendorPricing> {
+        BookVendor::iter()
+            .map(|vendor| {
+                self.print_pricing
+                    .iter()
+                    .flatten()
+                    .find(|pricing| pricing.vendor == vendor)
+                    .copied()
+                    .unwrap_or_else(|| vendor.default_pricing())
+            })
+            .collect()
+    }
+
+    pub fn layer_naming(&self) -> LayerNaming {
+        LayerNaming {
+            photo: self
+                .photo_layer_name
+                .clone()
+                .unwrap_or_else(|| DEFAULT_PHOTO_LAYER_NAME.to_string()),
+            text: self
+                .text_layer_name
+                .clone()
+                .unwrap_or_else(|| DEFAULT_TEXT_LAYER_NAME.to_string()),
+        }
+    }
 }
 
 impl PersistentModifiable<Config> for Config {
@@ -84,6 +325,192 @@ impl PersistentModifiable<Config> for Config {
             ConfigModification::SetLastProject(path_buf) => {
                 self.last_project = Some(path_buf);
             }
+            ConfigModification::SetTrashRetentionDays(days) => {
+                self.trash_retention_days = Some(days);
+            }
+            ConfigModification::SaveWorkspaceLayout(name, layout) => {
+                self.workspace_layouts
+                    .get_or_insert_with(IndexMap::new)
+                    .insert(name, layout);
+            }
+            ConfigModification::DeleteWorkspaceLayout(name) => {
+                if let Some(workspace_layouts) = &mut self.workspace_layouts {
+                    workspace_layouts.shift_remove(&name);
+                }
+            }
+            ConfigModification::SetPanInertia(enabled) => {
+                self.pan_inertia = Some(enabled);
+            }
+            ConfigModification::SetPageStrip(enabled) => {
+                self.page_strip = Some(enabled);
+            }
+            ConfigModification::SaveCropPreset(preset) => {
+                let crop_presets = self.crop_presets.get_or_insert_with(Vec::new);
+                crop_presets.retain(|existing| existing.name != preset.name);
+                crop_presets.push(preset);
+            }
+            ConfigModification::DeleteCropPreset(name) => {
+                if let Some(crop_presets) = &mut self.crop_presets {
+                    crop_presets.retain(|preset| preset.name != name);
+                }
+            }
+            ConfigModification::SetCropOverlays(overlays) => {
+                self.crop_overlays = Some(overlays);
+            }
+            ConfigModification::SaveSearch(name, query) => {
+                self.saved_searches
+                    .get_or_insert_with(IndexMap::new)
+                    .insert(name, query);
+            }
+            ConfigModification::DeleteSearch(name) => {
+                if let Some(saved_searches) = &mut self.saved_searches {
+                    saved_searches.shift_remove(&name);
+                }
+            }
+            ConfigModification::SavePagePreset(preset) => {
+                let page_presets = self.page_presets.get_or_insert_with(Vec::new);
+                page_presets.retain(|existing| existing.name != preset.name);
+                page_presets.push(preset);
+            }
+            ConfigModification::DeletePagePreset(name) => {
+                if let Some(page_presets) = &mut self.page_presets {
+                    page_presets.retain(|preset| preset.name != name);
+                }
+            }
+            ConfigModification::AddRecentPageSize(size) => {
+                let recent_page_sizes = self.recent_page_sizes.get_or_insert_with(Vec::new);
+                recent_page_sizes.retain(|existing| !existing.matches(&size.page()));
+                recent_page_sizes.insert(0, size);
+                recent_page_sizes.truncate(MAX_RECENT_PAGE_SIZES);
+            }
+            ConfigModification::SetHistoryMemoryLimit(limit_mb) => {
+                self.history_memory_limit_mb = Some(limit_mb);
+            }
+            ConfigModification::SetHistoryLength(length) => {
+                self.history_length = Some(length.max(1));
+            }
+            ConfigModification::SetThemeMode(mode) => {
+                self.theme_mode = Some(mode);
+            }
+            ConfigModification::SetAccentColor(accent) => {
+                self.accent_color = Some(accent);
+            }
+            ConfigModification::SetCameraImportDestination(path) => {
+                self.camera_import_destination = Some(path);
+            }
+            ConfigModification::SaveSmartCollection(replaces, collection) => {
+                let smart_collections = self.smart_collections.get_or_insert_with(Vec::new);
+                let replaces = replaces.unwrap_or_else(|| collection.name.clone());
+
+                // Edited collections keep their place in the sidebar
+                match smart_collections
+                    .iter()
+                    .position(|existing| existing.name == replaces)
+                {
+                    Some(index) => smart_collections[index] = collection,
+                    None => smart_collections.push(collection),
+                }
+            }
+            ConfigModification::DeleteSmartCollection(name) => {
+                if let Some(smart_collections) = &mut self.smart_collections {
+                    smart_collections.retain(|collection| collection.name != name);
+                }
+            }
+            ConfigModification::SetFitPageMargin(margin_mm) => {
+                self.fit_page_margin_mm = Some(margin_mm.max(0.0));
+            }
+            ConfigModification::SaveTemplate(replaces, template) => {
+                let templates = self.templates.get_or_insert_with(Vec::new);
+                let replaces = replaces.unwrap_or_else(|| template.name.clone());
+
+                match templates
+                    .iter()
+                    .position(|existing| existing.name == replaces)
+                {
+                    Some(index) => templates[index] = template,
+                    None => templates.push(template),
+                }
+            }
+            ConfigModification::DeleteTemplate(name) => {
+                if let Some(templates) = &mut self.templates {
+                    templates.retain(|template| template.name != name);
+                }
+            }
+            ConfigModification::SetWheelAction(action) => {
+                self.wheel_action = Some(action);
+            }
+            ConfigModification::SetZoomSensitivity(sensitivity) => {
+                self.zoom_sensitivity =
+                    Some(sensitivity.clamp(MIN_ZOOM_SENSITIVITY, MAX_ZOOM_SENSITIVITY));
+            }
+            ConfigModification::SetInvertZoom(invert) => {
+                self.invert_zoom = Some(invert);
+            }
+            ConfigModification::SetMiddleMousePan(enabled) => {
+                self.middle_mouse_pan = Some(enabled);
+            }
+            ConfigModification::SetLayerNaming(naming) => {
+                self.photo_layer_name = Some(naming.photo);
+                self.text_layer_name = Some(naming.text);
+            }
+            ConfigModification::SetNudgeDistance(distance) => {
+                self.nudge_distance = Some(NudgeDistance {
+                    amount: distance.amount.max(0.0),
+                    ..distance
+                });
+            }
+            ConfigModification::SetPrintPricing(pricing) => {
+                let print_pricing = self.print_pricing.get_or_insert_with(Vec::new);
+                let pricing = VendorPricing {
+                    base_price: pricing.base_price.max(0.0),
+                    price_per_extra_page: pricing.price_per_extra_page.max(0.0),
+                    ..pricing
+                };
+                match print_pricing
+                    .iter()
+                    .position(|existing| existing.vendor == pricing.vendor)
+                {
+                    Some(index) => print_pricing[index] = pricing,
+                    None => print_pricing.push(pricing),
+                }
+            }
+            ConfigModification::SetUiScale(scale) => {
+                self.ui_scale = Some(scale.clamp(MIN_UI_SCALE, MAX_UI_SCALE));
+            }
+            ConfigModification::AddTemplatePack(mut pack) => {
+                let template_packs = self.template_packs.get_or_insert_with(Vec::new);
+
+                // Named like "Travel (2)" so the pack already added isn't replaced
+                let taken =
+                    |name: &str| template_packs.iter().any(|existing| existing.name == name);
+                let base_name = pack.name.clone();
+                let mut number = 2;
+                while taken(&pack.name) {
+                    pack.name = format!("{} ({})", base_name, number);
+                    number += 1;
+                }
+
+                template_packs.push(pack);
+            }
+            ConfigModification::ReplaceTemplatePack(pack) => {
+                let template_packs = self.template_packs.get_or_insert_with(Vec::new);
+
+                match template_packs
+                    .iter()
+                    .position(|existing| existing.name == pack.name)
+                {
+                    Some(index) => template_packs[index] = pack,
+                    None => template_packs.push(pack),
+                }
+            }
+            ConfigModification::DeleteTemplatePack(name) => {
+                if let Some(template_packs) = &mut self.template_packs {
+                    template_packs.retain(|pack| pack.name != name);
+                }
+            }
+            ConfigModification::SetSmartCrop(enabled) => {
+                self.smart_crop = Some(enabled);
+            }
         }
 
         self.save()?;
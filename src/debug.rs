@@ -1,4 +1,359 @@
+use std::{
+    collections::VecDeque,
+    fmt::{Display, Write},
+    time::{Duration, Instant},
+};
+
+use eframe::glow::{self, HasContext};
+use strum::IntoEnumIterator;
+use strum_macros::EnumIter;
+
+use crate::{
+    autosave_manager::AutoSaveManager,
+    dependencies::{Dependency, SingletonFor},
+    photo_manager::{PendingLoads, PhotoManager},
+    scene::canvas_scene::HistoryMetrics,
+    texture_cache::TextureMetrics,
+};
+
 #[derive(Debug, Clone, Default)]
 pub struct DebugSettings {
     pub show_quick_layout_order: bool,
+    pub show_diagnostics: bool,
+}
+
+// About two seconds of frames at 60 FPS
+pub const FRAME_SAMPLES: usize = 120;
+
+const MIB: usize = 1024 * 1024;
+
+/// The parts of a frame that are timed on their own
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter)]
+pub enum FramePhase {
+    Fonts,
+    Scene,
+    Modals,
+    AutoSave,
+}
+
+impl Display for FramePhase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FramePhase::Fonts => write!(f, "Fonts"),
+            FramePhase::Scene => write!(f, "Scene"),
+            FramePhase::Modals => write!(f, "Modals"),
+            FramePhase::AutoSave => write!(f, "Auto Save"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct FrameTiming {
+    started_at: Instant,
+    total: Duration,
+    phases: [Duration; 4],
+}
+
+impl FrameTiming {
+    fn new() -> Self {
+        Self {
+            started_at: Instant::now(),
+            total: Duration::ZERO,
+            phases: [Duration::ZERO; 4],
+        }
+    }
+}
+
+/// The graphics driver the app is drawing with, read once on the first frame
+#[derive(Debug, Clone)]
+pub struct GpuInfo {
+    pub renderer: String,
+    pub vendor: String,
+    pub version: String,
+    pub max_texture_side: usize,
+}
+
+impl GpuInfo {
+    pub fn read(gl: &glow::Context, max_texture_side: usize) -> Self {
+        // Only reads strings the driver reports, which is fine on the thread drawing the frame
+        unsafe {
+            Self {
+                renderer: gl.get_parameter_string(glow::RENDERER),
+                vendor: gl.get_parameter_string(glow::VENDOR),
+                version: gl.get_parameter_string(glow::VERSION),
+                max_texture_side,
+            }
+        }
+    }
+}
+
+/// Timings of the last few seconds of frames, for the diagnostics panel. The app times each
+/// phase of the frame and records it here, the lock isn't held while the phase runs.
+#[derive(Debug)]
+pub struct FrameDiagnostics {
+    frames: VecDeque<FrameTiming>,
+    current: Option<FrameTiming>,
+    gpu: Option<GpuInfo>,
+}
+
+impl FrameDiagnostics {
+    pub fn new() -> Self {
+        Self {
+            frames: VecDeque::with_capacity(FRAME_SAMPLES),
+            current: None,
+            gpu: None,
+        }
+    }
+
+    pub fn begin_frame(&mut self) {
+        self.current = Some(FrameTiming::new());
+    }
+
+    pub fn record(&mut self, phase: FramePhase, duration: Duration) {
+        if let Some(current) = &mut self.current {
+            current.phases[phase as usize] += duration;
+        }
+    }
+
+    pub fn end_frame(&mut self) {
+        let Some(mut current) = self.current.take() else {
+            return;
+        };
+
+        current.total = current.started_at.elapsed();
+        if self.frames.len() == FRAME_SAMPLES {
+            self.frames.pop_front();
+        }
+        self.frames.push_back(current);
+    }
+
+    pub fn set_gpu(&mut self, gpu: GpuInfo) {
+        self.gpu = Some(gpu);
+    }
+
+    // Frames started per second, the app only repaints when something changes so this drops
+    // to nothing while it's idle
+    fn fps(&self) -> f32 {
+        let (Some(first), Some(last)) = (self.frames.front(), self.frames.back()) else {
+            return 0.0;
+        };
+
+        let span = (last.started_at - first.started_at).as_secs_f32();
+        if span <= 0.0 {
+            return 0.0;
+        }
+
+        (self.frames.len() - 1) as f32 / span
+    }
+
+    fn average(&self, duration: impl Fn(&FrameTiming) -> Duration) -> Duration {
+        if self.frames.is_empty() {
+            return Duration::ZERO;
+        }
+
+        self.frames.iter().map(duration).sum::<Duration>() / self.frames.len() as u32
+    }
+}
+
+/// Whether an auto save is running and how long the last one took
+#[derive(Debug, Clone, Copy)]
+pub struct AutoSaveTiming {
+    pub since_last_save: Option<Duration>,
+    pub last_save_duration: Option<Duration>,
+    pub saving: bool,
+}
+
+/// Everything the diagnostics panel shows, taken at once so it can be copied into a bug report
+pub struct DiagnosticsReport {
+    pub fps: f32,
+    pub frame_time: Duration,
+    pub slowest_frame: Duration,
+    pub phases: Vec<(FramePhase, Duration)>,
+    pub gpu: Option<GpuInfo>,
+    pub textures: TextureMetrics,
+    pub pending_loads: PendingLoads,
+    // None while no project is being edited
+    pub history: Option<HistoryMetrics>,
+    pub auto_save: AutoSaveTiming,
+}
+
+impl DiagnosticsReport {
+    pub fn collect(history: Option<HistoryMetrics>) -> Self {
+        let (fps, frame_time, slowest_frame, phases, gpu) = Dependency::<FrameDiagnostics>::get()
+            .with_lock(|diagnostics| {
+                (
+                    diagnostics.fps(),
+                    diagnostics.average(|frame| frame.total),
+                    diagnostics
+                        .frames
+                        .iter()
+                        .map(|frame| frame.total)
+                        .max()
+                        .unwrap_or_default(),
+                    FramePhase::iter()
+                        .map(|phase| {
+                            (
+                                phase,
+                                diagnostics.average(|frame| frame.phases[phase as usize]),
+                            )
+                        })
+                        .collect(),
+                    diagnostics.gpu.clone(),
+                )
+            });
+
+        let (textures, pending_loads) =
+            Dependency::<PhotoManager>::get().with_lock(|photo_manager| {
+                (
+                    photo_manager.texture_metrics(),
+                    photo_manager.pending_loads(),
+                )
+            });
+
+        let auto_save = Dependency::<AutoSaveManager>::get()
+            .with_lock(|auto_save_manager| auto_save_manager.timing());
+
+        Self {
+            fps,
+            frame_time,
+            slowest_frame,
+            phases,
+            gpu,
+            textures,
+            pending_loads,
+            history,
+            auto_save,
+        }
+    }
+
+    /// Name and value rows, grouped under headings
+    pub fn sections(&self) -> Vec<(&'static str, Vec<(String, String)>)> {
+        let mut frames = vec![
+            ("FPS".to_string(), format!("{:.1}", self.fps)),
+            ("Frame Time".to_string(), format_duration(self.frame_time)),
+            (
+                "Slowest Frame".to_string(),
+                format_duration(self.slowest_frame),
+            ),
+        ];
+        frames.extend(
+            self.phases
+                .iter()
+                .map(|(phase, duration)| (format!("  {}", phase), format_duration(*duration))),
+        );
+
+        let gpu = match &self.gpu {
+            Some(gpu) => vec![
+                ("Renderer".to_string(), gpu.renderer.clone()),
+                ("Vendor".to_string(), gpu.vendor.clone()),
+                ("Version".to_string(), gpu.version.clone()),
+                (
+                    "Max Texture Size".to_string(),
+                    gpu.max_texture_side.to_string(),
+                ),
+            ],
+            None => vec![("Renderer".to_string(), "Unknown".to_string())],
+        };
+
+        let textures = vec![
+            (
+                "Textures".to_string(),
+                format!(
+                    "{} ({} thumbnails, {} prefetched)",
+                    self.textures.textures, self.textures.thumbnails, self.textures.prefetched
+                ),
+            ),
+            (
+                "Texture Memory".to_string(),
+                format!(
+                    "{} / {} MiB",
+                    self.textures.bytes / MIB,
+                    self.textures.budget / MIB
+                ),
+            ),
+            (
+                "Texture Evictions".to_string(),
+                self.textures.evictions.to_string(),
+            ),
+        ];
+
+        let loads = vec![
+            (
+                "Queued Loads".to_string(),
+                self.pending_loads.queued.to_string(),
+            ),
+            (
+                "Loading".to_string(),
+                self.pending_loads.loading.to_string(),
+            ),
+            (
+                "Other Texture Loads".to_string(),
+                self.pending_loads.unqueued.to_string(),
+            ),
+        ];
+
+        let history = match &self.history {
+            Some(history) => vec![
+                ("Undo Steps".to_string(), history.entries.to_string()),
+                (
+                    "History Memory".to_string(),
+                    format!("{:.1} MiB", history.bytes as f32 / MIB as f32),
+                ),
+            ],
+            None => vec![("Undo Steps".to_string(), "No project".to_string())],
+        };
+
+        let auto_save = vec![
+            (
+                "Last Auto Save".to_string(),
+                match self.auto_save.since_last_save {
+                    Some(since) => format!("{}s ago", since.as_secs()),
+                    None => "Never".to_string(),
+                },
+            ),
+            (
+                "Auto Save Took".to_string(),
+                self.auto_save
+                    .last_save_duration
+                    .map(format_duration)
+                    .unwrap_or_else(|| "-".to_string()),
+            ),
+            (
+                "Saving".to_string(),
+                if self.auto_save.saving { "Yes" } else { "No" }.to_string(),
+            ),
+        ];
+
+        vec![
+            ("Frames", frames),
+            ("GPU", gpu),
+            ("Textures", textures),
+            ("Async Loads", loads),
+            ("Undo History", history),
+            ("Auto Save", auto_save),
+        ]
+    }
+
+    /// Plain text for pasting into a bug report
+    pub fn text(&self) -> String {
+        let mut text = format!(
+            "Photo Book {} on {} ({})\n",
+            env!("CARGO_PKG_VERSION"),
+            std::env::consts::OS,
+            std::env::consts::ARCH
+        );
+
+        for (heading, rows) in self.sections() {
+            writeln!(text, "\n[{}]", heading).unwrap();
+            for (name, value) in rows {
+                writeln!(text, "{}: {}", name, value).unwrap();
+            }
+        }
+
+        text
+    }
+}
+
+fn format_duration(duration: Duration) -> String {
+    format!("{:.2} ms", duration.as_secs_f64() * 1000.0)
 }
@@ -0,0 +1,117 @@
+use std::path::PathBuf;
+
+use egui::Vec2;
+use log::{error, info, warn};
+use strum_macros::EnumIter;
+
+use crate::{
+    dependencies::{Dependency, SingletonFor},
+    project_settings::ProjectSettingsManager,
+};
+
+// Used for vector assets and anything whose size can't be read
+const DEFAULT_DECORATION_SIZE: Vec2 = Vec2::splat(500.0);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter)]
+pub enum DecorationKind {
+    Sticker,
+    Ornament,
+    Texture,
+}
+
+impl DecorationKind {
+    pub fn extensions(&self) -> &'static [&'static str] {
+        match self {
+            DecorationKind::Sticker => &["png"],
+            DecorationKind::Ornament => &["svg"],
+            DecorationKind::Texture => &["png", "jpg", "jpeg"],
+        }
+    }
+}
+
+impl std::fmt::Display for DecorationKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DecorationKind::Sticker => write!(f, "Stickers"),
+            DecorationKind::Ornament => write!(f, "Ornaments"),
+            DecorationKind::Texture => write!(f, "Textures"),
+        }
+    }
+}
+
+/// A non-photo asset such as a sticker or background texture that belongs to the project
+#[derive(Debug, Clone, PartialEq)]
+pub struct Decoration {
+    pub path: PathBuf,
+    pub kind: DecorationKind,
+}
+
+impl Decoration {
+    pub fn new(path: PathBuf, kind: DecorationKind) -> Self {
+        Self { path, kind }
+    }
+
+    pub fn name(&self) -> String {
+        self.path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default()
+    }
+
+    // Loaded through the egui image loaders, which rasterize SVGs at the size they are drawn at
+    pub fn uri(&self) -> String {
+        format!("file://{}", self.path.display())
+    }
+
+    pub fn is_vector(&self) -> bool {
+        self.path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("svg"))
+    }
+
+    pub fn size(&self) -> Vec2 {
+        if self.is_vector() {
+            return DEFAULT_DECORATION_SIZE;
+        }
+
+        match imagesize::size(&self.path) {
+            Ok(size) => Vec2::new(size.width as f32, size.height as f32),
+            Err(err) => {
+                warn!("Failed to read size of {}: {:?}", self.path.display(), err);
+                DEFAULT_DECORATION_SIZE
+            }
+        }
+    }
+
+    pub fn import(kind: DecorationKind) {
+        let paths = native_dialog::FileDialog::new()
+            .add_filter(&kind.to_string(), kind.extensions())
+            .show_open_multiple_file();
+
+        match paths {
+            Ok(paths) => {
+                info!("Importing {} {}", paths.len(), kind);
+                Dependency::<ProjectSettingsManager>::get().with_lock_mut(|manager| {
+                    let decorations = &mut manager.project_settings.decorations;
+                    for path in paths {
+                        if !decorations.iter().any(|decoration| decoration.path == path) {
+                            decorations.push(Decoration::new(path, kind));
+                        }
+                    }
+                });
+            }
+            Err(err) => {
+                error!("Error opening asset import dialog: {:?}", err);
+            }
+        }
+    }
+
+    pub fn remove(decoration: &Decoration) {
+        Dependency::<ProjectSettingsManager>::get().with_lock_mut(|manager| {
+            manager
+                .project_settings
+                .decorations
+                .retain(|existing| existing != decoration);
+        });
+    }
+}
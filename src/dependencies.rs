@@ -2,10 +2,23 @@ use parking_lot::{RwLock, RwLockReadGuard, RwLockWriteGuard};
 use std::{marker::PhantomData, sync::Arc};
 
 use crate::{
-    auto_persisting::AutoPersisting, autosave_manager::AutoSaveManager, config::Config,
-    cursor_manager::CursorManager, debug::DebugSettings, export::Exporter,
-    font_manager::FontManager, modal::manager::ModalManager, photo_manager::PhotoManager,
-    project_settings::ProjectSettingsManager, session::Session,
+    auto_persisting::AutoPersisting,
+    autosave_manager::AutoSaveManager,
+    config::Config,
+    cursor_manager::CursorManager,
+    debug::{DebugSettings, FrameDiagnostics},
+    error_sink::ErrorSink,
+    export::Exporter,
+    font_manager::FontManager,
+    modal::manager::ModalManager,
+    page_preloader::PagePreloader,
+    photo_manager::PhotoManager,
+    project_settings::ProjectSettingsManager,
+    saliency::SaliencyCache,
+    session::Session,
+    snippet::SnippetManager,
+    spell_check::SpellChecker,
+    trash::TrashManager,
 };
 
 macro_rules! singleton {
@@ -89,3 +102,17 @@ singleton!(AUTOSAVE_MANAGER, AutoSaveManager, AutoSaveManager::new());
 singleton!(SESSION, Session, Session::new());
 
 singleton!(DEBUG_SETTINGS, DebugSettings, DebugSettings::default());
+
+singleton!(FRAME_DIAGNOSTICS, FrameDiagnostics, FrameDiagnostics::new());
+
+singleton!(SPELL_CHECKER, SpellChecker, SpellChecker::new());
+
+singleton!(TRASH_MANAGER, TrashManager, TrashManager::new());
+
+singleton!(SNIPPET_MANAGER, SnippetManager, SnippetManager::new());
+
+singleton!(PAGE_PRELOADER, PagePreloader, PagePreloader::new());
+
+singleton!(ERROR_SINK, ErrorSink, ErrorSink::new());
+
+singleton!(SALIENCY_CACHE, SaliencyCache, SaliencyCache::new());
@@ -9,6 +9,14 @@ const SUBDIR: &str = "photo_album";
 pub enum Dirs {
     Thumbnails,
     Config,
+    // Fonts unpacked from project archives
+    Fonts,
+    // Frames taken from animated GIF and WebP files to use as photos
+    Stills,
+    // Indexes that are slow to build but can be rebuilt at any time
+    Cache,
+    // Word lists for the spell checker, for systems that don't come with one
+    Dictionaries,
 }
 
 impl Dirs {
@@ -16,6 +24,10 @@ impl Dirs {
         match *self {
             Dirs::Thumbnails => dirs::cache_dir().unwrap().join(SUBDIR),
             Dirs::Config => dirs::config_dir().unwrap().join(SUBDIR),
+            Dirs::Fonts => dirs::data_dir().unwrap().join(SUBDIR).join("fonts"),
+            Dirs::Stills => dirs::data_dir().unwrap().join(SUBDIR).join("stills"),
+            Dirs::Cache => dirs::cache_dir().unwrap().join(SUBDIR).join("index"),
+            Dirs::Dictionaries => dirs::data_dir().unwrap().join(SUBDIR).join("dictionaries"),
         }
     }
 }
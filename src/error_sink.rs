@@ -1 +1,338 @@
+use std::{
+    fmt::Debug,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
+use chrono::{DateTime, Local};
+use eframe::egui::{self, Align2, Context, RichText, ScrollArea, Vec2};
+use log::error;
+
+use crate::{
+    dependencies::{Dependency, SingletonFor},
+    theme,
+};
+
+// How long a toast stays up. Hovering it keeps it up.
+const TOAST_DURATION: Duration = Duration::from_secs(8);
+
+const MAX_TOASTS: usize = 3;
+
+const TOAST_WIDTH: f32 = 360.0;
+
+const TOAST_MARGIN: f32 = 16.0;
+
+// Toasts only show the start of the details, the log has the rest
+const DETAILS_PREVIEW_LENGTH: usize = 160;
+
+// Older errors are dropped so a failure that keeps repeating can't grow the log forever
+const MAX_ENTRIES: usize = 200;
+
+pub type RetryAction = Arc<dyn Fn() + Send + Sync>;
+
+#[derive(Clone)]
+pub struct ErrorEntry {
+    pub id: u64,
+    // What the user was trying to do, e.g. "Couldn't save the project"
+    pub summary: String,
+    pub details: String,
+    pub occurred_at: DateTime<Local>,
+    // How many times in a row the same error was reported
+    pub count: usize,
+    retry: Option<RetryAction>,
+    toast_until: Option<Instant>,
+    last_reported: Instant,
+}
+
+impl ErrorEntry {
+    /// Everything worth pasting into a bug report
+    pub fn report(&self) -> String {
+        format!(
+            "{}\nVersion: {}\nTime: {}\nOccurrences: {}\n\n{}",
+            self.summary,
+            env!("CARGO_PKG_VERSION"),
+            self.occurred_at.format("%Y-%m-%d %H:%M:%S"),
+            self.count,
+            self.details
+        )
+    }
+}
+
+/// Collects the errors the user should know about instead of them only ending up in the log. New
+/// errors pop up as toasts and every error stays in the error log until it's cleared.
+#[derive(Default)]
+pub struct ErrorSink {
+    entries: Vec<ErrorEntry>,
+    next_id: u64,
+    show_log: bool,
+    // Errors reported since the log was last opened
+    unseen: usize,
+}
+
+impl ErrorSink {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Logs the error and shows it to the user
+    pub fn report(summary: impl Into<String>, details: impl Debug) {
+        Self::report_inner(summary.into(), format!("{:?}", details), None);
+    }
+
+    /// Like `report` with a Retry button that runs `retry`
+    pub fn report_with_retry(
+        summary: impl Into<String>,
+        details: impl Debug,
+        retry: impl Fn() + Send + Sync + 'static,
+    ) {
+        Self::report_inner(
+            summary.into(),
+            format!("{:?}", details),
+            Some(Arc::new(retry)),
+        );
+    }
+
+    pub fn open_log() {
+        Dependency::<ErrorSink>::get().with_lock_mut(|sink| {
+            sink.show_log = true;
+            sink.unseen = 0;
+        });
+    }
+
+    pub fn unseen_count() -> usize {
+        Dependency::<ErrorSink>::get().with_lock(|sink| sink.unseen)
+    }
+
+    fn report_inner(summary: String, details: String, retry: Option<RetryAction>) {
+        error!("{}: {}", summary, details);
+
+        Dependency::<ErrorSink>::get().with_lock_mut(|sink| {
+            let now = Instant::now();
+            let toast_until = Some(now + TOAST_DURATION);
+
+            // Errors that happen every frame, like a texture that won't load, are counted instead
+            // of piling up. They only pop up again once they've been quiet for a while.
+            if let Some(existing) = sink
+                .entries
+                .iter_mut()
+                .find(|entry| entry.summary == summary && entry.details == details)
+            {
+                if now.duration_since(existing.last_reported) > TOAST_DURATION {
+                    existing.toast_until = toast_until;
+                }
+                existing.count += 1;
+                existing.occurred_at = Local::now();
+                existing.last_reported = now;
+                existing.retry = retry;
+                return;
+            }
+
+            let id = sink.next_id;
+            sink.entries.push(ErrorEntry {
+                id,
+                summary,
+                details,
+                occurred_at: Local::now(),
+                count: 1,
+                retry,
+                toast_until,
+                last_reported: now,
+            });
+            sink.next_id += 1;
+
+            if !sink.show_log {
+                sink.unseen += 1;
+            }
+
+            if sink.entries.len() > MAX_ENTRIES {
+                let excess = sink.entries.len() - MAX_ENTRIES;
+                sink.entries.drain(..excess);
+            }
+        });
+    }
+
+    /// Draws the toasts and the error log on top of everything else
+    pub fn show(ctx: &Context) {
+        // Retries run once the sink is unlocked since they may report errors of their own
+        let retries = Dependency::<ErrorSink>::get().with_lock_mut(|sink| {
+            let mut retries = sink.toasts_ui(ctx);
+            retries.extend(sink.log_ui(ctx));
+            retries
+        });
+
+        for retry in retries {
+            retry();
+        }
+    }
+
+    fn toasts_ui(&mut self, ctx: &Context) -> Vec<RetryAction> {
+        let now = Instant::now();
+        let mut retries = Vec::new();
+        let mut open_log = false;
+        let mut offset = 0.0;
+
+        let toasts: Vec<usize> = self
+            .entries
+            .iter()
+            .enumerate()
+            .rev()
+            .filter(|(_, entry)| entry.toast_until.is_some_and(|until| until > now))
+            .map(|(index, _)| index)
+            .take(MAX_TOASTS)
+            .collect();
+
+        for index in toasts {
+            let entry = &mut self.entries[index];
+
+            let response = egui::Area::new(egui::Id::new(("error_toast", entry.id)))
+                .anchor(
+                    Align2::RIGHT_BOTTOM,
+                    Vec2::new(-TOAST_MARGIN, -TOAST_MARGIN - offset),
+                )
+                .order(egui::Order::Foreground)
+                .show(ctx, |ui| {
+                    egui::Frame::popup(ui.style()).show(ui, |ui| {
+                        ui.set_width(TOAST_WIDTH);
+
+                        ui.horizontal(|ui| {
+                            ui.label(RichText::new("⚠").color(theme::color::ERROR));
+                            ui.label(RichText::new(&entry.summary).strong());
+                            if entry.count > 1 {
+                                ui.label(RichText::new(format!("×{}", entry.count)).weak());
+                            }
+                        });
+
+                        let mut preview: String =
+                            entry.details.chars().take(DETAILS_PREVIEW_LENGTH).collect();
+                        if preview.len() < entry.details.len() {
+                            preview.push('…');
+                        }
+                        ui.label(RichText::new(preview).weak().small());
+
+                        ui.horizontal(|ui| {
+                            if let Some(retry) = &entry.retry {
+                                if ui.button("Retry").clicked() {
+                                    retries.push(retry.clone());
+                                    entry.toast_until = None;
+                                }
+                            }
+
+                            if ui.button("Copy Details").clicked() {
+                                ctx.copy_text(entry.report());
+                            }
+
+                            if ui.button("View Log").clicked() {
+                                open_log = true;
+                                entry.toast_until = None;
+                            }
+
+                            if ui.button("Dismiss").clicked() {
+                                entry.toast_until = None;
+                            }
+                        });
+                    });
+                })
+                .response;
+
+            if response.contains_pointer() {
+                if let Some(until) = &mut entry.toast_until {
+                    *until = (*until).max(now + Duration::from_secs(2));
+                }
+            }
+
+            offset += response.rect.height() + ctx.style().spacing.item_spacing.y;
+        }
+
+        // Repaint when the next toast should disappear
+        if let Some(next) = self
+            .entries
+            .iter()
+            .filter_map(|entry| entry.toast_until)
+            .filter(|until| *until > now)
+            .min()
+        {
+            ctx.request_repaint_after(next - now);
+        }
+
+        if open_log {
+            self.show_log = true;
+            self.unseen = 0;
+        }
+
+        retries
+    }
+
+    fn log_ui(&mut self, ctx: &Context) -> Vec<RetryAction> {
+        let mut retries = Vec::new();
+
+        if !self.show_log {
+            return retries;
+        }
+
+        let mut open = true;
+        let mut clear = false;
+
+        egui::Window::new("Error Log")
+            .open(&mut open)
+            .default_size(Vec2::new(520.0, 360.0))
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(format!("{} error(s)", self.entries.len()));
+                    if ui.button("Clear").clicked() {
+                        clear = true;
+                    }
+                });
+
+                ui.separator();
+
+                if self.entries.is_empty() {
+                    ui.label(RichText::new("Nothing has gone wrong").weak());
+                    return;
+                }
+
+                ScrollArea::vertical().auto_shrink(false).show(ui, |ui| {
+                    for entry in self.entries.iter().rev() {
+                        ui.horizontal(|ui| {
+                            ui.label(
+                                RichText::new(entry.occurred_at.format("%H:%M:%S").to_string())
+                                    .weak()
+                                    .monospace(),
+                            );
+                            ui.label(RichText::new(&entry.summary).color(theme::color::ERROR));
+                            if entry.count > 1 {
+                                ui.label(RichText::new(format!("×{}", entry.count)).weak());
+                            }
+                        });
+
+                        egui::CollapsingHeader::new("Details")
+                            .id_salt(("error_details", entry.id))
+                            .show(ui, |ui| {
+                                ui.label(RichText::new(&entry.details).monospace());
+                            });
+
+                        ui.horizontal(|ui| {
+                            if ui.button("Copy Details").clicked() {
+                                ctx.copy_text(entry.report());
+                            }
+
+                            if let Some(retry) = &entry.retry {
+                                if ui.button("Retry").clicked() {
+                                    retries.push(retry.clone());
+                                }
+                            }
+                        });
+
+                        ui.separator();
+                    }
+                });
+            });
+
+        if clear {
+            self.entries.clear();
+        }
+
+        self.show_log = open;
+
+        retries
+    }
+}
@@ -1,16 +1,23 @@
-use egui::{Pos2, Rect};
-use log::{error, info};
+use egui::{
+    emath::Rot2, epaint::TextShape, load::BytesPoll, Color32, FontFamily, FontId, Painter, Pos2,
+    Rect, Vec2,
+};
+use log::{error, info, warn};
 
+use serde::Serialize;
 use skia_safe::surfaces::raster_n32_premul;
-use skia_safe::EncodedImageFormat;
+use skia_safe::{AlphaType, ColorType, EncodedImageFormat, ImageInfo, Paint, Surface};
 
 use printpdf::{ImageTransform, Mm, PdfDocument};
 use std::collections::HashMap;
 use std::default;
 use std::fs::File;
 use std::io::{BufWriter, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use strum_macros::{Display, EnumIter};
 
 use tokio::task::spawn_blocking;
 
@@ -18,14 +25,23 @@ use smol_egui_skia::{EguiSkia, RasterizeOptions};
 
 use thiserror::Error;
 
+use crate::book_vendor::{BookVendor, CoverSpec};
 use crate::dependencies::{Dependency, Singleton, SingletonFor};
+use crate::error_sink::ErrorSink;
 
 use crate::font_manager::FontManager;
-use crate::modal::manager::ModalManager;
+use crate::modal::manager::{ModalManager, TypedModalId};
 use crate::modal::progress::ProgressModal;
+use crate::model::fill::Fill;
+use crate::model::resize_quality::ResizeQuality;
+use crate::output_sharpening::{OutputSharpening, SharpeningPreset};
 use crate::photo_manager::PhotoManager;
+use crate::project_settings::ProjectSettingsManager;
+use crate::psd_export::{self, PsdLayer};
+use crate::render;
 use crate::scene::canvas_scene::CanvasHistoryManager;
-use crate::widget::canvas::{Canvas, CanvasState};
+use crate::svg_export::{SvgPhotoMode, SvgWriter};
+use crate::widget::canvas::{Canvas, CanvasPhoto, CanvasState};
 use crate::widget::canvas_info::layers::LayerContent;
 
 #[derive(Error, Debug, Clone)]
@@ -36,12 +52,16 @@ pub enum ExportError {
     TextureLoadingError(String),
     #[error("Failed to encode image")]
     ImageEncodingError,
+    #[error("Failed to decode image")]
+    ImageDecodingError,
     #[error("File operation error: {0}")]
     FileError(String),
     #[error("PDF rendering error: {0}")]
     PdfRenderingError(String),
     #[error("PDF saving error: {0}")]
     PdfSavingError(String),
+    #[error("Export cancelled")]
+    Cancelled,
 }
 
 #[derive(Debug, Hash, PartialEq, Eq, Clone, Copy)]
@@ -53,9 +73,132 @@ pub struct ExportTaskId {
 pub enum ExportTaskStatus {
     InProgress(f32),
     Completed,
+    Cancelled,
     Failed(ExportError),
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SlideshowSettings {
+    // All durations are in seconds
+    pub page_duration: f32,
+    pub transition_duration: f32,
+    pub frame_rate: u32,
+}
+
+impl Default for SlideshowSettings {
+    fn default() -> Self {
+        Self {
+            page_duration: 5.0,
+            transition_duration: 1.0,
+            frame_rate: 30,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Display, EnumIter)]
+pub enum ProofFormat {
+    #[strum(to_string = "PDF")]
+    Pdf,
+    #[strum(to_string = "JPEG Images")]
+    Jpeg,
+}
+
+/// A small, watermarked copy of the book for sending out for approval, separate from the print
+/// quality export
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProofSettings {
+    pub format: ProofFormat,
+    // Pixels along each page's long edge, pages already smaller than this aren't scaled up
+    pub long_edge: u32,
+    // JPEG quality from 1 to 100
+    pub quality: u32,
+    // Drawn across every page, nothing is drawn when it's empty
+    pub watermark: String,
+    // Scaling the pages down softens the photos
+    pub sharpening: Option<OutputSharpening>,
+}
+
+impl Default for ProofSettings {
+    fn default() -> Self {
+        Self {
+            format: ProofFormat::Pdf,
+            long_edge: 1600,
+            quality: 70,
+            watermark: "PROOF".to_string(),
+            sharpening: Some(SharpeningPreset::Screen.settings()),
+        }
+    }
+}
+
+impl ProofSettings {
+    /// How much the page is scaled down to fit the long edge
+    pub fn scale(&self, page: &CanvasState) -> f32 {
+        let size = page.page.size_pixels();
+        (self.long_edge as f32 / size.x.max(size.y)).min(1.0)
+    }
+
+    fn watermark(&self) -> Option<&str> {
+        Some(self.watermark.trim()).filter(|watermark| !watermark.is_empty())
+    }
+}
+
+// Describes the order and timing of the exported images so they can be
+// played back directly or assembled into a video with an external encoder
+#[derive(Debug, Serialize)]
+struct SlideshowManifest {
+    frame_rate: u32,
+    frames: Vec<SlideshowFrame>,
+}
+
+#[derive(Debug, Serialize)]
+struct SlideshowFrame {
+    file: String,
+    duration: f32,
+}
+
+// Reports how far along a background export is to its task and its progress modal. Cancelling
+// the modal closes it, which stops the export at its next step.
+struct ExportProgress {
+    ctx: egui::Context,
+    tasks: Arc<Mutex<HashMap<ExportTaskId, ExportTaskStatus>>>,
+    task_id: ExportTaskId,
+    modal_id: TypedModalId<ProgressModal>,
+    num_steps: usize,
+    completed_steps: usize,
+}
+
+impl ExportProgress {
+    fn step(&mut self, message: String) -> Result<(), ExportError> {
+        self.completed_steps += 1;
+        let progress = self.completed_steps as f32 / self.num_steps as f32;
+
+        let cancelled = Dependency::<ModalManager>::get().with_lock(|modal_manager| {
+            if !modal_manager.exists(&self.modal_id) {
+                return true;
+            }
+
+            let _ = modal_manager.modify(&self.modal_id, |progress_modal| {
+                progress_modal.progress = progress;
+                progress_modal.message = message;
+            });
+
+            false
+        });
+
+        if cancelled {
+            return Err(ExportError::Cancelled);
+        }
+
+        self.tasks
+            .lock()
+            .unwrap()
+            .insert(self.task_id, ExportTaskStatus::InProgress(progress));
+        self.ctx.request_repaint();
+
+        Ok(())
+    }
+}
+
 pub struct Exporter {
     pub tasks: Arc<Mutex<HashMap<ExportTaskId, ExportTaskStatus>>>,
 }
@@ -72,119 +215,761 @@ impl Exporter {
         tasks.get(&task_id).cloned()
     }
 
+    /// Exports the pages at `page_indices` of the book. The whole book is passed along because the
+    /// vendor's cover depends on its page count.
     pub fn export(
         &mut self,
         ctx: egui::Context,
         pages: Vec<CanvasState>,
+        page_indices: Vec<usize>,
         directory: PathBuf,
         file_name: &str,
+        vendor: Option<BookVendor>,
+        sharpening: Option<OutputSharpening>,
     ) -> ExportTaskId {
-        let task_id = ExportTaskId {
-            task_id: rand::random(),
+        let file_name = file_name.to_string();
+
+        let retry = {
+            let (ctx, pages, page_indices, directory, file_name) = (
+                ctx.clone(),
+                pages.clone(),
+                page_indices.clone(),
+                directory.clone(),
+                file_name.clone(),
+            );
+            move |exporter: &mut Exporter| {
+                exporter.export(
+                    ctx.clone(),
+                    pages.clone(),
+                    page_indices.clone(),
+                    directory.clone(),
+                    &file_name,
+                    vendor,
+                    sharpening,
+                );
+            }
         };
 
-        let tasks = self.tasks.clone();
+        // +1 for the PDF generation
+        let num_steps = page_indices.len() + 1;
+
+        self.run_export(
+            ctx,
+            "Exporting",
+            "Couldn't export the book",
+            directory,
+            num_steps,
+            retry,
+            move |directory, progress| {
+                // Recorded as the user laid them out, before the vendor adjusts them
+                let laid_out_book = pages.clone();
+                let book = match vendor {
+                    Some(vendor) => pages
+                        .iter()
+                        .map(|page| vendor.prepare_page(page))
+                        .collect::<Vec<_>>(),
+                    None => pages,
+                };
+                let cover_spec = vendor.and_then(|vendor| vendor.cover_spec(&book));
+                let pages = page_indices
+                    .iter()
+                    .map(|index| book[*index].clone())
+                    .collect::<Vec<_>>();
+                let file_stem = Path::new(&file_name)
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                // Named after their place in the book so exporting a few pages doesn't renumber them
+                let image_names = page_indices
+                    .iter()
+                    .map(|page_number| match vendor {
+                        Some(vendor) => vendor.page_file_name(&file_stem, *page_number),
+                        None => Self::page_image_name(*page_number),
+                    })
+                    .collect::<Vec<_>>();
+
+                let num_pages = pages.len();
+                for (page_number, page) in pages.iter().enumerate() {
+                    Self::export_page(
+                        page.clone(),
+                        directory,
+                        &image_names[page_number],
+                        None,
+                        sharpening.as_ref(),
+                    )?;
+                    progress.step(format!("Exporting page {}/{}", page_number + 1, num_pages))?;
+                }
+
+                if let Some(cover_spec) = cover_spec {
+                    Self::export_cover_spec(&cover_spec, directory)?;
+                }
+
+                Self::export_pdf(&pages, &image_names, directory, &file_name, None)?;
+
+                Dependency::<ProjectSettingsManager>::get().with_lock_mut(|manager| {
+                    manager
+                        .project_settings
+                        .record_export(&laid_out_book, &page_indices);
+                });
+
+                Ok(())
+            },
+        )
+    }
+
+    pub fn export_slideshow(
+        &mut self,
+        ctx: egui::Context,
+        pages: Vec<CanvasState>,
+        directory: PathBuf,
+        settings: SlideshowSettings,
+    ) -> ExportTaskId {
+        let retry = {
+            let (ctx, pages, directory) = (ctx.clone(), pages.clone(), directory.clone());
+            move |exporter: &mut Exporter| {
+                exporter.export_slideshow(ctx.clone(), pages.clone(), directory.clone(), settings);
+            }
+        };
+
+        let num_pages = pages.len();
+        // Each page is rendered and then each transition between pages is rendered
+        let num_steps = (num_pages * 2).saturating_sub(1).max(1);
+
+        self.run_export(
+            ctx,
+            "Exporting Slideshow",
+            "Couldn't export the slideshow",
+            directory,
+            num_steps,
+            retry,
+            move |directory, progress| {
+                let mut manifest = SlideshowManifest {
+                    frame_rate: settings.frame_rate,
+                    frames: Vec::new(),
+                };
 
+                for (page_number, page) in pages.iter().enumerate() {
+                    Self::export_page(
+                        page.clone(),
+                        directory,
+                        &Self::page_image_name(page_number),
+                        None,
+                        None,
+                    )?;
+                    progress.step(format!("Exporting page {}/{}", page_number + 1, num_pages))?;
+                }
+
+                for page_number in 0..num_pages {
+                    manifest.frames.push(SlideshowFrame {
+                        file: Self::page_image_name(page_number),
+                        duration: settings.page_duration,
+                    });
+
+                    if page_number + 1 < num_pages {
+                        let transition_frames = Self::export_transition(
+                            &directory.join(Self::page_image_name(page_number)),
+                            &directory.join(Self::page_image_name(page_number + 1)),
+                            directory,
+                            page_number,
+                            &settings,
+                        )?;
+
+                        manifest
+                            .frames
+                            .extend(transition_frames.into_iter().map(|file| SlideshowFrame {
+                                file,
+                                duration: 1.0 / settings.frame_rate as f32,
+                            }));
+
+                        progress.step(format!(
+                            "Rendering transition {}/{}",
+                            page_number + 1,
+                            num_pages - 1
+                        ))?;
+                    }
+                }
+
+                let manifest_file = File::create(directory.join("slideshow.json"))
+                    .map_err(|e| ExportError::FileError(e.to_string()))?;
+                serde_json::to_writer_pretty(BufWriter::new(manifest_file), &manifest)
+                    .map_err(|e| ExportError::FileError(e.to_string()))?;
+
+                Ok(())
+            },
+        )
+    }
+
+    /// Exports the pages at `page_indices` as a proof, a small watermarked PDF or set of JPEGs
+    /// for emailing out for approval. Vendor adjustments aren't applied and the export isn't
+    /// recorded against the pages since nothing was sent to print.
+    pub fn export_proof(
+        &mut self,
+        ctx: egui::Context,
+        pages: Vec<CanvasState>,
+        page_indices: Vec<usize>,
+        directory: PathBuf,
+        file_name: &str,
+        settings: ProofSettings,
+    ) -> ExportTaskId {
         let file_name = file_name.to_string();
 
-        if !directory.exists() {
-            if let Err(err) = std::fs::create_dir_all(&directory) {
-                let mut tasks = tasks.lock().unwrap();
-                tasks.insert(
-                    task_id,
-                    ExportTaskStatus::Failed(ExportError::FileError(err.to_string())),
+        let retry = {
+            let (ctx, pages, page_indices, directory, file_name, settings) = (
+                ctx.clone(),
+                pages.clone(),
+                page_indices.clone(),
+                directory.clone(),
+                file_name.clone(),
+                settings.clone(),
+            );
+            move |exporter: &mut Exporter| {
+                exporter.export_proof(
+                    ctx.clone(),
+                    pages.clone(),
+                    page_indices.clone(),
+                    directory.clone(),
+                    &file_name,
+                    settings.clone(),
                 );
-                ctx.request_repaint();
-                return task_id;
             }
-        }
+        };
 
-        spawn_blocking(move || {
-            let modal_manager: Singleton<ModalManager> = Dependency::get();
-            let modal_id =
-                ModalManager::push(ProgressModal::new("Exporting", "Preparing", "Cancel", 0.0));
-            let mut page_number = 0;
-            let num_pages = pages.len();
-            for page in &pages {
-                if let Err(err) = Self::export_page(page.clone(), &directory, page_number) {
-                    let mut tasks = tasks.lock().unwrap();
-                    tasks.insert(task_id, ExportTaskStatus::Failed(err));
-                    ctx.request_repaint();
-                    return;
+        let num_pages = page_indices.len();
+        let num_steps = match settings.format {
+            ProofFormat::Pdf => num_pages + 1,
+            ProofFormat::Jpeg => num_pages,
+        };
+
+        self.run_export(
+            ctx,
+            "Creating Proof",
+            "Couldn't create the proof",
+            directory,
+            num_steps,
+            retry,
+            move |directory, progress| {
+                let pages = page_indices
+                    .iter()
+                    .map(|index| pages[*index].clone())
+                    .collect::<Vec<_>>();
+                let file_stem = Path::new(&file_name)
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                // Numbered from 1 since they're read by people rather than a printer
+                let image_names = page_indices
+                    .iter()
+                    .map(|index| format!("{}_page_{}.jpg", file_stem, index + 1))
+                    .collect::<Vec<_>>();
+
+                for (page_number, page) in pages.iter().enumerate() {
+                    Self::export_page(
+                        page.clone(),
+                        directory,
+                        &image_names[page_number],
+                        Some(&settings),
+                        settings.sharpening.as_ref(),
+                    )?;
+                    progress.step(format!("Exporting page {}/{}", page_number + 1, num_pages))?;
                 }
-                page_number += 1;
-                let progress = page_number as f32 / (num_pages as f32 + 1.0); // +1 for the PDF generation
-                let mut tasks = tasks.lock().unwrap();
-                tasks.insert(task_id, ExportTaskStatus::InProgress(progress));
-                modal_manager.with_lock_mut(|modal_manager| {
-                    modal_manager.modify(&modal_id, |progress_modal| {
-                        progress_modal.progress = progress;
-                        progress_modal.message =
-                            format!("Exporting page {}/{}", page_number, num_pages);
+
+                if settings.format == ProofFormat::Pdf {
+                    Self::export_pdf(&pages, &image_names, directory, &file_name, Some(&settings))?;
+
+                    // The PDF is the proof, the page images were only needed to build it
+                    for image_name in &image_names {
+                        if let Err(err) = std::fs::remove_file(directory.join(image_name)) {
+                            warn!("Couldn't remove proof page {}: {}", image_name, err);
+                        }
+                    }
+
+                    progress.step("Saving PDF".to_string())?;
+                }
+
+                Ok(())
+            },
+        )
+    }
+
+    pub fn export_svg(
+        &mut self,
+        ctx: egui::Context,
+        pages: Vec<CanvasState>,
+        directory: PathBuf,
+        photo_mode: SvgPhotoMode,
+    ) -> ExportTaskId {
+        let retry = {
+            let (ctx, pages, directory) = (ctx.clone(), pages.clone(), directory.clone());
+            move |exporter: &mut Exporter| {
+                exporter.export_svg(ctx.clone(), pages.clone(), directory.clone(), photo_mode);
+            }
+        };
+
+        let num_pages = pages.len();
+
+        self.run_export(
+            ctx,
+            "Exporting SVG",
+            "Couldn't export the SVGs",
+            directory,
+            num_pages,
+            retry,
+            move |directory, progress| {
+                let font_definitions = Dependency::<FontManager>::get()
+                    .with_lock_mut(|font_manager| {
+                        font_manager.load_families(&page_font_families(&pages));
+                        font_manager.font_definitions.clone()
                     })
-                });
+                    .map(|font_definitions| (*font_definitions).clone())
+                    .unwrap_or_default();
+                let writer = SvgWriter::new(font_definitions, photo_mode);
 
-                ctx.request_repaint();
+                for (page_number, page) in pages.iter().enumerate() {
+                    let svg = writer.page_to_svg(page)?;
+                    std::fs::write(directory.join(format!("page_{}.svg", page_number)), svg)
+                        .map_err(|e| ExportError::FileError(e.to_string()))?;
+                    progress.step(format!("Exporting page {}/{}", page_number + 1, num_pages))?;
+                }
+
+                Ok(())
+            },
+        )
+    }
+
+    pub fn export_psd(
+        &mut self,
+        ctx: egui::Context,
+        pages: Vec<CanvasState>,
+        directory: PathBuf,
+    ) -> ExportTaskId {
+        let retry = {
+            let (ctx, pages, directory) = (ctx.clone(), pages.clone(), directory.clone());
+            move |exporter: &mut Exporter| {
+                exporter.export_psd(ctx.clone(), pages.clone(), directory.clone());
             }
+        };
 
-            if let Err(err) = Self::export_pdf(&pages, &directory, &file_name) {
+        let num_pages = pages.len();
+
+        self.run_export(
+            ctx,
+            "Exporting PSD",
+            "Couldn't export the PSDs",
+            directory,
+            num_pages,
+            retry,
+            move |directory, progress| {
+                for (page_number, page) in pages.iter().enumerate() {
+                    let psd = Self::page_to_psd(page)?;
+                    std::fs::write(directory.join(format!("page_{}.psd", page_number)), psd)
+                        .map_err(|e| ExportError::FileError(e.to_string()))?;
+                    progress.step(format!("Exporting page {}/{}", page_number + 1, num_pages))?;
+                }
+
+                Ok(())
+            },
+        )
+    }
+
+    /// Runs `export` in the background behind a progress modal titled `title`, once `directory`
+    /// exists. `export` reports each of its `num_steps` steps as it finishes them. If it fails
+    /// the user is told with `failure` and can start the same export again with `retry`.
+    fn run_export(
+        &mut self,
+        ctx: egui::Context,
+        title: &str,
+        failure: &'static str,
+        directory: PathBuf,
+        num_steps: usize,
+        retry: impl Fn(&mut Exporter) + Send + Sync + 'static,
+        export: impl FnOnce(&PathBuf, &mut ExportProgress) -> Result<(), ExportError> + Send + 'static,
+    ) -> ExportTaskId {
+        let task_id = ExportTaskId {
+            task_id: rand::random(),
+        };
+
+        let tasks = self.tasks.clone();
+
+        // Runs the same export again if it fails
+        let retry = move || {
+            Dependency::<Exporter>::get().with_lock_mut(|exporter| retry(exporter));
+        };
+
+        if !directory.exists() {
+            if let Err(err) = std::fs::create_dir_all(&directory) {
+                let err = ExportError::FileError(err.to_string());
+                ErrorSink::report_with_retry(failure, &err, retry);
                 let mut tasks = tasks.lock().unwrap();
                 tasks.insert(task_id, ExportTaskStatus::Failed(err));
                 ctx.request_repaint();
-                return;
+                return task_id;
             }
+        }
+
+        tasks
+            .lock()
+            .unwrap()
+            .insert(task_id, ExportTaskStatus::InProgress(0.0));
+
+        let title = title.to_string();
+        spawn_blocking(move || {
+            let mut progress = ExportProgress {
+                ctx: ctx.clone(),
+                tasks: tasks.clone(),
+                task_id,
+                modal_id: ModalManager::push(ProgressModal::new(
+                    title.clone(),
+                    "Preparing",
+                    "Cancel",
+                    0.0,
+                )),
+                num_steps: num_steps.max(1),
+                completed_steps: 0,
+            };
+            let result = export(&directory, &mut progress);
 
             let mut tasks = tasks.lock().unwrap();
-            tasks.insert(task_id, ExportTaskStatus::Completed);
-            modal_manager.with_lock_mut(|modal_manager| {
-                modal_manager.dismiss(modal_id);
+            match result {
+                Ok(()) => {
+                    tasks.insert(task_id, ExportTaskStatus::Completed);
+                }
+                Err(ExportError::Cancelled) => {
+                    info!("{} cancelled", title);
+                    tasks.insert(task_id, ExportTaskStatus::Cancelled);
+                }
+                Err(err) => {
+                    ErrorSink::report_with_retry(failure, &err, retry);
+                    tasks.insert(task_id, ExportTaskStatus::Failed(err));
+                }
+            }
+            Dependency::<ModalManager>::get().with_lock_mut(|modal_manager| {
+                modal_manager.dismiss(progress.modal_id);
             });
             ctx.request_repaint();
         });
 
-        let mut tasks = self.tasks.lock().unwrap();
-        tasks.insert(task_id, ExportTaskStatus::InProgress(0.0));
-
         task_id
     }
 
+    // Renders a cross-fade between two exported page images, returning the file names of the frames
+    fn export_transition(
+        from_path: &Path,
+        to_path: &Path,
+        directory: &Path,
+        transition_number: usize,
+        settings: &SlideshowSettings,
+    ) -> Result<Vec<String>, ExportError> {
+        let load_image = |path: &Path| -> Result<skia_safe::Image, ExportError> {
+            let bytes = std::fs::read(path).map_err(|e| ExportError::FileError(e.to_string()))?;
+            skia_safe::Image::from_encoded(skia_safe::Data::new_copy(&bytes))
+                .ok_or(ExportError::ImageDecodingError)
+        };
+
+        let from_image = load_image(from_path)?;
+        let to_image = load_image(to_path)?;
+
+        // Pages can differ in size so use a frame large enough to fit both
+        let width = from_image.width().max(to_image.width());
+        let height = from_image.height().max(to_image.height());
+
+        let centered_origin = |image: &skia_safe::Image| {
+            (
+                ((width - image.width()) / 2) as f32,
+                ((height - image.height()) / 2) as f32,
+            )
+        };
+
+        let num_frames =
+            (settings.transition_duration * settings.frame_rate as f32).round() as usize;
+
+        let mut file_names = Vec::with_capacity(num_frames);
+
+        for frame in 0..num_frames {
+            let alpha = (frame + 1) as f32 / (num_frames + 1) as f32;
+
+            let mut surface =
+                raster_n32_premul((width, height)).ok_or(ExportError::SurfaceCreationError)?;
+            let canvas = surface.canvas();
+            canvas.clear(skia_safe::Color::BLACK);
+
+            canvas.draw_image(&from_image, centered_origin(&from_image), None);
+
+            let mut paint = Paint::default();
+            paint.set_alpha_f(alpha);
+            canvas.draw_image(&to_image, centered_origin(&to_image), Some(&paint));
+
+            let data = surface
+                .image_snapshot()
+                .encode_to_data(EncodedImageFormat::JPEG)
+                .ok_or(ExportError::ImageEncodingError)?;
+
+            let file_name = format!("transition_{}_{}.jpg", transition_number, frame);
+
+            let mut output_file = File::create(directory.join(&file_name))
+                .map_err(|e| ExportError::FileError(e.to_string()))?;
+            output_file
+                .write_all(&data)
+                .map_err(|e| ExportError::FileError(e.to_string()))?;
+
+            file_names.push(file_name);
+        }
+
+        Ok(file_names)
+    }
+
+    fn page_image_name(page_number: usize) -> String {
+        format!("page_{}.jpg", page_number)
+    }
+
+    // Written next to the pages so the cover can be designed to the vendor's dimensions
+    fn export_cover_spec(cover_spec: &CoverSpec, directory: &Path) -> Result<(), ExportError> {
+        let cover_spec_file = File::create(directory.join("cover_spec.json"))
+            .map_err(|e| ExportError::FileError(e.to_string()))?;
+        serde_json::to_writer_pretty(BufWriter::new(cover_spec_file), cover_spec)
+            .map_err(|e| ExportError::FileError(e.to_string()))
+    }
+
+    // Proofs are scaled down, watermarked and compressed more than pages exported for print.
+    // Sharpening only changes the exported image, never the page.
     fn export_page(
         mut canvas_state: CanvasState,
         directory: &PathBuf,
-        page_number: u32,
+        image_name: &str,
+        proof: Option<&ProofSettings>,
+        sharpening: Option<&OutputSharpening>,
     ) -> Result<(), ExportError> {
         /* */
         let directory = PathBuf::from(directory);
 
-        let size = canvas_state.page.size_pixels();
-        canvas_state.zoom = 1.0;
+        let scale = proof.map_or(1.0, |proof| proof.scale(&canvas_state));
+        let size = (canvas_state.page.size_pixels() * scale).round();
 
-        let mut surface = raster_n32_premul((size.x as i32, size.y as i32))
-            .ok_or(ExportError::SurfaceCreationError)?;
+        let mut surface = Self::rasterize_page(
+            &mut canvas_state,
+            size,
+            proof.and_then(ProofSettings::watermark),
+            false,
+        )?;
+
+        if let Some(sharpening) = sharpening {
+            sharpening.apply(
+                &mut surface,
+                &canvas_state,
+                Rect::from_min_max(Pos2::ZERO, size.to_pos2()),
+            );
+        }
+
+        let image = surface.image_snapshot();
+        let data = match proof {
+            Some(proof) => image.encode(None, EncodedImageFormat::JPEG, proof.quality),
+            None => image.encode_to_data(EncodedImageFormat::JPEG),
+        }
+        .ok_or(ExportError::ImageEncodingError)?;
+
+        let image_path = directory.join(image_name);
+
+        let mut output_file =
+            File::create(&image_path).map_err(|e| ExportError::FileError(e.to_string()))?;
+        output_file
+            .write_all(&data)
+            .map_err(|e| ExportError::FileError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    // Layers are drawn one at a time over nothing so each becomes a layer of its own, with the
+    // page's background as the bottom layer. Hidden layers are kept but start out hidden. The
+    // photos are loaded once for all the layers, and the flattened image is made by compositing
+    // the visible layers rather than drawing the page again.
+    fn page_to_psd(page: &CanvasState) -> Result<Vec<u8>, ExportError> {
+        let size = page.page.size_pixels().round();
+        let (width, height) = (size.x as u32, size.y as u32);
+        let mut rasterizer = PageRasterizer::new(page, size)?;
+
+        let mut background = page.clone();
+        background.layers.clear();
+        let mut layers = vec![PsdLayer::from_page_pixels(
+            "Background",
+            &Self::page_pixels(&mut rasterizer, background, false)?,
+            width,
+            height,
+        )];
+
+        for (layer_id, layer) in &page.layers {
+            let mut single = page.clone();
+            single.layers.retain(|id, _| id == layer_id);
+            single.background = Fill::Solid(Color32::TRANSPARENT);
+
+            let mut psd_layer = PsdLayer::from_page_pixels(
+                &layer.name,
+                &Self::page_pixels(&mut rasterizer, single, true)?,
+                width,
+                height,
+            );
+            psd_layer.visible = layer.visible;
+            layers.push(psd_layer);
+        }
+
+        let mut composite = vec![0; width as usize * height as usize * 4];
+        for layer in layers.iter().filter(|layer| layer.visible) {
+            layer.composite_onto(&mut composite, width);
+        }
 
+        psd_export::write_psd(width, height, page.page.ppi(), &layers, &composite)
+    }
+
+    // The page drawn by `rasterizer` as unpremultiplied RGBA
+    fn page_pixels(
+        rasterizer: &mut PageRasterizer,
+        mut canvas_state: CanvasState,
+        transparent: bool,
+    ) -> Result<Vec<u8>, ExportError> {
+        let size = rasterizer.size;
+        let mut surface = rasterizer.rasterize(&mut canvas_state, None, transparent)?;
+
+        let info = ImageInfo::new(
+            (size.x as i32, size.y as i32),
+            ColorType::RGBA8888,
+            AlphaType::Unpremul,
+            None,
+        );
+        let row_bytes = size.x as usize * 4;
+        let mut pixels = vec![0; row_bytes * size.y as usize];
+        if !surface.read_pixels(&info, &mut pixels, row_bytes, (0, 0)) {
+            return Err(ExportError::ImageEncodingError);
+        }
+
+        Ok(pixels)
+    }
+
+    // Draws the page into a new surface `size` pixels large. A transparent page is drawn over
+    // nothing instead of the panel's background.
+    fn rasterize_page(
+        canvas_state: &mut CanvasState,
+        size: Vec2,
+        watermark: Option<&str>,
+        transparent: bool,
+    ) -> Result<Surface, ExportError> {
+        PageRasterizer::new(canvas_state, size)?.rasterize(canvas_state, watermark, transparent)
+    }
+
+    fn export_pdf(
+        pages: &Vec<CanvasState>,
+        image_names: &[String],
+        directory: &PathBuf,
+        file_name: &str,
+        proof: Option<&ProofSettings>,
+    ) -> Result<(), ExportError> {
+        let directory = PathBuf::from(directory);
+
+        let pdf = PdfDocument::empty(file_name);
+
+        for page_number in 0..pages.len() {
+            let image_path = directory.join(&image_names[page_number]);
+
+            let page_size = pages[page_number].page.size_mm();
+            let (mm_width, mm_height) = (Mm(page_size.x), Mm(page_size.y));
+
+            let (page_idx, layer_idx) = pdf.add_page(mm_width, mm_height, "Layer 1");
+
+            let current_layer = pdf.get_page(page_idx).get_layer(layer_idx);
+
+            use printpdf::image as printpdf_image;
+            use printpdf::image_crate::codecs::jpeg::JpegDecoder;
+
+            let image_file =
+                File::open(image_path).map_err(|e| ExportError::FileError(e.to_string()))?;
+            let image = printpdf_image::Image::try_from(JpegDecoder::new(image_file).unwrap())
+                .map_err(|e| {
+                    ExportError::PdfRenderingError(format!("Error loading image: {:?}", e))
+                })?;
+
+            // Scaled down proof pages keep their size on paper
+            let scale = proof.map_or(1.0, |proof| proof.scale(&pages[page_number]));
+            image.add_to_layer(
+                current_layer.clone(),
+                ImageTransform {
+                    dpi: Some(pages[page_number].page.ppi() as f32 * scale),
+                    ..Default::default()
+                },
+            );
+        }
+
+        let mut pdf_path = directory.join(file_name);
+        pdf_path.set_extension("pdf");
+
+        let output_pdf =
+            File::create(pdf_path).map_err(|e| ExportError::FileError(e.to_string()))?;
+
+        pdf.save(&mut BufWriter::new(output_pdf))
+            .map_err(|e| ExportError::PdfSavingError(e.to_string().to_string()))?;
+
+        Ok(())
+    }
+}
+
+// Draws a page into surfaces with an egui context of its own. The photos, assets and fonts of the
+// page are loaded once up front, so formats that draw a page a layer at a time only load them once.
+struct PageRasterizer {
+    backend: EguiSkia,
+    size: Vec2,
+    frames_before_screenshot: usize,
+}
+
+impl PageRasterizer {
+    // Loads everything `canvas_state` shows for drawing it `size` pixels large
+    fn new(canvas_state: &CanvasState, size: Vec2) -> Result<Self, ExportError> {
         let RasterizeOptions {
             pixels_per_point,
             frames_before_screenshot,
         } = default::Default::default();
-        let mut backend = EguiSkia::new(pixels_per_point);
+        let backend = EguiSkia::new(pixels_per_point);
         egui_extras::install_image_loaders(&backend.egui_ctx);
 
         backend.egui_ctx.input_mut(|input| {
             input.max_texture_side = usize::MAX; // TODO: What are the consequences of doing this?
         });
 
-        let photo_manager = Singleton::new(PhotoManager::new());
-        let mut history_manager = CanvasHistoryManager::preview();
+        // Textures can't be sampled with a Lanczos filter, so with Lanczos each photo is scaled
+        // down with it to the size it's drawn at instead
+        let resampled = if ResizeQuality::current() == ResizeQuality::Lanczos {
+            let sizes = drawn_photo_sizes(canvas_state, size);
+            for (photo, max_size) in sizes.values() {
+                PhotoManager::load_resampled_texture(
+                    &photo.photo,
+                    &photo.adjustments,
+                    *max_size,
+                    &backend.egui_ctx,
+                )
+                .map_err(|error| ExportError::TextureLoadingError(error.to_string()))?;
+            }
+            sizes
+        } else {
+            HashMap::new()
+        };
 
-        let mut canvas = Canvas::new(
-            &mut canvas_state,
-            Rect::from_min_max(Pos2::ZERO, size.to_pos2()),
-            &mut history_manager,
-        );
+        let photo_manager = Singleton::new(PhotoManager::new());
 
         photo_manager.with_lock_mut(|photo_manager| {
-            for layer in canvas.state.layers.values() {
+            for layer in canvas_state.layers.values() {
                 match &layer.content {
+                    LayerContent::Photo(photo)
+                    | LayerContent::TemplatePhoto {
+                        photo: Some(photo), ..
+                    } if resampled.contains_key(&photo.adjustments.uri(&photo.photo)) => {}
+                    LayerContent::Photo(photo)
+                    | LayerContent::TemplatePhoto {
+                        photo: Some(photo), ..
+                    } if !photo.adjustments.is_identity() => {
+                        if let Err(error) = PhotoManager::load_adjusted_texture(
+                            &photo.photo,
+                            &photo.adjustments,
+                            &backend.egui_ctx,
+                        ) {
+                            error!("Error loading adjusted texture: {:?}", error);
+                            return Err(ExportError::TextureLoadingError(error.to_string()));
+                        }
+                    }
                     LayerContent::Photo(photo)
                     | LayerContent::TemplatePhoto {
                         photo: Some(photo), ..
@@ -206,6 +991,24 @@ impl Exporter {
                     LayerContent::TemplatePhoto { photo: None, .. } => {}
                     LayerContent::Text(_) => {}
                     LayerContent::TemplateText { .. } => {}
+                    LayerContent::Path(_) => {}
+                    // The file loader reads on a background thread, decoding and rasterizing happen
+                    // synchronously once the bytes are available so only the bytes need to be waited on
+                    LayerContent::Decoration(decoration) => loop {
+                        match backend.egui_ctx.try_load_bytes(&decoration.uri()) {
+                            Ok(BytesPoll::Ready { .. }) => {
+                                info!("Loaded {}", decoration.uri());
+                                break;
+                            }
+                            Ok(BytesPoll::Pending { .. }) => {
+                                std::thread::sleep(Duration::from_millis(10));
+                            }
+                            Err(error) => {
+                                error!("Error loading asset: {:?}", error);
+                                return Err(ExportError::TextureLoadingError(error.to_string()));
+                            }
+                        }
+                    },
                 }
             }
             Ok(())
@@ -213,12 +1016,44 @@ impl Exporter {
 
         let font_manager: Singleton<FontManager> = Dependency::get();
 
-        if let Some(font_definitions) =
-            font_manager.with_lock(|font_manager| font_manager.font_definitions.clone())
-        {
+        if let Some(font_definitions) = font_manager.with_lock_mut(|font_manager| {
+            font_manager.load_families(&page_font_families(std::slice::from_ref(canvas_state)));
+            font_manager.font_definitions.clone()
+        }) {
             backend.egui_ctx.set_fonts((*font_definitions).clone());
         };
 
+        Ok(Self {
+            backend,
+            size,
+            frames_before_screenshot,
+        })
+    }
+
+    // Draws `canvas_state`, which can only show what was loaded for the page the rasterizer was
+    // made for, into a new surface
+    fn rasterize(
+        &mut self,
+        canvas_state: &mut CanvasState,
+        watermark: Option<&str>,
+        transparent: bool,
+    ) -> Result<Surface, ExportError> {
+        let size = self.size;
+        canvas_state.zoom = 1.0;
+
+        let mut surface = raster_n32_premul((size.x as i32, size.y as i32))
+            .ok_or(ExportError::SurfaceCreationError)?;
+        surface.canvas().clear(skia_safe::Color::TRANSPARENT);
+
+        let mut history_manager = CanvasHistoryManager::preview();
+
+        let mut canvas = Canvas::new(
+            canvas_state,
+            Rect::from_min_max(Pos2::ZERO, size.to_pos2()),
+            &mut history_manager,
+        )
+        .with_full_resolution();
+
         let image_info = surface.canvas().image_info();
 
         let input = egui::RawInput {
@@ -233,79 +1068,99 @@ impl Exporter {
         };
 
         let mut _output_surface: Option<_> = None;
-        for _ in 0..frames_before_screenshot {
-            _output_surface = Some(backend.run(input.clone(), |ctx: &egui::Context| {
-                egui::CentralPanel::default().show(ctx, |ui| {
-                    canvas.show_preview(ui, Rect::from_min_max(Pos2::ZERO, size.to_pos2()));
+        for _ in 0..self.frames_before_screenshot {
+            _output_surface = Some(self.backend.run(input.clone(), |ctx: &egui::Context| {
+                let mut panel = egui::CentralPanel::default();
+                if transparent {
+                    panel = panel.frame(egui::Frame::none());
+                }
+                panel.show(ctx, |ui| {
+                    let page_rect = Rect::from_min_max(Pos2::ZERO, size.to_pos2());
+                    canvas.show_preview(ui, page_rect);
+
+                    if let Some(watermark) = watermark {
+                        paint_watermark(ui.painter(), page_rect, watermark);
+                    }
                 });
             }));
         }
 
-        backend.paint(surface.canvas());
-
-        let data = surface
-            .image_snapshot()
-            .encode_to_data(EncodedImageFormat::JPEG)
-            .ok_or(ExportError::ImageEncodingError)?;
+        self.backend.paint(surface.canvas());
 
-        let image_path = directory.join(format!("page_{}.jpg", page_number));
-
-        let mut output_file =
-            File::create(&image_path).map_err(|e| ExportError::FileError(e.to_string()))?;
-        output_file
-            .write_all(&data)
-            .map_err(|e| ExportError::FileError(e.to_string()))?;
-
-        Ok(())
+        Ok(surface)
     }
+}
 
-    fn export_pdf(
-        pages: &Vec<CanvasState>,
-        directory: &PathBuf,
-        file_name: &str,
-    ) -> Result<(), ExportError> {
-        let directory = PathBuf::from(directory);
-
-        let pdf = PdfDocument::empty(file_name);
-
-        for page_number in 0..pages.len() {
-            let image_path = directory.join(format!("page_{}.jpg", page_number));
-
-            let page_size = pages[page_number].page.size_mm();
-            let (mm_width, mm_height) = (Mm(page_size.x), Mm(page_size.y));
-
-            let (page_idx, layer_idx) = pdf.add_page(mm_width, mm_height, "Layer 1");
-
-            let current_layer = pdf.get_page(page_idx).get_layer(layer_idx);
+// The longest side each photo on `page` needs to be drawn on a page `size` pixels large without
+// being scaled up, keyed by the uri of the photo with its adjustments. Cropped photos need the
+// whole photo, so the side is worked out from the smaller side of the crop to stay on the large side.
+fn drawn_photo_sizes(page: &CanvasState, size: Vec2) -> HashMap<String, (&CanvasPhoto, usize)> {
+    let scale = size.x / page.page.size_pixels().x;
+    let page_rect = Rect::from_min_size(Pos2::ZERO, size);
+    let mut sizes: HashMap<String, (&CanvasPhoto, usize)> = HashMap::new();
 
-            use printpdf::image as printpdf_image;
-            use printpdf::image_crate::codecs::jpeg::JpegDecoder;
+    for layer in page.layers.values() {
+        let (photo, longest_side) = match &layer.content {
+            LayerContent::Photo(photo) => {
+                let rect = layer.transform_state.rect;
+                let crop = photo
+                    .crop
+                    .width()
+                    .min(photo.crop.height())
+                    .max(f32::EPSILON);
+                (photo, rect.width().max(rect.height()) * scale / crop)
+            }
+            LayerContent::TemplatePhoto {
+                region,
+                photo: Some(photo),
+                scale_mode,
+                alignment,
+            } => {
+                let region_rect = render::region_rect(page_rect, region);
+                let rect = render::template_photo_rect(photo, region_rect, *scale_mode, *alignment);
+                (photo, rect.width().max(rect.height()))
+            }
+            _ => continue,
+        };
 
-            let image_file =
-                File::open(image_path).map_err(|e| ExportError::FileError(e.to_string()))?;
-            let image = printpdf_image::Image::try_from(JpegDecoder::new(image_file).unwrap())
-                .map_err(|e| {
-                    ExportError::PdfRenderingError(format!("Error loading image: {:?}", e))
-                })?;
+        let longest_side = longest_side.ceil().max(1.0) as usize;
+        sizes
+            .entry(photo.adjustments.uri(&photo.photo))
+            .and_modify(|(_, side)| *side = (*side).max(longest_side))
+            .or_insert((photo, longest_side));
+    }
 
-            image.add_to_layer(
-                current_layer.clone(),
-                ImageTransform {
-                    dpi: Some(pages[page_number].page.ppi() as f32),
-                    ..Default::default()
-                },
-            );
-        }
+    sizes
+}
 
-        let mut pdf_path = directory.join(file_name);
-        pdf_path.set_extension("pdf");
+// The font families of the text on `pages`, so they can be loaded before the pages are drawn
+fn page_font_families(pages: &[CanvasState]) -> Vec<String> {
+    pages
+        .iter()
+        .flat_map(|page| page.layers.values())
+        .filter_map(|layer| match &layer.content {
+            LayerContent::Text(text) | LayerContent::TemplateText { text, .. } => {
+                match &text.font_id.family {
+                    FontFamily::Name(name) => Some(name.to_string()),
+                    _ => None,
+                }
+            }
+            _ => None,
+        })
+        .collect()
+}
 
-        let output_pdf =
-            File::create(pdf_path).map_err(|e| ExportError::FileError(e.to_string()))?;
+// Draws `text` large and faint across the page's diagonal so the proof can't be printed as is
+fn paint_watermark(painter: &Painter, page_rect: Rect, text: &str) {
+    let color = Color32::from_rgba_unmultiplied(128, 128, 128, 110);
+    let size = page_rect.size();
 
-        pdf.save(&mut BufWriter::new(output_pdf))
-            .map_err(|e| ExportError::PdfSavingError(e.to_string().to_string()))?;
+    // Sized so the text spans most of the diagonal whatever its length
+    let measured = painter.layout_no_wrap(text.to_string(), FontId::proportional(100.0), color);
+    let font_size = 100.0 * size.length() * 0.7 / measured.size().x.max(1.0);
+    let galley = painter.layout_no_wrap(text.to_string(), FontId::proportional(font_size), color);
 
-        Ok(())
-    }
+    let angle = -size.y.atan2(size.x);
+    let pos = page_rect.center() - Rot2::from_angle(angle) * (galley.size() / 2.0);
+    painter.add(TextShape::new(pos, galley, color).with_angle(angle));
 }
@@ -1,8 +1,49 @@
-use std::{collections::HashSet, ffi::OsStr, path::PathBuf, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    ffi::OsStr,
+    path::PathBuf,
+    sync::Arc,
+    time::UNIX_EPOCH,
+};
 
 use egui::{text::Fonts, FontDefinitions, FontFamily, FontId};
-use font_kit::source::SystemSource;
+use font_kit::{handle::Handle, source::SystemSource};
 use indexmap::IndexMap;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use tokio::task::spawn_blocking;
+
+use crate::{
+    dependencies::{Dependency, SingletonFor},
+    dirs::Dirs,
+};
+
+// Fonts that cover scripts and symbols the user's chosen font usually won't. Glyphs missing
+// from a font are looked up in these, in order, so they are added to every family.
+const FALLBACK_FAMILY_HINTS: [&str; 11] = [
+    "Noto Sans Arabic",
+    "Noto Naskh Arabic",
+    "Noto Sans Hebrew",
+    "Noto Sans Devanagari",
+    "Noto Sans CJK",
+    "Noto Color Emoji",
+    "Noto Emoji",
+    "Segoe UI Emoji",
+    "Segoe UI Symbol",
+    "Nirmala UI",
+    "Arial Unicode",
+];
+
+// Bundled with egui so emoji always have something to fall back on
+const BUILTIN_FALLBACK_FONTS: [&str; 2] = ["NotoEmoji-Regular", "emoji-icon-font"];
+
+// Families that can't show these are left out, they're usually symbol or script only fonts
+const LATIN_SAMPLE: &str = "abcdefghijklmnopqrstuvwxyz1234567890";
+
+// The weight picked for a family when it has more than one style installed
+const REGULAR_WEIGHT: u16 = 400;
+
+const FONT_INDEX_FILE: &str = "font_index.json";
 
 #[derive(Debug, PartialEq)]
 pub enum LoadingState {
@@ -11,10 +52,23 @@ pub enum LoadingState {
     Loaded,
 }
 
+/// Keeps track of the installed fonts. Only the fonts the UI needs are loaded at start up, the
+/// installed families are indexed in the background and each is loaded the first time text is
+/// laid out in it.
 pub struct FontManager {
+    // Every installed font by family, whether it's loaded or not
     pub fonts: IndexMap<String, Vec<FontInfo>>,
     pub loading_state: LoadingState,
+    // The families loaded so far along with the UI and fallback fonts
     pub font_definitions: Option<Arc<FontDefinitions>>,
+    // Names of the fallback fonts in the definitions, added to every family loaded
+    fallback_fonts: Vec<String>,
+    // Families asked for since the last frame
+    requested: HashSet<String>,
+    // Families whose files couldn't be read or that can't show latin text
+    unusable: HashSet<String>,
+    // Set when the definitions have changed since they were last handed to egui
+    definitions_changed: bool,
 }
 
 impl FontManager {
@@ -23,111 +77,345 @@ impl FontManager {
             fonts: IndexMap::new(),
             loading_state: LoadingState::NotLoaded,
             font_definitions: None,
+            fallback_fonts: Vec::new(),
+            requested: HashSet::new(),
+            unusable: HashSet::new(),
+            definitions_changed: false,
+        }
+    }
+
+    // Picks up fonts that were unpacked after start up
+    pub fn reload_fonts(&mut self, ctx: &egui::Context) {
+        self.loading_state = LoadingState::NotLoaded;
+        self.load_fonts(ctx);
+    }
+
+    /// Whether text can be laid out with `family`. Installed families that haven't been loaded
+    /// yet count, text is shown in the default font until they are.
+    pub fn is_available(&self, family: &FontFamily) -> bool {
+        match family {
+            FontFamily::Proportional | FontFamily::Monospace => true,
+            FontFamily::Name(name) => {
+                self.is_loaded(family)
+                    || (self.fonts.contains_key(name.as_ref())
+                        && !self.unusable.contains(name.as_ref()))
+            }
+        }
+    }
+
+    /// Whether `family` is in the font definitions, egui panics on families that aren't
+    pub fn is_loaded(&self, family: &FontFamily) -> bool {
+        self.font_definitions
+            .as_ref()
+            .is_some_and(|definitions| definitions.families.contains_key(family))
+    }
+
+    /// The names of the installed families text can be set in, alphabetically
+    pub fn family_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .fonts
+            .keys()
+            .filter(|family| !self.unusable.contains(*family))
+            .cloned()
+            .collect();
+        names.sort_by_key(|name| name.to_lowercase());
+        names
+    }
+
+    /// The files of every style of `families` that are installed
+    pub fn font_files(&self, families: &HashSet<String>) -> Vec<PathBuf> {
+        families
+            .iter()
+            .filter_map(|family| self.fonts.get(family))
+            .flatten()
+            .map(|font| font.file_path.clone())
+            .collect()
+    }
+
+    /// Asks for `family` to be loaded at the start of the next frame
+    pub fn request(&mut self, family: &str) {
+        if !self.unusable.contains(family) {
+            self.requested.insert(family.to_string());
+        }
+    }
+
+    /// Loads the families asked for since the last frame and hands any fonts loaded since then
+    /// to egui. They can be used from the next frame.
+    pub fn load_requested(&mut self, ctx: &egui::Context) {
+        if self.loading_state != LoadingState::Loaded {
+            return;
+        }
+
+        if !self.requested.is_empty() {
+            let families: Vec<String> = self.requested.drain().collect();
+            self.load_families(&families);
+        }
+
+        if self.definitions_changed {
+            self.definitions_changed = false;
+            if let Some(definitions) = &self.font_definitions {
+                ctx.set_fonts((**definitions).clone());
+            }
+            ctx.request_repaint();
+        }
+    }
+
+    /// Loads `families` into the font definitions, for exports that set up their own context
+    /// from them. egui gets them at the start of the next frame.
+    pub fn load_families(&mut self, families: &[String]) {
+        let Some(definitions) = &self.font_definitions else {
+            return;
+        };
+
+        let mut definitions = (**definitions).clone();
+        let mut loaded = false;
+
+        for family in families {
+            let family_id = FontFamily::Name(Arc::from(family.as_str()));
+            if definitions.families.contains_key(&family_id) || self.unusable.contains(family) {
+                continue;
+            }
+
+            // Fonts that aren't installed are left to the missing fonts check
+            let Some(font) = self.fonts.get(family).and_then(|styles| {
+                styles
+                    .iter()
+                    .min_by_key(|font| font.weight.abs_diff(REGULAR_WEIGHT))
+            }) else {
+                self.unusable.insert(family.clone());
+                continue;
+            };
+
+            let font_data = match std::fs::read(&font.file_path) {
+                Ok(font_data) => Arc::new(egui::FontData::from_owned(font_data)),
+                Err(err) => {
+                    log::error!("Failed to read font file: {:?}", err);
+                    self.unusable.insert(family.clone());
+                    continue;
+                }
+            };
+
+            if !Self::has_latin_glyphs(family, &font_data) {
+                info!("Skipping font {} without latin glyphs", family);
+                self.unusable.insert(family.clone());
+                continue;
+            }
+
+            definitions.font_data.insert(family.clone(), font_data);
+
+            let mut font_names = vec![family.clone()];
+            font_names.extend(
+                self.fallback_fonts
+                    .iter()
+                    .map(String::as_str)
+                    .chain(BUILTIN_FALLBACK_FONTS)
+                    .filter(|fallback| *fallback != family.as_str())
+                    .map(str::to_string),
+            );
+            definitions.families.insert(family_id, font_names);
+            loaded = true;
+        }
+
+        if loaded {
+            self.font_definitions = Some(Arc::new(definitions));
+            self.definitions_changed = true;
         }
     }
 
+    fn has_latin_glyphs(family: &str, font_data: &Arc<egui::FontData>) -> bool {
+        let mut definitions = FontDefinitions::default();
+        definitions
+            .font_data
+            .insert(family.to_string(), font_data.clone());
+        let family_id = FontFamily::Name(Arc::from(family));
+        definitions
+            .families
+            .insert(family_id.clone(), vec![family.to_string()]);
+
+        Fonts::new(1.0, 1024, definitions).has_glyphs(&FontId::new(20.0, family_id), LATIN_SAMPLE)
+    }
+
+    fn imported_font_handles() -> Vec<Handle> {
+        let Ok(entries) = std::fs::read_dir(Dirs::Fonts.path()) else {
+            return Vec::new();
+        };
+
+        entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| Handle::from_path(entry.path(), 0))
+            .collect()
+    }
+
+    /// Indexes the installed fonts and loads the UI and fallback fonts on a background thread.
+    /// Families that were loaded before a reload are loaded again when they're next used.
     pub fn load_fonts(&mut self, ctx: &egui::Context) {
-        if self.loading_state == LoadingState::NotLoaded {
-            self.loading_state = LoadingState::Loading;
-
-            let source: SystemSource = SystemSource::new();
-            let fonts = source.all_fonts().unwrap();
-
-            for handle in fonts {
-                match &handle {
-                    font_kit::handle::Handle::Path {
-                        path,
-                        font_index: _,
-                    } => {
-                        if path.extension() != Some(OsStr::new("ttf"))
-                            && path.extension() != Some(OsStr::new("otf"))
-                        {
-                            continue;
-                        }
-                        match handle.load() {
-                            Ok(loaded_font) => {
-                                let family = loaded_font.family_name().to_string();
-                                let weight = loaded_font.properties().weight.0 as u16;
-                                let full_name = loaded_font.full_name().to_string();
-                                let font_info = FontInfo {
-                                    family: family.clone(),
-                                    weight,
-                                    weighted_name: format!("{}-{}", family, weight),
-                                    full_name,
-                                    file_path: path.clone(),
-                                };
-                                self.fonts.entry(family).or_default().push(font_info);
-                            }
-                            Err(err) => {
-                                log::error!("Failed to load font: {:?}", err);
-                            }
-                        }
+        if self.loading_state != LoadingState::NotLoaded {
+            return;
+        }
+        self.loading_state = LoadingState::Loading;
+
+        let ctx = ctx.clone();
+        spawn_blocking(move || {
+            let fonts = Self::index_fonts();
+            let (font_definitions, fallback_fonts) = Self::base_definitions(&fonts);
+
+            // Handed to egui on the UI thread, families asked for in the meantime are loaded then
+            Dependency::<FontManager>::get().with_lock_mut(|font_manager| {
+                font_manager.fonts = fonts;
+                font_manager.fallback_fonts = fallback_fonts;
+                font_manager.unusable.clear();
+                font_manager.font_definitions = Some(Arc::new(font_definitions));
+                font_manager.definitions_changed = true;
+                font_manager.loading_state = LoadingState::Loaded;
+            });
+
+            ctx.request_repaint();
+        });
+    }
+
+    // egui's own fonts with the fallback fonts that are installed added to them
+    fn base_definitions(fonts: &IndexMap<String, Vec<FontInfo>>) -> (FontDefinitions, Vec<String>) {
+        let mut font_definitions = FontDefinitions::default();
+        let mut fallback_fonts = Vec::new();
+
+        // Script fonts usually don't have latin glyphs so they aren't checked for them
+        for hint in FALLBACK_FAMILY_HINTS {
+            for (family, styles) in fonts.iter().filter(|(family, _)| family.starts_with(hint)) {
+                let Some(font) = styles
+                    .iter()
+                    .min_by_key(|font| font.weight.abs_diff(REGULAR_WEIGHT))
+                else {
+                    continue;
+                };
+
+                match std::fs::read(&font.file_path) {
+                    Ok(font_data) => {
+                        font_definitions.font_data.insert(
+                            family.clone(),
+                            Arc::new(egui::FontData::from_owned(font_data)),
+                        );
+                        fallback_fonts.push(family.clone());
+                    }
+                    Err(err) => {
+                        log::error!("Failed to read font file: {:?}", err);
                     }
-                    font_kit::handle::Handle::Memory {
-                        bytes: _,
-                        font_index: _,
-                    } => {}
                 }
             }
+        }
 
-            let mut font_definitions = egui::FontDefinitions::default();
-
-            for (_family, fonts) in &self.fonts {
-                for font in fonts {
-                    match std::fs::read(&font.file_path) {
-                        Ok(font_data) => {
-                            font_definitions.font_data.insert(
-                                font.family.clone(),
-                                Arc::new(egui::FontData::from_owned(font_data)),
-                            );
-
-                            font_definitions.families.insert(
-                                FontFamily::Name(Arc::from(font.family.clone())),
-                                vec![font.family.clone()],
-                            );
-                        }
-                        Err(err) => {
-                            log::error!("Failed to read font file: {:?}", err);
-                        }
-                    }
+        for font_names in font_definitions.families.values_mut() {
+            let fallbacks = fallback_fonts
+                .iter()
+                .map(String::as_str)
+                .chain(BUILTIN_FALLBACK_FONTS);
+            for fallback in fallbacks {
+                if !font_names.iter().any(|name| name == fallback) {
+                    font_names.push(fallback.to_string());
                 }
             }
+        }
 
-            let fonts = Fonts::new(1.0, 1024, font_definitions.clone());
+        (font_definitions, fallback_fonts)
+    }
 
-            let valid_fonts = fonts
-                .families()
-                .iter()
-                .map(|family| FontId::new(20.0, family.clone()))
-                .filter(|font_id| fonts.has_glyphs(font_id, "abcdefghijklmnopqrstuvwxyz1234567890"))
-                .map(|font_id| font_id.family.to_string())
-                .collect::<HashSet<String>>();
+    // Lists the installed fonts by family. Reading a font's names means parsing it, so what was
+    // read last time is kept in the cache and only new or changed files are parsed.
+    fn index_fonts() -> IndexMap<String, Vec<FontInfo>> {
+        let index_path = Dirs::Cache.path().join(FONT_INDEX_FILE);
+        let cached: HashMap<PathBuf, IndexedFont> = std::fs::read(&index_path)
+            .ok()
+            .and_then(|contents| serde_json::from_slice::<Vec<IndexedFont>>(&contents).ok())
+            .unwrap_or_default()
+            .into_iter()
+            .map(|indexed| (indexed.font.file_path.clone(), indexed))
+            .collect();
 
-            let mut valid_font_definitions = FontDefinitions::default();
+        let source: SystemSource = SystemSource::new();
+        let mut handles = source.all_fonts().unwrap_or_else(|err| {
+            warn!("Failed to list the system fonts: {:?}", err);
+            Vec::new()
+        });
+        handles.extend(Self::imported_font_handles());
 
-            font_definitions
-                .font_data
-                .iter()
-                .filter(|(family, _)| valid_fonts.contains(&family.to_string()))
-                .for_each(|(family, font_data)| {
-                    valid_font_definitions
-                        .font_data
-                        .insert(family.clone(), font_data.clone());
-
-                    valid_font_definitions.families.insert(
-                        FontFamily::Name(Arc::from(family.clone())),
-                        vec![family.clone()],
-                    );
-                });
-
-            ctx.set_fonts(valid_font_definitions.clone());
-
-            self.font_definitions = Some(Arc::new(valid_font_definitions));
-            self.loading_state = LoadingState::Loaded;
+        let mut index = Vec::new();
+        let mut parsed = 0;
+
+        for handle in handles {
+            let Handle::Path { path, .. } = &handle else {
+                continue;
+            };
+
+            if path.extension() != Some(OsStr::new("ttf"))
+                && path.extension() != Some(OsStr::new("otf"))
+            {
+                continue;
+            }
+
+            let modified = std::fs::metadata(path)
+                .and_then(|metadata| metadata.modified())
+                .ok()
+                .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+                .map_or(0, |modified| modified.as_secs());
+
+            if let Some(indexed) = cached
+                .get(path)
+                .filter(|cached| cached.modified == modified)
+            {
+                index.push(indexed.clone());
+                continue;
+            }
+
+            match handle.load() {
+                Ok(loaded_font) => {
+                    let family = loaded_font.family_name().to_string();
+                    let weight = loaded_font.properties().weight.0 as u16;
+                    parsed += 1;
+                    index.push(IndexedFont {
+                        modified,
+                        font: FontInfo {
+                            family: family.clone(),
+                            weight,
+                            weighted_name: format!("{}-{}", family, weight),
+                            full_name: loaded_font.full_name().to_string(),
+                            file_path: path.clone(),
+                        },
+                    });
+                }
+                Err(err) => {
+                    log::error!("Failed to load font: {:?}", err);
+                }
+            }
         }
+
+        info!(
+            "Indexed {} fonts, {} read from the cache",
+            index.len(),
+            index.len() - parsed
+        );
+
+        if parsed > 0 || index.len() != cached.len() {
+            match serde_json::to_vec(&index) {
+                Ok(contents) => {
+                    if let Err(err) = std::fs::write(&index_path, contents) {
+                        warn!("Failed to save the font index: {:?}", err);
+                    }
+                }
+                Err(err) => warn!("Failed to save the font index: {:?}", err),
+            }
+        }
+
+        let mut fonts: IndexMap<String, Vec<FontInfo>> = IndexMap::new();
+        for indexed in index {
+            fonts
+                .entry(indexed.font.family.clone())
+                .or_default()
+                .push(indexed.font);
+        }
+        fonts
     }
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FontInfo {
     pub family: String,
     pub weight: u16,
@@ -135,3 +423,10 @@ pub struct FontInfo {
     pub full_name: String,
     pub file_path: PathBuf,
 }
+
+// A font in the cached index, `modified` is the file's modification time when it was read
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct IndexedFont {
+    modified: u64,
+    font: FontInfo,
+}
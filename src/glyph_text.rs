@@ -0,0 +1,455 @@
+use std::sync::Arc;
+
+use ab_glyph::{point, Font, FontRef, GlyphId, OutlineCurve, PxScale};
+use egui::{Color32, ColorImage, FontData, FontDefinitions, FontFamily, Vec2};
+
+use crate::{
+    text_shaping,
+    typography::Typography,
+    widget::canvas_info::layers::{TextHorizontalAlignment, TextSpacing},
+};
+
+// egui draws every character with a glyph of its own, which isn't enough for scripts whose
+// characters combine, like Devanagari conjuncts and vowel signs. Text in those scripts is shaped
+// with rustybuzz and drawn by glyph id instead: rasterized for the canvas and exported pages and
+// written as outlines in SVGs.
+
+// Room around the text for glyphs that reach past their advance, in ems
+const RASTER_PADDING: f32 = 0.5;
+
+#[derive(Debug, Clone, Copy)]
+struct PlacedGlyph {
+    // Index into the fonts of the text
+    font: usize,
+    id: GlyphId,
+    // The start of the glyph's baseline from the top left of the text
+    pos: Vec2,
+}
+
+// A font of the family's fallback chain, parsed for shaping and for its outlines
+struct ShapingFont<'a> {
+    face: rustybuzz::Face<'a>,
+    outlines: FontRef<'a>,
+}
+
+impl<'a> ShapingFont<'a> {
+    fn parse(data: &'a FontData) -> Option<Self> {
+        Some(Self {
+            face: rustybuzz::Face::from_slice(&data.font, data.index)?,
+            outlines: FontRef::try_from_slice_and_index(&data.font, data.index).ok()?,
+        })
+    }
+
+    fn covers(&self, c: char) -> bool {
+        self.outlines.glyph_id(c).0 != 0
+    }
+
+    // Font units to pixels for text `font_size` pixels large
+    fn scale(&self, font_size: f32) -> f32 {
+        font_size / self.outlines.units_per_em().unwrap_or(1000.0)
+    }
+
+    // ab_glyph scales by the font's height rather than its em
+    fn px_scale(&self, font_size: f32) -> PxScale {
+        PxScale::from(self.outlines.height_unscaled() * self.scale(font_size))
+    }
+}
+
+/// Text shaped into lines of glyphs with the fonts of its family's fallback chain, `font_size`
+/// pixels large. Lines are broken like the text layer's own lines and aligned within the width
+/// of the longest.
+pub struct ShapedText {
+    fonts: Vec<Arc<FontData>>,
+    glyphs: Vec<PlacedGlyph>,
+    font_size: f32,
+    pub size: Vec2,
+}
+
+impl ShapedText {
+    /// None if `text` doesn't need shaping or none of the fonts have its glyphs, egui draws it
+    /// as well as it can then
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        definitions: &FontDefinitions,
+        family: &FontFamily,
+        text: &str,
+        font_size: f32,
+        spacing: &TextSpacing,
+        typography: &Typography,
+        alignment: TextHorizontalAlignment,
+        wrap_width: f32,
+    ) -> Option<Self> {
+        if text_shaping::complex_script(text).is_none() {
+            return None;
+        }
+
+        let fonts = family_fonts(definitions, family);
+        let parsed: Vec<Option<ShapingFont>> =
+            fonts.iter().map(|data| ShapingFont::parse(data)).collect();
+
+        if missing_glyphs(&parsed, text) {
+            return None;
+        }
+
+        // Rows are as tall as the family's first font makes them, the way egui lays them out
+        let metrics = parsed.iter().flatten().next()?;
+        let scale = metrics.scale(font_size);
+        let ascent = metrics.outlines.ascent_unscaled() * scale;
+        let row_height = (metrics.outlines.ascent_unscaled() - metrics.outlines.descent_unscaled()
+            + metrics.outlines.line_gap_unscaled())
+            * scale
+            * spacing.line_height;
+
+        let letter_spacing = spacing.letter_spacing * font_size;
+        let indent = spacing.first_line_indent * font_size;
+        let shape = |line: &str| shape_line(&parsed, line, font_size, letter_spacing);
+
+        // Each row's glyphs and width, the first of each paragraph indented
+        let mut rows: Vec<(Vec<PlacedGlyph>, f32)> = Vec::new();
+        let mut y = 0.0;
+
+        for (index, paragraph) in text.split('\n').enumerate() {
+            if index > 0 {
+                y += spacing.paragraph_spacing * font_size;
+            }
+
+            let mut lines =
+                typography
+                    .break_paragraph(paragraph, wrap_width, indent, &mut |line| shape(line).1);
+            if lines.is_empty() {
+                lines.push(String::new());
+            }
+
+            for (line_index, line) in lines.iter().enumerate() {
+                let (mut glyphs, width) = shape(line);
+                let indent = if line_index == 0 { indent } else { 0.0 };
+                for glyph in &mut glyphs {
+                    glyph.pos += Vec2::new(indent, y + ascent);
+                }
+
+                rows.push((glyphs, width + indent));
+                y += row_height;
+            }
+        }
+
+        let width = rows.iter().map(|(_, width)| *width).fold(0.0, f32::max);
+        let glyphs = rows
+            .into_iter()
+            .flat_map(|(glyphs, row_width)| {
+                let x = match alignment {
+                    TextHorizontalAlignment::Left => 0.0,
+                    TextHorizontalAlignment::Center => (width - row_width) / 2.0,
+                    TextHorizontalAlignment::Right => width - row_width,
+                };
+                glyphs.into_iter().map(move |glyph| PlacedGlyph {
+                    pos: glyph.pos + Vec2::new(x, 0.0),
+                    ..glyph
+                })
+            })
+            .collect();
+
+        Some(Self {
+            fonts,
+            glyphs,
+            font_size,
+            size: Vec2::new(width, y),
+        })
+    }
+
+    /// How far the image from `rasterize` reaches past the text on every side, in the text's
+    /// pixels
+    pub fn padding(&self) -> f32 {
+        (self.font_size * RASTER_PADDING).ceil()
+    }
+
+    /// The glyphs drawn in `color`, `pixels_per_point` image pixels to each of the text's pixels.
+    /// The image is `padding` larger than the text on every side.
+    pub fn rasterize(&self, color: Color32, pixels_per_point: f32) -> ColorImage {
+        let padding = self.padding();
+        let image_size = (self.size + Vec2::splat(padding * 2.0)) * pixels_per_point;
+        let [width, height] = [
+            image_size.x.ceil().max(1.0) as usize,
+            image_size.y.ceil().max(1.0) as usize,
+        ];
+        let mut coverage = vec![0.0f32; width * height];
+
+        let parsed: Vec<Option<ShapingFont>> = self
+            .fonts
+            .iter()
+            .map(|data| ShapingFont::parse(data))
+            .collect();
+
+        for glyph in &self.glyphs {
+            let Some(font) = &parsed[glyph.font] else {
+                continue;
+            };
+
+            let pos = (glyph.pos + Vec2::splat(padding)) * pixels_per_point;
+            let scaled = glyph.id.with_scale_and_position(
+                font.px_scale(self.font_size * pixels_per_point),
+                point(pos.x, pos.y),
+            );
+            let Some(outlined) = font.outlines.outline_glyph(scaled) else {
+                continue;
+            };
+
+            let bounds = outlined.px_bounds();
+            outlined.draw(|x, y, amount| {
+                let x = bounds.min.x as i64 + x as i64;
+                let y = bounds.min.y as i64 + y as i64;
+                if (0..width as i64).contains(&x) && (0..height as i64).contains(&y) {
+                    let pixel = &mut coverage[y as usize * width + x as usize];
+                    *pixel = (*pixel + amount).min(1.0);
+                }
+            });
+        }
+
+        let pixels = coverage
+            .into_iter()
+            .map(|amount| {
+                Color32::from_rgba_unmultiplied(
+                    color.r(),
+                    color.g(),
+                    color.b(),
+                    (color.a() as f32 * amount).round() as u8,
+                )
+            })
+            .collect();
+
+        ColorImage {
+            size: [width, height],
+            pixels,
+        }
+    }
+
+    /// The outlines of the glyphs as SVG path data, with the text's top left at `origin`
+    pub fn svg_path(&self, origin: Vec2) -> String {
+        let parsed: Vec<Option<ShapingFont>> = self
+            .fonts
+            .iter()
+            .map(|data| ShapingFont::parse(data))
+            .collect();
+
+        let mut path = String::new();
+
+        for glyph in &self.glyphs {
+            let Some(font) = &parsed[glyph.font] else {
+                continue;
+            };
+            let Some(outline) = font.outlines.outline(glyph.id) else {
+                continue;
+            };
+
+            // Outlines are in font units with y going up
+            let scale = font.scale(self.font_size);
+            let start = origin + glyph.pos;
+            let to_page = |p: ab_glyph::Point| {
+                format!("{:.2} {:.2}", start.x + p.x * scale, start.y - p.y * scale)
+            };
+
+            let mut last = None;
+            for curve in &outline.curves {
+                let (from, to) = match curve {
+                    OutlineCurve::Line(from, to) => (*from, *to),
+                    OutlineCurve::Quad(from, _, to) => (*from, *to),
+                    OutlineCurve::Cubic(from, _, _, to) => (*from, *to),
+                };
+
+                if last != Some(from) {
+                    if last.is_some() {
+                        path.push_str("Z ");
+                    }
+                    path.push_str(&format!("M{} ", to_page(from)));
+                }
+
+                match curve {
+                    OutlineCurve::Line(_, to) => path.push_str(&format!("L{} ", to_page(*to))),
+                    OutlineCurve::Quad(_, control, to) => {
+                        path.push_str(&format!("Q{} {} ", to_page(*control), to_page(*to)))
+                    }
+                    OutlineCurve::Cubic(_, first, second, to) => path.push_str(&format!(
+                        "C{} {} {} ",
+                        to_page(*first),
+                        to_page(*second),
+                        to_page(*to)
+                    )),
+                }
+
+                last = Some(to);
+            }
+
+            if last.is_some() {
+                path.push_str("Z ");
+            }
+        }
+
+        path
+    }
+}
+
+/// The name of the script in `text` that needs shaping when none of the family's fonts have its
+/// glyphs, so the user can be told to install a font for it
+pub fn missing_script(
+    definitions: &FontDefinitions,
+    family: &FontFamily,
+    text: &str,
+) -> Option<&'static str> {
+    let script = text_shaping::complex_script(text)?;
+
+    // Only the character maps are needed so the fonts aren't parsed for shaping
+    let fonts = family_fonts(definitions, family);
+    let parsed: Vec<FontRef> = fonts
+        .iter()
+        .filter_map(|data| FontRef::try_from_slice_and_index(&data.font, data.index).ok())
+        .collect();
+
+    text.chars()
+        .filter(|c| !c.is_whitespace())
+        .any(|c| !parsed.iter().any(|font| font.glyph_id(c).0 != 0))
+        .then_some(script)
+}
+
+// The fonts of the family followed by its fallbacks. Families that aren't loaded are drawn in the
+// proportional family, like egui draws them.
+fn family_fonts(definitions: &FontDefinitions, family: &FontFamily) -> Vec<Arc<FontData>> {
+    definitions
+        .families
+        .get(family)
+        .or_else(|| definitions.families.get(&FontFamily::Proportional))
+        .into_iter()
+        .flatten()
+        .filter_map(|name| definitions.font_data.get(name).cloned())
+        .collect()
+}
+
+fn missing_glyphs(fonts: &[Option<ShapingFont>], text: &str) -> bool {
+    text.chars()
+        .filter(|c| !c.is_whitespace())
+        .any(|c| !fonts.iter().flatten().any(|font| font.covers(c)))
+}
+
+// Shapes a line, each run of characters in the first font that has them, returning the glyphs on
+// a baseline at y = 0 and the width of the line
+fn shape_line(
+    fonts: &[Option<ShapingFont>],
+    line: &str,
+    font_size: f32,
+    letter_spacing: f32,
+) -> (Vec<PlacedGlyph>, f32) {
+    let mut glyphs = Vec::new();
+    let mut x = 0.0;
+
+    for (font_index, run) in font_runs(fonts, line) {
+        let Some(font) = &fonts[font_index] else {
+            continue;
+        };
+
+        let mut buffer = rustybuzz::UnicodeBuffer::new();
+        buffer.push_str(run);
+        buffer.guess_segment_properties();
+        let shaped = rustybuzz::shape(&font.face, &[], buffer);
+
+        let scale = font.scale(font_size);
+        let infos = shaped.glyph_infos();
+        let positions = shaped.glyph_positions();
+
+        for (index, (info, position)) in infos.iter().zip(positions).enumerate() {
+            glyphs.push(PlacedGlyph {
+                font: font_index,
+                id: GlyphId(info.glyph_id as u16),
+                pos: Vec2::new(
+                    x + position.x_offset as f32 * scale,
+                    -position.y_offset as f32 * scale,
+                ),
+            });
+            x += position.x_advance as f32 * scale;
+
+            // Letter spacing goes between clusters so combined glyphs stay together
+            let cluster_ends = infos
+                .get(index + 1)
+                .map_or(true, |next| next.cluster != info.cluster);
+            if cluster_ends {
+                x += letter_spacing;
+            }
+        }
+    }
+
+    (glyphs, x)
+}
+
+// Splits `line` into runs that are each shaped with one font. Characters stay in the run's font
+// while it has them so marks are shaped with the letter they belong to.
+fn font_runs<'t>(fonts: &[Option<ShapingFont>], line: &'t str) -> Vec<(usize, &'t str)> {
+    let mut runs: Vec<(usize, &'t str)> = Vec::new();
+    let mut current: Option<(usize, usize)> = None;
+
+    for (byte_index, c) in line.char_indices() {
+        let covers = |font: usize| fonts[font].as_ref().is_some_and(|font| font.covers(c));
+
+        match current {
+            Some((font, _)) if covers(font) || c.is_whitespace() => {}
+            _ => {
+                let font = (0..fonts.len()).find(|font| covers(*font)).unwrap_or(0);
+                if let Some((previous, start)) = current {
+                    if previous == font {
+                        continue;
+                    }
+                    runs.push((previous, &line[start..byte_index]));
+                }
+                current = Some((font, byte_index));
+            }
+        }
+    }
+
+    if let Some((font, start)) = current {
+        runs.push((font, &line[start..]));
+    }
+
+    runs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scripts_without_a_font_are_missing() {
+        // egui's own fonts don't have Devanagari
+        let definitions = FontDefinitions::default();
+        assert_eq!(
+            missing_script(&definitions, &FontFamily::Proportional, "नमस्ते"),
+            Some("Devanagari")
+        );
+        assert!(ShapedText::new(
+            &definitions,
+            &FontFamily::Proportional,
+            "नमस्ते",
+            20.0,
+            &TextSpacing::default(),
+            &Typography::default(),
+            TextHorizontalAlignment::Left,
+            100.0,
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn text_egui_can_draw_isnt_shaped() {
+        let definitions = FontDefinitions::default();
+        assert_eq!(
+            missing_script(&definitions, &FontFamily::Proportional, "Hello"),
+            None
+        );
+        assert!(ShapedText::new(
+            &definitions,
+            &FontFamily::Proportional,
+            "Hello",
+            20.0,
+            &TextSpacing::default(),
+            &Typography::default(),
+            TextHorizontalAlignment::Left,
+            100.0,
+        )
+        .is_none());
+    }
+}
@@ -73,4 +73,34 @@ where
 
         self.index = self.history.len() - 1;
     }
+
+    /// Moves straight to the entry at `index`, as if undoing or redoing until reaching it
+    pub fn jump_to(&mut self, index: usize) -> Value {
+        if self.history.is_empty() {
+            return self.initial_value.clone();
+        }
+
+        self.index = index.min(self.history.len() - 1);
+        self.history[self.index].1.clone()
+    }
+
+    /// Replaces the value of the current entry, for changes that continue the last step
+    pub fn amend_current(&mut self, value: Value) {
+        if let Some(current) = self.history.get_mut(self.index) {
+            current.1 = value;
+        }
+    }
+
+    /// Forgets the oldest history entry, making it the new initial value. Returns false if
+    /// there's nothing that can be dropped without losing the current value.
+    pub fn drop_oldest(&mut self) -> bool {
+        if self.history.len() < 2 || self.index == 0 {
+            return false;
+        }
+
+        let (_, oldest) = self.history.remove(0);
+        self.initial_value = oldest;
+        self.index -= 1;
+        true
+    }
 }
@@ -5,6 +5,8 @@ use once_cell::sync::Lazy;
 pub type LayerId = usize;
 pub type PageId = usize;
 pub type ModalId = usize;
+pub type CommentId = usize;
+pub type GuideId = usize;
 
 struct IdGenerator {
     next_id: LayerId,
@@ -32,6 +34,14 @@ pub fn next_modal_id() -> ModalId {
     next_id()
 }
 
+pub fn next_comment_id() -> CommentId {
+    next_id()
+}
+
+pub fn next_guide_id() -> GuideId {
+    next_id()
+}
+
 pub fn next_quick_layout_index() -> usize {
     next_id()
 }
@@ -0,0 +1,123 @@
+use std::hash::{Hash, Hasher};
+
+use fxhash::FxHasher;
+
+use crate::{
+    auto_persisting::AutoPersisting,
+    config::Config,
+    dependencies::{Dependency, SingletonFor},
+    widget::canvas_info::layers::{Layer, LayerContent},
+};
+
+pub const DEFAULT_PHOTO_LAYER_NAME: &str = "{filename}";
+pub const DEFAULT_TEXT_LAYER_NAME: &str = "{words}";
+
+// How many words of a text layer go into its name
+const NAME_WORDS: usize = 4;
+
+/// Patterns photo and text layers are named with until they're renamed, e.g.
+/// "Photo – {filename}" or "Text – {words}"
+#[derive(Debug, Clone, PartialEq)]
+pub struct LayerNaming {
+    pub photo: String,
+    pub text: String,
+}
+
+impl Default for LayerNaming {
+    fn default() -> Self {
+        Self {
+            photo: DEFAULT_PHOTO_LAYER_NAME.to_string(),
+            text: DEFAULT_TEXT_LAYER_NAME.to_string(),
+        }
+    }
+}
+
+impl LayerNaming {
+    pub const PHOTO_TOKENS: [&'static str; 2] = ["{filename}", "{stem}"];
+    pub const TEXT_TOKENS: [&'static str; 1] = ["{words}"];
+
+    pub fn current() -> Self {
+        Dependency::<AutoPersisting<Config>>::get().with_lock(|config| {
+            config
+                .read()
+                .map(|config| config.layer_naming())
+                .unwrap_or_default()
+        })
+    }
+
+    /// The name the rules give a layer with this content. Layers without a rule, like paths and
+    /// empty template regions, keep the name they were given.
+    pub fn name_for(&self, content: &LayerContent) -> Option<String> {
+        let name = match content {
+            LayerContent::Photo(photo)
+            | LayerContent::TemplatePhoto {
+                photo: Some(photo), ..
+            } => {
+                let path = &photo.photo.path;
+                let stem = path
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().to_string())
+                    .unwrap_or_default();
+
+                self.photo
+                    .replace("{filename}", photo.photo.file_name())
+                    .replace("{stem}", &stem)
+            }
+            LayerContent::Text(text) | LayerContent::TemplateText { text, .. } => {
+                let words = Self::first_words(&text.text);
+                if words.is_empty() {
+                    return Some("Text".to_string());
+                }
+
+                self.text.replace("{words}", &words)
+            }
+            LayerContent::TemplatePhoto { photo: None, .. }
+            | LayerContent::Decoration(_)
+            | LayerContent::Path(_) => return None,
+        };
+
+        let name = name.trim();
+        (!name.is_empty()).then(|| name.to_string())
+    }
+
+    /// A hash of the rules and of what the auto named `layers` are named after, it changes
+    /// whenever one of their names would
+    pub fn names_key<'a>(&self, layers: impl Iterator<Item = &'a Layer>) -> u64 {
+        let mut hasher = FxHasher::default();
+        self.photo.hash(&mut hasher);
+        self.text.hash(&mut hasher);
+
+        for layer in layers.filter(|layer| layer.auto_named) {
+            layer.id.hash(&mut hasher);
+            match &layer.content {
+                LayerContent::Photo(photo)
+                | LayerContent::TemplatePhoto {
+                    photo: Some(photo), ..
+                } => photo.photo.path.hash(&mut hasher),
+                LayerContent::Text(text) | LayerContent::TemplateText { text, .. } => {
+                    text.text.hash(&mut hasher)
+                }
+                LayerContent::TemplatePhoto { photo: None, .. }
+                | LayerContent::Decoration(_)
+                | LayerContent::Path(_) => {}
+            }
+        }
+
+        hasher.finish()
+    }
+
+    fn first_words(text: &str) -> String {
+        let mut words = text.split_whitespace();
+        let first = words
+            .by_ref()
+            .take(NAME_WORDS)
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        if words.next().is_some() {
+            format!("{}…", first)
+        } else {
+            first
+        }
+    }
+}
@@ -1,14 +1,15 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")] // hide console window on Windows in release
 
-use std::sync::Arc;
+use std::{sync::Arc, time::Instant};
 
 use auto_persisting::AutoPersisting;
 use autosave_manager::AutoSaveManager;
-use config::Config;
+use config::{Config, ConfigModification, DEFAULT_UI_SCALE, MAX_UI_SCALE, MIN_UI_SCALE};
 use cursor_manager::CursorManager;
+use debug::{FrameDiagnostics, FramePhase, GpuInfo};
 use dependencies::{Dependency, DependencyFor, Singleton, SingletonFor};
 use eframe::{
-    egui::{self, ViewportBuilder, Widget},
+    egui::{self, ViewportBuilder, ViewportCommand, Widget},
     // egui_wgpu::{WgpuConfiguration, WgpuSetup},
     //wgpu,
 };
@@ -16,40 +17,83 @@ use eframe::{
 use font_manager::FontManager;
 
 use dirs::Dirs;
-use log::info;
-use modal::manager::ModalManager;
+use error_sink::ErrorSink;
+use log::{error, info};
+use modal::{
+    manager::{ModalManager, TypedModalId},
+    save_warning::SaveWarningModal,
+    ModalActionResponse,
+};
 use photo_manager::PhotoManager;
 use project::v1::Project;
 use scene::{organize_edit_scene::OrganizeEditScene, SceneManager};
+use session::Session;
+use theme::{AccentColor, ThemeMode};
 use tokio::runtime;
 
 use flexi_logger::{Logger, WriteMode};
 use string_log::{ArcStringLog, StringLog};
 
+mod animation_still;
 mod assets;
 mod auto_persisting;
+mod auto_placement;
 mod autosave_manager;
+mod book_metadata;
+mod book_statistics;
+mod book_text;
+mod book_theme;
+mod book_vendor;
+mod camera_import;
 mod config;
 mod cursor_manager;
 mod debug;
+mod decoration;
 mod dependencies;
 mod dirs;
 mod error_sink;
 mod export;
 mod font_manager;
+mod glyph_text;
 mod history;
 mod id;
+mod layer_naming;
 mod modal;
 mod model;
+mod output_sharpening;
+mod page_preloader;
+mod page_rescale;
+mod panorama;
 mod photo;
+mod photo_adjustments;
+mod photo_captions;
+mod photo_database;
 mod photo_manager;
+mod photo_query;
+mod photo_stacks;
+mod print_check;
 mod project;
+mod project_fonts;
 mod project_settings;
+mod psd_export;
+mod render;
+mod review_report;
+mod saliency;
 mod scene;
 mod session;
+mod smart_collection;
+mod snippet;
+mod spell_check;
 mod string_log;
+mod svg_export;
 mod template;
+mod text_shaping;
+mod text_style;
+mod texture_cache;
+mod texture_load_queue;
 mod theme;
+mod trash;
+mod typography;
 mod utils;
 mod widget;
 
@@ -76,6 +120,11 @@ async fn main() -> anyhow::Result<()> {
         .write_mode(WriteMode::Direct)
         .start()?;
 
+    // Read while the window is being created instead of during the first frame
+    Dependency::<AutoPersisting<Config>>::get().with_lock_mut(|config| {
+        config.load_in_background();
+    });
+
     let options = eframe::NativeOptions {
         viewport: ViewportBuilder::default()
             .with_maximize_button(true)
@@ -135,6 +184,12 @@ struct PhotoBookApp {
     loaded_fonts: bool,
     scene_manager: SceneManager,
     loaded_initial_scene: bool,
+    close_warning_modal_id: Option<TypedModalId<SaveWarningModal>>,
+    allow_close: bool,
+    // The window was asked to close while a save was running, it closes once the save is done
+    close_after_save: bool,
+    applied_theme: Option<(ThemeMode, AccentColor)>,
+    applied_ui_scale: Option<f32>,
 }
 
 impl PhotoBookApp {
@@ -145,6 +200,113 @@ impl PhotoBookApp {
             loaded_fonts: false,
             scene_manager: SceneManager::default(),
             loaded_initial_scene: false,
+            close_warning_modal_id: None,
+            allow_close: false,
+            close_after_save: false,
+            applied_theme: None,
+            applied_ui_scale: None,
+        }
+    }
+
+    // Picks up theme changes from the config so they take effect straight away
+    fn apply_theme(&mut self, ctx: &egui::Context) {
+        let theme = Dependency::<AutoPersisting<Config>>::get().with_lock_mut(|config| {
+            config
+                .read()
+                .map(|config| (config.theme_mode(), config.accent_color()))
+                .unwrap_or_default()
+        });
+
+        if self.applied_theme != Some(theme) {
+            let (mode, accent) = theme;
+            theme::apply(ctx, mode, accent);
+            self.applied_theme = Some(theme);
+        }
+    }
+
+    // The UI scale multiplies the display's own scaling, which egui follows by itself when the
+    // window moves to another monitor or the system setting changes. Zooming with ctrl and +/-
+    // is kept as the new setting.
+    fn apply_ui_scale(&mut self, ctx: &egui::Context) {
+        let config = Dependency::<AutoPersisting<Config>>::get();
+        let zoom_factor = ctx.zoom_factor();
+
+        if let Some(applied) = self.applied_ui_scale {
+            if (zoom_factor - applied).abs() > f32::EPSILON {
+                let scale = zoom_factor.clamp(MIN_UI_SCALE, MAX_UI_SCALE);
+                config.with_lock_mut(|config| {
+                    if let Err(err) = config.modify(ConfigModification::SetUiScale(scale)) {
+                        error!("Failed to update UI scale: {:?}", err);
+                    }
+                });
+                ctx.set_zoom_factor(scale);
+                self.applied_ui_scale = Some(scale);
+                return;
+            }
+        }
+
+        let scale = config.with_lock_mut(|config| {
+            config
+                .read()
+                .map(|config| config.ui_scale())
+                .unwrap_or(DEFAULT_UI_SCALE)
+        });
+
+        if self.applied_ui_scale != Some(scale) {
+            ctx.set_zoom_factor(scale);
+            self.applied_ui_scale = Some(scale);
+        }
+    }
+
+    // Keeps the window open until unsaved changes have been saved or discarded
+    fn handle_close_request(&mut self, ctx: &egui::Context) {
+        if let Some(id) = &self.close_warning_modal_id {
+            let modal_manager: Singleton<ModalManager> = Dependency::get();
+
+            let exists = modal_manager.with_lock(|modal_manager| modal_manager.exists(id));
+
+            let modal_response =
+                modal_manager.with_lock(|modal_manager| modal_manager.response_for(id));
+            if let Some(ModalActionResponse::Confirm) = modal_response {
+                self.allow_close = true;
+                ctx.send_viewport_cmd(ViewportCommand::Close);
+            }
+
+            if !exists {
+                self.close_warning_modal_id = None;
+            }
+        }
+
+        let saving = Dependency::<Session>::get().with_lock(|session| session.is_saving());
+
+        // Asking again goes through the unsaved changes check with the finished save
+        if self.close_after_save && !saving {
+            self.close_after_save = false;
+            ctx.send_viewport_cmd(ViewportCommand::Close);
+        }
+
+        if self.allow_close || !ctx.input(|input| input.viewport().close_requested()) {
+            return;
+        }
+
+        if self.close_warning_modal_id.is_some() {
+            ctx.send_viewport_cmd(ViewportCommand::CancelClose);
+            return;
+        }
+
+        if saving {
+            ctx.send_viewport_cmd(ViewportCommand::CancelClose);
+            self.close_after_save = true;
+            return;
+        }
+
+        let project = self.scene_manager.root_scene.project();
+        let has_unsaved_changes =
+            Dependency::<Session>::get().with_lock(|session| session.has_unsaved_changes(&project));
+
+        if has_unsaved_changes {
+            ctx.send_viewport_cmd(ViewportCommand::CancelClose);
+            self.close_warning_modal_id = Some(ModalManager::push(SaveWarningModal::new(project)));
         }
     }
 
@@ -165,7 +327,10 @@ impl PhotoBookApp {
             return SceneManager::new(scene);
         }
 
-        SceneManager::default()
+        // Nothing to restore, so help the user set up their first book
+        let mut scene_manager = SceneManager::default();
+        scene_manager.root_scene.show_new_project_wizard();
+        scene_manager
     }
 
     fn try_load_auto_save() -> Option<OrganizeEditScene> {
@@ -188,6 +353,8 @@ impl PhotoBookApp {
         project_path: &Option<std::path::PathBuf>,
     ) -> Option<OrganizeEditScene> {
         let path = project_path.as_ref()?;
+        // Fonts are loaded after the first scene so embedded ones are picked up straight away
+        project_fonts::install_embedded_fonts(path);
         match Project::load(path) {
             Ok(scene) => Some(scene),
             Err(e) => {
@@ -211,46 +378,87 @@ impl PhotoBookApp {
 }
 
 impl eframe::App for PhotoBookApp {
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+    fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        let diagnostics: Singleton<FrameDiagnostics> = Dependency::get();
+        diagnostics.with_lock_mut(|diagnostics| diagnostics.begin_frame());
+
         if !self.loaded_initial_scene {
             egui_extras::install_image_loaders(ctx);
 
-            ctx.input_mut(|input| {
+            let max_texture_side = ctx.input_mut(|input| {
+                let max_texture_side = input.max_texture_side;
+                // The texture budget is sized for the adapter's limit before it's lifted
+                self.photo_manager.with_lock_mut(|photo_manager| {
+                    photo_manager.set_max_texture_side(max_texture_side);
+                });
                 input.max_texture_side = usize::MAX; // Allow maximum possible texture size
+                max_texture_side
             });
 
+            if let Some(gl) = frame.gl() {
+                diagnostics.with_lock_mut(|diagnostics| {
+                    diagnostics.set_gpu(GpuInfo::read(gl, max_texture_side));
+                });
+            }
+
             self.loaded_initial_scene = true;
             self.scene_manager = Self::initialize_scene_manager();
         }
 
-        if !self.loaded_fonts {
-            self.loaded_fonts = true;
-            // Just load all fonts at start up. Maybe there's a better time to do this?
-            let font_manager: Singleton<FontManager> = Dependency::get();
-            font_manager.with_lock_mut(|font_manager| {
+        // The installed fonts are indexed in the background, families are only loaded once
+        // something is laid out in them
+        let fonts_started_at = Instant::now();
+        let font_manager: Singleton<FontManager> = Dependency::get();
+        font_manager.with_lock_mut(|font_manager| {
+            if !self.loaded_fonts {
+                self.loaded_fonts = true;
                 font_manager.load_fonts(ctx);
-            });
-        }
+            }
+            font_manager.load_requested(ctx);
+        });
+        diagnostics.with_lock_mut(|diagnostics| {
+            diagnostics.record(FramePhase::Fonts, fonts_started_at.elapsed());
+        });
+
+        self.apply_theme(ctx);
+        self.apply_ui_scale(ctx);
 
         Dependency::<CursorManager>::get().with_lock_mut(|cursor_manager| {
             cursor_manager.begin_frame(ctx);
         });
 
         egui::CentralPanel::default().show(ctx, |ui| {
+            let scene_started_at = Instant::now();
             self.scene_manager.ui(ui);
+            let modals_started_at = Instant::now();
 
             let modal_manager: Singleton<ModalManager> = Dependency::get();
             modal_manager.with_lock_mut(|modal_manager| {
                 modal_manager.show_next(ui);
             });
+
+            diagnostics.with_lock_mut(|diagnostics| {
+                diagnostics.record(FramePhase::Scene, modals_started_at - scene_started_at);
+                diagnostics.record(FramePhase::Modals, modals_started_at.elapsed());
+            });
         });
 
+        ErrorSink::show(ctx);
+
         Dependency::<CursorManager>::get().with_lock_mut(|cursor_manager| {
             cursor_manager.end_frame(ctx);
         });
 
+        self.handle_close_request(ctx);
+
+        let auto_save_started_at = Instant::now();
         Dependency::<AutoSaveManager>::get().with_lock_mut(|auto_save_manager| {
             let _ = auto_save_manager.auto_save_if_needed(&self.scene_manager.root_scene);
         });
+
+        diagnostics.with_lock_mut(|diagnostics| {
+            diagnostics.record(FramePhase::AutoSave, auto_save_started_at.elapsed());
+            diagnostics.end_frame();
+        });
     }
 }
@@ -0,0 +1,212 @@
+use std::path::PathBuf;
+
+use egui::{ColorImage, Image, RichText, Slider, TextureHandle, TextureOptions, Vec2};
+use log::error;
+use tokio::task::spawn_blocking;
+
+use crate::{
+    animation_still::{self, AnimationFrames},
+    dependencies::{Dependency, SingletonFor},
+    photo_manager::PhotoManager,
+    project_settings::ProjectSettingsManager,
+};
+
+use super::{Modal, ModalActionResponse};
+
+const PREVIEW_SIZE: Vec2 = Vec2::new(480.0, 360.0);
+
+/// Picks the frame of each imported animation that's added to the library as a photo, one
+/// animation at a time. The picks are saved with the project so importing again gives the same
+/// stills.
+pub struct AnimationFrameModal {
+    animations: Vec<AnimationFrames>,
+    // The animation being picked for
+    current: usize,
+    frame: usize,
+    // The frame shown, made when the picked frame changes
+    preview: Option<(usize, usize, TextureHandle)>,
+    picked: Vec<(PathBuf, usize)>,
+}
+
+impl AnimationFrameModal {
+    pub fn new(animations: Vec<AnimationFrames>) -> Self {
+        let frame = animations
+            .first()
+            .map(AnimationFrames::default_frame)
+            .unwrap_or_default();
+
+        Self {
+            animations,
+            current: 0,
+            frame,
+            preview: None,
+            picked: Vec::new(),
+        }
+    }
+
+    fn preview_texture(&mut self, ctx: &egui::Context) -> Option<TextureHandle> {
+        match &self.preview {
+            Some((animation, frame, texture))
+                if *animation == self.current && *frame == self.frame =>
+            {
+                return Some(texture.clone());
+            }
+            _ => {}
+        }
+
+        let image = self.animations.get(self.current)?.frames.get(self.frame)?;
+        let texture = ctx.load_texture(
+            "animation_frame_preview",
+            ColorImage::from_rgba_unmultiplied(
+                [image.width() as usize, image.height() as usize],
+                image.as_raw(),
+            ),
+            TextureOptions::LINEAR,
+        );
+        self.preview = Some((self.current, self.frame, texture.clone()));
+
+        Some(texture)
+    }
+
+    // Moves on to the next animation. Returns false once they've all been seen.
+    fn next(&mut self) -> bool {
+        self.current += 1;
+        match self.animations.get(self.current) {
+            Some(animation) => {
+                self.frame = animation.default_frame();
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Writes the picked stills and adds them to the library
+    fn finish(&mut self) {
+        let picked = std::mem::take(&mut self.picked);
+        if picked.is_empty() {
+            return;
+        }
+
+        Dependency::<ProjectSettingsManager>::get().with_lock_mut(|manager| {
+            for (source, frame) in &picked {
+                manager
+                    .project_settings
+                    .animation_frames
+                    .insert(source.clone(), *frame);
+            }
+        });
+
+        let frames: Vec<_> = picked
+            .into_iter()
+            .filter_map(|(source, frame)| {
+                let animation = self
+                    .animations
+                    .iter_mut()
+                    .find(|animation| animation.source == source)?;
+                let image = std::mem::take(animation.frames.get_mut(frame)?);
+                Some((source, frame, image))
+            })
+            .collect();
+
+        tokio::spawn(async move {
+            let stills = spawn_blocking(move || {
+                frames
+                    .into_iter()
+                    .filter_map(|(source, frame, image)| {
+                        animation_still::write_still(&source, frame, &image)
+                            .map_err(|err| {
+                                error!("Failed to save still of {:?}: {:?}", source, err)
+                            })
+                            .ok()
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .await
+            .unwrap_or_default();
+
+            PhotoManager::import_files(stills).await;
+        });
+    }
+}
+
+impl Modal for AnimationFrameModal {
+    fn title(&self) -> String {
+        "Pick a Frame".to_string()
+    }
+
+    fn body_ui(&mut self, ui: &mut egui::Ui) {
+        let Some(animation) = self.animations.get(self.current) else {
+            return;
+        };
+
+        let file_name = animation
+            .source
+            .file_name()
+            .map(|file_name| file_name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let frame_count = animation.frames.len();
+
+        ui.label(RichText::new(file_name).strong());
+        if self.animations.len() > 1 {
+            ui.label(
+                RichText::new(format!(
+                    "Animation {} of {}",
+                    self.current + 1,
+                    self.animations.len()
+                ))
+                .weak(),
+            );
+        }
+
+        ui.add_space(5.0);
+
+        if let Some(texture) = self.preview_texture(ui.ctx()) {
+            ui.add_sized(
+                PREVIEW_SIZE,
+                Image::from_texture(&texture).max_size(PREVIEW_SIZE),
+            );
+        }
+
+        let mut frame_number = self.frame + 1;
+        ui.add(Slider::new(&mut frame_number, 1..=frame_count).text("Frame"));
+        self.frame = frame_number - 1;
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn actions_ui(&mut self, ui: &mut egui::Ui) -> ModalActionResponse {
+        if ui
+            .button("Cancel")
+            .on_hover_text("Don't add this or the remaining animations")
+            .clicked()
+        {
+            self.finish();
+            return ModalActionResponse::Cancel;
+        }
+
+        if ui
+            .button("Skip")
+            .on_hover_text("Don't add this animation")
+            .clicked()
+            && !self.next()
+        {
+            self.finish();
+            return ModalActionResponse::Confirm;
+        }
+
+        if ui.button("Use Frame").clicked() {
+            if let Some(animation) = self.animations.get(self.current) {
+                self.picked.push((animation.source.clone(), self.frame));
+            }
+
+            if !self.next() {
+                self.finish();
+                return ModalActionResponse::Confirm;
+            }
+        }
+
+        ModalActionResponse::None
+    }
+}
@@ -0,0 +1,189 @@
+use std::sync::{Arc, RwLock};
+
+use egui::{ComboBox, DragValue, Grid, RichText, ScrollArea, Sense, Slider, Vec2};
+use strum::IntoEnumIterator;
+
+use crate::{
+    auto_placement::{AutoPlacement, AutoPlacementOptions, PlacementGrouping, PlannedPage},
+    dependencies::{Dependency, SingletonFor},
+    photo::Photo,
+    photo_manager::PhotoManager,
+    scene::canvas_scene::CanvasScene,
+    theme,
+};
+
+use super::{Modal, ModalActionResponse};
+
+#[derive(Debug, Clone)]
+struct PlacementSummary {
+    options: AutoPlacementOptions,
+    groups: usize,
+    pages: Vec<PlannedPage>,
+}
+
+const PREVIEW_THUMBNAIL_SIZE: f32 = 40.0;
+
+#[derive(Debug, Clone)]
+pub struct AutoPlacementModal {
+    photos: Vec<Photo>,
+    edit: Arc<RwLock<CanvasScene>>,
+    options: AutoPlacementOptions,
+    // Grouping reads the capture date of every photo so it's only redone when the options change
+    summary: Option<PlacementSummary>,
+}
+
+impl AutoPlacementModal {
+    pub fn new(photos: Vec<Photo>, edit: Arc<RwLock<CanvasScene>>) -> Self {
+        Self {
+            photos,
+            edit,
+            options: AutoPlacementOptions::default(),
+            summary: None,
+        }
+    }
+
+    fn summary(&mut self) -> &PlacementSummary {
+        let stale = match &self.summary {
+            Some(summary) => summary.options != self.options,
+            None => true,
+        };
+
+        if stale {
+            let groups = AutoPlacement::group(self.photos.clone(), &self.options);
+            self.summary = Some(PlacementSummary {
+                options: self.options.clone(),
+                groups: groups.len(),
+                pages: AutoPlacement::plan(&groups, &self.options),
+            });
+        }
+
+        self.summary.as_ref().unwrap()
+    }
+
+    // The photos each page will get, updated as the options change
+    fn preview_ui(ui: &mut egui::Ui, pages: &[PlannedPage]) {
+        ScrollArea::vertical()
+            .id_salt("auto_placement_preview")
+            .max_height(260.0)
+            .auto_shrink([false, true])
+            .show(ui, |ui| {
+                for (index, page) in pages.iter().enumerate() {
+                    ui.horizontal(|ui| {
+                        ui.label(RichText::new(format!("Page {}", index + 1)).strong());
+                        if let Some(header) = &page.header {
+                            ui.weak(header);
+                        }
+                    });
+
+                    ui.horizontal_wrapped(|ui| {
+                        for photo in &page.photos {
+                            Self::preview_thumbnail(ui, photo);
+                        }
+                    });
+
+                    ui.add_space(5.0);
+                }
+            });
+    }
+
+    fn preview_thumbnail(ui: &mut egui::Ui, photo: &Photo) {
+        let (rect, response) =
+            ui.allocate_exact_size(Vec2::splat(PREVIEW_THUMBNAIL_SIZE), Sense::hover());
+        ui.painter()
+            .rect_filled(rect, 2.0, theme::color::placeholder(ui.visuals()));
+
+        let texture = Dependency::<PhotoManager>::get()
+            .with_lock_mut(|photo_manager| photo_manager.thumbnail_texture_for(photo, ui.ctx()));
+
+        if let Ok(Some(texture)) = texture {
+            photo.paint_fitted(ui, texture, rect.shrink(2.0));
+        }
+
+        response.on_hover_text(photo.file_name());
+    }
+}
+
+impl Modal for AutoPlacementModal {
+    fn title(&self) -> String {
+        "Auto Layout".to_string()
+    }
+
+    fn body_ui(&mut self, ui: &mut egui::Ui) {
+        ui.label("Photos are placed on new pages in the order they were taken");
+        ui.add_space(10.0);
+
+        Grid::new("auto_placement_options")
+            .num_columns(2)
+            .spacing([20.0, 5.0])
+            .show(ui, |ui| {
+                ui.label("Group By:");
+                ComboBox::from_id_salt("auto_placement_grouping")
+                    .selected_text(self.options.grouping.to_string())
+                    .show_ui(ui, |ui| {
+                        for grouping in PlacementGrouping::iter() {
+                            ui.selectable_value(
+                                &mut self.options.grouping,
+                                grouping,
+                                grouping.to_string(),
+                            );
+                        }
+                    });
+                ui.end_row();
+
+                if self.options.grouping == PlacementGrouping::Event {
+                    ui.label("New Page After Gap:");
+                    ui.add(
+                        Slider::new(&mut self.options.event_gap_hours, 0.25..=72.0)
+                            .logarithmic(true)
+                            .max_decimals(2)
+                            .suffix(" h"),
+                    )
+                    .on_hover_text("Photos taken further apart than this start a new page");
+                    ui.end_row();
+                }
+
+                ui.label("Photos Per Page:");
+                ui.add(DragValue::new(&mut self.options.max_photos_per_page).range(1..=9));
+                ui.end_row();
+
+                ui.label("Date Headers:");
+                ui.checkbox(&mut self.options.date_headers, "");
+                ui.end_row();
+            });
+
+        ui.add_space(10.0);
+
+        let photos = self.photos.len();
+        let summary = self.summary();
+        ui.label(format!(
+            "{} photos in {} groups on {} pages",
+            photos,
+            summary.groups,
+            summary.pages.len()
+        ));
+
+        ui.add_space(5.0);
+        Self::preview_ui(ui, &summary.pages);
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn actions_ui(&mut self, ui: &mut egui::Ui) -> ModalActionResponse {
+        if ui.button("Cancel").clicked() {
+            return ModalActionResponse::Cancel;
+        }
+
+        if ui
+            .add_enabled(!self.photos.is_empty(), egui::Button::new("Create Pages"))
+            .clicked()
+        {
+            let pages = AutoPlacement::pages(self.summary().pages.clone());
+            self.edit.write().unwrap().state.append_pages(pages);
+            return ModalActionResponse::Confirm;
+        }
+
+        ModalActionResponse::None
+    }
+}
@@ -0,0 +1,208 @@
+use egui::{ComboBox, Grid, RichText, TextEdit};
+use strum::IntoEnumIterator;
+
+use crate::{
+    dependencies::{Dependency, SingletonFor},
+    photo::Photo,
+    photo_captions::{CaptionPattern, CaptionTarget},
+    photo_manager::PhotoManager,
+};
+
+use super::{Modal, ModalActionResponse};
+
+// How many photos are shown in the preview
+const PREVIEW_COUNT: usize = 6;
+
+#[derive(Debug, Clone, PartialEq)]
+struct CaptionPreview {
+    pattern: CaptionPattern,
+    target: CaptionTarget,
+    overwrite: bool,
+    // File name and the text it would get, None when the existing text is kept
+    rows: Vec<(String, Option<String>)>,
+}
+
+/// Gives a batch of photos a title or caption built from their file names, folders and EXIF data
+#[derive(Debug, Clone)]
+pub struct BatchCaptionModal {
+    photos: Vec<Photo>,
+    pattern: CaptionPattern,
+    target: CaptionTarget,
+    overwrite: bool,
+    // Building the text can read file dates so it's only redone when the options change
+    preview: Option<CaptionPreview>,
+}
+
+impl BatchCaptionModal {
+    pub fn new(photos: Vec<Photo>) -> Self {
+        Self {
+            photos,
+            pattern: CaptionPattern::default(),
+            target: CaptionTarget::Caption,
+            overwrite: false,
+            preview: None,
+        }
+    }
+
+    // The text for the photo at `index`, None if it already has text that shouldn't be replaced
+    fn text_for(&self, photo_manager: &PhotoManager, index: usize) -> Option<String> {
+        let photo = &self.photos[index];
+        let has_text = photo_manager
+            .captions(&photo.path)
+            .get(self.target)
+            .is_some();
+
+        if has_text && !self.overwrite {
+            return None;
+        }
+
+        Some(self.pattern.apply(photo, index))
+    }
+
+    fn preview(&mut self) -> &CaptionPreview {
+        let stale = match &self.preview {
+            Some(preview) => {
+                preview.pattern != self.pattern
+                    || preview.target != self.target
+                    || preview.overwrite != self.overwrite
+            }
+            None => true,
+        };
+
+        if stale {
+            let rows = Dependency::<PhotoManager>::get().with_lock(|photo_manager| {
+                (0..self.photos.len().min(PREVIEW_COUNT))
+                    .map(|index| {
+                        (
+                            self.photos[index].file_name().to_string(),
+                            self.text_for(photo_manager, index),
+                        )
+                    })
+                    .collect()
+            });
+
+            self.preview = Some(CaptionPreview {
+                pattern: self.pattern.clone(),
+                target: self.target,
+                overwrite: self.overwrite,
+                rows,
+            });
+        }
+
+        self.preview.as_ref().unwrap()
+    }
+
+    fn apply(&self) {
+        Dependency::<PhotoManager>::get().with_lock_mut(|photo_manager| {
+            for index in 0..self.photos.len() {
+                if let Some(text) = self.text_for(photo_manager, index) {
+                    let path = self.photos[index].path.clone();
+                    photo_manager.set_caption(&path, self.target, Some(text));
+                }
+            }
+        });
+    }
+}
+
+impl Modal for BatchCaptionModal {
+    fn title(&self) -> String {
+        "Batch Captions".to_string()
+    }
+
+    fn body_ui(&mut self, ui: &mut egui::Ui) {
+        ui.label(format!("{} photos", self.photos.len()));
+        ui.add_space(10.0);
+
+        Grid::new("batch_caption_options")
+            .num_columns(2)
+            .spacing([20.0, 5.0])
+            .show(ui, |ui| {
+                ui.label("Set:");
+                ComboBox::from_id_salt("batch_caption_target")
+                    .selected_text(self.target.to_string())
+                    .show_ui(ui, |ui| {
+                        for target in CaptionTarget::iter() {
+                            ui.selectable_value(&mut self.target, target, target.to_string());
+                        }
+                    });
+                ui.end_row();
+
+                ui.label("Pattern:");
+                ui.add(TextEdit::singleline(&mut self.pattern.template).desired_width(220.0));
+                ui.end_row();
+
+                ui.label("");
+                ui.horizontal_wrapped(|ui| {
+                    for token in CaptionPattern::TOKENS {
+                        if ui.small_button(token).clicked() {
+                            self.pattern.template.push_str(token);
+                        }
+                    }
+                });
+                ui.end_row();
+
+                ui.label("Strip Camera Prefix:");
+                ui.checkbox(&mut self.pattern.strip_camera_prefix, "")
+                    .on_hover_text("IMG_1234 becomes 1234");
+                ui.end_row();
+
+                ui.label("Separators to Spaces:");
+                ui.checkbox(&mut self.pattern.separators_to_spaces, "")
+                    .on_hover_text("beach_day-2 becomes beach day 2");
+                ui.end_row();
+
+                ui.label("Title Case:");
+                ui.checkbox(&mut self.pattern.title_case, "");
+                ui.end_row();
+
+                ui.label("Replace Existing:");
+                ui.checkbox(&mut self.overwrite, "");
+                ui.end_row();
+            });
+
+        ui.add_space(10.0);
+        ui.label(RichText::new("Preview").strong());
+
+        let more = self.photos.len().saturating_sub(PREVIEW_COUNT);
+        let preview = self.preview();
+
+        Grid::new("batch_caption_preview")
+            .num_columns(2)
+            .striped(true)
+            .show(ui, |ui| {
+                for (file_name, text) in &preview.rows {
+                    ui.label(file_name);
+                    match text {
+                        Some(text) if text.is_empty() => ui.label(RichText::new("(empty)").weak()),
+                        Some(text) => ui.label(text),
+                        None => ui.label(RichText::new("(kept)").weak()),
+                    };
+                    ui.end_row();
+                }
+            });
+
+        if more > 0 {
+            ui.label(RichText::new(format!("and {} more", more)).weak());
+        }
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn actions_ui(&mut self, ui: &mut egui::Ui) -> ModalActionResponse {
+        if ui.button("Cancel").clicked() {
+            return ModalActionResponse::Cancel;
+        }
+
+        if ui
+            .add_enabled(!self.photos.is_empty(), egui::Button::new("Apply"))
+            .clicked()
+        {
+            self.apply();
+            return ModalActionResponse::Confirm;
+        }
+
+        ModalActionResponse::None
+    }
+}
@@ -0,0 +1,474 @@
+use std::{
+    collections::BTreeSet,
+    sync::{Arc, Mutex},
+};
+
+use egui::{Button, ComboBox, DragValue, Grid, RichText, ScrollArea, TextEdit};
+use log::{error, info};
+use strum::IntoEnumIterator;
+use strum_macros::{Display, EnumIter};
+
+use crate::{
+    book_vendor::{BookVendor, VendorSpecViolation},
+    dependencies::{Dependency, Singleton, SingletonFor},
+    export::Exporter,
+    id::LayerId,
+    output_sharpening::{OutputSharpening, SharpeningPreset},
+    print_check::{self, PrintIssue},
+    project_settings::ProjectSettingsManager,
+    theme,
+    widget::canvas::CanvasState,
+};
+
+use super::{Modal, ModalActionResponse};
+
+#[derive(Debug, Clone, Copy, PartialEq, Display, EnumIter)]
+enum PageSelection {
+    #[strum(to_string = "All Pages")]
+    All,
+    #[strum(to_string = "Page Range")]
+    Range,
+    #[strum(to_string = "Selected Pages")]
+    Selected,
+    #[strum(to_string = "Changed Since Last Export")]
+    Changed,
+}
+
+/// A layer picked from the print check to be shown on its page, by page index
+pub type ShowLayerRequest = Arc<Mutex<Option<(usize, LayerId)>>>;
+
+#[derive(Debug, Clone)]
+pub struct BookExportModal {
+    pages: Vec<CanvasState>,
+    vendor: Option<BookVendor>,
+    violations: Vec<VendorSpecViolation>,
+    page_selection: PageSelection,
+    // Page numbers as typed, e.g. "3-10" or "1, 4-6"
+    page_range: String,
+    // Indices of the pages selected in the pages panel
+    selected_pages: Vec<usize>,
+    // Indices of the pages that look different from when they were last exported
+    changed_pages: Vec<usize>,
+    // Text and lines on every page that may not print well
+    print_issues: Vec<PrintIssue>,
+    show_layer: ShowLayerRequest,
+    // Set when a print issue is clicked, the export is closed to show it
+    issue_picked: bool,
+    sharpening: Option<OutputSharpening>,
+}
+
+impl BookExportModal {
+    pub fn new(
+        pages: Vec<CanvasState>,
+        selected_pages: Vec<usize>,
+        show_layer: ShowLayerRequest,
+    ) -> Self {
+        let changed_pages = Dependency::<ProjectSettingsManager>::get().with_lock(|manager| {
+            pages
+                .iter()
+                .enumerate()
+                .filter(|(index, page)| manager.project_settings.changed_since_export(*index, page))
+                .map(|(index, _)| index)
+                .collect()
+        });
+
+        Self {
+            print_issues: print_check::check_pages(&pages),
+            pages,
+            vendor: None,
+            violations: vec![],
+            page_selection: PageSelection::All,
+            page_range: String::new(),
+            selected_pages,
+            changed_pages,
+            show_layer,
+            issue_picked: false,
+            sharpening: None,
+        }
+    }
+
+    /// Indices of the pages to export, None if the page range can't be read
+    fn page_indices(&self) -> Option<Vec<usize>> {
+        match self.page_selection {
+            PageSelection::All => Some((0..self.pages.len()).collect()),
+            PageSelection::Range => parse_page_range(&self.page_range, self.pages.len()),
+            PageSelection::Selected => Some(self.selected_pages.clone()),
+            PageSelection::Changed => Some(self.changed_pages.clone()),
+        }
+    }
+
+    // Lists the print issues on the pages being exported. Clicking one closes the export to show
+    // the layer on its page.
+    fn print_check_ui(&mut self, ui: &mut egui::Ui) {
+        let page_indices: BTreeSet<usize> = self
+            .page_indices()
+            .unwrap_or_default()
+            .into_iter()
+            .collect();
+        let issues: Vec<&PrintIssue> = self
+            .print_issues
+            .iter()
+            .filter(|issue| page_indices.contains(&issue.page))
+            .collect();
+
+        ui.add_space(10.0);
+
+        if issues.is_empty() {
+            ui.label("Text and lines are large enough to print and clear of the trim edge");
+            return;
+        }
+
+        ui.label(format!(
+            "{} print issue(s) found, click one to show it on the page:",
+            issues.len()
+        ));
+
+        ScrollArea::vertical()
+            .id_salt("book_export_print_issues")
+            .max_height(150.0)
+            .show(ui, |ui| {
+                for issue in issues {
+                    if ui
+                        .link(
+                            RichText::new(format!("Page {}: {}", issue.page + 1, issue.message))
+                                .color(theme::color::WARNING),
+                        )
+                        .clicked()
+                    {
+                        if let Ok(mut show_layer) = self.show_layer.lock() {
+                            *show_layer = Some((issue.page, issue.layer));
+                        }
+                        self.issue_picked = true;
+                    }
+                }
+            });
+    }
+
+    fn set_vendor(&mut self, vendor: Option<BookVendor>) {
+        self.vendor = vendor;
+        self.violations = match vendor {
+            Some(vendor) => vendor.validate(&self.pages),
+            None => vec![],
+        };
+    }
+}
+
+impl Modal for BookExportModal {
+    fn title(&self) -> String {
+        "Export".to_string()
+    }
+
+    fn body_ui(&mut self, ui: &mut egui::Ui) {
+        let vendor_name = |vendor: Option<BookVendor>| match vendor {
+            Some(vendor) => vendor.to_string(),
+            None => "None".to_string(),
+        };
+
+        let mut selected_vendor = self.vendor;
+
+        Grid::new("book_export_settings")
+            .num_columns(2)
+            .spacing([20.0, 5.0])
+            .show(ui, |ui| {
+                ui.label("Vendor Preset:");
+                ComboBox::from_id_salt("book_export_vendor")
+                    .selected_text(vendor_name(selected_vendor))
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut selected_vendor, None, vendor_name(None));
+                        for vendor in BookVendor::iter() {
+                            ui.selectable_value(
+                                &mut selected_vendor,
+                                Some(vendor),
+                                vendor_name(Some(vendor)),
+                            );
+                        }
+                    });
+                ui.end_row();
+
+                ui.label("Pages:");
+                ui.horizontal(|ui| {
+                    ComboBox::from_id_salt("book_export_pages")
+                        .selected_text(self.page_selection.to_string())
+                        .show_ui(ui, |ui| {
+                            for selection in PageSelection::iter() {
+                                ui.selectable_value(
+                                    &mut self.page_selection,
+                                    selection,
+                                    selection.to_string(),
+                                );
+                            }
+                        });
+
+                    if self.page_selection == PageSelection::Range {
+                        ui.add(
+                            TextEdit::singleline(&mut self.page_range)
+                                .hint_text("e.g. 3-10")
+                                .desired_width(100.0),
+                        );
+                    }
+                });
+                ui.end_row();
+
+                ui.label("");
+                match self.page_indices() {
+                    Some(indices) if !indices.is_empty() => {
+                        ui.label(format!("{} of {} pages", indices.len(), self.pages.len()));
+                    }
+                    Some(_) => {
+                        ui.label(RichText::new("No pages to export").color(theme::color::WARNING));
+                    }
+                    None => {
+                        ui.label(
+                            RichText::new(format!(
+                                "Enter page numbers from 1 to {}, like 3-10 or 1, 4-6",
+                                self.pages.len()
+                            ))
+                            .color(theme::color::WARNING),
+                        );
+                    }
+                }
+                ui.end_row();
+
+                sharpening_ui(ui, "book_export_sharpening", &mut self.sharpening);
+
+                if let Some(vendor) = self.vendor {
+                    let spec = vendor.spec();
+
+                    ui.label("Resolution:");
+                    ui.label(format!("{} PPI", spec.ppi));
+                    ui.end_row();
+
+                    ui.label("Bleed:");
+                    ui.label(format!("{:.1} mm", spec.bleed_mm));
+                    ui.end_row();
+
+                    ui.label("Spine Width:");
+                    ui.label(format!("{:.1} mm", vendor.spine_width_mm(self.pages.len())));
+                    ui.end_row();
+
+                    if let Some(cover_spec) = vendor.cover_spec(&self.pages) {
+                        ui.label("Cover Size:");
+                        ui.label(format!(
+                            "{:.1} × {:.1} mm",
+                            cover_spec.cover_width_mm, cover_spec.cover_height_mm
+                        ));
+                        ui.end_row();
+                    }
+                }
+            });
+
+        if selected_vendor != self.vendor {
+            self.set_vendor(selected_vendor);
+        }
+
+        // Checklist items left on the pages being exported, easy to forget before ordering
+        let pages_with_open_tasks: Vec<String> = self
+            .page_indices()
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|index| {
+                self.pages
+                    .get(*index)
+                    .is_some_and(|page| page.notes.open_task_count() > 0)
+            })
+            .map(|index| (index + 1).to_string())
+            .collect();
+        if !pages_with_open_tasks.is_empty() {
+            ui.add_space(10.0);
+            ui.label(
+                RichText::new(format!(
+                    "Open checklist items on page(s) {}",
+                    pages_with_open_tasks.join(", ")
+                ))
+                .color(theme::color::WARNING),
+            );
+        }
+
+        self.print_check_ui(ui);
+
+        if self.vendor.is_none() {
+            return;
+        }
+
+        ui.add_space(10.0);
+
+        if self.violations.is_empty() {
+            ui.label("All pages meet the vendor's specifications");
+            return;
+        }
+
+        ui.label(format!(
+            "{} issue(s) found, the export may be rejected by the printer:",
+            self.violations.len()
+        ));
+
+        ScrollArea::vertical()
+            .id_salt("book_export_violations")
+            .max_height(200.0)
+            .show(ui, |ui| {
+                for violation in &self.violations {
+                    let location = match violation.page {
+                        Some(page) => format!("Page {}", page + 1),
+                        None => "Book".to_string(),
+                    };
+                    ui.label(
+                        RichText::new(format!("{}: {}", location, violation.message))
+                            .color(theme::color::WARNING),
+                    );
+                }
+            });
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn actions_ui(&mut self, ui: &mut egui::Ui) -> ModalActionResponse {
+        if ui.button("Cancel").clicked() {
+            return ModalActionResponse::Cancel;
+        }
+
+        // A print issue was picked to fix first
+        if self.issue_picked {
+            return ModalActionResponse::Cancel;
+        }
+
+        let page_indices = self.page_indices().unwrap_or_default();
+
+        if ui
+            .add_enabled(!page_indices.is_empty(), Button::new("Export"))
+            .clicked()
+        {
+            let export_path = native_dialog::FileDialog::new()
+                .set_filename("export.pdf")
+                .show_save_single_file();
+
+            match export_path {
+                Ok(Some(export_path)) => {
+                    let directory = export_path.parent().unwrap();
+                    let file_name = export_path.file_name().unwrap();
+
+                    let exporter: Singleton<Exporter> = Dependency::get();
+                    exporter.with_lock_mut(|exporter| {
+                        exporter.export(
+                            ui.ctx().clone(),
+                            self.pages.clone(),
+                            page_indices,
+                            directory.into(),
+                            file_name.to_str().unwrap(),
+                            self.vendor,
+                            self.sharpening,
+                        );
+                    });
+                    return ModalActionResponse::Confirm;
+                }
+                Err(e) => {
+                    error!("Error opening export file dialog: {:?}", e);
+                }
+                Ok(None) => {
+                    info!("No export directory selected");
+                }
+            }
+        }
+
+        ModalActionResponse::None
+    }
+}
+
+/// Grid rows for picking the sharpening applied to photos as they're exported
+pub(super) fn sharpening_ui(
+    ui: &mut egui::Ui,
+    id_salt: &str,
+    sharpening: &mut Option<OutputSharpening>,
+) {
+    let sharpening_name = |sharpening: &Option<OutputSharpening>| match sharpening {
+        Some(settings) => settings
+            .preset()
+            .map_or("Custom".to_string(), |preset| preset.to_string()),
+        None => "Off".to_string(),
+    };
+
+    ui.label("Sharpening:");
+    ComboBox::from_id_salt(id_salt)
+        .selected_text(sharpening_name(sharpening))
+        .show_ui(ui, |ui| {
+            if ui.selectable_label(sharpening.is_none(), "Off").clicked() {
+                *sharpening = None;
+            }
+            for preset in SharpeningPreset::iter() {
+                let selected = sharpening.is_some_and(|settings| settings.preset() == Some(preset));
+                if ui
+                    .selectable_label(selected, preset.to_string())
+                    .on_hover_text(preset.description())
+                    .clicked()
+                {
+                    *sharpening = Some(preset.settings());
+                }
+            }
+        });
+    ui.end_row();
+
+    let Some(settings) = sharpening else {
+        return;
+    };
+
+    ui.label("Amount:");
+    ui.add(
+        DragValue::new(&mut settings.amount)
+            .range(0.0..=2.0)
+            .speed(0.01),
+    );
+    ui.end_row();
+
+    ui.label("Radius:");
+    ui.add(
+        DragValue::new(&mut settings.radius)
+            .range(0.3..=3.0)
+            .speed(0.01)
+            .suffix(" px"),
+    );
+    ui.end_row();
+
+    ui.label("Denoise:");
+    ui.add(
+        DragValue::new(&mut settings.denoise)
+            .range(0.0..=1.0)
+            .speed(0.01),
+    )
+    .on_hover_text("Smooths out grain before sharpening so it isn't made stronger");
+    ui.end_row();
+}
+
+// Reads page numbers counted from 1, like "3-10" or "1, 4-6", into sorted page indices
+fn parse_page_range(text: &str, page_count: usize) -> Option<Vec<usize>> {
+    let mut indices = BTreeSet::new();
+
+    for part in text
+        .split(',')
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+    {
+        let (start, end) = match part.split_once('-') {
+            Some((start, end)) => (
+                start.trim().parse::<usize>().ok()?,
+                end.trim().parse().ok()?,
+            ),
+            None => {
+                let page = part.parse::<usize>().ok()?;
+                (page, page)
+            }
+        };
+
+        if start == 0 || start > end || end > page_count {
+            return None;
+        }
+
+        indices.extend(start - 1..end);
+    }
+
+    if indices.is_empty() {
+        return None;
+    }
+
+    Some(indices.into_iter().collect())
+}
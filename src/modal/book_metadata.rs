@@ -0,0 +1,68 @@
+use egui::{Grid, RichText, TextEdit};
+use strum::IntoEnumIterator;
+
+use crate::{
+    book_metadata::{BookMetadata, TextVariable},
+    dependencies::{Dependency, Singleton, SingletonFor},
+    project_settings::ProjectSettingsManager,
+};
+
+use super::{Modal, ModalActionResponse};
+
+#[derive(Debug, Clone)]
+pub struct BookMetadataModal {
+    metadata: BookMetadata,
+}
+
+impl BookMetadataModal {
+    pub fn new() -> Self {
+        Self {
+            metadata: BookMetadata::current(),
+        }
+    }
+}
+
+impl Modal for BookMetadataModal {
+    fn title(&self) -> String {
+        "Book Details".to_string()
+    }
+
+    fn body_ui(&mut self, ui: &mut egui::Ui) {
+        ui.label("Text layers show these wherever their variable is used.");
+
+        Grid::new("book_metadata")
+            .num_columns(3)
+            .spacing([10.0, 5.0])
+            .show(ui, |ui| {
+                for variable in TextVariable::iter().filter(TextVariable::is_book_wide) {
+                    ui.label(format!("{}:", variable));
+                    ui.add(
+                        TextEdit::singleline(self.metadata.value_mut(variable))
+                            .desired_width(240.0),
+                    );
+                    ui.label(RichText::new(variable.token()).monospace().weak());
+                    ui.end_row();
+                }
+            });
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn actions_ui(&mut self, ui: &mut egui::Ui) -> ModalActionResponse {
+        if ui.button("Cancel").clicked() {
+            return ModalActionResponse::Cancel;
+        }
+
+        if ui.button("Save").clicked() {
+            let project_settings_manager: Singleton<ProjectSettingsManager> = Dependency::get();
+            project_settings_manager.with_lock_mut(|project_settings_manager| {
+                project_settings_manager.project_settings.metadata = self.metadata.clone();
+            });
+            return ModalActionResponse::Confirm;
+        }
+
+        ModalActionResponse::None
+    }
+}
@@ -0,0 +1,117 @@
+use std::sync::{Arc, Mutex};
+
+use egui::{Grid, RichText, ScrollArea};
+
+use crate::book_text::BookTextChange;
+
+use super::{Modal, ModalActionResponse};
+
+pub type BookTextImportResult = Arc<Mutex<Option<Vec<BookTextChange>>>>;
+
+/// Shows the corrections in an imported book text file side by side with the current text so
+/// they can be checked, and unwanted ones left out, before they're applied
+#[derive(Debug, Clone)]
+pub struct BookTextImportModal {
+    // Each change and whether it will be applied
+    changes: Vec<(BookTextChange, bool)>,
+    unmatched: usize,
+    result: BookTextImportResult,
+}
+
+impl BookTextImportModal {
+    pub fn new(
+        changes: Vec<BookTextChange>,
+        unmatched: usize,
+        result: BookTextImportResult,
+    ) -> Self {
+        Self {
+            changes: changes.into_iter().map(|change| (change, true)).collect(),
+            unmatched,
+            result,
+        }
+    }
+}
+
+impl Modal for BookTextImportModal {
+    fn title(&self) -> String {
+        "Import Book Text".to_string()
+    }
+
+    fn body_ui(&mut self, ui: &mut egui::Ui) {
+        if self.changes.is_empty() {
+            ui.label("The text in the file matches the book");
+        } else {
+            ui.label(format!("{} text layers changed", self.changes.len()));
+        }
+
+        if self.unmatched > 0 {
+            ui.label(
+                RichText::new(format!(
+                    "{} entries don't match a text layer in the book and will be skipped",
+                    self.unmatched
+                ))
+                .color(ui.visuals().warn_fg_color),
+            );
+        }
+
+        if self.changes.is_empty() {
+            return;
+        }
+
+        ui.add_space(10.0);
+
+        ScrollArea::vertical().max_height(400.0).show(ui, |ui| {
+            Grid::new("book_text_import_changes")
+                .num_columns(4)
+                .striped(true)
+                .spacing([20.0, 5.0])
+                .show(ui, |ui| {
+                    ui.label("");
+                    ui.label(RichText::new("Layer").strong());
+                    ui.label(RichText::new("Before").strong());
+                    ui.label(RichText::new("After").strong());
+                    ui.end_row();
+
+                    for (change, apply) in &mut self.changes {
+                        ui.checkbox(apply, "");
+                        ui.label(format!("Page {}: {}", change.page, change.layer));
+                        ui.add(egui::Label::new(RichText::new(&change.before).weak()).wrap());
+                        ui.add(egui::Label::new(change.after.as_str()).wrap());
+                        ui.end_row();
+                    }
+                });
+        });
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn actions_ui(&mut self, ui: &mut egui::Ui) -> ModalActionResponse {
+        if ui.button("Cancel").clicked() {
+            return ModalActionResponse::Cancel;
+        }
+
+        let selected: Vec<BookTextChange> = self
+            .changes
+            .iter()
+            .filter(|(_, apply)| *apply)
+            .map(|(change, _)| change.clone())
+            .collect();
+
+        if ui
+            .add_enabled(
+                !selected.is_empty(),
+                egui::Button::new(format!("Apply {}", selected.len())),
+            )
+            .clicked()
+        {
+            if let Ok(mut result) = self.result.lock() {
+                *result = Some(selected);
+            }
+            return ModalActionResponse::Confirm;
+        }
+
+        ModalActionResponse::None
+    }
+}
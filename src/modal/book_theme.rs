@@ -0,0 +1,147 @@
+use egui::{ComboBox, DragValue, Grid, Sense, Vec2};
+use strum::IntoEnumIterator;
+
+use crate::{
+    book_theme::{BookTheme, ThemeColorRole, ThemeFontRole},
+    dependencies::{Dependency, Singleton, SingletonFor},
+    font_manager::FontManager,
+    project_settings::ProjectSettingsManager,
+};
+
+use super::{Modal, ModalActionResponse};
+
+const STROKE_PREVIEW_SIZE: Vec2 = Vec2::new(60.0, 16.0);
+
+#[derive(Debug, Clone)]
+pub struct BookThemeModal {
+    theme: BookTheme,
+}
+
+impl BookThemeModal {
+    pub fn new() -> Self {
+        let theme = Dependency::<ProjectSettingsManager>::get()
+            .with_lock(|settings| settings.project_settings.theme.clone());
+        Self { theme }
+    }
+
+    fn font_combo_box(ui: &mut egui::Ui, role: ThemeFontRole, font: &mut Option<String>) {
+        let font_name = |font: &Option<String>| match font {
+            Some(font) => font.clone(),
+            None => "Default".to_string(),
+        };
+
+        let families =
+            Dependency::<FontManager>::get().with_lock(|font_manager| font_manager.family_names());
+
+        ComboBox::from_id_salt(("book_theme_font", role.to_string()))
+            .selected_text(font_name(font))
+            .show_ui(ui, |ui| {
+                ui.selectable_value(font, None, font_name(&None));
+
+                // Keep fonts from built-in themes that aren't installed selectable so they round trip
+                if let Some(current) = font.clone() {
+                    if !families.contains(&current) {
+                        ui.selectable_value(font, Some(current.clone()), current);
+                    }
+                }
+
+                for family in families {
+                    ui.selectable_value(font, Some(family.clone()), family);
+                }
+            });
+    }
+}
+
+impl Modal for BookThemeModal {
+    fn title(&self) -> String {
+        "Book Theme".to_string()
+    }
+
+    fn body_ui(&mut self, ui: &mut egui::Ui) {
+        ui.label("Text layers that use a theme font or color are restyled when the theme is saved");
+        ui.add_space(10.0);
+
+        Grid::new("book_theme_settings")
+            .num_columns(2)
+            .spacing([20.0, 5.0])
+            .show(ui, |ui| {
+                ui.label("Preset:");
+                ComboBox::from_id_salt("book_theme_preset")
+                    .selected_text(&self.theme.name)
+                    .show_ui(ui, |ui| {
+                        for theme in BookTheme::builtin() {
+                            if ui
+                                .selectable_label(self.theme.name == theme.name, &theme.name)
+                                .clicked()
+                            {
+                                self.theme = theme;
+                            }
+                        }
+                    });
+                ui.end_row();
+
+                ui.label("Name:");
+                ui.text_edit_singleline(&mut self.theme.name);
+                ui.end_row();
+
+                for role in ThemeFontRole::iter() {
+                    ui.label(format!("{} Font:", role));
+                    Self::font_combo_box(ui, role, self.theme.font_mut(role));
+                    ui.end_row();
+                }
+
+                for role in ThemeColorRole::iter() {
+                    ui.label(format!("{}:", role));
+                    ui.color_edit_button_srgba(self.theme.color_mut(role));
+                    ui.end_row();
+                }
+
+                ui.label("Stroke:");
+                ui.horizontal(|ui| {
+                    ui.add(
+                        DragValue::new(&mut self.theme.stroke.width)
+                            .range(0.0..=20.0)
+                            .speed(0.1)
+                            .suffix(" px"),
+                    );
+
+                    ComboBox::from_id_salt("book_theme_stroke_color")
+                        .selected_text(self.theme.stroke.color.to_string())
+                        .show_ui(ui, |ui| {
+                            for role in ThemeColorRole::iter() {
+                                ui.selectable_value(
+                                    &mut self.theme.stroke.color,
+                                    role,
+                                    role.to_string(),
+                                );
+                            }
+                        });
+
+                    let (rect, _) = ui.allocate_exact_size(STROKE_PREVIEW_SIZE, Sense::hover());
+                    ui.painter()
+                        .hline(rect.x_range(), rect.center().y, self.theme.stroke());
+                });
+                ui.end_row();
+            });
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn actions_ui(&mut self, ui: &mut egui::Ui) -> ModalActionResponse {
+        if ui.button("Cancel").clicked() {
+            return ModalActionResponse::Cancel;
+        }
+
+        if ui.button("Save").clicked() {
+            let project_settings_manager: Singleton<ProjectSettingsManager> = Dependency::get();
+            project_settings_manager.with_lock_mut(|project_settings_manager| {
+                project_settings_manager.project_settings.theme = self.theme.clone();
+            });
+            return ModalActionResponse::Confirm;
+        }
+
+        ModalActionResponse::None
+    }
+}
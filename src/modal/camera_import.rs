@@ -0,0 +1,455 @@
+use std::{
+    collections::HashSet,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+use egui::{Align2, Color32, ComboBox, FontId, Image, RichText, ScrollArea, Sense, Stroke, Vec2};
+use log::{error, info};
+use tokio::task::spawn_blocking;
+
+use crate::{
+    auto_persisting::AutoPersisting,
+    camera_import::{self, CameraFile, CameraVolume},
+    config::{Config, ConfigModification},
+    dependencies::{Dependency, Singleton, SingletonFor},
+    theme,
+};
+
+use super::{Modal, ModalActionResponse};
+
+const THUMBNAIL_SIZE: f32 = 96.0;
+const GRID_MAX_HEIGHT: f32 = 360.0;
+const GRID_WIDTH: f32 = 620.0;
+
+/// Copies photos off a camera or memory card into a folder and adds them to the library
+pub struct CameraImportModal {
+    volumes: Vec<CameraVolume>,
+    // Written by the thread looking for cameras, taken once it's done
+    volumes_result: Arc<Mutex<Option<Vec<CameraVolume>>>>,
+    dcim: Option<PathBuf>,
+    // Written by the scanning thread, taken once it's done
+    scan_result: Arc<Mutex<Option<Vec<CameraFile>>>>,
+    files: Option<Vec<CameraFile>>,
+    // Files already copied to the destination, worked out again when the destination changes
+    imported: Option<HashSet<PathBuf>>,
+    selected: HashSet<PathBuf>,
+    // Everything new is selected once a scan's results are in
+    select_new: bool,
+    destination: Option<PathBuf>,
+    sort_by_date: bool,
+    new_only: bool,
+}
+
+impl CameraImportModal {
+    pub fn new() -> Self {
+        let destination = Dependency::<AutoPersisting<Config>>::get()
+            .with_lock_mut(|config| {
+                config
+                    .read()
+                    .ok()
+                    .and_then(|config| config.camera_import_destination().cloned())
+            })
+            .or_else(dirs::picture_dir);
+
+        let mut modal = Self {
+            volumes: Vec::new(),
+            volumes_result: Arc::new(Mutex::new(None)),
+            dcim: None,
+            scan_result: Arc::new(Mutex::new(None)),
+            files: None,
+            imported: None,
+            selected: HashSet::new(),
+            select_new: false,
+            destination,
+            sort_by_date: true,
+            new_only: true,
+        };
+
+        modal.refresh_volumes();
+        modal
+    }
+
+    // Looking through the mounted volumes can stall on a slow or sleeping drive so it's kept off
+    // the UI thread
+    fn refresh_volumes(&mut self) {
+        let volumes_result = Arc::new(Mutex::new(None));
+        self.volumes_result = volumes_result.clone();
+
+        spawn_blocking(move || {
+            *volumes_result.lock().unwrap() = Some(camera_import::detect_volumes());
+        });
+    }
+
+    fn update_volumes(&mut self) {
+        let Some(volumes) = self.volumes_result.lock().unwrap().take() else {
+            return;
+        };
+        self.volumes = volumes;
+
+        let still_mounted = self
+            .volumes
+            .iter()
+            .any(|volume| Some(&volume.dcim) == self.dcim.as_ref());
+
+        if !still_mounted {
+            match self.volumes.first().map(|volume| volume.dcim.clone()) {
+                Some(dcim) => self.scan(dcim),
+                None => {
+                    self.dcim = None;
+                    self.files = Some(Vec::new());
+                }
+            }
+        }
+    }
+
+    // Reading the thumbnails off a card can take a while so it's kept off the UI thread
+    fn scan(&mut self, dcim: PathBuf) {
+        self.dcim = Some(dcim.clone());
+        self.files = None;
+        self.imported = None;
+        self.selected.clear();
+        self.select_new = true;
+
+        let scan_result = Arc::new(Mutex::new(None));
+        self.scan_result = scan_result.clone();
+
+        spawn_blocking(move || {
+            let files = camera_import::scan_dcim(&dcim);
+            info!("Found {} photos in {:?}", files.len(), dcim);
+            *scan_result.lock().unwrap() = Some(files);
+        });
+    }
+
+    fn set_destination(&mut self, destination: PathBuf) {
+        self.destination = Some(destination);
+        self.imported = None;
+    }
+
+    fn update_imported(&mut self) {
+        let Some(files) = &self.files else {
+            return;
+        };
+
+        if self.imported.is_some() {
+            return;
+        }
+
+        let imported: HashSet<PathBuf> = match &self.destination {
+            Some(destination) => files
+                .iter()
+                .filter(|file| file.is_imported(destination, self.sort_by_date))
+                .map(|file| file.path.clone())
+                .collect(),
+            None => HashSet::new(),
+        };
+
+        if self.select_new {
+            self.select_new = false;
+            self.selected = files
+                .iter()
+                .filter(|file| !imported.contains(&file.path))
+                .map(|file| file.path.clone())
+                .collect();
+        } else if self.new_only {
+            self.selected.retain(|path| !imported.contains(path));
+        }
+
+        self.imported = Some(imported);
+    }
+
+    fn visible_files(&self) -> Vec<&CameraFile> {
+        let Some(files) = &self.files else {
+            return Vec::new();
+        };
+
+        files
+            .iter()
+            .filter(|file| !self.new_only || !self.is_imported(file))
+            .collect()
+    }
+
+    fn is_imported(&self, file: &CameraFile) -> bool {
+        self.imported
+            .as_ref()
+            .is_some_and(|imported| imported.contains(&file.path))
+    }
+
+    fn selected_files(&self) -> Vec<CameraFile> {
+        self.visible_files()
+            .into_iter()
+            .filter(|file| self.selected.contains(&file.path))
+            .cloned()
+            .collect()
+    }
+
+    fn thumbnail_uri(file: &CameraFile) -> String {
+        format!("bytes://camera_import/{}", file.path.display())
+    }
+
+    // The thumbnails are only needed while the modal is open
+    fn forget_thumbnails(&self, ctx: &egui::Context) {
+        for file in self.files.iter().flatten() {
+            ctx.forget_image(&Self::thumbnail_uri(file));
+        }
+    }
+
+    fn file_ui(&mut self, ui: &mut egui::Ui, file: &CameraFile) {
+        let (rect, response) = ui.allocate_exact_size(Vec2::splat(THUMBNAIL_SIZE), Sense::click());
+
+        // Only thumbnails that have been scrolled into view are decoded
+        if ui.is_rect_visible(rect) {
+            let image_rect = rect.shrink(4.0);
+            match &file.thumbnail {
+                Some(thumbnail) => {
+                    Image::from_bytes(Self::thumbnail_uri(file), thumbnail.clone())
+                        .maintain_aspect_ratio(true)
+                        .paint_at(ui, image_rect);
+                }
+                None => {
+                    ui.painter().rect_filled(
+                        image_rect,
+                        2.0,
+                        theme::color::placeholder(ui.visuals()),
+                    );
+                    ui.painter().text(
+                        image_rect.center(),
+                        Align2::CENTER_CENTER,
+                        file.file_name(),
+                        FontId::proportional(10.0),
+                        ui.visuals().text_color(),
+                    );
+                }
+            }
+
+            if self.is_imported(file) {
+                ui.painter().text(
+                    image_rect.left_bottom() + Vec2::new(4.0, -4.0),
+                    Align2::LEFT_BOTTOM,
+                    "Imported",
+                    FontId::proportional(10.0),
+                    Color32::WHITE,
+                );
+            }
+
+            if self.selected.contains(&file.path) {
+                ui.painter().rect_stroke(
+                    rect.shrink(1.0),
+                    2.0,
+                    Stroke::new(2.0, theme::color::focused(ui.visuals())),
+                );
+            }
+        }
+
+        let response = response.on_hover_text(file.file_name());
+        if response.clicked() && !self.selected.remove(&file.path) {
+            self.selected.insert(file.path.clone());
+        }
+    }
+}
+
+impl Modal for CameraImportModal {
+    fn title(&self) -> String {
+        "Import from Camera".to_string()
+    }
+
+    fn body_ui(&mut self, ui: &mut egui::Ui) {
+        self.update_volumes();
+        if self.files.is_none() {
+            self.files = self.scan_result.lock().unwrap().take();
+        }
+        self.update_imported();
+
+        ui.set_width(GRID_WIDTH);
+
+        ui.horizontal(|ui| {
+            ui.label("Camera:");
+
+            let selected_text = self
+                .volumes
+                .iter()
+                .find(|volume| Some(&volume.dcim) == self.dcim.as_ref())
+                .map(|volume| volume.name.clone())
+                .or_else(|| self.dcim.as_ref().map(|dcim| dcim.display().to_string()))
+                .unwrap_or_else(|| "None found".to_string());
+
+            let mut chosen = None;
+            ComboBox::from_id_salt("camera_import_volume")
+                .selected_text(selected_text)
+                .show_ui(ui, |ui| {
+                    for volume in &self.volumes {
+                        let selected = Some(&volume.dcim) == self.dcim.as_ref();
+                        if ui.selectable_label(selected, &volume.name).clicked() && !selected {
+                            chosen = Some(volume.dcim.clone());
+                        }
+                    }
+                });
+
+            if ui.button("Refresh").clicked() {
+                self.refresh_volumes();
+            }
+
+            if ui
+                .button("Browse...")
+                .on_hover_text("Choose a card or folder that wasn't found")
+                .clicked()
+            {
+                match native_dialog::FileDialog::new().show_open_single_dir() {
+                    Ok(Some(folder)) => {
+                        chosen = Some(camera_import::find_dcim(&folder).unwrap_or(folder));
+                    }
+                    Ok(None) => {}
+                    Err(err) => error!("Error opening camera folder dialog: {:?}", err),
+                }
+            }
+
+            if let Some(dcim) = chosen {
+                self.scan(dcim);
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("Copy to:");
+            ui.label(
+                self.destination
+                    .as_ref()
+                    .map(|destination| destination.display().to_string())
+                    .unwrap_or_else(|| "No folder chosen".to_string()),
+            );
+
+            if ui.button("Choose...").clicked() {
+                match native_dialog::FileDialog::new().show_open_single_dir() {
+                    Ok(Some(destination)) => self.set_destination(destination),
+                    Ok(None) => {}
+                    Err(err) => error!("Error opening destination dialog: {:?}", err),
+                }
+            }
+        });
+
+        if ui
+            .checkbox(&mut self.sort_by_date, "Sort into folders by date")
+            .changed()
+        {
+            self.imported = None;
+        }
+
+        ui.separator();
+
+        let Some(num_files) = self.files.as_ref().map(|files| files.len()) else {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.label("Reading photos...");
+            });
+            return;
+        };
+
+        if self.dcim.is_none() {
+            ui.label("Connect a camera or memory card, then refresh");
+            return;
+        }
+
+        ui.horizontal(|ui| {
+            if ui.button("Select All").clicked() {
+                self.selected = self
+                    .visible_files()
+                    .iter()
+                    .map(|file| file.path.clone())
+                    .collect();
+            }
+
+            if ui.button("Select None").clicked() {
+                self.selected.clear();
+            }
+
+            if ui
+                .checkbox(&mut self.new_only, "New Only")
+                .on_hover_text("Hides photos that are already in the destination")
+                .changed()
+                && self.new_only
+            {
+                let imported = self.imported.clone().unwrap_or_default();
+                self.selected.retain(|path| !imported.contains(path));
+            }
+
+            let num_imported = self.imported.as_ref().map_or(0, |imported| imported.len());
+            ui.label(
+                RichText::new(format!(
+                    "{} photos, {} already imported",
+                    num_files, num_imported
+                ))
+                .weak(),
+            );
+        });
+
+        ScrollArea::vertical()
+            .max_height(GRID_MAX_HEIGHT)
+            .auto_shrink([false, true])
+            .show(ui, |ui| {
+                let visible: Vec<CameraFile> = self.visible_files().into_iter().cloned().collect();
+                if visible.is_empty() {
+                    ui.label("No new photos");
+                    return;
+                }
+
+                ui.horizontal_wrapped(|ui| {
+                    ui.spacing_mut().item_spacing = Vec2::splat(4.0);
+                    for file in &visible {
+                        self.file_ui(ui, file);
+                    }
+                });
+            });
+
+        if self.selected_files().iter().any(|file| file.is_raw()) {
+            ui.label(
+                RichText::new(
+                    "RAW and HEIC photos are copied with the rest, only JPEGs are added to the \
+                     library",
+                )
+                .weak(),
+            );
+        }
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn actions_ui(&mut self, ui: &mut egui::Ui) -> ModalActionResponse {
+        if ui.button("Cancel").clicked() {
+            self.forget_thumbnails(ui.ctx());
+            return ModalActionResponse::Cancel;
+        }
+
+        let selected_files = self.selected_files();
+        let import_label = match selected_files.len() {
+            1 => "Import 1 Photo".to_string(),
+            count => format!("Import {} Photos", count),
+        };
+
+        let Some(destination) = self.destination.clone() else {
+            ui.add_enabled(false, egui::Button::new(import_label));
+            return ModalActionResponse::None;
+        };
+
+        if ui
+            .add_enabled(!selected_files.is_empty(), egui::Button::new(import_label))
+            .clicked()
+        {
+            let config: Singleton<AutoPersisting<Config>> = Dependency::get();
+            config.with_lock_mut(|config| {
+                if let Err(err) = config.modify(ConfigModification::SetCameraImportDestination(
+                    destination.clone(),
+                )) {
+                    error!("Failed to save camera import destination: {:?}", err);
+                }
+            });
+
+            camera_import::import(selected_files, destination, self.sort_by_date);
+
+            self.forget_thumbnails(ui.ctx());
+            return ModalActionResponse::Confirm;
+        }
+
+        ModalActionResponse::None
+    }
+}
@@ -0,0 +1,147 @@
+use egui::{Grid, RichText, ScrollArea, Sense, Vec2};
+
+use crate::{
+    dependencies::{Dependency, SingletonFor},
+    project_settings::ProjectSettingsManager,
+    scene::canvas_scene::CanvasHistoryManager,
+    widget::canvas::{Canvas, CanvasState},
+};
+
+use super::{Modal, ModalActionResponse};
+
+const THUMBNAIL_SIZE: Vec2 = Vec2::new(220.0, 160.0);
+
+struct PageChange {
+    // Starting at 0
+    index: usize,
+    // None for pages added since the last export
+    before: Option<CanvasState>,
+    after: CanvasState,
+}
+
+/// Shows the pages that changed since the book was last exported next to how they looked then,
+/// to check what exporting again will change
+pub struct ExportChangesModal {
+    changes: Vec<PageChange>,
+    // Pages that were exported but are no longer in the book
+    removed: usize,
+}
+
+impl ExportChangesModal {
+    pub fn new<'a>(pages: impl ExactSizeIterator<Item = &'a CanvasState>) -> Self {
+        let page_count = pages.len();
+
+        Dependency::<ProjectSettingsManager>::get().with_lock(|manager| {
+            let settings = &manager.project_settings;
+
+            // Copies with their own widget ids so a page and its exported version can be shown
+            // side by side
+            let changes = pages
+                .enumerate()
+                .filter(|(index, page)| settings.changed_since_export(*index, page))
+                .map(|(index, page)| PageChange {
+                    index,
+                    before: settings
+                        .exported_pages
+                        .get(&index)
+                        .map(|exported| exported.page.clone_with_new_widget_ids()),
+                    after: page.clone_with_new_widget_ids(),
+                })
+                .collect();
+
+            Self {
+                changes,
+                removed: settings
+                    .exported_pages
+                    .keys()
+                    .filter(|index| **index >= page_count)
+                    .count(),
+            }
+        })
+    }
+
+    fn thumbnail(ui: &mut egui::Ui, page: Option<&mut CanvasState>) {
+        let (rect, _) = ui.allocate_exact_size(THUMBNAIL_SIZE, Sense::hover());
+
+        match page {
+            Some(page) => {
+                Canvas::new(page, rect, &mut CanvasHistoryManager::preview())
+                    .show_preview(ui, rect);
+            }
+            None => {
+                ui.painter().text(
+                    rect.center(),
+                    egui::Align2::CENTER_CENTER,
+                    "New page",
+                    egui::FontId::proportional(14.0),
+                    ui.visuals().weak_text_color(),
+                );
+            }
+        }
+    }
+}
+
+impl Modal for ExportChangesModal {
+    fn title(&self) -> String {
+        "Changes Since Last Export".to_string()
+    }
+
+    fn body_ui(&mut self, ui: &mut egui::Ui) {
+        if self.changes.is_empty() {
+            ui.label("No pages changed since the last export");
+        } else {
+            ui.label(format!(
+                "{} pages will look different when exported again",
+                self.changes.len()
+            ));
+        }
+
+        if self.removed > 0 {
+            ui.label(
+                RichText::new(format!(
+                    "{} previously exported pages are no longer in the book",
+                    self.removed
+                ))
+                .weak(),
+            );
+        }
+
+        if self.changes.is_empty() {
+            return;
+        }
+
+        ui.add_space(10.0);
+
+        ScrollArea::vertical().max_height(500.0).show(ui, |ui| {
+            Grid::new("export_changes")
+                .num_columns(3)
+                .striped(true)
+                .spacing([20.0, 10.0])
+                .show(ui, |ui| {
+                    ui.label("");
+                    ui.label(RichText::new("Last Export").strong());
+                    ui.label(RichText::new("Now").strong());
+                    ui.end_row();
+
+                    for change in &mut self.changes {
+                        ui.label(format!("Page {}", change.index + 1));
+                        Self::thumbnail(ui, change.before.as_mut());
+                        Self::thumbnail(ui, Some(&mut change.after));
+                        ui.end_row();
+                    }
+                });
+        });
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn actions_ui(&mut self, ui: &mut egui::Ui) -> ModalActionResponse {
+        if ui.button("Close").clicked() {
+            return ModalActionResponse::Cancel;
+        }
+
+        ModalActionResponse::None
+    }
+}
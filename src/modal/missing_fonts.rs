@@ -0,0 +1,189 @@
+use std::sync::{Arc, Mutex};
+
+use egui::{ComboBox, Grid, RichText, ScrollArea};
+
+use crate::{
+    dependencies::{Dependency, SingletonFor},
+    font_manager::FontManager,
+    project_fonts::MissingFont,
+};
+
+use super::{Modal, ModalActionResponse};
+
+// The font each missing one is replaced with, `None` being the default font
+pub type MissingFontsResult = Arc<Mutex<Option<Vec<(String, Option<String>)>>>>;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Replacement {
+    Keep,
+    Default,
+    Family(String),
+}
+
+impl Replacement {
+    fn name(&self) -> String {
+        match self {
+            Replacement::Keep => "Keep Missing".to_string(),
+            Replacement::Default => "Default".to_string(),
+            Replacement::Family(family) => family.clone(),
+        }
+    }
+}
+
+/// Lists the fonts the project uses that aren't installed along with the layers set in them, so
+/// each can be replaced with one that is
+#[derive(Debug, Clone)]
+pub struct MissingFontsModal {
+    missing: Vec<(MissingFont, Replacement)>,
+    result: MissingFontsResult,
+}
+
+impl MissingFontsModal {
+    pub fn new(missing: Vec<MissingFont>, result: MissingFontsResult) -> Self {
+        Self {
+            missing: missing
+                .into_iter()
+                .map(|font| (font, Replacement::Keep))
+                .collect(),
+            result,
+        }
+    }
+
+    fn usage(font: &MissingFont) -> String {
+        let mut pages: Vec<usize> = font.layers.iter().map(|layer| layer.page + 1).collect();
+        pages.dedup();
+
+        let mut usage = Vec::new();
+        match font.layers.len() {
+            0 => {}
+            1 => usage.push(format!("1 layer on page {}", pages[0])),
+            count => usage.push(format!(
+                "{} layers on {} {}",
+                count,
+                if pages.len() == 1 { "page" } else { "pages" },
+                pages
+                    .iter()
+                    .map(|page| page.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )),
+        }
+
+        for role in &font.theme_roles {
+            usage.push(format!(
+                "the {} font of the book theme",
+                role.to_string().to_lowercase()
+            ));
+        }
+
+        usage.join(" and ")
+    }
+}
+
+impl Modal for MissingFontsModal {
+    fn title(&self) -> String {
+        "Missing Fonts".to_string()
+    }
+
+    fn body_ui(&mut self, ui: &mut egui::Ui) {
+        ui.label(
+            "These fonts aren't installed on this computer. Text set in them is shown in the \
+             default font until they're installed or replaced.",
+        );
+
+        ui.add_space(10.0);
+
+        let families =
+            Dependency::<FontManager>::get().with_lock(|font_manager| font_manager.family_names());
+
+        ScrollArea::vertical().max_height(400.0).show(ui, |ui| {
+            Grid::new("missing_fonts")
+                .num_columns(3)
+                .striped(true)
+                .spacing([20.0, 5.0])
+                .show(ui, |ui| {
+                    ui.label(RichText::new("Font").strong());
+                    ui.label(RichText::new("Used By").strong());
+                    ui.label(RichText::new("Replace With").strong());
+                    ui.end_row();
+
+                    for (index, (font, replacement)) in self.missing.iter_mut().enumerate() {
+                        ui.label(&font.family);
+
+                        let layers = font
+                            .layers
+                            .iter()
+                            .map(|layer| format!("Page {}: {}", layer.page + 1, layer.layer_name))
+                            .collect::<Vec<_>>()
+                            .join("\n");
+                        let usage = ui.label(Self::usage(font));
+                        if !layers.is_empty() {
+                            usage.on_hover_text(layers);
+                        }
+
+                        ComboBox::from_id_salt(("missing_font_replacement", index))
+                            .selected_text(replacement.name())
+                            .show_ui(ui, |ui| {
+                                for option in [Replacement::Keep, Replacement::Default] {
+                                    let name = option.name();
+                                    ui.selectable_value(replacement, option, name);
+                                }
+
+                                for family in &families {
+                                    ui.selectable_value(
+                                        replacement,
+                                        Replacement::Family(family.clone()),
+                                        family,
+                                    );
+                                }
+                            });
+                        ui.end_row();
+                    }
+                });
+        });
+
+        ui.add_space(10.0);
+        ui.label(
+            RichText::new(
+                "Turn on Embed Fonts in the project settings to save the fonts with the project \
+                 so it opens the same on other computers.",
+            )
+            .weak(),
+        );
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn actions_ui(&mut self, ui: &mut egui::Ui) -> ModalActionResponse {
+        if ui.button("Close").clicked() {
+            return ModalActionResponse::Cancel;
+        }
+
+        let replacements: Vec<(String, Option<String>)> = self
+            .missing
+            .iter()
+            .filter_map(|(font, replacement)| match replacement {
+                Replacement::Keep => None,
+                Replacement::Default => Some((font.family.clone(), None)),
+                Replacement::Family(family) => Some((font.family.clone(), Some(family.clone()))),
+            })
+            .collect();
+
+        if ui
+            .add_enabled(
+                !replacements.is_empty(),
+                egui::Button::new(format!("Replace {}", replacements.len())),
+            )
+            .clicked()
+        {
+            if let Ok(mut result) = self.result.lock() {
+                *result = Some(replacements);
+            }
+            return ModalActionResponse::Confirm;
+        }
+
+        ModalActionResponse::None
+    }
+}
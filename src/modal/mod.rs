@@ -1,9 +1,30 @@
 use std::any::Any;
 
+pub mod animation_frame;
+pub mod auto_placement;
 pub mod basic;
+pub mod batch_caption;
+pub mod book_export;
+pub mod book_metadata;
+pub mod book_text_import;
+pub mod book_theme;
+pub mod camera_import;
+pub mod export_changes;
 pub mod manager;
+pub mod missing_fonts;
+pub mod new_project;
 pub mod page_settings;
+pub mod photo_filter;
 pub mod progress;
+pub mod project_archive;
+pub mod proof_export;
+pub mod psd_export;
+pub mod save_warning;
+pub mod save_workspace_layout;
+pub mod slideshow_export;
+pub mod smart_collection;
+pub mod svg_export;
+pub mod template_pack_import;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum ModalActionResponse {
@@ -0,0 +1,211 @@
+use std::{
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+use egui::{ComboBox, DragValue, Grid, RichText};
+use log::{error, info};
+
+use crate::{
+    auto_persisting::AutoPersisting,
+    config::{Config, DEFAULT_FIT_PAGE_MARGIN_MM},
+    dependencies::{Dependency, Singleton, SingletonFor},
+    model::{page::Page, page_preset::PagePreset, unit::Unit},
+};
+
+use super::{Modal, ModalActionResponse};
+
+const DEFAULT_PAGE_COUNT: usize = 20;
+
+const MAX_PAGE_COUNT: usize = 500;
+
+/// Everything picked in the wizard, applied by the scene once the modal is confirmed
+#[derive(Debug, Clone)]
+pub struct NewProject {
+    pub page: Page,
+    pub page_count: usize,
+    pub margin_mm: f32,
+    pub photo_folder: Option<PathBuf>,
+}
+
+pub type NewProjectResult = Arc<Mutex<Option<NewProject>>>;
+
+/// Walks through the basics of a book so a new project starts with pages and photos instead of an
+/// empty window
+#[derive(Debug, Clone)]
+pub struct NewProjectModal {
+    presets: Vec<PagePreset>,
+    selected_preset: usize,
+    page_count: usize,
+    // In the preset's unit
+    bleed: f32,
+    margin_mm: f32,
+    photo_folder: Option<PathBuf>,
+    result: NewProjectResult,
+}
+
+impl NewProjectModal {
+    pub fn new(result: NewProjectResult) -> Self {
+        let config: Singleton<AutoPersisting<Config>> = Dependency::get();
+        let (user_presets, margin_mm) = config.with_lock_mut(|config| {
+            config
+                .read()
+                .map(|config| (config.page_presets().to_vec(), config.fit_page_margin_mm()))
+                .unwrap_or_else(|_| (Vec::new(), DEFAULT_FIT_PAGE_MARGIN_MM))
+        });
+
+        let presets: Vec<PagePreset> = user_presets
+            .into_iter()
+            .chain(PagePreset::builtin())
+            .collect();
+        let bleed = presets.first().map(|preset| preset.bleed).unwrap_or(0.0);
+
+        Self {
+            presets,
+            selected_preset: 0,
+            page_count: DEFAULT_PAGE_COUNT,
+            bleed,
+            margin_mm,
+            photo_folder: None,
+            result,
+        }
+    }
+
+    fn preset(&self) -> Option<&PagePreset> {
+        self.presets.get(self.selected_preset)
+    }
+
+    fn page(&self) -> Page {
+        let mut page = self.preset().map(PagePreset::page).unwrap_or_default();
+        page.set_bleed(self.bleed);
+        page
+    }
+
+    fn choose_photo_folder(&mut self) {
+        match native_dialog::FileDialog::new().show_open_single_dir() {
+            Ok(Some(folder)) => self.photo_folder = Some(folder),
+            Ok(None) => info!("No photo folder selected"),
+            Err(e) => error!("Error opening photo folder dialog: {:?}", e),
+        }
+    }
+
+    fn unit_suffix(unit: Unit) -> &'static str {
+        match unit {
+            Unit::Pixels => " px",
+            Unit::Inches => " in",
+            Unit::Centimeters => " cm",
+        }
+    }
+}
+
+impl Modal for NewProjectModal {
+    fn title(&self) -> String {
+        "New Project".to_string()
+    }
+
+    fn body_ui(&mut self, ui: &mut egui::Ui) {
+        ui.label("Set up the book. Everything here can be changed later.");
+        ui.add_space(5.0);
+
+        Grid::new("new_project")
+            .num_columns(2)
+            .spacing([10.0, 8.0])
+            .show(ui, |ui| {
+                ui.label("Book Size:");
+                let previous_preset = self.selected_preset;
+                ComboBox::from_id_salt("new_project_size")
+                    .selected_text(
+                        self.preset()
+                            .map(|preset| preset.name.clone())
+                            .unwrap_or_default(),
+                    )
+                    .width(200.0)
+                    .show_ui(ui, |ui| {
+                        for (index, preset) in self.presets.iter().enumerate() {
+                            ui.selectable_value(&mut self.selected_preset, index, &preset.name)
+                                .on_hover_text(PagePreset::size_label(preset.size, preset.unit));
+                        }
+                    });
+                if self.selected_preset != previous_preset {
+                    self.bleed = self.preset().map(|preset| preset.bleed).unwrap_or(0.0);
+                }
+                ui.end_row();
+
+                ui.label("Pages:");
+                ui.add(DragValue::new(&mut self.page_count).range(1..=MAX_PAGE_COUNT));
+                ui.end_row();
+
+                let unit = self.page().unit();
+                ui.label("Bleed:");
+                ui.add(
+                    DragValue::new(&mut self.bleed)
+                        .range(0.0..=f32::MAX)
+                        .speed(0.01)
+                        .suffix(Self::unit_suffix(unit)),
+                );
+                ui.end_row();
+
+                ui.label("Margin:");
+                ui.add(
+                    DragValue::new(&mut self.margin_mm)
+                        .range(0.0..=100.0)
+                        .speed(0.5)
+                        .suffix(" mm"),
+                )
+                .on_hover_text("Space kept around photos fitted to the page");
+                ui.end_row();
+
+                ui.label("Photos:");
+                ui.horizontal(|ui| {
+                    match &self.photo_folder {
+                        Some(folder) => {
+                            ui.label(folder.display().to_string());
+                        }
+                        None => {
+                            ui.label(RichText::new("None").weak());
+                        }
+                    }
+
+                    if ui.button("Choose...").clicked() {
+                        self.choose_photo_folder();
+                    }
+
+                    if self.photo_folder.is_some() && ui.button("Clear").clicked() {
+                        self.photo_folder = None;
+                    }
+                });
+                ui.end_row();
+            });
+
+        if self.photo_folder.is_some() {
+            ui.label(
+                RichText::new("Photos in the folder are imported once the project is created")
+                    .weak(),
+            );
+        }
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn actions_ui(&mut self, ui: &mut egui::Ui) -> ModalActionResponse {
+        if ui.button("Skip").clicked() {
+            return ModalActionResponse::Cancel;
+        }
+
+        if ui.button("Create").clicked() {
+            if let Ok(mut result) = self.result.lock() {
+                *result = Some(NewProject {
+                    page: self.page(),
+                    page_count: self.page_count,
+                    margin_mm: self.margin_mm,
+                    photo_folder: self.photo_folder.clone(),
+                });
+            }
+            return ModalActionResponse::Confirm;
+        }
+
+        ModalActionResponse::None
+    }
+}
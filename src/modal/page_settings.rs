@@ -33,6 +33,7 @@ impl Modal for PageSettingsModal {
     }
 
     fn body_ui(&mut self, ui: &mut egui::Ui) {
+        ui.label("New pages in this project start with this size.");
         PageInfo::new(&mut PageInfoState::new(&mut self.editable_page)).show(ui);
     }
 
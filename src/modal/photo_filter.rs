@@ -0,0 +1,354 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use egui::{CollapsingHeader, DragValue, Grid, RichText, ScrollArea};
+
+use crate::{
+    dependencies::{Dependency, SingletonFor},
+    photo_database::ExifFacets,
+    photo_manager::PhotoManager,
+    widget::image_gallery::ImageGallery,
+};
+
+use super::{Modal, ModalActionResponse};
+
+/// An inclusive range of a numeric shooting detail, off until it's checked
+#[derive(Debug, Clone, Copy)]
+struct FacetRange {
+    enabled: bool,
+    min: f32,
+    max: f32,
+    // The smallest and largest values among the photos
+    bounds: (f32, f32),
+    decimals: usize,
+}
+
+impl FacetRange {
+    fn new(values: impl Iterator<Item = f32>, decimals: usize) -> Option<Self> {
+        let (min, max) = values.fold(None, |bounds: Option<(f32, f32)>, value| {
+            Some(bounds.map_or((value, value), |(min, max)| {
+                (min.min(value), max.max(value))
+            }))
+        })?;
+
+        Some(Self {
+            enabled: false,
+            min,
+            max,
+            bounds: (min, max),
+            decimals,
+        })
+    }
+
+    fn contains(&self, value: Option<f32>) -> bool {
+        !self.enabled || value.is_some_and(|value| self.min <= value && value <= self.max)
+    }
+
+    fn term(&self, field: &str) -> Option<String> {
+        self.enabled.then(|| {
+            format!(
+                "{}:{:.*}..{:.*}",
+                field, self.decimals, self.min, self.decimals, self.max
+            )
+        })
+    }
+}
+
+/// Narrows the gallery by camera, lens, ISO, aperture and focal length. The choices are added to
+/// the gallery search so they combine with rating and label filters already in it. Saved searches
+/// can be picked here too, or the filter saved as one.
+pub struct PhotoFilterModal {
+    // The shooting details of every photo, from the photo database's index
+    facets: Vec<ExifFacets>,
+    cameras: BTreeMap<String, usize>,
+    lenses: BTreeMap<String, usize>,
+    selected_cameras: BTreeSet<String>,
+    selected_lenses: BTreeSet<String>,
+    iso: Option<FacetRange>,
+    aperture: Option<FacetRange>,
+    focal_length: Option<FacetRange>,
+    // Set when a saved search was picked, the modal closes with it
+    picked_saved_search: bool,
+}
+
+impl PhotoFilterModal {
+    pub fn new() -> Self {
+        let facets: Vec<ExifFacets> =
+            Dependency::<PhotoManager>::get().with_lock(|photo_manager| {
+                photo_manager
+                    .photos
+                    .keys()
+                    .filter_map(|path| photo_manager.photo_database().facets(path).cloned())
+                    .collect()
+            });
+
+        let mut cameras = BTreeMap::new();
+        let mut lenses = BTreeMap::new();
+        for facet in &facets {
+            if let Some(camera) = &facet.camera {
+                *cameras.entry(camera.clone()).or_insert(0) += 1;
+            }
+            if let Some(lens) = &facet.lens {
+                *lenses.entry(lens.clone()).or_insert(0) += 1;
+            }
+        }
+
+        Self {
+            iso: FacetRange::new(
+                facets
+                    .iter()
+                    .filter_map(|facet| facet.iso.map(|iso| iso as f32)),
+                0,
+            ),
+            aperture: FacetRange::new(facets.iter().filter_map(|facet| facet.aperture), 1),
+            focal_length: FacetRange::new(facets.iter().filter_map(|facet| facet.focal_length), 1),
+            facets,
+            cameras,
+            lenses,
+            selected_cameras: BTreeSet::new(),
+            selected_lenses: BTreeSet::new(),
+            picked_saved_search: false,
+        }
+    }
+
+    fn matches(&self, facet: &ExifFacets) -> bool {
+        let in_set = |selected: &BTreeSet<String>, value: &Option<String>| {
+            selected.is_empty() || value.as_ref().is_some_and(|value| selected.contains(value))
+        };
+        let in_range = |range: &Option<FacetRange>, value: Option<f32>| {
+            range.as_ref().map_or(true, |range| range.contains(value))
+        };
+
+        in_set(&self.selected_cameras, &facet.camera)
+            && in_set(&self.selected_lenses, &facet.lens)
+            && in_range(&self.iso, facet.iso.map(|iso| iso as f32))
+            && in_range(&self.aperture, facet.aperture)
+            && in_range(&self.focal_length, facet.focal_length)
+    }
+
+    // The choices as search terms, cameras and lenses are matched on any of the selected ones
+    fn query(&self) -> String {
+        let any_of = |field: &str, values: &BTreeSet<String>| {
+            let terms: Vec<String> = values
+                .iter()
+                .map(|value| format!("{}:\"{}\"", field, value.replace('"', "")))
+                .collect();
+            match terms.len() {
+                0 => None,
+                1 => terms.into_iter().next(),
+                _ => Some(format!("({})", terms.join(" OR "))),
+            }
+        };
+
+        [
+            any_of("camera", &self.selected_cameras),
+            any_of("lens", &self.selected_lenses),
+            self.iso.and_then(|range| range.term("iso")),
+            self.aperture.and_then(|range| range.term("aperture")),
+            self.focal_length.and_then(|range| range.term("focal")),
+        ]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .join(" ")
+    }
+
+    // Added to whatever is already being searched for so it narrows it down
+    fn search(&self) -> String {
+        let query = self.query();
+        Dependency::<PhotoManager>::get().with_lock(|photo_manager| {
+            match (photo_manager.search_text().trim(), query.as_str()) {
+                (current, "") => current.to_string(),
+                ("", query) => query.to_string(),
+                (current, query) => format!("{} {}", current, query),
+            }
+        })
+    }
+
+    fn saved_searches_ui(&mut self, ui: &mut egui::Ui) {
+        CollapsingHeader::new("Saved Searches")
+            .id_salt("photo_filter_saved_searches")
+            .show(ui, |ui| {
+                let search = self.search();
+                let photo_manager = Dependency::<PhotoManager>::get();
+                if ImageGallery::saved_searches_ui(ui, &photo_manager, &search) {
+                    self.picked_saved_search = true;
+                }
+            });
+    }
+
+    fn values_ui(
+        ui: &mut egui::Ui,
+        id_salt: &str,
+        values: &BTreeMap<String, usize>,
+        selected: &mut BTreeSet<String>,
+    ) {
+        if values.is_empty() {
+            ui.label(RichText::new("None recorded").weak());
+            return;
+        }
+
+        ScrollArea::vertical()
+            .id_salt(id_salt)
+            .max_height(120.0)
+            .show(ui, |ui| {
+                for (value, count) in values {
+                    let mut checked = selected.contains(value);
+                    if ui
+                        .checkbox(&mut checked, format!("{} ({})", value, count))
+                        .changed()
+                    {
+                        if checked {
+                            selected.insert(value.clone());
+                        } else {
+                            selected.remove(value);
+                        }
+                    }
+                }
+            });
+    }
+
+    fn range_ui(
+        ui: &mut egui::Ui,
+        label: &str,
+        range: &mut Option<FacetRange>,
+        speed: f64,
+        count: impl Fn(&FacetRange) -> usize,
+    ) {
+        ui.label(format!("{}:", label));
+
+        let Some(range) = range else {
+            ui.label(RichText::new("None recorded").weak());
+            ui.end_row();
+            return;
+        };
+
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut range.enabled, "");
+            ui.add_enabled_ui(range.enabled, |ui| {
+                let (low, high) = range.bounds;
+                ui.add(
+                    DragValue::new(&mut range.min)
+                        .range(low..=range.max)
+                        .fixed_decimals(range.decimals)
+                        .speed(speed),
+                );
+                ui.label("to");
+                ui.add(
+                    DragValue::new(&mut range.max)
+                        .range(range.min..=high)
+                        .fixed_decimals(range.decimals)
+                        .speed(speed),
+                );
+            });
+
+            let mut in_range = *range;
+            in_range.enabled = true;
+            ui.label(RichText::new(format!("({})", count(&in_range))).weak());
+        });
+        ui.end_row();
+    }
+}
+
+impl Modal for PhotoFilterModal {
+    fn title(&self) -> String {
+        "Filter by Camera Settings".to_string()
+    }
+
+    fn body_ui(&mut self, ui: &mut egui::Ui) {
+        if self.facets.is_empty() {
+            ui.label("None of the photos have camera details");
+            ui.add_space(10.0);
+            self.saved_searches_ui(ui);
+            return;
+        }
+
+        ui.columns(2, |columns| {
+            columns[0].label(RichText::new("Camera").strong());
+            Self::values_ui(
+                &mut columns[0],
+                "photo_filter_cameras",
+                &self.cameras,
+                &mut self.selected_cameras,
+            );
+
+            columns[1].label(RichText::new("Lens").strong());
+            Self::values_ui(
+                &mut columns[1],
+                "photo_filter_lenses",
+                &self.lenses,
+                &mut self.selected_lenses,
+            );
+        });
+
+        ui.add_space(10.0);
+
+        let facets = &self.facets;
+        Grid::new("photo_filter_ranges")
+            .num_columns(2)
+            .spacing([20.0, 5.0])
+            .show(ui, |ui| {
+                Self::range_ui(ui, "ISO", &mut self.iso, 10.0, |range| {
+                    facets
+                        .iter()
+                        .filter(|facet| range.contains(facet.iso.map(|iso| iso as f32)))
+                        .count()
+                });
+                Self::range_ui(ui, "Aperture", &mut self.aperture, 0.1, |range| {
+                    facets
+                        .iter()
+                        .filter(|facet| range.contains(facet.aperture))
+                        .count()
+                });
+                Self::range_ui(ui, "Focal Length", &mut self.focal_length, 1.0, |range| {
+                    facets
+                        .iter()
+                        .filter(|facet| range.contains(facet.focal_length))
+                        .count()
+                });
+            });
+
+        ui.add_space(10.0);
+
+        let matching = self
+            .facets
+            .iter()
+            .filter(|facet| self.matches(facet))
+            .count();
+        ui.label(format!(
+            "{} of {} photos match",
+            matching,
+            self.facets.len()
+        ));
+
+        ui.add_space(10.0);
+        self.saved_searches_ui(ui);
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn actions_ui(&mut self, ui: &mut egui::Ui) -> ModalActionResponse {
+        // The saved search is already being searched for
+        if self.picked_saved_search {
+            return ModalActionResponse::Confirm;
+        }
+
+        if ui.button("Cancel").clicked() {
+            return ModalActionResponse::Cancel;
+        }
+
+        if ui
+            .add_enabled(!self.query().is_empty(), egui::Button::new("Apply"))
+            .clicked()
+        {
+            let search = self.search();
+            Dependency::<PhotoManager>::get()
+                .with_lock_mut(|photo_manager| photo_manager.set_search(search));
+
+            return ModalActionResponse::Confirm;
+        }
+
+        ModalActionResponse::None
+    }
+}
@@ -0,0 +1,102 @@
+use egui::DragValue;
+use log::{error, info};
+
+use crate::project::{
+    archive::{ArchiveOptions, ProjectArchive, ARCHIVE_EXTENSION},
+    v1::Project,
+};
+
+use super::{basic::BasicModal, manager::ModalManager, Modal, ModalActionResponse};
+
+const DEFAULT_MAX_PHOTO_DIMENSION: u32 = 3000;
+
+pub struct ProjectArchiveModal {
+    project: Project,
+    downscale_photos: bool,
+    max_photo_dimension: u32,
+}
+
+impl ProjectArchiveModal {
+    pub fn new(project: Project) -> Self {
+        Self {
+            project,
+            downscale_photos: false,
+            max_photo_dimension: DEFAULT_MAX_PHOTO_DIMENSION,
+        }
+    }
+}
+
+impl Modal for ProjectArchiveModal {
+    fn title(&self) -> String {
+        "Export Project Archive".to_string()
+    }
+
+    fn body_ui(&mut self, ui: &mut egui::Ui) {
+        ui.label("The project, its photos, assets and fonts are bundled into a single file");
+
+        ui.add_space(10.0);
+
+        ui.checkbox(&mut self.downscale_photos, "Downscale photos")
+            .on_hover_text("Only JPEGs are downscaled, other photos are archived as they are");
+        ui.add_enabled_ui(self.downscale_photos, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Longest side:");
+                ui.add(
+                    DragValue::new(&mut self.max_photo_dimension)
+                        .range(500..=10000)
+                        .suffix(" px"),
+                );
+            });
+        });
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn actions_ui(&mut self, ui: &mut egui::Ui) -> ModalActionResponse {
+        if ui.button("Cancel").clicked() {
+            return ModalActionResponse::Cancel;
+        }
+
+        if ui.button("Export").clicked() {
+            let export_path = native_dialog::FileDialog::new()
+                .add_filter("Project Archive", &[ARCHIVE_EXTENSION])
+                .set_filename(&format!("project.{}", ARCHIVE_EXTENSION))
+                .show_save_single_file();
+
+            match export_path {
+                Ok(Some(export_path)) => {
+                    let project = self.project.clone();
+                    let options = ArchiveOptions {
+                        max_photo_dimension: self
+                            .downscale_photos
+                            .then_some(self.max_photo_dimension),
+                    };
+
+                    // Copying every photo can take a while so it's kept off the UI thread
+                    std::thread::spawn(move || {
+                        if let Err(err) = ProjectArchive::export(project, &export_path, options) {
+                            error!("Error exporting project archive: {:?}", err);
+                            ModalManager::push(BasicModal::new(
+                                "Error",
+                                format!("Error exporting project archive: {}", err),
+                                "OK",
+                            ));
+                        }
+                    });
+
+                    return ModalActionResponse::Confirm;
+                }
+                Err(e) => {
+                    error!("Error opening project archive dialog: {:?}", e);
+                }
+                Ok(None) => {
+                    info!("No project archive path selected");
+                }
+            }
+        }
+
+        ModalActionResponse::None
+    }
+}
@@ -0,0 +1,205 @@
+use egui::{Button, ComboBox, DragValue, Grid, RichText, TextEdit};
+use log::{error, info};
+use strum::IntoEnumIterator;
+use strum_macros::{Display, EnumIter};
+
+use crate::{
+    dependencies::{Dependency, Singleton, SingletonFor},
+    export::{Exporter, ProofFormat, ProofSettings},
+    widget::canvas::CanvasState,
+};
+
+use super::{book_export::sharpening_ui, Modal, ModalActionResponse};
+
+#[derive(Debug, Clone, Copy, PartialEq, Display, EnumIter)]
+enum ProofPreset {
+    Email,
+    #[strum(to_string = "Screen Review")]
+    ScreenReview,
+}
+
+impl ProofPreset {
+    fn apply(&self, settings: &mut ProofSettings) {
+        let (long_edge, quality) = match self {
+            ProofPreset::Email => (1600, 70),
+            ProofPreset::ScreenReview => (2400, 85),
+        };
+        settings.long_edge = long_edge;
+        settings.quality = quality;
+    }
+
+    fn description(&self) -> &'static str {
+        match self {
+            ProofPreset::Email => "Small enough to attach to an email",
+            ProofPreset::ScreenReview => "Sharper pages for looking over on a large screen",
+        }
+    }
+}
+
+pub struct ProofExportModal {
+    pages: Vec<CanvasState>,
+    // Indices of the pages selected in the pages panel
+    selected_pages: Vec<usize>,
+    selected_only: bool,
+    settings: ProofSettings,
+}
+
+impl ProofExportModal {
+    pub fn new(pages: Vec<CanvasState>, selected_pages: Vec<usize>) -> Self {
+        Self {
+            pages,
+            selected_pages,
+            selected_only: false,
+            settings: ProofSettings::default(),
+        }
+    }
+
+    fn page_indices(&self) -> Vec<usize> {
+        if self.selected_only {
+            self.selected_pages.clone()
+        } else {
+            (0..self.pages.len()).collect()
+        }
+    }
+}
+
+impl Modal for ProofExportModal {
+    fn title(&self) -> String {
+        "Create Proof".to_string()
+    }
+
+    fn body_ui(&mut self, ui: &mut egui::Ui) {
+        ui.label(
+            "A small, watermarked copy of the book to send out for approval. Use Export for the \
+             print quality files.",
+        );
+
+        ui.add_space(10.0);
+
+        ui.horizontal(|ui| {
+            for preset in ProofPreset::iter() {
+                if ui
+                    .button(preset.to_string())
+                    .on_hover_text(preset.description())
+                    .clicked()
+                {
+                    preset.apply(&mut self.settings);
+                }
+            }
+        });
+
+        ui.add_space(10.0);
+
+        Grid::new("proof_export_settings")
+            .num_columns(2)
+            .spacing([20.0, 5.0])
+            .show(ui, |ui| {
+                ui.label("Format:");
+                ComboBox::from_id_salt("proof_export_format")
+                    .selected_text(self.settings.format.to_string())
+                    .show_ui(ui, |ui| {
+                        for format in ProofFormat::iter() {
+                            ui.selectable_value(
+                                &mut self.settings.format,
+                                format,
+                                format.to_string(),
+                            );
+                        }
+                    });
+                ui.end_row();
+
+                ui.label("Long Edge:");
+                ui.add(
+                    DragValue::new(&mut self.settings.long_edge)
+                        .range(400..=4000)
+                        .speed(10)
+                        .suffix(" px"),
+                );
+                ui.end_row();
+
+                ui.label("Quality:");
+                ui.add(DragValue::new(&mut self.settings.quality).range(1..=100));
+                ui.end_row();
+
+                ui.label("Watermark:");
+                ui.add(
+                    TextEdit::singleline(&mut self.settings.watermark)
+                        .hint_text("No watermark")
+                        .desired_width(150.0),
+                );
+                ui.end_row();
+
+                sharpening_ui(ui, "proof_export_sharpening", &mut self.settings.sharpening);
+
+                ui.label("Pages:");
+                ui.add_enabled(
+                    !self.selected_pages.is_empty(),
+                    egui::Checkbox::new(&mut self.selected_only, "Selected pages only"),
+                );
+                ui.end_row();
+            });
+
+        ui.add_space(10.0);
+        ui.label(
+            RichText::new(format!(
+                "{} of {} pages",
+                self.page_indices().len(),
+                self.pages.len()
+            ))
+            .weak(),
+        );
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn actions_ui(&mut self, ui: &mut egui::Ui) -> ModalActionResponse {
+        if ui.button("Cancel").clicked() {
+            return ModalActionResponse::Cancel;
+        }
+
+        let page_indices = self.page_indices();
+
+        if ui
+            .add_enabled(!page_indices.is_empty(), Button::new("Create Proof"))
+            .clicked()
+        {
+            let file_name = match self.settings.format {
+                ProofFormat::Pdf => "proof.pdf",
+                ProofFormat::Jpeg => "proof",
+            };
+            let export_path = native_dialog::FileDialog::new()
+                .set_filename(file_name)
+                .show_save_single_file();
+
+            match export_path {
+                Ok(Some(export_path)) => {
+                    let directory = export_path.parent().unwrap();
+                    let file_name = export_path.file_name().unwrap();
+
+                    let exporter: Singleton<Exporter> = Dependency::get();
+                    exporter.with_lock_mut(|exporter| {
+                        exporter.export_proof(
+                            ui.ctx().clone(),
+                            self.pages.clone(),
+                            page_indices,
+                            directory.into(),
+                            file_name.to_str().unwrap(),
+                            self.settings.clone(),
+                        );
+                    });
+                    return ModalActionResponse::Confirm;
+                }
+                Err(e) => {
+                    error!("Error opening proof export file dialog: {:?}", e);
+                }
+                Ok(None) => {
+                    info!("No proof export file selected");
+                }
+            }
+        }
+
+        ModalActionResponse::None
+    }
+}
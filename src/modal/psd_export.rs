@@ -0,0 +1,65 @@
+use log::{error, info};
+
+use crate::{
+    dependencies::{Dependency, Singleton, SingletonFor},
+    export::Exporter,
+    widget::canvas::CanvasState,
+};
+
+use super::{Modal, ModalActionResponse};
+
+pub struct PsdExportModal {
+    pages: Vec<CanvasState>,
+}
+
+impl PsdExportModal {
+    pub fn new(pages: Vec<CanvasState>) -> Self {
+        Self { pages }
+    }
+}
+
+impl Modal for PsdExportModal {
+    fn title(&self) -> String {
+        "Export PSD".to_string()
+    }
+
+    fn body_ui(&mut self, ui: &mut egui::Ui) {
+        ui.label("Each layer on a page becomes a named raster layer for touching up in Photoshop or Affinity");
+
+        ui.add_space(10.0);
+        ui.label(format!(
+            "{} pages will be exported as layered PSD files",
+            self.pages.len()
+        ));
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn actions_ui(&mut self, ui: &mut egui::Ui) -> ModalActionResponse {
+        if ui.button("Cancel").clicked() {
+            return ModalActionResponse::Cancel;
+        }
+
+        if ui.button("Export").clicked() {
+            match native_dialog::FileDialog::new().show_open_single_dir() {
+                Ok(Some(directory)) => {
+                    let exporter: Singleton<Exporter> = Dependency::get();
+                    exporter.with_lock_mut(|exporter| {
+                        exporter.export_psd(ui.ctx().clone(), self.pages.clone(), directory);
+                    });
+                    return ModalActionResponse::Confirm;
+                }
+                Err(e) => {
+                    error!("Error opening PSD export dialog: {:?}", e);
+                }
+                Ok(None) => {
+                    info!("No PSD export directory selected");
+                }
+            }
+        }
+
+        ModalActionResponse::None
+    }
+}
@@ -0,0 +1,149 @@
+use egui::{Button, RichText};
+use log::{error, info};
+
+use crate::{
+    dependencies::{Dependency, SingletonFor},
+    error_sink::ErrorSink,
+    project::v1::Project,
+    session::Session,
+    theme,
+};
+
+use super::{Modal, ModalActionResponse};
+
+/// Asks what to do with unsaved changes before they would be lost.
+/// Confirm means the changes were either saved or discarded and it's safe to continue.
+#[derive(Debug, Clone)]
+pub struct SaveWarningModal {
+    project: Project,
+    message: String,
+    discard_title: String,
+    cancel_title: String,
+    error: Option<String>,
+    // A save started from the modal is running
+    saving: bool,
+}
+
+impl SaveWarningModal {
+    pub fn new(project: Project) -> Self {
+        Self {
+            project,
+            message: "You have unsaved changes. Do you want to save them first?".to_string(),
+            discard_title: "Don't Save".to_string(),
+            cancel_title: "Cancel".to_string(),
+            error: None,
+            saving: false,
+        }
+    }
+
+    pub fn recovered(project: Project) -> Self {
+        Self {
+            project,
+            message: "Unsaved changes from your last session were restored.".to_string(),
+            discard_title: "Discard Changes".to_string(),
+            cancel_title: "Keep Editing".to_string(),
+            error: None,
+            saving: false,
+        }
+    }
+
+    // Starts saving in the background like the Save menu item does, the modal waits for it to
+    // finish before confirming
+    fn save(&mut self, ctx: &egui::Context) {
+        let active_project =
+            Dependency::<Session>::get().with_lock(|session| session.active_project.clone());
+
+        let save_path = match active_project {
+            Some(path) => path,
+            None => match native_dialog::FileDialog::new()
+                .add_filter("Images", &["rpb"])
+                .show_save_single_file()
+            {
+                Ok(Some(path)) => path,
+                Ok(None) => {
+                    info!("No save path selected");
+                    return;
+                }
+                Err(e) => {
+                    error!("Error opening save file dialog: {:?}", e);
+                    return;
+                }
+            },
+        };
+
+        self.error = None;
+        match self.project.clone().save(save_path, ctx) {
+            Ok(()) => self.saving = true,
+            Err(err) => {
+                ErrorSink::report("Couldn't save the project", &err);
+                self.error = Some(format!("Error saving project: {}", err));
+            }
+        }
+    }
+
+    // Whether the save started by the modal has finished and wrote this project. A failed save
+    // has already been reported, the modal stays open so it can be tried again.
+    fn finished_saving(&mut self) -> bool {
+        let (saving, saved_hash) = Dependency::<Session>::get()
+            .with_lock(|session| (session.is_saving(), session.saved_project_hash));
+        if saving {
+            return false;
+        }
+
+        self.saving = false;
+        let saved = self
+            .project
+            .content_hash()
+            .ok()
+            .is_some_and(|hash| Some(hash) == saved_hash);
+        if !saved {
+            self.error = Some("The project couldn't be saved".to_string());
+        }
+
+        saved
+    }
+}
+
+impl Modal for SaveWarningModal {
+    fn title(&self) -> String {
+        "Unsaved Changes".to_string()
+    }
+
+    fn body_ui(&mut self, ui: &mut egui::Ui) {
+        ui.label(&self.message);
+
+        if let Some(error) = &self.error {
+            ui.add_space(10.0);
+            ui.label(RichText::new(error).color(theme::color::WARNING));
+        }
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn actions_ui(&mut self, ui: &mut egui::Ui) -> ModalActionResponse {
+        if self.saving {
+            if self.finished_saving() {
+                return ModalActionResponse::Confirm;
+            }
+
+            ui.add_enabled(false, Button::new("Saving..."));
+            return ModalActionResponse::None;
+        }
+
+        if ui.button(&self.cancel_title).clicked() {
+            return ModalActionResponse::Cancel;
+        }
+
+        if ui.button(&self.discard_title).clicked() {
+            return ModalActionResponse::Confirm;
+        }
+
+        if ui.button("Save").clicked() {
+            self.save(ui.ctx());
+        }
+
+        ModalActionResponse::None
+    }
+}
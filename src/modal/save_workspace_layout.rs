@@ -0,0 +1,81 @@
+use crate::{
+    auto_persisting::AutoPersisting,
+    config::{Config, ConfigModification},
+    dependencies::{Dependency, Singleton, SingletonFor},
+    scene::workspace::WorkspaceLayout,
+};
+
+use super::{Modal, ModalActionResponse};
+
+pub struct SaveWorkspaceLayoutModal {
+    name: String,
+    layout: WorkspaceLayout,
+}
+
+impl SaveWorkspaceLayoutModal {
+    pub fn new(layout: WorkspaceLayout) -> Self {
+        Self {
+            name: String::new(),
+            layout,
+        }
+    }
+}
+
+impl Modal for SaveWorkspaceLayoutModal {
+    fn title(&self) -> String {
+        "Save Workspace Layout".to_string()
+    }
+
+    fn body_ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Name:");
+            ui.text_edit_singleline(&mut self.name);
+        });
+
+        let name_taken = Dependency::<AutoPersisting<Config>>::get().with_lock_mut(|config| {
+            config
+                .read()
+                .map(|config| {
+                    config
+                        .workspace_layouts()
+                        .iter()
+                        .any(|(name, _)| *name == self.name.trim())
+                })
+                .unwrap_or(false)
+        });
+
+        if name_taken {
+            ui.label("A layout with this name already exists and will be replaced");
+        }
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn actions_ui(&mut self, ui: &mut egui::Ui) -> ModalActionResponse {
+        if ui.button("Cancel").clicked() {
+            return ModalActionResponse::Cancel;
+        }
+
+        let name = self.name.trim();
+
+        if ui
+            .add_enabled(!name.is_empty(), egui::Button::new("Save"))
+            .clicked()
+        {
+            let config: Singleton<AutoPersisting<Config>> = Dependency::get();
+            config.with_lock_mut(|config| {
+                if let Err(err) = config.modify(ConfigModification::SaveWorkspaceLayout(
+                    name.to_string(),
+                    self.layout.clone(),
+                )) {
+                    log::error!("Failed to save workspace layout: {:?}", err);
+                }
+            });
+            return ModalActionResponse::Confirm;
+        }
+
+        ModalActionResponse::None
+    }
+}
@@ -0,0 +1,98 @@
+use egui::{DragValue, Grid};
+use log::{error, info};
+
+use crate::{
+    dependencies::{Dependency, Singleton, SingletonFor},
+    export::{Exporter, SlideshowSettings},
+    widget::canvas::CanvasState,
+};
+
+use super::{Modal, ModalActionResponse};
+
+pub struct SlideshowExportModal {
+    pages: Vec<CanvasState>,
+    settings: SlideshowSettings,
+}
+
+impl SlideshowExportModal {
+    pub fn new(pages: Vec<CanvasState>) -> Self {
+        Self {
+            pages,
+            settings: SlideshowSettings::default(),
+        }
+    }
+}
+
+impl Modal for SlideshowExportModal {
+    fn title(&self) -> String {
+        "Export Slideshow".to_string()
+    }
+
+    fn body_ui(&mut self, ui: &mut egui::Ui) {
+        Grid::new("slideshow_export_settings")
+            .num_columns(2)
+            .spacing([20.0, 5.0])
+            .show(ui, |ui| {
+                ui.label("Page Duration (s):");
+                ui.add(
+                    DragValue::new(&mut self.settings.page_duration)
+                        .range(0.5..=60.0)
+                        .speed(0.1),
+                );
+                ui.end_row();
+
+                ui.label("Transition Duration (s):");
+                ui.add(
+                    DragValue::new(&mut self.settings.transition_duration)
+                        .range(0.0..=10.0)
+                        .speed(0.1),
+                );
+                ui.end_row();
+
+                ui.label("Frame Rate:");
+                ui.add(DragValue::new(&mut self.settings.frame_rate).range(1..=60));
+                ui.end_row();
+            });
+
+        ui.add_space(10.0);
+        ui.label(format!(
+            "{} pages will be exported as an image sequence with a timing manifest",
+            self.pages.len()
+        ));
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn actions_ui(&mut self, ui: &mut egui::Ui) -> ModalActionResponse {
+        if ui.button("Cancel").clicked() {
+            return ModalActionResponse::Cancel;
+        }
+
+        if ui.button("Export").clicked() {
+            match native_dialog::FileDialog::new().show_open_single_dir() {
+                Ok(Some(directory)) => {
+                    let exporter: Singleton<Exporter> = Dependency::get();
+                    exporter.with_lock_mut(|exporter| {
+                        exporter.export_slideshow(
+                            ui.ctx().clone(),
+                            self.pages.clone(),
+                            directory,
+                            self.settings,
+                        );
+                    });
+                    return ModalActionResponse::Confirm;
+                }
+                Err(e) => {
+                    error!("Error opening slideshow export dialog: {:?}", e);
+                }
+                Ok(None) => {
+                    info!("No slideshow export directory selected");
+                }
+            }
+        }
+
+        ModalActionResponse::None
+    }
+}
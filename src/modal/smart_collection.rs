@@ -0,0 +1,186 @@
+use egui::{ComboBox, Grid, RichText, TextEdit};
+use log::error;
+use strum::IntoEnumIterator;
+
+use crate::{
+    auto_persisting::AutoPersisting,
+    config::{Config, ConfigModification},
+    dependencies::{Dependency, Singleton, SingletonFor},
+    photo_manager::{PhotoManager, PhotosSort, PhotosSortField, SortDirection},
+    photo_query::PhotoQuery,
+    smart_collection::SmartCollection,
+    theme,
+};
+
+use super::{Modal, ModalActionResponse};
+
+/// Creates a smart collection or edits an existing one
+pub struct SmartCollectionModal {
+    collection: SmartCollection,
+    // The name of the collection being edited, None for a new one
+    original_name: Option<String>,
+}
+
+impl SmartCollectionModal {
+    /// A new collection starting from the gallery's current search
+    pub fn new(query: impl Into<String>) -> Self {
+        Self {
+            collection: SmartCollection::new("", query),
+            original_name: None,
+        }
+    }
+
+    pub fn edit(collection: SmartCollection) -> Self {
+        Self {
+            original_name: Some(collection.name.clone()),
+            collection,
+        }
+    }
+
+    fn name_taken(&self) -> bool {
+        let name = self.collection.name.trim();
+        if Some(name) == self.original_name.as_deref() {
+            return false;
+        }
+
+        Dependency::<AutoPersisting<Config>>::get().with_lock_mut(|config| {
+            config
+                .read()
+                .map(|config| {
+                    config
+                        .smart_collections()
+                        .iter()
+                        .any(|collection| collection.name == name)
+                })
+                .unwrap_or(false)
+        })
+    }
+}
+
+impl Modal for SmartCollectionModal {
+    fn title(&self) -> String {
+        match self.original_name {
+            Some(_) => "Edit Smart Collection".to_string(),
+            None => "New Smart Collection".to_string(),
+        }
+    }
+
+    fn body_ui(&mut self, ui: &mut egui::Ui) {
+        Grid::new("smart_collection_fields")
+            .num_columns(2)
+            .spacing([20.0, 5.0])
+            .show(ui, |ui| {
+                ui.label("Name:");
+                ui.add(TextEdit::singleline(&mut self.collection.name).hint_text("Best of June"));
+                ui.end_row();
+
+                ui.label("Query:");
+                ui.add(
+                    TextEdit::singleline(&mut self.collection.query)
+                        .hint_text("rating:yes AND orientation:landscape AND date:2023-06")
+                        .desired_width(320.0),
+                );
+                ui.end_row();
+
+                ui.label("Sort:");
+                ui.horizontal(|ui| {
+                    let selected_text = self
+                        .collection
+                        .sort
+                        .map(|sort| sort.field.to_string())
+                        .unwrap_or_else(|| "Gallery Sort".to_string());
+
+                    ComboBox::from_id_salt("smart_collection_sort")
+                        .selected_text(selected_text)
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(&mut self.collection.sort, None, "Gallery Sort");
+                            for field in PhotosSortField::iter() {
+                                let direction = self
+                                    .collection
+                                    .sort
+                                    .map(|sort| sort.direction)
+                                    .unwrap_or(SortDirection::Descending);
+
+                                ui.selectable_value(
+                                    &mut self.collection.sort,
+                                    Some(PhotosSort { field, direction }),
+                                    field.to_string(),
+                                );
+                            }
+                        });
+
+                    if let Some(sort) = &mut self.collection.sort {
+                        let direction_text = match sort.direction {
+                            SortDirection::Ascending => "⬆",
+                            SortDirection::Descending => "⬇",
+                        };
+                        if ui
+                            .button(direction_text)
+                            .on_hover_text(format!("{:?}", sort.direction))
+                            .clicked()
+                        {
+                            sort.direction = sort.direction.toggled();
+                        }
+                    }
+                });
+                ui.end_row();
+            });
+
+        ui.add_space(10.0);
+
+        if self.name_taken() {
+            ui.label(
+                RichText::new("A collection with this name already exists")
+                    .color(theme::color::WARNING),
+            );
+        }
+
+        if self.collection.query.trim().is_empty() {
+            ui.label(RichText::new("Without a query the collection shows every photo").weak());
+        } else if let Err(err) = PhotoQuery::parse(&self.collection.query) {
+            ui.label(RichText::new(err.to_string()).color(theme::color::WARNING));
+        }
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn actions_ui(&mut self, ui: &mut egui::Ui) -> ModalActionResponse {
+        if ui.button("Cancel").clicked() {
+            return ModalActionResponse::Cancel;
+        }
+
+        let valid = !self.collection.name.trim().is_empty()
+            && !self.name_taken()
+            && (self.collection.query.trim().is_empty()
+                || PhotoQuery::parse(&self.collection.query).is_ok());
+
+        if ui.add_enabled(valid, egui::Button::new("Save")).clicked() {
+            let mut collection = self.collection.clone();
+            collection.name = collection.name.trim().to_string();
+
+            let config: Singleton<AutoPersisting<Config>> = Dependency::get();
+            config.with_lock_mut(|config| {
+                if let Err(err) = config.modify(ConfigModification::SaveSmartCollection(
+                    self.original_name.clone(),
+                    collection.clone(),
+                )) {
+                    error!("Failed to save smart collection: {:?}", err);
+                }
+            });
+
+            // The gallery picks up the changes if it's showing the collection
+            Dependency::<PhotoManager>::get().with_lock_mut(|photo_manager| {
+                let active = photo_manager.active_collection().map(str::to_string);
+                if active.is_some() && active == self.original_name {
+                    photo_manager.set_collection(Some(&collection));
+                }
+            });
+
+            return ModalActionResponse::Confirm;
+        }
+
+        ModalActionResponse::None
+    }
+}
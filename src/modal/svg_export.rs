@@ -0,0 +1,86 @@
+use log::{error, info};
+
+use crate::{
+    dependencies::{Dependency, Singleton, SingletonFor},
+    export::Exporter,
+    svg_export::SvgPhotoMode,
+    widget::canvas::CanvasState,
+};
+
+use super::{Modal, ModalActionResponse};
+
+pub struct SvgExportModal {
+    pages: Vec<CanvasState>,
+    embed_photos: bool,
+}
+
+impl SvgExportModal {
+    pub fn new(pages: Vec<CanvasState>) -> Self {
+        Self {
+            pages,
+            embed_photos: true,
+        }
+    }
+}
+
+impl Modal for SvgExportModal {
+    fn title(&self) -> String {
+        "Export SVG".to_string()
+    }
+
+    fn body_ui(&mut self, ui: &mut egui::Ui) {
+        ui.checkbox(&mut self.embed_photos, "Embed photos");
+        ui.label(if self.embed_photos {
+            "Photos are stored inside each SVG file"
+        } else {
+            "Photos are linked to their original files"
+        });
+
+        ui.add_space(10.0);
+        ui.label(format!(
+            "{} pages will be exported as SVG files with editable text",
+            self.pages.len()
+        ));
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn actions_ui(&mut self, ui: &mut egui::Ui) -> ModalActionResponse {
+        if ui.button("Cancel").clicked() {
+            return ModalActionResponse::Cancel;
+        }
+
+        if ui.button("Export").clicked() {
+            match native_dialog::FileDialog::new().show_open_single_dir() {
+                Ok(Some(directory)) => {
+                    let photo_mode = if self.embed_photos {
+                        SvgPhotoMode::Embed
+                    } else {
+                        SvgPhotoMode::Link
+                    };
+
+                    let exporter: Singleton<Exporter> = Dependency::get();
+                    exporter.with_lock_mut(|exporter| {
+                        exporter.export_svg(
+                            ui.ctx().clone(),
+                            self.pages.clone(),
+                            directory,
+                            photo_mode,
+                        );
+                    });
+                    return ModalActionResponse::Confirm;
+                }
+                Err(e) => {
+                    error!("Error opening SVG export dialog: {:?}", e);
+                }
+                Ok(None) => {
+                    info!("No SVG export directory selected");
+                }
+            }
+        }
+
+        ModalActionResponse::None
+    }
+}
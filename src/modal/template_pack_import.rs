@@ -0,0 +1,69 @@
+use crate::{
+    auto_persisting::AutoPersisting,
+    config::{Config, ConfigModification},
+    dependencies::{Dependency, Singleton, SingletonFor},
+    template::pack::TemplatePack,
+};
+
+use super::{Modal, ModalActionResponse};
+
+/// Asks what to do with an imported pack named like one that's already there, but with different
+/// templates.
+pub struct TemplatePackImportModal {
+    pack: TemplatePack,
+}
+
+impl TemplatePackImportModal {
+    pub fn new(pack: TemplatePack) -> Self {
+        Self { pack }
+    }
+
+    fn add(&self, modification: ConfigModification) {
+        let config: Singleton<AutoPersisting<Config>> = Dependency::get();
+        config.with_lock_mut(|config| {
+            if let Err(err) = config.modify(modification) {
+                log::error!("Failed to save template pack: {:?}", err);
+            }
+        });
+    }
+}
+
+impl Modal for TemplatePackImportModal {
+    fn title(&self) -> String {
+        "Import Template Pack".to_string()
+    }
+
+    fn body_ui(&mut self, ui: &mut egui::Ui) {
+        ui.label(format!(
+            "A template pack named \"{}\" has already been imported. Replace it with this one, or \
+             keep both?",
+            self.pack.name
+        ));
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn actions_ui(&mut self, ui: &mut egui::Ui) -> ModalActionResponse {
+        if ui.button("Cancel").clicked() {
+            return ModalActionResponse::Cancel;
+        }
+
+        if ui
+            .button("Keep Both")
+            .on_hover_text("Add this pack with a number after its name")
+            .clicked()
+        {
+            self.add(ConfigModification::AddTemplatePack(self.pack.clone()));
+            return ModalActionResponse::Confirm;
+        }
+
+        if ui.button("Replace").clicked() {
+            self.add(ConfigModification::ReplaceTemplatePack(self.pack.clone()));
+            return ModalActionResponse::Confirm;
+        }
+
+        ModalActionResponse::None
+    }
+}
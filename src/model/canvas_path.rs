@@ -0,0 +1,357 @@
+use eframe::{
+    egui::Painter,
+    emath::Rot2,
+    epaint::{Color32, Pos2, Rect, Shape, Stroke, Vec2},
+};
+
+use super::fill::Fill;
+
+// Each curved segment is drawn and hit tested as this many straight lines
+const CURVE_STEPS: usize = 16;
+
+// A straight line still gets a rect this thick so it can be moved and resized
+const MIN_PATH_SIZE: f32 = 1.0;
+
+/// An anchor of a path with the bezier handles either side of it. Positions are relative to the
+/// layer's rect, 0 to 1 across it, so the path follows the layer when it's moved or resized.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PathPoint {
+    pub anchor: Pos2,
+    // Offsets from the anchor. A point without handles is a corner.
+    pub handle_in: Vec2,
+    pub handle_out: Vec2,
+}
+
+impl PathPoint {
+    pub fn corner(anchor: Pos2) -> Self {
+        Self {
+            anchor,
+            handle_in: Vec2::ZERO,
+            handle_out: Vec2::ZERO,
+        }
+    }
+
+    pub fn is_corner(&self) -> bool {
+        self.handle_in == Vec2::ZERO && self.handle_out == Vec2::ZERO
+    }
+}
+
+/// A freeform shape drawn with the pen tool. Closed paths are filled, open ones are only stroked.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CanvasPath {
+    pub points: Vec<PathPoint>,
+    pub closed: bool,
+    pub fill: Fill,
+    pub stroke_color: Color32,
+    // In page pixels
+    pub stroke_width: f32,
+}
+
+impl CanvasPath {
+    pub fn new(points: Vec<PathPoint>, closed: bool) -> Self {
+        Self {
+            points,
+            closed,
+            fill: Fill::Solid(Color32::from_gray(200)),
+            stroke_color: Color32::BLACK,
+            stroke_width: 4.0,
+        }
+    }
+
+    pub fn is_filled(&self) -> bool {
+        self.closed && self.points.len() > 2 && self.fill.is_visible()
+    }
+
+    /// The cubic bezier segments between the anchors as start, control, control and end points,
+    /// including the segment back to the start of a closed path
+    pub fn segments(&self) -> Vec<[Pos2; 4]> {
+        let count = self.points.len();
+        if count < 2 {
+            return Vec::new();
+        }
+
+        let num_segments = if self.closed { count } else { count - 1 };
+
+        (0..num_segments)
+            .map(|index| {
+                let start = self.points[index];
+                let end = self.points[(index + 1) % count];
+                [
+                    start.anchor,
+                    start.anchor + start.handle_out,
+                    end.anchor + end.handle_in,
+                    end.anchor,
+                ]
+            })
+            .collect()
+    }
+
+    /// The path as a line through its relative space. Closed paths don't repeat the first point.
+    pub fn flatten(&self) -> Vec<Pos2> {
+        let Some(first) = self.points.first() else {
+            return Vec::new();
+        };
+
+        let mut polyline = vec![first.anchor];
+
+        for segment in self.segments() {
+            let is_straight = segment[1] == segment[0] && segment[2] == segment[3];
+            let steps = if is_straight { 1 } else { CURVE_STEPS };
+
+            for step in 1..=steps {
+                polyline.push(cubic_point(segment, step as f32 / steps as f32));
+            }
+        }
+
+        if self.closed && polyline.len() > 1 {
+            polyline.pop();
+        }
+
+        polyline
+    }
+
+    /// Maps points relative to the path into `rect`, rotated around its center like the layer
+    pub fn transform(rect: Rect, rotation: f32) -> impl Fn(Pos2) -> Pos2 {
+        let rotation = Rot2::from_angle(rotation);
+        move |point: Pos2| {
+            let unrotated = rect.min + point.to_vec2() * rect.size();
+            rect.center() + rotation * (unrotated - rect.center())
+        }
+    }
+
+    /// Whether `pos` is on the painted part of the path drawn into `rect`. Anywhere inside a
+    /// filled path counts, otherwise `pos` has to be within `reach` of the line.
+    pub fn hit_test(&self, rect: Rect, rotation: f32, pos: Pos2, reach: f32) -> bool {
+        let to_rect = Self::transform(rect, rotation);
+        let polyline: Vec<Pos2> = self.flatten().into_iter().map(to_rect).collect();
+
+        if polyline.is_empty() {
+            return false;
+        }
+
+        if self.is_filled() && polygon_contains(&polyline, pos) {
+            return true;
+        }
+
+        let closing = self
+            .closed
+            .then(|| (polyline[polyline.len() - 1], polyline[0]));
+
+        polyline
+            .windows(2)
+            .map(|line| (line[0], line[1]))
+            .chain(closing)
+            .any(|(start, end)| distance_to_line(pos, start, end) <= reach)
+    }
+
+    /// Fits the layer's rect to the path after points were added or moved. The points are moved
+    /// into the new rect so they stay where they were on the page.
+    pub fn fit_rect(&mut self, rect: &mut Rect, rotation: f32) {
+        let polyline = self.flatten();
+        if polyline.is_empty() {
+            return;
+        }
+
+        let bounds = Rect::from_points(&polyline);
+        let size = rect.size();
+
+        let content_min = rect.min + bounds.min.to_vec2() * size;
+        let content_max = rect.min + bounds.max.to_vec2() * size;
+        let content_center = content_min + (content_max - content_min) / 2.0;
+        let new_size = (content_max - content_min).max(Vec2::splat(MIN_PATH_SIZE));
+        let new_min = content_center - new_size / 2.0;
+
+        for point in &mut self.points {
+            let anchor = rect.min + point.anchor.to_vec2() * size;
+            point.anchor = ((anchor - new_min) / new_size).to_pos2();
+            point.handle_in = point.handle_in * size / new_size;
+            point.handle_out = point.handle_out * size / new_size;
+        }
+
+        // The rect rotates around its center so moving the center keeps the points in place
+        let center = rect.center() + Rot2::from_angle(rotation) * (content_center - rect.center());
+        *rect = Rect::from_center_size(center, new_size);
+    }
+
+    /// Gives the point at `index` handles along the line between its neighbors, or takes them
+    /// away to make a corner
+    pub fn toggle_smooth(&mut self, index: usize) {
+        let count = self.points.len();
+        let Some(point) = self.points.get(index).copied() else {
+            return;
+        };
+
+        if !point.is_corner() {
+            self.points[index] = PathPoint::corner(point.anchor);
+            return;
+        }
+
+        let neighbor = |offset: isize| {
+            let neighbor_index = index as isize + offset;
+            if self.closed {
+                Some(self.points[neighbor_index.rem_euclid(count as isize) as usize].anchor)
+            } else {
+                usize::try_from(neighbor_index)
+                    .ok()
+                    .and_then(|neighbor_index| self.points.get(neighbor_index))
+                    .map(|neighbor| neighbor.anchor)
+            }
+        };
+
+        let direction = match (neighbor(-1), neighbor(1)) {
+            (Some(previous), Some(next)) => (next - previous) / 6.0,
+            (None, Some(next)) => (next - point.anchor) / 3.0,
+            (Some(previous), None) => (point.anchor - previous) / 3.0,
+            (None, None) => return,
+        };
+
+        self.points[index].handle_in = -direction;
+        self.points[index].handle_out = direction;
+    }
+
+    /// Paints the path into `rect`, rotated around its center. `scale` converts the stroke width
+    /// from page pixels.
+    pub fn paint(&self, painter: &Painter, rect: Rect, rotation: f32, scale: f32) {
+        let to_rect = Self::transform(rect, rotation);
+        let relative = self.flatten();
+
+        if self.is_filled() {
+            // Gradients are worked out in the path's relative space so they turn with the layer
+            let triangles = triangulate(&relative);
+            painter.add(Shape::mesh(self.fill.mesh(&relative, &triangles, &to_rect)));
+        }
+
+        let polyline: Vec<Pos2> = relative.into_iter().map(&to_rect).collect();
+
+        if self.stroke_width > 0.0 {
+            let stroke = Stroke::new(self.stroke_width * scale, self.stroke_color);
+            painter.add(if self.closed {
+                Shape::closed_line(polyline, stroke)
+            } else {
+                Shape::line(polyline, stroke)
+            });
+        }
+    }
+}
+
+fn cubic_point([p0, p1, p2, p3]: [Pos2; 4], t: f32) -> Pos2 {
+    let u = 1.0 - t;
+    (p0.to_vec2() * (u * u * u)
+        + p1.to_vec2() * (3.0 * u * u * t)
+        + p2.to_vec2() * (3.0 * u * t * t)
+        + p3.to_vec2() * (t * t * t))
+        .to_pos2()
+}
+
+fn cross(a: Vec2, b: Vec2) -> f32 {
+    a.x * b.y - a.y * b.x
+}
+
+fn distance_to_line(pos: Pos2, start: Pos2, end: Pos2) -> f32 {
+    let line = end - start;
+    let length_sq = line.length_sq();
+    if length_sq == 0.0 {
+        return pos.distance(start);
+    }
+
+    let t = ((pos - start).dot(line) / length_sq).clamp(0.0, 1.0);
+    pos.distance(start + line * t)
+}
+
+// Even-odd rule, so self-intersecting paths get holes like they do in SVG viewers
+fn polygon_contains(polygon: &[Pos2], pos: Pos2) -> bool {
+    let mut inside = false;
+    let mut previous = match polygon.last() {
+        Some(previous) => *previous,
+        None => return false,
+    };
+
+    for &point in polygon {
+        if (point.y > pos.y) != (previous.y > pos.y)
+            && pos.x < (previous.x - point.x) * (pos.y - point.y) / (previous.y - point.y) + point.x
+        {
+            inside = !inside;
+        }
+        previous = point;
+    }
+
+    inside
+}
+
+fn triangle_contains(pos: Pos2, [a, b, c]: [Pos2; 3]) -> bool {
+    let ab = cross(b - a, pos - a);
+    let bc = cross(c - b, pos - b);
+    let ca = cross(a - c, pos - c);
+    (ab >= 0.0 && bc >= 0.0 && ca >= 0.0) || (ab <= 0.0 && bc <= 0.0 && ca <= 0.0)
+}
+
+// Ear clipping. Self-intersecting polygons have no ears left at some point, what remains of
+// those is filled as a fan.
+fn triangulate(polygon: &[Pos2]) -> Vec<[u32; 3]> {
+    let mut remaining: Vec<usize> = (0..polygon.len()).collect();
+    let mut triangles = Vec::new();
+
+    let doubled_area: f32 = (0..polygon.len())
+        .map(|index| {
+            cross(
+                polygon[index].to_vec2(),
+                polygon[(index + 1) % polygon.len()].to_vec2(),
+            )
+        })
+        .sum();
+    let winding = doubled_area.signum();
+
+    while remaining.len() > 3 {
+        let count = remaining.len();
+        let mut clipped = false;
+
+        for index in 0..count {
+            let previous = remaining[(index + count - 1) % count];
+            let current = remaining[index];
+            let next = remaining[(index + 1) % count];
+            let corner = [polygon[previous], polygon[current], polygon[next]];
+
+            let turn = cross(corner[1] - corner[0], corner[2] - corner[1]) * winding;
+
+            // Points on a straight line don't add anything to the shape
+            if turn.abs() <= f32::EPSILON {
+                remaining.remove(index);
+                clipped = true;
+                break;
+            }
+
+            if turn < 0.0 {
+                continue;
+            }
+
+            let is_ear = remaining
+                .iter()
+                .filter(|other| ![previous, current, next].contains(other))
+                .all(|other| !triangle_contains(polygon[*other], corner));
+
+            if is_ear {
+                triangles.push([previous as u32, current as u32, next as u32]);
+                remaining.remove(index);
+                clipped = true;
+                break;
+            }
+        }
+
+        if !clipped {
+            for index in 1..remaining.len() - 1 {
+                triangles.push([
+                    remaining[0] as u32,
+                    remaining[index] as u32,
+                    remaining[index + 1] as u32,
+                ]);
+            }
+            return triangles;
+        }
+    }
+
+    if let [a, b, c] = remaining[..] {
+        triangles.push([a as u32, b as u32, c as u32]);
+    }
+
+    triangles
+}
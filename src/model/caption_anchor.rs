@@ -0,0 +1,93 @@
+use egui::{emath::Rot2, Pos2, Rect, Vec2};
+use strum_macros::EnumIter;
+
+use crate::id::LayerId;
+
+// Space left between a new caption and its photo, in page pixels
+pub const DEFAULT_CAPTION_GAP: f32 = 12.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, EnumIter)]
+pub enum CaptionSide {
+    #[default]
+    Below,
+    Above,
+    Left,
+    Right,
+}
+
+impl std::fmt::Display for CaptionSide {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CaptionSide::Below => write!(f, "Below"),
+            CaptionSide::Above => write!(f, "Above"),
+            CaptionSide::Left => write!(f, "Left"),
+            CaptionSide::Right => write!(f, "Right"),
+        }
+    }
+}
+
+/// Keeps a caption text layer next to a photo layer on the same page. The caption is moved
+/// whenever the photo is, until it's detached.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CaptionAnchor {
+    pub photo_layer: LayerId,
+    pub side: CaptionSide,
+    // Space between the photo and the caption, in page pixels
+    pub gap: f32,
+}
+
+impl CaptionAnchor {
+    pub fn new(photo_layer: LayerId) -> Self {
+        Self {
+            photo_layer,
+            side: CaptionSide::default(),
+            gap: DEFAULT_CAPTION_GAP,
+        }
+    }
+
+    /// Where a caption of `caption_size` goes next to a photo at `photo_rect` turned by `rotation`,
+    /// before the caption is given the same rotation. The caption turns around the photo's center
+    /// with it so it stays against the same edge.
+    pub fn rotated_caption_rect(
+        &self,
+        photo_rect: Rect,
+        rotation: f32,
+        caption_size: Vec2,
+    ) -> Rect {
+        let rect = self.caption_rect(photo_rect, caption_size);
+        let center = photo_rect.center();
+        Rect::from_center_size(
+            center + Rot2::from_angle(rotation) * (rect.center() - center),
+            rect.size(),
+        )
+    }
+
+    /// Where a caption of `caption_size` goes next to an unrotated photo at `photo_rect`. Captions
+    /// above or below take the photo's width, captions beside it line up with its top edge.
+    pub fn caption_rect(&self, photo_rect: Rect, caption_size: Vec2) -> Rect {
+        match self.side {
+            CaptionSide::Below => Rect::from_min_size(
+                Pos2::new(photo_rect.left(), photo_rect.bottom() + self.gap),
+                Vec2::new(photo_rect.width(), caption_size.y),
+            ),
+            CaptionSide::Above => Rect::from_min_size(
+                Pos2::new(
+                    photo_rect.left(),
+                    photo_rect.top() - self.gap - caption_size.y,
+                ),
+                Vec2::new(photo_rect.width(), caption_size.y),
+            ),
+            CaptionSide::Left => Rect::from_min_size(
+                Pos2::new(
+                    photo_rect.left() - self.gap - caption_size.x,
+                    photo_rect.top(),
+                ),
+                caption_size,
+            ),
+            CaptionSide::Right => Rect::from_min_size(
+                Pos2::new(photo_rect.right() + self.gap, photo_rect.top()),
+                caption_size,
+            ),
+        }
+    }
+}
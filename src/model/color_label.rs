@@ -0,0 +1,71 @@
+use std::fmt::Display;
+
+use egui::{Color32, Context, Key, Painter, Pos2, Stroke};
+use serde::{Deserialize, Serialize};
+use strum::IntoEnumIterator;
+use strum_macros::EnumIter;
+
+/// A colored tag for sorting photos while culling or tracking where each page is at, e.g. red for
+/// needs review and green for done. What each color means is up to the user.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, EnumIter, Serialize, Deserialize)]
+pub enum ColorLabel {
+    Red,
+    Yellow,
+    Green,
+    Blue,
+    Purple,
+}
+
+impl Display for ColorLabel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ColorLabel::Red => write!(f, "Red"),
+            ColorLabel::Yellow => write!(f, "Yellow"),
+            ColorLabel::Green => write!(f, "Green"),
+            ColorLabel::Blue => write!(f, "Blue"),
+            ColorLabel::Purple => write!(f, "Purple"),
+        }
+    }
+}
+
+impl ColorLabel {
+    pub fn color(&self) -> Color32 {
+        match self {
+            ColorLabel::Red => Color32::from_rgb(0xe0, 0x40, 0x40),
+            ColorLabel::Yellow => Color32::from_rgb(0xf0, 0xc0, 0x20),
+            ColorLabel::Green => Color32::from_rgb(0x30, 0xb0, 0x50),
+            ColorLabel::Blue => Color32::from_rgb(0x30, 0x80, 0xe0),
+            ColorLabel::Purple => Color32::from_rgb(0x90, 0x50, 0xd0),
+        }
+    }
+
+    /// Keyboard shortcut, numbers after the 1-3 used for ratings
+    pub fn key(&self) -> Key {
+        match self {
+            ColorLabel::Red => Key::Num6,
+            ColorLabel::Yellow => Key::Num7,
+            ColorLabel::Green => Key::Num8,
+            ColorLabel::Blue => Key::Num9,
+            ColorLabel::Purple => Key::Num0,
+        }
+    }
+
+    /// The label whose shortcut was pressed this frame, ignored while typing into a text field
+    pub fn pressed(ctx: &Context) -> Option<ColorLabel> {
+        if ctx.memory(|memory| memory.focused().is_some()) {
+            return None;
+        }
+
+        ColorLabel::iter().find(|label| ctx.input(|input| input.key_pressed(label.key())))
+    }
+
+    /// Draws the label as a dot, outlined so it stays visible on a background of the same color
+    pub fn paint_dot(&self, painter: &Painter, center: Pos2, radius: f32) {
+        painter.circle(
+            center,
+            radius,
+            self.color(),
+            Stroke::new(1.0, Color32::from_black_alpha(120)),
+        );
+    }
+}
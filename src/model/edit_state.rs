@@ -27,6 +27,7 @@ impl Editable<Page, PageEditState> {
             height: EditableValue::new(value.size().y),
             ppi: EditableValue::new(value.ppi()),
             unit: EditableValue::new(value.unit()),
+            bleed: EditableValue::new(value.bleed()),
         };
         Self { value, edit_state }
     }
@@ -40,6 +41,9 @@ impl Editable<Page, PageEditState> {
             .update_if_not_active(self.value.size().y);
         self.edit_state.ppi.update_if_not_active(self.value.ppi());
         self.edit_state.unit.update_if_not_active(self.value.unit());
+        self.edit_state
+            .bleed
+            .update_if_not_active(self.value.bleed());
     }
 }
 
@@ -0,0 +1,247 @@
+use eframe::epaint::{Color32, Mesh, Pos2, Rect};
+use strum_macros::{Display, EnumIter};
+
+// Gradients are drawn as triangles with a color at each corner. Triangles are split until their
+// sides are about this long on screen so the colors blend smoothly.
+const GRADIENT_STEP: f32 = 16.0;
+
+// Limits how finely a single triangle is split, for very large exports
+const MAX_GRADIENT_STEPS: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GradientStop {
+    // 0 at the start of the gradient, 1 at the end
+    pub offset: f32,
+    pub color: Color32,
+}
+
+impl GradientStop {
+    pub fn new(offset: f32, color: Color32) -> Self {
+        Self { offset, color }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, EnumIter)]
+pub enum GradientKind {
+    Linear,
+    Radial,
+}
+
+/// A blend between colors. The start and end are relative to the rect being filled, 0 to 1
+/// across it, so the gradient follows the layer or page when it's resized. A linear gradient
+/// runs from the start to the end, a radial one is centered on the start and reaches the end.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gradient {
+    pub kind: GradientKind,
+    // Kept in order of their offsets
+    pub stops: Vec<GradientStop>,
+    pub start: Pos2,
+    pub end: Pos2,
+}
+
+impl Gradient {
+    pub fn new(kind: GradientKind, from: Color32, to: Color32) -> Self {
+        let (start, end) = match kind {
+            GradientKind::Linear => (Pos2::new(0.0, 0.5), Pos2::new(1.0, 0.5)),
+            GradientKind::Radial => (Pos2::new(0.5, 0.5), Pos2::new(1.0, 0.5)),
+        };
+
+        Self {
+            kind,
+            stops: vec![GradientStop::new(0.0, from), GradientStop::new(1.0, to)],
+            start,
+            end,
+        }
+    }
+
+    /// How far along the gradient a relative point is. Points before the start or past the end
+    /// take the color of the first or last stop.
+    pub fn t_at(&self, point: Pos2) -> f32 {
+        let axis = self.end - self.start;
+        let t = match self.kind {
+            GradientKind::Linear => {
+                let length_sq = axis.length_sq();
+                if length_sq == 0.0 {
+                    return 0.0;
+                }
+                (point - self.start).dot(axis) / length_sq
+            }
+            GradientKind::Radial => {
+                let radius = axis.length();
+                if radius == 0.0 {
+                    return 1.0;
+                }
+                point.distance(self.start) / radius
+            }
+        };
+
+        t.clamp(0.0, 1.0)
+    }
+
+    pub fn color_at(&self, t: f32) -> Color32 {
+        let (Some(first), Some(last)) = (self.stops.first(), self.stops.last()) else {
+            return Color32::TRANSPARENT;
+        };
+
+        if t <= first.offset {
+            return first.color;
+        }
+
+        for stops in self.stops.windows(2) {
+            let [from, to] = [stops[0], stops[1]];
+            if t <= to.offset {
+                let span = to.offset - from.offset;
+                let amount = if span > 0.0 {
+                    (t - from.offset) / span
+                } else {
+                    1.0
+                };
+                return lerp_color(from.color, to.color, amount);
+            }
+        }
+
+        last.color
+    }
+
+    /// Adds a stop at `offset` with the color the gradient already has there so adding it
+    /// doesn't change how it looks. Returns the index of the new stop.
+    pub fn insert_stop(&mut self, offset: f32) -> usize {
+        let offset = offset.clamp(0.0, 1.0);
+        let stop = GradientStop::new(offset, self.color_at(offset));
+        let index = self
+            .stops
+            .iter()
+            .position(|existing| existing.offset > offset)
+            .unwrap_or(self.stops.len());
+        self.stops.insert(index, stop);
+        index
+    }
+
+    fn is_visible(&self) -> bool {
+        self.stops.iter().any(|stop| stop.color.a() > 0)
+    }
+}
+
+/// What the inside of a shape or a page background is painted with
+#[derive(Debug, Clone, PartialEq)]
+pub enum Fill {
+    Solid(Color32),
+    Gradient(Gradient),
+}
+
+impl Default for Fill {
+    fn default() -> Self {
+        Fill::Solid(Color32::WHITE)
+    }
+}
+
+impl Fill {
+    pub fn is_visible(&self) -> bool {
+        match self {
+            Fill::Solid(color) => color.a() > 0,
+            Fill::Gradient(gradient) => gradient.is_visible(),
+        }
+    }
+
+    /// The color the fill is closest to, used where only a single color can be shown
+    pub fn average_color(&self) -> Color32 {
+        match self {
+            Fill::Solid(color) => *color,
+            Fill::Gradient(gradient) => gradient.color_at(0.5),
+        }
+    }
+
+    /// A mesh of `triangles` over the relative `points` with the fill's colors, placed on the
+    /// page by `to_rect`
+    pub fn mesh(
+        &self,
+        points: &[Pos2],
+        triangles: &[[u32; 3]],
+        to_rect: impl Fn(Pos2) -> Pos2,
+    ) -> Mesh {
+        let mut mesh = Mesh::default();
+
+        let gradient = match self {
+            Fill::Solid(color) => {
+                for point in points {
+                    mesh.colored_vertex(to_rect(*point), *color);
+                }
+                for [a, b, c] in triangles {
+                    mesh.add_triangle(*a, *b, *c);
+                }
+                return mesh;
+            }
+            Fill::Gradient(gradient) => gradient,
+        };
+
+        for triangle in triangles {
+            let corners = triangle.map(|index| points[index as usize]);
+            let placed = corners.map(&to_rect);
+            let longest_side = placed[0]
+                .distance(placed[1])
+                .max(placed[1].distance(placed[2]))
+                .max(placed[2].distance(placed[0]));
+            let steps =
+                ((longest_side / GRADIENT_STEP).ceil() as usize).clamp(1, MAX_GRADIENT_STEPS);
+
+            // The triangle is split into a grid of smaller ones, `row` steps towards the second
+            // corner and `column` steps towards the third
+            let first_vertex = mesh.vertices.len() as u32;
+            for row in 0..=steps {
+                for column in 0..=(steps - row) {
+                    let point = corners[0]
+                        + (corners[1] - corners[0]) * (row as f32 / steps as f32)
+                        + (corners[2] - corners[0]) * (column as f32 / steps as f32);
+                    mesh.colored_vertex(to_rect(point), gradient.color_at(gradient.t_at(point)));
+                }
+            }
+
+            // Rows get shorter by one towards the second corner
+            let vertex = |row: usize, column: usize| {
+                let row_start = row * (steps + 1) - row * (row.saturating_sub(1)) / 2;
+                first_vertex + (row_start + column) as u32
+            };
+            for row in 0..steps {
+                for column in 0..(steps - row) {
+                    mesh.add_triangle(
+                        vertex(row, column),
+                        vertex(row + 1, column),
+                        vertex(row, column + 1),
+                    );
+                    if column + 1 < steps - row {
+                        mesh.add_triangle(
+                            vertex(row + 1, column),
+                            vertex(row + 1, column + 1),
+                            vertex(row, column + 1),
+                        );
+                    }
+                }
+            }
+        }
+
+        mesh
+    }
+
+    /// Fills the whole of `rect`, like a page background
+    pub fn rect_mesh(&self, rect: Rect) -> Mesh {
+        let corners = [
+            Pos2::new(0.0, 0.0),
+            Pos2::new(1.0, 0.0),
+            Pos2::new(1.0, 1.0),
+            Pos2::new(0.0, 1.0),
+        ];
+        self.mesh(&corners, &[[0, 1, 2], [0, 2, 3]], |point| {
+            rect.min + point.to_vec2() * rect.size()
+        })
+    }
+}
+
+// Blends in sRGB like SVG and PDF viewers do so exports match the canvas
+fn lerp_color(from: Color32, to: Color32, t: f32) -> Color32 {
+    let from = from.to_srgba_unmultiplied();
+    let to = to.to_srgba_unmultiplied();
+    let channel = |index: usize| {
+        (from[index] as f32 + (to[index] as f32 - from[index] as f32) * t).round() as u8
+    };
+    Color32::from_rgba_unmultiplied(channel(0), channel(1), channel(2), channel(3))
+}
@@ -0,0 +1,70 @@
+use strum_macros::EnumIter;
+
+use crate::id::{next_guide_id, GuideId};
+
+use super::page::Page;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, EnumIter)]
+pub enum GuideOrientation {
+    Horizontal,
+    Vertical,
+}
+
+impl std::fmt::Display for GuideOrientation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GuideOrientation::Horizontal => write!(f, "Horizontal"),
+            GuideOrientation::Vertical => write!(f, "Vertical"),
+        }
+    }
+}
+
+/// A line across the page that layers snap to. Guides are only shown while editing and never
+/// exported.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Guide {
+    pub id: GuideId,
+    pub orientation: GuideOrientation,
+    // Relative to the page, 0 to 1 from the top or left edge, so guides stay put when the page is
+    // resized
+    pub position: f32,
+    pub locked: bool,
+}
+
+impl Guide {
+    pub fn new(orientation: GuideOrientation, position: f32) -> Self {
+        Self {
+            id: next_guide_id(),
+            orientation,
+            position,
+            locked: false,
+        }
+    }
+
+    /// The distance from the top or left edge in the page's unit
+    pub fn position_in_unit(&self, page: &Page) -> f32 {
+        self.position * self.page_length(page)
+    }
+
+    pub fn set_position_in_unit(&mut self, page: &Page, position: f32) {
+        let length = self.page_length(page);
+        if length > 0.0 {
+            self.position = position / length;
+        }
+    }
+
+    pub fn position_pixels(&self, page: &Page) -> f32 {
+        let size = page.size_pixels();
+        match self.orientation {
+            GuideOrientation::Horizontal => self.position * size.y,
+            GuideOrientation::Vertical => self.position * size.x,
+        }
+    }
+
+    fn page_length(&self, page: &Page) -> f32 {
+        match self.orientation {
+            GuideOrientation::Horizontal => page.size().y,
+            GuideOrientation::Vertical => page.size().x,
+        }
+    }
+}
@@ -0,0 +1,169 @@
+use egui::{Pos2, Rect, Vec2};
+use serde::{Deserialize, Serialize};
+use strum_macros::EnumIter;
+
+/// Where a layer keeps its place on one axis when the page changes size
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, EnumIter, Serialize, Deserialize)]
+pub enum Pin {
+    /// Moves with the rest of the layout
+    #[default]
+    None,
+    /// Keeps its distance from the left or top edge
+    Start,
+    /// Keeps its distance from the middle of the page
+    Center,
+    /// Keeps its distance from the right or bottom edge
+    End,
+    /// Keeps its center at the same percentage of the page
+    Percentage,
+}
+
+impl Pin {
+    pub fn name(&self, horizontal: bool) -> &'static str {
+        match (self, horizontal) {
+            (Pin::None, _) => "None",
+            (Pin::Start, true) => "Left",
+            (Pin::Start, false) => "Top",
+            (Pin::Center, _) => "Center",
+            (Pin::End, true) => "Right",
+            (Pin::End, false) => "Bottom",
+            (Pin::Percentage, _) => "Percentage",
+        }
+    }
+
+    // Where the layer starts on the axis. `start` and `size` are the layer before the page changed
+    // from `from` to `to`, `moved_start` and `moved_size` are where the rest of the layout put it.
+    fn start(
+        &self,
+        start: f32,
+        size: f32,
+        moved_start: f32,
+        moved_size: f32,
+        from: f32,
+        to: f32,
+    ) -> f32 {
+        match self {
+            Pin::None => moved_start,
+            Pin::Start => start,
+            Pin::Center => to / 2.0 + (start + size / 2.0 - from / 2.0) - moved_size / 2.0,
+            Pin::End => to - (from - start - size) - moved_size,
+            Pin::Percentage => (start + size / 2.0) / from * to - moved_size / 2.0,
+        }
+    }
+}
+
+/// Pins a layer to the page so it reflows predictably when the page size or orientation changes,
+/// margins to the pinned edge are kept in page pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct LayerConstraints {
+    #[serde(default)]
+    pub horizontal: Pin,
+    #[serde(default)]
+    pub vertical: Pin,
+}
+
+impl LayerConstraints {
+    pub fn is_pinned(&self) -> bool {
+        self.horizontal != Pin::None || self.vertical != Pin::None
+    }
+
+    /// The rect of a layer at `rect` on a page of size `from` after the page changed to `to`.
+    /// `moved` is where the rest of the layout put the layer, its size is kept.
+    pub fn reflow(&self, rect: Rect, moved: Rect, from: Vec2, to: Vec2) -> Rect {
+        let x = self.horizontal.start(
+            rect.min.x,
+            rect.width(),
+            moved.min.x,
+            moved.width(),
+            from.x,
+            to.x,
+        );
+        let y = self.vertical.start(
+            rect.min.y,
+            rect.height(),
+            moved.min.y,
+            moved.height(),
+            from.y,
+            to.y,
+        );
+
+        Rect::from_min_size(Pos2::new(x, y), moved.size())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FROM: Vec2 = Vec2::new(1000.0, 800.0);
+    const TO: Vec2 = Vec2::new(2000.0, 400.0);
+
+    // 100px from the left and top, 700px from the right and 600px from the bottom
+    fn rect() -> Rect {
+        Rect::from_min_size(Pos2::new(100.0, 100.0), Vec2::new(200.0, 100.0))
+    }
+
+    // Where scaling with the page put it
+    fn moved() -> Rect {
+        Rect::from_min_size(Pos2::new(200.0, 50.0), Vec2::new(400.0, 50.0))
+    }
+
+    fn reflow(horizontal: Pin, vertical: Pin) -> Rect {
+        LayerConstraints {
+            horizontal,
+            vertical,
+        }
+        .reflow(rect(), moved(), FROM, TO)
+    }
+
+    #[test]
+    fn unpinned_layers_follow_the_layout() {
+        assert_eq!(reflow(Pin::None, Pin::None), moved());
+        assert!(!LayerConstraints::default().is_pinned());
+    }
+
+    #[test]
+    fn pinned_layers_keep_the_moved_size() {
+        for pin in [Pin::Start, Pin::Center, Pin::End, Pin::Percentage] {
+            assert_eq!(reflow(pin, pin).size(), moved().size());
+        }
+    }
+
+    #[test]
+    fn start_keeps_the_left_and_top_margins() {
+        assert_eq!(reflow(Pin::Start, Pin::Start).min, Pos2::new(100.0, 100.0));
+    }
+
+    #[test]
+    fn end_keeps_the_right_and_bottom_margins() {
+        let reflowed = reflow(Pin::End, Pin::End);
+        assert_eq!(TO.x - reflowed.max.x, 700.0);
+        assert_eq!(TO.y - reflowed.max.y, 600.0);
+    }
+
+    #[test]
+    fn center_keeps_the_distance_from_the_middle() {
+        let reflowed = reflow(Pin::Center, Pin::Center);
+        assert_eq!(
+            reflowed.center().x - TO.x / 2.0,
+            rect().center().x - FROM.x / 2.0
+        );
+        assert_eq!(
+            reflowed.center().y - TO.y / 2.0,
+            rect().center().y - FROM.y / 2.0
+        );
+    }
+
+    #[test]
+    fn percentage_keeps_the_center_at_the_same_fraction() {
+        let reflowed = reflow(Pin::Percentage, Pin::Percentage);
+        assert_eq!(reflowed.center().x / TO.x, rect().center().x / FROM.x);
+        assert_eq!(reflowed.center().y / TO.y, rect().center().y / FROM.y);
+    }
+
+    #[test]
+    fn axes_are_pinned_separately() {
+        let reflowed = reflow(Pin::Start, Pin::None);
+        assert_eq!(reflowed.min, Pos2::new(100.0, moved().min.y));
+    }
+}
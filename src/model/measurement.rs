@@ -0,0 +1,84 @@
+use egui::{Pos2, Rect, Vec2};
+
+use super::{page::Page, unit::Unit};
+
+/// A distance measured on the page with the measure tool. Like guides, measurements are only
+/// shown while editing and never exported.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Measurement {
+    // In page pixels, the space layers are positioned in
+    pub start: Pos2,
+    pub end: Pos2,
+}
+
+impl Measurement {
+    pub fn new(start: Pos2, end: Pos2) -> Self {
+        Self { start, end }
+    }
+
+    /// The shortest distance between two layers' bounds. Layers that overlap are measured center
+    /// to center instead.
+    pub fn between_rects(from: Rect, to: Rect) -> Self {
+        if from.intersects(to) {
+            return Self::new(from.center(), to.center());
+        }
+
+        // Along an axis where the rects overlap the line runs through the middle of the overlap,
+        // otherwise it runs between the facing edges
+        let span = |from_min: f32, from_max: f32, to_min: f32, to_max: f32| {
+            if from_max < to_min {
+                (from_max, to_min)
+            } else if to_max < from_min {
+                (from_min, to_max)
+            } else {
+                let middle = (from_min.max(to_min) + from_max.min(to_max)) / 2.0;
+                (middle, middle)
+            }
+        };
+
+        let (start_x, end_x) = span(from.min.x, from.max.x, to.min.x, to.max.x);
+        let (start_y, end_y) = span(from.min.y, from.max.y, to.min.y, to.max.y);
+
+        Self::new(Pos2::new(start_x, start_y), Pos2::new(end_x, end_y))
+    }
+
+    pub fn delta(&self) -> Vec2 {
+        self.end - self.start
+    }
+
+    pub fn length(&self) -> f32 {
+        self.delta().length()
+    }
+
+    /// The length in `unit` at the page's ppi, like "2.25 in"
+    pub fn label(&self, page: &Page, unit: Unit) -> String {
+        format_length(self.length(), page, unit)
+    }
+
+    /// How far across and down the measurement goes, like "2 in × 0.5 in"
+    pub fn extent_label(&self, page: &Page, unit: Unit) -> String {
+        let delta = self.delta();
+        format!(
+            "{} × {}",
+            format_length(delta.x.abs(), page, unit),
+            format_length(delta.y.abs(), page, unit)
+        )
+    }
+}
+
+fn format_length(pixels: f32, page: &Page, unit: Unit) -> String {
+    let ppi = page.ppi() as f32;
+    match unit {
+        Unit::Pixels => format!("{:.0}{}", pixels, unit_suffix(unit)),
+        Unit::Inches => format!("{:.2}{}", pixels / ppi, unit_suffix(unit)),
+        Unit::Centimeters => format!("{:.2}{}", pixels / ppi * 2.54, unit_suffix(unit)),
+    }
+}
+
+fn unit_suffix(unit: Unit) -> &'static str {
+    match unit {
+        Unit::Pixels => " px",
+        Unit::Inches => " in",
+        Unit::Centimeters => " cm",
+    }
+}
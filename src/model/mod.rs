@@ -1,5 +1,19 @@
+pub mod canvas_path;
+pub mod caption_anchor;
+pub mod color_label;
 pub mod edit_state;
 pub mod editable_value;
+pub mod fill;
+pub mod guide;
+pub mod layer_constraints;
+pub mod measurement;
+pub mod nudge;
 pub mod page;
+pub mod page_comment;
+pub mod page_notes;
+pub mod page_preset;
+pub mod photo_effects;
+pub mod photo_orientation;
+pub mod resize_quality;
 pub mod scale_mode;
 pub mod unit;
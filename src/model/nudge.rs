@@ -0,0 +1,83 @@
+use egui::{Modifiers, Vec2};
+use serde::{Deserialize, Serialize};
+use strum_macros::{Display, EnumIter};
+
+use super::page::Page;
+
+// How much further shift and alt move a layer with the arrow keys
+const COARSE_FACTOR: f32 = 10.0;
+const FINE_FACTOR: f32 = 0.1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, EnumIter, Serialize, Deserialize)]
+pub enum NudgeUnit {
+    Millimeters,
+    Inches,
+    // Moves the same distance on screen at any zoom
+    #[strum(to_string = "Screen Points")]
+    ScreenPoints,
+}
+
+impl NudgeUnit {
+    pub fn suffix(&self) -> &'static str {
+        match self {
+            NudgeUnit::Millimeters => " mm",
+            NudgeUnit::Inches => " in",
+            NudgeUnit::ScreenPoints => " pt",
+        }
+    }
+}
+
+/// How far the arrow keys move the selected layers
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct NudgeDistance {
+    pub amount: f32,
+    pub unit: NudgeUnit,
+}
+
+impl Default for NudgeDistance {
+    fn default() -> Self {
+        Self {
+            amount: 1.0,
+            unit: NudgeUnit::Millimeters,
+        }
+    }
+}
+
+impl NudgeDistance {
+    /// The distance in page pixels for a page shown at `zoom`. Shift moves ten times as far and
+    /// alt a tenth as far.
+    pub fn page_pixels(&self, page: &Page, zoom: f32, modifiers: Modifiers) -> f32 {
+        let ppi = page.ppi() as f32;
+        let pixels = match self.unit {
+            NudgeUnit::Millimeters => self.amount / 25.4 * ppi,
+            NudgeUnit::Inches => self.amount * ppi,
+            NudgeUnit::ScreenPoints => self.amount / zoom,
+        };
+
+        if modifiers.shift {
+            pixels * COARSE_FACTOR
+        } else if modifiers.alt {
+            pixels * FINE_FACTOR
+        } else {
+            pixels
+        }
+    }
+
+    /// How far a nudge moved in this distance's unit, like "+2 mm, -0.5 mm"
+    pub fn delta_label(&self, delta: Vec2, page: &Page, zoom: f32) -> String {
+        let ppi = page.ppi() as f32;
+        let format = |pixels: f32| match self.unit {
+            NudgeUnit::Millimeters => format!("{:+.2}", pixels / ppi * 25.4),
+            NudgeUnit::Inches => format!("{:+.3}", pixels / ppi),
+            NudgeUnit::ScreenPoints => format!("{:+.0}", pixels * zoom),
+        };
+
+        format!(
+            "{}{}, {}{}",
+            format(delta.x),
+            self.unit.suffix(),
+            format(delta.y),
+            self.unit.suffix()
+        )
+    }
+}
@@ -1,42 +1,61 @@
 use egui::Vec2;
+use serde::{Deserialize, Serialize};
 
 use super::{editable_value::EditableValue, unit::Unit};
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Page {
     size: Vec2,
     ppi: i32,
     unit: Unit,
+    // Extra space around each edge that's trimmed off after printing, in the page's unit
+    #[serde(default)]
+    bleed: f32,
 }
 
 impl Page {
     pub fn new(size: Vec2, ppi: i32, unit: Unit) -> Self {
-        Self { size, ppi, unit }
+        Self {
+            size,
+            ppi,
+            unit,
+            bleed: 0.0,
+        }
     }
 
     pub fn with_size_inches(size: Vec2) -> Self {
         let ppi = 300;
         let unit = Unit::Inches;
 
-        Self { size, ppi, unit }
+        Self::new(size, ppi, unit)
     }
 
     fn a4() -> Self {
         let ppi = 300;
         let unit = Unit::Inches;
 
-        Self {
-            size: Vec2::new(8.27, 11.69),
-            ppi,
-            unit,
-        }
+        Self::new(Vec2::new(8.27, 11.69), ppi, unit)
     }
 
     pub fn size_pixels(&self) -> Vec2 {
+        self.size * self.pixels_per_unit()
+    }
+
+    pub fn bleed_pixels(&self) -> f32 {
+        self.bleed * self.pixels_per_unit()
+    }
+
+    /// How far inside the trim edge content should stay so it isn't cut off when the page is
+    /// trimmed. Printers commonly ask for 1/8", or as much as the bleed if that's more.
+    pub fn safe_margin_pixels(&self) -> f32 {
+        (self.ppi as f32 / 8.0).max(self.bleed_pixels())
+    }
+
+    fn pixels_per_unit(&self) -> f32 {
         match self.unit {
-            Unit::Pixels => self.size,
-            Unit::Inches => self.size * self.ppi as f32,
-            Unit::Centimeters => self.size * (self.ppi as f32 / 2.54),
+            Unit::Pixels => 1.0,
+            Unit::Inches => self.ppi as f32,
+            Unit::Centimeters => self.ppi as f32 / 2.54,
         }
     }
 
@@ -60,6 +79,10 @@ impl Page {
         self.unit
     }
 
+    pub fn bleed(&self) -> f32 {
+        self.bleed
+    }
+
     pub fn aspect_ratio(&self) -> f32 {
         self.size.x / self.size.y
     }
@@ -70,12 +93,15 @@ impl Page {
 
     pub fn set_unit(&mut self, unit: Unit) {
         let size_pixels = self.size_pixels();
-        match unit {
-            Unit::Pixels => self.size = size_pixels,
-            Unit::Inches => self.size = size_pixels / self.ppi as f32,
-            Unit::Centimeters => self.size = size_pixels / (self.ppi as f32 / 2.54),
-        }
+        let bleed_pixels = self.bleed_pixels();
         self.unit = unit;
+        let pixels_per_unit = self.pixels_per_unit();
+        self.size = size_pixels / pixels_per_unit;
+        self.bleed = bleed_pixels / pixels_per_unit;
+    }
+
+    pub fn set_bleed(&mut self, bleed: f32) {
+        self.bleed = bleed.max(0.0);
     }
 
     pub fn set_ppi(&mut self, ppi: i32) {
@@ -99,4 +125,5 @@ pub struct PageEditState {
     pub height: EditableValue<f32>,
     pub ppi: EditableValue<i32>,
     pub unit: EditableValue<Unit>,
+    pub bleed: EditableValue<f32>,
 }
@@ -0,0 +1,42 @@
+use chrono::{DateTime, Local, Utc};
+use egui::Pos2;
+
+use crate::id::{next_comment_id, CommentId};
+
+/// A review comment pinned to a spot on a page
+#[derive(Debug, Clone, PartialEq)]
+pub struct PageComment {
+    pub id: CommentId,
+    // Relative to the page, 0 to 1 on each axis, so pins stay put when the page is resized
+    pub position: Pos2,
+    pub text: String,
+    pub resolved: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+impl PageComment {
+    pub fn new(position: Pos2) -> Self {
+        Self {
+            id: next_comment_id(),
+            position: position.clamp(Pos2::ZERO, Pos2::new(1.0, 1.0)),
+            text: String::new(),
+            resolved: false,
+            created_at: Utc::now(),
+        }
+    }
+
+    pub fn status(&self) -> &'static str {
+        if self.resolved {
+            "Resolved"
+        } else {
+            "Open"
+        }
+    }
+
+    pub fn created_label(&self) -> String {
+        self.created_at
+            .with_timezone(&Local)
+            .format("%Y-%m-%d %H:%M")
+            .to_string()
+    }
+}
@@ -0,0 +1,37 @@
+/// Something left to do on a page before the book is ordered, e.g. "confirm names"
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChecklistItem {
+    pub text: String,
+    pub done: bool,
+}
+
+impl ChecklistItem {
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            done: false,
+        }
+    }
+}
+
+/// Free form notes and a checklist kept with a page. Like comments they aren't part of the undo
+/// history and don't show up in exports.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct PageNotes {
+    pub text: String,
+    pub checklist: Vec<ChecklistItem>,
+}
+
+impl PageNotes {
+    pub fn is_empty(&self) -> bool {
+        self.text.trim().is_empty() && self.checklist.is_empty()
+    }
+
+    pub fn open_tasks(&self) -> impl Iterator<Item = &ChecklistItem> {
+        self.checklist.iter().filter(|item| !item.done)
+    }
+
+    pub fn open_task_count(&self) -> usize {
+        self.open_tasks().count()
+    }
+}
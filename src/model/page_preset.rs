@@ -0,0 +1,107 @@
+use egui::Vec2;
+use serde::{Deserialize, Serialize};
+
+use super::{page::Page, unit::Unit};
+
+// How many recently used sizes are remembered
+pub const MAX_RECENT_PAGE_SIZES: usize = 5;
+
+const DEFAULT_PPI: i32 = 300;
+
+/// A named page size that can be applied to the project default or to a single page
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PagePreset {
+    pub name: String,
+    pub size: Vec2,
+    pub unit: Unit,
+    pub ppi: i32,
+    // In the preset's unit, added around each edge of the page
+    #[serde(default)]
+    pub bleed: f32,
+}
+
+impl PagePreset {
+    fn inches(name: &str, width: f32, height: f32) -> Self {
+        Self {
+            name: name.to_string(),
+            size: Vec2::new(width, height),
+            unit: Unit::Inches,
+            ppi: DEFAULT_PPI,
+            bleed: 0.125,
+        }
+    }
+
+    fn centimeters(name: &str, width: f32, height: f32) -> Self {
+        Self {
+            name: name.to_string(),
+            size: Vec2::new(width, height),
+            unit: Unit::Centimeters,
+            ppi: DEFAULT_PPI,
+            bleed: 0.3,
+        }
+    }
+
+    /// Standard photo book sizes offered by most printers
+    pub fn builtin() -> Vec<PagePreset> {
+        vec![
+            Self::inches("8\" × 8\" Square", 8.0, 8.0),
+            Self::inches("10\" × 10\" Square", 10.0, 10.0),
+            Self::inches("12\" × 12\" Square", 12.0, 12.0),
+            Self::inches("11\" × 8.5\" Landscape", 11.0, 8.5),
+            Self::inches("8.5\" × 11\" Portrait", 8.5, 11.0),
+            Self::inches("14\" × 11\" Landscape", 14.0, 11.0),
+            Self::centimeters("A4 Portrait", 21.0, 29.7),
+            Self::centimeters("A4 Landscape", 29.7, 21.0),
+            Self::centimeters("A5 Landscape", 21.0, 14.8),
+        ]
+    }
+
+    /// A preset for a page that isn't saved anywhere yet, named after its size
+    pub fn from_page(page: &Page) -> Self {
+        Self {
+            name: Self::size_label(page.size(), page.unit()),
+            size: page.size(),
+            unit: page.unit(),
+            ppi: page.ppi(),
+            bleed: page.bleed(),
+        }
+    }
+
+    pub fn page(&self) -> Page {
+        let mut page = Page::new(self.size, self.ppi, self.unit);
+        page.set_bleed(self.bleed);
+        page
+    }
+
+    /// Whether applying the preset would leave the page unchanged, regardless of units
+    pub fn matches(&self, page: &Page) -> bool {
+        let preset_page = self.page();
+        (preset_page.size_pixels() - page.size_pixels()).length() < 0.5
+            && preset_page.ppi() == page.ppi()
+            && (preset_page.bleed_pixels() - page.bleed_pixels()).abs() < 0.5
+    }
+
+    pub fn size_label(size: Vec2, unit: Unit) -> String {
+        let suffix = match unit {
+            Unit::Pixels => " px",
+            Unit::Inches => "\"",
+            Unit::Centimeters => " cm",
+        };
+        format!(
+            "{}{} × {}{}",
+            Self::trimmed(size.x),
+            suffix,
+            Self::trimmed(size.y),
+            suffix
+        )
+    }
+
+    // 8.50 shows as 8.5 and 8.00 as 8
+    fn trimmed(value: f32) -> String {
+        let formatted = format!("{:.2}", value);
+        formatted
+            .trim_end_matches('0')
+            .trim_end_matches('.')
+            .to_string()
+    }
+}
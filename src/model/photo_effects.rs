@@ -0,0 +1,63 @@
+use std::f32::consts::SQRT_2;
+
+use egui::Vec2;
+
+pub const DEFAULT_VIGNETTE_FEATHER: f32 = 0.5;
+
+/// Effects drawn with a photo layer. Like adjustments they never change the photo itself.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PhotoEffects {
+    // In page pixels, no more than half the photo's shorter side is used
+    pub corner_radius: f32,
+    // How much the vignette darkens the edges, from 0, no vignette, to 1, black
+    pub vignette_strength: f32,
+    // How gradually the vignette fades in, from 0, a hard oval, to 1, from the center out
+    pub vignette_feather: f32,
+}
+
+impl Default for PhotoEffects {
+    fn default() -> Self {
+        Self {
+            corner_radius: 0.0,
+            vignette_strength: 0.0,
+            vignette_feather: DEFAULT_VIGNETTE_FEATHER,
+        }
+    }
+}
+
+impl PhotoEffects {
+    pub fn is_none(&self) -> bool {
+        self.corner_radius <= 0.0 && self.vignette_strength <= 0.0
+    }
+
+    /// The corner radius of a photo of `size` drawn at `scale` times its size on the page
+    pub fn corner_radius_at(&self, size: Vec2, scale: f32) -> f32 {
+        (self.corner_radius * scale).clamp(0.0, size.min_elem() / 2.0)
+    }
+
+    /// How much the vignette darkens a point `distance` from the center of the photo, measured so
+    /// the middle of each edge is at 1 and the corners at √2
+    pub fn vignette_at(&self, distance: f32) -> f32 {
+        if self.vignette_strength <= 0.0 {
+            return 0.0;
+        }
+
+        // The fade is centered on the oval through the middle of the edges and widens with the
+        // feather, reaching its full strength by the corners
+        let feather = self.vignette_feather.clamp(0.0, 1.0);
+        let start = 1.0 - feather;
+        let end = 1.0 + feather * (SQRT_2 - 1.0);
+
+        let t = if end - start <= f32::EPSILON {
+            if distance >= 1.0 {
+                1.0
+            } else {
+                0.0
+            }
+        } else {
+            ((distance - start) / (end - start)).clamp(0.0, 1.0)
+        };
+
+        self.vignette_strength.clamp(0.0, 1.0) * t * t * (3.0 - 2.0 * t)
+    }
+}
@@ -0,0 +1,210 @@
+use std::f32::consts::PI;
+
+use egui::{emath::Rot2, Mesh, Pos2, Rect, Vec2};
+
+use crate::photo::PhotoRotation;
+
+/// Rotation and flips the user applied to a placed photo. They're applied on top of the
+/// orientation from the photo's EXIF data, so a photo that's already upright stays upright until
+/// rotated. EXIF mirrors the stored image before rotating it while these flips are in display
+/// space, after all rotation, so flipping horizontally always mirrors the photo left to right as
+/// it's seen on the page.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PhotoOrientation {
+    // Clockwise quarter turns, 0 to 3
+    quarter_turns: u8,
+    flip_horizontal: bool,
+    flip_vertical: bool,
+}
+
+impl PhotoOrientation {
+    pub fn new(quarter_turns: u8, flip_horizontal: bool, flip_vertical: bool) -> Self {
+        Self {
+            quarter_turns: quarter_turns % 4,
+            flip_horizontal,
+            flip_vertical,
+        }
+    }
+
+    pub fn quarter_turns(&self) -> u8 {
+        self.quarter_turns
+    }
+
+    pub fn is_flipped_horizontal(&self) -> bool {
+        self.flip_horizontal
+    }
+
+    pub fn is_flipped_vertical(&self) -> bool {
+        self.flip_vertical
+    }
+
+    pub fn rotate_cw(&mut self) {
+        self.rotate_quarter_turns(1);
+    }
+
+    pub fn rotate_ccw(&mut self) {
+        self.rotate_quarter_turns(3);
+    }
+
+    fn rotate_quarter_turns(&mut self, turns: u8) {
+        // Flips are applied after the rotation, so with a single flip a rotation on screen is
+        // the opposite rotation underneath it
+        let turns = if self.flip_horizontal != self.flip_vertical {
+            4 - turns
+        } else {
+            turns
+        };
+        self.quarter_turns = (self.quarter_turns + turns) % 4;
+    }
+
+    pub fn flip_horizontal(&mut self) {
+        self.flip_horizontal = !self.flip_horizontal;
+    }
+
+    pub fn flip_vertical(&mut self) {
+        self.flip_vertical = !self.flip_vertical;
+    }
+
+    fn total_quarter_turns(&self, exif_rotation: PhotoRotation) -> u8 {
+        (exif_rotation.quarter_turns() + self.quarter_turns) % 4
+    }
+
+    /// The combined EXIF and user rotation
+    pub fn radians(&self, exif_rotation: PhotoRotation) -> f32 {
+        self.total_quarter_turns(exif_rotation) as f32 * PI / 2.0
+    }
+
+    /// Whether the photo is shown on its side, swapping its width and height
+    pub fn is_sideways(&self, exif_rotation: PhotoRotation) -> bool {
+        self.total_quarter_turns(exif_rotation) % 2 == 1
+    }
+
+    /// The size of the photo as it's shown, from the size of the stored image
+    pub fn oriented_size(&self, exif_rotation: PhotoRotation, image_size: Vec2) -> Vec2 {
+        if self.is_sideways(exif_rotation) {
+            Vec2::new(image_size.y, image_size.x)
+        } else {
+            image_size
+        }
+    }
+
+    /// The unrotated rect the photo's mesh is built in before it's rotated into `display_rect`
+    pub fn mesh_rect(&self, exif_rotation: PhotoRotation, display_rect: Rect) -> Rect {
+        if self.is_sideways(exif_rotation) {
+            Rect::from_center_size(
+                display_rect.center(),
+                Vec2::new(display_rect.height(), display_rect.width()),
+            )
+        } else {
+            display_rect
+        }
+    }
+
+    /// Rotates and flips a mesh built in `mesh_rect` into place around `center`
+    pub fn orient_mesh(&self, exif_rotation: PhotoRotation, mesh: &mut Mesh, center: Pos2) {
+        if exif_rotation.is_mirrored() {
+            for vertex in &mut mesh.vertices {
+                vertex.pos.x = 2.0 * center.x - vertex.pos.x;
+            }
+        }
+
+        mesh.rotate(Rot2::from_angle(self.radians(exif_rotation)), center);
+
+        if self.flip_horizontal || self.flip_vertical {
+            for vertex in &mut mesh.vertices {
+                if self.flip_horizontal {
+                    vertex.pos.x = 2.0 * center.x - vertex.pos.x;
+                }
+                if self.flip_vertical {
+                    vertex.pos.y = 2.0 * center.y - vertex.pos.y;
+                }
+            }
+        }
+    }
+
+    /// Maps a normalized rect on the stored image, like a crop, to the same area of the photo
+    /// as it's shown
+    pub fn image_rect_to_display(&self, exif_rotation: PhotoRotation, rect: Rect) -> Rect {
+        let turns = self.total_quarter_turns(exif_rotation);
+        self.map_rect(rect, |point| {
+            self.flip(rotate_cw(exif_mirror(point, exif_rotation), turns))
+        })
+    }
+
+    /// The inverse of [`Self::image_rect_to_display`]
+    pub fn display_rect_to_image(&self, exif_rotation: PhotoRotation, rect: Rect) -> Rect {
+        let turns = self.total_quarter_turns(exif_rotation);
+        self.map_rect(rect, |point| {
+            exif_mirror(rotate_cw(self.flip(point), 4 - turns), exif_rotation)
+        })
+    }
+
+    fn flip(&self, point: Vec2) -> Vec2 {
+        Vec2::new(
+            if self.flip_horizontal {
+                -point.x
+            } else {
+                point.x
+            },
+            if self.flip_vertical {
+                -point.y
+            } else {
+                point.y
+            },
+        )
+    }
+
+    // Maps the corners of a normalized rect around the center of the photo. Quarter turns and
+    // flips keep rects axis aligned so the corners are enough.
+    fn map_rect(&self, rect: Rect, map: impl Fn(Vec2) -> Vec2) -> Rect {
+        let center = Vec2::splat(0.5);
+        let a = map(rect.min.to_vec2() - center) + center;
+        let b = map(rect.max.to_vec2() - center) + center;
+        Rect::from_two_pos(a.to_pos2(), b.to_pos2())
+    }
+}
+
+/// A single rotate or flip, as exposed in the action bar and shortcuts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrientCommand {
+    RotateCw,
+    RotateCcw,
+    FlipHorizontal,
+    FlipVertical,
+}
+
+impl OrientCommand {
+    pub fn apply(&self, orientation: &mut PhotoOrientation) {
+        match self {
+            OrientCommand::RotateCw => orientation.rotate_cw(),
+            OrientCommand::RotateCcw => orientation.rotate_ccw(),
+            OrientCommand::FlipHorizontal => orientation.flip_horizontal(),
+            OrientCommand::FlipVertical => orientation.flip_vertical(),
+        }
+    }
+}
+
+impl std::fmt::Display for OrientCommand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OrientCommand::RotateCw => write!(f, "Rotate Right"),
+            OrientCommand::RotateCcw => write!(f, "Rotate Left"),
+            OrientCommand::FlipHorizontal => write!(f, "Flip Horizontal"),
+            OrientCommand::FlipVertical => write!(f, "Flip Vertical"),
+        }
+    }
+}
+
+// Clockwise on screen, where y points down
+fn rotate_cw(point: Vec2, quarter_turns: u8) -> Vec2 {
+    (0..quarter_turns % 4).fold(point, |point, _| Vec2::new(-point.y, point.x))
+}
+
+// EXIF mirrors the stored image left to right before rotating it
+fn exif_mirror(point: Vec2, exif_rotation: PhotoRotation) -> Vec2 {
+    if exif_rotation.is_mirrored() {
+        Vec2::new(-point.x, point.y)
+    } else {
+        point
+    }
+}
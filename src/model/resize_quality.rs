@@ -0,0 +1,71 @@
+use std::fmt::{Display, Formatter};
+
+use egui::{TextureFilter, TextureOptions};
+use image::imageops::FilterType;
+use strum_macros::EnumIter;
+
+use crate::{
+    dependencies::{Dependency, SingletonFor},
+    project_settings::ProjectSettingsManager,
+};
+
+/// How photos are resampled when they're drawn smaller than their native size, on the canvas
+/// and in exports
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, EnumIter)]
+pub enum ResizeQuality {
+    Nearest,
+    #[default]
+    Bilinear,
+    Lanczos,
+}
+
+impl Display for ResizeQuality {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ResizeQuality::Nearest => write!(f, "Nearest"),
+            ResizeQuality::Bilinear => write!(f, "Bilinear"),
+            ResizeQuality::Lanczos => write!(f, "Lanczos"),
+        }
+    }
+}
+
+impl ResizeQuality {
+    /// The quality the current project is set to
+    pub fn current() -> Self {
+        Dependency::<ProjectSettingsManager>::get()
+            .with_lock(|manager| manager.project_settings.resize_quality)
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            ResizeQuality::Nearest => "Fastest, photos scaled down a lot look jagged",
+            ResizeQuality::Bilinear => "Smooth when photos are close to their native size",
+            ResizeQuality::Lanczos => {
+                "Sharpest and free of aliasing however far photos are scaled down, uses more memory"
+            }
+        }
+    }
+
+    /// How photo textures are sampled. Textures can't be sampled with a Lanczos filter so on the
+    /// canvas Lanczos uses mipmaps, which are made from the full size photo, to keep scaled down
+    /// photos from aliasing. Exports resample each photo with Lanczos to the size it's drawn at.
+    pub fn texture_options(&self) -> TextureOptions {
+        match self {
+            ResizeQuality::Nearest => TextureOptions::NEAREST,
+            ResizeQuality::Bilinear => TextureOptions::LINEAR,
+            ResizeQuality::Lanczos => TextureOptions {
+                mipmap_mode: Some(TextureFilter::Linear),
+                ..TextureOptions::LINEAR
+            },
+        }
+    }
+
+    /// The filter photos are scaled down with before they become textures
+    pub fn filter_type(&self) -> FilterType {
+        match self {
+            ResizeQuality::Nearest => FilterType::Nearest,
+            ResizeQuality::Bilinear => FilterType::Triangle,
+            ResizeQuality::Lanczos => FilterType::Lanczos3,
+        }
+    }
+}
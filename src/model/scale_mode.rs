@@ -1,5 +1,6 @@
 use std::fmt::{Display, Formatter};
 
+use egui::{Rect, Vec2};
 use strum_macros::EnumIter;
 
 #[derive(Debug, PartialEq, EnumIter, Clone, Copy)]
@@ -18,3 +19,71 @@ impl Display for ScaleMode {
         }
     }
 }
+
+impl ScaleMode {
+    /// The rect a photo of `photo_size` covers when placed in `region`. `photo_size` is the
+    /// size of the photo as displayed, so after any rotation.
+    pub fn photo_rect(&self, region: Rect, photo_size: Vec2, alignment: PhotoAlignment) -> Rect {
+        let size = match self {
+            ScaleMode::Fit => {
+                photo_size * (region.width() / photo_size.x).min(region.height() / photo_size.y)
+            }
+            ScaleMode::Fill => {
+                photo_size * (region.width() / photo_size.x).max(region.height() / photo_size.y)
+            }
+            ScaleMode::Stretch => region.size(),
+        };
+
+        Rect::from_min_size(
+            region.min + (region.size() - size) * alignment.to_vec2(),
+            size,
+        )
+    }
+}
+
+/// Which part of a photo stays visible when it overflows its region, or which side of the
+/// region it sits against when it doesn't fill it. (0, 0) is the top left and (1, 1) the
+/// bottom right.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct PhotoAlignment {
+    pub x: f32,
+    pub y: f32,
+}
+
+impl Default for PhotoAlignment {
+    fn default() -> Self {
+        Self::new(0.5, 0.5)
+    }
+}
+
+impl PhotoAlignment {
+    pub fn new(x: f32, y: f32) -> Self {
+        Self {
+            x: x.clamp(0.0, 1.0),
+            y: y.clamp(0.0, 1.0),
+        }
+    }
+
+    pub fn to_vec2(self) -> Vec2 {
+        Vec2::new(self.x, self.y)
+    }
+
+    /// Moves the photo by `delta` inside `region`, for dragging a photo that overflows its
+    /// region to choose the visible part. Axes the photo doesn't overflow are left unchanged.
+    pub fn panned(self, delta: Vec2, region: Rect, photo_rect: Rect) -> Self {
+        let overflow = region.size() - photo_rect.size();
+
+        let pan = |alignment: f32, delta: f32, overflow: f32| {
+            if overflow < 0.0 {
+                alignment + delta / overflow
+            } else {
+                alignment
+            }
+        };
+
+        Self::new(
+            pan(self.x, delta.x, overflow.x),
+            pan(self.y, delta.y, overflow.y),
+        )
+    }
+}
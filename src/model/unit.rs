@@ -1,8 +1,9 @@
 use std::{fmt::Display, str::FromStr};
 
+use serde::{Deserialize, Serialize};
 use strum_macros::EnumIter;
 
-#[derive(Debug, Clone, PartialEq, Copy, EnumIter)]
+#[derive(Debug, Clone, PartialEq, Copy, EnumIter, Serialize, Deserialize)]
 pub enum Unit {
     Pixels,
     Inches,
@@ -31,36 +32,3 @@ impl FromStr for Unit {
         }
     }
 }
-
-#[derive(Debug, Clone, Copy, PartialEq, EnumIter)]
-pub enum PageSizePreset {
-    Custom,
-    Square8x8,
-    Landscape8x11,
-    Portrait11x8,
-    Square12x12,
-}
-
-impl PageSizePreset {
-    pub fn dimensions(&self) -> Option<(f32, f32)> {
-        match self {
-            Self::Custom => None,
-            Self::Square8x8 => Some((8.0, 8.0)),
-            Self::Landscape8x11 => Some((11.0, 8.0)),
-            Self::Portrait11x8 => Some((8.0, 11.0)),
-            Self::Square12x12 => Some((12.0, 12.0)),
-        }
-    }
-}
-
-impl std::fmt::Display for PageSizePreset {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Self::Custom => write!(f, "Custom"),
-            Self::Square8x8 => write!(f, "8\" × 8\""),
-            Self::Landscape8x11 => write!(f, "11\" × 8\""),
-            Self::Portrait11x8 => write!(f, "8\" × 11\""),
-            Self::Square12x12 => write!(f, "12\" × 12\""),
-        }
-    }
-}
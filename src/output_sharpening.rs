@@ -0,0 +1,222 @@
+use std::convert::Infallible;
+
+use egui::{emath::Rot2, Pos2, Rect};
+use skia_safe::{image_filters, ClipOp, ImageFilter, Paint, Path, Point, Surface};
+use strum::IntoEnumIterator;
+use strum_macros::{Display, EnumIter};
+
+use crate::{
+    decoration::Decoration,
+    model::canvas_path::CanvasPath,
+    render::{self, RenderTarget},
+    widget::{
+        canvas::{CanvasPhoto, CanvasState},
+        canvas_info::layers::CanvasText,
+    },
+};
+
+// Sharpening applied to the photos on a page as it's rasterized for export, after it has been
+// scaled to the export size, the way photo editors sharpen their output. The layers themselves
+// are never changed. An unsharp mask adds back the difference between the page and a blurred copy
+// of it, only where a photo is what ends up on the page, so text, lines and decorations drawn
+// around or over the photos keep their edges.
+
+// Blur used for denoising, in pixels. Kept small so it only takes the grain out.
+const DENOISE_SIGMA: f32 = 0.8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Display, EnumIter)]
+pub enum SharpeningPreset {
+    // Ink spreads on paper so prints take more sharpening, over a wider radius
+    Print,
+    Screen,
+}
+
+impl SharpeningPreset {
+    pub fn settings(&self) -> OutputSharpening {
+        match self {
+            SharpeningPreset::Print => OutputSharpening {
+                amount: 0.8,
+                radius: 1.5,
+                denoise: 0.0,
+            },
+            SharpeningPreset::Screen => OutputSharpening {
+                amount: 0.5,
+                radius: 0.7,
+                denoise: 0.0,
+            },
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            SharpeningPreset::Print => "Stronger sharpening that holds up once printed",
+            SharpeningPreset::Screen => "Light sharpening for pages viewed on a screen",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OutputSharpening {
+    // How much of the detail found by the mask is added back, 0 leaves the photo as is
+    pub amount: f32,
+    // Size of the blur used to find detail, in exported pixels
+    pub radius: f32,
+    // How much of a light blur is mixed in before sharpening, from 0 to 1
+    pub denoise: f32,
+}
+
+impl OutputSharpening {
+    /// The preset these settings came from, None once they've been changed
+    pub fn preset(&self) -> Option<SharpeningPreset> {
+        SharpeningPreset::iter().find(|preset| preset.settings() == *self)
+    }
+
+    fn image_filter(&self) -> Option<ImageFilter> {
+        // None stands for the page as it was drawn
+        let mut filter: Option<ImageFilter> = None;
+
+        if self.denoise > 0.0 {
+            let blur = image_filters::blur((DENOISE_SIGMA, DENOISE_SIGMA), None, None, None)?;
+            filter = Some(image_filters::arithmetic(
+                0.0,
+                1.0 - self.denoise,
+                self.denoise,
+                0.0,
+                true,
+                blur,
+                None,
+                None,
+            )?);
+        }
+
+        if self.amount > 0.0 && self.radius > 0.0 {
+            let blur = image_filters::blur((self.radius, self.radius), None, filter.clone(), None)?;
+            filter = Some(image_filters::arithmetic(
+                0.0,
+                1.0 + self.amount,
+                -self.amount,
+                0.0,
+                true,
+                blur,
+                filter,
+                None,
+            )?);
+        }
+
+        filter
+    }
+
+    /// Sharpens the photos of `page` drawn to `surface` at `page_rect`
+    pub fn apply(&self, surface: &mut Surface, page: &CanvasState, page_rect: Rect) {
+        let Some(filter) = self.image_filter() else {
+            return;
+        };
+
+        let mut regions = LayerRegions(Vec::new());
+        render::render_page(page, page_rect, &mut regions).unwrap_or_else(|never| match never {});
+        if !regions.0.iter().any(|region| region.is_photo) {
+            return;
+        }
+
+        // Filtered from a snapshot so overlapping photos aren't sharpened twice
+        let image = surface.image_snapshot();
+        let mut paint = Paint::default();
+        paint.set_image_filter(filter);
+
+        let canvas = surface.canvas();
+        for (index, region) in regions.0.iter().enumerate() {
+            if !region.is_photo {
+                continue;
+            }
+            canvas.save();
+            canvas.clip_path(&region.path(), None, true);
+            // Anything drawn after the photo that isn't a photo covers it, leave that part alone
+            for above in regions.0[index + 1..]
+                .iter()
+                .filter(|above| !above.is_photo)
+            {
+                canvas.clip_path(&above.path(), ClipOp::Difference, true);
+            }
+            canvas.draw_image(&image, (0.0, 0.0), Some(&paint));
+            canvas.restore();
+        }
+    }
+}
+
+// The corners of a layer drawn on a page
+struct LayerRegion {
+    corners: [Pos2; 4],
+    is_photo: bool,
+}
+
+impl LayerRegion {
+    fn new(rect: Rect, rotation: f32, is_photo: bool) -> Self {
+        let rotation = Rot2::from_angle(rotation);
+        let center = rect.center();
+        Self {
+            corners: [
+                rect.left_top(),
+                rect.right_top(),
+                rect.right_bottom(),
+                rect.left_bottom(),
+            ]
+            .map(|corner| center + rotation * (corner - center)),
+            is_photo,
+        }
+    }
+
+    fn path(&self) -> Path {
+        let points = self.corners.map(|corner| Point::new(corner.x, corner.y));
+        Path::polygon(&points, true, None, None)
+    }
+}
+
+// Every layer on a page in the order it's drawn
+struct LayerRegions(Vec<LayerRegion>);
+
+impl RenderTarget for LayerRegions {
+    type Error = Infallible;
+
+    fn photo(
+        &mut self,
+        _photo: &CanvasPhoto,
+        rect: Rect,
+        rotation: f32,
+    ) -> Result<(), Self::Error> {
+        self.0.push(LayerRegion::new(rect, rotation, true));
+        Ok(())
+    }
+
+    fn template_photo(
+        &mut self,
+        _photo: &CanvasPhoto,
+        region_rect: Rect,
+        photo_rect: Rect,
+    ) -> Result<(), Self::Error> {
+        let rect = region_rect.intersect(photo_rect);
+        if rect.is_positive() {
+            self.0.push(LayerRegion::new(rect, 0.0, true));
+        }
+        Ok(())
+    }
+
+    fn text(&mut self, _text: &CanvasText, rect: Rect, rotation: f32) -> Result<(), Self::Error> {
+        self.0.push(LayerRegion::new(rect, rotation, false));
+        Ok(())
+    }
+
+    fn decoration(
+        &mut self,
+        _decoration: &Decoration,
+        rect: Rect,
+        rotation: f32,
+    ) -> Result<(), Self::Error> {
+        self.0.push(LayerRegion::new(rect, rotation, false));
+        Ok(())
+    }
+
+    fn path(&mut self, _path: &CanvasPath, rect: Rect, rotation: f32) -> Result<(), Self::Error> {
+        self.0.push(LayerRegion::new(rect, rotation, false));
+        Ok(())
+    }
+}
@@ -0,0 +1,305 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use egui::{load::SizedTexture, Context};
+use fxhash::hash64;
+use indexmap::{IndexMap, IndexSet};
+use log::{info, warn};
+
+use crate::{
+    auto_persisting::AutoPersisting,
+    config::Config,
+    dependencies::{Dependency, SingletonFor},
+    id::PageId,
+    photo::Photo,
+    photo_adjustments::PhotoAdjustments,
+    photo_manager::PhotoManager,
+    saliency::SaliencyCache,
+    widget::{canvas::CanvasState, canvas_info::layers::LayerContent},
+};
+
+// Pause between photos so the photo manager's queue isn't filled with every photo in the book.
+// Photos are loaded at prefetch priority so the visible pages' textures go first either way.
+const PRELOAD_INTERVAL: Duration = Duration::from_millis(20);
+const PENDING_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Loads the textures for every photo placed on a page in the background so page previews
+/// are ready before the pages panel is scrolled to them.
+/// Thumbnails are used when they exist since that's what the previews fall back to while the
+/// full resolution photo loads.
+/// The full resolution textures of the pages either side of the page being edited are loaded
+/// too so moving to the next or previous page doesn't wait on them.
+pub struct PagePreloader {
+    pages_hash: Option<u64>,
+    cancelled: Arc<AtomicBool>,
+    adjacent_hash: Option<u64>,
+    adjacent_cancelled: Arc<AtomicBool>,
+}
+
+impl PagePreloader {
+    pub fn new() -> Self {
+        Self {
+            pages_hash: None,
+            cancelled: Arc::new(AtomicBool::new(false)),
+            adjacent_hash: None,
+            adjacent_cancelled: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Starts preloading the photos on `pages`, cancelling any previous run if the photos have
+    /// changed since. This is cheap when nothing has changed so it can be called every frame.
+    pub fn preload(&mut self, ctx: &Context, pages: &IndexMap<PageId, CanvasState>) {
+        let photos = Self::page_photos(pages);
+        let pages_hash = hash64(&photos.iter().map(|photo| &photo.path).collect::<Vec<_>>());
+
+        if self.pages_hash == Some(pages_hash) {
+            return;
+        }
+
+        self.cancel();
+        self.pages_hash = Some(pages_hash);
+
+        // Photos on the pages are the ones crops get suggested for, made ahead so the crop scene
+        // rarely waits
+        let smart_crop = Dependency::<AutoPersisting<Config>>::get().with_lock(|config| {
+            config
+                .read()
+                .map(|config| config.smart_crop())
+                .unwrap_or(false)
+        });
+        if smart_crop {
+            Dependency::<SaliencyCache>::get().with_lock_mut(|cache| {
+                for photo in &photos {
+                    cache.prepare(photo);
+                }
+            });
+        }
+
+        let uris: IndexSet<String> = photos
+            .into_iter()
+            .map(|photo| match photo.thumbnail_path() {
+                Ok(thumbnail_path) if thumbnail_path.exists() => photo.thumbnail_uri(),
+                _ => photo.uri(),
+            })
+            .collect();
+
+        if uris.is_empty() {
+            return;
+        }
+
+        let cancelled = Arc::new(AtomicBool::new(false));
+        self.cancelled = cancelled.clone();
+
+        info!("Preloading {} page photos", uris.len());
+
+        tokio::spawn(Self::load_textures(ctx.clone(), uris, cancelled));
+    }
+
+    pub fn cancel(&mut self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+        self.pages_hash = None;
+        self.cancel_adjacent();
+    }
+
+    /// Starts loading the full resolution photos on the pages before and after `selected_page`,
+    /// the next page first. A run for another page is cancelled so jumping elsewhere doesn't
+    /// wait on pages that are no longer adjacent. Like [`Self::preload`] this can be called
+    /// every frame.
+    pub fn prefetch_adjacent(
+        &mut self,
+        ctx: &Context,
+        pages: &IndexMap<PageId, CanvasState>,
+        selected_page: PageId,
+    ) {
+        let Some(index) = pages.get_index_of(&selected_page) else {
+            self.cancel_adjacent();
+            return;
+        };
+
+        let photos: Vec<(Photo, PhotoAdjustments)> = [Some(index + 1), index.checked_sub(1)]
+            .into_iter()
+            .flatten()
+            .filter_map(|index| pages.get_index(index))
+            .flat_map(|(_, page)| Self::page_photo_layers(page))
+            .collect();
+
+        let adjacent_hash = hash64(&(
+            selected_page,
+            photos
+                .iter()
+                .map(|(photo, adjustments)| Self::texture_uri(photo, adjustments))
+                .collect::<Vec<_>>(),
+        ));
+
+        if self.adjacent_hash == Some(adjacent_hash) {
+            return;
+        }
+
+        self.cancel_adjacent();
+        self.adjacent_hash = Some(adjacent_hash);
+
+        if photos.is_empty() {
+            return;
+        }
+
+        let cancelled = Arc::new(AtomicBool::new(false));
+        self.adjacent_cancelled = cancelled.clone();
+
+        tokio::spawn(Self::prefetch_textures(ctx.clone(), photos, cancelled));
+    }
+
+    pub fn cancel_adjacent(&mut self) {
+        self.adjacent_cancelled.store(true, Ordering::Relaxed);
+        self.adjacent_hash = None;
+    }
+
+    fn page_photo_layers(page: &CanvasState) -> Vec<(Photo, PhotoAdjustments)> {
+        page.layers
+            .values()
+            .filter_map(|layer| match &layer.content {
+                LayerContent::Photo(photo)
+                | LayerContent::TemplatePhoto {
+                    photo: Some(photo), ..
+                } => Some((photo.photo.clone(), photo.adjustments)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    // The uri the canvas draws the photo with
+    fn texture_uri(photo: &Photo, adjustments: &PhotoAdjustments) -> String {
+        if adjustments.is_identity() {
+            photo.uri()
+        } else {
+            adjustments.uri(photo)
+        }
+    }
+
+    fn page_photos(pages: &IndexMap<PageId, CanvasState>) -> Vec<&Photo> {
+        pages
+            .values()
+            .flat_map(|page| page.layers.values())
+            .filter_map(|layer| match &layer.content {
+                LayerContent::Photo(photo)
+                | LayerContent::TemplatePhoto {
+                    photo: Some(photo), ..
+                } => Some(&photo.photo),
+                _ => None,
+            })
+            .collect()
+    }
+
+    async fn load_textures(ctx: Context, uris: IndexSet<String>, cancelled: Arc<AtomicBool>) {
+        for uri in uris {
+            // Already loaded, or being loaded because the page is visible
+            let requested = Dependency::<PhotoManager>::get()
+                .with_lock(|photo_manager| photo_manager.has_requested_texture(&uri));
+
+            if requested {
+                continue;
+            }
+
+            // Cached by the photo manager once it's loaded
+            if Self::load_texture(&ctx, &uri, &cancelled).await.is_none()
+                && cancelled.load(Ordering::Relaxed)
+            {
+                info!("Page preloading cancelled");
+                return;
+            }
+
+            tokio::time::sleep(PRELOAD_INTERVAL).await;
+        }
+
+        ctx.request_repaint();
+    }
+
+    async fn prefetch_textures(
+        ctx: Context,
+        photos: Vec<(Photo, PhotoAdjustments)>,
+        cancelled: Arc<AtomicBool>,
+    ) {
+        for (photo, adjustments) in photos {
+            if cancelled.load(Ordering::Relaxed) {
+                return;
+            }
+
+            let uri = Self::texture_uri(&photo, &adjustments);
+            let (requested, has_room) =
+                Dependency::<PhotoManager>::get().with_lock(|photo_manager| {
+                    (
+                        photo_manager.has_requested_texture(&uri),
+                        photo_manager.has_prefetch_room(),
+                    )
+                });
+
+            if requested {
+                continue;
+            }
+
+            if !has_room {
+                info!("Stopped prefetching adjacent pages, the texture budget is nearly used");
+                return;
+            }
+
+            if adjustments.is_identity() {
+                // Cached by the photo manager as a prefetched texture once it's loaded
+                Self::load_texture(&ctx, &uri, &cancelled).await;
+            } else {
+                let task_ctx = ctx.clone();
+                let texture = tokio::task::spawn_blocking(move || {
+                    PhotoManager::load_adjusted_texture(&photo, &adjustments, &task_ctx)
+                })
+                .await
+                .map_err(|err| warn!("Prefetch task for {} failed: {:?}", uri, err))
+                .ok()
+                .and_then(|texture| {
+                    texture
+                        .map_err(|err| warn!("Failed to prefetch {}: {:?}", uri, err))
+                        .ok()
+                });
+
+                // Kept even if cancelled meanwhile, it's the first to go if the budget runs out
+                if let Some(texture) = texture {
+                    Dependency::<PhotoManager>::get().with_lock_mut(|photo_manager| {
+                        photo_manager.cache_prefetched_texture(uri, texture, &ctx);
+                    });
+                }
+            }
+
+            tokio::time::sleep(PRELOAD_INTERVAL).await;
+        }
+    }
+
+    // Asks the photo manager for the texture until it's loaded. Returns None if the texture
+    // couldn't be loaded or loading was cancelled, a cancelled request goes stale in its queue.
+    async fn load_texture(
+        ctx: &Context,
+        uri: &str,
+        cancelled: &AtomicBool,
+    ) -> Option<SizedTexture> {
+        loop {
+            if cancelled.load(Ordering::Relaxed) {
+                return None;
+            }
+
+            let texture = Dependency::<PhotoManager>::get()
+                .with_lock_mut(|photo_manager| photo_manager.prefetch_texture(uri, ctx));
+
+            match texture {
+                Ok(Some(texture)) => return Some(texture),
+                Ok(None) => {
+                    tokio::time::sleep(PENDING_POLL_INTERVAL).await;
+                }
+                Err(err) => {
+                    warn!("Failed to preload {}: {:?}", uri, err);
+                    return None;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,165 @@
+use egui::{Pos2, Rect, Vec2};
+use strum_macros::EnumIter;
+
+use crate::widget::{
+    canvas::CanvasState,
+    canvas_info::layers::{Layer, LayerContent},
+};
+
+/// Where the layout ends up on a page of a different size
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, EnumIter)]
+pub enum RescaleAnchor {
+    /// Scaled to fit and centered, leaving equal margins on the longer side
+    #[default]
+    Center,
+    /// Scaled to fit and kept in the top left corner
+    TopLeft,
+    /// Layers are spread over the whole page but keep their aspect ratio
+    ScaleToFit,
+}
+
+impl std::fmt::Display for RescaleAnchor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RescaleAnchor::Center => write!(f, "Center"),
+            RescaleAnchor::TopLeft => write!(f, "Top Left"),
+            RescaleAnchor::ScaleToFit => write!(f, "Scale to Fit"),
+        }
+    }
+}
+
+/// Moves and resizes the layers of a page laid out for `from` so they fit a page of size `to`.
+/// Sizes are in pixels. Photos and text are scaled uniformly so they're never stretched, layers
+/// pinned to the page are scaled the same way but placed by their constraints.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PageRescale {
+    from: Vec2,
+    to: Vec2,
+    anchor: RescaleAnchor,
+}
+
+impl PageRescale {
+    pub fn new(from: Vec2, to: Vec2, anchor: RescaleAnchor) -> Self {
+        Self { from, to, anchor }
+    }
+
+    fn is_valid(&self) -> bool {
+        self.from.x > 0.0 && self.from.y > 0.0 && self.to.x > 0.0 && self.to.y > 0.0
+    }
+
+    // The factor layer sizes are scaled by
+    fn uniform_scale(&self) -> f32 {
+        (self.to.x / self.from.x).min(self.to.y / self.from.y)
+    }
+
+    pub fn transform_rect(&self, rect: Rect) -> Rect {
+        if !self.is_valid() {
+            return rect;
+        }
+
+        let scale = self.uniform_scale();
+        let size = rect.size() * scale;
+
+        let center = match self.anchor {
+            RescaleAnchor::Center => {
+                let offset = (self.to - self.from * scale) / 2.0;
+                rect.center().to_vec2() * scale + offset
+            }
+            RescaleAnchor::TopLeft => rect.center().to_vec2() * scale,
+            RescaleAnchor::ScaleToFit => rect.center().to_vec2() * (self.to / self.from),
+        };
+
+        Rect::from_center_size(Pos2::ZERO + center, size)
+    }
+
+    /// Applies the rescale to every layer on the page
+    pub fn apply(&self, canvas_state: &mut CanvasState) {
+        if !self.is_valid() || self.from == self.to {
+            return;
+        }
+
+        let scale = self.uniform_scale();
+
+        for layer in canvas_state.layers.values_mut() {
+            if layer.constraints.is_pinned() && layer.content.is_template() {
+                self.reflow_template_region(layer);
+            } else {
+                let rect = layer.transform_state.rect;
+                let moved = self.transform_rect(rect);
+                layer.transform_state.rect =
+                    layer.constraints.reflow(rect, moved, self.from, self.to);
+            }
+
+            match &mut layer.content {
+                LayerContent::Text(text) | LayerContent::TemplateText { text, .. } => {
+                    text.font_size *= scale;
+                }
+                LayerContent::Path(path) => {
+                    path.stroke_width *= scale;
+                }
+                LayerContent::Photo(_)
+                | LayerContent::TemplatePhoto { .. }
+                | LayerContent::Decoration(_) => {}
+            }
+        }
+
+        // The multi select bounds are rebuilt from the layers on the next frame
+        canvas_state.multi_select = None;
+    }
+
+    /// Moves only the pinned layers, keeping their size, for when the rest of the layout is kept
+    /// as it was. Returns true if any layer is pinned.
+    pub fn reflow_pinned(&self, canvas_state: &mut CanvasState) -> bool {
+        if !self.is_valid() || self.from == self.to {
+            return false;
+        }
+
+        let mut reflowed = false;
+
+        for layer in canvas_state
+            .layers
+            .values_mut()
+            .filter(|layer| layer.constraints.is_pinned())
+        {
+            if layer.content.is_template() {
+                self.reflow_template_region(layer);
+            } else {
+                let rect = layer.transform_state.rect;
+                layer.transform_state.rect =
+                    layer.constraints.reflow(rect, rect, self.from, self.to);
+            }
+            reflowed = true;
+        }
+
+        if reflowed {
+            canvas_state.multi_select = None;
+        }
+
+        reflowed
+    }
+
+    // Template layers are drawn from their region, which is relative to the page and so stretches
+    // with it. Pinned ones keep the stretched size but are placed by their constraints.
+    fn reflow_template_region(&self, layer: &mut Layer) {
+        let constraints = layer.constraints;
+        let (LayerContent::TemplatePhoto { region, .. }
+        | LayerContent::TemplateText { region, .. }) = &mut layer.content
+        else {
+            return;
+        };
+
+        let rect = Rect::from_min_size(
+            (region.relative_position.to_vec2() * self.from).to_pos2(),
+            region.relative_size * self.from,
+        );
+        let moved = Rect::from_min_size(
+            (region.relative_position.to_vec2() * self.to).to_pos2(),
+            region.relative_size * self.to,
+        );
+        let reflowed = constraints.reflow(rect, moved, self.from, self.to);
+
+        region.relative_position = (reflowed.min.to_vec2() / self.to).to_pos2();
+        region.relative_size = reflowed.size() / self.to;
+        layer.transform_state.rect = reflowed;
+    }
+}
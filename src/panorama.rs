@@ -0,0 +1,93 @@
+use egui::{Pos2, Rect, Vec2};
+use strum_macros::EnumIter;
+
+use crate::{
+    photo::Photo,
+    widget::{
+        canvas::CanvasState,
+        canvas_info::layers::{Layer, LayerContent, LayerTransformEditState},
+    },
+};
+
+/// How a panorama is laid out on new pages
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter)]
+pub enum PanoramaPlacement {
+    /// Across a left and right page, split down the middle
+    FullSpread,
+    /// Edge to edge on a single page
+    FullBleedPage,
+}
+
+impl std::fmt::Display for PanoramaPlacement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PanoramaPlacement::FullSpread => write!(f, "Place as Full Spread"),
+            PanoramaPlacement::FullBleedPage => write!(f, "Place as Full-Bleed Page"),
+        }
+    }
+}
+
+impl PanoramaPlacement {
+    /// New pages, using the project's default page size, with the photo cropped to cover them.
+    /// The photo touches every edge so the bleed is filled from the photo when the book is
+    /// exported.
+    pub fn pages(&self, photo: &Photo) -> Vec<CanvasState> {
+        match self {
+            PanoramaPlacement::FullBleedPage => {
+                let mut page = CanvasState::new();
+                let crop = Self::cover_crop(photo, page.page.size_pixels().x, &page);
+                Self::add_cover_layer(&mut page, photo, crop);
+                vec![page]
+            }
+            PanoramaPlacement::FullSpread => {
+                let mut left = CanvasState::new();
+                let mut right = CanvasState::new();
+
+                let crop = Self::cover_crop(photo, left.page.size_pixels().x * 2.0, &left);
+                let middle = crop.center().x;
+
+                Self::add_cover_layer(
+                    &mut left,
+                    photo,
+                    Rect::from_min_max(crop.min, Pos2::new(middle, crop.max.y)),
+                );
+                Self::add_cover_layer(
+                    &mut right,
+                    photo,
+                    Rect::from_min_max(Pos2::new(middle, crop.min.y), crop.max),
+                );
+
+                vec![left, right]
+            }
+        }
+    }
+
+    // The centered crop, in the space of the photo as it's shown, that covers an area `width`
+    // wide and as tall as the page without stretching
+    fn cover_crop(photo: &Photo, width: f32, page: &CanvasState) -> Rect {
+        let target_aspect_ratio = width / page.page.size_pixels().y;
+        let photo_aspect_ratio = photo.aspect_ratio();
+
+        let size = if photo_aspect_ratio > target_aspect_ratio {
+            Vec2::new(target_aspect_ratio / photo_aspect_ratio, 1.0)
+        } else {
+            Vec2::new(1.0, photo_aspect_ratio / target_aspect_ratio)
+        };
+
+        Rect::from_center_size(Pos2::new(0.5, 0.5), size)
+    }
+
+    fn add_cover_layer(page: &mut CanvasState, photo: &Photo, display_crop: Rect) {
+        let mut layer = Layer::with_photo(photo.clone());
+        layer.transform_state.rect = Rect::from_min_size(Pos2::ZERO, page.page.size_pixels());
+        layer.transform_edit_state = LayerTransformEditState::from(&layer.transform_state);
+
+        if let LayerContent::Photo(canvas_photo) = &mut layer.content {
+            let crop = canvas_photo.display_crop_to_image(display_crop);
+            canvas_photo.set_crop(crop);
+        }
+
+        page.layers.insert(layer.id, layer);
+        page.update_quick_layout_order();
+    }
+}
@@ -14,6 +14,7 @@ use tokio::io::BufReader as TokioBufReader;
 use crate::{
     dependencies::{Dependency, Singleton, SingletonFor},
     dirs::Dirs,
+    model::color_label::ColorLabel,
     photo_manager::PhotoManager,
     utils::ExifDateTimeExt,
 };
@@ -22,9 +23,10 @@ use eframe::{
     emath::Rot2,
     epaint::{Pos2, Rect, Vec2},
 };
+use egui::{load::SizedTexture, Image, Ui};
 
 use chrono::{DateTime, Utc};
-use exif::{In, Reader, Tag, Value};
+use exif::{Context, In, Reader, Tag, Value};
 use fxhash::hash64;
 use log::error;
 use serde::{Deserialize, Serialize};
@@ -52,6 +54,9 @@ macro_rules! metadata_fields {
     }
 }
 
+// Photos at least this many times wider than they are tall are panoramas
+pub const PANORAMA_ASPECT_RATIO: f32 = 2.0;
+
 #[derive(Debug, thiserror::Error)]
 pub enum PhotoError {
     #[error("Failed to load photo: {0}")]
@@ -63,7 +68,7 @@ pub enum MaxPhotoDimension {
     Height,
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, EnumIter)]
 pub enum PhotoRotation {
     Normal,
     MirrorHorizontal,
@@ -98,6 +103,38 @@ impl PhotoRotation {
         }
     }
 
+    /// Clockwise quarter turns, 0 to 3
+    pub fn quarter_turns(&self) -> u8 {
+        match self {
+            Self::Normal | Self::MirrorHorizontal => 0,
+            Self::MirrorHorizontalAndRotate90CW | Self::Rotate90CW => 1,
+            Self::Rotate180 | Self::MirrorVerticalAndRotate180 => 2,
+            Self::MirrorHorizontalAndRotate270CW | Self::Rotate270CW => 3,
+        }
+    }
+
+    /// Whether the image is mirrored horizontally before it's rotated. A vertical mirror is a
+    /// horizontal one turned upside down.
+    pub fn is_mirrored(&self) -> bool {
+        match self {
+            Self::Normal | Self::Rotate180 | Self::Rotate90CW | Self::Rotate270CW => false,
+            Self::MirrorHorizontal
+            | Self::MirrorVerticalAndRotate180
+            | Self::MirrorHorizontalAndRotate90CW
+            | Self::MirrorHorizontalAndRotate270CW => true,
+        }
+    }
+
+    /// The uv of the whole image with the mirror applied, egui rotates images after mapping
+    /// them so this goes with [`Self::radians`]
+    pub fn mirror_uv(&self) -> Rect {
+        if self.is_mirrored() {
+            Rect::from_min_max(Pos2::new(1.0, 0.0), Pos2::new(0.0, 1.0))
+        } else {
+            Rect::from_min_max(Pos2::ZERO, Pos2::new(1.0, 1.0))
+        }
+    }
+
     pub fn is_horizontal(&self) -> bool {
         match self {
             Self::Normal => true,
@@ -142,6 +179,12 @@ pub struct Rational {
     pub denom: i32,
 }
 
+impl Rational {
+    pub fn value(&self) -> Option<f32> {
+        (self.denom != 0).then(|| self.num as f32 / self.denom as f32)
+    }
+}
+
 impl MetadataCollection {
     pub fn new() -> Self {
         Self {
@@ -170,7 +213,10 @@ metadata_fields!(
     (ISO, u32),
     (ShutterSpeed, Rational),
     (Aperture, Rational),
-    (FocalLength, Rational)
+    (FocalLength, Rational),
+    (Lens, String),
+    (StarRating, u32),
+    (Keywords, Vec<String>)
 );
 
 impl Display for PhotoMetadataField {
@@ -198,6 +244,9 @@ impl Display for PhotoMetadataField {
             PhotoMetadataField::FocalLength(focal_length) => {
                 write!(f, "{}mm", focal_length.num / focal_length.denom)
             }
+            PhotoMetadataField::Lens(lens) => write!(f, "{}", lens),
+            PhotoMetadataField::StarRating(stars) => write!(f, "{} of 5", stars),
+            PhotoMetadataField::Keywords(keywords) => f.write_str(&keywords.join(", ")),
         }
     }
 }
@@ -217,6 +266,9 @@ impl Display for PhotoMetadataFieldLabel {
             PhotoMetadataFieldLabel::ShutterSpeed => f.write_str("Shutter Speed"),
             PhotoMetadataFieldLabel::Aperture => f.write_str("Aperture"),
             PhotoMetadataFieldLabel::FocalLength => f.write_str("Focal Length"),
+            PhotoMetadataFieldLabel::Lens => f.write_str("Lens"),
+            PhotoMetadataFieldLabel::StarRating => f.write_str("Stars"),
+            PhotoMetadataFieldLabel::Keywords => f.write_str("Keywords"),
         }
     }
 }
@@ -224,6 +276,10 @@ impl Display for PhotoMetadataFieldLabel {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PhotoMetadata {
     pub fields: MetadataCollection,
+    // Picked in the photo info when the EXIF orientation is wrong or missing. It's saved with
+    // the project rather than cached with the metadata read from the file.
+    #[serde(skip)]
+    pub orientation_override: Option<PhotoRotation>,
 }
 
 impl PhotoMetadata {
@@ -359,6 +415,43 @@ impl PhotoMetadata {
                 }
             }
 
+            if let Some(field) = exif.get_field(Tag::LensModel, In::PRIMARY) {
+                if let Value::Ascii(ref vec) = field.value {
+                    if let Some(value) = vec.first() {
+                        let lens = String::from_utf8_lossy(value).trim().to_string();
+                        if !lens.is_empty() {
+                            fields.insert(PhotoMetadataField::Lens(lens));
+                        }
+                    }
+                }
+            }
+
+            // The 0 to 5 stars set in other photo apps, Windows stores it in IFD0 as Rating
+            if let Some(field) = exif.get_field(Tag(Context::Tiff, 0x4746), In::PRIMARY) {
+                if let Some(value) = field.value.get_uint(0) {
+                    fields.insert(PhotoMetadataField::StarRating(value.min(5)));
+                }
+            }
+
+            // Windows keeps tags as XPKeywords, UTF-16 text separated by semicolons
+            if let Some(field) = exif.get_field(Tag(Context::Tiff, 0x9C9E), In::PRIMARY) {
+                if let Value::Byte(ref bytes) = field.value {
+                    let units: Vec<u16> = bytes
+                        .chunks_exact(2)
+                        .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+                        .collect();
+                    let keywords: Vec<String> = String::from_utf16_lossy(&units)
+                        .trim_end_matches('\0')
+                        .split(';')
+                        .map(|keyword| keyword.trim().to_string())
+                        .filter(|keyword| !keyword.is_empty())
+                        .collect();
+                    if !keywords.is_empty() {
+                        fields.insert(PhotoMetadataField::Keywords(keywords));
+                    }
+                }
+            }
+
             if let Some(field) = exif.get_field(Tag::FocalLength, In::PRIMARY) {
                 if let Value::Rational(ref vec) = field.value {
                     if let Some(value) = vec.first() {
@@ -388,6 +481,7 @@ impl PhotoMetadata {
 
         Ok(Self {
             fields: Self::process_metadata(path, exif, size),
+            orientation_override: None,
         })
     }
 
@@ -401,6 +495,7 @@ impl PhotoMetadata {
 
         Ok(Self {
             fields: Self::process_metadata(path, exif, size),
+            orientation_override: None,
         })
     }
 
@@ -418,7 +513,13 @@ impl PhotoMetadata {
         }
     }
 
+    /// The orientation the photo is shown in, the override if there is one
     pub fn rotation(&self) -> PhotoRotation {
+        self.orientation_override
+            .unwrap_or_else(|| self.exif_rotation())
+    }
+
+    pub fn exif_rotation(&self) -> PhotoRotation {
         match self.fields.get(PhotoMetadataFieldLabel::Rotation) {
             Some(PhotoMetadataField::Rotation(rotation)) => *rotation,
             _ => PhotoRotation::Normal,
@@ -426,39 +527,81 @@ impl PhotoMetadata {
     }
 
     pub fn rotated_width(&self) -> usize {
-        match self.fields.get(PhotoMetadataFieldLabel::RotatedWidth) {
-            Some(PhotoMetadataField::RotatedWidth(rotated_width)) => *rotated_width,
-            _ => 0,
+        if self.does_rotation_alter_dimensions() {
+            self.height()
+        } else {
+            self.width()
         }
     }
 
     pub fn rotated_height(&self) -> usize {
-        match self.fields.get(PhotoMetadataFieldLabel::RotatedHeight) {
-            Some(PhotoMetadataField::RotatedHeight(rotated_height)) => *rotated_height,
-            _ => 0,
+        if self.does_rotation_alter_dimensions() {
+            self.width()
+        } else {
+            self.height()
         }
     }
 
     pub fn does_rotation_alter_dimensions(&self) -> bool {
-        match self.fields.get(PhotoMetadataFieldLabel::Rotation) {
-            Some(PhotoMetadataField::Rotation(rotation)) => match rotation {
-                PhotoRotation::Normal => false,
-                PhotoRotation::MirrorHorizontal => false,
-                PhotoRotation::Rotate180 => false,
-                PhotoRotation::MirrorVerticalAndRotate180 => false,
-                PhotoRotation::MirrorHorizontalAndRotate90CW => true,
-                PhotoRotation::Rotate90CW => true,
-                PhotoRotation::MirrorHorizontalAndRotate270CW => true,
-                PhotoRotation::Rotate270CW => true,
-            },
-            _ => false,
-        }
+        !self.rotation().is_horizontal()
     }
 
     pub fn get(&self, label: PhotoMetadataFieldLabel) -> Option<&PhotoMetadataField> {
         self.fields.get(label)
     }
 
+    pub fn camera(&self) -> Option<&str> {
+        match self.fields.get(PhotoMetadataFieldLabel::Camera) {
+            Some(PhotoMetadataField::Camera(camera)) => Some(camera.trim()),
+            _ => None,
+        }
+    }
+
+    pub fn lens(&self) -> Option<&str> {
+        match self.fields.get(PhotoMetadataFieldLabel::Lens) {
+            Some(PhotoMetadataField::Lens(lens)) => Some(lens.as_str()),
+            _ => None,
+        }
+    }
+
+    pub fn iso(&self) -> Option<u32> {
+        match self.fields.get(PhotoMetadataFieldLabel::ISO) {
+            Some(PhotoMetadataField::ISO(iso)) => Some(*iso),
+            _ => None,
+        }
+    }
+
+    /// The f-number, e.g. 2.8 for f/2.8
+    pub fn aperture(&self) -> Option<f32> {
+        match self.fields.get(PhotoMetadataFieldLabel::Aperture) {
+            Some(PhotoMetadataField::Aperture(aperture)) => aperture.value(),
+            _ => None,
+        }
+    }
+
+    /// In millimeters
+    pub fn focal_length(&self) -> Option<f32> {
+        match self.fields.get(PhotoMetadataFieldLabel::FocalLength) {
+            Some(PhotoMetadataField::FocalLength(focal_length)) => focal_length.value(),
+            _ => None,
+        }
+    }
+
+    /// The 0 to 5 stars another photo app gave the photo
+    pub fn star_rating(&self) -> Option<u32> {
+        match self.fields.get(PhotoMetadataFieldLabel::StarRating) {
+            Some(PhotoMetadataField::StarRating(stars)) => Some(*stars),
+            _ => None,
+        }
+    }
+
+    pub fn keywords(&self) -> &[String] {
+        match self.fields.get(PhotoMetadataFieldLabel::Keywords) {
+            Some(PhotoMetadataField::Keywords(keywords)) => keywords,
+            _ => &[],
+        }
+    }
+
     pub fn iter(&self) -> impl Iterator<Item = (PhotoMetadataFieldLabel, &PhotoMetadataField)> {
         vec![
             PhotoMetadataFieldLabel::Path,
@@ -473,6 +616,9 @@ impl PhotoMetadata {
             PhotoMetadataFieldLabel::ShutterSpeed,
             PhotoMetadataFieldLabel::Aperture,
             PhotoMetadataFieldLabel::FocalLength,
+            PhotoMetadataFieldLabel::Lens,
+            PhotoMetadataFieldLabel::StarRating,
+            PhotoMetadataFieldLabel::Keywords,
         ]
         .into_iter()
         .filter_map(|label| self.fields.get(label).map(|value| (label, value)))
@@ -508,6 +654,8 @@ pub struct Photo {
     pub metadata: PhotoMetadata,
     pub thumbnail_hash: String,
     pub rating: PhotoRating,
+    #[serde(default)]
+    pub color_label: Option<ColorLabel>,
 }
 
 impl Photo {
@@ -520,6 +668,7 @@ impl Photo {
             metadata,
             thumbnail_hash,
             rating,
+            color_label: None,
         })
     }
 
@@ -532,6 +681,7 @@ impl Photo {
             metadata,
             thumbnail_hash,
             rating,
+            color_label: None,
         })
     }
 
@@ -547,6 +697,39 @@ impl Photo {
         Ok(res)
     }
 
+    /// A photo built from metadata that was already read, like the metadata saved with a project
+    pub fn with_metadata(path: PathBuf, metadata: PhotoMetadata, rating: PhotoRating) -> Self {
+        let thumbnail_hash = hash64(&path.to_string_lossy()).to_string();
+        Self {
+            path,
+            metadata,
+            thumbnail_hash,
+            rating,
+            color_label: None,
+        }
+    }
+
+    /// A photo whose file can't be found. Only the path is known so the photo can still be
+    /// shown as missing and relinked later.
+    pub fn missing(path: PathBuf, rating: PhotoRating) -> Self {
+        let mut fields = MetadataCollection::new();
+        fields.insert(PhotoMetadataField::Path(path.clone()));
+        fields.insert(PhotoMetadataField::Rotation(PhotoRotation::Normal));
+
+        let thumbnail_hash = hash64(&path.to_string_lossy()).to_string();
+
+        Self {
+            path,
+            metadata: PhotoMetadata {
+                fields,
+                orientation_override: None,
+            },
+            thumbnail_hash,
+            rating,
+            color_label: None,
+        }
+    }
+
     pub fn file_name(&self) -> &str {
         match self.path.file_name() {
             Some(file_name) => file_name.to_str().unwrap_or("Unknown"),
@@ -586,6 +769,11 @@ impl Photo {
         self.metadata.rotated_width() as f32 / self.metadata.rotated_height() as f32
     }
 
+    /// Whether the photo is wide enough to be shown across a spread
+    pub fn is_panorama(&self) -> bool {
+        self.aspect_ratio() >= PANORAMA_ASPECT_RATIO
+    }
+
     pub fn size_with_max_size(&self, max_size: f32) -> (f32, f32) {
         let (width, height) = match self.max_dimension() {
             MaxPhotoDimension::Width => {
@@ -601,6 +789,28 @@ impl Photo {
         };
         (width, height)
     }
+
+    /// Paints the photo's texture as large as it fits in `rect`, centered and turned to the
+    /// photo's orientation
+    pub fn paint_fitted(&self, ui: &Ui, texture: SizedTexture, rect: Rect) {
+        let rotation = self.metadata.rotation();
+        let image_size = Vec2::new(self.metadata.width() as f32, self.metadata.height() as f32);
+        let displayed_size = if self.metadata.does_rotation_alter_dimensions() {
+            Vec2::new(image_size.y, image_size.x)
+        } else {
+            image_size
+        };
+        let scale = (rect.width() / displayed_size.x).min(rect.height() / displayed_size.y);
+
+        // Painted unrotated around the center then rotated into place
+        Image::from_texture(texture)
+            .uv(rotation.mirror_uv())
+            .rotate(rotation.radians(), Vec2::splat(0.5))
+            .paint_at(
+                ui,
+                Rect::from_center_size(rect.center(), image_size * scale),
+            );
+    }
 }
 
 impl PartialEq for Photo {
@@ -0,0 +1,202 @@
+use std::path::Path;
+
+use egui::ColorImage;
+use image::{imageops::FilterType, DynamicImage, RgbaImage};
+
+use crate::photo::Photo;
+
+// Fraction of the darkest and brightest pixels ignored when finding the black and white points
+const CLIP_FRACTION: f32 = 0.005;
+// Stretching a very flat histogram only amplifies noise
+const MIN_TONAL_RANGE: f32 = 0.1;
+const MAX_WHITE_BALANCE_GAIN: f32 = 1.5;
+// Analysing a small copy of the photo is much faster and gives practically the same result
+const ANALYSIS_SIZE: u32 = 512;
+
+#[derive(Debug, thiserror::Error)]
+pub enum AdjustmentError {
+    #[error("Image error: {0}")]
+    Image(#[from] image::ImageError),
+}
+
+/// Non-destructive tone and color corrections for a placed photo. The photo file is never
+/// modified, the adjustments are applied whenever the photo is drawn.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PhotoAdjustments {
+    // Input levels mapped to black and white, 0.0 to 1.0
+    pub black_point: f32,
+    pub white_point: f32,
+    // Per channel red, green and blue multipliers
+    pub white_balance: [f32; 3],
+    // How much of the adjustments are applied, 0.0 leaves the photo unchanged
+    pub strength: f32,
+}
+
+impl Default for PhotoAdjustments {
+    fn default() -> Self {
+        Self {
+            black_point: 0.0,
+            white_point: 1.0,
+            white_balance: [1.0; 3],
+            strength: 1.0,
+        }
+    }
+}
+
+impl PhotoAdjustments {
+    /// Computes levels and white balance from the photo's histogram
+    pub fn auto(photo: &Photo) -> Result<Self, AdjustmentError> {
+        // The thumbnail is already small so prefer it when it exists
+        let image = match photo.thumbnail_path() {
+            Ok(thumbnail_path) if thumbnail_path.exists() => image::open(thumbnail_path)?,
+            _ => image::open(&photo.path)?,
+        };
+
+        Ok(Self::from_histogram(
+            &image.thumbnail(ANALYSIS_SIZE, ANALYSIS_SIZE),
+        ))
+    }
+
+    pub fn from_histogram(image: &DynamicImage) -> Self {
+        let image = image.to_rgb8();
+
+        let mut luminance_histogram = [0u32; 256];
+        let mut channel_sums = [0f64; 3];
+
+        for pixel in image.pixels() {
+            let [r, g, b] = pixel.0;
+            luminance_histogram[Self::luminance(r, g, b) as usize] += 1;
+            channel_sums[0] += r as f64;
+            channel_sums[1] += g as f64;
+            channel_sums[2] += b as f64;
+        }
+
+        let pixel_count = (image.width() * image.height()) as usize;
+        if pixel_count == 0 {
+            return Self::default();
+        }
+
+        let mut adjustments = Self::default();
+
+        let black_point = Self::percentile(&luminance_histogram, CLIP_FRACTION) as f32 / 255.0;
+        let white_point =
+            Self::percentile(&luminance_histogram, 1.0 - CLIP_FRACTION) as f32 / 255.0;
+
+        if white_point - black_point >= MIN_TONAL_RANGE {
+            adjustments.black_point = black_point;
+            adjustments.white_point = white_point;
+        }
+
+        // Gray world: a typical scene averages out to neutral gray, so any cast in the average
+        // color is assumed to come from the lighting
+        let means = channel_sums.map(|sum| (sum / pixel_count as f64) as f32);
+        let gray = (means[0] + means[1] + means[2]) / 3.0;
+        if means.iter().all(|mean| *mean > 0.0) {
+            adjustments.white_balance = means.map(|mean| {
+                (gray / mean).clamp(1.0 / MAX_WHITE_BALANCE_GAIN, MAX_WHITE_BALANCE_GAIN)
+            });
+        }
+
+        adjustments
+    }
+
+    pub fn is_identity(&self) -> bool {
+        self.strength == 0.0
+            || (self.black_point == 0.0
+                && self.white_point == 1.0
+                && self.white_balance == [1.0; 3])
+    }
+
+    /// A stable key for caching the adjusted texture of a photo
+    pub fn uri(&self, photo: &Photo) -> String {
+        let bits = [
+            self.black_point,
+            self.white_point,
+            self.white_balance[0],
+            self.white_balance[1],
+            self.white_balance[2],
+            self.strength,
+        ]
+        .map(f32::to_bits);
+
+        format!("{}#adjusted-{:x}", photo.uri(), fxhash::hash64(&bits))
+    }
+
+    /// Each channel's adjustment as `slope * value + intercept` on values from 0.0 to 1.0,
+    /// with the strength already applied
+    pub fn linear_transfer(&self) -> [(f32, f32); 3] {
+        let strength = self.strength.clamp(0.0, 1.0);
+        let black_point = self.black_point * strength;
+        let white_point = 1.0 + (self.white_point - 1.0) * strength;
+        let range = (white_point - black_point).max(f32::EPSILON);
+
+        [0, 1, 2].map(|channel| {
+            let gain = 1.0 + (self.white_balance[channel] - 1.0) * strength;
+            (gain / range, -black_point / range)
+        })
+    }
+
+    // Lookup tables mapping each input channel value to its adjusted value
+    fn lookup_tables(&self) -> [[u8; 256]; 3] {
+        self.linear_transfer().map(|(slope, intercept)| {
+            let mut table = [0u8; 256];
+            for (value, adjusted) in table.iter_mut().enumerate() {
+                let linear = value as f32 / 255.0 * slope + intercept;
+                *adjusted = (linear.clamp(0.0, 1.0) * 255.0).round() as u8;
+            }
+            table
+        })
+    }
+
+    pub fn apply(&self, image: &mut RgbaImage) {
+        let tables = self.lookup_tables();
+        for pixel in image.pixels_mut() {
+            for channel in 0..3 {
+                pixel.0[channel] = tables[channel][pixel.0[channel] as usize];
+            }
+        }
+    }
+
+    /// Loads the photo with the adjustments applied, scaled down to fit `max_size` with `filter`
+    /// if needed. Orientation is left to the renderer like any other photo texture.
+    pub fn adjusted_image(
+        &self,
+        path: &Path,
+        max_size: usize,
+        filter: FilterType,
+    ) -> Result<ColorImage, AdjustmentError> {
+        let mut image = image::open(path)?;
+
+        let max_size = max_size.min(u32::MAX as usize) as u32;
+        if image.width() > max_size || image.height() > max_size {
+            image = image.resize(max_size, max_size, filter);
+        }
+
+        let mut image = image.to_rgba8();
+        self.apply(&mut image);
+
+        Ok(ColorImage::from_rgba_unmultiplied(
+            [image.width() as usize, image.height() as usize],
+            image.as_raw(),
+        ))
+    }
+
+    fn luminance(r: u8, g: u8, b: u8) -> u8 {
+        (0.2126 * r as f32 + 0.7152 * g as f32 + 0.0722 * b as f32).round() as u8
+    }
+
+    fn percentile(histogram: &[u32; 256], fraction: f32) -> usize {
+        let total: u32 = histogram.iter().sum();
+        let target = (total as f32 * fraction) as u32;
+
+        let mut count = 0;
+        for (value, bucket) in histogram.iter().enumerate() {
+            count += bucket;
+            if count > target {
+                return value;
+            }
+        }
+
+        255
+    }
+}
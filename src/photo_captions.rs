@@ -0,0 +1,216 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use strum_macros::EnumIter;
+
+use crate::{
+    photo::{Photo, PhotoMetadataField, PhotoMetadataFieldLabel},
+    photo_manager::PhotoManager,
+};
+
+// Prefixes cameras and phones put in front of the sequence number of a file name
+const CAMERA_PREFIXES: [&str; 12] = [
+    "MVIMG", "IMG", "DSCN", "DSCF", "DSC", "PXL", "GOPR", "DJI", "SAM", "VID", "PANO", "_MG",
+];
+
+/// Text attached to a photo in the library, used by text layers and captions on pages
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PhotoCaptions {
+    pub title: Option<String>,
+    pub caption: Option<String>,
+}
+
+impl PhotoCaptions {
+    pub fn get(&self, target: CaptionTarget) -> Option<&str> {
+        match target {
+            CaptionTarget::Title => self.title.as_deref(),
+            CaptionTarget::Caption => self.caption.as_deref(),
+        }
+    }
+
+    pub fn set(&mut self, target: CaptionTarget, text: Option<String>) {
+        let text = text.filter(|text| !text.trim().is_empty());
+        match target {
+            CaptionTarget::Title => self.title = text,
+            CaptionTarget::Caption => self.caption = text,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.title.is_none() && self.caption.is_none()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter)]
+pub enum CaptionTarget {
+    Title,
+    Caption,
+}
+
+impl std::fmt::Display for CaptionTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CaptionTarget::Title => write!(f, "Title"),
+            CaptionTarget::Caption => write!(f, "Caption"),
+        }
+    }
+}
+
+/// Builds a title or caption for each photo from a template like `{folder} - {name}`.
+///
+/// Tokens:
+/// - `{name}` the file name without its extension, cleaned up with the pattern's options
+/// - `{file}` the file name without its extension, as is
+/// - `{folder}` the name of the folder the photo is in, often the event it was taken at
+/// - `{date}` the capture date, `{year}` and `{month}` parts of it
+/// - `{camera}` the camera from the photo's EXIF data
+/// - `{index}` the position of the photo in the batch, starting at 1
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CaptionPattern {
+    pub template: String,
+    pub strip_camera_prefix: bool,
+    pub separators_to_spaces: bool,
+    pub title_case: bool,
+}
+
+impl Default for CaptionPattern {
+    fn default() -> Self {
+        Self {
+            template: "{name}".to_string(),
+            strip_camera_prefix: true,
+            separators_to_spaces: true,
+            title_case: true,
+        }
+    }
+}
+
+impl CaptionPattern {
+    pub const TOKENS: [&'static str; 8] = [
+        "{name}", "{file}", "{folder}", "{date}", "{year}", "{month}", "{camera}", "{index}",
+    ];
+
+    /// The text for `photo`, empty if every token in the template was empty
+    pub fn apply(&self, photo: &Photo, index: usize) -> String {
+        let file = file_stem(&photo.path);
+        let capture_date = if self.uses(&["{date}", "{year}", "{month}"]) {
+            PhotoManager::capture_date(photo)
+        } else {
+            None
+        };
+
+        let camera = match photo.metadata.fields.get(PhotoMetadataFieldLabel::Camera) {
+            Some(PhotoMetadataField::Camera(camera)) => camera.trim().to_string(),
+            _ => String::new(),
+        };
+
+        let folder = photo
+            .path
+            .parent()
+            .and_then(|parent| parent.file_name())
+            .map(|folder| self.clean(&folder.to_string_lossy(), false))
+            .unwrap_or_default();
+
+        let text = self
+            .template
+            .replace("{name}", &self.clean(&file, self.strip_camera_prefix))
+            .replace("{file}", &file)
+            .replace("{folder}", &folder)
+            .replace(
+                "{date}",
+                &capture_date
+                    .map(|date| date.format("%B %-d, %Y").to_string())
+                    .unwrap_or_default(),
+            )
+            .replace(
+                "{year}",
+                &capture_date
+                    .map(|date| date.format("%Y").to_string())
+                    .unwrap_or_default(),
+            )
+            .replace(
+                "{month}",
+                &capture_date
+                    .map(|date| date.format("%B").to_string())
+                    .unwrap_or_default(),
+            )
+            .replace("{camera}", &camera)
+            .replace("{index}", &(index + 1).to_string());
+
+        // Separators left around empty tokens, like the dash in "{folder} - {camera}"
+        text.split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .trim_matches(|c: char| c == '-' || c == ',' || c == '|' || c.is_whitespace())
+            .to_string()
+    }
+
+    fn uses(&self, tokens: &[&str]) -> bool {
+        tokens.iter().any(|token| self.template.contains(token))
+    }
+
+    fn clean(&self, text: &str, strip_camera_prefix: bool) -> String {
+        let mut text = text.to_string();
+
+        if strip_camera_prefix {
+            text = strip_prefix(&text);
+        }
+
+        if self.separators_to_spaces {
+            text = text
+                .replace(['_', '-', '.'], " ")
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" ");
+        }
+
+        if self.title_case {
+            text = title_case(&text);
+        }
+
+        text
+    }
+}
+
+fn file_stem(path: &Path) -> String {
+    path.file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+// "IMG_1234 Beach" becomes "1234 Beach", names without a known prefix are left alone. The
+// prefix has to be followed by a separator or a number so names like "Samantha" are kept.
+fn strip_prefix(text: &str) -> String {
+    for prefix in CAMERA_PREFIXES {
+        let Some(head) = text.get(..prefix.len()) else {
+            continue;
+        };
+
+        let rest = &text[prefix.len()..];
+        let followed_by_separator = rest
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_digit() || c == '_' || c == '-' || c == ' ');
+
+        if head.eq_ignore_ascii_case(prefix) && followed_by_separator {
+            let rest = rest.trim_start_matches(['_', '-', ' ']);
+            if !rest.is_empty() {
+                return rest.to_string();
+            }
+        }
+    }
+    text.to_string()
+}
+
+// Only the first letter of each word is changed so acronyms like "NYC" are kept
+fn title_case(text: &str) -> String {
+    text.split(' ')
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
@@ -0,0 +1,193 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs::Metadata,
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::photo::PhotoMetadata;
+
+// Bumped whenever the stored metadata changes shape so stale databases are rebuilt instead of
+// misread
+const DATABASE_VERSION: u32 = 3;
+
+const DATABASE_EXTENSION: &str = "rpbdb";
+
+#[derive(Error, Debug)]
+pub enum PhotoDatabaseError {
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+
+    #[error("Serde error: {0}")]
+    SerdeError(#[from] serde_json::Error),
+}
+
+/// What a photo's file looked like when its metadata was read. A file with a different stamp has
+/// changed since and needs to be read again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileStamp {
+    // Milliseconds since the Unix epoch
+    modified: u64,
+    size: u64,
+}
+
+impl FileStamp {
+    pub fn from_metadata(metadata: &Metadata) -> Option<Self> {
+        let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+        Some(Self {
+            modified: modified.as_millis() as u64,
+            size: metadata.len(),
+        })
+    }
+
+    pub async fn read_async(path: &Path) -> Option<Self> {
+        let metadata = tokio::fs::metadata(path).await.ok()?;
+        Self::from_metadata(&metadata)
+    }
+}
+
+/// The shooting details photos are filtered by, pulled out of the metadata once when a photo is
+/// added so filtering doesn't go through every photo's metadata fields
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ExifFacets {
+    pub camera: Option<String>,
+    pub lens: Option<String>,
+    pub iso: Option<u32>,
+    pub aperture: Option<f32>,
+    // In millimeters
+    pub focal_length: Option<f32>,
+}
+
+impl ExifFacets {
+    pub fn new(metadata: &PhotoMetadata) -> Self {
+        Self {
+            camera: metadata
+                .camera()
+                .filter(|camera| !camera.is_empty())
+                .map(str::to_string),
+            lens: metadata.lens().map(str::to_string),
+            iso: metadata.iso(),
+            aperture: metadata.aperture(),
+            focal_length: metadata.focal_length(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct PhotoDatabaseEntry {
+    path: PathBuf,
+    stamp: FileStamp,
+    metadata: PhotoMetadata,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct StoredPhotoDatabase {
+    version: u32,
+    entries: Vec<PhotoDatabaseEntry>,
+}
+
+/// Photo metadata saved next to the project so opening it only reads the photos that changed on
+/// disk since it was last saved
+#[derive(Debug, Clone, Default)]
+pub struct PhotoDatabase {
+    entries: HashMap<PathBuf, PhotoDatabaseEntry>,
+    // Kept alongside the entries rather than saved, it's rebuilt when the database is loaded
+    facets: HashMap<PathBuf, ExifFacets>,
+}
+
+impl PhotoDatabase {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Where the database for the project at `project_path` lives
+    pub fn path_for(project_path: &Path) -> PathBuf {
+        project_path.with_extension(DATABASE_EXTENSION)
+    }
+
+    /// Reads the database saved with the project. A missing or unreadable database just means
+    /// every photo is read again.
+    pub fn load(project_path: &Path) -> Self {
+        let path = Self::path_for(project_path);
+        if !path.exists() {
+            return Self::new();
+        }
+
+        let stored: StoredPhotoDatabase = match std::fs::File::open(&path)
+            .map_err(PhotoDatabaseError::from)
+            .and_then(|file| Ok(serde_json::from_reader(std::io::BufReader::new(file))?))
+        {
+            Ok(stored) => stored,
+            Err(err) => {
+                warn!("Ignoring unreadable photo database {:?}: {:?}", path, err);
+                return Self::new();
+            }
+        };
+
+        if stored.version != DATABASE_VERSION {
+            info!(
+                "Rebuilding photo database {:?} saved with version {}",
+                path, stored.version
+            );
+            return Self::new();
+        }
+
+        let mut database = Self::new();
+        for entry in stored.entries {
+            database.insert(entry.path, entry.stamp, entry.metadata);
+        }
+        database
+    }
+
+    /// Writes the database next to the project. It's written to a temporary file first so a
+    /// failed save can't leave a truncated database behind.
+    pub fn save(&self, project_path: &Path) -> Result<(), PhotoDatabaseError> {
+        let path = Self::path_for(project_path);
+        let temp_path = path.with_extension(format!("{}.tmp", DATABASE_EXTENSION));
+
+        let stored = StoredPhotoDatabase {
+            version: DATABASE_VERSION,
+            entries: self.entries.values().cloned().collect(),
+        };
+
+        std::fs::write(&temp_path, serde_json::to_vec(&stored)?)?;
+        std::fs::rename(&temp_path, &path)?;
+
+        Ok(())
+    }
+
+    /// The saved metadata for the photo, as long as its file hasn't changed since it was read
+    pub fn cached_metadata(&self, path: &Path, stamp: &FileStamp) -> Option<PhotoMetadata> {
+        self.entries
+            .get(path)
+            .filter(|entry| entry.stamp == *stamp)
+            .map(|entry| entry.metadata.clone())
+    }
+
+    pub fn insert(&mut self, path: PathBuf, stamp: FileStamp, metadata: PhotoMetadata) {
+        self.facets.insert(path.clone(), ExifFacets::new(&metadata));
+        self.entries.insert(
+            path.clone(),
+            PhotoDatabaseEntry {
+                path,
+                stamp,
+                metadata,
+            },
+        );
+    }
+
+    /// Drops the photos that are no longer part of the project
+    pub fn retain_paths(&mut self, paths: &HashSet<&PathBuf>) {
+        self.entries.retain(|path, _| paths.contains(path));
+        self.facets.retain(|path, _| paths.contains(path));
+    }
+
+    /// The indexed shooting details of the photo
+    pub fn facets(&self, path: &Path) -> Option<&ExifFacets> {
+        self.facets.get(path)
+    }
+}
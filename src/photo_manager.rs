@@ -1,13 +1,15 @@
 use std::{
+    cmp::Ordering,
     collections::{HashMap, HashSet},
+    fmt::Display,
     io::BufWriter,
-    path::PathBuf,
+    path::{Path, PathBuf},
 };
 
 use glob::MatchOptions;
 
 use chrono::{DateTime, Datelike, Utc};
-use eframe::egui::{load::SizedTexture, Context};
+use eframe::egui::{load::SizedTexture, Context, Id, TextureHandle};
 use egui::emath::OrderedFloat;
 use fxhash::hash64;
 use image::{
@@ -15,14 +17,32 @@ use image::{
     ExtendedColorType,
 };
 use indexmap::IndexMap;
-use log::{error, info};
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize};
+use strum_macros::EnumIter;
+use tokio::sync::mpsc;
 use tokio::task::spawn_blocking;
 use tokio::{fs::File as TokioFile, io::AsyncWriteExt};
 
 use crate::{
+    animation_still::{self, ANIMATION_EXTENSIONS},
     dependencies::{Dependency, Singleton},
     dirs::Dirs,
-    photo::{self, Photo, PhotoMetadataField, PhotoMetadataFieldLabel, PhotoRating},
+    error_sink::ErrorSink,
+    modal::{manager::ModalManager, progress::ProgressModal},
+    model::{color_label::ColorLabel, resize_quality::ResizeQuality},
+    photo::{
+        self, Photo, PhotoError, PhotoMetadataField, PhotoMetadataFieldLabel, PhotoRating,
+        PhotoRotation,
+    },
+    photo_adjustments::{AdjustmentError, PhotoAdjustments},
+    photo_captions::{CaptionTarget, PhotoCaptions},
+    photo_database::{ExifFacets, FileStamp, PhotoDatabase},
+    photo_query::{PhotoQuery, QueryError},
+    photo_stacks::PhotoStacks,
+    smart_collection::SmartCollection,
+    texture_cache::{TextureCache, TextureMetrics},
+    texture_load_queue::{LoadPriority, TextureLoadQueue},
 };
 
 use anyhow::{anyhow, Ok};
@@ -40,6 +60,10 @@ use crate::utils;
 
 const THUMBNAIL_SIZE: f32 = 256.0;
 
+const IMPORT_WORKERS: usize = 8;
+const IMPORT_QUEUE_SIZE: usize = 64;
+const IMPORT_REGROUP_INTERVAL: usize = 100;
+
 #[derive(Clone, Debug)]
 pub enum PhotoLoadResult {
     Pending(PathBuf),
@@ -55,9 +79,10 @@ impl PhotoLoadResult {
     }
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Copy)]
+#[derive(Clone, Debug, PartialEq, Eq, Copy, EnumIter)]
 pub enum PhotosGrouping {
     Date,
+    Folder,
     Rating,
 }
 
@@ -67,13 +92,176 @@ impl Default for PhotosGrouping {
     }
 }
 
+impl Display for PhotosGrouping {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PhotosGrouping::Date => f.write_str("Day"),
+            PhotosGrouping::Folder => f.write_str("Folder"),
+            PhotosGrouping::Rating => f.write_str("Rating"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Copy, EnumIter, Serialize, Deserialize)]
+pub enum PhotosSortField {
+    CaptureDate,
+    FileName,
+    Rating,
+    ImportDate,
+    FileSize,
+}
+
+impl Display for PhotosSortField {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PhotosSortField::CaptureDate => f.write_str("Capture Date"),
+            PhotosSortField::FileName => f.write_str("File Name"),
+            PhotosSortField::Rating => f.write_str("Rating"),
+            PhotosSortField::ImportDate => f.write_str("Import Date"),
+            PhotosSortField::FileSize => f.write_str("File Size"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Copy, Serialize, Deserialize)]
+pub enum SortDirection {
+    Ascending,
+    Descending,
+}
+
+impl SortDirection {
+    pub fn apply(&self, ordering: Ordering) -> Ordering {
+        match self {
+            SortDirection::Ascending => ordering,
+            SortDirection::Descending => ordering.reverse(),
+        }
+    }
+
+    pub fn toggled(&self) -> Self {
+        match self {
+            SortDirection::Ascending => SortDirection::Descending,
+            SortDirection::Descending => SortDirection::Ascending,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Copy, Serialize, Deserialize)]
+pub struct PhotosSort {
+    pub field: PhotosSortField,
+    pub direction: SortDirection,
+}
+
+impl Default for PhotosSort {
+    fn default() -> Self {
+        Self {
+            field: PhotosSortField::CaptureDate,
+            direction: SortDirection::Descending,
+        }
+    }
+}
+
+/// The gallery search. The query is compiled once when the text changes and whether each photo
+/// matches is cached until the query or the photo changes.
+#[derive(Debug, Default)]
+struct PhotoSearch {
+    text: String,
+    query: Option<PhotoQuery>,
+    error: Option<QueryError>,
+    matches: HashMap<PathBuf, bool>,
+}
+
+impl PhotoSearch {
+    fn new(text: String) -> Self {
+        let mut search = Self::default();
+        search.set_text(text);
+        search
+    }
+
+    // An invalid query keeps the last valid one applied so the gallery doesn't flicker while typing
+    fn set_text(&mut self, text: String) {
+        if text.trim().is_empty() {
+            self.query = None;
+            self.error = None;
+        } else {
+            match PhotoQuery::parse(&text) {
+                Result::Ok(query) => {
+                    self.query = Some(query);
+                    self.error = None;
+                }
+                Result::Err(err) => {
+                    self.error = Some(err);
+                }
+            }
+        }
+
+        self.text = text;
+        self.matches.clear();
+    }
+
+    fn matches(&mut self, photo: &Photo, photo_database: &PhotoDatabase) -> bool {
+        let Some(query) = &self.query else {
+            return true;
+        };
+
+        *self.matches.entry(photo.path.clone()).or_insert_with(|| {
+            // Photos are indexed as their metadata is read, details of any that aren't are read here
+            match photo_database.facets(&photo.path) {
+                Some(facets) => query.matches(photo, facets),
+                None => query.matches(photo, &ExifFacets::new(&photo.metadata)),
+            }
+        })
+    }
+}
+
+/// Texture loads that haven't finished, for the diagnostics panel
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PendingLoads {
+    pub queued: usize,
+    pub loading: usize,
+    // Textures loaded outside the queue, like adjusted ones
+    pub unqueued: usize,
+}
+
+#[derive(Debug)]
+struct ActiveCollection {
+    name: String,
+    search: PhotoSearch,
+}
+
+/// A photo as it's saved in a project: its path, rating, label, when it was imported and the
+/// orientation picked for it
+pub type SavedPhoto = (
+    PathBuf,
+    Option<PhotoRating>,
+    Option<ColorLabel>,
+    Option<DateTime<Utc>>,
+    Option<PhotoRotation>,
+);
+
 #[derive(Debug)]
 pub struct PhotoManager {
     pub photos: IndexMap<PathBuf, Photo>, // TODO: Use an Arc or something
     grouped_photos: (PhotosGrouping, IndexMap<String, IndexMap<PathBuf, Photo>>), // TODO: Use an Arc or something
-    texture_cache: HashMap<String, SizedTexture>,
+    sort: PhotosSort,
+    import_dates: HashMap<PathBuf, DateTime<Utc>>,
+    captions: HashMap<PathBuf, PhotoCaptions>,
+    search: PhotoSearch,
+    collection: Option<ActiveCollection>,
+    // The gallery's own sort while a collection with its own sort order is shown
+    library_sort: Option<PhotosSort>,
+    stacks: PhotoStacks,
+    texture_cache: TextureCache,
+    // Textures loaded outside the queue, adjusted ones being made or that failed and blocking
+    // loads still waiting on their file
     pending_textures: HashSet<String>,
+    load_queue: TextureLoadQueue,
     thumbnail_existence_cache: HashSet<String>,
+    // Photos whose files couldn't be found when they were loaded
+    missing_photos: HashSet<PathBuf>,
+    // Metadata saved with the project so photos that haven't changed aren't read again
+    photo_database: PhotoDatabase,
+    // Photos whose orientation override changed, taken by the pages to update placed copies
+    orientation_changes: Vec<Photo>,
 }
 
 impl PhotoManager {
@@ -81,9 +269,39 @@ impl PhotoManager {
         Self {
             photos: IndexMap::new(),
             grouped_photos: (PhotosGrouping::default(), IndexMap::new()),
-            texture_cache: HashMap::new(),
+            sort: PhotosSort::default(),
+            import_dates: HashMap::new(),
+            captions: HashMap::new(),
+            search: PhotoSearch::default(),
+            collection: None,
+            library_sort: None,
+            stacks: PhotoStacks::default(),
+            texture_cache: TextureCache::new(),
             pending_textures: HashSet::new(),
+            load_queue: TextureLoadQueue::new(),
             thumbnail_existence_cache: HashSet::new(),
+            missing_photos: HashSet::new(),
+            photo_database: PhotoDatabase::new(),
+            orientation_changes: Vec::new(),
+        }
+    }
+
+    pub fn photo_database(&self) -> &PhotoDatabase {
+        &self.photo_database
+    }
+
+    pub fn set_photo_database(&mut self, photo_database: PhotoDatabase) {
+        self.photo_database = photo_database;
+    }
+
+    /// Saves the metadata of the library's photos next to the project at `project_path`. The
+    /// database only speeds up opening the project so failing to save it isn't an error.
+    pub fn save_photo_database(&self, project_path: &Path) {
+        let mut photo_database = self.photo_database.clone();
+        photo_database.retain_paths(&self.photos.keys().collect());
+
+        if let Err(err) = photo_database.save(project_path) {
+            warn!("Failed to save photo database: {:?}", err);
         }
     }
 
@@ -93,10 +311,11 @@ impl PhotoManager {
 
     pub fn load_directory(path: PathBuf) -> anyhow::Result<()> {
         tokio::spawn(async move {
-            let glob_patterns = vec![
-                format!("{}/**/*.jpg", path.to_string_lossy()),
-                format!("{}/**/*.jpeg", path.to_string_lossy()),
-            ];
+            let glob_patterns = ["jpg", "jpeg"]
+                .iter()
+                .chain(ANIMATION_EXTENSIONS.iter())
+                .map(|extension| format!("{}/**/*.{}", path.to_string_lossy(), extension))
+                .collect::<Vec<_>>();
 
             let glob_iter = glob_patterns.iter().flat_map(|pattern: &String| {
                 glob::glob_with(
@@ -110,9 +329,15 @@ impl PhotoManager {
                 .unwrap()
             });
 
+            let mut animations = Vec::new();
             let pending_photos: Vec<PathBuf> = glob_iter
                 .filter_map(|entry| {
                     let path = entry.as_ref().ok()?;
+                    if animation_still::is_animation(path) {
+                        animations.push(path.clone());
+                        return None;
+                    }
+
                     let lowercase_extension = path.extension()?.to_ascii_lowercase();
                     if (lowercase_extension == "jpg" || lowercase_extension == "jpeg")
                         && !Dependency::<PhotoManager>::get().with_lock(|pm| pm.photo_exists(path))
@@ -124,76 +349,233 @@ impl PhotoManager {
                 })
                 .collect();
 
-            for photo_path in pending_photos {
-                match Photo::new_async(photo_path.clone()).await {
-                    Result::Ok(photo) => {
-                        Dependency::<PhotoManager>::get().with_lock_mut(|photo_manager| {
-                            photo_manager.photos.insert(photo_path.clone(), photo);
-                        });
-                    }
-                    Err(err) => {
-                        error!("Failed to load photo: {:?} - {:?}", photo_path, err);
+            Self::import_photos(pending_photos).await;
+
+            // Animations are added as a still of one of their frames
+            if !animations.is_empty() {
+                animation_still::import(animations).await;
+            }
+
+            Ok(())
+        });
+
+        Ok(())
+    }
+
+    /// Adds the photos at the given paths that aren't already in the library
+    pub async fn import_files(paths: Vec<PathBuf>) {
+        let pending_photos: Vec<PathBuf> = paths
+            .into_iter()
+            .filter(|path| !Dependency::<PhotoManager>::get().with_lock(|pm| pm.photo_exists(path)))
+            .collect();
+
+        Self::import_photos(pending_photos).await;
+    }
+
+    // Reads photo metadata on a set of worker tasks and streams the results into the
+    // photo manager so the gallery fills in while the import is still running
+    async fn import_photos(photo_paths: Vec<PathBuf>) {
+        let num_photos = photo_paths.len();
+        if num_photos == 0 {
+            return;
+        }
+
+        let modal_manager: Singleton<ModalManager> = Dependency::get();
+        let modal_id = ModalManager::push(ProgressModal::new(
+            "Importing Photos",
+            format!("Importing {} photos", num_photos),
+            "Cancel",
+            0.0,
+        ));
+
+        let (sender, mut receiver) =
+            mpsc::channel::<(PathBuf, Option<FileStamp>, Result<Photo, PhotoError>)>(
+                IMPORT_QUEUE_SIZE,
+            );
+
+        for partition in utils::partition_iterator(photo_paths.into_iter(), IMPORT_WORKERS) {
+            let sender = sender.clone();
+            tokio::spawn(async move {
+                for photo_path in partition {
+                    let stamp = FileStamp::read_async(&photo_path).await;
+                    let result = Photo::new_async(photo_path.clone()).await;
+
+                    // The receiver is dropped if the import is cancelled
+                    if sender.send((photo_path, stamp, result)).await.is_err() {
+                        break;
                     }
                 }
+            });
+        }
+
+        drop(sender);
+
+        let mut num_imported: usize = 0;
+        let mut photos_since_regroup: usize = 0;
+
+        while let Some((photo_path, stamp, result)) = receiver.recv().await {
+            num_imported += 1;
+
+            match result {
+                Result::Ok(photo) => {
+                    Dependency::<PhotoManager>::get().with_lock_mut(|photo_manager| {
+                        if let Some(stamp) = stamp {
+                            photo_manager.photo_database.insert(
+                                photo_path.clone(),
+                                stamp,
+                                photo.metadata.clone(),
+                            );
+                        }
+                        photo_manager
+                            .import_dates
+                            .insert(photo_path.clone(), Utc::now());
+                        photo_manager.photos.insert(photo_path, photo);
+
+                        photos_since_regroup += 1;
+                        if photos_since_regroup >= IMPORT_REGROUP_INTERVAL {
+                            photos_since_regroup = 0;
+                            photo_manager.sort_and_regroup();
+                        }
+                    });
+                }
+                Err(err) => {
+                    Self::report_load_failure(photo_path, None, None, None, None, err);
+                }
             }
 
-            Dependency::<PhotoManager>::get().with_lock_mut(|photo_manager| {
-                photo_manager.photos.sort_by(|_, a, _, b| {
-                    match (
-                        a.metadata.fields.get(PhotoMetadataFieldLabel::DateTime),
-                        b.metadata.fields.get(PhotoMetadataFieldLabel::DateTime),
-                    ) {
-                        (
-                            Some(PhotoMetadataField::DateTime(a)),
-                            Some(PhotoMetadataField::DateTime(b)),
-                        ) => b.cmp(a),
-                        _ => b.path.cmp(&a.path),
-                    }
+            let cancelled = modal_manager.with_lock(|modal_manager| {
+                if !modal_manager.exists(&modal_id) {
+                    return true;
+                }
+
+                let _ = modal_manager.modify(&modal_id, |progress_modal| {
+                    progress_modal.progress = num_imported as f32 / num_photos as f32;
+                    progress_modal.message =
+                        format!("Imported {}/{} photos", num_imported, num_photos);
                 });
 
-                photo_manager.regroup_photos();
+                false
             });
 
-            let photo_paths: Vec<PathBuf> =
-                Dependency::<PhotoManager>::get().with_lock(|photo_manager| {
-                    photo_manager
-                        .photos
-                        .keys()
-                        .cloned()
-                        .collect::<Vec<PathBuf>>()
-                });
+            if cancelled {
+                info!("Photo import cancelled after {} photos", num_imported);
+                break;
+            }
+        }
 
-            let _ = Self::gen_thumbnails(photo_paths);
+        drop(receiver);
 
-            Ok(())
+        modal_manager.with_lock_mut(|modal_manager| {
+            modal_manager.dismiss(modal_id);
         });
 
-        Ok(())
+        let photo_paths: Vec<PathBuf> =
+            Dependency::<PhotoManager>::get().with_lock_mut(|photo_manager| {
+                photo_manager.sort_and_regroup();
+                photo_manager.photos.keys().cloned().collect()
+            });
+
+        let _ = Self::gen_thumbnails(photo_paths);
     }
 
-    pub fn load_photos(&self, photos: Vec<(PathBuf, Option<PhotoRating>)>) {
+    // Photos that are there but can't be read may only have been half copied, so they can be
+    // retried
+    fn report_load_failure(
+        path: PathBuf,
+        rating: Option<PhotoRating>,
+        color_label: Option<ColorLabel>,
+        imported_at: Option<DateTime<Utc>>,
+        orientation_override: Option<PhotoRotation>,
+        err: impl std::fmt::Debug,
+    ) {
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        ErrorSink::report_with_retry(format!("Couldn't load {}", file_name), err, move || {
+            Dependency::<PhotoManager>::get().with_lock(|photo_manager| {
+                photo_manager.load_photos(vec![(
+                    path.clone(),
+                    rating,
+                    color_label,
+                    imported_at,
+                    orientation_override,
+                )])
+            });
+        });
+    }
+
+    pub fn load_photos(&self, photos: Vec<SavedPhoto>) {
+        let photo_database = self.photo_database.clone();
         tokio::spawn(async move {
             let mut photos_since_regroup: usize = 0;
-            let filtered_photos: Vec<(PathBuf, Option<PhotoRating>)> = photos
+            let filtered_photos: Vec<SavedPhoto> = photos
                 .into_iter()
-                .filter(|(path, _)| {
+                .filter(|(path, _, _, _, _)| {
                     !Dependency::<PhotoManager>::get().with_lock(|pm| pm.photo_exists(path))
                 })
                 .collect();
 
             let num_photos = filtered_photos.len();
 
-            for (path, rating) in filtered_photos {
-                let photo =
-                    Photo::with_rating_async(path.clone(), rating.unwrap_or_default()).await;
+            for (path, rating, color_label, imported_at, orientation_override) in filtered_photos {
+                // Only photos that changed since the project was saved are read again
+                let stamp = FileStamp::read_async(&path).await;
+                let cached_metadata = stamp
+                    .as_ref()
+                    .and_then(|stamp| photo_database.cached_metadata(&path, stamp));
+                let photo = match cached_metadata {
+                    Some(metadata) => Result::Ok(Photo::with_metadata(
+                        path.clone(),
+                        metadata,
+                        rating.unwrap_or_default(),
+                    )),
+                    None => {
+                        Photo::with_rating_async(path.clone(), rating.unwrap_or_default()).await
+                    }
+                };
 
                 match photo {
                     Result::Err(err) => {
+                        if path.exists() {
+                            Self::report_load_failure(
+                                path,
+                                rating,
+                                color_label,
+                                imported_at,
+                                orientation_override,
+                                err,
+                            );
+                            continue;
+                        }
+
                         error!("Failed to load photo: {:?} - {:?}", path, err);
+
+                        // Keep missing photos so they can be shown as missing and relinked
+                        let mut photo = Photo::missing(path.clone(), rating.unwrap_or_default());
+                        photo.color_label = color_label;
+                        photo.metadata.orientation_override = orientation_override;
+                        Dependency::<PhotoManager>::get().with_lock_mut(|photo_manager| {
+                            photo_manager.missing_photos.insert(path.clone());
+                            photo_manager.photos.insert(path.clone(), photo);
+                        });
                         continue;
                     }
-                    Result::Ok(photo) => {
+                    Result::Ok(mut photo) => {
+                        photo.color_label = color_label;
+                        photo.metadata.orientation_override = orientation_override;
                         Dependency::<PhotoManager>::get().with_lock_mut(|photo_manager| {
+                            if let Some(stamp) = stamp {
+                                photo_manager.photo_database.insert(
+                                    path.clone(),
+                                    stamp,
+                                    photo.metadata.clone(),
+                                );
+                            }
+                            if let Some(imported_at) = imported_at {
+                                photo_manager.import_dates.insert(path.clone(), imported_at);
+                            }
                             photo_manager.photos.insert(path.clone(), photo);
 
                             photos_since_regroup += 1;
@@ -209,7 +591,7 @@ impl PhotoManager {
 
             let (photo_paths, _) =
                 Dependency::<PhotoManager>::get().with_lock_mut(|photo_manager| {
-                    photo_manager.regroup_photos();
+                    photo_manager.sort_and_regroup();
 
                     let photo_paths: Vec<PathBuf> = photo_manager.photos.keys().cloned().collect();
                     let thumbnail_dir = Dirs::Thumbnails.path();
@@ -220,23 +602,153 @@ impl PhotoManager {
         });
     }
 
-    // Add helper method for sorting and regrouping
     fn sort_and_regroup(&mut self) {
-        self.photos.sort_by(|_, a, _, b| {
-            match (
-                a.metadata.fields.get(PhotoMetadataFieldLabel::DateTime),
-                b.metadata.fields.get(PhotoMetadataFieldLabel::DateTime),
-            ) {
-                (Some(PhotoMetadataField::DateTime(a)), Some(PhotoMetadataField::DateTime(b))) => {
-                    b.cmp(a)
+        let sort = self.sort;
+
+        // Keys that need to touch the file system are read once up front rather than on every comparison
+        let capture_dates: HashMap<PathBuf, Option<DateTime<Utc>>> =
+            if sort.field == PhotosSortField::CaptureDate {
+                self.photos
+                    .iter()
+                    .map(|(path, photo)| (path.clone(), Self::capture_date(photo)))
+                    .collect()
+            } else {
+                HashMap::new()
+            };
+
+        let file_sizes: HashMap<PathBuf, u64> = if sort.field == PhotosSortField::FileSize {
+            self.photos
+                .keys()
+                .map(|path| {
+                    let size = std::fs::metadata(path)
+                        .map(|metadata| metadata.len())
+                        .unwrap_or_default();
+                    (path.clone(), size)
+                })
+                .collect()
+        } else {
+            HashMap::new()
+        };
+
+        let import_dates = &self.import_dates;
+
+        self.photos.sort_by(|a_path, a, b_path, b| {
+            let ordering = match sort.field {
+                PhotosSortField::CaptureDate => capture_dates[a_path].cmp(&capture_dates[b_path]),
+                PhotosSortField::FileName => a.file_name().cmp(b.file_name()),
+                // Lower rating values are better so they are compared in reverse
+                PhotosSortField::Rating => (b.rating as u8).cmp(&(a.rating as u8)),
+                PhotosSortField::ImportDate => {
+                    import_dates.get(a_path).cmp(&import_dates.get(b_path))
                 }
-                _ => b.path.cmp(&a.path),
-            }
+                PhotosSortField::FileSize => file_sizes[a_path].cmp(&file_sizes[b_path]),
+            };
+
+            sort.direction
+                .apply(ordering.then_with(|| a_path.cmp(b_path)))
         });
 
         self.regroup_photos();
     }
 
+    // The EXIF capture date, falling back to the file's modified or created date
+    pub fn capture_date(photo: &Photo) -> Option<DateTime<Utc>> {
+        if let Some(PhotoMetadataField::DateTime(date_time)) =
+            photo.metadata.fields.get(PhotoMetadataFieldLabel::DateTime)
+        {
+            return Some(date_time.clone());
+        }
+
+        let file_metadata = std::fs::metadata(&photo.path).ok()?;
+        file_metadata
+            .modified()
+            .or_else(|_| file_metadata.created())
+            .ok()
+            .map(|time| time.into())
+    }
+
+    pub fn photos_sort(&self) -> PhotosSort {
+        self.sort
+    }
+
+    pub fn sort_photos_by(&mut self, sort: PhotosSort) {
+        self.sort = sort;
+        self.sort_and_regroup();
+    }
+
+    pub fn import_date(&self, path: &PathBuf) -> Option<DateTime<Utc>> {
+        self.import_dates.get(path).cloned()
+    }
+
+    pub fn captions(&self, path: &Path) -> PhotoCaptions {
+        self.captions.get(path).cloned().unwrap_or_default()
+    }
+
+    pub fn set_caption(&mut self, path: &Path, target: CaptionTarget, text: Option<String>) {
+        let captions = self.captions.entry(path.to_path_buf()).or_default();
+        captions.set(target, text);
+        if captions.is_empty() {
+            self.captions.remove(path);
+        }
+    }
+
+    pub fn all_captions(&self) -> &HashMap<PathBuf, PhotoCaptions> {
+        &self.captions
+    }
+
+    pub fn set_all_captions(&mut self, captions: HashMap<PathBuf, PhotoCaptions>) {
+        self.captions = captions;
+    }
+
+    pub fn search_text(&self) -> &str {
+        &self.search.text
+    }
+
+    pub fn search_error(&self) -> Option<&QueryError> {
+        self.search.error.as_ref()
+    }
+
+    pub fn set_search(&mut self, text: String) {
+        if text == self.search.text {
+            return;
+        }
+
+        self.search.set_text(text);
+        self.regroup_photos();
+    }
+
+    pub fn active_collection(&self) -> Option<&str> {
+        self.collection
+            .as_ref()
+            .map(|collection| collection.name.as_str())
+    }
+
+    pub fn collection_error(&self) -> Option<&QueryError> {
+        self.collection
+            .as_ref()
+            .and_then(|collection| collection.search.error.as_ref())
+    }
+
+    /// Narrows the gallery to the photos in a smart collection, on top of the search. A collection
+    /// with its own sort order replaces the gallery's until the collection is left.
+    pub fn set_collection(&mut self, collection: Option<&SmartCollection>) {
+        if let Some(sort) = self.library_sort.take() {
+            self.sort = sort;
+        }
+
+        self.collection = collection.map(|collection| ActiveCollection {
+            name: collection.name.clone(),
+            search: PhotoSearch::new(collection.query.clone()),
+        });
+
+        if let Some(sort) = collection.and_then(|collection| collection.sort) {
+            self.library_sort = Some(self.sort);
+            self.sort = sort;
+        }
+
+        self.sort_and_regroup();
+    }
+
     pub fn grouped_photos(&self) -> &IndexMap<String, IndexMap<PathBuf, Photo>> {
         &self.grouped_photos.1
     }
@@ -254,73 +766,82 @@ impl PhotoManager {
         &mut self,
         photos_grouping: PhotosGrouping,
     ) -> &IndexMap<String, IndexMap<PathBuf, Photo>> {
-        let photos = &self.photos;
+        self.stacks.rebuild(self.photos.keys());
+
+        let search = &mut self.search;
+        let collection = &mut self.collection;
+        let stacks = &self.stacks;
+        let photo_database = &self.photo_database;
+        let photos: Vec<(&PathBuf, &Photo)> = self
+            .photos
+            .iter()
+            .filter(|(path, photo)| {
+                stacks.is_primary(path)
+                    && search.matches(photo, photo_database)
+                    && collection.as_mut().map_or(true, |collection| {
+                        collection.search.matches(photo, photo_database)
+                    })
+            })
+            .collect();
         match photos_grouping {
             PhotosGrouping::Date => {
                 let mut grouped_photos: IndexMap<String, IndexMap<PathBuf, Photo>> =
                     IndexMap::new();
 
-                for (photo_path, photo) in photos.iter() {
-                    let exif_date_time = photo
-                        .metadata
-                        .fields
-                        .get(PhotoMetadataFieldLabel::DateTime)
-                        .and_then(|field| match field {
-                            PhotoMetadataField::DateTime(date_time) => Some(date_time.clone()),
-                            _ => {
-                                if let Result::Ok(file_metadata) = std::fs::metadata(photo_path) {
-                                    if let Result::Ok(modified) = file_metadata.modified() {
-                                        let modified_date_time: DateTime<Utc> = modified.into();
-                                        return Some(modified_date_time);
-                                    } else if let Result::Ok(created) = file_metadata.created() {
-                                        let created_date_time: DateTime<Utc> = created.into();
-                                        return Some(created_date_time);
-                                    } else {
-                                        return None;
-                                    }
-                                } else {
-                                    return None;
-                                }
-                            }
-                        });
+                for &(photo_path, photo) in photos.iter() {
+                    let key = Self::capture_date(photo)
+                        .map(|date_time| {
+                            format!(
+                                "{:04}-{:02}-{:02}",
+                                date_time.year(),
+                                date_time.month(),
+                                date_time.day()
+                            )
+                        })
+                        .unwrap_or_else(|| "Unknown Date".to_string());
+
+                    grouped_photos
+                        .entry(key)
+                        .or_default()
+                        .insert(photo_path.clone(), photo.clone());
+                }
 
-                    let key = if let Some(date_time) = exif_date_time {
-                        let year = date_time.year();
-                        let month = date_time.month();
-                        let day = date_time.day();
+                // Days read chronologically when the photos are sorted by date, newest first otherwise
+                match self.sort {
+                    PhotosSort {
+                        field: PhotosSortField::CaptureDate,
+                        direction: SortDirection::Ascending,
+                    } => grouped_photos.sort_by(|a, _, b, _| a.cmp(b)),
+                    _ => grouped_photos.sort_by(|a, _, b, _| b.cmp(a)),
+                }
 
-                        Some(format!("{:04}-{:02}-{:02}", year, month, day))
-                    } else {
-                        // Get the last modified date of the file
-                        let metadata = std::fs::metadata(photo_path).unwrap();
-                        let modified = metadata.modified().unwrap();
-                        let modified_date_time: DateTime<Utc> = modified.into();
-                        let year = modified_date_time.year();
-                        let month: u32 = modified_date_time.month();
-                        let day = modified_date_time.day();
-
-                        Some(format!("{:04}-{:02}-{:02}", year, month, day))
-                    }
-                    .unwrap_or_else(|| "Unknown Date".to_string());
+                self.grouped_photos = (PhotosGrouping::Date, grouped_photos);
+            }
+            PhotosGrouping::Folder => {
+                let mut grouped_photos: IndexMap<String, IndexMap<PathBuf, Photo>> =
+                    IndexMap::new();
 
-                    if let Some(group) = grouped_photos.get_mut(&key) {
-                        group.insert(photo_path.clone(), photo.clone());
-                    } else {
-                        let mut group = IndexMap::new();
-                        group.insert(photo_path.clone(), photo.clone());
-                        grouped_photos.insert(key, group);
-                    }
+                for &(photo_path, photo) in photos.iter() {
+                    let key = photo_path
+                        .parent()
+                        .map(|parent| parent.display().to_string())
+                        .unwrap_or_else(|| "Unknown Folder".to_string());
+
+                    grouped_photos
+                        .entry(key)
+                        .or_default()
+                        .insert(photo_path.clone(), photo.clone());
                 }
 
-                grouped_photos.sort_by(|a, _, b, _| b.cmp(a));
+                grouped_photos.sort_by(|a, _, b, _| a.cmp(b));
 
-                self.grouped_photos = (PhotosGrouping::Date, grouped_photos);
+                self.grouped_photos = (PhotosGrouping::Folder, grouped_photos);
             }
             PhotosGrouping::Rating => {
                 let mut grouped_photos: IndexMap<String, IndexMap<PathBuf, Photo>> =
                     IndexMap::new();
 
-                for (photo_path, photo) in photos.iter() {
+                for &(photo_path, photo) in photos.iter() {
                     let rating = photo.rating;
                     let key = format!("{:?}", rating);
 
@@ -349,15 +870,184 @@ impl PhotoManager {
         &self.grouped_photos.1
     }
 
+    /// Every version of the photo at `path`, including itself
+    pub fn stack_versions(&self, path: &PathBuf) -> Vec<Photo> {
+        self.stacks
+            .versions(path)
+            .iter()
+            .filter_map(|version| self.photos.get(version).cloned())
+            .collect()
+    }
+
+    pub fn stack_size(&self, path: &PathBuf) -> usize {
+        self.stacks.versions(path).len().max(1)
+    }
+
+    pub fn stack_primary(&self, path: &PathBuf) -> Option<PathBuf> {
+        self.stacks.primary(path).cloned()
+    }
+
+    pub fn set_stack_primary(&mut self, path: PathBuf) {
+        self.stacks.set_primary(path);
+        self.regroup_photos();
+    }
+
+    pub fn chosen_stack_primaries(&self) -> Vec<PathBuf> {
+        self.stacks.chosen_primaries()
+    }
+
+    pub fn set_chosen_stack_primaries(&mut self, primaries: Vec<PathBuf>) {
+        self.stacks.set_chosen_primaries(primaries);
+        self.regroup_photos();
+    }
+
+    pub fn is_missing(&self, path: &Path) -> bool {
+        self.missing_photos.contains(path)
+    }
+
+    pub fn mark_missing(&mut self, path: PathBuf) {
+        self.missing_photos.insert(path);
+    }
+
+    /// Every missing photo, including photos placed on pages that aren't in the library
+    pub fn missing_photos(&self) -> Vec<PathBuf> {
+        let mut missing: Vec<PathBuf> = self.missing_photos.iter().cloned().collect();
+        missing.sort();
+        missing
+    }
+
+    /// Looks for each missing photo by file name in `folder` and its subfolders.
+    /// Returns the missing path and the photo found for it.
+    pub fn find_relinks(missing: &[PathBuf], folder: &Path) -> Vec<(PathBuf, Photo)> {
+        let pattern = format!("{}/**/*", folder.to_string_lossy());
+        let candidates: HashMap<String, PathBuf> = match glob::glob_with(
+            &pattern,
+            MatchOptions {
+                case_sensitive: false,
+                require_literal_separator: false,
+                require_literal_leading_dot: false,
+            },
+        ) {
+            Result::Ok(paths) => paths
+                .filter_map(|entry| {
+                    let path = entry.ok()?;
+                    let file_name = path.file_name()?.to_string_lossy().to_lowercase();
+                    path.is_file().then_some((file_name, path))
+                })
+                .collect(),
+            Result::Err(err) => {
+                error!("Invalid folder to search for missing photos: {:?}", err);
+                return Vec::new();
+            }
+        };
+
+        missing
+            .iter()
+            .filter_map(|missing_path| {
+                let file_name = missing_path.file_name()?.to_string_lossy().to_lowercase();
+                let found_path = candidates.get(&file_name)?;
+
+                match Photo::new(found_path.clone()) {
+                    Result::Ok(photo) => Some((missing_path.clone(), photo)),
+                    Result::Err(err) => {
+                        error!("Failed to relink {:?}: {:?}", found_path, err);
+                        None
+                    }
+                }
+            })
+            .collect()
+    }
+
+    /// Replaces missing photos with the photos found for them, keeping their ratings, labels,
+    /// orientations, import dates and captions. Returns the relinked photos as they were added.
+    pub fn relink(&mut self, relinks: Vec<(PathBuf, Photo)>) -> Vec<(PathBuf, Photo)> {
+        let relinks: Vec<(PathBuf, Photo)> = relinks
+            .into_iter()
+            .map(|(missing_path, mut photo)| {
+                // Photos only placed on pages aren't added to the library
+                if let Some(missing_photo) = self.photos.shift_remove(&missing_path) {
+                    photo.rating = missing_photo.rating;
+                    photo.color_label = missing_photo.color_label;
+                    photo.metadata.orientation_override =
+                        missing_photo.metadata.orientation_override;
+                    self.photos.insert(photo.path.clone(), photo.clone());
+                }
+                if let Some(import_date) = self.import_dates.remove(&missing_path) {
+                    self.import_dates.insert(photo.path.clone(), import_date);
+                }
+                if let Some(captions) = self.captions.remove(&missing_path) {
+                    self.captions.insert(photo.path.clone(), captions);
+                }
+                self.missing_photos.remove(&missing_path);
+
+                info!("Relinked {:?} to {:?}", missing_path, photo.path);
+
+                (missing_path, photo)
+            })
+            .collect();
+
+        self.sort_and_regroup();
+        self.load_queue.retry_failed();
+
+        let _ = Self::gen_thumbnails(
+            relinks
+                .iter()
+                .map(|(_, photo)| photo.path.clone())
+                .collect(),
+        );
+
+        relinks
+    }
+
+    /// Labels every photo in `paths`, or clears their label with `None`
+    pub fn set_color_label<'a>(
+        &mut self,
+        paths: impl IntoIterator<Item = &'a PathBuf>,
+        color_label: Option<ColorLabel>,
+    ) {
+        for path in paths {
+            if let Some(photo) = self.photos.get(path) {
+                let mut photo = photo.clone();
+                photo.color_label = color_label;
+                self.update_photo(photo);
+            }
+        }
+    }
+
     pub fn update_photo(&mut self, photo: Photo) {
+        let orientation_changed = self.photos.get(&photo.path).is_some_and(|previous| {
+            previous.metadata.orientation_override != photo.metadata.orientation_override
+        });
+        if orientation_changed {
+            self.orientation_changes.push(photo.clone());
+        }
+
         self.photos.insert(photo.path.clone(), photo.clone());
+        self.search.matches.remove(&photo.path);
+        if let Some(collection) = &mut self.collection {
+            collection.search.matches.remove(&photo.path);
+        }
         for group in self.grouped_photos.1.values_mut() {
             if group.contains_key(&photo.path) {
                 group.insert(photo.path.clone(), photo.clone());
-                self.regroup_photos(); // TODO: This isn't very efficient
+                if self.sort.field == PhotosSortField::Rating {
+                    self.sort_and_regroup();
+                } else {
+                    self.regroup_photos(); // TODO: This isn't very efficient
+                }
                 return;
             }
         }
+
+        // The photo was hidden by the search or collection but may match now
+        if self.search.query.is_some() || self.collection.is_some() {
+            self.regroup_photos();
+        }
+    }
+
+    /// Photos whose orientation override changed since this was last called
+    pub fn take_orientation_changes(&mut self) -> Vec<Photo> {
+        std::mem::take(&mut self.orientation_changes)
     }
 
     pub fn thumbnail_texture_for(
@@ -374,9 +1064,10 @@ impl PhotoManager {
 
         Self::load_texture(
             &photo.thumbnail_uri(),
+            LoadPriority::Thumbnail,
             ctx,
             &mut self.texture_cache,
-            &mut self.pending_textures,
+            &mut self.load_queue,
         )
     }
 
@@ -395,9 +1086,10 @@ impl PhotoManager {
                 }
                 Self::load_texture(
                     &photo.thumbnail_uri(),
+                    LoadPriority::Thumbnail,
                     ctx,
                     &mut self.texture_cache,
-                    &mut self.pending_textures,
+                    &mut self.load_queue,
                 )
             }
             _ => Ok(None),
@@ -411,9 +1103,10 @@ impl PhotoManager {
     ) -> anyhow::Result<Option<SizedTexture>> {
         Self::load_texture(
             &photo.uri(),
+            LoadPriority::Visible,
             ctx,
             &mut self.texture_cache,
-            &mut self.pending_textures,
+            &mut self.load_queue,
         )
     }
 
@@ -437,22 +1130,160 @@ impl PhotoManager {
     ) -> anyhow::Result<Option<SizedTexture>> {
         match Self::load_texture(
             &photo.uri(),
+            LoadPriority::Visible,
             ctx,
             &mut self.texture_cache,
-            &mut self.pending_textures,
+            &mut self.load_queue,
         ) {
             Result::Ok(Some(tex)) => Ok(Some(tex)),
-            _ => Ok(self.texture_cache.get(&photo.thumbnail_uri()).copied()),
+            _ => Ok(self.texture_cache.get(&photo.thumbnail_uri())),
+        }
+    }
+
+    /// The photo's texture with `adjustments` applied. The adjusted texture is created in the
+    /// background, the unadjusted photo is returned until it's ready.
+    pub fn adjusted_texture_for(
+        &mut self,
+        photo: &Photo,
+        adjustments: &PhotoAdjustments,
+        ctx: &Context,
+    ) -> anyhow::Result<Option<SizedTexture>> {
+        if adjustments.is_identity() {
+            return self.texture_for_photo_with_thumbail_backup(photo, ctx);
+        }
+
+        let uri = adjustments.uri(photo);
+        if let Some(texture) = self.texture_cache.get(&uri) {
+            return Ok(Some(texture));
+        }
+
+        // Failed loads stay pending so they aren't retried every frame
+        if self.pending_textures.insert(uri.clone()) {
+            let photo = photo.clone();
+            let adjustments = *adjustments;
+            let ctx = ctx.clone();
+            spawn_blocking(
+                move || match Self::load_adjusted_texture(&photo, &adjustments, &ctx) {
+                    Result::Ok(texture) => {
+                        Dependency::<PhotoManager>::get().with_lock_mut(|photo_manager| {
+                            photo_manager.cache_texture(uri, texture, &ctx);
+                        });
+                        ctx.request_repaint();
+                    }
+                    Result::Err(err) => {
+                        error!("Failed to adjust {}: {:?}", photo.string_path(), err);
+                    }
+                },
+            );
+        }
+
+        self.texture_for_photo_with_thumbail_backup(photo, ctx)
+    }
+
+    /// The texture for drawing the photo on a page that isn't being edited, like a page preview.
+    /// Full resolution textures are only used if they're already loaded, otherwise the thumbnail
+    /// is used so off-screen pages don't keep full resolution textures in memory.
+    pub fn preview_texture_for(
+        &mut self,
+        photo: &Photo,
+        adjustments: &PhotoAdjustments,
+        ctx: &Context,
+    ) -> anyhow::Result<Option<SizedTexture>> {
+        let uri = if adjustments.is_identity() {
+            photo.uri()
+        } else {
+            adjustments.uri(photo)
+        };
+
+        // Peek so previews alone don't stop the texture from being evicted
+        if let Some(texture) = self.texture_cache.peek(&uri) {
+            return Ok(Some(texture));
+        }
+
+        if self
+            .thumbnail_existence_cache
+            .contains(&photo.thumbnail_hash)
+        {
+            self.thumbnail_texture_for(photo, ctx)
+        } else {
+            self.adjusted_texture_for(photo, adjustments, ctx)
         }
     }
 
+    /// The full resolution texture of the photo, with `adjustments` applied, loaded in `ctx` itself
+    /// rather than the app's texture cache. Used to draw pages for exports, which run in their own
+    /// context and have already loaded the photos, so there's never a thumbnail in its place. A
+    /// photo the export resampled to the size it's drawn at is used as it is.
+    pub fn full_resolution_texture_for(
+        photo: &Photo,
+        adjustments: &PhotoAdjustments,
+        ctx: &Context,
+    ) -> anyhow::Result<Option<SizedTexture>> {
+        if let Some(texture) = Self::cached_adjusted_texture(&adjustments.uri(photo), ctx) {
+            return Result::Ok(Some(texture));
+        }
+
+        if !adjustments.is_identity() {
+            return Result::Ok(Some(Self::load_adjusted_texture(photo, adjustments, ctx)?));
+        }
+
+        match ctx.try_load_texture(
+            &photo.uri(),
+            ResizeQuality::current().texture_options(),
+            eframe::egui::SizeHint::Scale(OrderedFloat::from(1.0)),
+        )? {
+            eframe::egui::load::TexturePoll::Ready { texture } => Result::Ok(Some(texture)),
+            eframe::egui::load::TexturePoll::Pending { .. } => Result::Ok(None),
+        }
+    }
+
+    /// Creates the adjusted texture for `photo` on the calling thread.
+    /// Textures are stored in the context's memory since they belong to that context.
+    pub fn load_adjusted_texture(
+        photo: &Photo,
+        adjustments: &PhotoAdjustments,
+        ctx: &Context,
+    ) -> Result<SizedTexture, AdjustmentError> {
+        let max_size = ctx.input(|input| input.max_texture_side);
+        Self::load_resampled_texture(photo, adjustments, max_size, ctx)
+    }
+
+    /// Like `load_adjusted_texture` but scaled down with the project's resize quality to fit in
+    /// `max_size`, for exports which know how large each photo is drawn
+    pub fn load_resampled_texture(
+        photo: &Photo,
+        adjustments: &PhotoAdjustments,
+        max_size: usize,
+        ctx: &Context,
+    ) -> Result<SizedTexture, AdjustmentError> {
+        let uri = adjustments.uri(photo);
+        if let Some(texture) = Self::cached_adjusted_texture(&uri, ctx) {
+            return Result::Ok(texture);
+        }
+
+        let quality = ResizeQuality::current();
+        let image = adjustments.adjusted_image(&photo.path, max_size, quality.filter_type())?;
+        let handle = ctx.load_texture(&uri, image, quality.texture_options());
+        let texture = SizedTexture::from_handle(&handle);
+
+        ctx.data_mut(|data| data.insert_temp(Id::new(&uri), handle));
+
+        Result::Ok(texture)
+    }
+
+    fn cached_adjusted_texture(uri: &str, ctx: &Context) -> Option<SizedTexture> {
+        ctx.data(|data| data.get_temp::<TextureHandle>(Id::new(uri)))
+            .map(|handle| SizedTexture::from_handle(&handle))
+    }
+
     pub fn texture_at(&mut self, at: usize, ctx: &Context) -> anyhow::Result<Option<SizedTexture>> {
         match self.photos.get_index(at) {
             Some((_, photo)) => Self::load_texture(
                 &photo.uri(),
+                LoadPriority::Visible,
                 ctx,
                 &mut self.texture_cache,
-                &mut self.pending_textures,
+                &mut self.load_queue,
             ),
             _ => Ok(None),
         }
@@ -461,7 +1292,7 @@ impl PhotoManager {
     pub fn next_photo(
         &mut self,
         current_photo: &Photo,
-        _ctx: &Context,
+        ctx: &Context,
     ) -> anyhow::Result<Option<(Photo, usize)>> {
         let current_index = self
             .index_for_photo(current_photo)
@@ -470,11 +1301,7 @@ impl PhotoManager {
         match self.photos.get_index(next_index) {
             Some((_, next_photo)) => {
                 if let Some((_, current_photo)) = self.photos.get_index(current_index) {
-                    if let Some(_texture) = self.texture_cache.remove(&current_photo.uri()) {
-                        // info!("Freeing texture for photo {}", current_photo.uri());
-                        // ctx.forget_image(&current_photo.uri());
-                        // ctx.tex_manager().write().free(texture.id);
-                    }
+                    self.texture_cache.remove(&current_photo.uri(), ctx);
                 }
 
                 Ok(Some((next_photo.clone(), next_index)))
@@ -486,7 +1313,7 @@ impl PhotoManager {
     pub fn previous_photo(
         &mut self,
         current_photo: &Photo,
-        _ctx: &Context,
+        ctx: &Context,
     ) -> anyhow::Result<Option<(Photo, usize)>> {
         let current_index = self
             .index_for_photo(current_photo)
@@ -495,11 +1322,7 @@ impl PhotoManager {
         match self.photos.get_index(prev_index) {
             Some((_, previous_photo)) => {
                 if let Some((_, current_photo)) = self.photos.get_index(current_index) {
-                    if let Some(_texture) = self.texture_cache.remove(&current_photo.uri()) {
-                        // info!("Freeing texture for photo {}", current_photo.uri());
-                        // ctx.forget_image(&current_photo.uri());
-                        // ctx.tex_manager().write().free(texture.id);
-                    }
+                    self.texture_cache.remove(&current_photo.uri(), ctx);
                 }
 
                 Ok(Some((previous_photo.clone(), prev_index)))
@@ -508,70 +1331,176 @@ impl PhotoManager {
         }
     }
 
+    pub fn has_requested_texture(&self, uri: &str) -> bool {
+        self.texture_cache.contains_key(uri)
+            || self.pending_textures.contains(uri)
+            || self.load_queue.is_requested(uri)
+    }
+
+    /// Queues `uri` to be loaded ahead of time, behind everything that's on screen. Loading has
+    /// to be asked for again until the texture is returned or the request goes stale. Errors if
+    /// the texture can't be loaded.
+    pub fn prefetch_texture(
+        &mut self,
+        uri: &str,
+        ctx: &Context,
+    ) -> anyhow::Result<Option<SizedTexture>> {
+        // Peek so prefetching doesn't count as the texture being used
+        if let Some(texture) = self.texture_cache.peek(uri) {
+            return Ok(Some(texture));
+        }
+
+        if self.load_queue.has_failed(uri) {
+            return Err(anyhow!("Couldn't load {}", uri));
+        }
+
+        Self::load_texture(
+            uri,
+            LoadPriority::Prefetch,
+            ctx,
+            &mut self.texture_cache,
+            &mut self.load_queue,
+        )
+    }
+
+    pub fn cache_texture(&mut self, uri: String, texture: SizedTexture, ctx: &Context) {
+        self.pending_textures.remove(&uri);
+        self.texture_cache.insert(uri, texture, ctx);
+    }
+
+    /// Caches a texture loaded before it's drawn, see [`TextureCache::insert_prefetched`]
+    pub fn cache_prefetched_texture(&mut self, uri: String, texture: SizedTexture, ctx: &Context) {
+        self.pending_textures.remove(&uri);
+        self.texture_cache.insert_prefetched(uri, texture, ctx);
+    }
+
+    pub fn has_prefetch_room(&self) -> bool {
+        self.texture_cache.has_prefetch_room()
+    }
+
+    /// Sizes the texture budget for the largest texture the graphics adapter supports
+    pub fn set_max_texture_side(&mut self, max_texture_side: usize) {
+        self.texture_cache.set_max_texture_side(max_texture_side);
+    }
+
+    /// Frees every texture so photos are loaded again, e.g. with a different resize quality
+    pub fn clear_textures(&mut self, ctx: &Context) {
+        self.texture_cache.clear(ctx);
+        self.pending_textures.clear();
+        self.load_queue.clear();
+    }
+
+    pub fn texture_metrics(&self) -> TextureMetrics {
+        self.texture_cache.metrics()
+    }
+
+    pub fn pending_loads(&self) -> PendingLoads {
+        PendingLoads {
+            queued: self.load_queue.queued_count(),
+            loading: self.load_queue.loading_count(),
+            unqueued: self.pending_textures.len(),
+        }
+    }
+
     fn index_for_photo(&self, photo: &Photo) -> Option<usize> {
         self.photos.get_full(&photo.path).map(|(index, _, _)| index)
     }
 
+    // Queues the texture if it isn't loaded yet. Textures that failed to load aren't queued again.
     fn load_texture(
         uri: &str,
+        priority: LoadPriority,
         ctx: &Context,
-        texture_cache: &mut HashMap<String, SizedTexture>,
-        pending_textures: &mut HashSet<String>,
+        texture_cache: &mut TextureCache,
+        load_queue: &mut TextureLoadQueue,
     ) -> anyhow::Result<Option<SizedTexture>> {
-        match texture_cache.get(uri) {
-            Some(texture) => {
-                pending_textures.remove(uri);
-                Ok(Some(*texture))
-            }
-            None => {
-                let uri = uri.to_string();
-                let ctx = ctx.clone();
-                spawn_blocking(move || {
-                    let texture: Result<egui::load::TexturePoll, egui::load::LoadError> = ctx
-                        .try_load_texture(
-                            &uri,
-                            eframe::egui::TextureOptions::default(),
-                            eframe::egui::SizeHint::Scale(OrderedFloat(1.0)),
-                        );
-
-                    let photo_manager = Dependency::<PhotoManager>::get();
-                    match texture {
+        if let Some(texture) = texture_cache.get(uri) {
+            return Ok(Some(texture));
+        }
+
+        load_queue.request(uri, priority);
+        Self::start_texture_loads(load_queue, ctx);
+
+        Ok(None)
+    }
+
+    // Starts the queued loads there's room for. Each one that finishes starts the next.
+    fn start_texture_loads(load_queue: &mut TextureLoadQueue, ctx: &Context) {
+        for uri in load_queue.next_loads() {
+            let ctx = ctx.clone();
+            spawn_blocking(move || {
+                let cancelled = Dependency::<PhotoManager>::get().with_lock_mut(|photo_manager| {
+                    if photo_manager.load_queue.is_wanted(&uri) {
+                        return false;
+                    }
+                    photo_manager.load_queue.finished(&uri);
+                    Self::start_texture_loads(&mut photo_manager.load_queue, &ctx);
+                    true
+                });
+                if cancelled {
+                    return;
+                }
+
+                let texture: Result<egui::load::TexturePoll, egui::load::LoadError> = ctx
+                    .try_load_texture(
+                        &uri,
+                        ResizeQuality::current().texture_options(),
+                        eframe::egui::SizeHint::Scale(OrderedFloat(1.0)),
+                    );
+
+                let error = Dependency::<PhotoManager>::get().with_lock_mut(|photo_manager| {
+                    let error = match texture {
+                        // The file is still being read, whatever wants it asks again
                         Result::Ok(eframe::egui::load::TexturePoll::Pending { size: _ }) => {
-                            photo_manager.with_lock_mut(|photo_manager| {
-                                photo_manager.pending_textures.insert(uri)
-                            });
+                            photo_manager.load_queue.finished(&uri);
+                            None
                         }
                         Result::Ok(eframe::egui::load::TexturePoll::Ready { texture }) => {
-                            photo_manager.with_lock_mut(|photo_manager| {
-                                photo_manager.texture_cache.insert(uri, texture);
-                            });
+                            match photo_manager.load_queue.finished(&uri) {
+                                Some(LoadPriority::Prefetch) => photo_manager
+                                    .texture_cache
+                                    .insert_prefetched(uri, texture, &ctx),
+                                _ => photo_manager.texture_cache.insert(uri, texture, &ctx),
+                            }
+                            None
                         }
                         Result::Err(err) => {
-                            error!("Failed to load texture {:?}", err);
+                            photo_manager.load_queue.failed(&uri);
+                            // Otherwise the loader keeps handing back the same error on a retry
+                            ctx.forget_image(&uri);
+                            Some(err)
                         }
-                    }
+                    };
+
+                    Self::start_texture_loads(&mut photo_manager.load_queue, &ctx);
+                    error
                 });
 
-                Ok(None)
-            }
+                // Reported outside the lock since showing the error needs other singletons
+                if let Some(err) = error {
+                    ErrorSink::report("Couldn't load a photo preview", err);
+                }
+
+                ctx.request_repaint();
+            });
         }
     }
 
     fn load_texture_blocking(
         uri: &str,
         ctx: &Context,
-        texture_cache: &mut HashMap<String, SizedTexture>,
+        texture_cache: &mut TextureCache,
         pending_textures: &mut HashSet<String>,
     ) -> anyhow::Result<Option<SizedTexture>> {
         match texture_cache.get(uri) {
             Some(texture) => {
                 pending_textures.remove(uri);
-                Ok(Some(*texture))
+                Ok(Some(texture))
             }
             None => {
                 let texture = ctx.try_load_texture(
                     uri,
-                    eframe::egui::TextureOptions::default(),
+                    ResizeQuality::current().texture_options(),
                     eframe::egui::SizeHint::Scale(OrderedFloat::from(1.0)),
                 );
 
@@ -581,7 +1510,7 @@ impl PhotoManager {
                         Ok(None)
                     }
                     Result::Ok(eframe::egui::load::TexturePoll::Ready { texture }) => {
-                        texture_cache.insert(uri.to_string(), texture);
+                        texture_cache.insert(uri.to_string(), texture, ctx);
                         Ok(Some(texture))
                     }
                     Result::Err(err) => Err(anyhow!(err)),
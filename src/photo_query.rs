@@ -0,0 +1,728 @@
+use chrono::{Months, NaiveDate};
+use strum::IntoEnumIterator;
+
+use crate::{
+    model::color_label::ColorLabel,
+    photo::{Photo, PhotoRating},
+    photo_database::ExifFacets,
+    photo_manager::PhotoManager,
+};
+
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum QueryError {
+    #[error("Unknown field \"{0}\", expected one of rating, label, date, orientation, camera, lens, iso, aperture, focal, tag, name or folder")]
+    UnknownField(String),
+
+    #[error("\"{value}\" is not a valid {field}")]
+    InvalidValue { field: String, value: String },
+
+    #[error("{field} can't be compared with {operator}")]
+    UnsupportedOperator { field: String, operator: String },
+
+    #[error("Missing value for {0}")]
+    MissingValue(String),
+
+    #[error("Unexpected \"{0}\"")]
+    UnexpectedToken(String),
+
+    #[error("Missing closing parenthesis")]
+    UnclosedParenthesis,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Comparison {
+    Equal,
+    NotEqual,
+    Greater,
+    GreaterOrEqual,
+    Less,
+    LessOrEqual,
+}
+
+impl Comparison {
+    fn symbol(&self) -> &'static str {
+        match self {
+            Comparison::Equal => ":",
+            Comparison::NotEqual => "!=",
+            Comparison::Greater => ">",
+            Comparison::GreaterOrEqual => ">=",
+            Comparison::Less => "<",
+            Comparison::LessOrEqual => "<=",
+        }
+    }
+
+    fn compare<T: PartialOrd>(&self, lhs: T, rhs: T) -> bool {
+        match self {
+            Comparison::Equal => lhs == rhs,
+            Comparison::NotEqual => lhs != rhs,
+            Comparison::Greater => lhs > rhs,
+            Comparison::GreaterOrEqual => lhs >= rhs,
+            Comparison::Less => lhs < rhs,
+            Comparison::LessOrEqual => lhs <= rhs,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Orientation {
+    Landscape,
+    Portrait,
+    Square,
+    Panorama,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PhotoPredicate {
+    Rating(Comparison, PhotoRating),
+    // The 0 to 5 stars from the photo's metadata, photos without any have 0
+    Stars(Comparison, u32),
+    // None matches photos without a label
+    Label(Comparison, Option<ColorLabel>),
+    // Inclusive range of days, partial dates like 2023-06 cover the whole month
+    Date(Comparison, NaiveDate, NaiveDate),
+    Orientation(Orientation),
+    Camera(Comparison, String),
+    Lens(Comparison, String),
+    Iso(Comparison, u32),
+    // The f-number, e.g. 2.8
+    Aperture(Comparison, f32),
+    // In millimeters
+    FocalLength(Comparison, f32),
+    Name(Comparison, String),
+    Folder(Comparison, String),
+    // Matches if any of the photo's keywords do
+    Tag(Comparison, String),
+}
+
+impl PhotoPredicate {
+    fn matches(&self, photo: &Photo, facets: &ExifFacets) -> bool {
+        match self {
+            PhotoPredicate::Rating(comparison, rating) => {
+                comparison.compare(Self::rating_rank(photo.rating), Self::rating_rank(*rating))
+            }
+            PhotoPredicate::Stars(comparison, stars) => {
+                comparison.compare(photo.metadata.star_rating().unwrap_or(0), *stars)
+            }
+            PhotoPredicate::Label(comparison, label) => match comparison {
+                Comparison::NotEqual => photo.color_label != *label,
+                _ => photo.color_label == *label,
+            },
+            PhotoPredicate::Date(comparison, start, end) => {
+                let Some(date) = PhotoManager::capture_date(photo) else {
+                    return false;
+                };
+                let date = date.date_naive();
+
+                match comparison {
+                    Comparison::Equal => *start <= date && date <= *end,
+                    Comparison::NotEqual => date < *start || *end < date,
+                    Comparison::Greater => date > *end,
+                    Comparison::GreaterOrEqual => date >= *start,
+                    Comparison::Less => date < *start,
+                    Comparison::LessOrEqual => date <= *end,
+                }
+            }
+            // Panoramas are also landscape, they're matched on their own
+            PhotoPredicate::Orientation(Orientation::Panorama) => photo.is_panorama(),
+            PhotoPredicate::Orientation(orientation) => {
+                let width = photo.metadata.rotated_width();
+                let height = photo.metadata.rotated_height();
+                let photo_orientation = if width > height {
+                    Orientation::Landscape
+                } else if height > width {
+                    Orientation::Portrait
+                } else {
+                    Orientation::Square
+                };
+                photo_orientation == *orientation
+            }
+            PhotoPredicate::Camera(comparison, camera) => match &facets.camera {
+                Some(photo_camera) => Self::text_matches(*comparison, photo_camera, camera),
+                None => *comparison == Comparison::NotEqual,
+            },
+            PhotoPredicate::Lens(comparison, lens) => match &facets.lens {
+                Some(photo_lens) => Self::text_matches(*comparison, photo_lens, lens),
+                None => *comparison == Comparison::NotEqual,
+            },
+            PhotoPredicate::Iso(comparison, iso) => facets
+                .iso
+                .is_some_and(|photo_iso| comparison.compare(photo_iso, *iso)),
+            PhotoPredicate::Aperture(comparison, aperture) => {
+                facets.aperture.is_some_and(|photo_aperture| {
+                    comparison.compare(Self::tenths(photo_aperture), Self::tenths(*aperture))
+                })
+            }
+            PhotoPredicate::FocalLength(comparison, focal_length) => {
+                facets.focal_length.is_some_and(|photo_focal_length| {
+                    comparison.compare(
+                        Self::tenths(photo_focal_length),
+                        Self::tenths(*focal_length),
+                    )
+                })
+            }
+            PhotoPredicate::Name(comparison, name) => {
+                Self::text_matches(*comparison, photo.file_name(), name)
+            }
+            PhotoPredicate::Folder(comparison, folder) => {
+                let parent = photo
+                    .path
+                    .parent()
+                    .map(|parent| parent.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                Self::text_matches(*comparison, &parent, folder)
+            }
+            PhotoPredicate::Tag(comparison, tag) => {
+                let tagged = photo
+                    .metadata
+                    .keywords()
+                    .iter()
+                    .any(|keyword| Self::text_matches(Comparison::Equal, keyword, tag));
+                match comparison {
+                    Comparison::NotEqual => !tagged,
+                    _ => tagged,
+                }
+            }
+        }
+    }
+
+    // Text fields match on a case insensitive substring
+    fn text_matches(comparison: Comparison, text: &str, search: &str) -> bool {
+        let contains = text.to_lowercase().contains(&search.to_lowercase());
+        match comparison {
+            Comparison::NotEqual => !contains,
+            _ => contains,
+        }
+    }
+
+    // Cameras store values like 28/10 or 280/100, they're compared to a tenth so f/2.8 matches
+    // either way
+    fn tenths(value: f32) -> i64 {
+        (value * 10.0).round() as i64
+    }
+
+    // Lower rating values are better so they're flipped to make "rating>=maybe" read naturally
+    fn rating_rank(rating: PhotoRating) -> u8 {
+        2 - rating as u8
+    }
+}
+
+/// A compiled photo search such as `rating>=maybe AND date:2023-06..2023-07 AND orientation:landscape`.
+/// Terms next to each other are joined with AND, and OR, NOT and parentheses are supported.
+/// Words without a field match the file name.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PhotoQuery {
+    Predicate(PhotoPredicate),
+    Not(Box<PhotoQuery>),
+    And(Vec<PhotoQuery>),
+    Or(Vec<PhotoQuery>),
+}
+
+impl PhotoQuery {
+    pub fn parse(query: &str) -> Result<PhotoQuery, QueryError> {
+        let tokens = Self::tokenize(query);
+        let mut parser = Parser {
+            tokens,
+            position: 0,
+        };
+
+        let query = parser.parse_or()?;
+
+        match parser.next() {
+            Some(token) => Err(QueryError::UnexpectedToken(token.to_string())),
+            None => Ok(query),
+        }
+    }
+
+    /// Whether `photo` matches the query. Camera, lens and exposure predicates are checked
+    /// against `facets`, the photo's details from the photo database's index.
+    pub fn matches(&self, photo: &Photo, facets: &ExifFacets) -> bool {
+        match self {
+            PhotoQuery::Predicate(predicate) => predicate.matches(photo, facets),
+            PhotoQuery::Not(query) => !query.matches(photo, facets),
+            PhotoQuery::And(queries) => queries.iter().all(|query| query.matches(photo, facets)),
+            PhotoQuery::Or(queries) => queries.iter().any(|query| query.matches(photo, facets)),
+        }
+    }
+
+    fn tokenize(query: &str) -> Vec<Token> {
+        let mut tokens = Vec::new();
+        let mut word = String::new();
+        let mut in_quotes = false;
+
+        fn end_word(word: &mut String, tokens: &mut Vec<Token>) {
+            if !word.is_empty() {
+                tokens.push(Token::Word(std::mem::take(word)));
+            }
+        }
+
+        for char in query.chars() {
+            match char {
+                '"' => in_quotes = !in_quotes,
+                _ if in_quotes => word.push(char),
+                '(' => {
+                    end_word(&mut word, &mut tokens);
+                    tokens.push(Token::OpenParenthesis);
+                }
+                ')' => {
+                    end_word(&mut word, &mut tokens);
+                    tokens.push(Token::CloseParenthesis);
+                }
+                _ if char.is_whitespace() => end_word(&mut word, &mut tokens),
+                _ => word.push(char),
+            }
+        }
+
+        end_word(&mut word, &mut tokens);
+
+        tokens
+    }
+
+    fn parse_term(term: &str) -> Result<PhotoQuery, QueryError> {
+        let Some(operator_start) = term.find([':', '=', '!', '<', '>']) else {
+            return Ok(PhotoQuery::Predicate(PhotoPredicate::Name(
+                Comparison::Equal,
+                term.to_string(),
+            )));
+        };
+
+        let field = term[..operator_start].to_lowercase();
+        let rest = &term[operator_start..];
+
+        let (comparison, value) = [
+            (">=", Comparison::GreaterOrEqual),
+            ("<=", Comparison::LessOrEqual),
+            ("!=", Comparison::NotEqual),
+            (">", Comparison::Greater),
+            ("<", Comparison::Less),
+            ("=", Comparison::Equal),
+            (":", Comparison::Equal),
+        ]
+        .into_iter()
+        .find_map(|(symbol, comparison)| {
+            rest.strip_prefix(symbol)
+                .map(|value| (comparison, value.trim()))
+        })
+        .ok_or_else(|| QueryError::UnexpectedToken(term.to_string()))?;
+
+        if value.is_empty() {
+            return Err(QueryError::MissingValue(field));
+        }
+
+        let invalid_value = || QueryError::InvalidValue {
+            field: field.clone(),
+            value: value.to_string(),
+        };
+
+        let unsupported_operator = || QueryError::UnsupportedOperator {
+            field: field.clone(),
+            operator: comparison.symbol().to_string(),
+        };
+
+        let is_equality = matches!(comparison, Comparison::Equal | Comparison::NotEqual);
+
+        let predicate = match field.as_str() {
+            // Numbers are the stars from other photo apps, words are the app's own ratings
+            "rating" | "stars" => match value.parse::<u32>() {
+                Ok(stars) if stars <= 5 => PhotoPredicate::Stars(comparison, stars),
+                Err(_) if field == "rating" => {
+                    let rating = PhotoRating::iter()
+                        .find(|rating| rating.to_string().eq_ignore_ascii_case(value))
+                        .ok_or_else(invalid_value)?;
+                    PhotoPredicate::Rating(comparison, rating)
+                }
+                _ => return Err(invalid_value()),
+            },
+            "label" | "color" => {
+                if !is_equality {
+                    return Err(unsupported_operator());
+                }
+
+                let label = match value.to_lowercase().as_str() {
+                    "none" => None,
+                    value => Some(
+                        ColorLabel::iter()
+                            .find(|label| label.to_string().eq_ignore_ascii_case(value))
+                            .ok_or_else(invalid_value)?,
+                    ),
+                };
+                PhotoPredicate::Label(comparison, label)
+            }
+            "date" => match value.split_once("..") {
+                Some((start, end)) => {
+                    if !is_equality {
+                        return Err(unsupported_operator());
+                    }
+                    let (start, _) = Self::parse_date(start).ok_or_else(invalid_value)?;
+                    let (_, end) = Self::parse_date(end).ok_or_else(invalid_value)?;
+                    PhotoPredicate::Date(comparison, start, end)
+                }
+                None => {
+                    let (start, end) = Self::parse_date(value).ok_or_else(invalid_value)?;
+                    PhotoPredicate::Date(comparison, start, end)
+                }
+            },
+            "orientation" => {
+                let orientation = match value.to_lowercase().as_str() {
+                    "landscape" | "horizontal" => Orientation::Landscape,
+                    "portrait" | "vertical" => Orientation::Portrait,
+                    "square" => Orientation::Square,
+                    "panorama" | "pano" => Orientation::Panorama,
+                    _ => return Err(invalid_value()),
+                };
+
+                match comparison {
+                    Comparison::Equal => PhotoPredicate::Orientation(orientation),
+                    Comparison::NotEqual => {
+                        return Ok(PhotoQuery::Not(Box::new(PhotoQuery::Predicate(
+                            PhotoPredicate::Orientation(orientation),
+                        ))))
+                    }
+                    _ => return Err(unsupported_operator()),
+                }
+            }
+            "iso" | "aperture" | "f" | "focal" => {
+                // Ranges like iso:100..800 include both ends
+                if let Some((start, end)) = value.split_once("..") {
+                    if comparison != Comparison::Equal {
+                        return Err(unsupported_operator());
+                    }
+
+                    let start = Self::number_predicate(&field, Comparison::GreaterOrEqual, start)
+                        .ok_or_else(invalid_value)?;
+                    let end = Self::number_predicate(&field, Comparison::LessOrEqual, end)
+                        .ok_or_else(invalid_value)?;
+                    return Ok(PhotoQuery::And(vec![
+                        PhotoQuery::Predicate(start),
+                        PhotoQuery::Predicate(end),
+                    ]));
+                }
+
+                Self::number_predicate(&field, comparison, value).ok_or_else(invalid_value)?
+            }
+            "camera" | "lens" | "name" | "folder" | "tag" | "keyword" => {
+                if !is_equality {
+                    return Err(unsupported_operator());
+                }
+
+                let value = value.to_string();
+                match field.as_str() {
+                    "camera" => PhotoPredicate::Camera(comparison, value),
+                    "lens" => PhotoPredicate::Lens(comparison, value),
+                    "name" => PhotoPredicate::Name(comparison, value),
+                    "tag" | "keyword" => PhotoPredicate::Tag(comparison, value),
+                    _ => PhotoPredicate::Folder(comparison, value),
+                }
+            }
+            _ => return Err(QueryError::UnknownField(field)),
+        };
+
+        Ok(PhotoQuery::Predicate(predicate))
+    }
+
+    // Apertures may be written as f/2.8 and focal lengths as 35mm
+    fn number_predicate(
+        field: &str,
+        comparison: Comparison,
+        value: &str,
+    ) -> Option<PhotoPredicate> {
+        let value = value.trim().to_lowercase();
+
+        match field {
+            "iso" => Some(PhotoPredicate::Iso(comparison, value.parse().ok()?)),
+            "aperture" | "f" => Some(PhotoPredicate::Aperture(
+                comparison,
+                value.trim_start_matches("f/").parse().ok()?,
+            )),
+            _ => Some(PhotoPredicate::FocalLength(
+                comparison,
+                value.trim_end_matches("mm").parse().ok()?,
+            )),
+        }
+    }
+
+    // Parses YYYY, YYYY-MM or YYYY-MM-DD into the first and last day it covers
+    fn parse_date(value: &str) -> Option<(NaiveDate, NaiveDate)> {
+        let parts = value
+            .split('-')
+            .map(|part| part.parse::<u32>().ok())
+            .collect::<Option<Vec<u32>>>()?;
+
+        match parts.as_slice() {
+            [year] => {
+                let start = NaiveDate::from_ymd_opt(*year as i32, 1, 1)?;
+                let end = NaiveDate::from_ymd_opt(*year as i32, 12, 31)?;
+                Some((start, end))
+            }
+            [year, month] => {
+                let start = NaiveDate::from_ymd_opt(*year as i32, *month, 1)?;
+                let end = start.checked_add_months(Months::new(1))?.pred_opt()?;
+                Some((start, end))
+            }
+            [year, month, day] => {
+                let date = NaiveDate::from_ymd_opt(*year as i32, *month, *day)?;
+                Some((date, date))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    OpenParenthesis,
+    CloseParenthesis,
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Word(word) => f.write_str(word),
+            Token::OpenParenthesis => f.write_str("("),
+            Token::CloseParenthesis => f.write_str(")"),
+        }
+    }
+}
+
+// NOT binds tightest, then AND, then OR
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn peek_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token::Word(word)) if word.eq_ignore_ascii_case(keyword))
+    }
+
+    fn parse_or(&mut self) -> Result<PhotoQuery, QueryError> {
+        let mut queries = vec![self.parse_and()?];
+
+        while self.peek_keyword("OR") {
+            self.next();
+            queries.push(self.parse_and()?);
+        }
+
+        Ok(match queries.len() {
+            1 => queries.remove(0),
+            _ => PhotoQuery::Or(queries),
+        })
+    }
+
+    fn parse_and(&mut self) -> Result<PhotoQuery, QueryError> {
+        let mut queries = vec![self.parse_unary()?];
+
+        loop {
+            if self.peek_keyword("AND") {
+                self.next();
+            } else if self.peek_keyword("OR")
+                || matches!(self.peek(), None | Some(Token::CloseParenthesis))
+            {
+                break;
+            }
+
+            queries.push(self.parse_unary()?);
+        }
+
+        Ok(match queries.len() {
+            1 => queries.remove(0),
+            _ => PhotoQuery::And(queries),
+        })
+    }
+
+    fn parse_unary(&mut self) -> Result<PhotoQuery, QueryError> {
+        match self.next() {
+            Some(Token::Word(word)) if word.eq_ignore_ascii_case("NOT") => {
+                Ok(PhotoQuery::Not(Box::new(self.parse_unary()?)))
+            }
+            Some(Token::Word(word)) if word.len() > 1 && word.starts_with('-') => Ok(
+                PhotoQuery::Not(Box::new(PhotoQuery::parse_term(&word[1..])?)),
+            ),
+            Some(Token::Word(word)) => PhotoQuery::parse_term(&word),
+            Some(Token::OpenParenthesis) => {
+                let query = self.parse_or()?;
+                match self.next() {
+                    Some(Token::CloseParenthesis) => Ok(query),
+                    _ => Err(QueryError::UnclosedParenthesis),
+                }
+            }
+            Some(Token::CloseParenthesis) => Err(QueryError::UnexpectedToken(")".to_string())),
+            None => Err(QueryError::MissingValue("search".to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn predicate(query: &str) -> PhotoPredicate {
+        match PhotoQuery::parse(query) {
+            Ok(PhotoQuery::Predicate(predicate)) => predicate,
+            other => panic!("{} parsed to {:?}", query, other),
+        }
+    }
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn fields_and_operators() {
+        assert_eq!(
+            predicate("rating>=maybe"),
+            PhotoPredicate::Rating(Comparison::GreaterOrEqual, PhotoRating::Maybe)
+        );
+        assert_eq!(
+            predicate("label!=red"),
+            PhotoPredicate::Label(Comparison::NotEqual, Some(ColorLabel::Red))
+        );
+        assert_eq!(
+            predicate("label:none"),
+            PhotoPredicate::Label(Comparison::Equal, None)
+        );
+        assert_eq!(
+            predicate("iso<800"),
+            PhotoPredicate::Iso(Comparison::Less, 800)
+        );
+        assert_eq!(
+            predicate("aperture<=f/2.8"),
+            PhotoPredicate::Aperture(Comparison::LessOrEqual, 2.8)
+        );
+        assert_eq!(
+            predicate("focal>35mm"),
+            PhotoPredicate::FocalLength(Comparison::Greater, 35.0)
+        );
+        assert_eq!(
+            predicate("orientation:vertical"),
+            PhotoPredicate::Orientation(Orientation::Portrait)
+        );
+    }
+
+    #[test]
+    fn numeric_ratings_are_stars() {
+        assert_eq!(
+            predicate("rating>=4"),
+            PhotoPredicate::Stars(Comparison::GreaterOrEqual, 4)
+        );
+        assert_eq!(
+            predicate("stars:0"),
+            PhotoPredicate::Stars(Comparison::Equal, 0)
+        );
+        assert!(matches!(
+            PhotoQuery::parse("rating>=6"),
+            Err(QueryError::InvalidValue { .. })
+        ));
+        assert!(matches!(
+            PhotoQuery::parse("stars:yes"),
+            Err(QueryError::InvalidValue { .. })
+        ));
+    }
+
+    #[test]
+    fn tags_and_quoted_text() {
+        assert_eq!(
+            predicate("tag:beach"),
+            PhotoPredicate::Tag(Comparison::Equal, "beach".to_string())
+        );
+        assert_eq!(
+            predicate("camera:\"Canon EOS R5\""),
+            PhotoPredicate::Camera(Comparison::Equal, "Canon EOS R5".to_string())
+        );
+        assert_eq!(
+            predicate("sunset"),
+            PhotoPredicate::Name(Comparison::Equal, "sunset".to_string())
+        );
+    }
+
+    #[test]
+    fn dates_cover_the_whole_period() {
+        assert_eq!(
+            predicate("date:2023"),
+            PhotoPredicate::Date(Comparison::Equal, date(2023, 1, 1), date(2023, 12, 31))
+        );
+        assert_eq!(
+            predicate("date:2024-02"),
+            PhotoPredicate::Date(Comparison::Equal, date(2024, 2, 1), date(2024, 2, 29))
+        );
+        assert_eq!(
+            predicate("date:2023-06..2023-07-15"),
+            PhotoPredicate::Date(Comparison::Equal, date(2023, 6, 1), date(2023, 7, 15))
+        );
+    }
+
+    #[test]
+    fn number_ranges_include_both_ends() {
+        assert_eq!(
+            PhotoQuery::parse("iso:100..800"),
+            Ok(PhotoQuery::And(vec![
+                PhotoQuery::Predicate(PhotoPredicate::Iso(Comparison::GreaterOrEqual, 100)),
+                PhotoQuery::Predicate(PhotoPredicate::Iso(Comparison::LessOrEqual, 800)),
+            ]))
+        );
+    }
+
+    #[test]
+    fn not_binds_tighter_than_and_which_binds_tighter_than_or() {
+        let yes =
+            PhotoQuery::Predicate(PhotoPredicate::Rating(Comparison::Equal, PhotoRating::Yes));
+        let red = PhotoQuery::Predicate(PhotoPredicate::Label(
+            Comparison::Equal,
+            Some(ColorLabel::Red),
+        ));
+        let square = PhotoQuery::Predicate(PhotoPredicate::Orientation(Orientation::Square));
+
+        assert_eq!(
+            PhotoQuery::parse("rating:yes label:red OR NOT orientation:square"),
+            Ok(PhotoQuery::Or(vec![
+                PhotoQuery::And(vec![yes.clone(), red.clone()]),
+                PhotoQuery::Not(Box::new(square.clone())),
+            ]))
+        );
+        assert_eq!(
+            PhotoQuery::parse("rating:yes AND (label:red OR -orientation:square)"),
+            Ok(PhotoQuery::And(vec![
+                yes,
+                PhotoQuery::Or(vec![red, PhotoQuery::Not(Box::new(square))]),
+            ]))
+        );
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            PhotoQuery::parse("colour:red"),
+            Err(QueryError::UnknownField("colour".to_string()))
+        );
+        assert!(matches!(
+            PhotoQuery::parse("rating:great"),
+            Err(QueryError::InvalidValue { .. })
+        ));
+        assert!(matches!(
+            PhotoQuery::parse("camera>canon"),
+            Err(QueryError::UnsupportedOperator { .. })
+        ));
+        assert_eq!(
+            PhotoQuery::parse("iso:"),
+            Err(QueryError::MissingValue("iso".to_string()))
+        );
+        assert_eq!(
+            PhotoQuery::parse("(rating:yes"),
+            Err(QueryError::UnclosedParenthesis)
+        );
+        assert_eq!(
+            PhotoQuery::parse("rating:yes)"),
+            Err(QueryError::UnexpectedToken(")".to_string()))
+        );
+    }
+}
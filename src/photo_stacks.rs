@@ -0,0 +1,142 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+// Suffixes editors and exporters commonly add to the name of a derivative file
+const DERIVATIVE_SUFFIXES: [&str; 9] = [
+    "edit",
+    "edited",
+    "retouched",
+    "export",
+    "exported",
+    "final",
+    "copy",
+    "hdr",
+    "bw",
+];
+
+/// Groups versions of the same image, like an original and its retouched export, into stacks.
+/// Versions are matched by folder and file name, ignoring the extension and any derivative
+/// suffix such as "-edited" or " (1)". Only the primary version of a stack is shown in the
+/// gallery.
+#[derive(Debug, Default)]
+pub struct PhotoStacks {
+    // Stack key to the paths of every version, in photo order
+    versions: HashMap<PathBuf, Vec<PathBuf>>,
+    // Versions picked as the primary of their stack. Stacks without one use their original.
+    primaries: HashMap<PathBuf, PathBuf>,
+}
+
+impl PhotoStacks {
+    pub fn rebuild<'a>(&mut self, paths: impl Iterator<Item = &'a PathBuf>) {
+        self.versions.clear();
+        for path in paths {
+            self.versions
+                .entry(Self::key(path))
+                .or_default()
+                .push(path.clone());
+        }
+    }
+
+    /// Every version in the stack `path` belongs to, including `path` itself
+    pub fn versions(&self, path: &Path) -> &[PathBuf] {
+        self.versions
+            .get(&Self::key(path))
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    pub fn primary(&self, path: &Path) -> Option<&PathBuf> {
+        let key = Self::key(path);
+        let versions = self.versions.get(&key)?;
+
+        self.primaries
+            .get(&key)
+            .filter(|primary| versions.contains(*primary))
+            .or_else(|| {
+                // The version without a derivative suffix is the original
+                versions
+                    .iter()
+                    .find(|version| {
+                        version
+                            .file_stem()
+                            .map(|stem| stem.to_string_lossy().to_lowercase())
+                            == key
+                                .file_name()
+                                .map(|name| name.to_string_lossy().to_string())
+                    })
+                    .or_else(|| versions.first())
+            })
+    }
+
+    pub fn is_primary(&self, path: &Path) -> bool {
+        match self.primary(path) {
+            Some(primary) => primary == path,
+            None => true,
+        }
+    }
+
+    pub fn set_primary(&mut self, path: PathBuf) {
+        self.primaries.insert(Self::key(&path), path);
+    }
+
+    /// The versions explicitly picked as primary, for saving with the project
+    pub fn chosen_primaries(&self) -> Vec<PathBuf> {
+        self.primaries.values().cloned().collect()
+    }
+
+    pub fn set_chosen_primaries(&mut self, primaries: Vec<PathBuf>) {
+        self.primaries = primaries
+            .into_iter()
+            .map(|primary| (Self::key(&primary), primary))
+            .collect();
+    }
+
+    fn key(path: &Path) -> PathBuf {
+        let stem = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+
+        path.with_file_name(Self::base_stem(&stem))
+    }
+
+    // Strips derivative suffixes until none are left, so "img_1-edited (2)" becomes "img_1"
+    fn base_stem(stem: &str) -> String {
+        let mut stem = stem.trim_end();
+
+        loop {
+            let stripped = Self::strip_copy_number(stem)
+                .or_else(|| Self::strip_derivative_suffix(stem))
+                .map(str::trim_end);
+
+            match stripped {
+                Some(stripped) if !stripped.is_empty() => stem = stripped,
+                _ => return stem.to_string(),
+            }
+        }
+    }
+
+    // " (1)", added by most file managers when a name is taken
+    fn strip_copy_number(stem: &str) -> Option<&str> {
+        let without_paren = stem.strip_suffix(')')?;
+        let open = without_paren.rfind('(')?;
+        let number = &without_paren[open + 1..];
+
+        (!number.is_empty() && number.chars().all(|c| c.is_ascii_digit())).then(|| &stem[..open])
+    }
+
+    fn strip_derivative_suffix(stem: &str) -> Option<&str> {
+        // Allows a trailing number, "edit2" or "edited-3"
+        let without_number = stem
+            .trim_end_matches(|c: char| c.is_ascii_digit())
+            .trim_end_matches(['-', '_', ' ']);
+
+        DERIVATIVE_SUFFIXES.iter().find_map(|suffix| {
+            let rest = without_number.strip_suffix(suffix)?;
+            let separator = rest.chars().last()?;
+            matches!(separator, '-' | '_' | ' ' | '.').then(|| &rest[..rest.len() - 1])
+        })
+    }
+}
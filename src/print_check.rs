@@ -0,0 +1,137 @@
+use egui::{emath::Rot2, Rect};
+
+use crate::{
+    id::LayerId,
+    render,
+    widget::{
+        canvas::CanvasState,
+        canvas_info::layers::{Layer, LayerContent},
+    },
+};
+
+const POINTS_PER_INCH: f32 = 72.0;
+
+// Text smaller than this is hard to read once printed
+pub const MIN_TEXT_SIZE_PT: f32 = 6.0;
+
+// Lines thinner than this may not show up at all in print, about 0.1 mm
+pub const MIN_STROKE_WIDTH_PT: f32 = 0.25;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PrintIssueKind {
+    SmallText,
+    TextNearTrim,
+    ThinStroke,
+}
+
+/// Something on a page that's likely to print badly, found before the book is exported
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrintIssue {
+    // Starting at 0
+    pub page: usize,
+    pub layer: LayerId,
+    pub kind: PrintIssueKind,
+    pub message: String,
+}
+
+/// Checks the visible layers of `pages` for text and lines that are too small to print well and
+/// text close enough to the trim edge to be cut off. Sizes are measured at each page's PPI so the
+/// check follows the physical size of the page.
+pub fn check_pages(pages: &[CanvasState]) -> Vec<PrintIssue> {
+    let mut issues = vec![];
+
+    for (index, page) in pages.iter().enumerate() {
+        let ppi = page.page.ppi() as f32;
+        let to_points = |pixels: f32| pixels / ppi * POINTS_PER_INCH;
+
+        let page_rect = render::page_rect(page);
+        let safe_margin = page.page.safe_margin_pixels();
+        let safe_rect = page_rect.shrink(safe_margin);
+
+        for layer in page.layers.values().filter(|layer| layer.visible) {
+            let mut issue = |kind, message| {
+                issues.push(PrintIssue {
+                    page: index,
+                    layer: layer.id,
+                    kind,
+                    message,
+                })
+            };
+
+            match &layer.content {
+                LayerContent::Text(text) | LayerContent::TemplateText { text, .. } => {
+                    if text.text.trim().is_empty() {
+                        continue;
+                    }
+
+                    let size_pt = to_points(text.font_size);
+                    if size_pt < MIN_TEXT_SIZE_PT {
+                        issue(
+                            PrintIssueKind::SmallText,
+                            format!(
+                                "{} is {:.1} pt, text under {:.0} pt is hard to read in print",
+                                layer.name, size_pt, MIN_TEXT_SIZE_PT
+                            ),
+                        );
+                    }
+
+                    let bounds = printed_bounds(page_rect, layer);
+                    if bounds.intersects(page_rect) && !safe_rect.contains_rect(bounds) {
+                        issue(
+                            PrintIssueKind::TextNearTrim,
+                            format!(
+                                "{} is within {:.1} mm of the trim edge and may be cut off",
+                                layer.name,
+                                safe_margin / ppi * 25.4
+                            ),
+                        );
+                    }
+                }
+                LayerContent::Path(path) => {
+                    let width_pt = to_points(path.stroke_width);
+                    if path.stroke_width > 0.0
+                        && path.stroke_color.a() > 0
+                        && width_pt < MIN_STROKE_WIDTH_PT
+                    {
+                        issue(
+                            PrintIssueKind::ThinStroke,
+                            format!(
+                                "{} has a {:.2} pt outline, lines under {:.2} pt may not print",
+                                layer.name, width_pt, MIN_STROKE_WIDTH_PT
+                            ),
+                        );
+                    }
+                }
+                LayerContent::Photo(_)
+                | LayerContent::TemplatePhoto { .. }
+                | LayerContent::Decoration(_) => {}
+            }
+        }
+    }
+
+    issues
+}
+
+// The area of the page a layer covers once rotated
+fn printed_bounds(page_rect: Rect, layer: &Layer) -> Rect {
+    let (rect, rotation) = match &layer.content {
+        LayerContent::TemplateText { region, .. } => (render::region_rect(page_rect, region), 0.0),
+        _ => (layer.transform_state.rect, layer.transform_state.rotation),
+    };
+
+    if rotation == 0.0 {
+        return rect;
+    }
+
+    let center = rect.center();
+    let rotation = Rot2::from_angle(rotation);
+    Rect::from_points(
+        &[
+            rect.left_top(),
+            rect.right_top(),
+            rect.right_bottom(),
+            rect.left_bottom(),
+        ]
+        .map(|corner| center + rotation * (corner - center)),
+    )
+}
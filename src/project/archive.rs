@@ -0,0 +1,250 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{Read, Write},
+    path::{Path, PathBuf},
+};
+
+use image::{
+    codecs::jpeg::JpegEncoder, imageops::FilterType, DynamicImage, ImageDecoder, ImageFormat,
+    ImageReader,
+};
+use log::{info, warn};
+use zip::{write::SimpleFileOptions, ZipArchive, ZipWriter};
+
+use crate::{
+    dependencies::{Dependency, SingletonFor},
+    font_manager::FontManager,
+    project_fonts,
+};
+
+use super::v1::{Project, ProjectError, ProjectFileKind};
+
+pub const ARCHIVE_EXTENSION: &str = "pbz";
+
+const PROJECT_FILE_NAME: &str = "project.rpb";
+const PHOTOS_DIR: &str = "photos";
+const DECORATIONS_DIR: &str = "decorations";
+const FONTS_DIR: &str = "fonts";
+
+// Close to how most cameras save, the encoder's default is noticeably softer
+const ARCHIVE_JPEG_QUALITY: u8 = 90;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ArchiveOptions {
+    // JPEGs larger than this on their longest side are resized before being added
+    pub max_photo_dimension: Option<u32>,
+}
+
+/// A single zip file holding a project along with every photo, asset and font it uses.
+/// Paths inside the archived project file are relative to the archive root.
+pub struct ProjectArchive;
+
+impl ProjectArchive {
+    pub fn export(
+        mut project: Project,
+        path: &Path,
+        options: ArchiveOptions,
+    ) -> Result<(), ProjectError> {
+        let mut zip = ZipWriter::new(File::create(path)?);
+        let file_options = SimpleFileOptions::default();
+
+        let mut archived: HashMap<PathBuf, PathBuf> = HashMap::new();
+
+        for (kind, file_path) in project.file_paths_mut() {
+            if let Some(archived_path) = archived.get(file_path) {
+                *file_path = archived_path.clone();
+                continue;
+            }
+
+            let Some(file_name) = file_path.file_name() else {
+                continue;
+            };
+
+            let directory = match kind {
+                ProjectFileKind::Photo => PHOTOS_DIR,
+                ProjectFileKind::Decoration => DECORATIONS_DIR,
+            };
+
+            // Prefixed so files with the same name from different folders don't collide
+            let archived_path = PathBuf::from(directory).join(format!(
+                "{}_{}",
+                archived.len(),
+                file_name.to_string_lossy()
+            ));
+
+            let downscaled = match (kind, options.max_photo_dimension) {
+                (ProjectFileKind::Photo, Some(max_dimension)) => {
+                    match Self::downscaled_photo(file_path, max_dimension) {
+                        Ok(bytes) => Some(bytes),
+                        Err(err) => {
+                            warn!(
+                                "Failed to downscale {}, archiving the original: {:?}",
+                                file_path.display(),
+                                err
+                            );
+                            None
+                        }
+                    }
+                }
+                _ => None,
+            };
+
+            let bytes = match downscaled.map_or_else(|| std::fs::read(&*file_path), Ok) {
+                Ok(bytes) => bytes,
+                Err(err) => {
+                    warn!(
+                        "Skipping {} in project archive: {:?}",
+                        file_path.display(),
+                        err
+                    );
+                    continue;
+                }
+            };
+
+            zip.start_file(Self::entry_name(&archived_path), file_options)?;
+            zip.write_all(&bytes)?;
+
+            archived.insert(file_path.clone(), archived_path.clone());
+            *file_path = archived_path;
+        }
+
+        let font_files = Dependency::<FontManager>::get()
+            .with_lock(|font_manager| font_manager.font_files(&project.font_families()));
+        for font_path in font_files {
+            let Some(file_name) = font_path.file_name() else {
+                continue;
+            };
+
+            match std::fs::read(&font_path) {
+                Ok(bytes) => {
+                    let archived_path = PathBuf::from(FONTS_DIR).join(file_name);
+                    zip.start_file(Self::entry_name(&archived_path), file_options)?;
+                    zip.write_all(&bytes)?;
+                }
+                Err(err) => {
+                    warn!("Skipping font {}: {:?}", font_path.display(), err);
+                }
+            }
+        }
+
+        zip.start_file(PROJECT_FILE_NAME, file_options)?;
+        zip.write_all(serde_json::to_string_pretty(&project)?.as_bytes())?;
+        zip.finish()?;
+
+        info!(
+            "Exported project archive with {} files to {}",
+            archived.len(),
+            path.display()
+        );
+
+        Ok(())
+    }
+
+    /// Unpacks the archive into `destination` and returns the path of the relinked project file
+    pub fn import(path: &Path, destination: &Path) -> Result<PathBuf, ProjectError> {
+        let mut zip = ZipArchive::new(File::open(path)?)?;
+
+        let mut project_data = String::new();
+        zip.by_name(PROJECT_FILE_NAME)
+            .map_err(|_| ProjectError::MissingProjectFile)?
+            .read_to_string(&mut project_data)?;
+
+        zip.extract(destination)?;
+
+        let mut project: Project = serde_json::from_str(&project_data)?;
+        for (_, file_path) in project.file_paths_mut() {
+            if file_path.is_relative() {
+                *file_path = destination.join(&*file_path);
+            }
+        }
+
+        let project_path = destination.join(PROJECT_FILE_NAME);
+        std::fs::write(&project_path, serde_json::to_string_pretty(&project)?)?;
+
+        project_fonts::install_fonts(&destination.join(FONTS_DIR))?;
+
+        Ok(project_path)
+    }
+
+    // The pixels keep their stored orientation and the EXIF block is copied over, so the photo's
+    // rotation, stored crops and shooting details stay as they were. Only JPEGs get the EXIF
+    // block back, other formats and JPEGs whose EXIF block doesn't fit in a segment are archived
+    // as they are rather than lose it.
+    fn downscaled_photo(path: &Path, max_dimension: u32) -> Result<Vec<u8>, ProjectError> {
+        let reader = ImageReader::open(path)?.with_guessed_format()?;
+        if reader.format() != Some(ImageFormat::Jpeg) {
+            return Ok(std::fs::read(path)?);
+        }
+
+        let mut decoder = reader.into_decoder()?;
+        let (width, height) = decoder.dimensions();
+        if width.max(height) <= max_dimension {
+            return Ok(std::fs::read(path)?);
+        }
+
+        let exif = decoder.exif_metadata()?;
+        let image = DynamicImage::from_decoder(decoder)?;
+
+        let mut bytes = Vec::new();
+        image
+            .resize(max_dimension, max_dimension, FilterType::Lanczos3)
+            .to_rgb8()
+            .write_with_encoder(JpegEncoder::new_with_quality(
+                &mut bytes,
+                ARCHIVE_JPEG_QUALITY,
+            ))?;
+
+        match exif {
+            Some(exif) => match with_exif(&bytes, &exif) {
+                Some(bytes) => Ok(bytes),
+                None => Ok(std::fs::read(path)?),
+            },
+            None => Ok(bytes),
+        }
+    }
+
+    // Zip entries always use forward slashes regardless of platform
+    fn entry_name(path: &Path) -> String {
+        path.to_string_lossy().replace('\\', "/")
+    }
+}
+
+// Adds an APP1 segment holding `exif` to an encoded JPEG, after its JFIF header if it has one.
+// None if the EXIF block is too big for a segment or the JPEG isn't one.
+fn with_exif(jpeg: &[u8], exif: &[u8]) -> Option<Vec<u8>> {
+    const EXIF_HEADER: &[u8] = b"Exif\0\0";
+    const START_OF_IMAGE: [u8; 2] = [0xFF, 0xD8];
+    const APP0: [u8; 2] = [0xFF, 0xE0];
+
+    if !jpeg.starts_with(&START_OF_IMAGE) {
+        return None;
+    }
+
+    // Decoders give the block with or without the identifier in front
+    let header: &[u8] = if exif.starts_with(EXIF_HEADER) {
+        &[]
+    } else {
+        EXIF_HEADER
+    };
+
+    // The segment length counts its own two bytes
+    let segment_length = u16::try_from(header.len() + exif.len() + 2).ok()?;
+
+    let mut insert_at = START_OF_IMAGE.len();
+    if jpeg.get(2..4) == Some(&APP0[..]) {
+        if let Some(length) = jpeg.get(4..6) {
+            insert_at += 2 + u16::from_be_bytes([length[0], length[1]]) as usize;
+        }
+    }
+    let insert_at = insert_at.min(jpeg.len());
+
+    let mut with_exif = Vec::with_capacity(jpeg.len() + segment_length as usize + 2);
+    with_exif.extend_from_slice(&jpeg[..insert_at]);
+    with_exif.extend_from_slice(&[0xFF, 0xE1]);
+    with_exif.extend_from_slice(&segment_length.to_be_bytes());
+    with_exif.extend_from_slice(header);
+    with_exif.extend_from_slice(exif);
+    with_exif.extend_from_slice(&jpeg[insert_at..]);
+    Some(with_exif)
+}
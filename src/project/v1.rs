@@ -1,42 +1,101 @@
-use std::path::PathBuf;
+use std::{
+    cell::RefCell,
+    collections::HashSet,
+    fs::File,
+    io::Write,
+    path::{Path, PathBuf},
+};
 
-use egui::{Color32, FontId, Id, Pos2, Rect, Vec2};
+use chrono::{DateTime, Utc};
+use egui::{Color32, FontFamily, FontId, Id, Pos2, Rect, Vec2};
+use fxhash::hash64;
 use indexmap::IndexMap;
-use serde::{Deserialize, Serialize};
+use log::warn;
+use serde::{ser::SerializeSeq, Deserialize, Serialize};
 use thiserror::Error;
+use tokio::task::spawn_blocking;
 
 use crate::{
+    auto_persisting::AutoPersisting,
+    book_metadata::BookMetadata as AppBookMetadata,
+    book_theme::{
+        BookTheme as AppBookTheme, ThemeColorRole as AppThemeColorRole,
+        ThemeFontRole as AppThemeFontRole, ThemeStroke as AppThemeStroke,
+    },
+    config::{Config, ConfigModification},
+    decoration::{Decoration as AppDecoration, DecorationKind as AppDecorationKind},
     dependencies::{Dependency, Singleton, SingletonFor},
+    error_sink::ErrorSink,
     id::{next_layer_id, next_page_id, set_min_layer_id, LayerId, PageId},
     model::{
-        edit_state::EditablePage, page::Page as AppPage, scale_mode::ScaleMode as AppScaleMode,
+        canvas_path::{CanvasPath as AppCanvasPath, PathPoint as AppPathPoint},
+        caption_anchor::{CaptionAnchor as AppCaptionAnchor, CaptionSide as AppCaptionSide},
+        color_label::ColorLabel as AppColorLabel,
+        edit_state::EditablePage,
+        fill::{
+            Fill as AppFill, Gradient as AppGradient, GradientKind as AppGradientKind,
+            GradientStop as AppGradientStop,
+        },
+        guide::{Guide as AppGuide, GuideOrientation as AppGuideOrientation},
+        layer_constraints::{LayerConstraints as AppLayerConstraints, Pin as AppPin},
+        measurement::Measurement as AppMeasurement,
+        page::Page as AppPage,
+        page_comment::PageComment as AppPageComment,
+        page_notes::{ChecklistItem as AppChecklistItem, PageNotes as AppPageNotes},
+        photo_effects::PhotoEffects as AppPhotoEffects,
+        photo_orientation::PhotoOrientation as AppPhotoOrientation,
+        resize_quality::ResizeQuality as AppResizeQuality,
+        scale_mode::{PhotoAlignment as AppPhotoAlignment, ScaleMode as AppScaleMode},
         unit::Unit as AppUnit,
     },
-    photo::{Photo as AppPhoto, PhotoRating as AppPhotoRating},
-    photo_manager::{PhotoManager, PhotosGrouping as AppPhotosGrouping},
-    project_settings::{ProjectSettings as AppProjectSettings, ProjectSettingsManager},
+    photo::{Photo as AppPhoto, PhotoRating as AppPhotoRating, PhotoRotation as AppPhotoRotation},
+    photo_adjustments::PhotoAdjustments as AppPhotoAdjustments,
+    photo_captions::PhotoCaptions as AppPhotoCaptions,
+    photo_database::PhotoDatabase,
+    photo_manager::{
+        PhotoManager, PhotosGrouping as AppPhotosGrouping, PhotosSort as AppPhotosSort,
+        PhotosSortField as AppPhotosSortField, SortDirection as AppSortDirection,
+    },
+    project_fonts,
+    project_settings::{
+        ExportedPage as AppExportedPage, PageHashCache, ProjectSettings as AppProjectSettings,
+        ProjectSettingsManager,
+    },
     scene::{
         canvas_scene::{CanvasScene, CanvasSceneState},
         organize_edit_scene::OrganizeEditScene,
         organize_scene::GalleryScene,
     },
+    session::Session,
+    snippet::{Snippet as AppSnippet, SnippetManager},
     template::{
-        Template as AppTemplate, TemplateRegion as AppTemplateRegion,
-        TemplateRegionKind as AppTemplateRegionKind,
+        autofill::TemplateAutofill as AppTemplateAutofill, Template as AppTemplate,
+        TemplateRegion as AppTemplateRegion, TemplateRegionKind as AppTemplateRegionKind,
     },
+    text_style::TextStylePreset as AppTextStylePreset,
+    trash::{TrashEntry as AppTrashEntry, TrashManager, TrashedItem as AppTrashedItem},
+    typography::{HyphenationLanguage as AppHyphenationLanguage, Typography as AppTypography},
     utils::IdExt,
     widget::{
         canvas::{CanvasPhoto as AppCanvasPhoto, CanvasState},
-        canvas_info::layers::{
-            CanvasText as AppCanvasText, CanvasTextEditState, Layer as AppLayer,
-            LayerContent as AppLayerContent, LayerTransformEditState,
-            TextHorizontalAlignment as AppTextHorizontalAlignment,
-            TextVerticalAlignment as AppTextVerticalAlignment,
+        canvas_info::{
+            layers::{
+                CanvasText as AppCanvasText, CanvasTextEditState, Layer as AppLayer,
+                LayerContent as AppLayerContent, LayerTransformEditState,
+                TextHorizontalAlignment as AppTextHorizontalAlignment,
+                TextOrientation as AppTextOrientation, TextSpacing as AppTextSpacing,
+                TextVerticalAlignment as AppTextVerticalAlignment,
+            },
+            panel::{InfoPanelState as AppInfoPanelState, InfoTab as AppInfoTab},
+            quick_layout::QuickLayoutParameters as AppQuickLayoutParameters,
         },
         transformable::{ResizeMode, TransformHandleMode::Resize, TransformableState},
     },
 };
 
+// Project files are written this many bytes at a time so saving big projects can show progress
+const WRITE_CHUNK_SIZE: usize = 256 * 1024;
+
 #[derive(Error, Debug)]
 pub enum ProjectError {
     #[error("IO error: {0}")]
@@ -44,6 +103,24 @@ pub enum ProjectError {
 
     #[error("Serde error: {0}")]
     SerdeError(#[from] serde_json::Error),
+
+    #[error("Archive error: {0}")]
+    ArchiveError(#[from] zip::result::ZipError),
+
+    #[error("Image error: {0}")]
+    ImageError(#[from] image::ImageError),
+
+    #[error("Archive does not contain a project file")]
+    MissingProjectFile,
+
+    #[error("The project is already being saved")]
+    SaveInProgress,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProjectFileKind {
+    Photo,
+    Decoration,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -51,7 +128,20 @@ pub struct Project {
     pub photos: Vec<Photo>,
     pub pages: Vec<CanvasPage>,
     pub group_by: PhotosGrouping,
+    #[serde(default)]
+    pub sort_by: PhotosSort,
     pub project_settings: ProjectSettings,
+    #[serde(default)]
+    pub trash: Vec<TrashEntry>,
+    // Photos picked as the primary version of their stack
+    #[serde(default)]
+    pub stack_primaries: Vec<PathBuf>,
+    #[serde(default)]
+    pub captions: Vec<PhotoCaptions>,
+    #[serde(default)]
+    pub info_panel: InfoPanel,
+    #[serde(default)]
+    pub snippets: Vec<Snippet>,
 }
 
 impl Project {
@@ -62,6 +152,13 @@ impl Project {
             .map(|photo| Photo {
                 path: photo.0.clone(),
                 rating: photo.1.rating.into(),
+                color_label: photo.1.color_label.map(AppColorLabel::into),
+                imported_at: photo_manager.import_date(photo.0),
+                orientation_override: photo
+                    .1
+                    .metadata
+                    .orientation_override
+                    .map(AppPhotoRotation::into),
             })
             .collect();
 
@@ -72,231 +169,291 @@ impl Project {
 
         let pages: Vec<CanvasPage> = app_pages
             .values_mut()
-            .map(|canvas_state| {
-                let layers = canvas_state
-                    .layers
-                    .values_mut()
-                    .map(|layer| {
-                        layer.transform_edit_state.update(&layer.transform_state);
-
-                        Layer {
-                            content: match layer.content.clone() {
-                                AppLayerContent::Photo(canvas_photo) => {
-                                    LayerContent::Photo(CanvasPhoto {
-                                        photo: Photo {
-                                            path: canvas_photo.photo.path,
-                                            rating: canvas_photo.photo.rating.into(),
-                                        },
-                                        crop: canvas_photo.crop,
-                                    })
-                                }
-                                AppLayerContent::Text(canvas_text) => {
-                                    LayerContent::Text(CanvasText {
-                                        text: canvas_text.text,
-                                        font_size: canvas_text.font_size,
-                                        font_id: canvas_text.font_id,
-                                        color: canvas_text.color,
-                                        horizontal_alignment: match canvas_text.horizontal_alignment
-                                        {
-                                            AppTextHorizontalAlignment::Left => {
-                                                TextHorizontalAlignment::Left
-                                            }
-                                            AppTextHorizontalAlignment::Center => {
-                                                TextHorizontalAlignment::Center
-                                            }
-                                            AppTextHorizontalAlignment::Right => {
-                                                TextHorizontalAlignment::Right
-                                            }
-                                        },
-                                        vertical_alignment: match canvas_text.vertical_alignment {
-                                            AppTextVerticalAlignment::Top => {
-                                                TextVerticalAlignment::Top
-                                            }
-                                            AppTextVerticalAlignment::Center => {
-                                                TextVerticalAlignment::Center
-                                            }
-                                            AppTextVerticalAlignment::Bottom => {
-                                                TextVerticalAlignment::Bottom
-                                            }
-                                        },
-                                    })
-                                }
-                                AppLayerContent::TemplatePhoto {
-                                    region,
-                                    photo,
-                                    scale_mode,
-                                } => LayerContent::TemplatePhoto {
-                                    region: TemplateRegion {
-                                        relative_position: region.relative_position,
-                                        relative_size: region.relative_size,
-                                        kind: match region.kind {
-                                            AppTemplateRegionKind::Image => {
-                                                TemplateRegionKind::Image
-                                            }
-                                            AppTemplateRegionKind::Text {
-                                                sample_text,
-                                                font_size,
-                                            } => TemplateRegionKind::Text {
-                                                sample_text,
-                                                font_size,
-                                            },
-                                        },
-                                    },
-                                    photo: photo.map(|canvas_photo| CanvasPhoto {
-                                        photo: Photo {
-                                            path: canvas_photo.photo.path,
-                                            rating: canvas_photo.photo.rating.into(),
-                                        },
-                                        crop: canvas_photo.crop,
-                                    }),
-                                    scale_mode: match scale_mode {
-                                        AppScaleMode::Fit => ScaleMode::Fit,
-                                        AppScaleMode::Fill => ScaleMode::Fill,
-                                        AppScaleMode::Stretch => ScaleMode::Stretch,
-                                    },
-                                },
-                                AppLayerContent::TemplateText { region, text } => {
-                                    LayerContent::TemplateText {
-                                        region: TemplateRegion {
-                                            relative_position: region.relative_position,
-                                            relative_size: region.relative_size,
-                                            kind: match region.kind {
-                                                AppTemplateRegionKind::Image => {
-                                                    TemplateRegionKind::Image
-                                                }
-                                                AppTemplateRegionKind::Text {
-                                                    sample_text,
-                                                    font_size,
-                                                } => TemplateRegionKind::Text {
-                                                    sample_text,
-                                                    font_size,
-                                                },
-                                            },
-                                        },
-                                        text: CanvasText {
-                                            text: text.text,
-                                            font_size: text.font_size,
-                                            font_id: text.font_id,
-                                            color: text.color,
-                                            horizontal_alignment: match text.horizontal_alignment {
-                                                AppTextHorizontalAlignment::Left => {
-                                                    TextHorizontalAlignment::Left
-                                                }
-                                                AppTextHorizontalAlignment::Center => {
-                                                    TextHorizontalAlignment::Center
-                                                }
-                                                AppTextHorizontalAlignment::Right => {
-                                                    TextHorizontalAlignment::Right
-                                                }
-                                            },
-                                            vertical_alignment: match text.vertical_alignment {
-                                                AppTextVerticalAlignment::Top => {
-                                                    TextVerticalAlignment::Top
-                                                }
-                                                AppTextVerticalAlignment::Center => {
-                                                    TextVerticalAlignment::Center
-                                                }
-                                                AppTextVerticalAlignment::Bottom => {
-                                                    TextVerticalAlignment::Bottom
-                                                }
-                                            },
-                                        },
-                                    }
-                                }
-                            },
-                            name: layer.name.clone(),
-                            visible: layer.visible,
-                            locked: layer.locked,
-                            selected: layer.selected,
-                            id: layer.id,
-                            rect: layer.transform_state.rect,
-                            rotation: layer.transform_state.rotation,
-                        }
-                    })
-                    .collect();
-
-                let template = canvas_state.template.clone();
-                CanvasPage {
-                    layers,
-                    page: Page {
-                        size: canvas_state.page.size(),
-                        ppi: canvas_state.page.ppi(),
-                        unit: match canvas_state.page.unit() {
-                            AppUnit::Pixels => Unit::Pixels,
-                            AppUnit::Inches => Unit::Inches,
-                            AppUnit::Centimeters => Unit::Centimeters,
-                        },
-                    },
-                    template: template.map(|template| Template {
-                        name: template.name,
-                        page: Page {
-                            size: template.page.size(),
-                            ppi: template.page.ppi(),
-                            unit: match template.page.unit() {
-                                AppUnit::Pixels => Unit::Pixels,
-                                AppUnit::Inches => Unit::Inches,
-                                AppUnit::Centimeters => Unit::Centimeters,
-                            },
-                        },
-                        regions: template
-                            .regions
-                            .iter()
-                            .map(|region| TemplateRegion {
-                                relative_position: region.relative_position,
-                                relative_size: region.relative_size,
-                                kind: match &region.kind {
-                                    AppTemplateRegionKind::Image => TemplateRegionKind::Image,
-                                    AppTemplateRegionKind::Text {
-                                        sample_text,
-                                        font_size,
-                                    } => TemplateRegionKind::Text {
-                                        sample_text: sample_text.clone(),
-                                        font_size: *font_size,
-                                    },
-                                },
-                            })
-                            .collect(),
-                    }),
-                    quick_layout_order: canvas_state.quick_layout_order.clone(),
-                }
-            })
+            .map(CanvasPage::from_canvas_state)
             .collect();
 
+        let info_panel = match &root_scene.edit {
+            Some(edit) => edit.read().unwrap().state.info_panel.clone().into(),
+            None => InfoPanel::default(),
+        };
+
         let group_by = photo_manager.photo_grouping();
+        let sort_by = photo_manager.photos_sort();
 
         let project_settings: AppProjectSettings = Dependency::<ProjectSettingsManager>::get()
             .with_lock(|settings| settings.project_settings.clone());
 
+        let trash = Dependency::<TrashManager>::get().with_lock(|trash_manager| {
+            trash_manager
+                .entries
+                .iter()
+                .map(|entry| TrashEntry {
+                    item: match entry.item.clone() {
+                        AppTrashedItem::Page(mut page) => {
+                            TrashedItem::Page(CanvasPage::from_canvas_state(&mut page))
+                        }
+                        AppTrashedItem::Layer {
+                            mut layer, page, ..
+                        } => TrashedItem::Layer {
+                            layer: Layer::from_app_layer(&mut layer),
+                            page: page.into(),
+                        },
+                    },
+                    deleted_at: entry.deleted_at,
+                })
+                .collect()
+        });
+
+        let snippets = Dependency::<SnippetManager>::get().with_lock(|snippet_manager| {
+            snippet_manager
+                .snippets
+                .iter()
+                .map(|snippet| Snippet {
+                    name: snippet.name.clone(),
+                    layers: snippet
+                        .layers
+                        .iter()
+                        .map(|layer| Layer::from_app_layer(&mut layer.clone()))
+                        .collect(),
+                    page: snippet.page.clone().into(),
+                })
+                .collect()
+        });
+
+        // Sorted so saving the same captions always produces the same file
+        let mut captions: Vec<PhotoCaptions> = photo_manager
+            .all_captions()
+            .iter()
+            .map(|(path, captions)| PhotoCaptions {
+                path: path.clone(),
+                title: captions.title.clone(),
+                caption: captions.caption.clone(),
+            })
+            .collect();
+        captions.sort_by(|a, b| a.path.cmp(&b.path));
+
         let project = Project {
             photos,
             pages,
             group_by: group_by.into(),
+            sort_by: sort_by.into(),
             project_settings: project_settings.into(),
+            trash,
+            stack_primaries: photo_manager.chosen_stack_primaries(),
+            captions,
+            info_panel,
+            snippets,
         };
 
         project
     }
 
-    pub fn save(
+    /// Saves the project to `path` on the runtime so big projects don't hold up the UI. Every
+    /// save goes through here so only one runs at a time, the session tracks the progress until
+    /// it's written.
+    pub fn save(self, path: PathBuf, ctx: &egui::Context) -> Result<(), ProjectError> {
+        let session: Singleton<Session> = Dependency::get();
+        let already_saving = session.with_lock_mut(|session| {
+            let saving = session.is_saving();
+            if !saving {
+                session.save_progress = Some(0.0);
+            }
+            saving
+        });
+        if already_saving {
+            return Err(ProjectError::SaveInProgress);
+        }
+
+        let ctx = ctx.clone();
+        spawn_blocking(move || {
+            let session: Singleton<Session> = Dependency::get();
+            let report_progress = |progress: f32| {
+                session.with_lock_mut(|session| session.save_progress = Some(progress));
+                ctx.request_repaint();
+            };
+
+            match self.write_files(&path, report_progress) {
+                Ok(()) => {
+                    Dependency::<AutoPersisting<Config>>::get().with_lock_mut(|config| {
+                        let _ = config.modify(ConfigModification::AddRecentProject(path.clone()));
+                        let _ = config.modify(ConfigModification::SetLastProject(path.clone()));
+                    });
+
+                    session.with_lock_mut(|session| session.active_project = Some(path));
+                }
+                Err(err) => ErrorSink::report("Couldn't save the project", err),
+            }
+
+            session.with_lock_mut(|session| session.save_progress = None);
+            ctx.request_repaint();
+        });
+
+        Ok(())
+    }
+
+    // Writes the project and the files kept next to it, the project file takes most of the time
+    fn write_files(
+        &self,
         path: &PathBuf,
-        root_scene: &OrganizeEditScene,
-        photo_manager: &PhotoManager,
+        mut progress: impl FnMut(f32),
     ) -> Result<(), ProjectError> {
-        let project = Project::new(root_scene, photo_manager);
+        self.write_with_progress(path, |written| progress(written * 0.8))?;
 
-        let project_data = serde_json::to_string_pretty(&project)?;
+        Dependency::<PhotoManager>::get()
+            .with_lock(|photo_manager| photo_manager.save_photo_database(path));
+        progress(0.85);
 
-        std::fs::write(path, project_data)?;
+        if self.project_settings.embed_fonts {
+            project_fonts::embed_fonts(path, &self.font_families(), |embedded| {
+                progress(0.85 + embedded * 0.15)
+            })?;
+        }
+        progress(1.0);
 
         Ok(())
     }
 
-    pub fn load(path: &PathBuf) -> Result<OrganizeEditScene, ProjectError> {
+    /// Writes the project to `path` and marks it as the saved state of the session, calling
+    /// `progress` with how much of it has been written from 0 to 1. The project is written next
+    /// to `path` and then moved over it, so a crash part way through leaves the previous save as
+    /// it was.
+    pub fn write_with_progress(
+        &self,
+        path: &PathBuf,
+        mut progress: impl FnMut(f32),
+    ) -> Result<(), ProjectError> {
+        // Serializing a big project takes about as long as writing it
+        let project_data = serde_json::to_string_pretty(&SavedProject::new(self, |serialized| {
+            progress(serialized * 0.5)
+        }))?;
+
+        let mut temp_name = path.file_name().unwrap_or_default().to_os_string();
+        temp_name.push(".saving");
+        let temp_path = path.with_file_name(temp_name);
+
+        let written = write_in_chunks(&temp_path, project_data.as_bytes(), &mut |written| {
+            progress(0.5 + written * 0.5)
+        })
+        .and_then(|()| std::fs::rename(&temp_path, path));
+        if let Err(err) = written {
+            let _ = std::fs::remove_file(&temp_path);
+            return Err(err.into());
+        }
+
+        let content_hash = self.content_hash()?;
+        Dependency::<Session>::get().with_lock_mut(|session| {
+            session.saved_project_hash = Some(content_hash);
+        });
+
+        Ok(())
+    }
+
+    pub fn read(path: &PathBuf) -> Result<Project, ProjectError> {
         let file = std::fs::File::open(path)?;
-        let project: Project = serde_json::from_reader(file)?;
+        Ok(serde_json::from_reader(file)?)
+    }
+
+    /// Hash of everything that gets saved, used to tell whether there are unsaved changes.
+    /// Layer selection and the layout of the info panel are saved but aren't treated as changes.
+    /// What was last exported is, so an export gets saved and the next one can tell what changed.
+    pub fn content_hash(&self) -> Result<u64, ProjectError> {
+        let mut project = self.clone();
+        project.info_panel = InfoPanel::default();
+        for layer in project
+            .pages
+            .iter_mut()
+            .flat_map(|page| page.layers.iter_mut())
+        {
+            layer.selected = false;
+        }
+
+        Ok(hash64(&serde_json::to_string(&project)?))
+    }
+
+    /// Whether there is anything in the project worth saving
+    pub fn is_empty(&self) -> bool {
+        self.photos.is_empty() && self.pages.is_empty()
+    }
+
+    /// Every file the project points at so they can be bundled into an archive and relinked after unpacking
+    pub fn file_paths_mut(&mut self) -> Vec<(ProjectFileKind, &mut PathBuf)> {
+        let mut paths = Vec::new();
+
+        for photo in &mut self.photos {
+            paths.push((ProjectFileKind::Photo, &mut photo.path));
+        }
+
+        for page in &mut self.pages {
+            page.collect_file_paths(&mut paths);
+        }
+
+        for entry in &mut self.trash {
+            match &mut entry.item {
+                TrashedItem::Page(page) => page.collect_file_paths(&mut paths),
+                TrashedItem::Layer { layer, .. } => layer.collect_file_paths(&mut paths),
+            }
+        }
+
+        for snippet in &mut self.snippets {
+            for layer in &mut snippet.layers {
+                layer.collect_file_paths(&mut paths);
+            }
+        }
+
+        for decoration in &mut self.project_settings.decorations {
+            paths.push((ProjectFileKind::Decoration, &mut decoration.path));
+        }
+
+        for path in &mut self.project_settings.tray {
+            paths.push((ProjectFileKind::Photo, path));
+        }
+
+        paths
+    }
+
+    /// Names of the non-default font families used by text layers and the book theme
+    pub fn font_families(&self) -> HashSet<String> {
+        let mut families: HashSet<String> = self
+            .pages
+            .iter()
+            .flat_map(|page| page.layers.iter())
+            .chain(
+                self.snippets
+                    .iter()
+                    .flat_map(|snippet| snippet.layers.iter()),
+            )
+            .filter_map(|layer| match &layer.content {
+                LayerContent::Text(text) | LayerContent::TemplateText { text, .. } => {
+                    match &text.font_id.family {
+                        FontFamily::Name(name) => Some(name.to_string()),
+                        _ => None,
+                    }
+                }
+                _ => None,
+            })
+            .collect();
+
+        let theme = &self.project_settings.theme;
+        families.extend(theme.heading_font.iter().cloned());
+        families.extend(theme.body_font.iter().cloned());
+
+        families
+    }
+
+    pub fn load(path: &PathBuf) -> Result<OrganizeEditScene, ProjectError> {
+        let project = Project::read(path)?;
 
         println!("Loaded project: {:?}", project);
 
+        let content_hash = project.content_hash()?;
+        Dependency::<Session>::get().with_lock_mut(|session| {
+            session.saved_project_hash = Some(content_hash);
+        });
+
+        Dependency::<PhotoManager>::get().with_lock_mut(|photo_manager| {
+            photo_manager.set_photo_database(PhotoDatabase::load(path));
+        });
+
         Ok(project.into())
     }
 }
@@ -307,229 +464,84 @@ impl Into<OrganizeEditScene> for Project {
             settings.project_settings = self.project_settings.into();
         });
 
-        Dependency::<PhotoManager>::get().with_lock(|photo_manager| {
+        Dependency::<PhotoManager>::get().with_lock_mut(|photo_manager| {
+            photo_manager.group_photos_by(self.group_by.into());
+            photo_manager.sort_photos_by(self.sort_by.into());
+            photo_manager.set_chosen_stack_primaries(self.stack_primaries);
+            photo_manager.set_all_captions(
+                self.captions
+                    .into_iter()
+                    .map(|captions| {
+                        (
+                            captions.path,
+                            AppPhotoCaptions {
+                                title: captions.title,
+                                caption: captions.caption,
+                            },
+                        )
+                    })
+                    .collect(),
+            );
             photo_manager.load_photos(
                 self.photos
                     .into_iter()
-                    .map(|photo| (photo.path, Some(photo.rating.into())))
+                    .map(|photo| {
+                        (
+                            photo.path,
+                            Some(photo.rating.into()),
+                            photo.color_label.map(ColorLabel::into),
+                            photo.imported_at,
+                            photo.orientation_override.map(PhotoRotation::into),
+                        )
+                    })
                     .collect(),
             );
         });
 
-        let pages: IndexMap<PageId, CanvasState> = self
-            .pages
-            .into_iter()
-            .map(|page| {
-                let layers: IndexMap<LayerId, AppLayer> = page
-                    .layers
-                    .into_iter()
-                    .map(|layer| {
-                        let transformable_state = TransformableState {
-                            rect: layer.rect,
-                            active_handle: None,
-                            is_moving: false,
-                            handle_mode: Resize(ResizeMode::Free),
-                            rotation: layer.rotation,
-                            last_frame_rotation: layer.rotation,
-                            change_in_rotation: None,
-                            id: Id::random(),
-                        };
-
-                        let layer = AppLayer {
-                            content: match layer.content {
-                                LayerContent::Photo(photo) => {
-                                    /// TODO: Don't unwrap
-                                    AppLayerContent::Photo(AppCanvasPhoto {
-                                        photo: AppPhoto::with_rating(
-                                            photo.photo.path,
-                                            photo.photo.rating.into(),
-                                        )
-                                        .unwrap(),
-                                        crop: photo.crop,
-                                    })
-                                }
-                                LayerContent::Text(text) => AppLayerContent::Text(AppCanvasText {
-                                    text: text.text,
-                                    font_size: text.font_size,
-                                    font_id: text.font_id,
-                                    color: text.color,
-                                    edit_state: CanvasTextEditState::new(text.font_size),
-                                    horizontal_alignment: match text.horizontal_alignment {
-                                        TextHorizontalAlignment::Left => {
-                                            AppTextHorizontalAlignment::Left
-                                        }
-                                        TextHorizontalAlignment::Center => {
-                                            AppTextHorizontalAlignment::Center
-                                        }
-                                        TextHorizontalAlignment::Right => {
-                                            AppTextHorizontalAlignment::Right
-                                        }
-                                    },
-                                    vertical_alignment: match text.vertical_alignment {
-                                        TextVerticalAlignment::Top => AppTextVerticalAlignment::Top,
-                                        TextVerticalAlignment::Center => {
-                                            AppTextVerticalAlignment::Center
-                                        }
-                                        TextVerticalAlignment::Bottom => {
-                                            AppTextVerticalAlignment::Bottom
-                                        }
-                                    },
-                                }),
-                                LayerContent::TemplatePhoto {
-                                    region,
-                                    photo,
-                                    scale_mode,
-                                } => AppLayerContent::TemplatePhoto {
-                                    region: AppTemplateRegion {
-                                        relative_position: region.relative_position,
-                                        relative_size: region.relative_size,
-                                        kind: match region.kind {
-                                            TemplateRegionKind::Image => {
-                                                AppTemplateRegionKind::Image
-                                            }
-                                            TemplateRegionKind::Text {
-                                                sample_text,
-                                                font_size,
-                                            } => AppTemplateRegionKind::Text {
-                                                sample_text,
-                                                font_size,
-                                            },
-                                        },
-                                    },
-                                    photo: photo.map(|photo| AppCanvasPhoto {
-                                        photo: AppPhoto::with_rating(
-                                            photo.photo.path,
-                                            photo.photo.rating.into(),
-                                        )
-                                        .unwrap(), // TODO: Don't unwrap
-                                        crop: photo.crop,
-                                    }),
-                                    scale_mode: match scale_mode {
-                                        ScaleMode::Fit => AppScaleMode::Fit,
-                                        ScaleMode::Fill => AppScaleMode::Fill,
-                                        ScaleMode::Stretch => AppScaleMode::Stretch,
-                                    },
-                                },
-                                LayerContent::TemplateText { region, text } => {
-                                    AppLayerContent::TemplateText {
-                                        region: AppTemplateRegion {
-                                            relative_position: region.relative_position,
-                                            relative_size: region.relative_size,
-                                            kind: match region.kind {
-                                                TemplateRegionKind::Image => {
-                                                    AppTemplateRegionKind::Image
-                                                }
-                                                TemplateRegionKind::Text {
-                                                    sample_text,
-                                                    font_size,
-                                                } => AppTemplateRegionKind::Text {
-                                                    sample_text,
-                                                    font_size,
-                                                },
-                                            },
-                                        },
-                                        text: AppCanvasText {
-                                            text: text.text,
-                                            font_size: text.font_size,
-                                            font_id: text.font_id,
-                                            color: text.color,
-                                            edit_state: CanvasTextEditState::new(text.font_size),
-                                            horizontal_alignment: match text.horizontal_alignment {
-                                                TextHorizontalAlignment::Left => {
-                                                    AppTextHorizontalAlignment::Left
-                                                }
-                                                TextHorizontalAlignment::Center => {
-                                                    AppTextHorizontalAlignment::Center
-                                                }
-                                                TextHorizontalAlignment::Right => {
-                                                    AppTextHorizontalAlignment::Right
-                                                }
-                                            },
-                                            vertical_alignment: match text.vertical_alignment {
-                                                TextVerticalAlignment::Top => {
-                                                    AppTextVerticalAlignment::Top
-                                                }
-                                                TextVerticalAlignment::Center => {
-                                                    AppTextVerticalAlignment::Center
-                                                }
-                                                TextVerticalAlignment::Bottom => {
-                                                    AppTextVerticalAlignment::Bottom
-                                                }
-                                            },
-                                        },
-                                    }
-                                }
+        Dependency::<TrashManager>::get().with_lock_mut(|trash_manager| {
+            trash_manager.entries = self
+                .trash
+                .into_iter()
+                .map(|entry| {
+                    AppTrashEntry::new(
+                        match entry.item {
+                            TrashedItem::Page(page) => AppTrashedItem::Page(page.into()),
+                            TrashedItem::Layer { layer, page } => AppTrashedItem::Layer {
+                                layer: layer.into(),
+                                page: page.into(),
+                                canvas_id: None,
                             },
-                            name: layer.name,
-                            visible: layer.visible,
-                            locked: layer.locked,
-                            selected: layer.selected,
-                            id: layer.id,
-                            transform_edit_state: LayerTransformEditState::from(
-                                &transformable_state,
-                            ),
-                            transform_state: transformable_state,
-                        };
-
-                        set_min_layer_id(layer.id);
-
-                        (layer.id, layer)
-                    })
-                    .collect();
-
-                let canvas_state = CanvasState::with_layers(
-                    layers,
-                    EditablePage::new(AppPage::new(
-                        page.page.size,
-                        page.page.ppi,
-                        match page.page.unit {
-                            Unit::Pixels => AppUnit::Pixels,
-                            Unit::Inches => AppUnit::Inches,
-                            Unit::Centimeters => AppUnit::Centimeters,
                         },
-                    )),
-                    page.template.map(|template| AppTemplate {
-                        name: template.name,
-                        page: AppPage::new(
-                            template.page.size,
-                            template.page.ppi,
-                            match template.page.unit {
-                                Unit::Pixels => AppUnit::Pixels,
-                                Unit::Inches => AppUnit::Inches,
-                                Unit::Centimeters => AppUnit::Centimeters,
-                            },
-                        ),
-                        regions: template
-                            .regions
-                            .iter()
-                            .map(|region| AppTemplateRegion {
-                                relative_position: region.relative_position,
-                                relative_size: region.relative_size,
-                                kind: match &region.kind {
-                                    TemplateRegionKind::Image => AppTemplateRegionKind::Image,
-                                    TemplateRegionKind::Text {
-                                        sample_text,
-                                        font_size,
-                                    } => AppTemplateRegionKind::Text {
-                                        sample_text: sample_text.clone(),
-                                        font_size: *font_size,
-                                    },
-                                },
-                            })
-                            .collect(),
-                    }),
-                    page.quick_layout_order,
-                );
+                        entry.deleted_at,
+                    )
+                })
+                .collect();
+        });
 
-                (next_page_id(), canvas_state)
-            })
+        Dependency::<SnippetManager>::get().with_lock_mut(|snippet_manager| {
+            snippet_manager.snippets = self
+                .snippets
+                .into_iter()
+                .map(|snippet| {
+                    AppSnippet::with_layers(
+                        snippet.name,
+                        snippet.layers.into_iter().map(Layer::into).collect(),
+                        snippet.page.into(),
+                    )
+                })
+                .collect();
+        });
+
+        let pages: IndexMap<PageId, CanvasState> = self
+            .pages
+            .into_iter()
+            .map(|page| (next_page_id(), page.into()))
             .collect();
 
         let edit_scene = if let Some(first_page_id) = pages.first().map(|(id, _)| *id) {
-            Some(CanvasScene::with_state(CanvasSceneState::with_pages(
-                pages,
-                first_page_id,
-            )))
+            let mut state = CanvasSceneState::with_pages(pages, first_page_id);
+            state.info_panel = self.info_panel.into();
+            Some(CanvasScene::with_state(state))
         } else {
             None
         };
@@ -538,165 +550,2009 @@ impl Into<OrganizeEditScene> for Project {
 
         let organize_edit_scene = OrganizeEditScene::new(organize_scene, edit_scene);
 
-        //photo_manager.group_photos_by(project.group_by.into());
-
         organize_edit_scene
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct CanvasPage {
-    pub layers: Vec<Layer>,
-    pub page: Page,
-    pub template: Option<Template>,
-    pub quick_layout_order: Vec<LayerId>,
+// The project as it's saved, calling back as each page is serialized since they make up most of
+// it. Built by taking the project apart so a field added to it can't be left out here.
+#[derive(Serialize)]
+#[serde(bound = "")]
+struct SavedProject<'a, F: FnMut(f32)> {
+    photos: &'a Vec<Photo>,
+    pages: PagesWithProgress<'a, F>,
+    group_by: &'a PhotosGrouping,
+    sort_by: &'a PhotosSort,
+    project_settings: &'a ProjectSettings,
+    trash: &'a Vec<TrashEntry>,
+    stack_primaries: &'a Vec<PathBuf>,
+    captions: &'a Vec<PhotoCaptions>,
+    info_panel: &'a InfoPanel,
+    snippets: &'a Vec<Snippet>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct Page {
-    size: Vec2,
-    ppi: i32,
-    unit: Unit,
-}
+impl<'a, F: FnMut(f32)> SavedProject<'a, F> {
+    fn new(project: &'a Project, progress: F) -> Self {
+        let Project {
+            photos,
+            pages,
+            group_by,
+            sort_by,
+            project_settings,
+            trash,
+            stack_primaries,
+            captions,
+            info_panel,
+            snippets,
+        } = project;
 
-#[derive(Debug, Clone, PartialEq, Copy, Serialize, Deserialize)]
-enum Unit {
-    Pixels,
-    Inches,
-    Centimeters,
+        Self {
+            photos,
+            pages: PagesWithProgress {
+                pages,
+                progress: RefCell::new(progress),
+            },
+            group_by,
+            sort_by,
+            project_settings,
+            trash,
+            stack_primaries,
+            captions,
+            info_panel,
+            snippets,
+        }
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct Photo {
-    pub path: PathBuf,
-    pub rating: PhotoRating,
+struct PagesWithProgress<'a, F> {
+    pages: &'a [CanvasPage],
+    // Serialize only gets a shared reference
+    progress: RefCell<F>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct Layer {
-    pub content: LayerContent,
-    pub name: String,
-    pub visible: bool,
-    pub locked: bool,
-    pub selected: bool,
-    pub id: LayerId,
-    pub rect: Rect,
-    pub rotation: f32,
+impl<F: FnMut(f32)> Serialize for PagesWithProgress<'_, F> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut progress = self.progress.borrow_mut();
+        let mut seq = serializer.serialize_seq(Some(self.pages.len()))?;
+        for (index, page) in self.pages.iter().enumerate() {
+            seq.serialize_element(page)?;
+            progress((index + 1) as f32 / self.pages.len() as f32);
+        }
+        seq.end()
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-enum ScaleMode {
-    Fit,
-    Fill,
-    Stretch,
-}
+// Writes `data` to a new file at `path` a chunk at a time, so the progress of big projects can be
+// shown, and makes sure it's on disk before returning
+fn write_in_chunks(
+    path: &Path,
+    data: &[u8],
+    progress: &mut impl FnMut(f32),
+) -> std::io::Result<()> {
+    let mut file = File::create(path)?;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Template {
-    pub name: String,
-    pub page: Page,
-    pub regions: Vec<TemplateRegion>,
-}
+    let mut written = 0;
+    for chunk in data.chunks(WRITE_CHUNK_SIZE) {
+        file.write_all(chunk)?;
+        written += chunk.len();
+        progress(written as f32 / data.len() as f32);
+    }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct TemplateRegion {
-    pub relative_position: Pos2,
-    pub relative_size: Vec2,
-    pub kind: TemplateRegionKind,
+    file.sync_all()
 }
 
-#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
-enum TemplateRegionKind {
-    Image,
-    Text { sample_text: String, font_size: f32 },
-}
+/// Hash of what a page looks like when exported, to find the pages changed since the last export.
+/// Selection, comments, notes, guides, measurements, labels and sections don't show up in exports
+/// so they're left out.
+pub fn page_content_hash(page: &CanvasState) -> u64 {
+    let mut page = CanvasPage::from_canvas_state(&mut page.clone());
+    page.comments.clear();
+    page.guides.clear();
+    page.measurements.clear();
+    page.color_label = None;
+    page.notes = PageNotes::default();
+    page.section_start = None;
+    page.quick_layout_parameters = QuickLayoutParameters::default();
+    for layer in &mut page.layers {
+        layer.selected = false;
+    }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct CanvasPhoto {
-    pub photo: Photo,
-    pub crop: Rect,
+    hash64(&serde_json::to_string(&page).unwrap_or_default())
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-struct CanvasText {
-    pub text: String,
-    pub font_size: f32,
-    pub font_id: FontId,
-    pub color: Color32,
-    pub horizontal_alignment: TextHorizontalAlignment,
-    pub vertical_alignment: TextVerticalAlignment,
+pub struct TrashEntry {
+    item: TrashedItem,
+    deleted_at: DateTime<Utc>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-enum LayerContent {
-    Photo(CanvasPhoto),
-    Text(CanvasText),
-    TemplatePhoto {
-        region: TemplateRegion,
-        photo: Option<CanvasPhoto>,
-        scale_mode: ScaleMode,
-    },
-    TemplateText {
-        region: TemplateRegion,
-        text: CanvasText,
-    },
-}
-
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
-pub enum TextHorizontalAlignment {
-    Left,
-    Center,
-    Right,
-}
-
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
-pub enum TextVerticalAlignment {
-    Top,
-    Center,
-    Bottom,
+enum TrashedItem {
+    Page(CanvasPage),
+    Layer { layer: Layer, page: Page },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub enum PhotoRating {
-    Yes,
-    No,
-    Maybe,
+pub struct Snippet {
+    name: String,
+    layers: Vec<Layer>,
+    page: Page,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ProjectSettings {
-    default_page: Option<Page>,
+struct CanvasPage {
+    pub layers: Vec<Layer>,
+    pub page: Page,
+    pub template: Option<Template>,
+    pub quick_layout_order: Vec<LayerId>,
+    #[serde(default)]
+    pub comments: Vec<PageComment>,
+    #[serde(default)]
+    pub guides: Vec<Guide>,
+    #[serde(default)]
+    pub measurements: Vec<Measurement>,
+    #[serde(default)]
+    pub color_label: Option<ColorLabel>,
+    #[serde(default)]
+    pub quick_layout_parameters: QuickLayoutParameters,
+    #[serde(default)]
+    pub notes: PageNotes,
+    #[serde(default)]
+    pub background: Fill,
+    #[serde(default)]
+    pub section_start: Option<String>,
 }
 
-impl Into<AppProjectSettings> for ProjectSettings {
-    fn into(self) -> AppProjectSettings {
-        AppProjectSettings {
-            default_page: self.default_page.map(Page::into),
+impl CanvasPage {
+    fn from_canvas_state(canvas_state: &mut CanvasState) -> Self {
+        let layers = canvas_state
+            .layers
+            .values_mut()
+            .map(Layer::from_app_layer)
+            .collect();
+
+        let template = canvas_state.template.clone();
+        CanvasPage {
+            layers,
+            page: canvas_state.page.value.clone().into(),
+            comments: canvas_state
+                .comments
+                .iter()
+                .cloned()
+                .map(|comment| comment.into())
+                .collect(),
+            guides: canvas_state
+                .guides
+                .iter()
+                .cloned()
+                .map(|guide| guide.into())
+                .collect(),
+            measurements: canvas_state
+                .measurements
+                .iter()
+                .copied()
+                .map(AppMeasurement::into)
+                .collect(),
+            template: template.map(|template| Template {
+                name: template.name,
+                page: template.page.into(),
+                regions: template
+                    .regions
+                    .iter()
+                    .map(|region| TemplateRegion {
+                        relative_position: region.relative_position,
+                        relative_size: region.relative_size,
+                        constraints: region.constraints.into(),
+                        kind: match &region.kind {
+                            AppTemplateRegionKind::Image => TemplateRegionKind::Image,
+                            AppTemplateRegionKind::Text {
+                                sample_text,
+                                font_size,
+                                autofill,
+                            } => TemplateRegionKind::Text {
+                                sample_text: sample_text.clone(),
+                                font_size: *font_size,
+                                autofill: autofill.map(AppTemplateAutofill::into),
+                            },
+                        },
+                    })
+                    .collect(),
+            }),
+            quick_layout_order: canvas_state.quick_layout_order.clone(),
+            color_label: canvas_state.color_label.map(AppColorLabel::into),
+            quick_layout_parameters: canvas_state.quick_layout_parameters.into(),
+            notes: canvas_state.notes.clone().into(),
+            background: canvas_state.background.clone().into(),
+            section_start: canvas_state.section_start.clone(),
         }
     }
 }
 
-impl Into<ProjectSettings> for AppProjectSettings {
-    fn into(self) -> ProjectSettings {
-        ProjectSettings {
-            default_page: self.default_page.map(AppPage::into),
+impl CanvasPage {
+    fn collect_file_paths<'a>(&'a mut self, paths: &mut Vec<(ProjectFileKind, &'a mut PathBuf)>) {
+        for layer in &mut self.layers {
+            layer.collect_file_paths(paths);
         }
     }
 }
 
-impl Into<AppPhotoRating> for PhotoRating {
-    fn into(self) -> AppPhotoRating {
-        match self {
-            PhotoRating::Yes => AppPhotoRating::Yes,
-            PhotoRating::No => AppPhotoRating::No,
-            PhotoRating::Maybe => AppPhotoRating::Maybe,
+impl Layer {
+    fn collect_file_paths<'a>(&'a mut self, paths: &mut Vec<(ProjectFileKind, &'a mut PathBuf)>) {
+        match &mut self.content {
+            LayerContent::Photo(photo)
+            | LayerContent::TemplatePhoto {
+                photo: Some(photo), ..
+            } => {
+                paths.push((ProjectFileKind::Photo, &mut photo.photo.path));
+            }
+            LayerContent::Decoration(decoration) => {
+                paths.push((ProjectFileKind::Decoration, &mut decoration.path));
+            }
+            LayerContent::Text(_)
+            | LayerContent::TemplatePhoto { photo: None, .. }
+            | LayerContent::TemplateText { .. }
+            | LayerContent::Path(_) => {}
         }
     }
 }
 
-impl Into<PhotoRating> for AppPhotoRating {
-    fn into(self) -> PhotoRating {
-        match self {
+impl Into<CanvasState> for CanvasPage {
+    fn into(self) -> CanvasState {
+        let layers: IndexMap<LayerId, AppLayer> = self
+            .layers
+            .into_iter()
+            .map(|layer| {
+                let layer: AppLayer = layer.into();
+                (layer.id, layer)
+            })
+            .collect();
+
+        let mut canvas_state = CanvasState::with_layers(
+            layers,
+            EditablePage::new(self.page.into()),
+            self.template.map(|template| AppTemplate {
+                name: template.name,
+                page: template.page.into(),
+                regions: template
+                    .regions
+                    .iter()
+                    .map(|region| AppTemplateRegion {
+                        relative_position: region.relative_position,
+                        relative_size: region.relative_size,
+                        constraints: region.constraints.into(),
+                        kind: match &region.kind {
+                            TemplateRegionKind::Image => AppTemplateRegionKind::Image,
+                            TemplateRegionKind::Text {
+                                sample_text,
+                                font_size,
+                                autofill,
+                            } => AppTemplateRegionKind::Text {
+                                sample_text: sample_text.clone(),
+                                font_size: *font_size,
+                                autofill: autofill.map(TemplateAutofill::into),
+                            },
+                        },
+                    })
+                    .collect(),
+            }),
+            self.quick_layout_order,
+        );
+
+        canvas_state.comments = self
+            .comments
+            .into_iter()
+            .map(|comment| comment.into())
+            .collect();
+        canvas_state.guides = self.guides.into_iter().map(|guide| guide.into()).collect();
+        canvas_state.measurements = self
+            .measurements
+            .into_iter()
+            .map(Measurement::into)
+            .collect();
+        canvas_state.color_label = self.color_label.map(ColorLabel::into);
+        canvas_state.quick_layout_parameters = self.quick_layout_parameters.into();
+        canvas_state.notes = self.notes.into();
+        canvas_state.background = self.background.into();
+        canvas_state.section_start = self.section_start;
+
+        canvas_state
+    }
+}
+
+impl Layer {
+    fn from_app_layer(layer: &mut AppLayer) -> Self {
+        layer.transform_edit_state.update(&layer.transform_state);
+
+        Layer {
+            content: match layer.content.clone() {
+                AppLayerContent::Photo(canvas_photo) => LayerContent::Photo(CanvasPhoto {
+                    photo: Photo {
+                        path: canvas_photo.photo.path,
+                        rating: canvas_photo.photo.rating.into(),
+                        color_label: None,
+                        imported_at: None,
+                        orientation_override: canvas_photo
+                            .photo
+                            .metadata
+                            .orientation_override
+                            .map(AppPhotoRotation::into),
+                    },
+                    crop: canvas_photo.crop,
+                    crop_history: canvas_photo.crop_history,
+                    adjustments: canvas_photo.adjustments.into(),
+                    orientation: canvas_photo.orientation.into(),
+                    effects: canvas_photo.effects.into(),
+                }),
+                AppLayerContent::Text(canvas_text) => LayerContent::Text(CanvasText {
+                    text: canvas_text.text,
+                    font_size: canvas_text.font_size,
+                    font_id: canvas_text.font_id,
+                    color: canvas_text.color,
+                    horizontal_alignment: match canvas_text.horizontal_alignment {
+                        AppTextHorizontalAlignment::Left => TextHorizontalAlignment::Left,
+                        AppTextHorizontalAlignment::Center => TextHorizontalAlignment::Center,
+                        AppTextHorizontalAlignment::Right => TextHorizontalAlignment::Right,
+                    },
+                    vertical_alignment: match canvas_text.vertical_alignment {
+                        AppTextVerticalAlignment::Top => TextVerticalAlignment::Top,
+                        AppTextVerticalAlignment::Center => TextVerticalAlignment::Center,
+                        AppTextVerticalAlignment::Bottom => TextVerticalAlignment::Bottom,
+                    },
+                    font_role: canvas_text.font_role.map(AppThemeFontRole::into),
+                    color_role: canvas_text.color_role.map(AppThemeColorRole::into),
+                    spacing: canvas_text.spacing.into(),
+                    style: canvas_text.style,
+                    anchor: canvas_text.anchor.map(AppCaptionAnchor::into),
+                    typography: canvas_text.typography.into(),
+                    orientation: canvas_text.orientation.into(),
+                }),
+                AppLayerContent::TemplatePhoto {
+                    region,
+                    photo,
+                    scale_mode,
+                    alignment,
+                } => LayerContent::TemplatePhoto {
+                    region: TemplateRegion {
+                        relative_position: region.relative_position,
+                        relative_size: region.relative_size,
+                        constraints: region.constraints.into(),
+                        kind: match region.kind {
+                            AppTemplateRegionKind::Image => TemplateRegionKind::Image,
+                            AppTemplateRegionKind::Text {
+                                sample_text,
+                                font_size,
+                                autofill,
+                            } => TemplateRegionKind::Text {
+                                sample_text,
+                                font_size,
+                                autofill: autofill.map(AppTemplateAutofill::into),
+                            },
+                        },
+                    },
+                    photo: photo.map(|canvas_photo| CanvasPhoto {
+                        photo: Photo {
+                            path: canvas_photo.photo.path,
+                            rating: canvas_photo.photo.rating.into(),
+                            color_label: None,
+                            imported_at: None,
+                            orientation_override: canvas_photo
+                                .photo
+                                .metadata
+                                .orientation_override
+                                .map(AppPhotoRotation::into),
+                        },
+                        crop: canvas_photo.crop,
+                        crop_history: canvas_photo.crop_history,
+                        adjustments: canvas_photo.adjustments.into(),
+                        orientation: canvas_photo.orientation.into(),
+                        effects: canvas_photo.effects.into(),
+                    }),
+                    scale_mode: match scale_mode {
+                        AppScaleMode::Fit => ScaleMode::Fit,
+                        AppScaleMode::Fill => ScaleMode::Fill,
+                        AppScaleMode::Stretch => ScaleMode::Stretch,
+                    },
+                    alignment: alignment.into(),
+                },
+                AppLayerContent::TemplateText { region, text } => LayerContent::TemplateText {
+                    region: TemplateRegion {
+                        relative_position: region.relative_position,
+                        relative_size: region.relative_size,
+                        constraints: region.constraints.into(),
+                        kind: match region.kind {
+                            AppTemplateRegionKind::Image => TemplateRegionKind::Image,
+                            AppTemplateRegionKind::Text {
+                                sample_text,
+                                font_size,
+                                autofill,
+                            } => TemplateRegionKind::Text {
+                                sample_text,
+                                font_size,
+                                autofill: autofill.map(AppTemplateAutofill::into),
+                            },
+                        },
+                    },
+                    text: CanvasText {
+                        text: text.text,
+                        font_size: text.font_size,
+                        font_id: text.font_id,
+                        color: text.color,
+                        horizontal_alignment: match text.horizontal_alignment {
+                            AppTextHorizontalAlignment::Left => TextHorizontalAlignment::Left,
+                            AppTextHorizontalAlignment::Center => TextHorizontalAlignment::Center,
+                            AppTextHorizontalAlignment::Right => TextHorizontalAlignment::Right,
+                        },
+                        vertical_alignment: match text.vertical_alignment {
+                            AppTextVerticalAlignment::Top => TextVerticalAlignment::Top,
+                            AppTextVerticalAlignment::Center => TextVerticalAlignment::Center,
+                            AppTextVerticalAlignment::Bottom => TextVerticalAlignment::Bottom,
+                        },
+                        font_role: text.font_role.map(AppThemeFontRole::into),
+                        color_role: text.color_role.map(AppThemeColorRole::into),
+                        spacing: text.spacing.into(),
+                        style: text.style,
+                        anchor: text.anchor.map(AppCaptionAnchor::into),
+                        typography: text.typography.into(),
+                        orientation: text.orientation.into(),
+                    },
+                },
+                AppLayerContent::Decoration(decoration) => {
+                    LayerContent::Decoration(decoration.into())
+                }
+                AppLayerContent::Path(path) => LayerContent::Path(path.into()),
+            },
+            name: layer.name.clone(),
+            auto_named: layer.auto_named,
+            visible: layer.visible,
+            locked: layer.locked,
+            selected: layer.selected,
+            id: layer.id,
+            rect: layer.transform_state.rect,
+            rotation: layer.transform_state.rotation,
+            constraints: layer.constraints.into(),
+        }
+    }
+}
+
+impl Into<AppLayer> for Layer {
+    fn into(self) -> AppLayer {
+        let layer = self;
+
+        let transformable_state = TransformableState {
+            rect: layer.rect,
+            active_handle: None,
+            is_moving: false,
+            handle_mode: Resize(ResizeMode::Free),
+            rotation: layer.rotation,
+            last_frame_rotation: layer.rotation,
+            change_in_rotation: None,
+            id: Id::random(),
+        };
+
+        let layer = AppLayer {
+            content: match layer.content {
+                LayerContent::Photo(photo) => AppLayerContent::Photo(AppCanvasPhoto {
+                    photo: photo.photo.into_app_photo(),
+                    crop: photo.crop,
+                    crop_history: photo.crop_history,
+                    adjustments: photo.adjustments.into(),
+                    orientation: photo.orientation.into(),
+                    effects: photo.effects.into(),
+                }),
+                LayerContent::Text(text) => AppLayerContent::Text(AppCanvasText {
+                    text: text.text,
+                    font_size: text.font_size,
+                    font_id: text.font_id,
+                    color: text.color,
+                    edit_state: CanvasTextEditState::new(text.font_size),
+                    horizontal_alignment: match text.horizontal_alignment {
+                        TextHorizontalAlignment::Left => AppTextHorizontalAlignment::Left,
+                        TextHorizontalAlignment::Center => AppTextHorizontalAlignment::Center,
+                        TextHorizontalAlignment::Right => AppTextHorizontalAlignment::Right,
+                    },
+                    vertical_alignment: match text.vertical_alignment {
+                        TextVerticalAlignment::Top => AppTextVerticalAlignment::Top,
+                        TextVerticalAlignment::Center => AppTextVerticalAlignment::Center,
+                        TextVerticalAlignment::Bottom => AppTextVerticalAlignment::Bottom,
+                    },
+                    font_role: text.font_role.map(ThemeFontRole::into),
+                    color_role: text.color_role.map(ThemeColorRole::into),
+                    spacing: text.spacing.into(),
+                    style: text.style,
+                    anchor: text.anchor.map(CaptionAnchor::into),
+                    typography: text.typography.into(),
+                    orientation: text.orientation.into(),
+                }),
+                LayerContent::TemplatePhoto {
+                    region,
+                    photo,
+                    scale_mode,
+                    alignment,
+                } => AppLayerContent::TemplatePhoto {
+                    region: AppTemplateRegion {
+                        relative_position: region.relative_position,
+                        relative_size: region.relative_size,
+                        constraints: region.constraints.into(),
+                        kind: match region.kind {
+                            TemplateRegionKind::Image => AppTemplateRegionKind::Image,
+                            TemplateRegionKind::Text {
+                                sample_text,
+                                font_size,
+                                autofill,
+                            } => AppTemplateRegionKind::Text {
+                                sample_text,
+                                font_size,
+                                autofill: autofill.map(TemplateAutofill::into),
+                            },
+                        },
+                    },
+                    photo: photo.map(|photo| AppCanvasPhoto {
+                        photo: photo.photo.into_app_photo(),
+                        crop: photo.crop,
+                        crop_history: photo.crop_history,
+                        adjustments: photo.adjustments.into(),
+                        orientation: photo.orientation.into(),
+                        effects: photo.effects.into(),
+                    }),
+                    scale_mode: match scale_mode {
+                        ScaleMode::Fit => AppScaleMode::Fit,
+                        ScaleMode::Fill => AppScaleMode::Fill,
+                        ScaleMode::Stretch => AppScaleMode::Stretch,
+                    },
+                    alignment: alignment.into(),
+                },
+                LayerContent::TemplateText { region, text } => AppLayerContent::TemplateText {
+                    region: AppTemplateRegion {
+                        relative_position: region.relative_position,
+                        relative_size: region.relative_size,
+                        constraints: region.constraints.into(),
+                        kind: match region.kind {
+                            TemplateRegionKind::Image => AppTemplateRegionKind::Image,
+                            TemplateRegionKind::Text {
+                                sample_text,
+                                font_size,
+                                autofill,
+                            } => AppTemplateRegionKind::Text {
+                                sample_text,
+                                font_size,
+                                autofill: autofill.map(TemplateAutofill::into),
+                            },
+                        },
+                    },
+                    text: AppCanvasText {
+                        text: text.text,
+                        font_size: text.font_size,
+                        font_id: text.font_id,
+                        color: text.color,
+                        edit_state: CanvasTextEditState::new(text.font_size),
+                        horizontal_alignment: match text.horizontal_alignment {
+                            TextHorizontalAlignment::Left => AppTextHorizontalAlignment::Left,
+                            TextHorizontalAlignment::Center => AppTextHorizontalAlignment::Center,
+                            TextHorizontalAlignment::Right => AppTextHorizontalAlignment::Right,
+                        },
+                        vertical_alignment: match text.vertical_alignment {
+                            TextVerticalAlignment::Top => AppTextVerticalAlignment::Top,
+                            TextVerticalAlignment::Center => AppTextVerticalAlignment::Center,
+                            TextVerticalAlignment::Bottom => AppTextVerticalAlignment::Bottom,
+                        },
+                        font_role: text.font_role.map(ThemeFontRole::into),
+                        color_role: text.color_role.map(ThemeColorRole::into),
+                        spacing: text.spacing.into(),
+                        style: text.style,
+                        anchor: text.anchor.map(CaptionAnchor::into),
+                        typography: text.typography.into(),
+                        orientation: text.orientation.into(),
+                    },
+                },
+                LayerContent::Decoration(decoration) => {
+                    AppLayerContent::Decoration(decoration.into())
+                }
+                LayerContent::Path(path) => AppLayerContent::Path(path.into()),
+            },
+            name: layer.name,
+            auto_named: layer.auto_named,
+            visible: layer.visible,
+            locked: layer.locked,
+            selected: layer.selected,
+            id: layer.id,
+            transform_edit_state: LayerTransformEditState::from(&transformable_state),
+            transform_state: transformable_state,
+            constraints: layer.constraints.into(),
+        };
+
+        set_min_layer_id(layer.id);
+
+        layer
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Page {
+    size: Vec2,
+    ppi: i32,
+    unit: Unit,
+    #[serde(default)]
+    bleed: f32,
+}
+
+#[derive(Debug, Clone, PartialEq, Copy, Serialize, Deserialize)]
+enum Unit {
+    Pixels,
+    Inches,
+    Centimeters,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Photo {
+    pub path: PathBuf,
+    pub rating: PhotoRating,
+    // Only kept for photos in the library, placed photos take theirs from the library
+    #[serde(default)]
+    pub color_label: Option<ColorLabel>,
+    #[serde(default)]
+    pub imported_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub orientation_override: Option<PhotoRotation>,
+}
+
+impl Photo {
+    // A photo that can't be read, usually because it was moved, is kept as missing so the
+    // project still opens and the photo can be relinked
+    fn into_app_photo(self) -> AppPhoto {
+        let rating: AppPhotoRating = self.rating.into();
+        let mut photo = match AppPhoto::with_rating(self.path.clone(), rating) {
+            Ok(photo) => photo,
+            Err(err) => {
+                warn!("Photo {:?} is missing: {:?}", self.path, err);
+                Dependency::<PhotoManager>::get().with_lock_mut(|photo_manager| {
+                    photo_manager.mark_missing(self.path.clone());
+                });
+                AppPhoto::missing(self.path, rating)
+            }
+        };
+        photo.metadata.orientation_override = self.orientation_override.map(PhotoRotation::into);
+        photo
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct PageComment {
+    pub position: Pos2,
+    pub text: String,
+    pub resolved: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+// Comment ids are only used while the project is open so new ones are given out on load
+impl Into<AppPageComment> for PageComment {
+    fn into(self) -> AppPageComment {
+        AppPageComment {
+            text: self.text,
+            resolved: self.resolved,
+            created_at: self.created_at,
+            ..AppPageComment::new(self.position)
+        }
+    }
+}
+
+impl Into<PageComment> for AppPageComment {
+    fn into(self) -> PageComment {
+        PageComment {
+            position: self.position,
+            text: self.text,
+            resolved: self.resolved,
+            created_at: self.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct PageNotes {
+    pub text: String,
+    pub checklist: Vec<ChecklistItem>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ChecklistItem {
+    pub text: String,
+    pub done: bool,
+}
+
+impl Into<AppPageNotes> for PageNotes {
+    fn into(self) -> AppPageNotes {
+        AppPageNotes {
+            text: self.text,
+            checklist: self
+                .checklist
+                .into_iter()
+                .map(|item| AppChecklistItem {
+                    text: item.text,
+                    done: item.done,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl Into<PageNotes> for AppPageNotes {
+    fn into(self) -> PageNotes {
+        PageNotes {
+            text: self.text,
+            checklist: self
+                .checklist
+                .into_iter()
+                .map(|item| ChecklistItem {
+                    text: item.text,
+                    done: item.done,
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+enum GuideOrientation {
+    Horizontal,
+    Vertical,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Guide {
+    pub orientation: GuideOrientation,
+    pub position: f32,
+    pub locked: bool,
+}
+
+// Like comments, guide ids are given out again on load
+impl Into<AppGuide> for Guide {
+    fn into(self) -> AppGuide {
+        let orientation = match self.orientation {
+            GuideOrientation::Horizontal => AppGuideOrientation::Horizontal,
+            GuideOrientation::Vertical => AppGuideOrientation::Vertical,
+        };
+
+        AppGuide {
+            locked: self.locked,
+            ..AppGuide::new(orientation, self.position)
+        }
+    }
+}
+
+impl Into<Guide> for AppGuide {
+    fn into(self) -> Guide {
+        Guide {
+            orientation: match self.orientation {
+                AppGuideOrientation::Horizontal => GuideOrientation::Horizontal,
+                AppGuideOrientation::Vertical => GuideOrientation::Vertical,
+            },
+            position: self.position,
+            locked: self.locked,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Measurement {
+    pub start: Pos2,
+    pub end: Pos2,
+}
+
+impl Into<AppMeasurement> for Measurement {
+    fn into(self) -> AppMeasurement {
+        AppMeasurement::new(self.start, self.end)
+    }
+}
+
+impl Into<Measurement> for AppMeasurement {
+    fn into(self) -> Measurement {
+        Measurement {
+            start: self.start,
+            end: self.end,
+        }
+    }
+}
+
+// The title and caption given to a photo in the library
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct PhotoCaptions {
+    pub path: PathBuf,
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub caption: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Layer {
+    pub content: LayerContent,
+    pub name: String,
+    #[serde(default)]
+    pub auto_named: bool,
+    pub visible: bool,
+    pub locked: bool,
+    pub selected: bool,
+    pub id: LayerId,
+    pub rect: Rect,
+    pub rotation: f32,
+    #[serde(default)]
+    pub constraints: LayerConstraints,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+struct LayerConstraints {
+    #[serde(default)]
+    horizontal: Pin,
+    #[serde(default)]
+    vertical: Pin,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+enum Pin {
+    #[default]
+    None,
+    Start,
+    Center,
+    End,
+    Percentage,
+}
+
+impl Into<AppLayerConstraints> for LayerConstraints {
+    fn into(self) -> AppLayerConstraints {
+        AppLayerConstraints {
+            horizontal: self.horizontal.into(),
+            vertical: self.vertical.into(),
+        }
+    }
+}
+
+impl Into<LayerConstraints> for AppLayerConstraints {
+    fn into(self) -> LayerConstraints {
+        LayerConstraints {
+            horizontal: self.horizontal.into(),
+            vertical: self.vertical.into(),
+        }
+    }
+}
+
+impl Into<AppPin> for Pin {
+    fn into(self) -> AppPin {
+        match self {
+            Pin::None => AppPin::None,
+            Pin::Start => AppPin::Start,
+            Pin::Center => AppPin::Center,
+            Pin::End => AppPin::End,
+            Pin::Percentage => AppPin::Percentage,
+        }
+    }
+}
+
+impl Into<Pin> for AppPin {
+    fn into(self) -> Pin {
+        match self {
+            AppPin::None => Pin::None,
+            AppPin::Start => Pin::Start,
+            AppPin::Center => Pin::Center,
+            AppPin::End => Pin::End,
+            AppPin::Percentage => Pin::Percentage,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+enum ScaleMode {
+    Fit,
+    Fill,
+    Stretch,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct QuickLayoutParameters {
+    gutter: f32,
+    margin: f32,
+    bias: f32,
+    respect_safe_margin: bool,
+}
+
+impl Default for QuickLayoutParameters {
+    fn default() -> Self {
+        AppQuickLayoutParameters::default().into()
+    }
+}
+
+impl Into<AppQuickLayoutParameters> for QuickLayoutParameters {
+    fn into(self) -> AppQuickLayoutParameters {
+        AppQuickLayoutParameters {
+            gutter: self.gutter,
+            margin: self.margin,
+            bias: self.bias,
+            respect_safe_margin: self.respect_safe_margin,
+        }
+    }
+}
+
+impl Into<QuickLayoutParameters> for AppQuickLayoutParameters {
+    fn into(self) -> QuickLayoutParameters {
+        QuickLayoutParameters {
+            gutter: self.gutter,
+            margin: self.margin,
+            bias: self.bias,
+            respect_safe_margin: self.respect_safe_margin,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct PhotoAlignment {
+    x: f32,
+    y: f32,
+}
+
+impl Default for PhotoAlignment {
+    fn default() -> Self {
+        AppPhotoAlignment::default().into()
+    }
+}
+
+impl Into<AppPhotoAlignment> for PhotoAlignment {
+    fn into(self) -> AppPhotoAlignment {
+        AppPhotoAlignment::new(self.x, self.y)
+    }
+}
+
+impl Into<PhotoAlignment> for AppPhotoAlignment {
+    fn into(self) -> PhotoAlignment {
+        PhotoAlignment {
+            x: self.x,
+            y: self.y,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct PhotoOrientation {
+    quarter_turns: u8,
+    flip_horizontal: bool,
+    flip_vertical: bool,
+}
+
+impl Default for PhotoOrientation {
+    fn default() -> Self {
+        AppPhotoOrientation::default().into()
+    }
+}
+
+impl Into<AppPhotoOrientation> for PhotoOrientation {
+    fn into(self) -> AppPhotoOrientation {
+        AppPhotoOrientation::new(self.quarter_turns, self.flip_horizontal, self.flip_vertical)
+    }
+}
+
+impl Into<PhotoOrientation> for AppPhotoOrientation {
+    fn into(self) -> PhotoOrientation {
+        PhotoOrientation {
+            quarter_turns: self.quarter_turns(),
+            flip_horizontal: self.is_flipped_horizontal(),
+            flip_vertical: self.is_flipped_vertical(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct PhotoEffects {
+    corner_radius: f32,
+    vignette_strength: f32,
+    vignette_feather: f32,
+}
+
+impl Default for PhotoEffects {
+    fn default() -> Self {
+        AppPhotoEffects::default().into()
+    }
+}
+
+impl Into<AppPhotoEffects> for PhotoEffects {
+    fn into(self) -> AppPhotoEffects {
+        AppPhotoEffects {
+            corner_radius: self.corner_radius,
+            vignette_strength: self.vignette_strength,
+            vignette_feather: self.vignette_feather,
+        }
+    }
+}
+
+impl Into<PhotoEffects> for AppPhotoEffects {
+    fn into(self) -> PhotoEffects {
+        PhotoEffects {
+            corner_radius: self.corner_radius,
+            vignette_strength: self.vignette_strength,
+            vignette_feather: self.vignette_feather,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Template {
+    pub name: String,
+    pub page: Page,
+    pub regions: Vec<TemplateRegion>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct TemplateRegion {
+    pub relative_position: Pos2,
+    pub relative_size: Vec2,
+    #[serde(default)]
+    pub constraints: LayerConstraints,
+    pub kind: TemplateRegionKind,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+enum TemplateRegionKind {
+    Image,
+    Text {
+        sample_text: String,
+        font_size: f32,
+        #[serde(default)]
+        autofill: Option<TemplateAutofill>,
+    },
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+enum TemplateAutofill {
+    Location,
+    DateRange,
+    Caption,
+}
+
+impl Into<AppTemplateAutofill> for TemplateAutofill {
+    fn into(self) -> AppTemplateAutofill {
+        match self {
+            TemplateAutofill::Location => AppTemplateAutofill::Location,
+            TemplateAutofill::DateRange => AppTemplateAutofill::DateRange,
+            TemplateAutofill::Caption => AppTemplateAutofill::Caption,
+        }
+    }
+}
+
+impl Into<TemplateAutofill> for AppTemplateAutofill {
+    fn into(self) -> TemplateAutofill {
+        match self {
+            AppTemplateAutofill::Location => TemplateAutofill::Location,
+            AppTemplateAutofill::DateRange => TemplateAutofill::DateRange,
+            AppTemplateAutofill::Caption => TemplateAutofill::Caption,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CanvasPhoto {
+    pub photo: Photo,
+    pub crop: Rect,
+    #[serde(default)]
+    pub crop_history: Vec<Rect>,
+    #[serde(default)]
+    pub adjustments: PhotoAdjustments,
+    #[serde(default)]
+    pub orientation: PhotoOrientation,
+    #[serde(default)]
+    pub effects: PhotoEffects,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct PhotoAdjustments {
+    black_point: f32,
+    white_point: f32,
+    white_balance: [f32; 3],
+    strength: f32,
+}
+
+impl Default for PhotoAdjustments {
+    fn default() -> Self {
+        AppPhotoAdjustments::default().into()
+    }
+}
+
+impl Into<AppPhotoAdjustments> for PhotoAdjustments {
+    fn into(self) -> AppPhotoAdjustments {
+        AppPhotoAdjustments {
+            black_point: self.black_point,
+            white_point: self.white_point,
+            white_balance: self.white_balance,
+            strength: self.strength,
+        }
+    }
+}
+
+impl Into<PhotoAdjustments> for AppPhotoAdjustments {
+    fn into(self) -> PhotoAdjustments {
+        PhotoAdjustments {
+            black_point: self.black_point,
+            white_point: self.white_point,
+            white_balance: self.white_balance,
+            strength: self.strength,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CanvasText {
+    pub text: String,
+    pub font_size: f32,
+    pub font_id: FontId,
+    pub color: Color32,
+    pub horizontal_alignment: TextHorizontalAlignment,
+    pub vertical_alignment: TextVerticalAlignment,
+    #[serde(default)]
+    pub font_role: Option<ThemeFontRole>,
+    #[serde(default)]
+    pub color_role: Option<ThemeColorRole>,
+    #[serde(default)]
+    pub spacing: TextSpacing,
+    #[serde(default)]
+    pub style: Option<String>,
+    #[serde(default)]
+    pub anchor: Option<CaptionAnchor>,
+    #[serde(default)]
+    pub typography: Typography,
+    #[serde(default)]
+    pub orientation: TextOrientation,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct CaptionAnchor {
+    photo_layer: LayerId,
+    side: CaptionSide,
+    gap: f32,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+enum CaptionSide {
+    Below,
+    Above,
+    Left,
+    Right,
+}
+
+impl Into<AppCaptionAnchor> for CaptionAnchor {
+    fn into(self) -> AppCaptionAnchor {
+        AppCaptionAnchor {
+            photo_layer: self.photo_layer,
+            side: match self.side {
+                CaptionSide::Below => AppCaptionSide::Below,
+                CaptionSide::Above => AppCaptionSide::Above,
+                CaptionSide::Left => AppCaptionSide::Left,
+                CaptionSide::Right => AppCaptionSide::Right,
+            },
+            gap: self.gap,
+        }
+    }
+}
+
+impl Into<CaptionAnchor> for AppCaptionAnchor {
+    fn into(self) -> CaptionAnchor {
+        CaptionAnchor {
+            photo_layer: self.photo_layer,
+            side: match self.side {
+                AppCaptionSide::Below => CaptionSide::Below,
+                AppCaptionSide::Above => CaptionSide::Above,
+                AppCaptionSide::Left => CaptionSide::Left,
+                AppCaptionSide::Right => CaptionSide::Right,
+            },
+            gap: self.gap,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct TextSpacing {
+    line_height: f32,
+    letter_spacing: f32,
+    paragraph_spacing: f32,
+    first_line_indent: f32,
+}
+
+impl Default for TextSpacing {
+    fn default() -> Self {
+        AppTextSpacing::default().into()
+    }
+}
+
+impl Into<AppTextSpacing> for TextSpacing {
+    fn into(self) -> AppTextSpacing {
+        AppTextSpacing {
+            line_height: self.line_height,
+            letter_spacing: self.letter_spacing,
+            paragraph_spacing: self.paragraph_spacing,
+            first_line_indent: self.first_line_indent,
+        }
+    }
+}
+
+impl Into<TextSpacing> for AppTextSpacing {
+    fn into(self) -> TextSpacing {
+        TextSpacing {
+            line_height: self.line_height,
+            letter_spacing: self.letter_spacing,
+            paragraph_spacing: self.paragraph_spacing,
+            first_line_indent: self.first_line_indent,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+struct Typography {
+    #[serde(default)]
+    hyphenation: Option<HyphenationLanguage>,
+    #[serde(default)]
+    avoid_widows: bool,
+    #[serde(default)]
+    tie_short_words: bool,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+enum HyphenationLanguage {
+    English,
+    German,
+    French,
+    Spanish,
+    Italian,
+    Dutch,
+}
+
+impl Into<AppTypography> for Typography {
+    fn into(self) -> AppTypography {
+        AppTypography {
+            hyphenation: self.hyphenation.map(|language| match language {
+                HyphenationLanguage::English => AppHyphenationLanguage::English,
+                HyphenationLanguage::German => AppHyphenationLanguage::German,
+                HyphenationLanguage::French => AppHyphenationLanguage::French,
+                HyphenationLanguage::Spanish => AppHyphenationLanguage::Spanish,
+                HyphenationLanguage::Italian => AppHyphenationLanguage::Italian,
+                HyphenationLanguage::Dutch => AppHyphenationLanguage::Dutch,
+            }),
+            avoid_widows: self.avoid_widows,
+            tie_short_words: self.tie_short_words,
+        }
+    }
+}
+
+impl Into<Typography> for AppTypography {
+    fn into(self) -> Typography {
+        Typography {
+            hyphenation: self.hyphenation.map(|language| match language {
+                AppHyphenationLanguage::English => HyphenationLanguage::English,
+                AppHyphenationLanguage::German => HyphenationLanguage::German,
+                AppHyphenationLanguage::French => HyphenationLanguage::French,
+                AppHyphenationLanguage::Spanish => HyphenationLanguage::Spanish,
+                AppHyphenationLanguage::Italian => HyphenationLanguage::Italian,
+                AppHyphenationLanguage::Dutch => HyphenationLanguage::Dutch,
+            }),
+            avoid_widows: self.avoid_widows,
+            tie_short_words: self.tie_short_words,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+enum LayerContent {
+    Photo(CanvasPhoto),
+    Text(CanvasText),
+    TemplatePhoto {
+        region: TemplateRegion,
+        photo: Option<CanvasPhoto>,
+        scale_mode: ScaleMode,
+        #[serde(default)]
+        alignment: PhotoAlignment,
+    },
+    TemplateText {
+        region: TemplateRegion,
+        text: CanvasText,
+    },
+    Decoration(Decoration),
+    Path(CanvasPath),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Decoration {
+    pub path: PathBuf,
+    pub kind: DecorationKind,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+enum DecorationKind {
+    Sticker,
+    Ornament,
+    Texture,
+}
+
+impl Into<AppDecoration> for Decoration {
+    fn into(self) -> AppDecoration {
+        AppDecoration::new(
+            self.path,
+            match self.kind {
+                DecorationKind::Sticker => AppDecorationKind::Sticker,
+                DecorationKind::Ornament => AppDecorationKind::Ornament,
+                DecorationKind::Texture => AppDecorationKind::Texture,
+            },
+        )
+    }
+}
+
+impl Into<Decoration> for AppDecoration {
+    fn into(self) -> Decoration {
+        Decoration {
+            path: self.path,
+            kind: match self.kind {
+                AppDecorationKind::Sticker => DecorationKind::Sticker,
+                AppDecorationKind::Ornament => DecorationKind::Ornament,
+                AppDecorationKind::Texture => DecorationKind::Texture,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CanvasPath {
+    pub points: Vec<PathPoint>,
+    pub closed: bool,
+    // The solid fill, or the color closest to the gradient when there is one
+    pub fill: Color32,
+    #[serde(default)]
+    pub fill_gradient: Option<Gradient>,
+    pub stroke_color: Color32,
+    pub stroke_width: f32,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct PathPoint {
+    pub anchor: Pos2,
+    pub handle_in: Vec2,
+    pub handle_out: Vec2,
+}
+
+impl Into<AppCanvasPath> for CanvasPath {
+    fn into(self) -> AppCanvasPath {
+        AppCanvasPath {
+            points: self
+                .points
+                .into_iter()
+                .map(|point| AppPathPoint {
+                    anchor: point.anchor,
+                    handle_in: point.handle_in,
+                    handle_out: point.handle_out,
+                })
+                .collect(),
+            closed: self.closed,
+            fill: match self.fill_gradient {
+                Some(gradient) => AppFill::Gradient(gradient.into()),
+                None => AppFill::Solid(self.fill),
+            },
+            stroke_color: self.stroke_color,
+            stroke_width: self.stroke_width,
+        }
+    }
+}
+
+impl Into<CanvasPath> for AppCanvasPath {
+    fn into(self) -> CanvasPath {
+        CanvasPath {
+            points: self
+                .points
+                .into_iter()
+                .map(|point| PathPoint {
+                    anchor: point.anchor,
+                    handle_in: point.handle_in,
+                    handle_out: point.handle_out,
+                })
+                .collect(),
+            closed: self.closed,
+            fill: self.fill.average_color(),
+            fill_gradient: match self.fill {
+                AppFill::Solid(_) => None,
+                AppFill::Gradient(gradient) => Some(gradient.into()),
+            },
+            stroke_color: self.stroke_color,
+            stroke_width: self.stroke_width,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+enum Fill {
+    Solid(Color32),
+    Gradient(Gradient),
+}
+
+impl Default for Fill {
+    fn default() -> Self {
+        Fill::Solid(Color32::WHITE)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Gradient {
+    pub kind: GradientKind,
+    pub stops: Vec<GradientStop>,
+    pub start: Pos2,
+    pub end: Pos2,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+enum GradientKind {
+    Linear,
+    Radial,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct GradientStop {
+    pub offset: f32,
+    pub color: Color32,
+}
+
+impl Into<AppFill> for Fill {
+    fn into(self) -> AppFill {
+        match self {
+            Fill::Solid(color) => AppFill::Solid(color),
+            Fill::Gradient(gradient) => AppFill::Gradient(gradient.into()),
+        }
+    }
+}
+
+impl Into<Fill> for AppFill {
+    fn into(self) -> Fill {
+        match self {
+            AppFill::Solid(color) => Fill::Solid(color),
+            AppFill::Gradient(gradient) => Fill::Gradient(gradient.into()),
+        }
+    }
+}
+
+impl Into<AppGradient> for Gradient {
+    fn into(self) -> AppGradient {
+        AppGradient {
+            kind: match self.kind {
+                GradientKind::Linear => AppGradientKind::Linear,
+                GradientKind::Radial => AppGradientKind::Radial,
+            },
+            stops: self
+                .stops
+                .into_iter()
+                .map(|stop| AppGradientStop::new(stop.offset, stop.color))
+                .collect(),
+            start: self.start,
+            end: self.end,
+        }
+    }
+}
+
+impl Into<Gradient> for AppGradient {
+    fn into(self) -> Gradient {
+        Gradient {
+            kind: match self.kind {
+                AppGradientKind::Linear => GradientKind::Linear,
+                AppGradientKind::Radial => GradientKind::Radial,
+            },
+            stops: self
+                .stops
+                .into_iter()
+                .map(|stop| GradientStop {
+                    offset: stop.offset,
+                    color: stop.color,
+                })
+                .collect(),
+            start: self.start,
+            end: self.end,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum TextHorizontalAlignment {
+    Left,
+    Center,
+    Right,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum TextVerticalAlignment {
+    Top,
+    Center,
+    Bottom,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub enum TextOrientation {
+    #[default]
+    Horizontal,
+    Rotated90,
+    Rotated270,
+    Vertical,
+}
+
+impl Into<AppTextOrientation> for TextOrientation {
+    fn into(self) -> AppTextOrientation {
+        match self {
+            TextOrientation::Horizontal => AppTextOrientation::Horizontal,
+            TextOrientation::Rotated90 => AppTextOrientation::Rotated90,
+            TextOrientation::Rotated270 => AppTextOrientation::Rotated270,
+            TextOrientation::Vertical => AppTextOrientation::Vertical,
+        }
+    }
+}
+
+impl Into<TextOrientation> for AppTextOrientation {
+    fn into(self) -> TextOrientation {
+        match self {
+            AppTextOrientation::Horizontal => TextOrientation::Horizontal,
+            AppTextOrientation::Rotated90 => TextOrientation::Rotated90,
+            AppTextOrientation::Rotated270 => TextOrientation::Rotated270,
+            AppTextOrientation::Vertical => TextOrientation::Vertical,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum PhotoRating {
+    Yes,
+    No,
+    Maybe,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectSettings {
+    default_page: Option<Page>,
+    #[serde(default)]
+    custom_dictionary: Vec<String>,
+    #[serde(default)]
+    decorations: Vec<Decoration>,
+    #[serde(default)]
+    theme: BookTheme,
+    #[serde(default)]
+    guides: Vec<Guide>,
+    #[serde(default)]
+    metadata: BookMetadata,
+    #[serde(default)]
+    exported_pages: Vec<ExportedPage>,
+    #[serde(default)]
+    text_styles: Vec<TextStylePreset>,
+    #[serde(default)]
+    tray: Vec<PathBuf>,
+    #[serde(default)]
+    animation_frames: Vec<AnimationFrame>,
+    #[serde(default)]
+    embed_fonts: bool,
+    #[serde(default)]
+    resize_quality: ResizeQuality,
+    #[serde(default)]
+    fit_page_margin_mm: Option<f32>,
+}
+
+impl Into<AppProjectSettings> for ProjectSettings {
+    fn into(self) -> AppProjectSettings {
+        AppProjectSettings {
+            default_page: self.default_page.map(Page::into),
+            custom_dictionary: self.custom_dictionary,
+            decorations: self.decorations.into_iter().map(Decoration::into).collect(),
+            theme: self.theme.into(),
+            guides: self.guides.into_iter().map(Guide::into).collect(),
+            metadata: self.metadata.into(),
+            exported_pages: self
+                .exported_pages
+                .into_iter()
+                .map(|exported| (exported.index, AppExportedPage::new(&exported.page.into())))
+                .collect(),
+            page_hashes: PageHashCache::default(),
+            text_styles: self
+                .text_styles
+                .into_iter()
+                .map(TextStylePreset::into)
+                .collect(),
+            tray: self.tray,
+            animation_frames: self
+                .animation_frames
+                .into_iter()
+                .map(|picked| (picked.source, picked.frame))
+                .collect(),
+            embed_fonts: self.embed_fonts,
+            resize_quality: self.resize_quality.into(),
+            fit_page_margin_mm: self.fit_page_margin_mm,
+        }
+    }
+}
+
+impl Into<ProjectSettings> for AppProjectSettings {
+    fn into(self) -> ProjectSettings {
+        ProjectSettings {
+            default_page: self.default_page.map(AppPage::into),
+            custom_dictionary: self.custom_dictionary,
+            decorations: self
+                .decorations
+                .into_iter()
+                .map(AppDecoration::into)
+                .collect(),
+            theme: self.theme.into(),
+            guides: self.guides.into_iter().map(AppGuide::into).collect(),
+            metadata: self.metadata.into(),
+            exported_pages: self
+                .exported_pages
+                .into_iter()
+                .map(|(index, mut exported)| ExportedPage {
+                    index,
+                    page: CanvasPage::from_canvas_state(&mut exported.page),
+                })
+                .collect(),
+            text_styles: self
+                .text_styles
+                .into_iter()
+                .map(AppTextStylePreset::into)
+                .collect(),
+            tray: self.tray,
+            animation_frames: self
+                .animation_frames
+                .into_iter()
+                .map(|(source, frame)| AnimationFrame { source, frame })
+                .collect(),
+            embed_fonts: self.embed_fonts,
+            resize_quality: self.resize_quality.into(),
+            fit_page_margin_mm: self.fit_page_margin_mm,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+enum ResizeQuality {
+    Nearest,
+    #[default]
+    Bilinear,
+    Lanczos,
+}
+
+impl Into<AppResizeQuality> for ResizeQuality {
+    fn into(self) -> AppResizeQuality {
+        match self {
+            ResizeQuality::Nearest => AppResizeQuality::Nearest,
+            ResizeQuality::Bilinear => AppResizeQuality::Bilinear,
+            ResizeQuality::Lanczos => AppResizeQuality::Lanczos,
+        }
+    }
+}
+
+impl Into<ResizeQuality> for AppResizeQuality {
+    fn into(self) -> ResizeQuality {
+        match self {
+            AppResizeQuality::Nearest => ResizeQuality::Nearest,
+            AppResizeQuality::Bilinear => ResizeQuality::Bilinear,
+            AppResizeQuality::Lanczos => ResizeQuality::Lanczos,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct AnimationFrame {
+    source: PathBuf,
+    frame: usize,
+}
+
+// The hash isn't saved, it's worked out again from the page when the project is loaded
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ExportedPage {
+    index: usize,
+    page: CanvasPage,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct TextStylePreset {
+    name: String,
+    font_size: f32,
+    font_id: FontId,
+    color: Color32,
+    font_role: Option<ThemeFontRole>,
+    color_role: Option<ThemeColorRole>,
+    horizontal_alignment: TextHorizontalAlignment,
+    vertical_alignment: TextVerticalAlignment,
+    spacing: TextSpacing,
+    #[serde(default)]
+    typography: Typography,
+}
+
+impl Into<AppTextStylePreset> for TextStylePreset {
+    fn into(self) -> AppTextStylePreset {
+        AppTextStylePreset {
+            name: self.name,
+            font_size: self.font_size,
+            font_id: self.font_id,
+            color: self.color,
+            font_role: self.font_role.map(ThemeFontRole::into),
+            color_role: self.color_role.map(ThemeColorRole::into),
+            horizontal_alignment: match self.horizontal_alignment {
+                TextHorizontalAlignment::Left => AppTextHorizontalAlignment::Left,
+                TextHorizontalAlignment::Center => AppTextHorizontalAlignment::Center,
+                TextHorizontalAlignment::Right => AppTextHorizontalAlignment::Right,
+            },
+            vertical_alignment: match self.vertical_alignment {
+                TextVerticalAlignment::Top => AppTextVerticalAlignment::Top,
+                TextVerticalAlignment::Center => AppTextVerticalAlignment::Center,
+                TextVerticalAlignment::Bottom => AppTextVerticalAlignment::Bottom,
+            },
+            spacing: self.spacing.into(),
+            typography: self.typography.into(),
+        }
+    }
+}
+
+impl Into<TextStylePreset> for AppTextStylePreset {
+    fn into(self) -> TextStylePreset {
+        TextStylePreset {
+            name: self.name,
+            font_size: self.font_size,
+            font_id: self.font_id,
+            color: self.color,
+            font_role: self.font_role.map(AppThemeFontRole::into),
+            color_role: self.color_role.map(AppThemeColorRole::into),
+            horizontal_alignment: match self.horizontal_alignment {
+                AppTextHorizontalAlignment::Left => TextHorizontalAlignment::Left,
+                AppTextHorizontalAlignment::Center => TextHorizontalAlignment::Center,
+                AppTextHorizontalAlignment::Right => TextHorizontalAlignment::Right,
+            },
+            vertical_alignment: match self.vertical_alignment {
+                AppTextVerticalAlignment::Top => TextVerticalAlignment::Top,
+                AppTextVerticalAlignment::Center => TextVerticalAlignment::Center,
+                AppTextVerticalAlignment::Bottom => TextVerticalAlignment::Bottom,
+            },
+            spacing: self.spacing.into(),
+            typography: self.typography.into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct BookMetadata {
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    author: String,
+    #[serde(default)]
+    date_range: String,
+    #[serde(default)]
+    location: String,
+}
+
+impl Into<AppBookMetadata> for BookMetadata {
+    fn into(self) -> AppBookMetadata {
+        AppBookMetadata {
+            title: self.title,
+            author: self.author,
+            date_range: self.date_range,
+            location: self.location,
+            section: String::new(),
+        }
+    }
+}
+
+impl Into<BookMetadata> for AppBookMetadata {
+    fn into(self) -> BookMetadata {
+        BookMetadata {
+            title: self.title,
+            author: self.author,
+            date_range: self.date_range,
+            location: self.location,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+enum ThemeFontRole {
+    Heading,
+    Body,
+}
+
+impl Into<AppThemeFontRole> for ThemeFontRole {
+    fn into(self) -> AppThemeFontRole {
+        match self {
+            ThemeFontRole::Heading => AppThemeFontRole::Heading,
+            ThemeFontRole::Body => AppThemeFontRole::Body,
+        }
+    }
+}
+
+impl Into<ThemeFontRole> for AppThemeFontRole {
+    fn into(self) -> ThemeFontRole {
+        match self {
+            AppThemeFontRole::Heading => ThemeFontRole::Heading,
+            AppThemeFontRole::Body => ThemeFontRole::Body,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+enum ThemeColorRole {
+    Primary,
+    Secondary,
+    Accent,
+    Text,
+    Background,
+}
+
+impl Into<AppThemeColorRole> for ThemeColorRole {
+    fn into(self) -> AppThemeColorRole {
+        match self {
+            ThemeColorRole::Primary => AppThemeColorRole::Primary,
+            ThemeColorRole::Secondary => AppThemeColorRole::Secondary,
+            ThemeColorRole::Accent => AppThemeColorRole::Accent,
+            ThemeColorRole::Text => AppThemeColorRole::Text,
+            ThemeColorRole::Background => AppThemeColorRole::Background,
+        }
+    }
+}
+
+impl Into<ThemeColorRole> for AppThemeColorRole {
+    fn into(self) -> ThemeColorRole {
+        match self {
+            AppThemeColorRole::Primary => ThemeColorRole::Primary,
+            AppThemeColorRole::Secondary => ThemeColorRole::Secondary,
+            AppThemeColorRole::Accent => ThemeColorRole::Accent,
+            AppThemeColorRole::Text => ThemeColorRole::Text,
+            AppThemeColorRole::Background => ThemeColorRole::Background,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct BookTheme {
+    name: String,
+    heading_font: Option<String>,
+    body_font: Option<String>,
+    primary: Color32,
+    secondary: Color32,
+    accent: Color32,
+    text: Color32,
+    background: Color32,
+    stroke_width: f32,
+    stroke_color: ThemeColorRole,
+}
+
+impl Default for BookTheme {
+    fn default() -> Self {
+        AppBookTheme::default().into()
+    }
+}
+
+impl Into<AppBookTheme> for BookTheme {
+    fn into(self) -> AppBookTheme {
+        AppBookTheme {
+            name: self.name,
+            heading_font: self.heading_font,
+            body_font: self.body_font,
+            primary: self.primary,
+            secondary: self.secondary,
+            accent: self.accent,
+            text: self.text,
+            background: self.background,
+            stroke: AppThemeStroke {
+                width: self.stroke_width,
+                color: self.stroke_color.into(),
+            },
+        }
+    }
+}
+
+impl Into<BookTheme> for AppBookTheme {
+    fn into(self) -> BookTheme {
+        BookTheme {
+            name: self.name,
+            heading_font: self.heading_font,
+            body_font: self.body_font,
+            primary: self.primary,
+            secondary: self.secondary,
+            accent: self.accent,
+            text: self.text,
+            background: self.background,
+            stroke_width: self.stroke.width,
+            stroke_color: self.stroke.color.into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum ColorLabel {
+    Red,
+    Yellow,
+    Green,
+    Blue,
+    Purple,
+}
+
+impl Into<AppColorLabel> for ColorLabel {
+    fn into(self) -> AppColorLabel {
+        match self {
+            ColorLabel::Red => AppColorLabel::Red,
+            ColorLabel::Yellow => AppColorLabel::Yellow,
+            ColorLabel::Green => AppColorLabel::Green,
+            ColorLabel::Blue => AppColorLabel::Blue,
+            ColorLabel::Purple => AppColorLabel::Purple,
+        }
+    }
+}
+
+impl Into<ColorLabel> for AppColorLabel {
+    fn into(self) -> ColorLabel {
+        match self {
+            AppColorLabel::Red => ColorLabel::Red,
+            AppColorLabel::Yellow => ColorLabel::Yellow,
+            AppColorLabel::Green => ColorLabel::Green,
+            AppColorLabel::Blue => ColorLabel::Blue,
+            AppColorLabel::Purple => ColorLabel::Purple,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum PhotoRotation {
+    Normal,
+    MirrorHorizontal,
+    Rotate180,
+    MirrorVerticalAndRotate180,
+    MirrorHorizontalAndRotate90CW,
+    Rotate90CW,
+    MirrorHorizontalAndRotate270CW,
+    Rotate270CW,
+}
+
+impl Into<AppPhotoRotation> for PhotoRotation {
+    fn into(self) -> AppPhotoRotation {
+        match self {
+            PhotoRotation::Normal => AppPhotoRotation::Normal,
+            PhotoRotation::MirrorHorizontal => AppPhotoRotation::MirrorHorizontal,
+            PhotoRotation::Rotate180 => AppPhotoRotation::Rotate180,
+            PhotoRotation::MirrorVerticalAndRotate180 => {
+                AppPhotoRotation::MirrorVerticalAndRotate180
+            }
+            PhotoRotation::MirrorHorizontalAndRotate90CW => {
+                AppPhotoRotation::MirrorHorizontalAndRotate90CW
+            }
+            PhotoRotation::Rotate90CW => AppPhotoRotation::Rotate90CW,
+            PhotoRotation::MirrorHorizontalAndRotate270CW => {
+                AppPhotoRotation::MirrorHorizontalAndRotate270CW
+            }
+            PhotoRotation::Rotate270CW => AppPhotoRotation::Rotate270CW,
+        }
+    }
+}
+
+impl Into<PhotoRotation> for AppPhotoRotation {
+    fn into(self) -> PhotoRotation {
+        match self {
+            AppPhotoRotation::Normal => PhotoRotation::Normal,
+            AppPhotoRotation::MirrorHorizontal => PhotoRotation::MirrorHorizontal,
+            AppPhotoRotation::Rotate180 => PhotoRotation::Rotate180,
+            AppPhotoRotation::MirrorVerticalAndRotate180 => {
+                PhotoRotation::MirrorVerticalAndRotate180
+            }
+            AppPhotoRotation::MirrorHorizontalAndRotate90CW => {
+                PhotoRotation::MirrorHorizontalAndRotate90CW
+            }
+            AppPhotoRotation::Rotate90CW => PhotoRotation::Rotate90CW,
+            AppPhotoRotation::MirrorHorizontalAndRotate270CW => {
+                PhotoRotation::MirrorHorizontalAndRotate270CW
+            }
+            AppPhotoRotation::Rotate270CW => PhotoRotation::Rotate270CW,
+        }
+    }
+}
+
+impl Into<AppPhotoRating> for PhotoRating {
+    fn into(self) -> AppPhotoRating {
+        match self {
+            PhotoRating::Yes => AppPhotoRating::Yes,
+            PhotoRating::No => AppPhotoRating::No,
+            PhotoRating::Maybe => AppPhotoRating::Maybe,
+        }
+    }
+}
+
+impl Into<PhotoRating> for AppPhotoRating {
+    fn into(self) -> PhotoRating {
+        match self {
             AppPhotoRating::Yes => PhotoRating::Yes,
             AppPhotoRating::No => PhotoRating::No,
             AppPhotoRating::Maybe => PhotoRating::Maybe,
@@ -708,6 +2564,7 @@ impl Into<PhotoRating> for AppPhotoRating {
 pub enum PhotosGrouping {
     Rating,
     Date,
+    Folder,
 }
 
 impl Into<AppPhotosGrouping> for PhotosGrouping {
@@ -715,6 +2572,7 @@ impl Into<AppPhotosGrouping> for PhotosGrouping {
         match self {
             PhotosGrouping::Rating => AppPhotosGrouping::Rating,
             PhotosGrouping::Date => AppPhotosGrouping::Date,
+            PhotosGrouping::Folder => AppPhotosGrouping::Folder,
         }
     }
 }
@@ -724,6 +2582,70 @@ impl Into<PhotosGrouping> for AppPhotosGrouping {
         match self {
             AppPhotosGrouping::Rating => PhotosGrouping::Rating,
             AppPhotosGrouping::Date => PhotosGrouping::Date,
+            AppPhotosGrouping::Folder => PhotosGrouping::Folder,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum PhotosSortField {
+    CaptureDate,
+    FileName,
+    Rating,
+    ImportDate,
+    FileSize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum SortDirection {
+    Ascending,
+    Descending,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PhotosSort {
+    pub field: PhotosSortField,
+    pub direction: SortDirection,
+}
+
+impl Default for PhotosSort {
+    fn default() -> Self {
+        AppPhotosSort::default().into()
+    }
+}
+
+impl Into<AppPhotosSort> for PhotosSort {
+    fn into(self) -> AppPhotosSort {
+        AppPhotosSort {
+            field: match self.field {
+                PhotosSortField::CaptureDate => AppPhotosSortField::CaptureDate,
+                PhotosSortField::FileName => AppPhotosSortField::FileName,
+                PhotosSortField::Rating => AppPhotosSortField::Rating,
+                PhotosSortField::ImportDate => AppPhotosSortField::ImportDate,
+                PhotosSortField::FileSize => AppPhotosSortField::FileSize,
+            },
+            direction: match self.direction {
+                SortDirection::Ascending => AppSortDirection::Ascending,
+                SortDirection::Descending => AppSortDirection::Descending,
+            },
+        }
+    }
+}
+
+impl Into<PhotosSort> for AppPhotosSort {
+    fn into(self) -> PhotosSort {
+        PhotosSort {
+            field: match self.field {
+                AppPhotosSortField::CaptureDate => PhotosSortField::CaptureDate,
+                AppPhotosSortField::FileName => PhotosSortField::FileName,
+                AppPhotosSortField::Rating => PhotosSortField::Rating,
+                AppPhotosSortField::ImportDate => PhotosSortField::ImportDate,
+                AppPhotosSortField::FileSize => PhotosSortField::FileSize,
+            },
+            direction: match self.direction {
+                AppSortDirection::Ascending => SortDirection::Ascending,
+                AppSortDirection::Descending => SortDirection::Descending,
+            },
         }
     }
 }
@@ -738,13 +2660,14 @@ impl Into<Page> for AppPage {
                 AppUnit::Inches => Unit::Inches,
                 AppUnit::Centimeters => Unit::Centimeters,
             },
+            bleed: self.bleed(),
         }
     }
 }
 
 impl Into<AppPage> for Page {
     fn into(self) -> AppPage {
-        AppPage::new(
+        let mut page = AppPage::new(
             self.size,
             self.ppi,
             match self.unit {
@@ -752,6 +2675,62 @@ impl Into<AppPage> for Page {
                 Unit::Inches => AppUnit::Inches,
                 Unit::Centimeters => AppUnit::Centimeters,
             },
-        )
+        );
+        page.set_bleed(self.bleed);
+        page
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct InfoPanel {
+    #[serde(default)]
+    tab: InfoTab,
+    #[serde(default)]
+    collapsed_sections: Vec<String>,
+}
+
+impl Into<AppInfoPanelState> for InfoPanel {
+    fn into(self) -> AppInfoPanelState {
+        AppInfoPanelState::new(self.tab.into(), self.collapsed_sections)
+    }
+}
+
+impl Into<InfoPanel> for AppInfoPanelState {
+    fn into(self) -> InfoPanel {
+        InfoPanel {
+            tab: self.tab.into(),
+            collapsed_sections: self.collapsed_sections,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+enum InfoTab {
+    #[default]
+    Layer,
+    Page,
+    Tool,
+    Book,
+}
+
+impl Into<AppInfoTab> for InfoTab {
+    fn into(self) -> AppInfoTab {
+        match self {
+            InfoTab::Layer => AppInfoTab::Layer,
+            InfoTab::Page => AppInfoTab::Page,
+            InfoTab::Tool => AppInfoTab::Tool,
+            InfoTab::Book => AppInfoTab::Book,
+        }
+    }
+}
+
+impl Into<InfoTab> for AppInfoTab {
+    fn into(self) -> InfoTab {
+        match self {
+            AppInfoTab::Layer => InfoTab::Layer,
+            AppInfoTab::Page => InfoTab::Page,
+            AppInfoTab::Tool => InfoTab::Tool,
+            AppInfoTab::Book => InfoTab::Book,
+        }
     }
 }
@@ -0,0 +1,210 @@
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+
+use egui::FontFamily;
+use indexmap::IndexMap;
+use log::warn;
+
+use crate::{
+    book_theme::{BookTheme, ThemeFontRole},
+    dependencies::{Dependency, SingletonFor},
+    dirs::Dirs,
+    font_manager::FontManager,
+    id::LayerId,
+    widget::{canvas::CanvasState, canvas_info::layers::LayerContent},
+};
+
+/// A text layer set in a font that isn't installed
+#[derive(Debug, Clone, PartialEq)]
+pub struct FontUse {
+    // Starting at 0
+    pub page: usize,
+    pub layer: LayerId,
+    pub layer_name: String,
+}
+
+/// A font family the project uses that this machine doesn't have, with everything that uses it
+#[derive(Debug, Clone, PartialEq)]
+pub struct MissingFont {
+    pub family: String,
+    pub layers: Vec<FontUse>,
+    pub theme_roles: Vec<ThemeFontRole>,
+}
+
+/// Where the fonts of the project at `project_path` are embedded, a folder next to the project
+/// file named after it
+pub fn embedded_fonts_dir(project_path: &Path) -> PathBuf {
+    let stem = project_path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    project_path.with_file_name(format!("{}_fonts", stem))
+}
+
+/// Copies the installed files of `families` into the project's embedded fonts folder. Returns
+/// how many files were copied, fonts already embedded are left alone. `progress` is called with
+/// how many of the files have been gone through, from 0 to 1.
+pub fn embed_fonts(
+    project_path: &Path,
+    families: &HashSet<String>,
+    mut progress: impl FnMut(f32),
+) -> std::io::Result<usize> {
+    let font_files = Dependency::<FontManager>::get()
+        .with_lock(|font_manager| font_manager.font_files(families));
+    if font_files.is_empty() {
+        return Ok(0);
+    }
+
+    let embedded_dir = embedded_fonts_dir(project_path);
+    std::fs::create_dir_all(&embedded_dir)?;
+
+    let mut copied = 0;
+    let font_count = font_files.len();
+    for (index, font_file) in font_files.into_iter().enumerate() {
+        progress(index as f32 / font_count as f32);
+
+        let Some(file_name) = font_file.file_name() else {
+            continue;
+        };
+
+        let embedded_path = embedded_dir.join(file_name);
+        if embedded_path.exists() {
+            continue;
+        }
+
+        match std::fs::copy(&font_file, &embedded_path) {
+            Ok(_) => copied += 1,
+            Err(err) => warn!("Failed to embed font {}: {:?}", font_file.display(), err),
+        }
+    }
+    progress(1.0);
+
+    Ok(copied)
+}
+
+/// Installs the fonts embedded with the project at `project_path`. Returns how many weren't
+/// installed yet, the fonts need reloading if there are any.
+pub fn install_embedded_fonts(project_path: &Path) -> usize {
+    let embedded_dir = embedded_fonts_dir(project_path);
+    if !embedded_dir.is_dir() {
+        return 0;
+    }
+
+    install_fonts(&embedded_dir).unwrap_or_else(|err| {
+        warn!(
+            "Failed to install the fonts in {}: {:?}",
+            embedded_dir.display(),
+            err
+        );
+        0
+    })
+}
+
+/// Fonts are shared between projects so they're copied to the app's font directory rather than
+/// being loaded from wherever they came with the project. Returns how many were new.
+pub fn install_fonts(fonts_dir: &Path) -> std::io::Result<usize> {
+    let Ok(entries) = std::fs::read_dir(fonts_dir) else {
+        return Ok(0);
+    };
+
+    let installed_dir = Dirs::Fonts.path();
+    std::fs::create_dir_all(&installed_dir)?;
+
+    let mut installed = 0;
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let installed_path = installed_dir.join(entry.file_name());
+        if !installed_path.exists() {
+            std::fs::copy(entry.path(), installed_path)?;
+            installed += 1;
+        }
+    }
+
+    Ok(installed)
+}
+
+/// The font families used by `pages` and `theme` that aren't installed, in the order they're
+/// first used. Nothing is missing until the installed fonts have been loaded.
+pub fn find_missing<'a>(
+    pages: impl IntoIterator<Item = &'a CanvasState>,
+    theme: &BookTheme,
+) -> Vec<MissingFont> {
+    let font_manager = Dependency::<FontManager>::get();
+    let is_available = |family: &str| {
+        font_manager.with_lock(|font_manager| {
+            font_manager.font_definitions.is_none()
+                || font_manager.is_available(&FontFamily::Name(family.into()))
+        })
+    };
+
+    let mut missing: IndexMap<String, MissingFont> = IndexMap::new();
+
+    for (index, page) in pages.into_iter().enumerate() {
+        for layer in page.layers.values() {
+            let (LayerContent::Text(text) | LayerContent::TemplateText { text, .. }) =
+                &layer.content
+            else {
+                continue;
+            };
+            let FontFamily::Name(family) = &text.font_id.family else {
+                continue;
+            };
+
+            if !is_available(family) {
+                missing_entry(&mut missing, family).layers.push(FontUse {
+                    page: index,
+                    layer: layer.id,
+                    layer_name: layer.name.clone(),
+                });
+            }
+        }
+    }
+
+    for role in [ThemeFontRole::Heading, ThemeFontRole::Body] {
+        if let Some(family) = theme.font(role) {
+            if !is_available(family) {
+                missing_entry(&mut missing, family).theme_roles.push(role);
+            }
+        }
+    }
+
+    missing.into_values().collect()
+}
+
+fn missing_entry<'a>(
+    missing: &'a mut IndexMap<String, MissingFont>,
+    family: &str,
+) -> &'a mut MissingFont {
+    missing
+        .entry(family.to_string())
+        .or_insert_with(|| MissingFont {
+            family: family.to_string(),
+            layers: Vec::new(),
+            theme_roles: Vec::new(),
+        })
+}
+
+/// Sets the text layers of `pages` using `family` in `replacement` instead. Returns how many
+/// layers changed.
+pub fn substitute<'a>(
+    pages: impl IntoIterator<Item = &'a mut CanvasState>,
+    family: &str,
+    replacement: &FontFamily,
+) -> usize {
+    let mut substituted = 0;
+
+    for layer in pages.into_iter().flat_map(|page| page.layers.values_mut()) {
+        if let LayerContent::Text(text) | LayerContent::TemplateText { text, .. } =
+            &mut layer.content
+        {
+            if matches!(&text.font_id.family, FontFamily::Name(name) if name.as_ref() == family) {
+                text.font_id.family = replacement.clone();
+                substituted += 1;
+            }
+        }
+    }
+
+    substituted
+}
@@ -1,8 +1,154 @@
-use crate::model::page::Page;
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::PathBuf,
+};
+
+use parking_lot::Mutex;
+
+use crate::{
+    book_metadata::BookMetadata,
+    book_theme::BookTheme,
+    decoration::Decoration,
+    model::{guide::Guide, page::Page, resize_quality::ResizeQuality},
+    project::v1::page_content_hash,
+    text_style::TextStylePreset,
+    widget::canvas::CanvasState,
+};
+
+/// A page as it looked when it was last exported, kept to show what exporting it again changes
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExportedPage {
+    pub hash: u64,
+    pub page: CanvasState,
+}
+
+impl ExportedPage {
+    pub fn new(page: &CanvasState) -> Self {
+        Self {
+            hash: page_content_hash(page),
+            page: page.clone(),
+        }
+    }
+}
+
+/// The content hashes of the book's pages by their position, worked out again only once a page
+/// differs from the copy its hash was made from. Comparing pages is much quicker than hashing
+/// them, which the pages panel would otherwise do for every page every frame.
+#[derive(Debug, Default)]
+pub struct PageHashCache {
+    hashes: Mutex<HashMap<usize, (CanvasState, u64)>>,
+}
+
+impl PageHashCache {
+    fn hash(&self, index: usize, page: &CanvasState) -> u64 {
+        let mut hashes = self.hashes.lock();
+        if let Some((hashed_page, hash)) = hashes.get(&index) {
+            if hashed_page == page {
+                return *hash;
+            }
+        }
+
+        let hash = page_content_hash(page);
+        hashes.insert(index, (page.clone(), hash));
+        hash
+    }
+}
+
+// A cache isn't part of the settings, copies start out empty and it's never a difference
+impl Clone for PageHashCache {
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+impl PartialEq for PageHashCache {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct ProjectSettings {
     pub default_page: Option<Page>,
+    // Words the spell checker should accept for this project
+    pub custom_dictionary: Vec<String>,
+    // Stickers, ornaments and textures imported into the project
+    pub decorations: Vec<Decoration>,
+    pub theme: BookTheme,
+    // Guides shown on every page, on top of each page's own
+    pub guides: Vec<Guide>,
+    // Values for the variables text layers can use
+    pub metadata: BookMetadata,
+    // The pages as they were last exported by their position in the book. Exported files are
+    // named after the position so a page that moved counts as changed.
+    pub exported_pages: BTreeMap<usize, ExportedPage>,
+    pub page_hashes: PageHashCache,
+    // Named text formatting text layers can follow
+    pub text_styles: Vec<TextStylePreset>,
+    // Photos set aside as candidates while laying out the book
+    pub tray: Vec<PathBuf>,
+    // The frame picked as the still of each animated GIF or WebP, so importing it again gives
+    // the same photo
+    pub animation_frames: BTreeMap<PathBuf, usize>,
+    // Copies the fonts the project uses next to the project file when it's saved so it opens the
+    // same on machines without them
+    pub embed_fonts: bool,
+    // How photos are resampled when they're drawn smaller than their native size
+    pub resize_quality: ResizeQuality,
+    // Space left around a photo fitted to the page in this project, the margin from the config is
+    // used when it isn't set
+    pub fit_page_margin_mm: Option<f32>,
+}
+
+impl ProjectSettings {
+    /// Marks the pages of `book` at `exported` as exported. Pages past the end of the book are
+    /// dropped since there's nothing left to compare them with.
+    pub fn record_export(&mut self, book: &[CanvasState], exported: &[usize]) {
+        self.exported_pages.retain(|index, _| *index < book.len());
+
+        for index in exported {
+            if let Some(page) = book.get(*index) {
+                self.exported_pages.insert(*index, ExportedPage::new(page));
+            }
+        }
+    }
+
+    /// Whether the book has been exported in this project
+    pub fn has_exported(&self) -> bool {
+        !self.exported_pages.is_empty()
+    }
+
+    /// Whether the page at `index` looks different from the page exported there last
+    pub fn changed_since_export(&self, index: usize, page: &CanvasState) -> bool {
+        self.exported_pages.get(&index).map_or(true, |exported| {
+            exported.hash != self.page_hashes.hash(index, page)
+        })
+    }
+
+    pub fn text_style(&self, name: &str) -> Option<&TextStylePreset> {
+        self.text_styles.iter().find(|style| style.name == name)
+    }
+
+    /// Adds the style, replacing the one with the same name
+    pub fn save_text_style(&mut self, style: TextStylePreset) {
+        match self
+            .text_styles
+            .iter_mut()
+            .find(|existing| existing.name == style.name)
+        {
+            Some(existing) => *existing = style,
+            None => self.text_styles.push(style),
+        }
+    }
+
+    /// Adds the photos to the end of the tray, skipping those already in it
+    pub fn add_to_tray(&mut self, paths: impl IntoIterator<Item = PathBuf>) {
+        for path in paths {
+            if !self.tray.contains(&path) {
+                self.tray.push(path);
+            }
+        }
+    }
 }
 
 pub struct ProjectSettingsManager {
@@ -12,7 +158,22 @@ pub struct ProjectSettingsManager {
 impl ProjectSettingsManager {
     pub fn new() -> ProjectSettingsManager {
         ProjectSettingsManager {
-            project_settings: ProjectSettings { default_page: None },
+            project_settings: ProjectSettings {
+                default_page: None,
+                custom_dictionary: Vec::new(),
+                decorations: Vec::new(),
+                theme: BookTheme::default(),
+                guides: Vec::new(),
+                metadata: BookMetadata::default(),
+                exported_pages: BTreeMap::new(),
+                page_hashes: PageHashCache::default(),
+                text_styles: Vec::new(),
+                tray: Vec::new(),
+                animation_frames: BTreeMap::new(),
+                embed_fonts: false,
+                resize_quality: ResizeQuality::default(),
+                fit_page_margin_mm: None,
+            },
         }
     }
 }
@@ -0,0 +1,487 @@
+use crate::export::ExportError;
+
+// The largest width or height a PSD can have, larger documents need the PSB format
+const MAX_PSD_SIZE: u32 = 30_000;
+
+// PackBits runs and literal spans can't be longer than this
+const MAX_PACK_BITS_RUN: usize = 128;
+
+// Channel ids in the order the channels of a layer are written, -1 is the layer's transparency
+const LAYER_CHANNELS: [(i16, usize); 4] = [(-1, 3), (0, 0), (1, 1), (2, 2)];
+
+/// A raster layer of a PSD. Only the part of the page the layer covers is kept.
+pub struct PsdLayer {
+    pub name: String,
+    pub visible: bool,
+    // Where the pixels go on the page
+    left: u32,
+    top: u32,
+    width: u32,
+    height: u32,
+    // Unpremultiplied RGBA
+    pixels: Vec<u8>,
+}
+
+impl PsdLayer {
+    /// Makes a layer from unpremultiplied RGBA pixels the size of the page, trimmed to the pixels
+    /// that aren't transparent
+    pub fn from_page_pixels(name: &str, pixels: &[u8], page_width: u32, page_height: u32) -> Self {
+        let alpha_at =
+            |x: u32, y: u32| pixels[(y as usize * page_width as usize + x as usize) * 4 + 3];
+
+        let mut bounds: Option<(u32, u32, u32, u32)> = None;
+        for y in 0..page_height {
+            for x in 0..page_width {
+                if alpha_at(x, y) == 0 {
+                    continue;
+                }
+                bounds = Some(match bounds {
+                    Some((min_x, min_y, max_x, max_y)) => {
+                        (min_x.min(x), min_y.min(y), max_x.max(x), max_y.max(y))
+                    }
+                    None => (x, y, x, y),
+                });
+            }
+        }
+
+        let Some((min_x, min_y, max_x, max_y)) = bounds else {
+            return Self {
+                name: name.to_string(),
+                visible: true,
+                left: 0,
+                top: 0,
+                width: 0,
+                height: 0,
+                pixels: Vec::new(),
+            };
+        };
+
+        let (width, height) = (max_x - min_x + 1, max_y - min_y + 1);
+        let mut trimmed = Vec::with_capacity(width as usize * height as usize * 4);
+        for y in min_y..=max_y {
+            let start = (y as usize * page_width as usize + min_x as usize) * 4;
+            trimmed.extend_from_slice(&pixels[start..start + width as usize * 4]);
+        }
+
+        Self {
+            name: name.to_string(),
+            visible: true,
+            left: min_x,
+            top: min_y,
+            width,
+            height,
+            pixels: trimmed,
+        }
+    }
+
+    /// Blends the layer over `page`, unpremultiplied RGBA pixels `page_width` wide
+    pub fn composite_onto(&self, page: &mut [u8], page_width: u32) {
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let source = ((y * self.width + x) * 4) as usize;
+                let target = (((self.top + y) as usize * page_width as usize)
+                    + (self.left + x) as usize)
+                    * 4;
+                blend_over(
+                    &mut page[target..target + 4],
+                    &self.pixels[source..source + 4],
+                );
+            }
+        }
+    }
+
+    fn channel_data(&self, component: usize) -> Vec<u8> {
+        let channel: Vec<u8> = self
+            .pixels
+            .chunks_exact(4)
+            .map(|pixel| pixel[component])
+            .collect();
+
+        // Compression, 1 is PackBits
+        let mut data = 1u16.to_be_bytes().to_vec();
+        data.extend(pack_rows(&channel, self.width as usize));
+        data
+    }
+
+    fn write_record(&self, psd: &mut Vec<u8>, channels: &[Vec<u8>]) {
+        let bottom = self.top + self.height;
+        let right = self.left + self.width;
+        for edge in [self.top, self.left, bottom, right] {
+            psd.extend((edge as i32).to_be_bytes());
+        }
+
+        psd.extend((LAYER_CHANNELS.len() as u16).to_be_bytes());
+        for ((id, _), data) in LAYER_CHANNELS.iter().zip(channels) {
+            psd.extend(id.to_be_bytes());
+            psd.extend((data.len() as u32).to_be_bytes());
+        }
+
+        psd.extend(b"8BIMnorm");
+        // Opacity, clipping, flags and filler. Bit 1 of the flags hides the layer.
+        let flags = if self.visible { 0 } else { 0b10 };
+        psd.extend([255, 0, flags, 0]);
+
+        let mut extra = Vec::new();
+        // No layer mask or blending ranges
+        extra.extend(0u32.to_be_bytes());
+        extra.extend(0u32.to_be_bytes());
+        extra.extend(pascal_string(&self.name, 4));
+        extra.extend(unicode_name_block(&self.name));
+
+        psd.extend((extra.len() as u32).to_be_bytes());
+        psd.extend(extra);
+    }
+}
+
+/// Writes a PSD of a page `width` by `height` pixels with `layers` from bottom to top.
+/// `composite` is the flattened page as unpremultiplied RGBA, it's what apps that don't read
+/// layers show.
+pub fn write_psd(
+    width: u32,
+    height: u32,
+    ppi: i32,
+    layers: &[PsdLayer],
+    composite: &[u8],
+) -> Result<Vec<u8>, ExportError> {
+    if width > MAX_PSD_SIZE || height > MAX_PSD_SIZE {
+        return Err(ExportError::FileError(format!(
+            "Pages larger than {} pixels can't be saved as PSD files",
+            MAX_PSD_SIZE
+        )));
+    }
+
+    let mut psd = Vec::new();
+
+    // Header, RGB at 8 bits per channel with 3 channels in the composite
+    psd.extend(b"8BPS");
+    psd.extend(1u16.to_be_bytes());
+    psd.extend([0; 6]);
+    psd.extend(3u16.to_be_bytes());
+    psd.extend(height.to_be_bytes());
+    psd.extend(width.to_be_bytes());
+    psd.extend(8u16.to_be_bytes());
+    psd.extend(3u16.to_be_bytes());
+
+    // No color mode data
+    psd.extend(0u32.to_be_bytes());
+
+    let resources = resolution_resource(ppi);
+    psd.extend((resources.len() as u32).to_be_bytes());
+    psd.extend(resources);
+
+    let layer_info = layer_info(layers);
+    // Followed by an empty global layer mask
+    psd.extend((layer_info.len() as u32 + 4 + 4).to_be_bytes());
+    psd.extend((layer_info.len() as u32).to_be_bytes());
+    psd.extend(layer_info);
+    psd.extend(0u32.to_be_bytes());
+
+    psd.extend(1u16.to_be_bytes());
+    let channels: Vec<Vec<u8>> = (0..3)
+        .map(|component| {
+            let channel: Vec<u8> = composite
+                .chunks_exact(4)
+                .map(|pixel| pixel[component])
+                .collect();
+            pack_rows(&channel, width as usize)
+        })
+        .collect();
+    // The composite's row counts for every channel come before any of the rows
+    let counts_len = height as usize * 2;
+    for channel in &channels {
+        psd.extend(&channel[..counts_len]);
+    }
+    for channel in &channels {
+        psd.extend(&channel[counts_len..]);
+    }
+
+    Ok(psd)
+}
+
+// Source over blending of two unpremultiplied RGBA pixels
+fn blend_over(target: &mut [u8], source: &[u8]) {
+    let source_alpha = source[3] as f32 / 255.0;
+    if source_alpha == 0.0 {
+        return;
+    }
+
+    let target_alpha = target[3] as f32 / 255.0;
+    let alpha = source_alpha + target_alpha * (1.0 - source_alpha);
+
+    for channel in 0..3 {
+        let blended = (source[channel] as f32 * source_alpha
+            + target[channel] as f32 * target_alpha * (1.0 - source_alpha))
+            / alpha;
+        target[channel] = blended.round() as u8;
+    }
+    target[3] = (alpha * 255.0).round() as u8;
+}
+
+fn layer_info(layers: &[PsdLayer]) -> Vec<u8> {
+    let mut info = Vec::new();
+    info.extend((layers.len() as i16).to_be_bytes());
+
+    let channels: Vec<Vec<Vec<u8>>> = layers
+        .iter()
+        .map(|layer| {
+            LAYER_CHANNELS
+                .iter()
+                .map(|(_, component)| layer.channel_data(*component))
+                .collect()
+        })
+        .collect();
+
+    for (layer, channels) in layers.iter().zip(&channels) {
+        layer.write_record(&mut info, channels);
+    }
+
+    for data in channels.iter().flatten() {
+        info.extend(data);
+    }
+
+    if info.len() % 2 != 0 {
+        info.push(0);
+    }
+
+    info
+}
+
+// The page's resolution, so it opens at its print size
+fn resolution_resource(ppi: i32) -> Vec<u8> {
+    // Resolution as 16.16 fixed point followed by its unit, 1 is pixels per inch, and the unit
+    // lengths are shown in, 1 is inches
+    let mut data = Vec::new();
+    for _ in 0..2 {
+        data.extend(((ppi as u32) << 16).to_be_bytes());
+        data.extend(1u16.to_be_bytes());
+        data.extend(1u16.to_be_bytes());
+    }
+
+    let mut resource = Vec::new();
+    resource.extend(b"8BIM");
+    resource.extend(1005u16.to_be_bytes());
+    // An empty name, padded to an even length
+    resource.extend([0, 0]);
+    resource.extend((data.len() as u32).to_be_bytes());
+    resource.extend(data);
+    resource
+}
+
+// Layer names are stored as a Pascal string, which only holds ASCII well and is cut off at 255
+// bytes, then again in full as UTF-16 which is what Photoshop and Affinity show
+fn pascal_string(text: &str, padding: usize) -> Vec<u8> {
+    let bytes: Vec<u8> = text
+        .chars()
+        .map(|c| if c.is_ascii() { c as u8 } else { b'?' })
+        .take(255)
+        .collect();
+
+    let mut string = vec![bytes.len() as u8];
+    string.extend(bytes);
+    while string.len() % padding != 0 {
+        string.push(0);
+    }
+    string
+}
+
+fn unicode_name_block(name: &str) -> Vec<u8> {
+    let units: Vec<u16> = name.encode_utf16().collect();
+
+    let mut data = Vec::new();
+    data.extend((units.len() as u32).to_be_bytes());
+    for unit in units {
+        data.extend(unit.to_be_bytes());
+    }
+    while data.len() % 4 != 0 {
+        data.push(0);
+    }
+
+    let mut block = Vec::new();
+    block.extend(b"8BIMluni");
+    block.extend((data.len() as u32).to_be_bytes());
+    block.extend(data);
+    block
+}
+
+// Compresses each row of a channel with PackBits. The byte count of every row comes first.
+fn pack_rows(channel: &[u8], width: usize) -> Vec<u8> {
+    if width == 0 {
+        return Vec::new();
+    }
+
+    let rows: Vec<Vec<u8>> = channel.chunks(width).map(pack_bits).collect();
+
+    let mut data = Vec::new();
+    for row in &rows {
+        data.extend((row.len() as u16).to_be_bytes());
+    }
+    for row in rows {
+        data.extend(row);
+    }
+    data
+}
+
+// Repeated bytes become a count and the byte, everything else is copied with its length
+fn pack_bits(row: &[u8]) -> Vec<u8> {
+    let mut packed = Vec::new();
+    let mut i = 0;
+
+    while i < row.len() {
+        let mut run = 1;
+        while i + run < row.len() && run < MAX_PACK_BITS_RUN && row[i + run] == row[i] {
+            run += 1;
+        }
+
+        if run > 1 {
+            packed.push((1 - run as i32) as i8 as u8);
+            packed.push(row[i]);
+            i += run;
+            continue;
+        }
+
+        // Copied up to where the next run starts
+        let start = i;
+        i += 1;
+        while i < row.len()
+            && i - start < MAX_PACK_BITS_RUN
+            && !(i + 1 < row.len() && row[i] == row[i + 1])
+        {
+            i += 1;
+        }
+        packed.push((i - start - 1) as u8);
+        packed.extend_from_slice(&row[start..i]);
+    }
+
+    packed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unpack_bits(mut packed: &[u8]) -> Vec<u8> {
+        let mut row = Vec::new();
+        while let [header, rest @ ..] = packed {
+            let header = *header as i8;
+            if header >= 0 {
+                let length = header as usize + 1;
+                row.extend_from_slice(&rest[..length]);
+                packed = &rest[length..];
+            } else {
+                row.extend(std::iter::repeat(rest[0]).take((1 - header as isize) as usize));
+                packed = &rest[1..];
+            }
+        }
+        row
+    }
+
+    #[test]
+    fn pack_bits_encodes_runs_and_literals() {
+        assert_eq!(pack_bits(&[5, 5, 5]), vec![0xFE, 5]);
+        assert_eq!(pack_bits(&[1, 2, 3]), vec![2, 1, 2, 3]);
+        assert_eq!(pack_bits(&[1, 2, 2, 3]), vec![0, 1, 0xFF, 2, 0, 3]);
+        assert!(pack_bits(&[]).is_empty());
+    }
+
+    #[test]
+    fn pack_bits_splits_long_runs_and_literals() {
+        let run = vec![7; 300];
+        let packed = pack_bits(&run);
+        assert_eq!(packed, vec![0x81, 7, 0x81, 7, 0xD5, 7]);
+        assert_eq!(unpack_bits(&packed), run);
+
+        let literal: Vec<u8> = (0..300).map(|i| i as u8).collect();
+        let packed = pack_bits(&literal);
+        assert_eq!(packed[0], 127);
+        assert_eq!(unpack_bits(&packed), literal);
+    }
+
+    #[test]
+    fn pack_bits_round_trips() {
+        let row: Vec<u8> = (0..1000u32)
+            .map(|i| {
+                if (i / 7) % 3 == 0 {
+                    9
+                } else {
+                    (i * 31 % 251) as u8
+                }
+            })
+            .collect();
+        assert_eq!(unpack_bits(&pack_bits(&row)), row);
+    }
+
+    #[test]
+    fn pack_rows_puts_the_row_lengths_first() {
+        let data = pack_rows(&[1, 1, 1, 1, 2, 3, 4, 5], 4);
+        assert_eq!(&data[..4], &[0, 2, 0, 5]);
+        assert_eq!(unpack_bits(&data[4..6]), vec![1, 1, 1, 1]);
+        assert_eq!(unpack_bits(&data[6..]), vec![2, 3, 4, 5]);
+        assert!(pack_rows(&[], 0).is_empty());
+    }
+
+    #[test]
+    fn names_are_padded() {
+        assert_eq!(pascal_string("ab", 4), vec![2, b'a', b'b', 0]);
+        assert_eq!(
+            pascal_string("café", 4),
+            vec![4, b'c', b'a', b'f', b'?', 0, 0, 0]
+        );
+        assert_eq!(pascal_string(&"x".repeat(300), 4).len(), 256);
+
+        let block = unicode_name_block("é");
+        assert_eq!(&block[..8], b"8BIMluni");
+        // The character count and one UTF-16 unit, padded to 4 bytes
+        assert_eq!(&block[8..12], &8u32.to_be_bytes());
+        assert_eq!(&block[12..], &[0, 0, 0, 1, 0, 0xE9, 0, 0]);
+    }
+
+    #[test]
+    fn layers_are_trimmed_to_their_pixels() {
+        // A 4x3 page with two opaque pixels at (1, 0) and (2, 1)
+        let mut pixels = vec![0; 4 * 3 * 4];
+        for (x, y) in [(1, 0), (2, 1)] {
+            pixels[(y * 4 + x) * 4..(y * 4 + x) * 4 + 4].copy_from_slice(&[10, 20, 30, 255]);
+        }
+
+        let layer = PsdLayer::from_page_pixels("Photo", &pixels, 4, 3);
+        assert_eq!(
+            (layer.left, layer.top, layer.width, layer.height),
+            (1, 0, 2, 2)
+        );
+        assert_eq!(layer.pixels.len(), 2 * 2 * 4);
+
+        let empty = PsdLayer::from_page_pixels("Empty", &[0; 16], 2, 2);
+        assert_eq!((empty.width, empty.height), (0, 0));
+    }
+
+    #[test]
+    fn layers_are_composited_over_the_page() {
+        let mut page = [10, 20, 30, 255, 10, 20, 30, 255, 0, 0, 0, 0, 0, 0, 0, 0];
+        let pixels = [
+            0, 0, 0, 0, 200, 100, 0, 255, //
+            0, 0, 0, 0, 255, 255, 255, 128,
+        ];
+        PsdLayer::from_page_pixels("Photo", &pixels, 2, 2).composite_onto(&mut page, 2);
+
+        // Transparent pixels leave the page alone, opaque ones replace it
+        assert_eq!(&page[0..4], &[10, 20, 30, 255]);
+        assert_eq!(&page[4..8], &[200, 100, 0, 255]);
+        assert_eq!(&page[8..12], &[0, 0, 0, 0]);
+        // Over nothing the color stays as it was with the layer's transparency
+        assert_eq!(&page[12..16], &[255, 255, 255, 128]);
+    }
+
+    #[test]
+    fn psd_header_and_size_limit() {
+        let composite = vec![255; 3 * 2 * 4];
+        let psd = write_psd(3, 2, 300, &[], &composite).unwrap();
+
+        assert_eq!(&psd[..4], b"8BPS");
+        assert_eq!(&psd[4..6], &1u16.to_be_bytes());
+        assert_eq!(&psd[12..14], &3u16.to_be_bytes());
+        assert_eq!(&psd[14..18], &2u32.to_be_bytes());
+        assert_eq!(&psd[18..22], &3u32.to_be_bytes());
+
+        assert!(write_psd(MAX_PSD_SIZE + 1, 1, 300, &[], &[]).is_err());
+    }
+}
@@ -0,0 +1,482 @@
+use std::{
+    f32::consts::{FRAC_PI_2, PI},
+    sync::Arc,
+};
+
+use egui::{
+    emath::Rot2,
+    epaint::Vertex,
+    text::{Fonts, LayoutJob, TextFormat},
+    Align, Color32, FontDefinitions, FontFamily, FontId, Galley, Mesh, Pos2, Rect, TextureId, Vec2,
+};
+
+use crate::{
+    book_metadata::BookMetadata,
+    decoration::Decoration,
+    dependencies::{Dependency, SingletonFor},
+    font_manager::FontManager,
+    glyph_text::ShapedText,
+    model::{
+        canvas_path::CanvasPath,
+        photo_effects::PhotoEffects,
+        scale_mode::{PhotoAlignment, ScaleMode},
+    },
+    template::TemplateRegion,
+    text_shaping, typography,
+    widget::{
+        canvas::{CanvasPhoto, CanvasState},
+        canvas_info::layers::{
+            CanvasText, Layer, LayerContent, TextHorizontalAlignment, TextOrientation,
+            TextVerticalAlignment,
+        },
+    },
+};
+
+// The canvas and every export format draw a page from the geometry worked out here, so a page
+// looks the same on screen as it does in the exported file. Page previews, which the raster and
+// PDF exports are rasterized from, and the SVG export are all `RenderTarget`s driven by
+// `render_page`. The canvas being edited wraps each layer in the widgets that move it around, so
+// it paints them one at a time with the same functions as the previews.
+
+/// Something a page can be drawn into. Rects are in the coordinates of the `page_rect` passed to
+/// `render_page` and rotations are in radians around the rect's center.
+pub trait RenderTarget {
+    type Error;
+
+    fn begin_layer(&mut self, _layer: &Layer) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn end_layer(&mut self, _layer: &Layer) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn photo(&mut self, photo: &CanvasPhoto, rect: Rect, rotation: f32) -> Result<(), Self::Error>;
+
+    /// `photo_rect` is where the whole photo goes after scaling, it's clipped to `region_rect`
+    fn template_photo(
+        &mut self,
+        photo: &CanvasPhoto,
+        region_rect: Rect,
+        photo_rect: Rect,
+    ) -> Result<(), Self::Error>;
+
+    fn text(&mut self, text: &CanvasText, rect: Rect, rotation: f32) -> Result<(), Self::Error>;
+
+    fn decoration(
+        &mut self,
+        decoration: &Decoration,
+        rect: Rect,
+        rotation: f32,
+    ) -> Result<(), Self::Error>;
+
+    fn path(&mut self, path: &CanvasPath, rect: Rect, rotation: f32) -> Result<(), Self::Error>;
+}
+
+/// Draws the visible layers of the page from bottom to top
+pub fn render_page<T: RenderTarget>(
+    page: &CanvasState,
+    page_rect: Rect,
+    target: &mut T,
+) -> Result<(), T::Error> {
+    let scale = page_rect.width() / page.page.size_pixels().x;
+
+    for layer in page.layers.values().filter(|layer| layer.visible) {
+        let rect = layer_rect(page_rect, layer.transform_state.rect, scale);
+        let rotation = layer.transform_state.rotation;
+
+        target.begin_layer(layer)?;
+
+        match &layer.content {
+            LayerContent::Photo(photo) => target.photo(photo, rect, rotation)?,
+            LayerContent::TemplatePhoto {
+                region,
+                photo: Some(photo),
+                scale_mode,
+                alignment,
+            } => {
+                let region_rect = region_rect(page_rect, region);
+                let photo_rect = template_photo_rect(photo, region_rect, *scale_mode, *alignment);
+                target.template_photo(photo, region_rect, photo_rect)?;
+            }
+            LayerContent::TemplatePhoto { photo: None, .. } => {}
+            LayerContent::Text(text) => target.text(text, rect, rotation)?,
+            LayerContent::TemplateText { region, text } => {
+                target.text(text, region_rect(page_rect, region), 0.0)?
+            }
+            LayerContent::Decoration(decoration) => {
+                target.decoration(decoration, rect, rotation)?
+            }
+            LayerContent::Path(path) => target.path(path, rect, rotation)?,
+        }
+
+        target.end_layer(layer)?;
+    }
+
+    Ok(())
+}
+
+/// A layer's rect in page pixels placed on a page drawn at `page_rect` and `scale`
+pub fn layer_rect(page_rect: Rect, rect: Rect, scale: f32) -> Rect {
+    Rect::from_min_size(
+        page_rect.min + rect.min.to_vec2() * scale,
+        rect.size() * scale,
+    )
+}
+
+pub fn region_rect(page_rect: Rect, region: &TemplateRegion) -> Rect {
+    Rect::from_min_size(
+        page_rect.min + region.relative_position.to_vec2() * page_rect.size(),
+        region.relative_size * page_rect.size(),
+    )
+}
+
+pub fn template_photo_rect(
+    photo: &CanvasPhoto,
+    region_rect: Rect,
+    scale_mode: ScaleMode,
+    alignment: PhotoAlignment,
+) -> Rect {
+    scale_mode.photo_rect(region_rect, photo.oriented_size(), alignment)
+}
+
+/// The textured mesh of a photo drawn at `rect`, `scale` times its size on the page. The crop is in
+/// the coordinates of the unrotated image so the mesh is built unrotated, with the width and height
+/// swapped for photos on their side, and then oriented and rotated into place.
+pub fn photo_mesh(
+    texture_id: TextureId,
+    photo: &CanvasPhoto,
+    rect: Rect,
+    uv: Rect,
+    rotation: f32,
+    scale: f32,
+) -> Mesh {
+    let exif_rotation = photo.photo.metadata.rotation();
+    let mesh_rect = photo.orientation.mesh_rect(exif_rotation, rect);
+
+    let mut mesh = if photo.effects.is_none() {
+        let mut mesh = Mesh::with_texture(texture_id);
+        mesh.add_rect_with_uv(mesh_rect, uv, Color32::WHITE);
+        mesh
+    } else {
+        effects_mesh(texture_id, &photo.effects, mesh_rect, uv, scale)
+    };
+
+    let center = mesh_rect.center();
+    photo
+        .orientation
+        .orient_mesh(exif_rotation, &mut mesh, center);
+    if rotation != 0.0 {
+        mesh.rotate(Rot2::from_angle(rotation), center);
+    }
+
+    mesh
+}
+
+// A photo with rounded corners or a vignette is drawn as rings of vertices from its center out to
+// its outline. The vignette is shaded into the vertex colors so it needs enough rings, and points
+// along the edges, to look smooth.
+const EFFECT_RINGS: usize = 16;
+const CORNER_SEGMENTS: usize = 12;
+const EDGE_SEGMENTS: usize = 8;
+
+// Rounded corners fade out over this many points past the outline so they're anti-aliased
+const ANTI_ALIAS_WIDTH: f32 = 1.0;
+
+fn effects_mesh(
+    texture_id: TextureId,
+    effects: &PhotoEffects,
+    rect: Rect,
+    uv: Rect,
+    scale: f32,
+) -> Mesh {
+    let radius = effects.corner_radius_at(rect.size(), scale);
+    let outline = rounded_rect_outline(rect, radius);
+    let center = rect.center();
+    let half_size = rect.size() / 2.0;
+
+    let uv_at = |pos: Pos2| uv.min + (pos - rect.min) / rect.size() * uv.size();
+    let color_at = |pos: Pos2| {
+        let distance = ((pos - center) / half_size).length();
+        let shade = 1.0 - effects.vignette_at(distance);
+        Color32::from_gray((shade * 255.0).round() as u8)
+    };
+
+    let mut mesh = Mesh::with_texture(texture_id);
+    mesh.vertices.push(Vertex {
+        pos: center,
+        uv: uv_at(center),
+        color: color_at(center),
+    });
+
+    for ring in 1..=EFFECT_RINGS {
+        let t = ring as f32 / EFFECT_RINGS as f32;
+        for (point, _) in &outline {
+            let pos = center + (*point - center) * t;
+            mesh.vertices.push(Vertex {
+                pos,
+                uv: uv_at(pos),
+                color: color_at(pos),
+            });
+        }
+    }
+
+    if radius > 0.0 {
+        for (point, normal) in &outline {
+            mesh.vertices.push(Vertex {
+                pos: *point + *normal * ANTI_ALIAS_WIDTH,
+                uv: uv_at(*point),
+                color: Color32::TRANSPARENT,
+            });
+        }
+    }
+
+    let count = outline.len() as u32;
+    let ring_start = |ring: u32| 1 + (ring - 1) * count;
+    let rings = (mesh.vertices.len() as u32 - 1) / count;
+
+    for i in 0..count {
+        let next = (i + 1) % count;
+        mesh.add_triangle(0, ring_start(1) + i, ring_start(1) + next);
+
+        for ring in 1..rings {
+            let (inner, outer) = (ring_start(ring), ring_start(ring + 1));
+            mesh.add_triangle(inner + i, outer + i, outer + next);
+            mesh.add_triangle(inner + i, outer + next, inner + next);
+        }
+    }
+
+    mesh
+}
+
+// Points around the outline of `rect` with its corners rounded to `radius`, clockwise from the
+// top of the left edge, with the outward normal at each
+fn rounded_rect_outline(rect: Rect, radius: f32) -> Vec<(Pos2, Vec2)> {
+    let corners = [
+        (Pos2::new(rect.min.x + radius, rect.min.y + radius), PI),
+        (
+            Pos2::new(rect.max.x - radius, rect.min.y + radius),
+            PI * 1.5,
+        ),
+        (Pos2::new(rect.max.x - radius, rect.max.y - radius), 0.0),
+        (
+            Pos2::new(rect.min.x + radius, rect.max.y - radius),
+            PI * 0.5,
+        ),
+    ];
+
+    let mut outline = Vec::with_capacity(corners.len() * (CORNER_SEGMENTS + EDGE_SEGMENTS));
+    for (index, (corner_center, start_angle)) in corners.iter().enumerate() {
+        for segment in 0..=CORNER_SEGMENTS {
+            let angle = start_angle + FRAC_PI_2 * segment as f32 / CORNER_SEGMENTS as f32;
+            let normal = Vec2::angled(angle);
+            outline.push((*corner_center + normal * radius, normal));
+        }
+
+        // Points along the straight edge to the next corner
+        let (next_center, next_angle) = corners[(index + 1) % corners.len()];
+        let normal = Vec2::angled(start_angle + FRAC_PI_2);
+        let start = *corner_center + normal * radius;
+        let end = next_center + Vec2::angled(next_angle) * radius;
+        for segment in 1..EDGE_SEGMENTS {
+            let t = segment as f32 / EDGE_SEGMENTS as f32;
+            outline.push((start + (end - start) * t, normal));
+        }
+    }
+
+    outline
+}
+
+/// The full texture, for template photos which are clipped to their region instead of cropped
+pub fn full_uv() -> Rect {
+    Rect::from_min_max(Pos2::ZERO, Pos2::new(1.0, 1.0))
+}
+
+/// Lays out a text layer's text at `scale` times its font size with metadata fields resolved and
+/// shaping applied. Wrapping is left to the caller, `wrap_width` is only used to break lines
+/// ahead of time when the layer hyphenates or avoids widows, or has right to left text, whose
+/// lines are reordered once they're broken.
+pub fn text_layout_job(
+    fonts: &Fonts,
+    text: &CanvasText,
+    metadata: &BookMetadata,
+    scale: f32,
+    wrap_width: f32,
+) -> LayoutJob {
+    // A font the project uses may not be installed here or not loaded yet, egui panics on
+    // families it doesn't know. Installed ones are loaded for the next frame.
+    let family = match &text.font_id.family {
+        FontFamily::Name(name) if !fonts.families().contains(&text.font_id.family) => {
+            Dependency::<FontManager>::get().with_lock_mut(|font_manager| {
+                font_manager.request(name);
+            });
+            FontFamily::Proportional
+        }
+        family => family.clone(),
+    };
+    let font_id = FontId::new(text.font_size * scale, family);
+    let resolved = resolved_text(text, metadata);
+
+    let joined = text_shaping::join(&resolved);
+    let rtl = text_shaping::has_rtl(&joined);
+
+    if !text.typography.breaks_lines() && !rtl {
+        return text.spacing.layout_job(fonts, &joined, font_id, text.color);
+    }
+
+    let format = text.spacing.text_format(fonts, font_id.clone(), text.color);
+    let mut measure = |line: &str| {
+        fonts
+            .layout_job(LayoutJob::single_section(line.to_string(), format.clone()))
+            .size()
+            .x
+    };
+    let indent = text.spacing.first_line_indent * font_id.size;
+
+    let paragraphs: Vec<Vec<String>> = joined
+        .split('\n')
+        .map(|paragraph| {
+            text.typography
+                .break_paragraph(paragraph, wrap_width, indent, &mut measure)
+                .into_iter()
+                .map(|line| text_shaping::reorder(&line).into_owned())
+                .collect()
+        })
+        .collect();
+
+    text.spacing
+        .layout_lines(fonts, &paragraphs, font_id, text.color)
+}
+
+/// A text layer's text with metadata fields resolved and short words tied, ready to be laid out
+pub fn resolved_text(text: &CanvasText, metadata: &BookMetadata) -> String {
+    let resolved = metadata.resolve(&text.text);
+
+    if text.typography.tie_short_words {
+        typography::tie_short_words(&resolved)
+    } else {
+        resolved.into_owned()
+    }
+}
+
+/// The text of a text layer in a script egui can't draw, shaped at `scale` times its font size
+/// with lines broken to `wrap_width`. None when egui can draw the text itself or there's no font
+/// for it. Vertical text is drawn a character at a time so it's never shaped.
+pub fn shaped_text(
+    definitions: &FontDefinitions,
+    text: &CanvasText,
+    resolved: &str,
+    scale: f32,
+    wrap_width: f32,
+) -> Option<ShapedText> {
+    if text.orientation == TextOrientation::Vertical {
+        return None;
+    }
+
+    ShapedText::new(
+        definitions,
+        &text.font_id.family,
+        resolved,
+        text.font_size * scale,
+        &text.spacing,
+        &text.typography,
+        text.horizontal_alignment,
+        wrap_width,
+    )
+}
+
+/// Where shaped text `size` large goes in a text layer's `rect`, aligned like the layer's lines.
+/// Sideways text is placed in its `text_layout_rect` and then turned around `rect`'s center.
+pub fn shaped_text_rect(text: &CanvasText, size: Vec2, rect: Rect) -> Rect {
+    let layout_rect = text_layout_rect(text, rect);
+
+    let x = match text.horizontal_alignment {
+        TextHorizontalAlignment::Left => layout_rect.min.x,
+        TextHorizontalAlignment::Center => layout_rect.center().x - size.x / 2.0,
+        TextHorizontalAlignment::Right => layout_rect.max.x - size.x,
+    };
+    let y = match text.vertical_alignment {
+        TextVerticalAlignment::Top => layout_rect.min.y,
+        TextVerticalAlignment::Center => layout_rect.center().y - size.y / 2.0,
+        TextVerticalAlignment::Bottom => layout_rect.max.y - size.y,
+    };
+
+    Rect::from_min_size(Pos2::new(x, y), size)
+}
+
+/// The box a text layer's lines are laid out in, centered on the layer's `rect`. Sideways text
+/// runs along the layer's height so the width and height are swapped.
+pub fn text_layout_rect(text: &CanvasText, rect: Rect) -> Rect {
+    match text.orientation {
+        TextOrientation::Horizontal => rect,
+        _ => Rect::from_center_size(rect.center(), Vec2::new(rect.height(), rect.width())),
+    }
+}
+
+/// Lays out text that isn't horizontal, aligned within its `text_layout_rect`. The galley goes at
+/// the returned position and is then turned by the orientation's angle around `rect`'s center.
+pub fn sideways_text_galley(
+    fonts: &Fonts,
+    text: &CanvasText,
+    metadata: &BookMetadata,
+    scale: f32,
+    rect: Rect,
+) -> (Arc<Galley>, Pos2) {
+    let layout_rect = text_layout_rect(text, rect);
+
+    let mut job = text_layout_job(fonts, text, metadata, scale, layout_rect.width());
+    job.wrap.max_width = layout_rect.width();
+    job.halign = match text.horizontal_alignment {
+        TextHorizontalAlignment::Left => Align::Min,
+        TextHorizontalAlignment::Center => Align::Center,
+        TextHorizontalAlignment::Right => Align::Max,
+    };
+    let galley = fonts.layout_job(job);
+
+    // Rows aligned to the center or right are placed around x = 0 so the galley's rect is used
+    let x = match text.horizontal_alignment {
+        TextHorizontalAlignment::Left => layout_rect.min.x - galley.rect.min.x,
+        TextHorizontalAlignment::Center => layout_rect.center().x - galley.rect.center().x,
+        TextHorizontalAlignment::Right => layout_rect.max.x - galley.rect.max.x,
+    };
+    let y = match text.vertical_alignment {
+        TextVerticalAlignment::Top => layout_rect.min.y - galley.rect.min.y,
+        TextVerticalAlignment::Center => layout_rect.center().y - galley.rect.center().y,
+        TextVerticalAlignment::Bottom => layout_rect.max.y - galley.rect.max.y,
+    };
+
+    (galley, Pos2::new(x, y))
+}
+
+/// Where each character of vertical text is drawn upright, as the center of the character and
+/// the format it was laid out with. The rows of the galley become columns from right to left.
+pub fn vertical_text_glyphs<'a>(
+    galley: &'a Galley,
+    pos: Pos2,
+    rect: Rect,
+) -> Vec<(char, Pos2, &'a TextFormat)> {
+    let rotation = Rot2::from_angle(TextOrientation::Vertical.angle());
+
+    galley
+        .rows
+        .iter()
+        .flat_map(|row| {
+            row.glyphs.iter().map(move |glyph| {
+                let center =
+                    pos + Vec2::new(glyph.pos.x + glyph.advance_width / 2.0, row.rect.center().y);
+                let format = &galley.job.sections[glyph.section_index as usize].format;
+
+                (
+                    glyph.chr,
+                    rect.center() + rotation * (center - rect.center()),
+                    format,
+                )
+            })
+        })
+        .collect()
+}
+
+/// The size of the page in page pixels, for targets that start from the page's origin
+pub fn page_rect(page: &CanvasState) -> Rect {
+    Rect::from_min_size(Pos2::ZERO, page.page.size_pixels())
+}
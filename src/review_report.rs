@@ -0,0 +1,98 @@
+use std::path::Path;
+
+use indexmap::IndexMap;
+
+use crate::{
+    id::PageId, model::page_comment::PageComment, utils::csv_field, widget::canvas::CanvasState,
+};
+
+struct ReviewReportRow {
+    page: usize,
+    number: usize,
+    comment: PageComment,
+}
+
+/// Every comment in the book, for sharing outside the app. Written as CSV when the file has a
+/// `.csv` extension and as plain text otherwise.
+pub struct ReviewReport {
+    rows: Vec<ReviewReportRow>,
+}
+
+impl ReviewReport {
+    pub fn new(pages: &IndexMap<PageId, CanvasState>, include_resolved: bool) -> Self {
+        let rows = pages
+            .values()
+            .enumerate()
+            .flat_map(|(page_index, page)| {
+                page.comments
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, comment)| include_resolved || !comment.resolved)
+                    .map(move |(comment_index, comment)| ReviewReportRow {
+                        page: page_index + 1,
+                        number: comment_index + 1,
+                        comment: comment.clone(),
+                    })
+            })
+            .collect();
+
+        Self { rows }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    pub fn write(&self, path: &Path) -> std::io::Result<()> {
+        let is_csv = path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("csv"));
+
+        let contents = if is_csv { self.csv() } else { self.text() };
+        std::fs::write(path, contents)
+    }
+
+    fn csv(&self) -> String {
+        let mut csv = String::from("Page,Comment,Status,Created,Text\n");
+        for row in &self.rows {
+            csv.push_str(&format!(
+                "{},{},{},{},{}\n",
+                row.page,
+                row.number,
+                row.comment.status(),
+                row.comment.created_label(),
+                csv_field(&row.comment.text)
+            ));
+        }
+        csv
+    }
+
+    fn text(&self) -> String {
+        let open = self.rows.iter().filter(|row| !row.comment.resolved).count();
+        let mut text = format!(
+            "Review comments: {} open, {} resolved\n",
+            open,
+            self.rows.len() - open
+        );
+
+        let mut current_page = None;
+        for row in &self.rows {
+            if current_page != Some(row.page) {
+                text.push_str(&format!("\nPage {}\n", row.page));
+                current_page = Some(row.page);
+            }
+
+            text.push_str(&format!(
+                "  {}. [{}] {}\n",
+                row.number,
+                row.comment.status(),
+                row.comment.created_label()
+            ));
+            for line in row.comment.text.lines() {
+                text.push_str(&format!("     {}\n", line));
+            }
+        }
+
+        text
+    }
+}
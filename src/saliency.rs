@@ -0,0 +1,341 @@
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use egui::{Pos2, Rect, Vec2};
+use image::DynamicImage;
+use log::error;
+use tokio::task::spawn_blocking;
+
+use crate::{
+    dependencies::{Dependency, SingletonFor},
+    photo::Photo,
+    photo_database::FileStamp,
+};
+
+// Saliency only needs the rough layout of the photo, a tiny copy is plenty
+const ANALYSIS_SIZE: u32 = 64;
+// How much edges and fine detail count next to colors that stand out from the rest of the photo
+const EDGE_WEIGHT: f32 = 0.5;
+// How much less the corners count than the center, subjects tend to be near the middle
+const CENTER_BIAS: f32 = 0.3;
+// How long to wait before trying a photo whose map couldn't be made again
+const RETRY_AFTER: Duration = Duration::from_secs(30);
+
+#[derive(Debug, thiserror::Error)]
+pub enum SaliencyError {
+    #[error("Image error: {0}")]
+    Image(#[from] image::ImageError),
+    #[error("Thumbnail error: {0}")]
+    Thumbnail(#[from] anyhow::Error),
+    #[error("The photo has no thumbnail yet")]
+    NoThumbnail,
+}
+
+/// How visually important each part of a photo is, used to suggest crops that keep the subject
+/// when a photo is placed into a region of a different shape. The map is of the unrotated image,
+/// the same space as a photo's stored crop.
+#[derive(Debug, Clone)]
+pub struct SaliencyMap {
+    width: usize,
+    height: usize,
+    values: Vec<f32>,
+}
+
+impl SaliencyMap {
+    /// Made from the photo's thumbnail, which is plenty for the rough layout and doesn't mean
+    /// decoding the whole original
+    pub fn for_photo(photo: &Photo) -> Result<Self, SaliencyError> {
+        let thumbnail_path = photo.thumbnail_path()?;
+        if !thumbnail_path.exists() {
+            return Err(SaliencyError::NoThumbnail);
+        }
+
+        Ok(Self::from_image(&image::open(thumbnail_path)?))
+    }
+
+    /// Scores each pixel by how far its color is from the photo's average color, which picks out
+    /// subjects against plainer backgrounds, plus how much detail is around it
+    pub fn from_image(image: &DynamicImage) -> Self {
+        let image = image.thumbnail(ANALYSIS_SIZE, ANALYSIS_SIZE).to_rgb8();
+        let width = image.width() as usize;
+        let height = image.height() as usize;
+
+        // Brightness and two opponent color channels, so distances roughly follow how different
+        // colors look
+        let colors: Vec<[f32; 3]> = image
+            .pixels()
+            .map(|pixel| {
+                let [r, g, b] = pixel.0.map(|channel| channel as f32 / 255.0);
+                [(r + g + b) / 3.0, r - g, (r + g) / 2.0 - b]
+            })
+            .collect();
+
+        if colors.is_empty() {
+            return Self {
+                width,
+                height,
+                values: Vec::new(),
+            };
+        }
+
+        let mut mean = [0.0; 3];
+        for color in &colors {
+            for (mean, channel) in mean.iter_mut().zip(color) {
+                *mean += channel / colors.len() as f32;
+            }
+        }
+
+        let center = Vec2::new(width as f32, height as f32) / 2.0;
+        let mut values = Vec::with_capacity(colors.len());
+
+        for y in 0..height {
+            for x in 0..width {
+                let color = colors[y * width + x];
+                let right = colors[y * width + (x + 1).min(width - 1)];
+                let below = colors[(y + 1).min(height - 1) * width + x];
+
+                let contrast = distance(color, mean);
+                let edge = (color[0] - right[0]).abs() + (color[0] - below[0]).abs();
+
+                let from_center = (Vec2::new(x as f32 + 0.5, y as f32 + 0.5) - center) / center;
+                let bias = 1.0 - CENTER_BIAS * (from_center.length_sq() / 2.0);
+
+                values.push((contrast + EDGE_WEIGHT * edge) * bias);
+            }
+        }
+
+        Self {
+            width,
+            height,
+            values,
+        }
+    }
+
+    /// The normalized window of `size` holding the most salient part of the photo. `size` is
+    /// normalized too and kept as is, only where the window sits is chosen.
+    pub fn best_window(&self, size: Vec2) -> Rect {
+        let size = size.clamp(Vec2::ZERO, Vec2::splat(1.0));
+        let centered = Rect::from_center_size(Pos2::new(0.5, 0.5), size);
+
+        if self.values.is_empty() {
+            return centered;
+        }
+
+        let window_width = ((size.x * self.width as f32).round() as usize).clamp(1, self.width);
+        let window_height = ((size.y * self.height as f32).round() as usize).clamp(1, self.height);
+
+        // Summed area table, one larger than the map in each direction so every window is four
+        // lookups
+        let stride = self.width + 1;
+        let mut sums = vec![0.0f64; stride * (self.height + 1)];
+        for y in 0..self.height {
+            let mut row_sum = 0.0;
+            for x in 0..self.width {
+                row_sum += self.values[y * self.width + x] as f64;
+                sums[(y + 1) * stride + x + 1] = sums[y * stride + x + 1] + row_sum;
+            }
+        }
+
+        let window_sum = |x: usize, y: usize| {
+            let (x1, y1) = (x + window_width, y + window_height);
+            sums[y1 * stride + x1] - sums[y * stride + x1] - sums[y1 * stride + x]
+                + sums[y * stride + x]
+        };
+
+        let (mut best_x, mut best_y, mut best_sum) = (0, 0, f64::MIN);
+        for y in 0..=(self.height - window_height) {
+            for x in 0..=(self.width - window_width) {
+                let sum = window_sum(x, y);
+                if sum > best_sum {
+                    (best_x, best_y, best_sum) = (x, y, sum);
+                }
+            }
+        }
+
+        let min = Pos2::new(
+            (best_x as f32 / self.width as f32).clamp(0.0, 1.0 - size.x),
+            (best_y as f32 / self.height as f32).clamp(0.0, 1.0 - size.y),
+        );
+
+        Rect::from_min_size(min, size)
+    }
+}
+
+/// Saliency maps of photos made in the background so the UI never waits on one. A photo without
+/// a map has no suggestion yet, asking for it starts making the map.
+pub struct SaliencyCache {
+    maps: HashMap<PathBuf, SaliencyEntry>,
+}
+
+enum SaliencyEntry {
+    Making,
+    // The stamp of the thumbnail the map was made from, a new thumbnail means a new map
+    Made {
+        stamp: Option<FileStamp>,
+        map: Arc<SaliencyMap>,
+    },
+    Failed {
+        at: Instant,
+    },
+}
+
+impl SaliencyCache {
+    pub fn new() -> Self {
+        Self {
+            maps: HashMap::new(),
+        }
+    }
+
+    /// The photo's map if it's been made from its current thumbnail, otherwise starts making it
+    pub fn map(&mut self, photo: &Photo) -> Option<Arc<SaliencyMap>> {
+        if let Some(SaliencyEntry::Made { stamp, map }) = self.maps.get(&photo.path) {
+            if *stamp == thumbnail_stamp(photo) {
+                return Some(map.clone());
+            }
+
+            self.maps.remove(&photo.path);
+        }
+
+        self.prepare(photo);
+        None
+    }
+
+    /// Whether the photo's map is being made in the background
+    pub fn is_making(&self, photo: &Photo) -> bool {
+        matches!(self.maps.get(&photo.path), Some(SaliencyEntry::Making))
+    }
+
+    /// Starts making the map of the photo in the background unless it's been made or started.
+    /// Photos whose map couldn't be made are tried again after a while.
+    pub fn prepare(&mut self, photo: &Photo) {
+        match self.maps.get(&photo.path) {
+            Some(SaliencyEntry::Making | SaliencyEntry::Made { .. }) => return,
+            Some(SaliencyEntry::Failed { at }) if at.elapsed() < RETRY_AFTER => return,
+            _ => {}
+        }
+
+        // Tried again later once the thumbnail is made
+        if !photo
+            .thumbnail_path()
+            .is_ok_and(|thumbnail_path| thumbnail_path.exists())
+        {
+            return;
+        }
+
+        self.maps.insert(photo.path.clone(), SaliencyEntry::Making);
+
+        let photo = photo.clone();
+        spawn_blocking(move || {
+            let stamp = thumbnail_stamp(&photo);
+            let entry = match SaliencyMap::for_photo(&photo) {
+                Ok(map) => SaliencyEntry::Made {
+                    stamp,
+                    map: Arc::new(map),
+                },
+                Err(err) => {
+                    error!(
+                        "Failed to make saliency map for {:?}: {:?}",
+                        photo.path, err
+                    );
+                    SaliencyEntry::Failed { at: Instant::now() }
+                }
+            };
+
+            Dependency::<SaliencyCache>::get().with_lock_mut(|cache| {
+                cache.maps.insert(photo.path, entry);
+            });
+        });
+    }
+}
+
+fn thumbnail_stamp(photo: &Photo) -> Option<FileStamp> {
+    let metadata = std::fs::metadata(photo.thumbnail_path().ok()?).ok()?;
+    FileStamp::from_metadata(&metadata)
+}
+
+fn distance(a: [f32; 3], b: [f32; 3]) -> f32 {
+    ((a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2) + (a[2] - b[2]).powi(2)).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{Rgb, RgbImage};
+
+    use super::*;
+
+    // A 4x4 map that's empty apart from the one cell at `x`, `y`
+    fn map_with_peak(x: usize, y: usize) -> SaliencyMap {
+        let mut values = vec![0.0; 16];
+        values[y * 4 + x] = 1.0;
+        SaliencyMap {
+            width: 4,
+            height: 4,
+            values,
+        }
+    }
+
+    #[test]
+    fn window_covers_the_most_salient_part() {
+        let window = map_with_peak(3, 0).best_window(Vec2::new(0.5, 0.5));
+        assert_eq!(
+            window,
+            Rect::from_min_size(Pos2::new(0.5, 0.0), Vec2::splat(0.5))
+        );
+
+        let window = map_with_peak(0, 3).best_window(Vec2::new(0.25, 1.0));
+        assert_eq!(
+            window,
+            Rect::from_min_size(Pos2::ZERO, Vec2::new(0.25, 1.0))
+        );
+    }
+
+    #[test]
+    fn window_keeps_its_size_and_stays_inside_the_photo() {
+        for (x, y) in [(0, 0), (3, 3), (1, 2)] {
+            let window = map_with_peak(x, y).best_window(Vec2::new(0.3, 0.6));
+            assert!((window.width() - 0.3).abs() < 1e-6);
+            assert!((window.height() - 0.6).abs() < 1e-6);
+            assert!(window.min.x >= 0.0 && window.min.y >= 0.0);
+            assert!(window.max.x <= 1.0 + 1e-6 && window.max.y <= 1.0 + 1e-6);
+        }
+    }
+
+    #[test]
+    fn oversized_windows_are_the_whole_photo() {
+        let window = map_with_peak(2, 2).best_window(Vec2::new(2.0, 1.5));
+        assert_eq!(window, Rect::from_min_size(Pos2::ZERO, Vec2::splat(1.0)));
+    }
+
+    #[test]
+    fn empty_maps_give_a_centered_window() {
+        let map = SaliencyMap {
+            width: 0,
+            height: 0,
+            values: Vec::new(),
+        };
+        assert_eq!(
+            map.best_window(Vec2::splat(0.5)),
+            Rect::from_center_size(Pos2::new(0.5, 0.5), Vec2::splat(0.5))
+        );
+    }
+
+    #[test]
+    fn subjects_stand_out_from_plain_backgrounds() {
+        // A red square in the top left of a grey photo
+        let image = RgbImage::from_fn(64, 64, |x, y| {
+            if x < 16 && y < 16 {
+                Rgb([220, 30, 30])
+            } else {
+                Rgb([120, 120, 120])
+            }
+        });
+
+        let window =
+            SaliencyMap::from_image(&DynamicImage::ImageRgb8(image)).best_window(Vec2::splat(0.25));
+        assert!(window.contains(Pos2::new(0.125, 0.125)));
+    }
+}
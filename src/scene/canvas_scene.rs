@@ -1,33 +1,58 @@
-use std::fmt::Display;
+use std::{collections::HashSet, fmt::Display, path::PathBuf, sync::Arc};
 
-use egui::{Id, Key, Ui, Vec2};
+use egui::{FontFamily, Id, Key, Ui, Vec2};
 use egui_tiles::UiResponse;
 use indexmap::{indexmap, IndexMap};
+use serde::{Deserialize, Serialize};
 
 use crate::{
+    auto_persisting::AutoPersisting,
+    book_text::BookTextChange,
+    book_theme::BookTheme,
+    config::{Config, DEFAULT_HISTORY_LENGTH, DEFAULT_HISTORY_MEMORY_LIMIT_MB},
     dependencies::{Dependency, Singleton, SingletonFor},
     export::{ExportTaskId, ExportTaskStatus, Exporter},
     history::{HistoricallyEqual, UndoRedoStack},
-    id::{next_page_id, LayerId, PageId},
-    model::{edit_state::EditablePage, page::Page},
+    id::{next_layer_id, next_page_id, LayerId, PageId},
+    modal::{basic::BasicModal, manager::ModalManager},
+    model::{canvas_path::PathPoint, edit_state::EditablePage, fill::Fill, page::Page},
+    panorama::PanoramaPlacement,
+    photo::Photo,
+    project_fonts,
+    project_settings::ProjectSettingsManager,
+    trash::{TrashEntry, TrashedItem},
     utils::{IdExt, RectExt},
     widget::{
         canvas::{Canvas, CanvasPhoto, CanvasState, MultiSelect},
         canvas_info::{
             layers::{Layer, LayerContent},
-            panel::CanvasInfo,
+            panel::{CanvasInfo, InfoPanelState},
             quick_layout::{QuickLayout, QuickLayoutState},
         },
+        comments::Comments,
         crop::CropResponse,
+        decorations::{Decorations, DecorationsResponse},
+        guide_manager::GuideManager,
         image_gallery::{ImageGallery, ImageGalleryResponse, ImageGalleryState},
-        pages::{Pages, PagesResponse, PagesState},
+        outline::Outline,
+        page_strip::{PageStrip, PAGE_STRIP_HEIGHT},
+        pages::{PageLayoutChoice, Pages, PagesResponse, PagesState},
+        photo_tray::PhotoTray,
+        review::ReviewState,
+        snippets::{Snippets, SnippetsResponse},
+        statistics::Statistics,
         templates::{Templates, TemplatesResponse, TemplatesState},
         transformable::{ResizeMode, TransformHandleMode, TransformableState},
+        trash::{Trash, TrashResponse},
+        zoom_bar::{ZoomBar, ZOOM_BAR_HEIGHT},
     },
 };
 
 use super::{
-    viewer_scene::ViewerScene, NavigationRequest, Navigator, Scene, SceneResponse,
+    template_editor_scene::TemplateEditorScene,
+    viewer_scene::ViewerScene,
+    workspace::{self, WorkspaceLayout, WorkspaceTiles},
+    NavigationRequest, Navigator, Scene, SceneId, SceneResponse, SceneState, SceneTransition,
     SceneTransition::Viewer,
 };
 
@@ -43,6 +68,8 @@ pub struct CanvasSceneState {
     templates_state: TemplatesState,
     export_task_id: Option<ExportTaskId>,
     crop_state: Option<CropState>,
+    pub review: ReviewState,
+    pub info_panel: InfoPanelState,
 }
 
 impl CanvasSceneState {
@@ -57,6 +84,8 @@ impl CanvasSceneState {
             templates_state: TemplatesState::new(),
             export_task_id: None,
             crop_state: None,
+            review: ReviewState::default(),
+            info_panel: InfoPanelState::default(),
         }
     }
 
@@ -70,6 +99,8 @@ impl CanvasSceneState {
             templates_state: TemplatesState::new(),
             export_task_id: None,
             crop_state: None,
+            review: ReviewState::default(),
+            info_panel: InfoPanelState::default(),
         }
     }
 
@@ -98,12 +129,302 @@ impl CanvasSceneState {
         (&mut *page, &mut self.history_manager)
     }
 
+    // Saves a step for a change that went over several pages, `before` being the pages as they
+    // were before it, so undoing it restores all of them
+    fn save_book_history(
+        &mut self,
+        kind: CanvasHistoryKind,
+        before: &IndexMap<PageId, CanvasState>,
+    ) {
+        self.history_manager.save_book_history(
+            kind,
+            self.pages_state.selected_page,
+            before,
+            &self.pages_state.pages,
+        );
+    }
+
+    /// Brings back the other pages changed by the book wide steps the last undo or redo went over
+    pub fn restore_pending_pages(&mut self) {
+        for (page_id, history) in self.history_manager.take_pending_pages() {
+            if let Some(page) = self.pages_state.pages.get_mut(&page_id) {
+                CanvasHistoryManager::apply_history(history, page);
+            }
+        }
+
+        if let Some(order) = self.history_manager.take_pending_order() {
+            self.pages_state.restore_page_order(&order);
+        }
+    }
+
+    pub fn history_metrics(&self) -> HistoryMetrics {
+        self.history_manager.metrics()
+    }
+
     pub fn has_pages(&self) -> bool {
         !self.pages_state.pages.is_empty()
     }
+
+    /// Adds `pages` after the existing pages and selects the first of them.
+    /// A lone blank page, like the one new projects start with, is replaced.
+    pub fn append_pages(&mut self, pages: Vec<CanvasState>) {
+        if pages.is_empty() {
+            return;
+        }
+
+        if self.pages_state.pages.len() == 1 && self.selected_page().layers.is_empty() {
+            self.pages_state.pages.clear();
+        }
+
+        let mut first_page_id = None;
+        for page in pages {
+            let page_id = next_page_id();
+            self.pages_state.pages.insert(page_id, page);
+            first_page_id.get_or_insert(page_id);
+        }
+
+        if let Some(page_id) = first_page_id {
+            self.pages_state.selected_page = page_id;
+        }
+    }
+
+    /// Adds pages showing the panorama and selects the first of them. A spread always starts
+    /// on a left page, so a blank page is added first when the book would otherwise put it on
+    /// a right page.
+    pub fn place_panorama(&mut self, photo: &Photo, placement: PanoramaPlacement) {
+        let mut pages = placement.pages(photo);
+        let placed_count = pages.len();
+
+        let replaces_blank_page =
+            self.pages_state.pages.len() == 1 && self.selected_page().layers.is_empty();
+        let first_index = if replaces_blank_page {
+            0
+        } else {
+            self.pages_state.pages.len()
+        };
+
+        if placement == PanoramaPlacement::FullSpread && first_index % 2 == 1 {
+            pages.insert(0, CanvasState::new());
+        }
+
+        self.append_pages(pages);
+
+        let first_placed = self.pages_state.pages.len() - placed_count;
+        if let Some((page_id, _)) = self.pages_state.pages.get_index(first_placed) {
+            self.pages_state.selected_page = *page_id;
+        }
+    }
+
+    pub fn apply_book_theme(&mut self, theme: &BookTheme) {
+        let before = self.pages_state.pages.clone();
+        for page in self.pages_state.pages.values_mut() {
+            theme.apply_to_page(page);
+        }
+
+        self.save_book_history(CanvasHistoryKind::Theme, &before);
+    }
+
+    /// Sets the text in each missing font of `replacements` in its replacement, the default font
+    /// when there isn't one
+    pub fn replace_fonts(&mut self, replacements: &[(String, Option<String>)]) {
+        let before = self.pages_state.pages.clone();
+        let mut replaced = 0;
+        for (family, replacement) in replacements {
+            let replacement = match replacement {
+                Some(name) => FontFamily::Name(Arc::from(name.as_str())),
+                None => FontFamily::Proportional,
+            };
+            replaced += project_fonts::substitute(
+                self.pages_state.pages.values_mut(),
+                family,
+                &replacement,
+            );
+        }
+
+        if replaced > 0 {
+            self.save_book_history(CanvasHistoryKind::ReplaceFont, &before);
+        }
+    }
+
+    /// Replaces the text of the layers in `changes`. Layers edited since the changes were
+    /// previewed are left alone. Returns how many layers changed.
+    pub fn apply_text_changes(&mut self, changes: &[BookTextChange]) -> usize {
+        let before = self.pages_state.pages.clone();
+        let mut applied = 0;
+
+        for change in changes {
+            let Some(layer) = self
+                .pages_state
+                .pages
+                .get_mut(&change.page_id)
+                .and_then(|page| page.layers.get_mut(&change.layer_id))
+            else {
+                continue;
+            };
+
+            if let LayerContent::Text(text) | LayerContent::TemplateText { text, .. } =
+                &mut layer.content
+            {
+                if text.text == change.before {
+                    text.text = change.after.clone();
+                    applied += 1;
+                }
+            }
+        }
+
+        if applied > 0 {
+            self.save_book_history(CanvasHistoryKind::ImportText, &before);
+        }
+
+        applied
+    }
+
+    /// Selects the page at `page_index` with only `layer_id` selected on it and pans to the layer.
+    /// Returns false if the page or the layer is no longer there.
+    pub fn show_layer(&mut self, page_index: usize, layer_id: LayerId) -> bool {
+        let Some((page_id, page)) = self.pages_state.pages.get_index_mut(page_index) else {
+            return false;
+        };
+        if !page.layers.contains_key(&layer_id) {
+            return false;
+        }
+
+        for layer in page.layers.values_mut() {
+            layer.selected = layer.id == layer_id;
+        }
+        page.center_on_layer(layer_id);
+
+        self.pages_state.selected_page = *page_id;
+        self.pages_state.selected_pages.clear();
+
+        let (page, history_manager) = self.selected_page_and_history_mut();
+        history_manager.save_history(CanvasHistoryKind::SelectLayer, page);
+
+        true
+    }
+
+    /// Restyles the text layers on every page that follow the named style
+    pub fn apply_text_style(&mut self, style_name: &str) {
+        let (style, theme) = Dependency::<ProjectSettingsManager>::get().with_lock(|manager| {
+            (
+                manager.project_settings.text_style(style_name).cloned(),
+                manager.project_settings.theme.clone(),
+            )
+        });
+        let Some(style) = style else {
+            return;
+        };
+
+        let before = self.pages_state.pages.clone();
+        for page in self.pages_state.pages.values_mut() {
+            style.apply_to_page(page, &theme);
+        }
+
+        self.save_book_history(CanvasHistoryKind::TextStyle, &before);
+    }
+
+    /// Lays out the photos of page `to` like those of page `from`. The target page is selected
+    /// so the change can be undone.
+    pub fn apply_layout(&mut self, from: PageId, to: PageId) {
+        let Some(source) = self.pages_state.pages.get(&from).cloned() else {
+            return;
+        };
+        let Some(target) = self.pages_state.pages.get_mut(&to) else {
+            return;
+        };
+
+        if target.apply_layout_from(&source) {
+            self.pages_state.selected_page = to;
+            let (page, history_manager) = self.selected_page_and_history_mut();
+            history_manager.save_history(CanvasHistoryKind::ApplyLayout, page);
+        }
+    }
+
+    /// Reflows the photos of each of `pages` with `layout`, quick layouts are made for each page's
+    /// photo count. Saved as one step that undoes every page at once. Returns the numbers of the
+    /// pages that couldn't be reflowed, which have no photos or too few for the layout.
+    pub fn lay_out_pages(&mut self, pages: &[PageId], layout: &PageLayoutChoice) -> Vec<usize> {
+        let before = self.pages_state.pages.clone();
+        let mut skipped = vec![];
+        let mut changed = false;
+
+        for page_id in pages {
+            let Some(index) = self.pages_state.pages.get_index_of(page_id) else {
+                continue;
+            };
+            let page = &mut self.pages_state.pages[index];
+
+            match layout {
+                PageLayoutChoice::Quick(layout) => {
+                    page.update_quick_layout_order();
+                    match layout.for_count(page.quick_layout_order.len()) {
+                        Some(layout) => {
+                            layout.apply(page);
+                            page.last_quick_layout = Some(layout);
+                            changed = true;
+                        }
+                        None => skipped.push(index + 1),
+                    }
+                }
+                PageLayoutChoice::Template(template) => {
+                    if page.has_layout_photos() {
+                        changed |= page.apply_template_layout(template);
+                    } else {
+                        skipped.push(index + 1);
+                    }
+                }
+            }
+        }
+
+        if changed {
+            self.save_book_history(CanvasHistoryKind::LayOutPages, &before);
+        }
+
+        skipped
+    }
+
+    /// Points every layer showing one of the missing paths at the photo it was relinked to
+    pub fn relink_photos(&mut self, relinks: &[(PathBuf, Photo)]) {
+        for page in self.pages_state.pages.values_mut() {
+            for layer in page.layers.values_mut() {
+                if let LayerContent::Photo(canvas_photo)
+                | LayerContent::TemplatePhoto {
+                    photo: Some(canvas_photo),
+                    ..
+                } = &mut layer.content
+                {
+                    if let Some((_, photo)) = relinks
+                        .iter()
+                        .find(|(missing_path, _)| *missing_path == canvas_photo.photo.path)
+                    {
+                        // Photos only placed on pages keep the orientation picked for them
+                        let orientation_override = canvas_photo.photo.metadata.orientation_override;
+                        canvas_photo.photo = photo.clone();
+                        canvas_photo.photo.metadata.orientation_override =
+                            photo.metadata.orientation_override.or(orientation_override);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Gives every placed copy of `photos` their orientation override, as one step in the history
+    pub fn update_photo_orientations(&mut self, photos: &[Photo]) {
+        let before = self.pages_state.pages.clone();
+
+        for page in self.pages_state.pages.values_mut() {
+            for photo in photos {
+                page.update_photo_orientation(photo);
+            }
+        }
+
+        if self.pages_state.pages != before {
+            self.save_book_history(CanvasHistoryKind::PhotoOrientation, &before);
+        }
+    }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum CanvasScenePane {
     Gallery,
     Canvas,
@@ -111,47 +432,55 @@ pub enum CanvasScenePane {
     Pages,
     Templates,
     QuickLayout,
+    Trash,
+    Assets,
+    Comments,
+    Guides,
+    Tray,
+    Outline,
+    Statistics,
+    Snippets,
+}
+
+impl CanvasScenePane {
+    pub fn title(&self) -> &'static str {
+        match self {
+            CanvasScenePane::Gallery => "Gallery",
+            CanvasScenePane::Canvas => "Canvas",
+            CanvasScenePane::Info => "Info",
+            CanvasScenePane::Pages => "Pages",
+            CanvasScenePane::Templates => "Templates",
+            CanvasScenePane::QuickLayout => "Quick Layout",
+            CanvasScenePane::Trash => "Trash",
+            CanvasScenePane::Assets => "Assets",
+            CanvasScenePane::Comments => "Comments",
+            CanvasScenePane::Guides => "Guides",
+            CanvasScenePane::Tray => "Tray",
+            CanvasScenePane::Outline => "Outline",
+            CanvasScenePane::Statistics => "Statistics",
+            CanvasScenePane::Snippets => "Snippets",
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct CanvasScene {
     pub state: CanvasSceneState,
     pub tree: egui_tiles::Tree<CanvasScenePane>,
+    workspace_layout: WorkspaceLayout,
+    workspace_tiles: WorkspaceTiles,
 }
 
 impl CanvasScene {
     pub fn new() -> Self {
-        let mut tiles = egui_tiles::Tiles::default();
-
-        let left_tabs = vec![
-            tiles.insert_pane(CanvasScenePane::Gallery),
-            tiles.insert_pane(CanvasScenePane::Pages),
-            tiles.insert_pane(CanvasScenePane::Templates),
-        ];
-
-        let left_tabs_ids = tiles.insert_tab_tile(left_tabs);
-        let canvas_id = tiles.insert_pane(CanvasScenePane::Canvas);
-
-        let right_tabs = vec![
-            tiles.insert_pane(CanvasScenePane::Info),
-            tiles.insert_pane(CanvasScenePane::QuickLayout),
-        ];
-        let right_tabs_id = tiles.insert_tab_tile(right_tabs);
-
-        let children = vec![left_tabs_ids, canvas_id, right_tabs_id];
-
-        let mut linear_layout =
-            egui_tiles::Linear::new(egui_tiles::LinearDir::Horizontal, children);
-        linear_layout.shares.set_share(left_tabs_ids, 0.2);
-        linear_layout.shares.set_share(right_tabs_id, 0.2);
+        let workspace_layout = WorkspaceLayout::default();
+        let (tree, workspace_tiles) = workspace::build_tree(&workspace_layout);
 
         Self {
             state: CanvasSceneState::new(),
-            tree: egui_tiles::Tree::new(
-                "canvas_scene_tree",
-                tiles.insert_container(linear_layout),
-                tiles,
-            ),
+            tree,
+            workspace_layout,
+            workspace_tiles,
         }
     }
 
@@ -161,6 +490,17 @@ impl CanvasScene {
         res
     }
 
+    pub fn workspace_layout(&self) -> WorkspaceLayout {
+        workspace::capture_layout(&self.tree, &self.workspace_tiles, &self.workspace_layout)
+    }
+
+    pub fn apply_workspace_layout(&mut self, layout: WorkspaceLayout) {
+        let (tree, workspace_tiles) = workspace::build_tree(&layout);
+        self.tree = tree;
+        self.workspace_tiles = workspace_tiles;
+        self.workspace_layout = layout;
+    }
+
     // fn enter_crop_mode(&mut self, layer_id: LayerId) {
     //     let page = self.state.selected_page();
 
@@ -200,6 +540,12 @@ impl Scene for CanvasScene {
     fn ui(&mut self, ui: &mut egui::Ui) -> SceneResponse {
         // Remove the sync code since we're working directly with the selected page
 
+        // Pages may have been added, moved or loaded since the last frame
+        self.state.pages_state.update_sections();
+        self.state
+            .gallery_state
+            .update_placements(&self.state.pages_state);
+
         match self.state.export_task_id {
             Some(task_id) => {
                 let exporter: Singleton<Exporter> = Dependency::get();
@@ -213,6 +559,10 @@ impl Scene for CanvasScene {
                     Some(ExportTaskStatus::InProgress(progress)) => {
                         log::info!("Exporting... {:.0}%", progress * 100.0);
                     }
+                    Some(ExportTaskStatus::Cancelled) => {
+                        log::info!("Export cancelled");
+                        self.state.export_task_id = None;
+                    }
                     Some(ExportTaskStatus::Completed) | None => {
                         log::info!("Export Complete");
                         self.state.export_task_id = None;
@@ -226,8 +576,11 @@ impl Scene for CanvasScene {
                         exporter.export(
                             ui.ctx().clone(),
                             self.state.pages_state.pages.values().cloned().collect(),
+                            (0..self.state.pages_state.pages.len()).collect(),
                             "export".into(),
                             "out",
+                            None,
+                            None,
                         )
                     }));
                 }
@@ -244,12 +597,86 @@ impl Scene for CanvasScene {
             ui,
         );
 
+        // Undoing a step that changed several pages only restored the selected one in the canvas
+        self.state.restore_pending_pages();
+
         match navigator.process_pending_request() {
             Some(NavigationRequest::Push(scene_state)) => SceneResponse::Push(scene_state),
             Some(NavigationRequest::Pop) => SceneResponse::Pop,
             None => SceneResponse::None,
         }
     }
+
+    fn id(&self) -> SceneId {
+        SceneId::Canvas
+    }
+
+    // How each page was being looked at and the workspace, the pages themselves belong to the
+    // project
+    fn into_state(self: Box<Self>) -> Option<SceneState> {
+        Some(SceneState::Canvas {
+            selected_page: self.state.pages_state.selected_page,
+            views: self
+                .state
+                .pages_state
+                .pages
+                .iter()
+                .map(|(page_id, page)| (*page_id, PageView::new(page)))
+                .collect(),
+            workspace_layout: self.workspace_layout(),
+        })
+    }
+
+    fn restore(&mut self, state: SceneState) {
+        if let SceneState::Canvas {
+            selected_page,
+            views,
+            workspace_layout,
+        } = state
+        {
+            self.apply_workspace_layout(workspace_layout);
+
+            let pages_state = &mut self.state.pages_state;
+            for (page_id, page) in pages_state.pages.iter_mut() {
+                if let Some(view) = views.get(page_id) {
+                    view.apply(page);
+                }
+            }
+            if pages_state.pages.contains_key(&selected_page) {
+                pages_state.selected_page = selected_page;
+            }
+        }
+    }
+}
+
+/// Where a page was zoomed and scrolled to and which of its layers were selected
+pub struct PageView {
+    zoom: f32,
+    offset: Vec2,
+    selected_layers: HashSet<LayerId>,
+}
+
+impl PageView {
+    fn new(page: &CanvasState) -> Self {
+        Self {
+            zoom: page.zoom,
+            offset: page.offset,
+            selected_layers: page
+                .layers
+                .values()
+                .filter(|layer| layer.selected)
+                .map(|layer| layer.id)
+                .collect(),
+        }
+    }
+
+    fn apply(&self, page: &mut CanvasState) {
+        page.set_zoom(self.zoom);
+        page.offset = self.offset;
+        for layer in page.layers.values_mut() {
+            layer.selected = self.selected_layers.contains(&layer.id);
+        }
+    }
 }
 
 struct ViewerTreeBehavior<'a> {
@@ -257,6 +684,61 @@ struct ViewerTreeBehavior<'a> {
     navigator: &'a mut Navigator,
 }
 
+impl<'a> ViewerTreeBehavior<'a> {
+    fn restore_from_trash(&mut self, entry: TrashEntry) {
+        match entry.item {
+            TrashedItem::Page(page) => {
+                let page_id = next_page_id();
+                self.scene_state.pages_state.pages.insert(page_id, page);
+                self.scene_state.pages_state.selected_page = page_id;
+            }
+            TrashedItem::Layer {
+                mut layer,
+                canvas_id,
+                ..
+            } => {
+                // Back on the page it was deleted from if that's still there
+                let deleted_from = canvas_id.and_then(|canvas_id| {
+                    self.scene_state
+                        .pages_state
+                        .pages
+                        .iter()
+                        .find(|(_, page)| page.canvas_id == canvas_id)
+                        .map(|(page_id, _)| *page_id)
+                });
+                if let Some(page_id) = deleted_from {
+                    self.scene_state.pages_state.selected_page = page_id;
+                }
+
+                if !self.scene_state.has_pages() {
+                    let page_id = next_page_id();
+                    self.scene_state
+                        .pages_state
+                        .pages
+                        .insert(page_id, CanvasState::new());
+                    self.scene_state.pages_state.selected_page = page_id;
+                }
+
+                let page = self.scene_state.selected_page_mut();
+
+                // The layer may have been brought back by an undo since it was deleted
+                if page.layers.contains_key(&layer.id) {
+                    layer.id = next_layer_id();
+                }
+                layer.transform_state.id = Id::random();
+
+                page.layers.insert(layer.id, layer);
+                page.update_quick_layout_order();
+
+                let page_snapshot = self.scene_state.selected_page().clone();
+                self.scene_state
+                    .history_manager
+                    .save_history(CanvasHistoryKind::RestoreLayer, &page_snapshot);
+            }
+        }
+    }
+}
+
 impl<'a> egui_tiles::Behavior<CanvasScenePane> for ViewerTreeBehavior<'a> {
     fn pane_ui(
         &mut self,
@@ -274,11 +756,19 @@ impl<'a> egui_tiles::Behavior<CanvasScenePane> for ViewerTreeBehavior<'a> {
                         ImageGalleryResponse::SelectPhotoSecondaryAction(photo) => {
                             self.navigator.push(Viewer(ViewerScene::new(photo.clone())));
                         }
+                        ImageGalleryResponse::PlacePanorama(photo, placement) => {
+                            self.scene_state.place_panorama(&photo, placement);
+                        }
+                        ImageGalleryResponse::GoToPlacement(placement) => {
+                            self.scene_state
+                                .show_layer(placement.page_index, placement.layer);
+                        }
                         ImageGalleryResponse::SelectPhotoPrimaryAction(photo) => {
                             let is_template = self.scene_state.selected_page().template.is_some();
 
                             if is_template {
                                 let page = self.scene_state.selected_page_mut();
+                                let previous_autofill = page.template_autofill();
                                 let mut selected_template_photos: Vec<_> = page
                                     .layers
                                     .iter_mut()
@@ -292,11 +782,14 @@ impl<'a> egui_tiles::Behavior<CanvasScenePane> for ViewerTreeBehavior<'a> {
                                     if let LayerContent::TemplatePhoto {
                                         region: _,
                                         photo: canvas_photo,
-                                        scale_mode: _,
+                                        ..
                                     } = &mut selected_template_photos[0].1.content
                                     {
                                         *canvas_photo = Some(CanvasPhoto::new(photo.clone()));
                                     }
+                                    self.scene_state
+                                        .selected_page_mut()
+                                        .apply_template_autofill(&previous_autofill, false);
                                     // Create a snapshot of the state after modification
                                     let page_snapshot = self.scene_state.selected_page().clone();
                                     self.scene_state
@@ -325,13 +818,77 @@ impl<'a> egui_tiles::Behavior<CanvasScenePane> for ViewerTreeBehavior<'a> {
                     return UiResponse::None;
                 }
 
-                let rect = ui.available_rect_before_wrap();
+                let mut rect = ui.available_rect_before_wrap();
                 let mut crop_state: Option<CropState> = self.scene_state.crop_state.clone();
 
+                // Page navigation is left alone while cropping or typing into a text field
+                if crop_state.is_none() && !ui.ctx().wants_keyboard_input() {
+                    let offset = ui.ctx().input(|input| {
+                        if input.key_pressed(Key::PageDown)
+                            || (input.modifiers.ctrl
+                                && (input.key_pressed(Key::ArrowRight)
+                                    || input.key_pressed(Key::ArrowDown)))
+                        {
+                            1
+                        } else if input.key_pressed(Key::PageUp)
+                            || (input.modifiers.ctrl
+                                && (input.key_pressed(Key::ArrowLeft)
+                                    || input.key_pressed(Key::ArrowUp)))
+                        {
+                            -1
+                        } else {
+                            0
+                        }
+                    });
+
+                    if offset != 0 {
+                        self.scene_state.pages_state.select_relative(offset);
+                    }
+                }
+
+                let show_page_strip =
+                    Dependency::<AutoPersisting<Config>>::get().with_lock_mut(|config| {
+                        config
+                            .read()
+                            .map(|config| config.page_strip())
+                            .unwrap_or(true)
+                    });
+
+                let strip_rect = (show_page_strip && self.scene_state.pages_state.pages.len() > 1)
+                    .then(|| {
+                        let mut strip_rect = rect;
+                        strip_rect.min.y = rect.max.y - PAGE_STRIP_HEIGHT;
+                        rect.max.y = strip_rect.min.y;
+                        strip_rect
+                    });
+
+                // The crop has its own view of the photo, the zoom bar only zooms the page
+                let zoom_bar_rect = crop_state.is_none().then(|| {
+                    let mut zoom_bar_rect = rect;
+                    zoom_bar_rect.max.y = rect.min.y + ZOOM_BAR_HEIGHT;
+                    rect.min.y = zoom_bar_rect.max.y;
+                    zoom_bar_rect
+                });
+
+                if let Some(zoom_bar_rect) = zoom_bar_rect {
+                    ZoomBar::new(self.scene_state.selected_page_mut(), rect)
+                        .show(ui, zoom_bar_rect);
+                }
+
+                let mut review = self.scene_state.review.clone();
+                let placed_photos =
+                    if Canvas::is_photo_picker_open(ui.ctx(), self.scene_state.selected_page()) {
+                        self.scene_state.pages_state.placed_photos()
+                    } else {
+                        HashSet::new()
+                    };
                 let (page, history) = self.scene_state.selected_page_and_history_mut();
 
-                // Handle crop mode if active
-                if let Some(ref mut crop_state) = crop_state {
+                if review.enabled {
+                    Canvas::new(page, rect, history).show_review(ui, &mut review);
+                    self.scene_state.review = review;
+                } else if let Some(ref mut crop_state) = crop_state {
+                    // Handle crop mode if active
                     let (page, history) = self.scene_state.selected_page_and_history_mut();
                     if let CropResponse::Exit = Crop::new(page, rect, history, crop_state).show(ui)
                     {
@@ -340,22 +897,29 @@ impl<'a> egui_tiles::Behavior<CanvasScenePane> for ViewerTreeBehavior<'a> {
                         self.scene_state.crop_state = Some(crop_state.clone());
                     }
                 } else {
-                    match Canvas::new(page, rect, history).show(ui) {
+                    match Canvas::new(page, rect, history)
+                        .with_placed_photos(placed_photos)
+                        .show(ui)
+                    {
                         Some(CanvasResponse::EnterCropMode {
                             target_layer,
                             photo,
+                            suggest,
                         }) => {
                             let padded_available_rect = ui
                                 .max_rect()
                                 .shrink2(Vec2::new(rect.width() * 0.1, rect.height() * 0.1));
 
-                            let mut photo_rect =
-                                padded_available_rect.with_aspect_ratio(photo.photo.aspect_ratio());
+                            let oriented_size = photo.oriented_size();
+                            let mut photo_rect = padded_available_rect
+                                .with_aspect_ratio(oriented_size.x / oriented_size.y);
 
                             photo_rect = photo_rect.fit_and_center_within(padded_available_rect);
 
+                            // Start from the photo's current crop so recropping continues where the
+                            // last crop session left off
                             let transform_rect =
-                                photo_rect.translate_top_to(0.0).translate_left_to(0.0);
+                                Crop::crop_to_local_rect(photo.display_crop(), photo_rect);
 
                             let crop_transform_state = TransformableState {
                                 rect: transform_rect,
@@ -372,6 +936,9 @@ impl<'a> egui_tiles::Behavior<CanvasScenePane> for ViewerTreeBehavior<'a> {
                                 target_layer,
                                 transform_state: crop_transform_state,
                                 photo_rect: photo_rect,
+                                preset_name: String::new(),
+                                spiral_flip: [false; 2],
+                                suggesting: suggest,
                             });
                         }
                         Some(CanvasResponse::Exit) => {
@@ -380,6 +947,11 @@ impl<'a> egui_tiles::Behavior<CanvasScenePane> for ViewerTreeBehavior<'a> {
                         None => {}
                     }
                 }
+
+                // Drawn after the canvas so zoomed in pages don't paint over it
+                if let Some(strip_rect) = strip_rect {
+                    PageStrip::new(&mut self.scene_state.pages_state).show(ui, strip_rect);
+                }
             }
             CanvasScenePane::Info => {
                 ui.painter()
@@ -392,12 +964,18 @@ impl<'a> egui_tiles::Behavior<CanvasScenePane> for ViewerTreeBehavior<'a> {
                     return UiResponse::None;
                 }
 
-                let (page, history) = self.scene_state.selected_page_and_history_mut();
+                let scene_state = &mut self.scene_state;
                 let response: egui::InnerResponse<
                     crate::widget::canvas_info::panel::CanvasInfoResponse,
                 > = CanvasInfo {
-                    canvas_state: page,
-                    history_manager: history,
+                    page_id: scene_state.pages_state.selected_page,
+                    canvas_state: scene_state
+                        .pages_state
+                        .pages
+                        .get_mut(&scene_state.pages_state.selected_page)
+                        .unwrap(),
+                    history_manager: &mut scene_state.history_manager,
+                    panel_state: &mut scene_state.info_panel,
                 }
                 .show(ui);
 
@@ -407,6 +985,10 @@ impl<'a> egui_tiles::Behavior<CanvasScenePane> for ViewerTreeBehavior<'a> {
                         .history_manager
                         .save_history(history_kind, &page_snapshot);
                 }
+
+                if let Some(style_name) = response.inner.restyle {
+                    self.scene_state.apply_text_style(&style_name);
+                }
             }
             CanvasScenePane::Pages => {
                 ui.painter()
@@ -416,6 +998,28 @@ impl<'a> egui_tiles::Behavior<CanvasScenePane> for ViewerTreeBehavior<'a> {
                     PagesResponse::SelectPage => {
                         // No need to sync canvas_state anymore
                     }
+                    PagesResponse::ApplyLayout { from, to } => {
+                        self.scene_state.apply_layout(from, to);
+                    }
+                    PagesResponse::LayOutPages { pages, layout } => {
+                        let skipped = self.scene_state.lay_out_pages(&pages, &layout);
+                        if !skipped.is_empty() {
+                            let numbers = skipped
+                                .iter()
+                                .map(|number| number.to_string())
+                                .collect::<Vec<_>>()
+                                .join(", ");
+                            ModalManager::push(BasicModal::new(
+                                "Some Pages Weren't Laid Out",
+                                format!(
+                                    "{} {} couldn't be reflowed. Pages need photos, and enough of them for the layout.",
+                                    if skipped.len() == 1 { "Page" } else { "Pages" },
+                                    numbers
+                                ),
+                                "OK",
+                            ));
+                        }
+                    }
                     PagesResponse::None => {}
                 }
             }
@@ -435,6 +1039,15 @@ impl<'a> egui_tiles::Behavior<CanvasScenePane> for ViewerTreeBehavior<'a> {
 
                         self.scene_state.pages_state.selected_page = new_page_id;
                     }
+                    TemplatesResponse::NewTemplate => {
+                        self.navigator
+                            .push(SceneTransition::TemplateEditor(TemplateEditorScene::new()));
+                    }
+                    TemplatesResponse::EditTemplate(template) => {
+                        self.navigator.push(SceneTransition::TemplateEditor(
+                            TemplateEditorScene::edit(template),
+                        ));
+                    }
                     TemplatesResponse::None => {}
                 }
             }
@@ -449,20 +1062,93 @@ impl<'a> egui_tiles::Behavior<CanvasScenePane> for ViewerTreeBehavior<'a> {
                 let (page, history) = self.scene_state.selected_page_and_history_mut();
                 QuickLayout::new(&mut QuickLayoutState::new(page, history)).show(ui);
             }
+            CanvasScenePane::Trash => {
+                ui.painter()
+                    .rect_filled(ui.max_rect(), 0.0, ui.style().visuals.panel_fill);
+
+                if let TrashResponse::Restore(entry) = Trash::show(ui) {
+                    self.restore_from_trash(entry);
+                }
+            }
+            CanvasScenePane::Comments => {
+                ui.painter()
+                    .rect_filled(ui.max_rect(), 0.0, ui.style().visuals.panel_fill);
+
+                Comments::new(
+                    &mut self.scene_state.pages_state,
+                    &mut self.scene_state.review,
+                )
+                .show(ui);
+            }
+            CanvasScenePane::Guides => {
+                ui.painter()
+                    .rect_filled(ui.max_rect(), 0.0, ui.style().visuals.panel_fill);
+
+                if self.scene_state.has_pages() {
+                    GuideManager::new(self.scene_state.selected_page_mut()).show(ui);
+                }
+            }
+            CanvasScenePane::Outline => {
+                ui.painter()
+                    .rect_filled(ui.max_rect(), 0.0, ui.style().visuals.panel_fill);
+
+                Outline::new(
+                    &mut self.scene_state.pages_state,
+                    &mut self.scene_state.history_manager,
+                )
+                .show(ui);
+            }
+            CanvasScenePane::Statistics => {
+                ui.painter()
+                    .rect_filled(ui.max_rect(), 0.0, ui.style().visuals.panel_fill);
+
+                Statistics::new(&self.scene_state.pages_state).show(ui);
+            }
+            CanvasScenePane::Tray => {
+                ui.painter()
+                    .rect_filled(ui.max_rect(), 0.0, ui.style().visuals.panel_fill);
+
+                let usage = self.scene_state.pages_state.photo_usage();
+                PhotoTray::new(&usage, &self.scene_state.gallery_state.selected_images).show(ui);
+            }
+            CanvasScenePane::Assets => {
+                ui.painter()
+                    .rect_filled(ui.max_rect(), 0.0, ui.style().visuals.panel_fill);
+
+                if let DecorationsResponse::Place(decoration) = Decorations::show(ui) {
+                    if self.scene_state.has_pages() {
+                        self.scene_state
+                            .selected_page_mut()
+                            .add_decoration(decoration);
+                        let page_snapshot = self.scene_state.selected_page().clone();
+                        self.scene_state
+                            .history_manager
+                            .save_history(CanvasHistoryKind::AddDecoration, &page_snapshot);
+                    }
+                }
+            }
+            CanvasScenePane::Snippets => {
+                ui.painter()
+                    .rect_filled(ui.max_rect(), 0.0, ui.style().visuals.panel_fill);
+
+                let selected_page = self
+                    .scene_state
+                    .has_pages()
+                    .then(|| self.scene_state.selected_page());
+                if let SnippetsResponse::Add(snippet) = Snippets::new(selected_page).show(ui) {
+                    let (page, history_manager) = self.scene_state.selected_page_and_history_mut();
+                    let center = (page.page.size_pixels() / 2.0).to_pos2();
+                    page.add_snippet(&snippet, center);
+                    history_manager.save_history(CanvasHistoryKind::AddSnippet, page);
+                }
+            }
         }
 
         UiResponse::None
     }
 
     fn tab_title_for_pane(&mut self, pane: &CanvasScenePane) -> egui::WidgetText {
-        match pane {
-            CanvasScenePane::Gallery => "Gallery".into(),
-            CanvasScenePane::Canvas => "Canvas".into(),
-            CanvasScenePane::Info => "Info".into(),
-            CanvasScenePane::Pages => "Pages".into(),
-            CanvasScenePane::Templates => "Templates".into(),
-            CanvasScenePane::QuickLayout => "Quick Layout".into(),
-        }
+        pane.title().into()
     }
 }
 
@@ -477,12 +1163,45 @@ pub enum CanvasHistoryKind {
     SelectLayer,
     DeselectLayer,
     QuickLayout,
+    RestoreLayer,
+    AddDecoration,
+    Crop,
+    Theme,
+    Adjust,
+    Effects,
+    Version,
+    ScaleMode,
+    ReorderLayers,
+    RenameLayer,
+    PageSize,
+    RescaleLayers,
+    PinLayer,
+    Orient,
+    AddPath,
+    EditPath,
+    FillPage,
+    FitPage,
+    ApplyLayout,
+    EditText,
+    TextStyle,
+    AddCaption,
+    AutofillText,
+    ImportText,
+    Background,
+    ReplaceFont,
+    LayOutPages,
+    AddSnippet,
+    StartSection,
+    RenameSection,
+    RemoveSection,
+    MovePage,
+    PhotoOrientation,
 }
 
 impl Display for CanvasHistoryKind {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            CanvasHistoryKind::Transform => write!(f, "Move"),
+            CanvasHistoryKind::Transform => write!(f, "Transform"),
             CanvasHistoryKind::AddPhoto => write!(f, "Add Photo"),
             CanvasHistoryKind::DeletePhoto => write!(f, "Delete Photo"),
             CanvasHistoryKind::Select => write!(f, "Select"),
@@ -491,6 +1210,39 @@ impl Display for CanvasHistoryKind {
             CanvasHistoryKind::SelectLayer => write!(f, "Select Layer"),
             CanvasHistoryKind::DeselectLayer => write!(f, "Deselect Layer"),
             CanvasHistoryKind::QuickLayout => write!(f, "Quick Layout"),
+            CanvasHistoryKind::RestoreLayer => write!(f, "Restore Layer"),
+            CanvasHistoryKind::AddDecoration => write!(f, "Add Asset"),
+            CanvasHistoryKind::Crop => write!(f, "Crop"),
+            CanvasHistoryKind::Theme => write!(f, "Book Theme"),
+            CanvasHistoryKind::Adjust => write!(f, "Adjust Photo"),
+            CanvasHistoryKind::Effects => write!(f, "Photo Effects"),
+            CanvasHistoryKind::Version => write!(f, "Switch Version"),
+            CanvasHistoryKind::ScaleMode => write!(f, "Scale Mode"),
+            CanvasHistoryKind::ReorderLayers => write!(f, "Reorder Layers"),
+            CanvasHistoryKind::RenameLayer => write!(f, "Rename Layer"),
+            CanvasHistoryKind::PageSize => write!(f, "Page Size"),
+            CanvasHistoryKind::RescaleLayers => write!(f, "Rescale Layers"),
+            CanvasHistoryKind::PinLayer => write!(f, "Pin Layer"),
+            CanvasHistoryKind::Orient => write!(f, "Rotate or Flip"),
+            CanvasHistoryKind::AddPath => write!(f, "Add Path"),
+            CanvasHistoryKind::EditPath => write!(f, "Edit Path"),
+            CanvasHistoryKind::FillPage => write!(f, "Fill Page"),
+            CanvasHistoryKind::FitPage => write!(f, "Fit Page"),
+            CanvasHistoryKind::ApplyLayout => write!(f, "Apply Layout"),
+            CanvasHistoryKind::EditText => write!(f, "Edit Text"),
+            CanvasHistoryKind::TextStyle => write!(f, "Text Style"),
+            CanvasHistoryKind::AddCaption => write!(f, "Add Caption"),
+            CanvasHistoryKind::AutofillText => write!(f, "Fill Template Text"),
+            CanvasHistoryKind::ImportText => write!(f, "Import Text"),
+            CanvasHistoryKind::Background => write!(f, "Page Background"),
+            CanvasHistoryKind::ReplaceFont => write!(f, "Replace Font"),
+            CanvasHistoryKind::LayOutPages => write!(f, "Lay Out Pages"),
+            CanvasHistoryKind::AddSnippet => write!(f, "Add Snippet"),
+            CanvasHistoryKind::StartSection => write!(f, "Start Section"),
+            CanvasHistoryKind::RenameSection => write!(f, "Rename Section"),
+            CanvasHistoryKind::RemoveSection => write!(f, "Remove Section"),
+            CanvasHistoryKind::MovePage => write!(f, "Move Page"),
+            CanvasHistoryKind::PhotoOrientation => write!(f, "Photo Orientation"),
         }
     }
 }
@@ -502,22 +1254,109 @@ impl HistoricallyEqual for CanvasHistory {
                 .layers
                 .values()
                 .zip(other.layers.values())
-                .all(|(a, b)| a.historically_equal_to(b))
+                .all(|(a, b)| Arc::ptr_eq(a, b) || a.historically_equal_to(b))
             && self.page == other.page
             && self.multi_select == other.multi_select
+            && self.background == other.background
+            && self.section_start == other.section_start
+            && self.other_pages == other.other_pages
     }
 }
 
+/// A snapshot of a page for the undo history. Layers are shared with the previous snapshot when
+/// they haven't changed, so each entry only costs the layers its operation touched.
 #[derive(Debug, Clone, PartialEq)]
 pub struct CanvasHistory {
-    layers: IndexMap<LayerId, Layer>,
+    layers: IndexMap<LayerId, Arc<Layer>>,
     multi_select: Option<MultiSelect>,
     page: EditablePage,
+    background: Fill,
+    section_start: Option<String>,
+    other_pages: Option<Arc<OtherPagesChange>>,
+}
+
+/// How the pages other than the selected one looked before and after a step that changed them,
+/// for operations that go over the whole book
+#[derive(Debug, Clone, PartialEq)]
+pub struct OtherPagesChange {
+    before: IndexMap<PageId, CanvasHistory>,
+    after: IndexMap<PageId, CanvasHistory>,
+    // The order of the pages before and after, when the step moved them
+    order: Option<(Vec<PageId>, Vec<PageId>)>,
+}
+
+impl CanvasHistory {
+    fn new(canvas_state: &CanvasState, previous: Option<&CanvasHistory>) -> Self {
+        let layers = canvas_state
+            .layers
+            .iter()
+            .map(|(id, layer)| {
+                let shared = previous
+                    .and_then(|previous| previous.layers.get(id))
+                    .filter(|previous_layer| previous_layer.as_ref() == layer)
+                    .cloned();
+
+                (*id, shared.unwrap_or_else(|| Arc::new(layer.clone())))
+            })
+            .collect();
+
+        Self {
+            layers,
+            multi_select: canvas_state.multi_select.clone(),
+            page: canvas_state.page.clone(),
+            background: canvas_state.background.clone(),
+            section_start: canvas_state.section_start.clone(),
+            other_pages: None,
+        }
+    }
+
+    // Every layer the entry holds, including those of the other pages it changed
+    fn all_layers(&self) -> impl Iterator<Item = &Arc<Layer>> {
+        let other_pages = self.other_pages.iter().flat_map(|change| {
+            change
+                .before
+                .values()
+                .chain(change.after.values())
+                .flat_map(|page| page.layers.values())
+        });
+
+        self.layers.values().chain(other_pages)
+    }
+}
+
+// A rough estimate of the memory a layer holds, used to keep the history under its limit
+fn approximate_layer_size(layer: &Layer) -> usize {
+    let content_size = match &layer.content {
+        LayerContent::Photo(photo)
+        | LayerContent::TemplatePhoto {
+            photo: Some(photo), ..
+        } => {
+            photo.photo.path.as_os_str().len()
+                + photo.crop_history.len() * std::mem::size_of::<egui::Rect>()
+        }
+        LayerContent::Text(text) | LayerContent::TemplateText { text, .. } => text.text.len(),
+        LayerContent::Path(path) => path.points.len() * std::mem::size_of::<PathPoint>(),
+        LayerContent::TemplatePhoto { photo: None, .. } | LayerContent::Decoration(_) => 0,
+    };
+
+    std::mem::size_of::<Layer>() + layer.name.len() + content_size
+}
+
+/// The size of the selected page's undo history, for the diagnostics panel
+#[derive(Debug, Clone, Copy)]
+pub struct HistoryMetrics {
+    pub entries: usize,
+    pub bytes: usize,
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct CanvasHistoryManager {
     pub stack: UndoRedoStack<CanvasHistoryKind, CanvasHistory>,
+    // Other pages that undoing or redoing a book wide step has to restore, the scene applies
+    // them since the canvas only has the selected page
+    pending_pages: Vec<(PageId, CanvasHistory)>,
+    // The order the pages go back to, when the steps gone over moved them
+    pending_order: Option<Vec<PageId>>,
 }
 
 impl CanvasHistoryManager {
@@ -527,11 +1366,9 @@ impl CanvasHistoryManager {
 
     pub fn with_initial_state(state: CanvasState) -> Self {
         CanvasHistoryManager {
-            stack: UndoRedoStack::new(CanvasHistory {
-                layers: state.layers.clone(),
-                multi_select: state.multi_select.clone(),
-                page: state.page.clone(),
-            }),
+            stack: UndoRedoStack::new(CanvasHistory::new(&state, None)),
+            pending_pages: vec![],
+            pending_order: None,
         }
     }
 
@@ -540,35 +1377,207 @@ impl CanvasHistoryManager {
     }
 
     pub fn undo(&mut self, canvas_state: &mut CanvasState) {
+        let from = self.stack.index;
         let new_value = self.stack.undo();
-        self.apply_history(new_value, canvas_state);
+        self.queue_other_pages(from);
+        Self::apply_history(new_value, canvas_state);
     }
 
     pub fn redo(&mut self, canvas_state: &mut CanvasState) {
+        let from = self.stack.index;
         let new_value = self.stack.redo();
-        self.apply_history(new_value, canvas_state);
+        self.queue_other_pages(from);
+        Self::apply_history(new_value, canvas_state);
+    }
+
+    /// Saves a step that changed pages besides the selected one. `before` holds every page as it
+    /// was before the change and `pages` as they are now.
+    pub fn save_book_history(
+        &mut self,
+        kind: CanvasHistoryKind,
+        selected_page: PageId,
+        before: &IndexMap<PageId, CanvasState>,
+        pages: &IndexMap<PageId, CanvasState>,
+    ) {
+        let before_order: Vec<PageId> = before.keys().copied().collect();
+        let after_order: Vec<PageId> = pages.keys().copied().collect();
+        let mut change = OtherPagesChange {
+            before: IndexMap::new(),
+            after: IndexMap::new(),
+            order: (before_order != after_order).then_some((before_order, after_order)),
+        };
+
+        for (page_id, page) in pages {
+            let Some(previous) = before.get(page_id) else {
+                continue;
+            };
+            if *page_id == selected_page || previous == page {
+                continue;
+            }
+
+            let previous = CanvasHistory::new(previous, None);
+            let current = CanvasHistory::new(page, Some(&previous));
+            change.before.insert(*page_id, previous);
+            change.after.insert(*page_id, current);
+        }
+
+        let Some(selected) = pages.get(&selected_page) else {
+            return;
+        };
+
+        let mut history = CanvasHistory::new(selected, Some(self.current()));
+        if !change.after.is_empty() || change.order.is_some() {
+            history.other_pages = Some(Arc::new(change));
+        }
+
+        self.stack.save_history(kind, history);
+        self.enforce_limits();
+    }
+
+    /// Takes the other pages that the last undo, redo or jump restored
+    pub fn take_pending_pages(&mut self) -> Vec<(PageId, CanvasHistory)> {
+        std::mem::take(&mut self.pending_pages)
+    }
+
+    /// Takes the order of the pages that the last undo, redo or jump restored, if it moved them
+    pub fn take_pending_order(&mut self) -> Option<Vec<PageId>> {
+        self.pending_order.take()
+    }
+
+    // Queues the other pages changed by the steps passed over moving from the entry at `from` to
+    // the current one: undone steps restore how they were before, redone steps how they were after
+    fn queue_other_pages(&mut self, from: usize) {
+        let to = self.stack.index;
+
+        let (changes, orders): (Vec<_>, Vec<_>) = if to < from {
+            (to + 1..=from)
+                .rev()
+                .filter_map(|index| self.other_pages_at(index))
+                .map(|change| {
+                    let order = change.order.as_ref().map(|(before, _)| before.clone());
+                    (change.before.clone(), order)
+                })
+                .unzip()
+        } else {
+            (from + 1..=to)
+                .filter_map(|index| self.other_pages_at(index))
+                .map(|change| {
+                    let order = change.order.as_ref().map(|(_, after)| after.clone());
+                    (change.after.clone(), order)
+                })
+                .unzip()
+        };
+
+        self.pending_pages.extend(changes.into_iter().flatten());
+        // The last step gone over decides where the pages end up
+        if let Some(order) = orders.into_iter().flatten().last() {
+            self.pending_order = Some(order);
+        }
+    }
+
+    fn other_pages_at(&self, index: usize) -> Option<&OtherPagesChange> {
+        self.stack
+            .history
+            .get(index)
+            .and_then(|(_, history)| history.other_pages.as_deref())
     }
 
     pub fn save_history(&mut self, kind: CanvasHistoryKind, canvas_state: &CanvasState) {
-        self.stack.save_history(
-            kind,
-            CanvasHistory {
-                layers: canvas_state.layers.clone(),
-                multi_select: canvas_state.multi_select.clone(),
-                page: canvas_state.page.clone(),
-            },
-        );
+        let history = CanvasHistory::new(canvas_state, Some(self.current()));
+
+        // Typing is saved as it happens, keep it as one step instead of one per keystroke
+        if kind == CanvasHistoryKind::EditText && self.current_kind() == Some(&kind) {
+            self.stack.amend_current(history);
+        } else {
+            self.stack.save_history(kind, history);
+        }
+
+        self.enforce_limits();
     }
 
-    fn apply_history(&mut self, history: CanvasHistory, canvas_state: &mut CanvasState) {
-        canvas_state.layers = history.layers;
-        canvas_state.multi_select = history.multi_select;
-        canvas_state.page = history.page;
+    /// Restores the page to how it was after the step at `index`
+    pub fn jump_to(&mut self, index: usize, canvas_state: &mut CanvasState) {
+        let from = self.stack.index;
+        let new_value = self.stack.jump_to(index);
+        self.queue_other_pages(from);
+        Self::apply_history(new_value, canvas_state);
     }
 
-    pub fn apply_index(&mut self, index: usize, canvas_state: &mut CanvasState) {
-        let history = &self.stack.history[index];
-        self.apply_history(history.1.clone(), canvas_state);
+    // The kind of the latest step, if nothing has been undone since
+    fn current_kind(&self) -> Option<&CanvasHistoryKind> {
+        if self.stack.index + 1 != self.stack.history.len() {
+            return None;
+        }
+
+        self.stack.history.last().map(|(kind, _)| kind)
+    }
+
+    // The entry the page currently matches
+    fn current(&self) -> &CanvasHistory {
+        self.stack
+            .history
+            .get(self.stack.index)
+            .map(|(_, history)| history)
+            .unwrap_or(&self.stack.initial_value)
+    }
+
+    /// The approximate memory held by the history, counting shared layers once
+    pub fn approximate_memory(&self) -> usize {
+        let mut seen = HashSet::new();
+
+        std::iter::once(&self.stack.initial_value)
+            .chain(self.stack.history.iter().map(|(_, history)| history))
+            .flat_map(|history| history.all_layers())
+            .filter(|layer| seen.insert(Arc::as_ptr(layer)))
+            .map(|layer| approximate_layer_size(layer))
+            .sum()
+    }
+
+    pub fn metrics(&self) -> HistoryMetrics {
+        HistoryMetrics {
+            entries: self.stack.history.len(),
+            bytes: self.approximate_memory(),
+        }
+    }
+
+    // Drops the oldest entries until the history fits in the configured limits
+    fn enforce_limits(&mut self) {
+        let (limit_mb, length) = Dependency::<AutoPersisting<Config>>::get().with_lock(|config| {
+            config
+                .read()
+                .map(|config| (config.history_memory_limit_mb(), config.history_length()))
+                .unwrap_or((DEFAULT_HISTORY_MEMORY_LIMIT_MB, DEFAULT_HISTORY_LENGTH))
+        });
+        let limit = limit_mb as usize * 1024 * 1024;
+
+        let mut dropped = 0;
+        while (self.stack.history.len() > length as usize || self.approximate_memory() > limit)
+            && self.stack.drop_oldest()
+        {
+            dropped += 1;
+        }
+
+        if dropped > 0 {
+            log::info!(
+                "Dropped {} undo history entries to stay under {} steps and {} MiB",
+                dropped,
+                length,
+                limit_mb
+            );
+        }
+    }
+
+    /// Restores `canvas_state` to the page in `history`
+    pub fn apply_history(history: CanvasHistory, canvas_state: &mut CanvasState) {
+        canvas_state.layers = history
+            .layers
+            .into_iter()
+            .map(|(id, layer)| (id, Arc::unwrap_or_clone(layer)))
+            .collect();
+        canvas_state.multi_select = history.multi_select;
+        canvas_state.page = history.page;
+        canvas_state.background = history.background;
+        canvas_state.section_start = history.section_start;
     }
 
     pub fn capturing_history<T>(
@@ -1,19 +1,40 @@
+use std::collections::HashMap;
+
 use crate::{
-    photo::Photo,
+    id::PageId,
     widget::{
         canvas::CanvasState, image_gallery::ImageGalleryState, image_viewer::ImageViewerState,
     },
 };
 
 use self::{
-    canvas_scene::CanvasScene, organize_edit_scene::OrganizeEditScene,
-    organize_scene::GalleryScene, viewer_scene::ViewerScene,
+    canvas_scene::{CanvasScene, PageView},
+    organize_edit_scene::OrganizeEditScene,
+    organize_scene::{GalleryScene, GalleryScenePane},
+    template_editor_scene::TemplateEditorScene,
+    transition_animation::{TransitionAnimation, TransitionDirection},
+    viewer_scene::{ViewerScene, ViewerScenePane},
+    workspace::WorkspaceLayout,
 };
 
 pub mod canvas_scene;
 pub mod organize_edit_scene;
 pub mod organize_scene;
+pub mod template_editor_scene;
+pub mod transition_animation;
 pub mod viewer_scene;
+pub mod workspace;
+
+/// Identifies a scene, whichever instance of it is shown. State kept between visits to a scene is
+/// keyed by this.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SceneId {
+    OrganizeEdit,
+    Gallery,
+    Viewer,
+    Canvas,
+    TemplateEditor,
+}
 
 pub enum SceneResponse {
     None,
@@ -26,6 +47,7 @@ pub enum SceneTransition {
     Gallery(GalleryScene),
     Viewer(ViewerScene),
     Canvas(CanvasScene),
+    TemplateEditor(TemplateEditorScene),
 }
 
 impl SceneTransition {
@@ -35,6 +57,7 @@ impl SceneTransition {
             SceneTransition::Gallery(scene) => Box::new(scene),
             SceneTransition::Viewer(scene) => Box::new(scene),
             SceneTransition::Canvas(scene) => Box::new(scene),
+            SceneTransition::TemplateEditor(scene) => Box::new(scene),
         }
     }
 }
@@ -46,6 +69,7 @@ impl PartialEq for SceneTransition {
             (SceneTransition::Gallery(_), SceneTransition::Gallery(_)) => true,
             (SceneTransition::Viewer(_), SceneTransition::Viewer(_)) => true,
             (SceneTransition::Canvas(_), SceneTransition::Canvas(_)) => true,
+            (SceneTransition::TemplateEditor(_), SceneTransition::TemplateEditor(_)) => true,
             _ => false,
         }
     }
@@ -53,11 +77,24 @@ impl PartialEq for SceneTransition {
 
 pub trait Scene: Send + Sync {
     fn ui(&mut self, ui: &mut egui::Ui) -> SceneResponse;
+
+    fn id(&self) -> SceneId;
+
+    /// What to keep of the scene once it's popped, given back to the next scene pushed with the
+    /// same id. Scenes that start over each time keep nothing.
+    fn into_state(self: Box<Self>) -> Option<SceneState> {
+        None
+    }
+
+    fn restore(&mut self, _state: SceneState) {}
 }
 
 pub struct SceneManager {
     pub root_scene: OrganizeEditScene,
     scenes: Vec<Box<dyn Scene>>,
+    // State of popped scenes, picked up again when they're next pushed
+    kept_states: HashMap<SceneId, SceneState>,
+    transition: Option<TransitionAnimation>,
 }
 
 impl SceneManager {
@@ -65,27 +102,47 @@ impl SceneManager {
         Self {
             root_scene,
             scenes: vec![],
+            kept_states: HashMap::new(),
+            transition: None,
         }
     }
 
     pub fn push(&mut self, scene: SceneTransition) {
-        self.scenes.push(scene.scene());
+        let mut scene = scene.scene();
+        if let Some(state) = self.kept_states.remove(&scene.id()) {
+            scene.restore(state);
+        }
+
+        self.scenes.push(scene);
+        self.transition = Some(TransitionAnimation::new(TransitionDirection::Forward));
     }
 
     pub fn pop(&mut self) {
-        self.scenes.pop();
+        if let Some(scene) = self.scenes.pop() {
+            let id = scene.id();
+            if let Some(state) = scene.into_state() {
+                self.kept_states.insert(id, state);
+            }
+        }
+
+        self.transition = Some(TransitionAnimation::new(TransitionDirection::Back));
     }
 
     pub fn swap(&mut self, scene: SceneTransition) {
-        self.scenes.pop();
-        self.scenes.push(scene.scene());
+        self.pop();
+        self.push(scene);
     }
 
     pub fn ui(&mut self, ui: &mut egui::Ui) {
-        let response = if let Some(scene) = self.scenes.last_mut() {
-            scene.ui(ui)
-        } else {
-            self.root_scene.ui(ui)
+        let response = match self.scenes.last_mut() {
+            Some(scene) => {
+                TransitionAnimation::show(&mut self.transition, ui, scene.id(), |ui| scene.ui(ui))
+            }
+            None => {
+                TransitionAnimation::show(&mut self.transition, ui, self.root_scene.id(), |ui| {
+                    self.root_scene.ui(ui)
+                })
+            }
         };
 
         match response {
@@ -106,10 +163,7 @@ impl SceneManager {
 
 impl Default for SceneManager {
     fn default() -> Self {
-        Self {
-            root_scene: OrganizeEditScene::new(GalleryScene::new(), None),
-            scenes: vec![],
-        }
+        Self::new(OrganizeEditScene::new(GalleryScene::new(), None))
     }
 }
 
@@ -150,16 +204,52 @@ impl CanvasSceneState {
     }
 }
 
+/// What's kept of a scene after it's popped so coming back to it picks up where it was left
 pub enum SceneState {
-    Gallery {
-        state: ImageGalleryState,
-    },
     Viewer {
-        photo: Photo,
-        index: usize,
-        state: ImageViewerState,
+        viewer_state: ImageViewerState,
+        tree: egui_tiles::Tree<ViewerScenePane>,
+    },
+    Gallery {
+        gallery_state: ImageGalleryState,
+        tree: egui_tiles::Tree<GalleryScenePane>,
     },
+    // Pages are only looked at the way they were if they're still there when the scene comes back
     Canvas {
-        state: CanvasState,
+        selected_page: PageId,
+        views: HashMap<PageId, PageView>,
+        workspace_layout: WorkspaceLayout,
     },
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kept_gallery_scale(scene_manager: &mut SceneManager) -> Option<f32> {
+        match scene_manager.kept_states.remove(&SceneId::Gallery) {
+            Some(SceneState::Gallery { gallery_state, .. }) => Some(gallery_state.scale),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn state_is_restored_when_a_scene_is_pushed_again() {
+        let mut scene_manager = SceneManager::default();
+
+        let mut gallery = GalleryScene::new();
+        gallery.state.image_gallery_state.scale = 2.5;
+        scene_manager.push(SceneTransition::Gallery(gallery));
+        scene_manager.pop();
+
+        // A new gallery picks up the scale the last one was left at
+        scene_manager.push(SceneTransition::Gallery(GalleryScene::new()));
+        scene_manager.pop();
+        assert_eq!(kept_gallery_scale(&mut scene_manager), Some(2.5));
+
+        // Once taken nothing is left to restore
+        scene_manager.push(SceneTransition::Gallery(GalleryScene::new()));
+        scene_manager.pop();
+        assert_eq!(kept_gallery_scale(&mut scene_manager), Some(1.0));
+    }
+}
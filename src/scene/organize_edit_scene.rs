@@ -1,33 +1,78 @@
-use std::sync::{Arc, RwLock};
+use std::{
+    collections::HashSet,
+    path::PathBuf,
+    sync::{Arc, Mutex, RwLock},
+};
 
-use egui::{menu, Color32, CursorIcon, Pos2, Rect, RichText, Sense, Ui, Vec2};
+use egui::{menu, Button, CursorIcon, Layout, Pos2, Rect, RichText, Sense, Ui, Vec2};
+use indexmap::IndexMap;
 use log::{error, info};
 
 use crate::{
     auto_persisting::AutoPersisting,
-    config::{Config, ConfigModification},
+    book_text::BookText,
+    book_theme::ThemeFontRole,
+    config::{Config, ConfigModification, DEFAULT_UI_SCALE, MAX_UI_SCALE, MIN_UI_SCALE},
     cursor_manager::CursorManager,
-    debug::DebugSettings,
+    debug::{DebugSettings, DiagnosticsReport},
     dependencies::{Dependency, Singleton, SingletonFor},
-    export::Exporter,
+    error_sink::ErrorSink,
+    font_manager::{FontManager, LoadingState},
+    id::{next_page_id, PageId},
     modal::{
+        auto_placement::AutoPlacementModal,
         basic::BasicModal,
+        batch_caption::BatchCaptionModal,
+        book_export::{BookExportModal, ShowLayerRequest},
+        book_metadata::BookMetadataModal,
+        book_text_import::{BookTextImportModal, BookTextImportResult},
+        book_theme::BookThemeModal,
+        camera_import::CameraImportModal,
         manager::{ModalManager, TypedModalId},
+        missing_fonts::{MissingFontsModal, MissingFontsResult},
+        new_project::{NewProject, NewProjectModal, NewProjectResult},
         page_settings::PageSettingsModal,
+        project_archive::ProjectArchiveModal,
+        proof_export::ProofExportModal,
+        psd_export::PsdExportModal,
+        save_warning::SaveWarningModal,
+        save_workspace_layout::SaveWorkspaceLayoutModal,
+        slideshow_export::SlideshowExportModal,
+        svg_export::SvgExportModal,
         ModalActionResponse,
     },
-    photo_manager::{PhotoManager, PhotosGrouping},
-    project::v1::Project,
+    model::{
+        nudge::{NudgeDistance, NudgeUnit},
+        resize_quality::ResizeQuality,
+    },
+    page_preloader::PagePreloader,
+    photo_manager::{PhotoManager, PhotosGrouping, PhotosSort, PhotosSortField, SortDirection},
+    project::{
+        archive::{ProjectArchive, ARCHIVE_EXTENSION},
+        v1::Project,
+    },
+    project_fonts,
     project_settings::ProjectSettingsManager,
     session::Session,
+    snippet::SnippetManager,
+    theme::{self, AccentColor, ThemeMode},
+    trash::TrashManager,
     utils::{Either, Toggle},
-    widget::canvas::CanvasState,
+    widget::{
+        canvas::CanvasState,
+        diagnostics::DiagnosticsPanel,
+        pan_zoom::{WheelAction, MAX_ZOOM_SENSITIVITY, MIN_ZOOM_SENSITIVITY},
+    },
 };
 
+use strum::IntoEnumIterator;
+
 use super::{
     canvas_scene::{self, CanvasScene},
     organize_scene::GalleryScene,
-    CanvasSceneState, Scene, SceneResponse,
+    transition_animation::{TransitionAnimation, TransitionDirection},
+    workspace::{WorkspaceDockPosition, WorkspaceLayout},
+    CanvasSceneState, Scene, SceneId, SceneResponse,
     SceneTransition::{self},
 };
 
@@ -36,7 +81,30 @@ pub struct OrganizeEditScene {
     pub organize: Arc<RwLock<GalleryScene>>,
     pub edit: Option<Arc<RwLock<CanvasScene>>>,
     current: Either<Arc<RwLock<GalleryScene>>, Arc<RwLock<CanvasScene>>>,
+    // Runs after switching between organize and edit
+    transition: Option<TransitionAnimation>,
     page_settings_modal_id: Option<TypedModalId<PageSettingsModal>>,
+    book_theme_modal_id: Option<TypedModalId<BookThemeModal>>,
+    auto_placement_modal_id: Option<TypedModalId<AutoPlacementModal>>,
+    pending_project_action: Option<(TypedModalId<SaveWarningModal>, ProjectAction)>,
+    // Asked for while a save was running, requested again once it's done
+    queued_project_action: Option<ProjectAction>,
+    new_project_modal: Option<(TypedModalId<NewProjectModal>, NewProjectResult)>,
+    book_text_import_modal: Option<(TypedModalId<BookTextImportModal>, BookTextImportResult)>,
+    book_export_modal: Option<(TypedModalId<BookExportModal>, ShowLayerRequest)>,
+    missing_fonts_modal: Option<(TypedModalId<MissingFontsModal>, MissingFontsResult)>,
+    // The project is checked for fonts that aren't installed once they've been loaded
+    checked_missing_fonts: bool,
+}
+
+/// Actions that replace the open project and so need a chance to save first
+#[derive(Debug, Clone)]
+enum ProjectAction {
+    New,
+    Open,
+    OpenRecent(PathBuf),
+    ImportArchive,
+    DiscardRecoveredChanges { recovered_hash: u64 },
 }
 
 impl OrganizeEditScene {
@@ -47,11 +115,25 @@ impl OrganizeEditScene {
             organize: organize_scene.clone(),
             edit: edit,
             current: Either::Left(organize_scene.clone()),
+            transition: None,
             page_settings_modal_id: None,
+            book_theme_modal_id: None,
+            auto_placement_modal_id: None,
+            pending_project_action: None,
+            queued_project_action: None,
+            new_project_modal: None,
+            book_text_import_modal: None,
+            book_export_modal: None,
+            missing_fonts_modal: None,
+            checked_missing_fonts: false,
         }
     }
 
     pub fn show_organize(&mut self) {
+        if self.current.is_right() {
+            self.transition = Some(TransitionAnimation::new(TransitionDirection::Back));
+        }
+
         self.current = Either::Left(self.organize.clone());
         // TODO: This is a bit of a hack to keep the gallery state in sync between the two scenes
         // Introduce some sort of shared state between the two scenes
@@ -75,6 +157,10 @@ impl OrganizeEditScene {
         }
 
         if let Some(edit) = &self.edit {
+            if self.current.is_left() {
+                self.transition = Some(TransitionAnimation::new(TransitionDirection::Forward));
+            }
+
             self.current = Either::Right(edit.clone());
         }
 
@@ -91,6 +177,600 @@ impl OrganizeEditScene {
         }
     }
 
+    pub fn project(&self) -> Project {
+        Dependency::<PhotoManager>::get()
+            .with_lock(|photo_manager| Project::new(self, photo_manager))
+    }
+
+    /// Asks for a folder and relinks every missing photo with a file of the same name in it
+    fn locate_missing_files(&mut self) {
+        let missing = Dependency::<PhotoManager>::get()
+            .with_lock(|photo_manager| photo_manager.missing_photos());
+
+        if missing.is_empty() {
+            ModalManager::push(BasicModal::new(
+                "Locate Missing Files",
+                "No photos are missing",
+                "OK",
+            ));
+            return;
+        }
+
+        let folder = match native_dialog::FileDialog::new().show_open_single_dir() {
+            Ok(Some(folder)) => folder,
+            Ok(None) => {
+                info!("No folder selected to locate missing files");
+                return;
+            }
+            Err(e) => {
+                error!("Error opening locate missing files dialog: {:?}", e);
+                return;
+            }
+        };
+
+        let edit = self.edit.clone();
+
+        // Searching the folder and reading the found photos can take a while
+        tokio::task::spawn_blocking(move || {
+            let relinks = PhotoManager::find_relinks(&missing, &folder);
+            let relinks = Dependency::<PhotoManager>::get()
+                .with_lock_mut(|photo_manager| photo_manager.relink(relinks));
+
+            if let Some(edit) = edit {
+                edit.write().unwrap().state.relink_photos(&relinks);
+            }
+
+            ModalManager::push(BasicModal::new(
+                "Locate Missing Files",
+                format!(
+                    "Relinked {} of {} missing photos",
+                    relinks.len(),
+                    missing.len()
+                ),
+                "OK",
+            ));
+        });
+    }
+
+    /// Lists the fonts the project uses that aren't installed so they can be replaced. Tells the
+    /// user when nothing is missing if `report_none` is set.
+    fn show_missing_fonts(&mut self, report_none: bool) {
+        let theme = Dependency::<ProjectSettingsManager>::get()
+            .with_lock(|settings| settings.project_settings.theme.clone());
+
+        let missing = match &self.edit {
+            Some(edit) => project_fonts::find_missing(
+                edit.read().unwrap().state.pages_state.pages.values(),
+                &theme,
+            ),
+            None => project_fonts::find_missing(std::iter::empty::<&CanvasState>(), &theme),
+        };
+
+        if missing.is_empty() {
+            if report_none {
+                ModalManager::push(BasicModal::new(
+                    "Missing Fonts",
+                    "Every font the project uses is installed",
+                    "OK",
+                ));
+            }
+            return;
+        }
+
+        let result: MissingFontsResult = Arc::new(Mutex::new(None));
+        let modal_id = ModalManager::push(MissingFontsModal::new(missing, result.clone()));
+        self.missing_fonts_modal = Some((modal_id, result));
+    }
+
+    fn replace_fonts(&mut self, replacements: &[(String, Option<String>)]) {
+        Dependency::<ProjectSettingsManager>::get().with_lock_mut(|settings| {
+            let theme = &mut settings.project_settings.theme;
+            for role in ThemeFontRole::iter() {
+                let font = theme.font_mut(role);
+                if let Some((_, replacement)) = replacements
+                    .iter()
+                    .find(|(family, _)| font.as_ref() == Some(family))
+                {
+                    *font = replacement.clone();
+                }
+            }
+        });
+
+        if let Some(edit) = &self.edit {
+            edit.write().unwrap().state.replace_fonts(replacements);
+        }
+    }
+
+    fn gallery_selection(&self) -> HashSet<PathBuf> {
+        match &self.current {
+            Either::Left(organize) => organize
+                .read()
+                .unwrap()
+                .state
+                .image_gallery_state
+                .selected_images
+                .clone(),
+            Either::Right(edit) => edit
+                .read()
+                .unwrap()
+                .state
+                .gallery_state
+                .selected_images
+                .clone(),
+        }
+    }
+
+    /// Captions the photos selected in the gallery, or every photo shown when nothing is selected
+    fn batch_caption_photos(&mut self) {
+        let selected_images = self.gallery_selection();
+
+        let photos = Dependency::<PhotoManager>::get().with_lock(|photo_manager| {
+            photo_manager
+                .grouped_photos()
+                .values()
+                .flat_map(|group| group.values())
+                .filter(|photo| selected_images.is_empty() || selected_images.contains(&photo.path))
+                .cloned()
+                .collect::<Vec<_>>()
+        });
+
+        if photos.is_empty() {
+            ModalManager::push(BasicModal::new(
+                "Batch Captions",
+                "Import some photos to caption first",
+                "OK",
+            ));
+            return;
+        }
+
+        ModalManager::push(BatchCaptionModal::new(photos));
+    }
+
+    /// Writes the text of every text layer to a file a proofreader can edit
+    fn export_book_text(&self) {
+        let book_text = match &self.edit {
+            Some(edit) => BookText::new(&edit.read().unwrap().state.pages_state.pages),
+            None => BookText::default(),
+        };
+
+        if book_text.is_empty() {
+            ModalManager::push(BasicModal::new(
+                "Export Book Text",
+                "There is no text in the book to export",
+                "OK",
+            ));
+            return;
+        }
+
+        let path = match native_dialog::FileDialog::new()
+            .set_filename("book_text.csv")
+            .add_filter("CSV", &["csv"])
+            .add_filter("JSON", &["json"])
+            .show_save_single_file()
+        {
+            Ok(Some(path)) => path,
+            Ok(None) => {
+                info!("No book text export path selected");
+                return;
+            }
+            Err(err) => {
+                error!("Error opening book text export dialog: {:?}", err);
+                return;
+            }
+        };
+
+        if let Err(err) = book_text.write(&path) {
+            error!("Failed to export book text: {:?}", err);
+            ModalManager::push(BasicModal::new(
+                "Export Book Text",
+                format!("Failed to export book text: {}", err),
+                "OK",
+            ));
+        }
+    }
+
+    /// Reads back a proofread book text file and shows what it would change
+    fn import_book_text(&mut self) {
+        let Some(edit) = &self.edit else {
+            ModalManager::push(BasicModal::new(
+                "Import Book Text",
+                "There are no pages to import text into",
+                "OK",
+            ));
+            return;
+        };
+
+        let path = match native_dialog::FileDialog::new()
+            .add_filter("Book Text", &["csv", "json"])
+            .show_open_single_file()
+        {
+            Ok(Some(path)) => path,
+            Ok(None) => {
+                info!("No book text file selected");
+                return;
+            }
+            Err(err) => {
+                error!("Error opening book text import dialog: {:?}", err);
+                return;
+            }
+        };
+
+        let book_text = match BookText::read(&path) {
+            Ok(book_text) => book_text,
+            Err(err) => {
+                error!("Failed to read book text: {:?}", err);
+                ModalManager::push(BasicModal::new(
+                    "Import Book Text",
+                    format!("Failed to read book text: {}", err),
+                    "OK",
+                ));
+                return;
+            }
+        };
+
+        let (changes, unmatched) = book_text.changes(&edit.read().unwrap().state.pages_state.pages);
+
+        let result: BookTextImportResult = Arc::new(Mutex::new(None));
+        let modal_id =
+            ModalManager::push(BookTextImportModal::new(changes, unmatched, result.clone()));
+        self.book_text_import_modal = Some((modal_id, result));
+    }
+
+    /// Places the photos selected in the gallery onto new pages, grouped by when they were taken
+    fn auto_layout_selection(&mut self) {
+        let has_default_page = Dependency::<ProjectSettingsManager>::get()
+            .with_lock(|manager| manager.project_settings.default_page.is_some());
+
+        if !has_default_page {
+            self.page_settings_modal_id = Some(ModalManager::push(PageSettingsModal::new()));
+            return;
+        }
+
+        let selected_images = self.gallery_selection();
+
+        if selected_images.is_empty() {
+            ModalManager::push(BasicModal::new(
+                "Auto Layout",
+                "Select the photos to lay out in the gallery first",
+                "OK",
+            ));
+            return;
+        }
+
+        let photos = Dependency::<PhotoManager>::get().with_lock(|photo_manager| {
+            selected_images
+                .iter()
+                .filter_map(|path| photo_manager.photos.get(path).cloned())
+                .collect::<Vec<_>>()
+        });
+
+        let edit = self
+            .edit
+            .get_or_insert_with(|| Arc::new(RwLock::new(CanvasScene::new())))
+            .clone();
+
+        self.auto_placement_modal_id =
+            Some(ModalManager::push(AutoPlacementModal::new(photos, edit)));
+    }
+
+    /// Carries out a panorama placement requested from the organize gallery and switches to
+    /// editing the new pages
+    fn place_pending_panorama(&mut self) {
+        let Some((photo, placement)) = self.organize.write().unwrap().state.pending_panorama.take()
+        else {
+            return;
+        };
+
+        let has_default_page = Dependency::<ProjectSettingsManager>::get()
+            .with_lock(|manager| manager.project_settings.default_page.is_some());
+
+        if !has_default_page {
+            self.page_settings_modal_id = Some(ModalManager::push(PageSettingsModal::new()));
+            return;
+        }
+
+        self.edit
+            .get_or_insert_with(|| Arc::new(RwLock::new(CanvasScene::new())))
+            .write()
+            .unwrap()
+            .state
+            .place_panorama(&photo, placement);
+
+        self.show_edit();
+    }
+
+    /// Shows a placed photo picked in the organize gallery on its page in the edit scene
+    fn show_pending_placement(&mut self) {
+        let Some(placement) = self
+            .organize
+            .write()
+            .unwrap()
+            .state
+            .pending_placement
+            .take()
+        else {
+            return;
+        };
+
+        let Some(edit) = &self.edit else {
+            return;
+        };
+
+        if edit
+            .write()
+            .unwrap()
+            .state
+            .show_layer(placement.page_index, placement.layer)
+        {
+            self.show_edit();
+        }
+    }
+
+    fn diagnostics_ui(&self, ctx: &egui::Context) {
+        let debug_settings: Singleton<DebugSettings> = Dependency::get();
+        let mut open = debug_settings.with_lock(|debug_settings| debug_settings.show_diagnostics);
+        if !open {
+            return;
+        }
+
+        let history = self
+            .edit
+            .as_ref()
+            .map(|edit| edit.read().unwrap().state.history_metrics());
+
+        egui::Window::new("Diagnostics")
+            .open(&mut open)
+            .default_size(Vec2::new(360.0, 480.0))
+            .show(ctx, |ui| {
+                DiagnosticsPanel::new(DiagnosticsReport::collect(history)).show(ui);
+            });
+
+        debug_settings.with_lock_mut(|debug_settings| debug_settings.show_diagnostics = open);
+    }
+
+    /// Gives placed copies of photos the orientations picked for them in the photo info
+    fn apply_orientation_changes(&mut self) {
+        let photos = Dependency::<PhotoManager>::get()
+            .with_lock_mut(|photo_manager| photo_manager.take_orientation_changes());
+
+        if let (false, Some(edit)) = (photos.is_empty(), &self.edit) {
+            edit.write()
+                .unwrap()
+                .state
+                .update_photo_orientations(&photos);
+        }
+    }
+
+    /// Asks for the book size, page count and photos of a new project
+    pub fn show_new_project_wizard(&mut self) {
+        let result: NewProjectResult = Arc::new(Mutex::new(None));
+        let modal_id = ModalManager::push(NewProjectModal::new(result.clone()));
+        self.new_project_modal = Some((modal_id, result));
+    }
+
+    /// Replaces the open project with an empty book set up from the wizard
+    fn start_new_project(&mut self, new_project: NewProject) {
+        Dependency::<ProjectSettingsManager>::get().with_lock_mut(|manager| {
+            **manager = ProjectSettingsManager::new();
+            manager.project_settings.default_page = Some(new_project.page.clone());
+            manager.project_settings.fit_page_margin_mm = Some(new_project.margin_mm);
+        });
+
+        Dependency::<PhotoManager>::get().with_lock_mut(|photo_manager| {
+            **photo_manager = PhotoManager::new();
+        });
+
+        Dependency::<Session>::get().with_lock_mut(|session| {
+            **session = Session::new();
+        });
+
+        Dependency::<TrashManager>::get().with_lock_mut(|trash_manager| {
+            **trash_manager = TrashManager::new();
+        });
+
+        Dependency::<SnippetManager>::get().with_lock_mut(|snippet_manager| {
+            **snippet_manager = SnippetManager::new();
+        });
+
+        // Pages pick up the new default page from the project settings
+        let pages: IndexMap<PageId, CanvasState> = (0..new_project.page_count.max(1))
+            .map(|_| (next_page_id(), CanvasState::new()))
+            .collect();
+        let first_page = *pages.keys().next().unwrap();
+
+        *self = OrganizeEditScene::new(
+            GalleryScene::new(),
+            Some(CanvasScene::with_state(
+                canvas_scene::CanvasSceneState::with_pages(pages, first_page),
+            )),
+        );
+
+        if let Some(photo_folder) = new_project.photo_folder {
+            info!("Importing {:?} into the new project", photo_folder);
+            if let Err(err) = PhotoManager::load_directory(photo_folder) {
+                ErrorSink::report("Couldn't import photos", err);
+            }
+        }
+
+        self.show_organize();
+    }
+
+    /// Lets the user keep or throw away changes restored from an auto save
+    pub fn warn_recovered_changes(&mut self, project: Project) {
+        let Ok(recovered_hash) = project.content_hash() else {
+            return;
+        };
+
+        let modal_id = ModalManager::push(SaveWarningModal::recovered(project));
+        self.pending_project_action = Some((
+            modal_id,
+            ProjectAction::DiscardRecoveredChanges { recovered_hash },
+        ));
+    }
+
+    /// Runs `action` straight away, or once the user has decided what to do with their unsaved changes.
+    /// While a save is running the action waits for it so the project isn't replaced mid-write.
+    fn request_project_action(&mut self, action: ProjectAction, ctx: &egui::Context) {
+        if Dependency::<Session>::get().with_lock(|session| session.is_saving()) {
+            self.queued_project_action = Some(action);
+            return;
+        }
+
+        let project = self.project();
+        let has_unsaved_changes =
+            Dependency::<Session>::get().with_lock(|session| session.has_unsaved_changes(&project));
+
+        if has_unsaved_changes {
+            let modal_id = ModalManager::push(SaveWarningModal::new(project));
+            self.pending_project_action = Some((modal_id, action));
+        } else {
+            self.perform_project_action(action, ctx);
+        }
+    }
+
+    fn perform_project_action(&mut self, action: ProjectAction, ctx: &egui::Context) {
+        match action {
+            ProjectAction::New => self.show_new_project_wizard(),
+            ProjectAction::Open => {
+                let open_path = native_dialog::FileDialog::new()
+                    .add_filter("Images", &["rpb"])
+                    .show_open_single_file();
+
+                match open_path {
+                    Ok(Some(open_path)) => self.open_project(open_path, ctx),
+                    Err(e) => {
+                        error!("Error opening open file dialog: {:?}", e);
+                    }
+                    Ok(None) => {
+                        info!("No open path selected");
+                    }
+                }
+            }
+            ProjectAction::OpenRecent(path) => self.open_project(path, ctx),
+            ProjectAction::ImportArchive => self.import_project_archive(ctx),
+            ProjectAction::DiscardRecoveredChanges { recovered_hash } => {
+                let (saved_hash, active_project) =
+                    Dependency::<Session>::get().with_lock(|session| {
+                        (session.saved_project_hash, session.active_project.clone())
+                    });
+
+                // The recovered changes were saved rather than discarded
+                if saved_hash == Some(recovered_hash) {
+                    return;
+                }
+
+                if let Some(active_project) = active_project {
+                    self.open_project(active_project, ctx);
+                }
+            }
+        }
+    }
+
+    fn open_project(&mut self, path: PathBuf, ctx: &egui::Context) {
+        let installed_fonts = project_fonts::install_embedded_fonts(&path);
+        if installed_fonts > 0 {
+            info!(
+                "Installed {} fonts embedded with {:?}",
+                installed_fonts, path
+            );
+            Dependency::<FontManager>::get().with_lock_mut(|font_manager| {
+                font_manager.reload_fonts(ctx);
+            });
+        }
+
+        match Project::load(&path) {
+            Ok(scene) => {
+                Dependency::<AutoPersisting<Config>>::get().with_lock_mut(|config| {
+                    let _ = config.modify(ConfigModification::AddRecentProject(path.clone()));
+                    let _ = config.modify(ConfigModification::SetLastProject(path.clone()));
+                });
+
+                Dependency::<Session>::get().with_lock_mut(|session| {
+                    session.active_project = Some(path);
+                });
+
+                *self = scene;
+                self.show_organize();
+            }
+            Err(err) => {
+                error!("Error loading project: {:?}", err);
+
+                ModalManager::push(BasicModal::new(
+                    "Error",
+                    format!("Error loading project: {:?}", err),
+                    "OK",
+                ));
+            }
+        }
+    }
+
+    fn import_project_archive(&mut self, ctx: &egui::Context) {
+        let archive_path = match native_dialog::FileDialog::new()
+            .add_filter("Project Archive", &[ARCHIVE_EXTENSION])
+            .show_open_single_file()
+        {
+            Ok(Some(archive_path)) => archive_path,
+            Ok(None) => {
+                info!("No project archive selected");
+                return;
+            }
+            Err(e) => {
+                error!("Error opening project archive dialog: {:?}", e);
+                return;
+            }
+        };
+
+        let destination = match native_dialog::FileDialog::new().show_open_single_dir() {
+            Ok(Some(directory)) => match archive_path.file_stem() {
+                Some(stem) => directory.join(stem),
+                None => directory,
+            },
+            Ok(None) => {
+                info!("No project archive destination selected");
+                return;
+            }
+            Err(e) => {
+                error!("Error opening project archive destination dialog: {:?}", e);
+                return;
+            }
+        };
+
+        let imported =
+            ProjectArchive::import(&archive_path, &destination).and_then(|project_path| {
+                Project::load(&project_path).map(|scene| (project_path, scene))
+            });
+
+        match imported {
+            Ok((project_path, scene)) => {
+                Dependency::<FontManager>::get().with_lock_mut(|font_manager| {
+                    font_manager.reload_fonts(ctx);
+                });
+
+                Dependency::<AutoPersisting<Config>>::get().with_lock_mut(|config| {
+                    let _ =
+                        config.modify(ConfigModification::AddRecentProject(project_path.clone()));
+                    let _ = config.modify(ConfigModification::SetLastProject(project_path.clone()));
+                });
+
+                Dependency::<Session>::get().with_lock_mut(|session| {
+                    session.active_project = Some(project_path);
+                });
+
+                *self = scene;
+                self.show_organize();
+            }
+            Err(err) => {
+                error!("Error importing project archive: {:?}", err);
+
+                ModalManager::push(BasicModal::new(
+                    "Error",
+                    format!("Error importing project archive: {}", err),
+                    "OK",
+                ));
+            }
+        }
+    }
+
     fn mode_selector(&mut self, ui: &mut Ui) {
         ui.style_mut().interaction.selectable_labels = false;
 
@@ -148,15 +828,419 @@ impl OrganizeEditScene {
                 self.page_settings_modal_id = None;
             }
         }
+
+        if self.queued_project_action.is_some()
+            && !Dependency::<Session>::get().with_lock(|session| session.is_saving())
+        {
+            if let Some(action) = self.queued_project_action.take() {
+                self.request_project_action(action, ui.ctx());
+            }
+        }
+
+        if let Some((id, _)) = &self.pending_project_action {
+            let modal_manager: Singleton<ModalManager> = Dependency::get();
+
+            let exists = modal_manager.with_lock(|modal_manager| modal_manager.exists(id));
+
+            let modal_response =
+                modal_manager.with_lock(|modal_manager| modal_manager.response_for(id));
+            if let Some(ModalActionResponse::Confirm) = modal_response {
+                if let Some((_, action)) = self.pending_project_action.take() {
+                    self.perform_project_action(action, ui.ctx());
+                }
+            } else if !exists {
+                self.pending_project_action = None;
+            }
+        }
+
+        if let Some((id, result)) = &self.new_project_modal {
+            let exists = Dependency::<ModalManager>::get()
+                .with_lock(|modal_manager| modal_manager.exists(id));
+
+            let new_project = result.lock().ok().and_then(|mut result| result.take());
+            if let Some(new_project) = new_project {
+                self.new_project_modal = None;
+                self.start_new_project(new_project);
+            } else if !exists {
+                self.new_project_modal = None;
+            }
+        }
+
+        if let Some(id) = &self.auto_placement_modal_id {
+            let modal_manager: Singleton<ModalManager> = Dependency::get();
+
+            let exists = modal_manager.with_lock(|modal_manager| modal_manager.exists(id));
+
+            let modal_response =
+                modal_manager.with_lock(|modal_manager| modal_manager.response_for(id));
+            if let Some(ModalActionResponse::Confirm) = modal_response {
+                self.show_edit();
+            }
+
+            if !exists {
+                self.auto_placement_modal_id = None;
+            }
+        }
+
+        if let Some((id, result)) = &self.book_text_import_modal {
+            let exists = Dependency::<ModalManager>::get()
+                .with_lock(|modal_manager| modal_manager.exists(id));
+
+            let changes = result.lock().ok().and_then(|mut result| result.take());
+            if let Some(changes) = changes {
+                self.book_text_import_modal = None;
+                if let Some(edit) = &self.edit {
+                    edit.write().unwrap().state.apply_text_changes(&changes);
+                }
+            } else if !exists {
+                self.book_text_import_modal = None;
+            }
+        }
+
+        if let Some((id, show_layer)) = &self.book_export_modal {
+            let exists = Dependency::<ModalManager>::get()
+                .with_lock(|modal_manager| modal_manager.exists(id));
+
+            let layer = show_layer.lock().ok().and_then(|mut layer| layer.take());
+            if let Some((page_index, layer_id)) = layer {
+                self.book_export_modal = None;
+                if let Some(edit) = &self.edit {
+                    if edit.write().unwrap().state.show_layer(page_index, layer_id) {
+                        self.show_edit();
+                    }
+                }
+            } else if !exists {
+                self.book_export_modal = None;
+            }
+        }
+
+        if !self.checked_missing_fonts {
+            let fonts_loaded = Dependency::<FontManager>::get()
+                .with_lock(|font_manager| font_manager.loading_state == LoadingState::Loaded);
+            if fonts_loaded {
+                self.checked_missing_fonts = true;
+                self.show_missing_fonts(false);
+            }
+        }
+
+        if let Some((id, result)) = &self.missing_fonts_modal {
+            let exists = Dependency::<ModalManager>::get()
+                .with_lock(|modal_manager| modal_manager.exists(id));
+
+            let replacements = result.lock().ok().and_then(|mut result| result.take());
+            if let Some(replacements) = replacements {
+                self.missing_fonts_modal = None;
+                self.replace_fonts(&replacements);
+            } else if !exists {
+                self.missing_fonts_modal = None;
+            }
+        }
+
+        if let Some(id) = &self.book_theme_modal_id {
+            let modal_manager: Singleton<ModalManager> = Dependency::get();
+
+            let exists = modal_manager.with_lock(|modal_manager| modal_manager.exists(id));
+
+            let modal_response =
+                modal_manager.with_lock(|modal_manager| modal_manager.response_for(id));
+            if let Some(ModalActionResponse::Confirm) = modal_response {
+                if let Some(edit) = &self.edit {
+                    let theme = Dependency::<ProjectSettingsManager>::get()
+                        .with_lock(|settings| settings.project_settings.theme.clone());
+                    edit.write().unwrap().state.apply_book_theme(&theme);
+                }
+            }
+
+            if !exists {
+                self.book_theme_modal_id = None;
+            }
+        }
+    }
+}
+
+impl OrganizeEditScene {
+    fn pan_zoom_settings_ui(config: &mut AutoPersisting<Config>, ui: &mut Ui) {
+        let settings = config
+            .read()
+            .map(|config| config.pan_zoom_settings())
+            .unwrap_or_default();
+
+        ui.label("Mouse Wheel");
+        for action in WheelAction::iter() {
+            if ui
+                .radio(settings.wheel_action == action, action.to_string())
+                .on_hover_text("Hold ctrl while turning the wheel to do the other")
+                .clicked()
+                && action != settings.wheel_action
+            {
+                if let Err(err) = config.modify(ConfigModification::SetWheelAction(action)) {
+                    error!("Failed to update mouse wheel action: {:?}", err);
+                }
+            }
+        }
+
+        ui.separator();
+
+        // The config is only written once the slider is let go
+        let sensitivity_id = ui.id().with("zoom_sensitivity");
+        let mut zoom_sensitivity = ui
+            .data(|data| data.get_temp(sensitivity_id))
+            .unwrap_or(settings.zoom_sensitivity);
+
+        let slider = ui.add(
+            egui::Slider::new(
+                &mut zoom_sensitivity,
+                MIN_ZOOM_SENSITIVITY..=MAX_ZOOM_SENSITIVITY,
+            )
+            .logarithmic(true)
+            .text("Zoom Sensitivity"),
+        );
+
+        if slider.dragged() {
+            ui.data_mut(|data| data.insert_temp(sensitivity_id, zoom_sensitivity));
+        } else if slider.drag_stopped() || slider.changed() {
+            ui.data_mut(|data| data.remove::<f32>(sensitivity_id));
+            if let Err(err) =
+                config.modify(ConfigModification::SetZoomSensitivity(zoom_sensitivity))
+            {
+                error!("Failed to update zoom sensitivity: {:?}", err);
+            }
+        }
+
+        let mut invert_zoom = settings.invert_zoom;
+        if ui.checkbox(&mut invert_zoom, "Invert Zoom").changed() {
+            if let Err(err) = config.modify(ConfigModification::SetInvertZoom(invert_zoom)) {
+                error!("Failed to update zoom direction: {:?}", err);
+            }
+        }
+
+        let mut middle_mouse_pan = settings.middle_mouse_pan;
+        if ui
+            .checkbox(&mut middle_mouse_pan, "Middle Mouse Pans")
+            .on_hover_text("Drag with the middle mouse button to pan, like holding space")
+            .changed()
+        {
+            if let Err(err) = config.modify(ConfigModification::SetMiddleMousePan(middle_mouse_pan))
+            {
+                error!("Failed to update middle mouse panning: {:?}", err);
+            }
+        }
+    }
+
+    fn nudge_settings_ui(config: &mut AutoPersisting<Config>, ui: &mut Ui) {
+        let distance = config
+            .read()
+            .map(|config| config.nudge_distance())
+            .unwrap_or_default();
+        let mut changed = distance;
+
+        ui.horizontal(|ui| {
+            ui.label("Distance");
+            ui.add(
+                egui::DragValue::new(&mut changed.amount)
+                    .range(0.0..=100.0)
+                    .speed(0.01)
+                    .suffix(changed.unit.suffix()),
+            );
+        });
+
+        for unit in NudgeUnit::iter() {
+            if ui.radio(changed.unit == unit, unit.to_string()).clicked() {
+                // Keeps about the same distance in the new unit
+                changed = NudgeDistance {
+                    amount: match (changed.unit, unit) {
+                        (NudgeUnit::Millimeters, NudgeUnit::Inches) => changed.amount / 25.4,
+                        (NudgeUnit::Inches, NudgeUnit::Millimeters) => changed.amount * 25.4,
+                        (from, to) if from == to => changed.amount,
+                        _ => NudgeDistance::default().amount,
+                    },
+                    unit,
+                };
+            }
+        }
+
+        ui.separator();
+        ui.label(RichText::new("Hold shift to move 10× as far, alt for a tenth").weak());
+
+        if changed != distance {
+            if let Err(err) = config.modify(ConfigModification::SetNudgeDistance(changed)) {
+                error!("Failed to update nudge distance: {:?}", err);
+            }
+        }
+    }
+
+    fn ui_scale_settings_ui(config: &mut AutoPersisting<Config>, ui: &mut Ui) {
+        let scale = config
+            .read()
+            .map(|config| config.ui_scale())
+            .unwrap_or(DEFAULT_UI_SCALE);
+
+        // Rescaling while the slider is dragged would move it out from under the pointer, so the
+        // scale is only applied once it's let go
+        let scale_id = ui.id().with("ui_scale");
+        let mut percent = ui
+            .data(|data| data.get_temp(scale_id))
+            .unwrap_or(scale * 100.0);
+
+        let slider = ui.add(
+            egui::Slider::new(&mut percent, MIN_UI_SCALE * 100.0..=MAX_UI_SCALE * 100.0)
+                .step_by(5.0)
+                .suffix("%")
+                .text("UI Scale"),
+        );
+
+        let mut changed = None;
+        if slider.dragged() {
+            ui.data_mut(|data| data.insert_temp(scale_id, percent));
+        } else if slider.drag_stopped() || slider.changed() {
+            ui.data_mut(|data| data.remove::<f32>(scale_id));
+            changed = Some(percent / 100.0);
+        }
+
+        ui.horizontal(|ui| {
+            for preset in [100.0, 125.0, 150.0, 200.0] {
+                if ui
+                    .selectable_label((scale * 100.0 - preset).abs() < 0.5, format!("{}%", preset))
+                    .clicked()
+                {
+                    changed = Some(preset / 100.0);
+                }
+            }
+        });
+
+        ui.separator();
+        ui.label(
+            RichText::new(format!(
+                "Applied on top of the display's {:.0}% scaling",
+                ui.ctx().native_pixels_per_point().unwrap_or(1.0) * 100.0
+            ))
+            .weak(),
+        );
+        ui.label(RichText::new("Ctrl with + and - changes it too").weak());
+
+        if let Some(scale) = changed {
+            if let Err(err) = config.modify(ConfigModification::SetUiScale(scale)) {
+                error!("Failed to update UI scale: {:?}", err);
+            }
+        }
+    }
+
+    fn workspace_menu_ui(edit: &mut CanvasScene, ui: &mut Ui) {
+        let mut layout = edit.workspace_layout();
+        let original_layout = layout.clone();
+
+        for position in WorkspaceDockPosition::iter() {
+            let dock = layout.dock_mut(position);
+            let mut shown = !dock.collapsed;
+            if ui
+                .checkbox(&mut shown, format!("Show {} Panels", position))
+                .changed()
+            {
+                dock.collapsed = !shown;
+            }
+        }
+
+        ui.separator();
+
+        ui.menu_button("Move Panel", |ui| {
+            for pane in layout
+                .left
+                .panes
+                .iter()
+                .chain(layout.right.panes.iter())
+                .chain(layout.bottom.panes.iter())
+                .cloned()
+                .collect::<Vec<_>>()
+            {
+                ui.menu_button(pane.title(), |ui| {
+                    let current_position = layout.position_of(&pane);
+                    for position in WorkspaceDockPosition::iter() {
+                        if ui
+                            .radio(current_position == Some(position), position.to_string())
+                            .clicked()
+                        {
+                            layout.move_pane(pane.clone(), position);
+                            layout.dock_mut(position).collapsed = false;
+                            ui.close_menu();
+                        }
+                    }
+                });
+            }
+        });
+
+        ui.separator();
+
+        let config: Singleton<AutoPersisting<Config>> = Dependency::get();
+        let saved_layouts = config.with_lock_mut(|config| {
+            config
+                .read()
+                .map(|config| config.workspace_layouts())
+                .unwrap_or_default()
+        });
+
+        ui.menu_button("Layouts", |ui| {
+            if saved_layouts.is_empty() {
+                ui.label("No saved layouts");
+            }
+
+            for (name, saved_layout) in &saved_layouts {
+                ui.horizontal(|ui| {
+                    if ui.button(name).clicked() {
+                        layout = saved_layout.clone();
+                        ui.close_menu();
+                    }
+
+                    if ui.small_button("Delete").clicked() {
+                        config.with_lock_mut(|config| {
+                            let _ = config
+                                .modify(ConfigModification::DeleteWorkspaceLayout(name.clone()));
+                        });
+                    }
+                });
+            }
+        });
+
+        if ui.button("Save Layout...").clicked() {
+            ModalManager::push(SaveWorkspaceLayoutModal::new(layout.clone()));
+            ui.close_menu();
+        }
+
+        if ui.button("Reset Layout").clicked() {
+            layout = WorkspaceLayout::default();
+            ui.close_menu();
+        }
+
+        if layout != original_layout {
+            edit.apply_workspace_layout(layout);
+        }
     }
 }
 
 impl Scene for OrganizeEditScene {
     fn ui(&mut self, ui: &mut Ui) -> SceneResponse {
+        // Runs regardless of the current view so previews are ready as soon as a project is opened
+        if let Some(edit) = &self.edit {
+            let edit = edit.read().unwrap();
+            let editing = self.current.is_right();
+            Dependency::<PagePreloader>::get().with_lock_mut(|preloader| {
+                preloader.preload(ui.ctx(), &edit.state.pages_state.pages);
+
+                if editing {
+                    preloader.prefetch_adjacent(
+                        ui.ctx(),
+                        &edit.state.pages_state.pages,
+                        edit.state.pages_state.selected_page,
+                    );
+                } else {
+                    preloader.cancel_adjacent();
+                }
+            });
+        }
+
         ui.painter().rect_filled(
             Rect::from_min_max(Pos2::ZERO, Pos2::new(ui.max_rect().width() + 100.0, 50.0)),
             0.0,
-            Color32::from_gray(40),
+            theme::color::chrome(ui.visuals()),
         );
 
         ui.vertical(|ui| {
@@ -183,6 +1267,17 @@ impl Scene for OrganizeEditScene {
                             nav_buttons_response.response.rect.width(),
                         );
                     });
+
+                    // Saving runs in the background, show that it's still going
+                    let save_progress =
+                        Dependency::<Session>::get().with_lock(|session| session.save_progress);
+                    if let Some(save_progress) = save_progress {
+                        ui.with_layout(Layout::right_to_left(egui::Align::Center), |ui| {
+                            ui.add_space(10.0);
+                            ui.label(format!("Saving {:.0}%", save_progress * 100.0));
+                            ui.spinner();
+                        });
+                    }
                 });
             });
 
@@ -190,49 +1285,14 @@ impl Scene for OrganizeEditScene {
 
             menu::bar(ui, |ui| {
                 ui.menu_button("File", |ui| {
+                    if ui.button("New Project...").clicked() {
+                        self.request_project_action(ProjectAction::New, ui.ctx());
+                        ui.close_menu();
+                    }
+
                     if ui.button("Open").clicked() {
-                        let open_path = native_dialog::FileDialog::new()
-                            .add_filter("Images", &["rpb"])
-                            .show_open_single_file();
-
-                        match open_path {
-                            Ok(Some(open_path)) => match Project::load(&open_path) {
-                                Ok(scene) => {
-                                    let config: Singleton<AutoPersisting<Config>> =
-                                        Dependency::get();
-                                    config.with_lock_mut(|config| {
-                                        let _ = config.modify(
-                                            ConfigModification::AddRecentProject(open_path.clone()),
-                                        );
-                                        let _ = config.modify(ConfigModification::SetLastProject(
-                                            open_path.clone(),
-                                        ));
-                                    });
-
-                                    Dependency::<Session>::get().with_lock_mut(|session| {
-                                        session.active_project = Some(open_path);
-                                    });
-
-                                    *self = scene;
-                                    self.show_organize();
-                                }
-                                Err(err) => {
-                                    error!("Error loading project: {:?}", err);
-
-                                    ModalManager::push(BasicModal::new(
-                                        "Error",
-                                        format!("Error loading project: {:?}", err),
-                                        "OK",
-                                    ));
-                                }
-                            },
-                            Err(e) => {
-                                error!("Error opening open file dialog: {:?}", e);
-                            }
-                            Ok(None) => {
-                                info!("No open path selected");
-                            }
-                        }
+                        self.request_project_action(ProjectAction::Open, ui.ctx());
+                        ui.close_menu();
                     }
 
                     ui.menu_button("Open Recent", |ui| {
@@ -246,35 +1306,20 @@ impl Scene for OrganizeEditScene {
                         } else {
                             for recent in &recents {
                                 if ui.button(recent.display().to_string()).clicked() {
-                                    match Project::load(&recent.into()) {
-                                        Ok(scene) => {
-                                            config.with_lock_mut(|config| {
-                                                let _ = config.modify(
-                                                    ConfigModification::AddRecentProject(
-                                                        recent.into(),
-                                                    ),
-                                                );
-                                            });
-
-                                            *self = scene;
-                                            self.show_organize();
-                                        }
-                                        Err(err) => {
-                                            error!("Error loading project: {:?}", err);
-
-                                            ModalManager::push(BasicModal::new(
-                                                "Error",
-                                                format!("Error loading project: {:?}", err),
-                                                "OK",
-                                            ));
-                                        }
-                                    }
+                                    self.request_project_action(
+                                        ProjectAction::OpenRecent(recent.into()),
+                                        ui.ctx(),
+                                    );
+                                    ui.close_menu();
                                 }
                             }
                         }
                     });
 
-                    if ui.button("Save").clicked() {
+                    let saving =
+                        Dependency::<Session>::get().with_lock(|session| session.is_saving());
+
+                    if ui.add_enabled(!saving, Button::new("Save")).clicked() {
                         let save_path: Result<Option<std::path::PathBuf>, native_dialog::Error> =
                             native_dialog::FileDialog::new()
                                 .add_filter("Images", &["rpb"])
@@ -282,33 +1327,9 @@ impl Scene for OrganizeEditScene {
 
                         match save_path {
                             Ok(Some(save_path)) => {
-                                let photo_manager: Singleton<PhotoManager> = Dependency::get();
-
-                                photo_manager.with_lock(|photo_manager| {
-                                    if let Err(err) = Project::save(&save_path, self, photo_manager)
-                                    {
-                                        error!("Error saving project: {:?}", err);
-                                    } else {
-                                        Dependency::<AutoPersisting<Config>>::get().with_lock_mut(
-                                            |config| {
-                                                let _ = config.modify(
-                                                    ConfigModification::AddRecentProject(
-                                                        save_path.clone(),
-                                                    ),
-                                                );
-                                                let _ = config.modify(
-                                                    ConfigModification::SetLastProject(
-                                                        save_path.clone(),
-                                                    ),
-                                                );
-                                            },
-                                        );
-
-                                        Dependency::<Session>::get().with_lock_mut(|session| {
-                                            session.active_project = Some(save_path);
-                                        });
-                                    }
-                                });
+                                if let Err(err) = self.project().save(save_path, ui.ctx()) {
+                                    ErrorSink::report("Couldn't save the project", err);
+                                }
                             }
                             Err(e) => {
                                 error!("Error opening save file dialog: {:?}", e);
@@ -338,65 +1359,226 @@ impl Scene for OrganizeEditScene {
                         }
                     }
 
+                    if ui.button("Import from Camera...").clicked() {
+                        ModalManager::push(CameraImportModal::new());
+                    }
+
+                    let missing_count = Dependency::<PhotoManager>::get()
+                        .with_lock(|photo_manager| photo_manager.missing_photos().len());
+
+                    if ui
+                        .add_enabled(
+                            missing_count > 0,
+                            egui::Button::new(format!(
+                                "Locate Missing Files ({})...",
+                                missing_count
+                            )),
+                        )
+                        .clicked()
+                    {
+                        self.locate_missing_files();
+                        ui.close_menu();
+                    }
+
+                    if ui.button("Auto Layout Selection...").clicked() {
+                        self.auto_layout_selection();
+                        ui.close_menu();
+                    }
+
+                    if ui.button("Batch Captions...").clicked() {
+                        self.batch_caption_photos();
+                        ui.close_menu();
+                    }
+
                     if ui.button("Export").clicked() {
-                        let export_path = native_dialog::FileDialog::new()
-                            .set_filename("export.pdf")
-                            .show_save_single_file();
-
-                        match export_path {
-                            Ok(Some(export_path)) => {
-                                let exporter: Singleton<Exporter> = Dependency::get();
-
-                                let directory = export_path.parent().unwrap();
-                                let file_name = export_path.file_name().unwrap();
-
-                                match &self.edit {
-                                    Some(edit) => {
-                                        exporter.with_lock_mut(|exporter| {
-                                            exporter.export(
-                                                ui.ctx().clone(),
-                                                edit.read()
-                                                    .unwrap()
-                                                    .state
-                                                    .pages_state
-                                                    .pages
-                                                    .values()
-                                                    .into_iter()
-                                                    .map(|x| x.clone())
-                                                    .collect::<Vec<_>>(),
-                                                directory.into(),
-                                                file_name.to_str().unwrap(),
-                                            );
-                                        });
-                                    }
-                                    None => {
-                                        // Show alert
-                                        ModalManager::push(BasicModal::new(
-                                            "Error",
-                                            "Nothing to export",
-                                            "OK",
-                                        ));
-                                    }
-                                };
+                        match &self.edit {
+                            Some(edit) => {
+                                let edit = edit.read().unwrap();
+                                let pages_state = &edit.state.pages_state;
+                                let pages = pages_state.pages.values().cloned().collect::<Vec<_>>();
+                                let show_layer: ShowLayerRequest = Arc::new(Mutex::new(None));
+                                let modal_id = ModalManager::push(BookExportModal::new(
+                                    pages,
+                                    pages_state.selected_page_indices(),
+                                    show_layer.clone(),
+                                ));
+                                self.book_export_modal = Some((modal_id, show_layer));
                             }
-                            Err(e) => {
-                                error!("Error opening export file dialog: {:?}", e);
+                            None => {
+                                // Show alert
+                                ModalManager::push(BasicModal::new(
+                                    "Error",
+                                    "Nothing to export",
+                                    "OK",
+                                ));
                             }
-                            Ok(None) => {
-                                info!("No export directory selected");
+                        }
+                    }
+
+                    if ui
+                        .button("Create Proof")
+                        .on_hover_text("Export a small watermarked copy to send out for approval")
+                        .clicked()
+                    {
+                        match &self.edit {
+                            Some(edit) => {
+                                let edit = edit.read().unwrap();
+                                let pages_state = &edit.state.pages_state;
+                                let pages = pages_state.pages.values().cloned().collect::<Vec<_>>();
+                                ModalManager::push(ProofExportModal::new(
+                                    pages,
+                                    pages_state.selected_page_indices(),
+                                ));
+                            }
+                            None => {
+                                ModalManager::push(BasicModal::new(
+                                    "Error",
+                                    "Nothing to export",
+                                    "OK",
+                                ));
+                            }
+                        }
+                    }
+
+                    if ui.button("Export Slideshow").clicked() {
+                        match &self.edit {
+                            Some(edit) => {
+                                let pages = edit
+                                    .read()
+                                    .unwrap()
+                                    .state
+                                    .pages_state
+                                    .pages
+                                    .values()
+                                    .cloned()
+                                    .collect::<Vec<_>>();
+                                ModalManager::push(SlideshowExportModal::new(pages));
+                            }
+                            None => {
+                                ModalManager::push(BasicModal::new(
+                                    "Error",
+                                    "Nothing to export",
+                                    "OK",
+                                ));
                             }
                         }
                     }
+
+                    if ui.button("Export SVG").clicked() {
+                        match &self.edit {
+                            Some(edit) => {
+                                let pages = edit
+                                    .read()
+                                    .unwrap()
+                                    .state
+                                    .pages_state
+                                    .pages
+                                    .values()
+                                    .cloned()
+                                    .collect::<Vec<_>>();
+                                ModalManager::push(SvgExportModal::new(pages));
+                            }
+                            None => {
+                                ModalManager::push(BasicModal::new(
+                                    "Error",
+                                    "Nothing to export",
+                                    "OK",
+                                ));
+                            }
+                        }
+                    }
+
+                    if ui.button("Export PSD").clicked() {
+                        match &self.edit {
+                            Some(edit) => {
+                                let pages = edit
+                                    .read()
+                                    .unwrap()
+                                    .state
+                                    .pages_state
+                                    .pages
+                                    .values()
+                                    .cloned()
+                                    .collect::<Vec<_>>();
+                                ModalManager::push(PsdExportModal::new(pages));
+                            }
+                            None => {
+                                ModalManager::push(BasicModal::new(
+                                    "Error",
+                                    "Nothing to export",
+                                    "OK",
+                                ));
+                            }
+                        }
+                    }
+
+                    ui.separator();
+
+                    if ui
+                        .button("Export Book Text...")
+                        .on_hover_text(
+                            "Save every text layer to a CSV or JSON file for proofreading",
+                        )
+                        .clicked()
+                    {
+                        self.export_book_text();
+                        ui.close_menu();
+                    }
+
+                    if ui.button("Import Book Text...").clicked() {
+                        self.import_book_text();
+                        ui.close_menu();
+                    }
+
+                    ui.separator();
+
+                    if ui.button("Export Project Archive").clicked() {
+                        let project = Dependency::<PhotoManager>::get()
+                            .with_lock(|photo_manager| Project::new(self, photo_manager));
+                        ModalManager::push(ProjectArchiveModal::new(project));
+                        ui.close_menu();
+                    }
+
+                    if ui.button("Import Project Archive").clicked() {
+                        self.request_project_action(ProjectAction::ImportArchive, ui.ctx());
+                        ui.close_menu();
+                    }
                 });
 
                 ui.menu_button("Group By", |ui| {
                     let photo_manager: Singleton<PhotoManager> = Dependency::get();
                     photo_manager.with_lock_mut(|photo_manager| {
-                        if ui.button("Date").clicked() {
-                            photo_manager.group_photos_by(PhotosGrouping::Date);
+                        for grouping in PhotosGrouping::iter() {
+                            if ui.button(grouping.to_string()).clicked() {
+                                photo_manager.group_photos_by(grouping);
+                            }
                         }
-                        if ui.button("Rating").clicked() {
-                            photo_manager.group_photos_by(PhotosGrouping::Rating);
+                    });
+                });
+
+                ui.menu_button("Sort By", |ui| {
+                    let photo_manager: Singleton<PhotoManager> = Dependency::get();
+                    photo_manager.with_lock_mut(|photo_manager| {
+                        let sort = photo_manager.photos_sort();
+                        for field in PhotosSortField::iter() {
+                            if ui.button(field.to_string()).clicked() {
+                                photo_manager.sort_photos_by(PhotosSort { field, ..sort });
+                            }
+                        }
+
+                        ui.separator();
+
+                        if ui.button("Ascending").clicked() {
+                            photo_manager.sort_photos_by(PhotosSort {
+                                direction: SortDirection::Ascending,
+                                ..sort
+                            });
+                        }
+                        if ui.button("Descending").clicked() {
+                            photo_manager.sort_photos_by(PhotosSort {
+                                direction: SortDirection::Descending,
+                                ..sort
+                            });
                         }
                     });
                 });
@@ -406,8 +1588,190 @@ impl Scene for OrganizeEditScene {
                         self.page_settings_modal_id =
                             Some(ModalManager::push(PageSettingsModal::new()));
                     }
+
+                    if ui.button("Book Theme").clicked() {
+                        self.book_theme_modal_id = Some(ModalManager::push(BookThemeModal::new()));
+                        ui.close_menu();
+                    }
+
+                    if ui.button("Book Details").clicked() {
+                        ModalManager::push(BookMetadataModal::new());
+                        ui.close_menu();
+                    }
+
+                    ui.separator();
+
+                    if ui.button("Missing Fonts...").clicked() {
+                        self.show_missing_fonts(true);
+                        ui.close_menu();
+                    }
+
+                    let resize_quality_changed = Dependency::<ProjectSettingsManager>::get()
+                        .with_lock_mut(|settings| {
+                            ui.checkbox(
+                                &mut settings.project_settings.embed_fonts,
+                                "Save Fonts Next to Project",
+                            )
+                            .on_hover_text(
+                                "Copy the fonts the project uses into a folder beside the project \
+                                 file, named after it with \"_fonts\" on the end. The fonts aren't \
+                                 in the project file itself, so keep the folder with it when moving \
+                                 the project to another computer, or export a project archive, \
+                                 which holds the fonts.",
+                            );
+
+                            let previous = settings.project_settings.resize_quality;
+                            ui.menu_button("Resize Quality", |ui| {
+                                for quality in ResizeQuality::iter() {
+                                    ui.radio_value(
+                                        &mut settings.project_settings.resize_quality,
+                                        quality,
+                                        quality.to_string(),
+                                    )
+                                    .on_hover_text(quality.description());
+                                }
+                            })
+                            .response
+                            .on_hover_text(
+                                "How photos are smoothed when they're shown or exported smaller \
+                                 than their size",
+                            );
+
+                            settings.project_settings.resize_quality != previous
+                        });
+
+                    // Loaded textures were sampled with the previous quality
+                    if resize_quality_changed {
+                        Dependency::<PhotoManager>::get()
+                            .with_lock_mut(|photo_manager| photo_manager.clear_textures(ui.ctx()));
+                    }
+                });
+
+                ui.menu_button("View", |ui| {
+                    let config: Singleton<AutoPersisting<Config>> = Dependency::get();
+                    config.with_lock_mut(|config| {
+                        let mut pan_inertia = config
+                            .read()
+                            .map(|config| config.pan_inertia())
+                            .unwrap_or(true);
+
+                        if ui.checkbox(&mut pan_inertia, "Pan Inertia").changed() {
+                            if let Err(err) =
+                                config.modify(ConfigModification::SetPanInertia(pan_inertia))
+                            {
+                                error!("Failed to update pan inertia: {:?}", err);
+                            }
+                        }
+
+                        let mut page_strip = config
+                            .read()
+                            .map(|config| config.page_strip())
+                            .unwrap_or(true);
+
+                        if ui.checkbox(&mut page_strip, "Page Strip").changed() {
+                            if let Err(err) =
+                                config.modify(ConfigModification::SetPageStrip(page_strip))
+                            {
+                                error!("Failed to update page strip: {:?}", err);
+                            }
+                        }
+
+                        let mut smart_crop = config
+                            .read()
+                            .map(|config| config.smart_crop())
+                            .unwrap_or(false);
+
+                        if ui
+                            .checkbox(&mut smart_crop, "Smart Crop Suggestions")
+                            .on_hover_text(
+                                "Crop photos around their subject when they're placed into a \
+                                 spot of a very different shape",
+                            )
+                            .changed()
+                        {
+                            if let Err(err) =
+                                config.modify(ConfigModification::SetSmartCrop(smart_crop))
+                            {
+                                error!("Failed to update smart crop: {:?}", err);
+                            }
+                        }
+
+                        ui.menu_button("Mouse & Trackpad", |ui| {
+                            Self::pan_zoom_settings_ui(config, ui);
+                        });
+
+                        ui.menu_button("Arrow Key Nudge", |ui| {
+                            Self::nudge_settings_ui(config, ui);
+                        });
+
+                        ui.menu_button("UI Scale", |ui| {
+                            Self::ui_scale_settings_ui(config, ui);
+                        });
+
+                        let (theme_mode, accent_color) = config
+                            .read()
+                            .map(|config| (config.theme_mode(), config.accent_color()))
+                            .unwrap_or_default();
+
+                        ui.separator();
+
+                        ui.menu_button("Theme", |ui| {
+                            for mode in ThemeMode::iter() {
+                                if ui.radio(theme_mode == mode, mode.to_string()).clicked() {
+                                    if let Err(err) =
+                                        config.modify(ConfigModification::SetThemeMode(mode))
+                                    {
+                                        error!("Failed to update theme: {:?}", err);
+                                    }
+                                    ui.close_menu();
+                                }
+                            }
+                        });
+
+                        ui.menu_button("Accent Color", |ui| {
+                            for accent in AccentColor::iter() {
+                                let clicked = ui
+                                    .horizontal(|ui| {
+                                        let (swatch, _) = ui
+                                            .allocate_exact_size(Vec2::splat(12.0), Sense::hover());
+                                        ui.painter().rect_filled(swatch, 2.0, accent.color());
+                                        ui.radio(accent_color == accent, accent.to_string())
+                                            .clicked()
+                                    })
+                                    .inner;
+
+                                if clicked {
+                                    if let Err(err) =
+                                        config.modify(ConfigModification::SetAccentColor(accent))
+                                    {
+                                        error!("Failed to update accent color: {:?}", err);
+                                    }
+                                    ui.close_menu();
+                                }
+                            }
+                        });
+                    });
+
+                    ui.separator();
+
+                    let unseen_errors = ErrorSink::unseen_count();
+                    let error_log_label = if unseen_errors > 0 {
+                        format!("Error Log ({})", unseen_errors)
+                    } else {
+                        "Error Log".to_string()
+                    };
+                    if ui.button(error_log_label).clicked() {
+                        ErrorSink::open_log();
+                        ui.close_menu();
+                    }
                 });
 
+                if let Some(edit) = &self.edit {
+                    ui.menu_button("Workspace", |ui| {
+                        Self::workspace_menu_ui(&mut edit.write().unwrap(), ui);
+                    });
+                }
+
                 ui.menu_button("Debug", |ui| {
                     Dependency::<DebugSettings>::get().with_lock_mut(|debug_settings| {
                         fn enabled_disabled_suffix(enabled: bool) -> &'static str {
@@ -427,16 +1791,29 @@ impl Scene for OrganizeEditScene {
                         {
                             debug_settings.show_quick_layout_order.toggle();
                         }
+
+                        ui.separator();
+
+                        if ui.button("Diagnostics...").clicked() {
+                            debug_settings.show_diagnostics = true;
+                            ui.close_menu();
+                        }
                     });
                 })
             });
 
             ui.add_space(10.0);
 
-            let scene_response = ui
-                .allocate_ui(
-                    Vec2::new(ui.available_width(), ui.available_height()),
-                    |ui| match &self.current {
+            // Each view's widgets are kept apart by its id so their scroll positions are still
+            // there when switching back
+            let scene_id = match &self.current {
+                Either::Left(_) => SceneId::Gallery,
+                Either::Right(_) => SceneId::Canvas,
+            };
+
+            let scene_response =
+                TransitionAnimation::show(&mut self.transition, ui, scene_id, |ui| {
+                    match &self.current {
                         Either::Left(organize) => {
                             let mut organize = organize.write().unwrap();
                             organize.ui(ui)
@@ -445,16 +1822,38 @@ impl Scene for OrganizeEditScene {
                             let mut edit = edit.write().unwrap();
                             edit.ui(ui)
                         }
-                    },
-                )
-                .inner;
+                    }
+                });
+
+            self.place_pending_panorama();
+            self.show_pending_placement();
+            self.apply_orientation_changes();
+            self.diagnostics_ui(ui.ctx());
+
+            // The organize gallery shows where photos are placed too
+            if let (Either::Left(organize), Some(edit)) = (&self.current, &self.edit) {
+                organize
+                    .write()
+                    .unwrap()
+                    .state
+                    .image_gallery_state
+                    .update_placements(&edit.read().unwrap().state.pages_state);
+            }
 
             // Act as the navigator for certain scene transitions
             // TODO: Is there a more elegant way to do this?
             match scene_response {
                 SceneResponse::Push(transition) => match transition {
-                    SceneTransition::Gallery(scene) => {
-                        *self.organize.write().unwrap() = scene;
+                    SceneTransition::Gallery(mut scene) => {
+                        // Picks up where the gallery it replaces was left
+                        {
+                            let mut organize = self.organize.write().unwrap();
+                            let previous = std::mem::replace(&mut *organize, GalleryScene::new());
+                            if let Some(state) = Box::new(previous).into_state() {
+                                scene.restore(state);
+                            }
+                            *organize = scene;
+                        }
                         self.show_organize();
                         SceneResponse::None
                     }
@@ -466,6 +1865,13 @@ impl Scene for OrganizeEditScene {
                             .state
                             .image_gallery_state
                             .clone();
+                        if let Some(edit) = &self.edit {
+                            let previous =
+                                std::mem::replace(&mut *edit.write().unwrap(), CanvasScene::new());
+                            if let Some(state) = Box::new(previous).into_state() {
+                                scene.restore(state);
+                            }
+                        }
                         self.edit = Some(Arc::new(RwLock::new(scene)));
                         self.show_edit();
                         SceneResponse::None
@@ -477,4 +1883,8 @@ impl Scene for OrganizeEditScene {
         })
         .inner
     }
+
+    fn id(&self) -> SceneId {
+        SceneId::OrganizeEdit
+    }
 }
@@ -1,41 +1,47 @@
-use std::collections::HashSet;
-
 use egui::Widget;
 use egui_tiles::UiResponse;
 
 use crate::{
     dependencies::{Dependency, Singleton, SingletonFor},
-    photo::SaveOnDropPhoto,
+    panorama::PanoramaPlacement,
+    photo::{Photo, SaveOnDropPhoto},
     photo_manager::PhotoManager,
     utils::EguiUiExt,
     widget::{
         image_gallery::{ImageGallery, ImageGalleryResponse, ImageGalleryState},
+        pages::PhotoPlacement,
         photo_info::PhotoInfo,
+        smart_collections::SmartCollections,
     },
 };
 
 use super::{
-    viewer_scene::ViewerScene, NavigationRequest, Navigator, Scene, SceneResponse, SceneTransition,
+    viewer_scene::ViewerScene, NavigationRequest, Navigator, Scene, SceneId, SceneResponse,
+    SceneState, SceneTransition,
 };
 
 #[derive(Debug, Clone)]
 pub struct GallerySceneState {
     pub image_gallery_state: ImageGalleryState,
+    /// A panorama placement requested from the gallery, carried out by the edit scene
+    pub pending_panorama: Option<(Photo, PanoramaPlacement)>,
+    /// A placed photo to show in the edit scene, requested from the gallery
+    pub pending_placement: Option<PhotoPlacement>,
 }
 
 impl Default for GallerySceneState {
     fn default() -> Self {
         Self {
-            image_gallery_state: ImageGalleryState {
-                selected_images: HashSet::new(),
-                scale: 1.0,
-            },
+            image_gallery_state: ImageGalleryState::default(),
+            pending_panorama: None,
+            pending_placement: None,
         }
     }
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum GalleryScenePane {
+    Collections,
     Gallery,
     PhotoInfo,
 }
@@ -50,6 +56,9 @@ impl GalleryScene {
     pub fn new() -> Self {
         let mut tiles = egui_tiles::Tiles::default();
 
+        let left_tabs = vec![tiles.insert_pane(GalleryScenePane::Collections)];
+        let left_tabs_id = tiles.insert_tab_tile(left_tabs);
+
         let gallery_pane_id = tiles.insert_pane(GalleryScenePane::Gallery);
 
         let right_tabs = vec![tiles.insert_pane(GalleryScenePane::PhotoInfo)];
@@ -57,9 +66,10 @@ impl GalleryScene {
 
         let mut linear_layout = egui_tiles::Linear::new(
             egui_tiles::LinearDir::Horizontal,
-            vec![gallery_pane_id, right_tabs_id],
+            vec![left_tabs_id, gallery_pane_id, right_tabs_id],
         );
 
+        linear_layout.shares.set_share(left_tabs_id, 0.15);
         linear_layout.shares.set_share(right_tabs_id, 0.2);
 
         Self {
@@ -91,6 +101,29 @@ impl Scene for GalleryScene {
             None => SceneResponse::None,
         }
     }
+
+    fn id(&self) -> SceneId {
+        SceneId::Gallery
+    }
+
+    // The selection, thumbnail size and panes, placements still to be made are dropped
+    fn into_state(self: Box<Self>) -> Option<SceneState> {
+        Some(SceneState::Gallery {
+            gallery_state: self.state.image_gallery_state,
+            tree: self.tree,
+        })
+    }
+
+    fn restore(&mut self, state: SceneState) {
+        if let SceneState::Gallery {
+            gallery_state,
+            tree,
+        } = state
+        {
+            self.state.image_gallery_state = gallery_state;
+            self.tree = tree;
+        }
+    }
 }
 
 struct GalleryTreeBehavior<'a> {
@@ -101,6 +134,7 @@ struct GalleryTreeBehavior<'a> {
 impl<'a> egui_tiles::Behavior<GalleryScenePane> for GalleryTreeBehavior<'a> {
     fn tab_title_for_pane(&mut self, _pane: &GalleryScenePane) -> egui::WidgetText {
         match _pane {
+            GalleryScenePane::Collections => "Collections".into(),
             GalleryScenePane::Gallery => "Gallery".into(),
             GalleryScenePane::PhotoInfo => "Photo Info".into(),
         }
@@ -113,6 +147,7 @@ impl<'a> egui_tiles::Behavior<GalleryScenePane> for GalleryTreeBehavior<'a> {
         component: &mut GalleryScenePane,
     ) -> egui_tiles::UiResponse {
         match component {
+            GalleryScenePane::Collections => SmartCollections::show(ui),
             GalleryScenePane::Gallery => {
                 let gallery_response =
                     ImageGallery::show(ui, &mut self.scene_state.image_gallery_state);
@@ -126,19 +161,34 @@ impl<'a> egui_tiles::Behavior<GalleryScenePane> for GalleryTreeBehavior<'a> {
                         ImageGalleryResponse::SelectPhotoSecondaryAction(_) => {
                             // Do nothing for now
                         }
+                        ImageGalleryResponse::PlacePanorama(photo, placement) => {
+                            self.scene_state.pending_panorama = Some((photo, placement));
+                        }
+                        ImageGalleryResponse::GoToPlacement(placement) => {
+                            self.scene_state.pending_placement = Some(placement);
+                        }
                     }
                 }
             }
             GalleryScenePane::PhotoInfo => {
                 let photo_manager: Singleton<PhotoManager> = Dependency::get();
 
-                let gallery_state = &self.scene_state.image_gallery_state;
+                let gallery_state = &mut self.scene_state.image_gallery_state;
 
-                if let Some(selected_image) = gallery_state.selected_images.iter().next() {
+                if let Some(selected_image) = gallery_state.selected_images.iter().next().cloned() {
                     let mut photo = photo_manager
-                        .with_lock(|photo_manager| photo_manager.photos[selected_image].clone());
+                        .with_lock(|photo_manager| photo_manager.photos[&selected_image].clone());
 
                     PhotoInfo::new(SaveOnDropPhoto::new(&mut photo)).show(ui);
+
+                    // Follow the stack if another version was made primary, since the selected
+                    // one is no longer shown in the gallery
+                    let primary = photo_manager
+                        .with_lock(|photo_manager| photo_manager.stack_primary(&selected_image));
+                    if let Some(primary) = primary.filter(|primary| *primary != selected_image) {
+                        gallery_state.selected_images.remove(&selected_image);
+                        gallery_state.selected_images.insert(primary);
+                    }
                 } else {
                     ui.both_centered(|ui| {
                         ui.heading("Nothing selected");
@@ -0,0 +1,700 @@
+use eframe::egui::{self, load::SizedTexture};
+use egui::{
+    Align, Color32, ComboBox, DragValue, FontId, Grid, Key, Layout, Pos2, Rect, RichText, Sense,
+    Stroke, TextEdit, Ui, Vec2,
+};
+use strum::IntoEnumIterator;
+use strum_macros::{Display, EnumIter};
+
+use crate::{
+    auto_persisting::AutoPersisting,
+    config::{Config, ConfigModification},
+    dependencies::{Dependency, Singleton, SingletonFor},
+    error_sink::ErrorSink,
+    model::{layer_constraints::LayerConstraints, page_preset::PagePreset},
+    photo::Photo,
+    photo_manager::PhotoManager,
+    project_settings::ProjectSettingsManager,
+    template::{self, autofill::TemplateAutofill, Template, TemplateRegion, TemplateRegionKind},
+    theme,
+    widget::canvas_info::pin_control::PinControl,
+};
+
+use super::{Scene, SceneId, SceneResponse};
+
+// Smallest region that can be drawn, relative to the page
+const MIN_REGION_SIZE: f32 = 0.02;
+
+const HANDLE_SIZE: f32 = 10.0;
+
+const PAGE_PADDING: f32 = 20.0;
+
+// In page pixels, like the built in templates
+const DEFAULT_FONT_SIZE: f32 = 100.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Display, EnumIter)]
+enum RegionTool {
+    Select,
+    #[strum(to_string = "Image Region")]
+    Image,
+    #[strum(to_string = "Text Region")]
+    Text,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum RegionDrag {
+    // A new region from `start` to the pointer, relative to the page
+    Draw { start: Pos2 },
+    Move { region: usize, grab_offset: Vec2 },
+    Resize { region: usize },
+}
+
+/// Lets users design their own templates by drawing image and text regions on a page. Saved
+/// templates are kept in the config and listed after the built in ones.
+pub struct TemplateEditorScene {
+    template: Template,
+    // The name the template is saved under, None until it's saved for the first time
+    saved_name: Option<String>,
+    tool: RegionTool,
+    selected_region: Option<usize>,
+    drag: Option<RegionDrag>,
+    // Fill the image regions with photos from the library to see how the template looks in use
+    preview_photos: bool,
+    error: Option<String>,
+}
+
+impl TemplateEditorScene {
+    pub fn new() -> Self {
+        let page = Dependency::<ProjectSettingsManager>::get().with_lock(|manager| {
+            manager
+                .project_settings
+                .default_page
+                .clone()
+                .unwrap_or_default()
+        });
+
+        Self::with_template(
+            Template {
+                name: "New Template".to_string(),
+                page,
+                regions: Vec::new(),
+            },
+            None,
+        )
+    }
+
+    pub fn edit(template: Template) -> Self {
+        let saved_name = Some(template.name.clone());
+        Self::with_template(template, saved_name)
+    }
+
+    fn with_template(template: Template, saved_name: Option<String>) -> Self {
+        Self {
+            template,
+            saved_name,
+            tool: RegionTool::Select,
+            selected_region: None,
+            drag: None,
+            preview_photos: false,
+            error: None,
+        }
+    }
+
+    // Returns true when the editor should close
+    fn toolbar_ui(&mut self, ui: &mut Ui) -> bool {
+        let mut close = false;
+
+        ui.horizontal(|ui| {
+            ui.label("Name:");
+            ui.add(TextEdit::singleline(&mut self.template.name).desired_width(180.0));
+
+            ui.separator();
+
+            self.page_size_ui(ui);
+
+            ui.separator();
+
+            for tool in RegionTool::iter() {
+                ui.selectable_value(&mut self.tool, tool, tool.to_string());
+            }
+
+            ui.separator();
+
+            ui.checkbox(&mut self.preview_photos, "Preview with Photos");
+
+            ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                if ui.button("Save").clicked() && self.save() {
+                    close = true;
+                }
+
+                if ui.button("Cancel").clicked() {
+                    close = true;
+                }
+
+                if let Some(error) = &self.error {
+                    ui.label(RichText::new(error).color(theme::color::ERROR));
+                }
+            });
+        });
+
+        close
+    }
+
+    fn page_size_ui(&mut self, ui: &mut Ui) {
+        let user_presets = Dependency::<AutoPersisting<Config>>::get().with_lock_mut(|config| {
+            config
+                .read()
+                .map(|config| config.page_presets().to_vec())
+                .unwrap_or_default()
+        });
+        let presets: Vec<PagePreset> = user_presets
+            .into_iter()
+            .chain(PagePreset::builtin())
+            .collect();
+
+        let page = &self.template.page;
+        let selected_text = presets
+            .iter()
+            .find(|preset| preset.matches(page))
+            .map(|preset| preset.name.clone())
+            .unwrap_or_else(|| PagePreset::size_label(page.size(), page.unit()));
+
+        ComboBox::from_label("Page Size")
+            .selected_text(selected_text)
+            .width(180.0)
+            .show_ui(ui, |ui| {
+                for preset in &presets {
+                    if ui
+                        .selectable_label(preset.matches(&self.template.page), &preset.name)
+                        .clicked()
+                    {
+                        // Regions are relative to the page so they follow the new size
+                        self.template.page = preset.page();
+                    }
+                }
+            });
+    }
+
+    fn region_ui(&mut self, ui: &mut Ui) {
+        let Some(index) = self
+            .selected_region
+            .filter(|index| *index < self.template.regions.len())
+        else {
+            ui.label(RichText::new("Draw a region on the page or click one to edit it").weak());
+            ui.add_space(10.0);
+            ui.label(format!("{} region(s)", self.template.regions.len()));
+            return;
+        };
+
+        let mut delete = false;
+        let mut duplicate = false;
+
+        let region = &mut self.template.regions[index];
+
+        ui.label(
+            RichText::new(match region.kind {
+                TemplateRegionKind::Image => "Image Region",
+                TemplateRegionKind::Text { .. } => "Text Region",
+            })
+            .strong(),
+        );
+
+        Grid::new("template_region")
+            .num_columns(2)
+            .spacing([10.0, 5.0])
+            .show(ui, |ui| {
+                ui.label("X:");
+                Self::percent_drag(ui, &mut region.relative_position.x);
+                ui.end_row();
+
+                ui.label("Y:");
+                Self::percent_drag(ui, &mut region.relative_position.y);
+                ui.end_row();
+
+                ui.label("Width:");
+                Self::percent_drag(ui, &mut region.relative_size.x);
+                ui.end_row();
+
+                ui.label("Height:");
+                Self::percent_drag(ui, &mut region.relative_size.y);
+                ui.end_row();
+
+                if let TemplateRegionKind::Text {
+                    sample_text,
+                    font_size,
+                    autofill,
+                } = &mut region.kind
+                {
+                    ui.label("Font Size:");
+                    ui.add(DragValue::new(font_size).range(1.0..=1000.0).speed(1.0));
+                    ui.end_row();
+
+                    ui.label("Sample Text:");
+                    ui.add(TextEdit::multiline(sample_text).desired_rows(4));
+                    ui.end_row();
+
+                    ui.label("Autofill:");
+                    Self::autofill_combo(ui, autofill);
+                    ui.end_row();
+                }
+            });
+
+        Self::clamp_region(region);
+
+        ui.add_space(10.0);
+        ui.label(RichText::new("Pin to Page").strong());
+        PinControl::show(ui, &mut region.constraints);
+
+        ui.add_space(10.0);
+        ui.horizontal(|ui| {
+            if ui.button("Duplicate").clicked() {
+                duplicate = true;
+            }
+
+            if ui.button("Delete").clicked() {
+                delete = true;
+            }
+        });
+
+        if duplicate {
+            let mut copy = self.template.regions[index].clone();
+            copy.relative_position += Vec2::splat(MIN_REGION_SIZE);
+            Self::clamp_region(&mut copy);
+            self.template.regions.push(copy);
+            self.selected_region = Some(self.template.regions.len() - 1);
+        }
+
+        if delete {
+            self.delete_selected_region();
+        }
+    }
+
+    fn percent_drag(ui: &mut Ui, value: &mut f32) {
+        let mut percent = *value * 100.0;
+        if ui
+            .add(
+                DragValue::new(&mut percent)
+                    .range(0.0..=100.0)
+                    .speed(0.5)
+                    .suffix("%"),
+            )
+            .changed()
+        {
+            *value = percent / 100.0;
+        }
+    }
+
+    fn autofill_combo(ui: &mut Ui, autofill: &mut Option<TemplateAutofill>) {
+        ComboBox::from_id_salt("template_region_autofill")
+            .selected_text(match autofill {
+                Some(autofill) => autofill.to_string(),
+                None => "None".to_string(),
+            })
+            .show_ui(ui, |ui| {
+                ui.selectable_value(autofill, None, "None")
+                    .on_hover_text("Keep the sample text until it's typed over");
+                for option in TemplateAutofill::iter() {
+                    ui.selectable_value(autofill, Some(option), option.to_string())
+                        .on_hover_text(option.description());
+                }
+            })
+            .response
+            .on_hover_text("Fill the region in from the photos placed in the template");
+    }
+
+    // Keeps the region on the page
+    fn clamp_region(region: &mut TemplateRegion) {
+        region.relative_size = region
+            .relative_size
+            .clamp(Vec2::splat(MIN_REGION_SIZE), Vec2::splat(1.0));
+        region.relative_position = region.relative_position.clamp(
+            Pos2::ZERO,
+            (Vec2::splat(1.0) - region.relative_size).to_pos2(),
+        );
+    }
+
+    fn delete_selected_region(&mut self) {
+        if let Some(index) = self.selected_region.take() {
+            if index < self.template.regions.len() {
+                self.template.regions.remove(index);
+            }
+        }
+        self.drag = None;
+    }
+
+    fn page_ui(&mut self, ui: &mut Ui) {
+        let available_rect = ui.available_rect_before_wrap();
+        let response = ui.allocate_rect(available_rect, Sense::click_and_drag());
+
+        let page_area = available_rect.shrink(PAGE_PADDING);
+        let aspect_ratio = self.template.page.aspect_ratio();
+        let page_size = if page_area.width() / page_area.height() > aspect_ratio {
+            Vec2::new(page_area.height() * aspect_ratio, page_area.height())
+        } else {
+            Vec2::new(page_area.width(), page_area.width() / aspect_ratio)
+        };
+        let page_rect = Rect::from_center_size(page_area.center(), page_size);
+
+        self.handle_pointer(&response, page_rect);
+
+        let painter = ui.painter_at(available_rect);
+        painter.rect_filled(page_rect, 0.0, Color32::WHITE);
+
+        let photos = if self.preview_photos {
+            self.preview_textures(ui.ctx())
+        } else {
+            Vec::new()
+        };
+
+        // Font sizes are in page pixels
+        let scale = page_rect.width() / self.template.page.size_pixels().x;
+        let mut image_index = 0;
+
+        for (index, region) in self.template.regions.iter().enumerate() {
+            let region_rect = Self::region_rect(page_rect, region);
+
+            match &region.kind {
+                TemplateRegionKind::Image => {
+                    match photos.get(image_index).copied().flatten() {
+                        Some(texture) => {
+                            painter.image(
+                                texture.id,
+                                region_rect,
+                                Self::cover_uv(texture.size, region_rect.size()),
+                                Color32::WHITE,
+                            );
+                        }
+                        None => {
+                            painter.rect_filled(region_rect, 0.0, Color32::LIGHT_BLUE);
+                        }
+                    }
+                    image_index += 1;
+                }
+                TemplateRegionKind::Text {
+                    sample_text,
+                    font_size,
+                    ..
+                } => {
+                    painter.rect_stroke(region_rect, 0.0, Stroke::new(1.0, Color32::DARK_GRAY));
+
+                    let galley = painter.layout(
+                        sample_text.clone(),
+                        FontId::proportional((*font_size * scale).max(1.0)),
+                        Color32::BLACK,
+                        region_rect.width(),
+                    );
+                    painter.with_clip_rect(region_rect).galley(
+                        region_rect.min,
+                        galley,
+                        Color32::BLACK,
+                    );
+                }
+            }
+
+            if Some(index) == self.selected_region {
+                let selection_color = ui.visuals().selection.stroke.color;
+                painter.rect_stroke(region_rect, 0.0, Stroke::new(2.0, selection_color));
+                painter.rect_filled(Self::handle_rect(region_rect), 0.0, selection_color);
+            }
+        }
+
+        if let (Some(RegionDrag::Draw { start }), Some(pointer)) =
+            (self.drag, response.interact_pointer_pos())
+        {
+            let end = Self::relative_position(page_rect, pointer);
+            let draw_rect = Rect::from_two_pos(
+                page_rect.min + start.to_vec2() * page_rect.size(),
+                page_rect.min + end.to_vec2() * page_rect.size(),
+            );
+            painter.rect_stroke(
+                draw_rect,
+                0.0,
+                Stroke::new(1.0, ui.visuals().selection.stroke.color),
+            );
+        }
+
+        if self.preview_photos && photos.is_empty() {
+            painter.text(
+                page_rect.center_bottom() + Vec2::new(0.0, PAGE_PADDING / 2.0),
+                egui::Align2::CENTER_CENTER,
+                "Import some photos to preview the template with",
+                FontId::proportional(12.0),
+                ui.visuals().weak_text_color(),
+            );
+        }
+    }
+
+    fn handle_pointer(&mut self, response: &egui::Response, page_rect: Rect) {
+        // Drags keep going when the pointer leaves the page area
+        let Some(pointer) = response.ctx.input(|input| input.pointer.latest_pos()) else {
+            return;
+        };
+        let position = Self::relative_position(page_rect, pointer);
+
+        if response.drag_started() {
+            self.drag = match self.tool {
+                RegionTool::Image | RegionTool::Text => Some(RegionDrag::Draw { start: position }),
+                RegionTool::Select => self.start_select_drag(page_rect, pointer, position),
+            };
+        } else if response.clicked() && self.tool == RegionTool::Select {
+            self.selected_region = self.region_at(position);
+        }
+
+        if response.dragged() {
+            match self.drag {
+                Some(RegionDrag::Move {
+                    region,
+                    grab_offset,
+                }) => {
+                    if let Some(region) = self.template.regions.get_mut(region) {
+                        region.relative_position = position - grab_offset;
+                        Self::clamp_region(region);
+                    }
+                }
+                Some(RegionDrag::Resize { region }) => {
+                    if let Some(region) = self.template.regions.get_mut(region) {
+                        region.relative_size = position - region.relative_position;
+                        Self::clamp_region(region);
+                    }
+                }
+                Some(RegionDrag::Draw { .. }) | None => {}
+            }
+        }
+
+        if response.drag_stopped() {
+            if let Some(RegionDrag::Draw { start }) = self.drag {
+                self.finish_drawing(Rect::from_two_pos(start, position));
+            }
+            self.drag = None;
+        }
+    }
+
+    fn start_select_drag(
+        &mut self,
+        page_rect: Rect,
+        pointer: Pos2,
+        position: Pos2,
+    ) -> Option<RegionDrag> {
+        // The selected region's resize handle wins over any region on top of it
+        if let Some(index) = self.selected_region {
+            if let Some(region) = self.template.regions.get(index) {
+                if Self::handle_rect(Self::region_rect(page_rect, region))
+                    .expand(2.0)
+                    .contains(pointer)
+                {
+                    return Some(RegionDrag::Resize { region: index });
+                }
+            }
+        }
+
+        self.selected_region = self.region_at(position);
+        self.selected_region.map(|index| RegionDrag::Move {
+            region: index,
+            grab_offset: position - self.template.regions[index].relative_position,
+        })
+    }
+
+    fn finish_drawing(&mut self, rect: Rect) {
+        if rect.width() < MIN_REGION_SIZE || rect.height() < MIN_REGION_SIZE {
+            return;
+        }
+
+        let kind = match self.tool {
+            RegionTool::Text => TemplateRegionKind::Text {
+                sample_text: "Text".to_string(),
+                font_size: DEFAULT_FONT_SIZE,
+                autofill: None,
+            },
+            RegionTool::Image | RegionTool::Select => TemplateRegionKind::Image,
+        };
+
+        self.template.regions.push(TemplateRegion {
+            relative_position: rect.min,
+            relative_size: rect.size(),
+            constraints: LayerConstraints::default(),
+            kind,
+        });
+        self.selected_region = Some(self.template.regions.len() - 1);
+        self.tool = RegionTool::Select;
+    }
+
+    // The topmost region under the position
+    fn region_at(&self, position: Pos2) -> Option<usize> {
+        self.template.regions.iter().rposition(|region| {
+            Rect::from_min_size(region.relative_position, region.relative_size).contains(position)
+        })
+    }
+
+    fn relative_position(page_rect: Rect, pointer: Pos2) -> Pos2 {
+        ((pointer - page_rect.min) / page_rect.size())
+            .to_pos2()
+            .clamp(Pos2::ZERO, Pos2::new(1.0, 1.0))
+    }
+
+    fn region_rect(page_rect: Rect, region: &TemplateRegion) -> Rect {
+        Rect::from_min_size(
+            page_rect.min + region.relative_position.to_vec2() * page_rect.size(),
+            region.relative_size * page_rect.size(),
+        )
+    }
+
+    fn handle_rect(region_rect: Rect) -> Rect {
+        Rect::from_center_size(region_rect.max, Vec2::splat(HANDLE_SIZE))
+    }
+
+    // Crops the texture to cover the region like a photo placed with Fill
+    fn cover_uv(texture_size: Vec2, region_size: Vec2) -> Rect {
+        let texture_aspect = texture_size.x / texture_size.y;
+        let region_aspect = region_size.x / region_size.y;
+
+        if texture_aspect > region_aspect {
+            let width = region_aspect / texture_aspect;
+            Rect::from_min_max(
+                Pos2::new((1.0 - width) / 2.0, 0.0),
+                Pos2::new((1.0 + width) / 2.0, 1.0),
+            )
+        } else {
+            let height = texture_aspect / region_aspect;
+            Rect::from_min_max(
+                Pos2::new(0.0, (1.0 - height) / 2.0),
+                Pos2::new(1.0, (1.0 + height) / 2.0),
+            )
+        }
+    }
+
+    // Thumbnails of the first photos in the library, one per image region
+    fn preview_textures(&self, ctx: &egui::Context) -> Vec<Option<SizedTexture>> {
+        let image_regions = self
+            .template
+            .regions
+            .iter()
+            .filter(|region| region.kind == TemplateRegionKind::Image)
+            .count();
+
+        Dependency::<PhotoManager>::get().with_lock_mut(|photo_manager| {
+            let photos: Vec<Photo> = photo_manager
+                .photos
+                .values()
+                .take(image_regions)
+                .cloned()
+                .collect();
+
+            photos
+                .iter()
+                .map(|photo| {
+                    photo_manager
+                        .thumbnail_texture_for(photo, ctx)
+                        .ok()
+                        .flatten()
+                })
+                .collect()
+        })
+    }
+
+    fn handle_keys(&mut self, ui: &Ui) {
+        // Keys go to the text fields while one is being edited
+        if ui.ctx().memory(|memory| memory.focused().is_some()) {
+            return;
+        }
+
+        ui.input(|input| {
+            if input.key_pressed(Key::Delete) || input.key_pressed(Key::Backspace) {
+                self.delete_selected_region();
+            }
+
+            if input.key_pressed(Key::Escape) {
+                self.selected_region = None;
+                self.tool = RegionTool::Select;
+            }
+        });
+    }
+
+    // Returns true when the template was saved
+    fn save(&mut self) -> bool {
+        let name = self.template.name.trim().to_string();
+
+        if name.is_empty() {
+            self.error = Some("Give the template a name".to_string());
+            return false;
+        }
+
+        if self.template.regions.is_empty() {
+            self.error = Some("Draw at least one region".to_string());
+            return false;
+        }
+
+        let config: Singleton<AutoPersisting<Config>> = Dependency::get();
+
+        let user_templates = config.with_lock_mut(|config| {
+            config
+                .read()
+                .map(|config| config.templates().to_vec())
+                .unwrap_or_default()
+        });
+        let name_taken = template::BUILT_IN
+            .iter()
+            .any(|template| template.name == name)
+            || user_templates.iter().any(|template| {
+                template.name == name && self.saved_name.as_ref() != Some(&template.name)
+            });
+
+        if name_taken {
+            self.error = Some(format!("There's already a template named \"{}\"", name));
+            return false;
+        }
+
+        self.template.name = name;
+
+        let saved = config.with_lock_mut(|config| {
+            config.modify(ConfigModification::SaveTemplate(
+                self.saved_name.clone(),
+                self.template.clone(),
+            ))
+        });
+
+        match saved {
+            Ok(()) => {
+                self.saved_name = Some(self.template.name.clone());
+                self.error = None;
+                true
+            }
+            Err(err) => {
+                ErrorSink::report("Couldn't save the template", err);
+                false
+            }
+        }
+    }
+}
+
+impl Scene for TemplateEditorScene {
+    fn ui(&mut self, ui: &mut Ui) -> SceneResponse {
+        let mut close = false;
+
+        egui::TopBottomPanel::top("template_editor_toolbar").show_inside(ui, |ui| {
+            close = self.toolbar_ui(ui);
+        });
+
+        egui::SidePanel::right("template_editor_region")
+            .resizable(false)
+            .default_width(260.0)
+            .show_inside(ui, |ui| {
+                self.region_ui(ui);
+            });
+
+        egui::CentralPanel::default().show_inside(ui, |ui| {
+            self.page_ui(ui);
+        });
+
+        self.handle_keys(ui);
+
+        if close {
+            SceneResponse::Pop
+        } else {
+            SceneResponse::None
+        }
+    }
+
+    fn id(&self) -> SceneId {
+        SceneId::TemplateEditor
+    }
+}
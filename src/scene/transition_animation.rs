@@ -0,0 +1,82 @@
+use std::hash::Hash;
+
+use egui::{emath::easing, Ui, UiBuilder, Vec2};
+
+// How long a scene takes to come in, in seconds
+const DURATION: f64 = 0.2;
+// How far a scene slides in from, as a fraction of its width
+const SLIDE_DISTANCE: f32 = 0.04;
+
+/// Which way a scene slides in. Going forward to a scene brings it in from the right, going
+/// back brings it in from the left.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransitionDirection {
+    Forward,
+    Back,
+}
+
+/// Slides and fades in a scene that was just switched to so the change isn't abrupt
+#[derive(Debug, Clone, Copy)]
+pub struct TransitionAnimation {
+    direction: TransitionDirection,
+    // Set the first frame the scene is shown, switching scenes happens outside of a frame
+    started_at: Option<f64>,
+}
+
+impl TransitionAnimation {
+    pub fn new(direction: TransitionDirection) -> Self {
+        Self {
+            direction,
+            started_at: None,
+        }
+    }
+
+    /// Shows a scene in the rest of `ui`, sliding and fading in while `animation` runs. The
+    /// animation is cleared once it's done. `id_salt` identifies the scene so the state of its
+    /// widgets, like scroll positions, is kept apart from other scenes shown in the same place.
+    pub fn show<R>(
+        animation: &mut Option<Self>,
+        ui: &mut Ui,
+        id_salt: impl Hash,
+        add_contents: impl FnOnce(&mut Ui) -> R,
+    ) -> R {
+        let rect = ui.available_rect_before_wrap();
+        let time = ui.input(|input| input.time);
+
+        let (progress, direction) = match animation {
+            Some(animation) => {
+                let started_at = *animation.started_at.get_or_insert(time);
+                (
+                    ((time - started_at) / DURATION).clamp(0.0, 1.0) as f32,
+                    animation.direction,
+                )
+            }
+            None => (1.0, TransitionDirection::Forward),
+        };
+
+        if progress >= 1.0 {
+            *animation = None;
+        } else {
+            ui.ctx().request_repaint();
+        }
+
+        let eased = easing::cubic_out(progress);
+        let side = match direction {
+            TransitionDirection::Forward => 1.0,
+            TransitionDirection::Back => -1.0,
+        };
+        let offset = Vec2::new((1.0 - eased) * SLIDE_DISTANCE * rect.width() * side, 0.0);
+
+        ui.allocate_new_ui(
+            UiBuilder::new()
+                .id_salt(id_salt)
+                .max_rect(rect.translate(offset)),
+            |ui| {
+                ui.set_clip_rect(rect.intersect(ui.clip_rect()));
+                ui.multiply_opacity(eased);
+                add_contents(ui)
+            },
+        )
+        .inner
+    }
+}
@@ -1,8 +1,9 @@
-use egui::Widget;
+use egui::{Button, Widget};
 use egui_tiles::UiResponse;
 
 use crate::{
     dependencies::{Dependency, Singleton, SingletonFor},
+    model::color_label::ColorLabel,
     photo::{Photo, SaveOnDropPhoto},
     photo_manager::PhotoManager,
     widget::{
@@ -11,11 +12,18 @@ use crate::{
     },
 };
 
-use super::{NavigationRequest, Navigator, Scene, SceneResponse};
+use super::{NavigationRequest, Navigator, Scene, SceneId, SceneResponse, SceneState};
+
+// A photo pinned to flip back to while moving through the others, for comparing sharpness
+struct Comparison {
+    pinned: Photo,
+    showing_pinned: bool,
+}
 
 pub struct ViewerSceneState {
     photo: Photo,
     viewer_state: ImageViewerState,
+    comparison: Option<Comparison>,
 }
 
 impl ViewerSceneState {
@@ -23,8 +31,79 @@ impl ViewerSceneState {
         Self {
             photo,
             viewer_state: ImageViewerState::default(),
+            comparison: None,
+        }
+    }
+
+    fn shown_photo(&mut self) -> &mut Photo {
+        match &mut self.comparison {
+            Some(comparison) if comparison.showing_pinned => &mut comparison.pinned,
+            _ => &mut self.photo,
+        }
+    }
+
+    fn toggle_compare(&mut self) {
+        match self.comparison.take() {
+            // Stay on whichever photo was being looked at
+            Some(comparison) if comparison.showing_pinned => self.photo = comparison.pinned,
+            Some(_) => {}
+            None => {
+                self.comparison = Some(Comparison {
+                    pinned: self.photo.clone(),
+                    showing_pinned: false,
+                })
+            }
+        }
+    }
+
+    fn swap_compare(&mut self) {
+        if let Some(comparison) = &mut self.comparison {
+            comparison.showing_pinned = !comparison.showing_pinned;
+        }
+    }
+
+    // Moving through the photos always shows the unpinned side
+    fn show_unpinned(&mut self) {
+        if let Some(comparison) = &mut self.comparison {
+            comparison.showing_pinned = false;
         }
     }
+
+    fn toolbar(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            if ui
+                .selectable_label(self.viewer_state.actual_size, "100%")
+                .on_hover_text("Show the photo's actual pixels (Z or double-click)")
+                .clicked()
+            {
+                self.viewer_state.toggle_actual_size();
+            }
+
+            ui.toggle_value(&mut self.viewer_state.show_loupe, "Loupe")
+                .on_hover_text("Magnify the photo under the cursor (L)");
+
+            ui.toggle_value(&mut self.viewer_state.show_exif, "Info")
+                .on_hover_text("Show the camera settings over the photo (I)");
+
+            ui.separator();
+
+            if ui
+                .selectable_label(self.comparison.is_some(), "Compare")
+                .on_hover_text("Pin this photo to compare the others against (C)")
+                .clicked()
+            {
+                self.toggle_compare();
+            }
+
+            if ui
+                .add_enabled(self.comparison.is_some(), Button::new("Swap A/B"))
+                .on_hover_text("Switch between the pinned photo and this one (X)")
+                .clicked()
+            {
+                self.swap_compare();
+            }
+        });
+    }
 }
 
 pub enum ViewerScenePane {
@@ -79,6 +158,26 @@ impl Scene for ViewerScene {
             None => SceneResponse::None,
         }
     }
+
+    fn id(&self) -> SceneId {
+        SceneId::Viewer
+    }
+
+    // The zoom, overlays and panes carry over to the next photo opened, like they do when moving
+    // between photos
+    fn into_state(self: Box<Self>) -> Option<SceneState> {
+        Some(SceneState::Viewer {
+            viewer_state: self.state.viewer_state,
+            tree: self.tree,
+        })
+    }
+
+    fn restore(&mut self, state: SceneState) {
+        if let SceneState::Viewer { viewer_state, tree } = state {
+            self.state.viewer_state = viewer_state;
+            self.tree = tree;
+        }
+    }
 }
 
 struct ViewerTreeBehavior<'a> {
@@ -97,9 +196,33 @@ impl<'a> egui_tiles::Behavior<ViewerScenePane> for ViewerTreeBehavior<'a> {
 
         match pane {
             ViewerScenePane::Viewer => {
-                let viewer_response =
-                    ImageViewer::new(&self.scene_state.photo, &mut self.scene_state.viewer_state)
-                        .show(ui);
+                self.scene_state.toolbar(ui);
+
+                let compare_label = self.scene_state.comparison.as_ref().map(|comparison| {
+                    if comparison.showing_pinned {
+                        format!("A  {}", comparison.pinned.file_name())
+                    } else {
+                        format!("B  {}", self.scene_state.photo.file_name())
+                    }
+                });
+
+                let ViewerSceneState {
+                    photo,
+                    viewer_state,
+                    comparison,
+                } = &mut *self.scene_state;
+                let shown_photo = match comparison {
+                    Some(comparison) if comparison.showing_pinned => &comparison.pinned,
+                    _ => &*photo,
+                };
+
+                let mut viewer = ImageViewer::new(shown_photo, viewer_state);
+                if let Some(compare_label) = compare_label {
+                    viewer = viewer.compare_label(compare_label);
+                }
+                let viewer_response = viewer.show(ui);
+
+                // The zoom and position are kept so the same spot can be checked on each photo
                 if let Some(request) = viewer_response.request {
                     match request {
                         image_viewer::Request::Exit => {
@@ -113,7 +236,7 @@ impl<'a> egui_tiles::Behavior<ViewerScenePane> for ViewerTreeBehavior<'a> {
                                     .unwrap();
 
                                 self.scene_state.photo = prev_photo;
-                                self.scene_state.viewer_state = ImageViewerState::default();
+                                self.scene_state.show_unpinned();
                             });
                         }
                         image_viewer::Request::Next => {
@@ -124,15 +247,29 @@ impl<'a> egui_tiles::Behavior<ViewerScenePane> for ViewerTreeBehavior<'a> {
                                     .unwrap();
 
                                 self.scene_state.photo = next_photo;
-                                self.scene_state.viewer_state = ImageViewerState::default();
+                                self.scene_state.show_unpinned();
                             });
                         }
+                        image_viewer::Request::PinCompare => {
+                            self.scene_state.toggle_compare();
+                        }
+                        image_viewer::Request::SwapCompare => {
+                            self.scene_state.swap_compare();
+                        }
                     }
                 }
             }
             ViewerScenePane::PhotoInfo => {
                 ui.set_max_width(600.0);
-                PhotoInfo::new(SaveOnDropPhoto::new(&mut self.scene_state.photo)).show(ui);
+                let mut photo = SaveOnDropPhoto::new(self.scene_state.shown_photo());
+
+                // Pressing the photo's own label again clears it
+                if let Some(color_label) = ColorLabel::pressed(ui.ctx()) {
+                    photo.color_label =
+                        (photo.color_label != Some(color_label)).then_some(color_label);
+                }
+
+                PhotoInfo::new(photo).show(ui);
             }
         }
 
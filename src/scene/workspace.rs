@@ -0,0 +1,254 @@
+use egui_tiles::{Container, Linear, LinearDir, Tile, TileId, Tiles, Tree};
+use serde::{Deserialize, Serialize};
+use strum::IntoEnumIterator;
+use strum_macros::{Display, EnumIter};
+
+use super::canvas_scene::CanvasScenePane;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Display, EnumIter)]
+pub enum WorkspaceDockPosition {
+    Left,
+    Right,
+    Bottom,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorkspaceDock {
+    pub panes: Vec<CanvasScenePane>,
+    // Size relative to the canvas, which always has a share of 1.0
+    pub share: f32,
+    pub collapsed: bool,
+}
+
+impl WorkspaceDock {
+    fn new(panes: Vec<CanvasScenePane>, share: f32) -> Self {
+        Self {
+            panes,
+            share,
+            collapsed: false,
+        }
+    }
+
+    fn is_shown(&self) -> bool {
+        !self.collapsed && !self.panes.is_empty()
+    }
+}
+
+/// Describes where each pane of the canvas scene is docked so that the arrangement
+/// can be saved in the config and restored later
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorkspaceLayout {
+    pub left: WorkspaceDock,
+    pub right: WorkspaceDock,
+    pub bottom: WorkspaceDock,
+}
+
+impl Default for WorkspaceLayout {
+    fn default() -> Self {
+        Self {
+            left: WorkspaceDock::new(
+                vec![
+                    CanvasScenePane::Gallery,
+                    CanvasScenePane::Pages,
+                    CanvasScenePane::Outline,
+                    CanvasScenePane::Templates,
+                    CanvasScenePane::Assets,
+                    CanvasScenePane::Snippets,
+                    CanvasScenePane::Trash,
+                ],
+                0.2,
+            ),
+            right: WorkspaceDock::new(
+                vec![
+                    CanvasScenePane::Info,
+                    CanvasScenePane::QuickLayout,
+                    CanvasScenePane::Comments,
+                    CanvasScenePane::Guides,
+                    CanvasScenePane::Statistics,
+                ],
+                0.2,
+            ),
+            bottom: WorkspaceDock::new(vec![CanvasScenePane::Tray], 0.15),
+        }
+    }
+}
+
+impl WorkspaceLayout {
+    pub fn dock(&self, position: WorkspaceDockPosition) -> &WorkspaceDock {
+        match position {
+            WorkspaceDockPosition::Left => &self.left,
+            WorkspaceDockPosition::Right => &self.right,
+            WorkspaceDockPosition::Bottom => &self.bottom,
+        }
+    }
+
+    pub fn dock_mut(&mut self, position: WorkspaceDockPosition) -> &mut WorkspaceDock {
+        match position {
+            WorkspaceDockPosition::Left => &mut self.left,
+            WorkspaceDockPosition::Right => &mut self.right,
+            WorkspaceDockPosition::Bottom => &mut self.bottom,
+        }
+    }
+
+    pub fn position_of(&self, pane: &CanvasScenePane) -> Option<WorkspaceDockPosition> {
+        WorkspaceDockPosition::iter().find(|position| self.dock(*position).panes.contains(pane))
+    }
+
+    pub fn move_pane(&mut self, pane: CanvasScenePane, to: WorkspaceDockPosition) {
+        for dock in [&mut self.left, &mut self.right, &mut self.bottom] {
+            dock.panes.retain(|existing| *existing != pane);
+        }
+        self.dock_mut(to).panes.push(pane);
+    }
+}
+
+// Tile ids of the containers created for the layout, used to read the arrangement back out of the tree
+#[derive(Debug, Clone, Default)]
+pub struct WorkspaceTiles {
+    row: Option<TileId>,
+    column: Option<TileId>,
+    left: Option<TileId>,
+    right: Option<TileId>,
+    bottom: Option<TileId>,
+}
+
+pub fn build_tree(layout: &WorkspaceLayout) -> (Tree<CanvasScenePane>, WorkspaceTiles) {
+    let mut tiles = Tiles::default();
+    let mut workspace_tiles = WorkspaceTiles::default();
+
+    let insert_dock = |tiles: &mut Tiles<CanvasScenePane>, dock: &WorkspaceDock| {
+        if !dock.is_shown() {
+            return None;
+        }
+        let children = dock
+            .panes
+            .iter()
+            .map(|pane| tiles.insert_pane(pane.clone()))
+            .collect();
+        Some(tiles.insert_tab_tile(children))
+    };
+
+    workspace_tiles.left = insert_dock(&mut tiles, &layout.left);
+    let canvas_id = tiles.insert_pane(CanvasScenePane::Canvas);
+    workspace_tiles.right = insert_dock(&mut tiles, &layout.right);
+    workspace_tiles.bottom = insert_dock(&mut tiles, &layout.bottom);
+
+    let row_children = [workspace_tiles.left, Some(canvas_id), workspace_tiles.right]
+        .into_iter()
+        .flatten()
+        .collect();
+    let mut row = Linear::new(LinearDir::Horizontal, row_children);
+    if let Some(left) = workspace_tiles.left {
+        row.shares.set_share(left, layout.left.share);
+    }
+    if let Some(right) = workspace_tiles.right {
+        row.shares.set_share(right, layout.right.share);
+    }
+    let row_id = tiles.insert_container(row);
+    workspace_tiles.row = Some(row_id);
+
+    let root = match workspace_tiles.bottom {
+        Some(bottom) => {
+            let mut column = Linear::new(LinearDir::Vertical, vec![row_id, bottom]);
+            column.shares.set_share(bottom, layout.bottom.share);
+            let column_id = tiles.insert_container(column);
+            workspace_tiles.column = Some(column_id);
+            column_id
+        }
+        None => row_id,
+    };
+
+    (Tree::new("canvas_scene_tree", root, tiles), workspace_tiles)
+}
+
+// Reads the current arrangement out of the tree. Panes the user has dragged somewhere other
+// than one of the docks are assigned to the right dock.
+pub fn capture_layout(
+    tree: &Tree<CanvasScenePane>,
+    workspace_tiles: &WorkspaceTiles,
+    previous: &WorkspaceLayout,
+) -> WorkspaceLayout {
+    let mut layout = previous.clone();
+
+    let docks = [
+        (WorkspaceDockPosition::Left, workspace_tiles.left),
+        (WorkspaceDockPosition::Right, workspace_tiles.right),
+        (WorkspaceDockPosition::Bottom, workspace_tiles.bottom),
+    ];
+
+    let mut placed: Vec<CanvasScenePane> = Vec::new();
+
+    for (position, tile_id) in docks {
+        let dock = layout.dock_mut(position);
+        // Collapsed docks aren't in the tree so keep what they had
+        if dock.collapsed {
+            placed.extend(dock.panes.iter().cloned());
+            continue;
+        }
+
+        dock.panes = match tile_id {
+            Some(tile_id) if tree.tiles.get(tile_id).is_some() => {
+                let mut panes = Vec::new();
+                collect_panes(tree, tile_id, &mut panes);
+                panes
+            }
+            _ => Vec::new(),
+        };
+        placed.extend(dock.panes.iter().cloned());
+    }
+
+    for (_, tile) in tree.tiles.iter() {
+        if let Tile::Pane(pane) = tile {
+            if *pane != CanvasScenePane::Canvas && !placed.contains(pane) {
+                layout.right.panes.push(pane.clone());
+            }
+        }
+    }
+
+    if let Some(Tile::Container(Container::Linear(row))) =
+        workspace_tiles.row.and_then(|row| tree.tiles.get(row))
+    {
+        if let Some(left) = workspace_tiles
+            .left
+            .filter(|left| row.children.contains(left))
+        {
+            layout.left.share = row.shares[left];
+        }
+        if let Some(right) = workspace_tiles
+            .right
+            .filter(|right| row.children.contains(right))
+        {
+            layout.right.share = row.shares[right];
+        }
+    }
+
+    if let Some(Tile::Container(Container::Linear(column))) = workspace_tiles
+        .column
+        .and_then(|column| tree.tiles.get(column))
+    {
+        if let Some(bottom) = workspace_tiles
+            .bottom
+            .filter(|bottom| column.children.contains(bottom))
+        {
+            layout.bottom.share = column.shares[bottom];
+        }
+    }
+
+    layout
+}
+
+fn collect_panes(tree: &Tree<CanvasScenePane>, tile_id: TileId, panes: &mut Vec<CanvasScenePane>) {
+    match tree.tiles.get(tile_id) {
+        Some(Tile::Pane(pane)) => {
+            if *pane != CanvasScenePane::Canvas {
+                panes.push(pane.clone());
+            }
+        }
+        Some(Tile::Container(container)) => {
+            for child in container.children() {
+                collect_panes(tree, *child, panes);
+            }
+        }
+        None => {}
+    }
+}
@@ -1,13 +1,45 @@
 use std::path::PathBuf;
 
+use crate::project::v1::{Project, ProjectError};
+
 pub struct Session {
     pub active_project: Option<PathBuf>,
+    // Content hash of the project as it was last saved or opened
+    pub saved_project_hash: Option<u64>,
+    // How far along the save running in the background is from 0 to 1, None when not saving
+    pub save_progress: Option<f32>,
 }
 
 impl Session {
     pub fn new() -> Self {
         Self {
             active_project: None,
+            saved_project_hash: None,
+            save_progress: None,
         }
     }
+
+    pub fn has_unsaved_changes(&self, project: &Project) -> bool {
+        match self.saved_project_hash {
+            Some(saved_hash) => project
+                .content_hash()
+                .map_or(true, |content_hash| content_hash != saved_hash),
+            None => !project.is_empty(),
+        }
+    }
+
+    /// Sets the saved state to the active project's file so a restored auto save is only
+    /// considered unsaved if it differs from what is on disk
+    pub fn mark_active_project_saved(&mut self) -> Result<(), ProjectError> {
+        self.saved_project_hash = match &self.active_project {
+            Some(path) => Some(Project::read(path)?.content_hash()?),
+            None => None,
+        };
+
+        Ok(())
+    }
+
+    pub fn is_saving(&self) -> bool {
+        self.save_progress.is_some()
+    }
 }
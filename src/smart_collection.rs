@@ -0,0 +1,25 @@
+use serde::{Deserialize, Serialize};
+
+use crate::photo_manager::PhotosSort;
+
+/// A saved gallery query shown as a folder in the gallery sidebar. The photos in it aren't stored,
+/// they're whatever matches the query at the time so the collection follows rating and metadata
+/// changes.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SmartCollection {
+    pub name: String,
+    pub query: String,
+    // None keeps the gallery's sort order
+    #[serde(default)]
+    pub sort: Option<PhotosSort>,
+}
+
+impl SmartCollection {
+    pub fn new(name: impl Into<String>, query: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            query: query.into(),
+            sort: None,
+        }
+    }
+}
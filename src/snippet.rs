@@ -0,0 +1,133 @@
+use std::collections::HashMap;
+
+use egui::{Id, Pos2, Rect, Vec2};
+
+use crate::{
+    id::{next_layer_id, LayerId},
+    model::{caption_anchor::CaptionAnchor, page::Page, unit::Unit},
+    utils::IdExt,
+    widget::canvas_info::layers::{Layer, LayerContent},
+};
+
+pub type SnippetId = u64;
+
+/// Layers saved together to be dropped onto any page, like a caption block with a line and a
+/// title. The layers keep their positions relative to each other.
+#[derive(Debug, Clone)]
+pub struct Snippet {
+    pub id: SnippetId,
+    pub name: String,
+    // Positioned relative to the top left of the area they cover, in page pixels
+    pub layers: Vec<Layer>,
+    // Just big enough for the layers, used to preview the snippet
+    pub page: Page,
+}
+
+impl Snippet {
+    /// Makes a snippet out of `layers` from `page`. Template layers belong to the regions of
+    /// their template so they're left out, returns None if that leaves nothing.
+    pub fn new(name: String, layers: impl IntoIterator<Item = Layer>, page: &Page) -> Option<Self> {
+        let mut layers: Vec<Layer> = layers
+            .into_iter()
+            .filter(|layer| Self::can_save(layer))
+            .collect();
+
+        let bounds = layers
+            .iter()
+            .map(|layer| layer.transform_state.rect)
+            .reduce(|bounds, rect| bounds.union(rect))?;
+
+        for layer in &mut layers {
+            layer.selected = false;
+            layer.transform_state.rect =
+                layer.transform_state.rect.translate(-bounds.min.to_vec2());
+            layer.transform_edit_state.update(&layer.transform_state);
+        }
+
+        let mut preview_page = Page::new(
+            bounds.size().max(Vec2::splat(1.0)),
+            page.ppi(),
+            Unit::Pixels,
+        );
+        preview_page.set_unit(page.unit());
+
+        Some(Self::with_layers(name, layers, preview_page))
+    }
+
+    pub fn with_layers(name: String, layers: Vec<Layer>, page: Page) -> Self {
+        Self {
+            id: rand::random(),
+            name,
+            layers,
+            page,
+        }
+    }
+
+    pub fn can_save(layer: &Layer) -> bool {
+        !matches!(
+            layer.content,
+            LayerContent::TemplatePhoto { .. } | LayerContent::TemplateText { .. }
+        )
+    }
+
+    pub fn size(&self) -> Vec2 {
+        self.page.size_pixels()
+    }
+
+    /// Copies of the layers with new ids, centered on `center` and selected. Captions anchored
+    /// to a photo in the snippet are anchored to the copy of that photo.
+    pub fn instantiate(&self, center: Pos2) -> Vec<Layer> {
+        let new_ids: HashMap<LayerId, LayerId> = self
+            .layers
+            .iter()
+            .map(|layer| (layer.id, next_layer_id()))
+            .collect();
+        let offset = Rect::from_center_size(center, self.size()).min.to_vec2();
+
+        self.layers
+            .iter()
+            .map(|layer| {
+                let mut layer = layer.clone();
+                layer.id = new_ids[&layer.id];
+                layer.selected = true;
+                layer.transform_state.id = Id::random();
+                layer.transform_state.rect = layer.transform_state.rect.translate(offset);
+                layer.transform_edit_state.update(&layer.transform_state);
+
+                if let LayerContent::Text(text) = &mut layer.content {
+                    text.anchor = text.anchor.and_then(|anchor| {
+                        new_ids
+                            .get(&anchor.photo_layer)
+                            .map(|photo_layer| CaptionAnchor {
+                                photo_layer: *photo_layer,
+                                ..anchor
+                            })
+                    });
+                }
+
+                layer
+            })
+            .collect()
+    }
+}
+
+/// The snippets of the project
+pub struct SnippetManager {
+    pub snippets: Vec<Snippet>,
+}
+
+impl SnippetManager {
+    pub fn new() -> Self {
+        Self {
+            snippets: Vec::new(),
+        }
+    }
+
+    pub fn add(&mut self, snippet: Snippet) {
+        self.snippets.push(snippet);
+    }
+
+    pub fn delete(&mut self, id: SnippetId) {
+        self.snippets.retain(|snippet| snippet.id != id);
+    }
+}
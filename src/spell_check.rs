@@ -0,0 +1,647 @@
+use std::{
+    collections::{HashMap, HashSet},
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+use fxhash::hash64;
+use log::{info, warn};
+
+use crate::{
+    dependencies::{Dependency, Singleton, SingletonFor},
+    dirs::Dirs,
+    project_settings::ProjectSettingsManager,
+};
+
+// Word lists and Hunspell dictionaries that are commonly installed on unix systems and macOS
+const SYSTEM_WORD_LISTS: [&str; 6] = [
+    "/usr/share/dict/words",
+    "/usr/share/dict/american-english",
+    "/usr/share/dict/british-english",
+    "/usr/share/hunspell/en_US.dic",
+    "/usr/share/myspell/en_US.dic",
+    "/Library/Spelling/en_US.dic",
+];
+
+// Hunspell dictionaries installed with LibreOffice, relative to the program files folder. Windows
+// doesn't come with a word list of its own.
+const LIBRE_OFFICE_DICTIONARIES: [&str; 2] = [
+    "LibreOffice/share/extensions/dict-en/en_US.dic",
+    "LibreOffice/share/extensions/dict-en/en_GB.dic",
+];
+
+const MAX_SUGGESTION_DISTANCE: usize = 2;
+
+#[derive(Debug, PartialEq)]
+enum LoadingState {
+    NotLoaded,
+    Loaded,
+    Unavailable,
+}
+
+pub struct SpellChecker {
+    words: HashSet<String>,
+    loading_state: LoadingState,
+    // The text last checked and its misspelled words, text being edited is checked every frame
+    last_checked: Option<(u64, Vec<Range<usize>>)>,
+    // The word suggestions were last found for, they're asked for every frame the menu is open
+    last_suggestions: Option<(String, usize, Vec<String>)>,
+}
+
+impl SpellChecker {
+    pub fn new() -> Self {
+        Self {
+            words: HashSet::new(),
+            loading_state: LoadingState::NotLoaded,
+            last_checked: None,
+            last_suggestions: None,
+        }
+    }
+
+    // Word lists put in the app's dictionaries folder come first so they can replace the system's
+    fn word_list_paths() -> Vec<PathBuf> {
+        let mut paths: Vec<PathBuf> = std::fs::read_dir(Dirs::Dictionaries.path())
+            .map(|entries| {
+                entries
+                    .flatten()
+                    .map(|entry| entry.path())
+                    .filter(|path| path.is_file())
+                    .collect()
+            })
+            .unwrap_or_default();
+        paths.sort();
+
+        paths.extend(SYSTEM_WORD_LISTS.iter().map(PathBuf::from));
+
+        if let Some(home) = dirs::home_dir() {
+            paths.push(home.join("Library/Spelling/en_US.dic"));
+        }
+
+        for program_files in ["ProgramFiles", "ProgramFiles(x86)"]
+            .into_iter()
+            .filter_map(std::env::var_os)
+        {
+            paths.extend(
+                LIBRE_OFFICE_DICTIONARIES
+                    .iter()
+                    .map(|dictionary| PathBuf::from(&program_files).join(dictionary)),
+            );
+        }
+
+        paths
+    }
+
+    fn ensure_loaded(&mut self) {
+        if self.loading_state != LoadingState::NotLoaded {
+            return;
+        }
+
+        for path in Self::word_list_paths() {
+            if let Some(words) = Self::read_word_list(&path) {
+                self.words = words;
+
+                info!(
+                    "Loaded {} words for spell checking from {}",
+                    self.words.len(),
+                    path.display()
+                );
+
+                self.loading_state = LoadingState::Loaded;
+                return;
+            }
+        }
+
+        warn!(
+            "No word list found, spell checking is disabled until one is added to {}",
+            Dirs::Dictionaries.path().display()
+        );
+        self.loading_state = LoadingState::Unavailable;
+    }
+
+    // Plain word lists have a word on each line. Hunspell dictionaries list stems with the flags of
+    // the affix rules in the .aff file next to them, e.g. "photo/MS", and are expanded into every
+    // form of each word. Dictionaries without their rules aren't used, most of their words would
+    // be flagged.
+    fn read_word_list(path: &Path) -> Option<HashSet<String>> {
+        let contents = std::fs::read_to_string(path).ok()?;
+
+        if path.extension().is_some_and(|extension| extension == "dic") {
+            let Ok(affixes) = std::fs::read_to_string(path.with_extension("aff")) else {
+                warn!("Skipping {} without its .aff file", path.display());
+                return None;
+            };
+
+            return Some(AffixRules::parse(&affixes).expand(&contents));
+        }
+
+        Some(
+            contents
+                .lines()
+                .map(|word| word.trim().to_lowercase())
+                .filter(|word| !word.is_empty())
+                .collect(),
+        )
+    }
+
+    pub fn is_available(&mut self) -> bool {
+        self.ensure_loaded();
+        self.loading_state == LoadingState::Loaded
+    }
+
+    pub fn is_correct(&mut self, word: &str) -> bool {
+        if !self.is_available() {
+            return true;
+        }
+
+        // Don't flag things like years or "3rd"
+        if word.chars().any(|c| c.is_numeric()) {
+            return true;
+        }
+
+        let lowercase = word.to_lowercase();
+        let trimmed = lowercase.trim_end_matches("'s");
+
+        self.words.contains(&lowercase)
+            || self.words.contains(trimmed)
+            || Self::custom_dictionary_contains(&lowercase)
+    }
+
+    /// Byte ranges of each misspelled word in the text. The result is kept until the text or the
+    /// custom dictionary changes.
+    pub fn misspelled_ranges(&mut self, text: &str) -> Vec<Range<usize>> {
+        let key = Dependency::<ProjectSettingsManager>::get()
+            .with_lock(|manager| hash64(&(text, &manager.project_settings.custom_dictionary)));
+
+        if let Some((checked, ranges)) = &self.last_checked {
+            if *checked == key {
+                return ranges.clone();
+            }
+        }
+
+        let ranges: Vec<Range<usize>> = word_ranges(text)
+            .into_iter()
+            .filter(|range| !self.is_correct(&text[range.clone()]))
+            .collect();
+
+        self.last_checked = Some((key, ranges.clone()));
+        ranges
+    }
+
+    /// Up to `max` words close to `word`. Looking through the word list is slow so the result for
+    /// the last word is kept.
+    pub fn suggestions(&mut self, word: &str, max: usize) -> Vec<String> {
+        if let Some((last_word, last_max, suggestions)) = &self.last_suggestions {
+            if last_word == word && *last_max == max {
+                return suggestions.clone();
+            }
+        }
+
+        let suggestions = self.find_suggestions(word, max);
+        self.last_suggestions = Some((word.to_string(), max, suggestions.clone()));
+        suggestions
+    }
+
+    fn find_suggestions(&mut self, word: &str, max: usize) -> Vec<String> {
+        if !self.is_available() {
+            return vec![];
+        }
+
+        let lowercase = word.to_lowercase();
+        let word_len = lowercase.chars().count();
+
+        let mut candidates: Vec<(usize, &String)> = self
+            .words
+            .iter()
+            .filter(|candidate| {
+                candidate.chars().count().abs_diff(word_len) <= MAX_SUGGESTION_DISTANCE
+            })
+            .filter_map(|candidate| {
+                let distance = edit_distance(&lowercase, candidate);
+                (distance <= MAX_SUGGESTION_DISTANCE).then_some((distance, candidate))
+            })
+            .collect();
+
+        candidates.sort_by(|a, b| a.0.cmp(&b.0).then(a.1.cmp(b.1)));
+
+        let capitalized = word.chars().next().is_some_and(|c| c.is_uppercase());
+
+        candidates
+            .into_iter()
+            .take(max)
+            .map(|(_, candidate)| {
+                if capitalized {
+                    let mut chars = candidate.chars();
+                    match chars.next() {
+                        Some(first) => first.to_uppercase().chain(chars).collect(),
+                        None => String::new(),
+                    }
+                } else {
+                    candidate.clone()
+                }
+            })
+            .collect()
+    }
+
+    pub fn add_to_custom_dictionary(word: &str) {
+        let word = word.to_lowercase();
+        Dependency::<ProjectSettingsManager>::get().with_lock_mut(|manager| {
+            let dictionary = &mut manager.project_settings.custom_dictionary;
+            if !dictionary.contains(&word) {
+                dictionary.push(word);
+            }
+        });
+    }
+
+    fn custom_dictionary_contains(word: &str) -> bool {
+        let project_settings: Singleton<ProjectSettingsManager> = Dependency::get();
+        project_settings.with_lock(|manager| {
+            manager
+                .project_settings
+                .custom_dictionary
+                .iter()
+                .any(|custom| custom == word)
+        })
+    }
+}
+
+// How a Hunspell dictionary writes the flags after each stem
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum FlagKind {
+    // One character each, the default
+    Char,
+    // Two characters each
+    Long,
+    // Numbers separated by commas
+    Number,
+}
+
+// One character of an affix's condition, a character, any character or a [set]
+#[derive(Debug, Clone, PartialEq)]
+enum ConditionChar {
+    Any,
+    Is(char),
+    In { chars: Vec<char>, negated: bool },
+}
+
+impl ConditionChar {
+    fn matches(&self, c: char) -> bool {
+        match self {
+            ConditionChar::Any => true,
+            ConditionChar::Is(expected) => c == *expected,
+            ConditionChar::In { chars, negated } => chars.contains(&c) != *negated,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Affix {
+    strip: String,
+    add: String,
+    condition: Vec<ConditionChar>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct AffixGroup {
+    // Whether the prefixes and suffixes of the group can be used together
+    cross_product: bool,
+    affixes: Vec<Affix>,
+}
+
+/// The prefix and suffix rules of a Hunspell .aff file. Only what's needed to list the forms of
+/// each word is read, rules for compounds and suggestions are ignored.
+#[derive(Debug)]
+struct AffixRules {
+    flag_kind: FlagKind,
+    prefixes: HashMap<String, AffixGroup>,
+    suffixes: HashMap<String, AffixGroup>,
+}
+
+impl AffixRules {
+    fn parse(aff: &str) -> Self {
+        let mut rules = Self {
+            flag_kind: FlagKind::Char,
+            prefixes: HashMap::new(),
+            suffixes: HashMap::new(),
+        };
+
+        for line in aff.lines() {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+
+            match fields.as_slice() {
+                ["FLAG", "long", ..] => rules.flag_kind = FlagKind::Long,
+                ["FLAG", "num", ..] => rules.flag_kind = FlagKind::Number,
+                [kind @ ("PFX" | "SFX"), flag, cross_product, count]
+                    if count.parse::<usize>().is_ok() =>
+                {
+                    rules.groups(kind).insert(
+                        flag.to_string(),
+                        AffixGroup {
+                            cross_product: *cross_product == "Y",
+                            affixes: Vec::new(),
+                        },
+                    );
+                }
+                [kind @ ("PFX" | "SFX"), flag, strip, add, rest @ ..] => {
+                    let Some(group) = rules.groups(kind).get_mut(*flag) else {
+                        continue;
+                    };
+
+                    // "0" is nothing and flags after a slash continue the affix, which isn't
+                    // followed
+                    let none_if_zero = |value: &str| match value {
+                        "0" => String::new(),
+                        value => value.to_string(),
+                    };
+                    let add = add.split('/').next().unwrap_or_default();
+
+                    group.affixes.push(Affix {
+                        strip: none_if_zero(strip),
+                        add: none_if_zero(add),
+                        condition: parse_condition(rest.first().copied().unwrap_or(".")),
+                    });
+                }
+                _ => {}
+            }
+        }
+
+        rules
+    }
+
+    fn groups(&mut self, kind: &str) -> &mut HashMap<String, AffixGroup> {
+        if kind == "PFX" {
+            &mut self.prefixes
+        } else {
+            &mut self.suffixes
+        }
+    }
+
+    fn flags(&self, flags: &str) -> Vec<String> {
+        match self.flag_kind {
+            FlagKind::Char => flags.chars().map(String::from).collect(),
+            FlagKind::Long => flags
+                .chars()
+                .collect::<Vec<_>>()
+                .chunks(2)
+                .map(|pair| pair.iter().collect())
+                .collect(),
+            FlagKind::Number => flags.split(',').map(str::to_string).collect(),
+        }
+    }
+
+    // Every form of every stem in the .dic file, the first line of which is the number of stems
+    fn expand(&self, dic: &str) -> HashSet<String> {
+        let mut words = HashSet::new();
+
+        for line in dic.lines().skip(1) {
+            // Stems can be followed by morphological fields after a tab
+            let entry = line.split('\t').next().unwrap_or_default().trim();
+            let (stem, flags) = entry.split_once('/').unwrap_or((entry, ""));
+            let stem = stem.to_lowercase();
+            if stem.is_empty() {
+                continue;
+            }
+
+            let flags = self.flags(flags);
+            let prefixes: Vec<&AffixGroup> = flags
+                .iter()
+                .filter_map(|flag| self.prefixes.get(flag))
+                .collect();
+            let suffixes: Vec<&AffixGroup> = flags
+                .iter()
+                .filter_map(|flag| self.suffixes.get(flag))
+                .collect();
+
+            for suffix_group in &suffixes {
+                for suffix in &suffix_group.affixes {
+                    let Some(suffixed) = apply_suffix(&stem, suffix) else {
+                        continue;
+                    };
+
+                    for prefix_group in prefixes
+                        .iter()
+                        .filter(|group| group.cross_product && suffix_group.cross_product)
+                    {
+                        words.extend(
+                            prefix_group
+                                .affixes
+                                .iter()
+                                .filter_map(|prefix| apply_prefix(&suffixed, prefix)),
+                        );
+                    }
+
+                    words.insert(suffixed);
+                }
+            }
+
+            for prefix_group in &prefixes {
+                words.extend(
+                    prefix_group
+                        .affixes
+                        .iter()
+                        .filter_map(|prefix| apply_prefix(&stem, prefix)),
+                );
+            }
+
+            words.insert(stem);
+        }
+
+        words
+    }
+}
+
+fn parse_condition(condition: &str) -> Vec<ConditionChar> {
+    let mut parsed = Vec::new();
+    let mut chars = condition.chars();
+
+    while let Some(c) = chars.next() {
+        parsed.push(match c {
+            '.' => ConditionChar::Any,
+            '[' => {
+                let mut set: Vec<char> = chars.by_ref().take_while(|c| *c != ']').collect();
+                let negated = set.first() == Some(&'^');
+                if negated {
+                    set.remove(0);
+                }
+                ConditionChar::In {
+                    chars: set,
+                    negated,
+                }
+            }
+            c => ConditionChar::Is(c),
+        });
+    }
+
+    parsed
+}
+
+fn condition_matches(condition: &[ConditionChar], chars: &[char]) -> bool {
+    condition.len() <= chars.len()
+        && condition
+            .iter()
+            .zip(chars)
+            .all(|(condition, c)| condition.matches(*c))
+}
+
+fn apply_suffix(stem: &str, suffix: &Affix) -> Option<String> {
+    let chars: Vec<char> = stem.chars().collect();
+    let condition_start = chars.len().checked_sub(suffix.condition.len())?;
+    if !condition_matches(&suffix.condition, &chars[condition_start..]) {
+        return None;
+    }
+
+    let base = stem.strip_suffix(suffix.strip.as_str())?;
+    Some(format!("{}{}", base, suffix.add.to_lowercase()))
+}
+
+fn apply_prefix(stem: &str, prefix: &Affix) -> Option<String> {
+    let chars: Vec<char> = stem.chars().collect();
+    if !condition_matches(&prefix.condition, &chars) {
+        return None;
+    }
+
+    let base = stem.strip_prefix(prefix.strip.as_str())?;
+    Some(format!("{}{}", prefix.add.to_lowercase(), base))
+}
+
+pub fn word_ranges(text: &str) -> Vec<Range<usize>> {
+    let mut ranges = vec![];
+    let mut start: Option<usize> = None;
+
+    for (index, c) in text.char_indices() {
+        let is_word_char = c.is_alphanumeric() || (c == '\'' && start.is_some());
+        match (is_word_char, start) {
+            (true, None) => start = Some(index),
+            (false, Some(word_start)) => {
+                ranges.push(word_start..index);
+                start = None;
+            }
+            _ => {}
+        }
+    }
+
+    if let Some(word_start) = start {
+        ranges.push(word_start..text.len());
+    }
+
+    // Trailing apostrophes aren't part of the word
+    ranges
+        .into_iter()
+        .map(|range| {
+            let word = &text[range.clone()];
+            range.start..range.start + word.trim_end_matches('\'').len()
+        })
+        .filter(|range| !range.is_empty())
+        .collect()
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b_chars: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b_chars.len()).collect();
+    let mut current = vec![0; b_chars.len() + 1];
+
+    for (i, a_char) in a.chars().enumerate() {
+        current[0] = i + 1;
+        for (j, b_char) in b_chars.iter().enumerate() {
+            let substitution_cost = if a_char == *b_char { 0 } else { 1 };
+            current[j + 1] = (previous[j] + substitution_cost)
+                .min(previous[j + 1] + 1)
+                .min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    previous[b_chars.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(text: &str) -> Vec<&str> {
+        word_ranges(text)
+            .into_iter()
+            .map(|range| &text[range])
+            .collect()
+    }
+
+    #[test]
+    fn words_are_split_on_punctuation_and_spaces() {
+        assert_eq!(words("Hello, world!"), vec!["Hello", "world"]);
+        assert_eq!(words("  one\ttwo\nthree  "), vec!["one", "two", "three"]);
+        assert!(words("").is_empty());
+        assert!(words("... --").is_empty());
+    }
+
+    #[test]
+    fn apostrophes_inside_words_are_kept() {
+        assert_eq!(words("don't stop"), vec!["don't", "stop"]);
+        assert_eq!(words("'quoted'"), vec!["quoted"]);
+        assert_eq!(words("the players' ball"), vec!["the", "players", "ball"]);
+    }
+
+    #[test]
+    fn ranges_are_in_bytes() {
+        assert_eq!(word_ranges("café au lait"), vec![0..5, 6..8, 9..13]);
+    }
+
+    #[test]
+    fn edit_distance_counts_insertions_deletions_and_substitutions() {
+        assert_eq!(edit_distance("same", "same"), 0);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("abc", ""), 3);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("colour", "color"), 1);
+        // A swap is two substitutions
+        assert_eq!(edit_distance("teh", "the"), 2);
+    }
+
+    #[test]
+    fn edit_distance_counts_characters_not_bytes() {
+        assert_eq!(edit_distance("café", "cafe"), 1);
+    }
+
+    const AFFIXES: &str = "SET UTF-8
+PFX U Y 1
+PFX U   0     un         .
+SFX S Y 3
+SFX S   y     ies        [^aeiou]y
+SFX S   0     s          [aeiou]y
+SFX S   0     s          [^y]
+SFX D N 1
+SFX D   0     ed         .
+";
+
+    fn expanded(dic: &str) -> Vec<String> {
+        let mut words: Vec<String> = AffixRules::parse(AFFIXES).expand(dic).into_iter().collect();
+        words.sort();
+        words
+    }
+
+    #[test]
+    fn suffixes_follow_their_conditions() {
+        assert_eq!(
+            expanded("3\nCity/S\nday/S\nphoto/S"),
+            vec!["cities", "city", "day", "days", "photo", "photos"]
+        );
+    }
+
+    #[test]
+    fn cross_products_combine_prefixes_and_suffixes() {
+        assert_eq!(
+            expanded("2\nlock/UD\ndo/U"),
+            vec!["do", "lock", "locked", "undo", "unlock"]
+        );
+        assert_eq!(
+            expanded("1\nknot/US"),
+            vec!["knot", "knots", "unknot", "unknots"]
+        );
+    }
+
+    #[test]
+    fn long_flags_are_read_in_pairs() {
+        let rules = AffixRules::parse("FLAG long\nSFX Ab Y 1\nSFX Ab 0 s .\n");
+        let mut words: Vec<String> = rules.expand("1\ntree/AbCd").into_iter().collect();
+        words.sort();
+        assert_eq!(words, vec!["tree", "trees"]);
+    }
+}
@@ -0,0 +1,907 @@
+use std::{f32::consts::SQRT_2, fmt::Write, path::Path};
+
+use egui::{text::Fonts, Color32, FontDefinitions, FontFamily, Pos2, Rect, Vec2};
+use fxhash::hash64;
+use url::Url;
+
+use crate::{
+    book_metadata::BookMetadata,
+    decoration::Decoration,
+    export::ExportError,
+    model::{
+        canvas_path::CanvasPath,
+        fill::{Fill, GradientKind},
+    },
+    render::{self, RenderTarget},
+    widget::{
+        canvas::{CanvasPhoto, CanvasState},
+        canvas_info::layers::{
+            CanvasText, Layer, TextHorizontalAlignment, TextOrientation, TextVerticalAlignment,
+        },
+    },
+};
+
+// Points sampled along the vignette's fade for its gradient
+const VIGNETTE_STOPS: usize = 12;
+
+// Photos can either be written into the SVG or referenced by their path on disk. Embedding
+// makes the file self contained, linking keeps it small and lets the editor pick up changes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SvgPhotoMode {
+    Embed,
+    Link,
+}
+
+// Converts pages to SVG documents. Text and vector assets are written as vector elements so they
+// stay editable, photos become image elements with their crop and rotation applied as transforms.
+pub struct SvgWriter {
+    fonts: Fonts,
+    // Kept for text that's shaped by glyph id rather than laid out by egui
+    font_definitions: FontDefinitions,
+    photo_mode: SvgPhotoMode,
+    metadata: BookMetadata,
+}
+
+impl SvgWriter {
+    pub fn new(font_definitions: FontDefinitions, photo_mode: SvgPhotoMode) -> Self {
+        Self {
+            // Text is laid out with the same fonts as the canvas so lines break in the same places
+            fonts: Fonts::new(1.0, 1024, font_definitions.clone()),
+            font_definitions,
+            photo_mode,
+            metadata: BookMetadata::current(),
+        }
+    }
+
+    pub fn page_to_svg(&self, canvas_state: &CanvasState) -> Result<String, ExportError> {
+        let size = canvas_state.page.size_pixels();
+        let size_mm = canvas_state.page.size_mm();
+        let page_rect = render::page_rect(canvas_state);
+
+        let mut svg = String::new();
+        writeln!(svg, r#"<?xml version="1.0" encoding="UTF-8"?>"#).unwrap();
+        writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink" xmlns:inkscape="http://www.inkscape.org/namespaces/inkscape" width="{}mm" height="{}mm" viewBox="0 0 {} {}">"#,
+            size_mm.x, size_mm.y, size.x, size.y
+        )
+        .unwrap();
+        let background_rect = Rect::from_min_size(Pos2::ZERO, size);
+        let (background, background_opacity) =
+            Self::write_fill(&mut svg, &canvas_state.background, background_rect);
+        writeln!(
+            svg,
+            r#"<rect x="0" y="0" width="{}" height="{}" fill="{}" fill-opacity="{}"/>"#,
+            size.x, size.y, background, background_opacity
+        )
+        .unwrap();
+
+        let mut target = SvgPage {
+            writer: self,
+            metadata: self
+                .metadata
+                .clone()
+                .with_section(canvas_state.section.as_deref()),
+            svg,
+        };
+        render::render_page(canvas_state, page_rect, &mut target)?;
+        let mut svg = target.svg;
+
+        writeln!(svg, "</svg>").unwrap();
+
+        Ok(svg)
+    }
+
+    fn write_photo(
+        &self,
+        svg: &mut String,
+        photo: &CanvasPhoto,
+        rect: Rect,
+        rotation: f32,
+    ) -> Result<(), ExportError> {
+        let metadata = &photo.photo.metadata;
+        let image_size = Vec2::new(metadata.width() as f32, metadata.height() as f32);
+
+        // The crop is in the coordinates of the unrotated image so the image is drawn unrotated
+        // into a box with the width and height swapped, then rotated into place. This mirrors
+        // how the canvas builds its mesh.
+        let image_rect = photo.orientation.mesh_rect(metadata.rotation(), rect);
+
+        let crop = photo.crop;
+        let view_box = Rect::from_min_max(
+            (crop.min.to_vec2() * image_size).to_pos2(),
+            (crop.max.to_vec2() * image_size).to_pos2(),
+        );
+
+        writeln!(
+            svg,
+            r#"<g transform="rotate({} {} {})">"#,
+            rotation.to_degrees(),
+            rect.center().x,
+            rect.center().y
+        )
+        .unwrap();
+        // Effects are applied after orienting the photo, to the box it's shown in
+        let clip_id = Self::write_corner_clip(svg, photo, rect);
+        if let Some(clip_id) = &clip_id {
+            writeln!(svg, r#"<g clip-path="url(#{})">"#, clip_id).unwrap();
+        }
+        writeln!(
+            svg,
+            r#"<g transform="{}">"#,
+            Self::orientation_transform(photo, rect.center())
+        )
+        .unwrap();
+        writeln!(
+            svg,
+            r#"<svg x="{}" y="{}" width="{}" height="{}" viewBox="{} {} {} {}" preserveAspectRatio="none">"#,
+            image_rect.min.x,
+            image_rect.min.y,
+            image_rect.width(),
+            image_rect.height(),
+            view_box.min.x,
+            view_box.min.y,
+            view_box.width(),
+            view_box.height()
+        )
+        .unwrap();
+        let filter_id = self.write_adjustment_filter(svg, photo);
+        self.write_image(svg, &photo.photo.path, image_size, filter_id.as_deref())?;
+        writeln!(svg, "</svg>").unwrap();
+        writeln!(svg, "</g>").unwrap();
+        Self::write_vignette(svg, photo, rect);
+        if clip_id.is_some() {
+            writeln!(svg, "</g>").unwrap();
+        }
+        writeln!(svg, "</g>").unwrap();
+
+        Ok(())
+    }
+
+    // Rounds the corners of the photo's box, None if they aren't rounded. The page is written in
+    // page pixels so the radius isn't scaled.
+    fn write_corner_clip(svg: &mut String, photo: &CanvasPhoto, rect: Rect) -> Option<String> {
+        let radius = photo.effects.corner_radius_at(rect.size(), 1.0);
+        if radius <= 0.0 {
+            return None;
+        }
+
+        let clip_id = format!("corners-{:x}", hash64(&format!("{:?}{}", rect, radius)));
+        writeln!(
+            svg,
+            r#"<clipPath id="{}"><rect x="{}" y="{}" width="{}" height="{}" rx="{}" ry="{}"/></clipPath>"#,
+            clip_id,
+            rect.min.x,
+            rect.min.y,
+            rect.width(),
+            rect.height(),
+            radius,
+            radius
+        )
+        .unwrap();
+
+        Some(clip_id)
+    }
+
+    // Darkens the photo toward its edges with black faded in by a radial gradient, sampled from
+    // the same curve the canvas shades its mesh with
+    fn write_vignette(svg: &mut String, photo: &CanvasPhoto, rect: Rect) {
+        let effects = &photo.effects;
+        if effects.vignette_strength <= 0.0 {
+            return;
+        }
+
+        let id = format!("vignette-{:x}", hash64(&format!("{:?}{:?}", effects, rect)));
+        // Scaled to the box the radius reaches the corners, where the vignette is strongest
+        writeln!(
+            svg,
+            r#"<radialGradient id="{}" cx="0.5" cy="0.5" r="{}">"#,
+            id,
+            SQRT_2 / 2.0
+        )
+        .unwrap();
+        for stop in 0..=VIGNETTE_STOPS {
+            let offset = stop as f32 / VIGNETTE_STOPS as f32;
+            writeln!(
+                svg,
+                r#"<stop offset="{}" stop-color="black" stop-opacity="{}"/>"#,
+                offset,
+                effects.vignette_at(offset * SQRT_2)
+            )
+            .unwrap();
+        }
+        writeln!(svg, "</radialGradient>").unwrap();
+        writeln!(
+            svg,
+            r#"<rect x="{}" y="{}" width="{}" height="{}" fill="url(#{})"/>"#,
+            rect.min.x,
+            rect.min.y,
+            rect.width(),
+            rect.height(),
+            id
+        )
+        .unwrap();
+    }
+
+    // Mirrors the image for EXIF, rotates it into place around `center` and then flips it, the
+    // same as the canvas orients the photo's mesh
+    fn orientation_transform(photo: &CanvasPhoto, center: Pos2) -> String {
+        let orientation = photo.orientation;
+        let exif_rotation = photo.photo.metadata.rotation();
+        let degrees = orientation.radians(exif_rotation).to_degrees();
+
+        if !orientation.is_flipped_horizontal()
+            && !orientation.is_flipped_vertical()
+            && !exif_rotation.is_mirrored()
+        {
+            return format!("rotate({} {} {})", degrees, center.x, center.y);
+        }
+
+        format!(
+            "translate({} {}) scale({} {}) rotate({}) scale({} 1) translate({} {})",
+            center.x,
+            center.y,
+            if orientation.is_flipped_horizontal() {
+                -1
+            } else {
+                1
+            },
+            if orientation.is_flipped_vertical() {
+                -1
+            } else {
+                1
+            },
+            degrees,
+            if exif_rotation.is_mirrored() { -1 } else { 1 },
+            -center.x,
+            -center.y
+        )
+    }
+
+    fn write_template_photo(
+        &self,
+        svg: &mut String,
+        photo: &CanvasPhoto,
+        region_rect: Rect,
+        scaled_rect: Rect,
+    ) -> Result<(), ExportError> {
+        let metadata = &photo.photo.metadata;
+        let image_size = Vec2::new(metadata.width() as f32, metadata.height() as f32);
+        let image_rect = photo
+            .orientation
+            .mesh_rect(metadata.rotation(), scaled_rect);
+
+        // Nested svg elements clip their content which keeps filled photos inside the region
+        writeln!(
+            svg,
+            r#"<svg x="{}" y="{}" width="{}" height="{}" viewBox="{} {} {} {}">"#,
+            region_rect.min.x,
+            region_rect.min.y,
+            region_rect.width(),
+            region_rect.height(),
+            region_rect.min.x,
+            region_rect.min.y,
+            region_rect.width(),
+            region_rect.height()
+        )
+        .unwrap();
+        writeln!(
+            svg,
+            r#"<g transform="{}">"#,
+            Self::orientation_transform(photo, scaled_rect.center())
+        )
+        .unwrap();
+        writeln!(
+            svg,
+            r#"<svg x="{}" y="{}" width="{}" height="{}" viewBox="0 0 {} {}" preserveAspectRatio="none">"#,
+            image_rect.min.x,
+            image_rect.min.y,
+            image_rect.width(),
+            image_rect.height(),
+            image_size.x,
+            image_size.y
+        )
+        .unwrap();
+        let filter_id = self.write_adjustment_filter(svg, photo);
+        self.write_image(svg, &photo.photo.path, image_size, filter_id.as_deref())?;
+        writeln!(svg, "</svg>").unwrap();
+        writeln!(svg, "</g>").unwrap();
+        writeln!(svg, "</svg>").unwrap();
+
+        Ok(())
+    }
+
+    // The adjustments are linear per channel so they map directly onto a component transfer
+    // filter and the original photo can still be embedded or linked
+    fn write_adjustment_filter(&self, svg: &mut String, photo: &CanvasPhoto) -> Option<String> {
+        if photo.adjustments.is_identity() {
+            return None;
+        }
+
+        let filter_id = format!("adjust-{:x}", hash64(&photo.adjustments.uri(&photo.photo)));
+        writeln!(
+            svg,
+            r#"<filter id="{}" color-interpolation-filters="sRGB"><feComponentTransfer>"#,
+            filter_id
+        )
+        .unwrap();
+        for (function, (slope, intercept)) in ["feFuncR", "feFuncG", "feFuncB"]
+            .iter()
+            .zip(photo.adjustments.linear_transfer())
+        {
+            writeln!(
+                svg,
+                r#"<{} type="linear" slope="{}" intercept="{}"/>"#,
+                function, slope, intercept
+            )
+            .unwrap();
+        }
+        writeln!(svg, "</feComponentTransfer></filter>").unwrap();
+
+        Some(filter_id)
+    }
+
+    fn write_image(
+        &self,
+        svg: &mut String,
+        path: &Path,
+        size: Vec2,
+        filter_id: Option<&str>,
+    ) -> Result<(), ExportError> {
+        let href = match self.photo_mode {
+            SvgPhotoMode::Embed => {
+                let bytes =
+                    std::fs::read(path).map_err(|e| ExportError::FileError(e.to_string()))?;
+                format!("data:{};base64,{}", mime_type(path), base64_encode(&bytes))
+            }
+            SvgPhotoMode::Link => {
+                let url = Url::from_file_path(path).map_err(|_| {
+                    ExportError::FileError(format!("Can't link to {}", path.display()))
+                })?;
+                escape_xml(url.as_str())
+            }
+        };
+
+        let filter = filter_id
+            .map(|filter_id| format!(r#" filter="url(#{})""#, filter_id))
+            .unwrap_or_default();
+
+        // Orientation is applied by the surrounding transform so the renderer must not apply it again
+        writeln!(
+            svg,
+            r#"<image x="0" y="0" width="{}" height="{}" preserveAspectRatio="none" style="image-orientation: none"{} xlink:href="{}" href="{}"/>"#,
+            size.x, size.y, filter, href, href
+        )
+        .unwrap();
+
+        Ok(())
+    }
+
+    fn write_text(
+        &self,
+        svg: &mut String,
+        text: &CanvasText,
+        metadata: &BookMetadata,
+        rect: Rect,
+        rotation: f32,
+    ) {
+        if self.write_shaped_text(svg, text, metadata, rect, rotation) {
+            return;
+        }
+
+        if text.orientation.is_sideways() {
+            self.write_sideways_text(svg, text, metadata, rect, rotation);
+            return;
+        }
+
+        let mut job = render::text_layout_job(&self.fonts, text, metadata, 1.0, rect.width());
+        job.wrap.max_width = rect.width();
+        let galley = self.fonts.layout_job(job);
+
+        let top = match text.vertical_alignment {
+            TextVerticalAlignment::Top => rect.min.y,
+            TextVerticalAlignment::Center => rect.center().y - galley.size().y / 2.0,
+            TextVerticalAlignment::Bottom => rect.max.y - galley.size().y,
+        };
+
+        let (fill, opacity) = svg_color(text.color);
+
+        writeln!(
+            svg,
+            r#"<text font-family="{}" font-size="{}" letter-spacing="{}" fill="{}" fill-opacity="{}" transform="rotate({} {} {})" xml:space="preserve">"#,
+            escape_xml(&font_family_name(&text.font_id.family)),
+            text.font_size,
+            text.spacing.letter_spacing * text.font_size,
+            fill,
+            opacity,
+            rotation.to_degrees(),
+            rect.center().x,
+            rect.center().y
+        )
+        .unwrap();
+
+        for row in &galley.rows {
+            let Some(first_glyph) = row.glyphs.first() else {
+                continue;
+            };
+
+            // Measured from the glyphs so the first line indent is kept for left aligned text
+            let row_width = row
+                .glyphs
+                .last()
+                .map_or(0.0, |glyph| glyph.pos.x + glyph.advance_width)
+                - first_glyph.pos.x;
+            let x = match text.horizontal_alignment {
+                TextHorizontalAlignment::Left => rect.min.x + first_glyph.pos.x,
+                TextHorizontalAlignment::Center => rect.center().x - row_width / 2.0,
+                TextHorizontalAlignment::Right => rect.max.x - row_width,
+            };
+
+            let row_text: String = row.glyphs.iter().map(|glyph| glyph.chr).collect();
+
+            writeln!(
+                svg,
+                r#"<tspan x="{}" y="{}">{}</tspan>"#,
+                x,
+                top + first_glyph.pos.y,
+                escape_xml(&row_text)
+            )
+            .unwrap();
+        }
+
+        writeln!(svg, "</text>").unwrap();
+    }
+
+    // Text in scripts that need shaping is written as the outlines of its glyphs, like the canvas
+    // draws it. As text a viewer would shape it with whatever font it finds and break its lines
+    // in other places. Returns false for text that's written as text.
+    fn write_shaped_text(
+        &self,
+        svg: &mut String,
+        text: &CanvasText,
+        metadata: &BookMetadata,
+        rect: Rect,
+        rotation: f32,
+    ) -> bool {
+        let resolved = render::resolved_text(text, metadata);
+        let wrap_width = render::text_layout_rect(text, rect).width();
+        let Some(shaped) =
+            render::shaped_text(&self.font_definitions, text, &resolved, 1.0, wrap_width)
+        else {
+            return false;
+        };
+
+        let origin = render::shaped_text_rect(text, shaped.size, rect).min;
+        let (fill, opacity) = svg_color(text.color);
+
+        writeln!(
+            svg,
+            r#"<path d="{}" fill="{}" fill-opacity="{}" transform="rotate({} {} {})"/>"#,
+            shaped.svg_path(origin.to_vec2()),
+            fill,
+            opacity,
+            (rotation + text.orientation.angle()).to_degrees(),
+            rect.center().x,
+            rect.center().y
+        )
+        .unwrap();
+
+        true
+    }
+
+    // Sideways text is written unturned in its layout box and turned along with the layer.
+    // Vertical text is written a character at a time so each one stays upright.
+    fn write_sideways_text(
+        &self,
+        svg: &mut String,
+        text: &CanvasText,
+        metadata: &BookMetadata,
+        rect: Rect,
+        rotation: f32,
+    ) {
+        let (galley, pos) = render::sideways_text_galley(&self.fonts, text, metadata, 1.0, rect);
+        let vertical = text.orientation == TextOrientation::Vertical;
+
+        let (turn, letter_spacing, anchor) = if vertical {
+            (
+                rotation,
+                0.0,
+                r#" text-anchor="middle" dominant-baseline="central""#,
+            )
+        } else {
+            (
+                rotation + text.orientation.angle(),
+                text.spacing.letter_spacing * text.font_size,
+                "",
+            )
+        };
+
+        let (fill, opacity) = svg_color(text.color);
+
+        writeln!(
+            svg,
+            r#"<text font-family="{}" font-size="{}" letter-spacing="{}" fill="{}" fill-opacity="{}" transform="rotate({} {} {})"{} xml:space="preserve">"#,
+            escape_xml(&font_family_name(&text.font_id.family)),
+            text.font_size,
+            letter_spacing,
+            fill,
+            opacity,
+            turn.to_degrees(),
+            rect.center().x,
+            rect.center().y,
+            anchor
+        )
+        .unwrap();
+
+        if vertical {
+            for (chr, center, _) in render::vertical_text_glyphs(&galley, pos, rect) {
+                if chr.is_whitespace() {
+                    continue;
+                }
+
+                writeln!(
+                    svg,
+                    r#"<tspan x="{}" y="{}">{}</tspan>"#,
+                    center.x,
+                    center.y,
+                    escape_xml(&chr.to_string())
+                )
+                .unwrap();
+            }
+        } else {
+            for row in &galley.rows {
+                let Some(first_glyph) = row.glyphs.first() else {
+                    continue;
+                };
+
+                let row_text: String = row.glyphs.iter().map(|glyph| glyph.chr).collect();
+
+                writeln!(
+                    svg,
+                    r#"<tspan x="{}" y="{}">{}</tspan>"#,
+                    pos.x + first_glyph.pos.x,
+                    pos.y + first_glyph.pos.y,
+                    escape_xml(&row_text)
+                )
+                .unwrap();
+            }
+        }
+
+        writeln!(svg, "</text>").unwrap();
+    }
+
+    // Written in page coordinates with the same bezier segments the canvas draws
+    fn write_path(svg: &mut String, path: &CanvasPath, rect: Rect, rotation: f32) {
+        let Some(first) = path.points.first() else {
+            return;
+        };
+
+        let to_rect = CanvasPath::transform(rect, 0.0);
+        let start = to_rect(first.anchor);
+
+        let mut data = format!("M {} {}", start.x, start.y);
+        for segment in path.segments() {
+            let [_, control_out, control_in, end] = segment.map(&to_rect);
+            write!(
+                data,
+                " C {} {} {} {} {} {}",
+                control_out.x, control_out.y, control_in.x, control_in.y, end.x, end.y
+            )
+            .unwrap();
+        }
+        if path.closed {
+            data.push_str(" Z");
+        }
+
+        let (fill, fill_opacity) = if path.is_filled() {
+            Self::write_fill(svg, &path.fill, rect)
+        } else {
+            ("none".to_string(), 1.0)
+        };
+        let (stroke, stroke_opacity) = svg_color(path.stroke_color);
+
+        writeln!(
+            svg,
+            r#"<path d="{}" fill="{}" fill-opacity="{}" fill-rule="evenodd" stroke="{}" stroke-opacity="{}" stroke-width="{}" stroke-linejoin="round" stroke-linecap="round" transform="rotate({} {} {})"/>"#,
+            data,
+            fill,
+            fill_opacity,
+            stroke,
+            stroke_opacity,
+            path.stroke_width,
+            rotation.to_degrees(),
+            rect.center().x,
+            rect.center().y
+        )
+        .unwrap();
+    }
+
+    // Gradients are written as an element the fill refers to. Their points are relative to
+    // `rect` like on the canvas, the transform maps them onto it.
+    fn write_fill(svg: &mut String, fill: &Fill, rect: Rect) -> (String, f32) {
+        let gradient = match fill {
+            Fill::Solid(color) => return svg_color(*color),
+            Fill::Gradient(gradient) => gradient,
+        };
+
+        let id = format!(
+            "gradient-{:x}",
+            hash64(&format!("{:?}{:?}", gradient, rect))
+        );
+        let transform = format!(
+            "matrix({} 0 0 {} {} {})",
+            rect.width(),
+            rect.height(),
+            rect.min.x,
+            rect.min.y
+        );
+
+        match gradient.kind {
+            GradientKind::Linear => writeln!(
+                svg,
+                r#"<linearGradient id="{}" gradientUnits="userSpaceOnUse" gradientTransform="{}" x1="{}" y1="{}" x2="{}" y2="{}">"#,
+                id,
+                transform,
+                gradient.start.x,
+                gradient.start.y,
+                gradient.end.x,
+                gradient.end.y
+            ),
+            GradientKind::Radial => writeln!(
+                svg,
+                r#"<radialGradient id="{}" gradientUnits="userSpaceOnUse" gradientTransform="{}" cx="{}" cy="{}" r="{}">"#,
+                id,
+                transform,
+                gradient.start.x,
+                gradient.start.y,
+                gradient.start.distance(gradient.end)
+            ),
+        }
+        .unwrap();
+
+        for stop in &gradient.stops {
+            let (color, opacity) = svg_color(stop.color);
+            writeln!(
+                svg,
+                r#"<stop offset="{}" stop-color="{}" stop-opacity="{}"/>"#,
+                stop.offset, color, opacity
+            )
+            .unwrap();
+        }
+
+        match gradient.kind {
+            GradientKind::Linear => writeln!(svg, "</linearGradient>"),
+            GradientKind::Radial => writeln!(svg, "</radialGradient>"),
+        }
+        .unwrap();
+
+        (format!("url(#{})", id), 1.0)
+    }
+
+    fn write_decoration(
+        &self,
+        svg: &mut String,
+        decoration: &Decoration,
+        rect: Rect,
+        rotation: f32,
+    ) -> Result<(), ExportError> {
+        writeln!(
+            svg,
+            r#"<g transform="rotate({} {} {})">"#,
+            rotation.to_degrees(),
+            rect.center().x,
+            rect.center().y
+        )
+        .unwrap();
+
+        if decoration.is_vector() {
+            let contents = std::fs::read_to_string(&decoration.path)
+                .map_err(|e| ExportError::FileError(e.to_string()))?;
+            let (view_box, body) = split_svg_document(&contents).ok_or_else(|| {
+                ExportError::FileError(format!(
+                    "{} is not a valid SVG document",
+                    decoration.path.display()
+                ))
+            })?;
+
+            // Inlined so the ornament stays editable rather than becoming an image
+            writeln!(
+                svg,
+                r#"<svg x="{}" y="{}" width="{}" height="{}" viewBox="{}" preserveAspectRatio="none">"#,
+                rect.min.x,
+                rect.min.y,
+                rect.width(),
+                rect.height(),
+                view_box
+            )
+            .unwrap();
+            svg.push_str(body);
+            writeln!(svg, "</svg>").unwrap();
+        } else {
+            let size = decoration.size();
+            writeln!(
+                svg,
+                r#"<svg x="{}" y="{}" width="{}" height="{}" viewBox="0 0 {} {}" preserveAspectRatio="none">"#,
+                rect.min.x,
+                rect.min.y,
+                rect.width(),
+                rect.height(),
+                size.x,
+                size.y
+            )
+            .unwrap();
+            self.write_image(svg, &decoration.path, size, None)?;
+            writeln!(svg, "</svg>").unwrap();
+        }
+
+        writeln!(svg, "</g>").unwrap();
+
+        Ok(())
+    }
+}
+
+// Collects one page's elements as the page is rendered
+struct SvgPage<'a> {
+    writer: &'a SvgWriter,
+    // The book's metadata with the page's section
+    metadata: BookMetadata,
+    svg: String,
+}
+
+impl RenderTarget for SvgPage<'_> {
+    type Error = ExportError;
+
+    fn begin_layer(&mut self, layer: &Layer) -> Result<(), ExportError> {
+        // Names aren't unique and can hold anything, so the id comes from the layer's id and the
+        // name is kept as the label editors show
+        let name = escape_xml(&layer.name);
+        writeln!(
+            self.svg,
+            r#"<g id="layer-{}" inkscape:label="{}" data-name="{}">"#,
+            layer.id, name, name
+        )
+        .unwrap();
+        Ok(())
+    }
+
+    fn end_layer(&mut self, _layer: &Layer) -> Result<(), ExportError> {
+        writeln!(self.svg, "</g>").unwrap();
+        Ok(())
+    }
+
+    fn photo(&mut self, photo: &CanvasPhoto, rect: Rect, rotation: f32) -> Result<(), ExportError> {
+        self.writer
+            .write_photo(&mut self.svg, photo, rect, rotation)
+    }
+
+    fn template_photo(
+        &mut self,
+        photo: &CanvasPhoto,
+        region_rect: Rect,
+        photo_rect: Rect,
+    ) -> Result<(), ExportError> {
+        self.writer
+            .write_template_photo(&mut self.svg, photo, region_rect, photo_rect)
+    }
+
+    fn text(&mut self, text: &CanvasText, rect: Rect, rotation: f32) -> Result<(), ExportError> {
+        self.writer
+            .write_text(&mut self.svg, text, &self.metadata, rect, rotation);
+        Ok(())
+    }
+
+    fn decoration(
+        &mut self,
+        decoration: &Decoration,
+        rect: Rect,
+        rotation: f32,
+    ) -> Result<(), ExportError> {
+        self.writer
+            .write_decoration(&mut self.svg, decoration, rect, rotation)
+    }
+
+    fn path(&mut self, path: &CanvasPath, rect: Rect, rotation: f32) -> Result<(), ExportError> {
+        SvgWriter::write_path(&mut self.svg, path, rect, rotation);
+        Ok(())
+    }
+}
+
+fn font_family_name(family: &FontFamily) -> String {
+    match family {
+        FontFamily::Proportional => "sans-serif".to_string(),
+        FontFamily::Monospace => "monospace".to_string(),
+        FontFamily::Name(name) => name.to_string(),
+    }
+}
+
+fn svg_color(color: Color32) -> (String, f32) {
+    (
+        format!("#{:02x}{:02x}{:02x}", color.r(), color.g(), color.b()),
+        color.a() as f32 / 255.0,
+    )
+}
+
+fn mime_type(path: &Path) -> &'static str {
+    match path
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase())
+        .as_deref()
+    {
+        Some("png") => "image/png",
+        Some("svg") => "image/svg+xml",
+        _ => "image/jpeg",
+    }
+}
+
+// Returns the view box and the content of the root element
+fn split_svg_document(contents: &str) -> Option<(String, &str)> {
+    let root_start = contents.find("<svg")?;
+    let root_end = root_start + contents[root_start..].find('>')?;
+    let body_end = contents.rfind("</svg>")?;
+    if body_end < root_end {
+        return None;
+    }
+
+    let root = &contents[root_start..root_end];
+    let attribute = |name: &str| {
+        let start = root.find(&format!(" {}=\"", name))? + name.len() + 3;
+        let end = start + root[start..].find('"')?;
+        Some(root[start..end].to_string())
+    };
+
+    let view_box = attribute("viewBox").or_else(|| {
+        // Lengths can have units, e.g. "100px", which aren't meaningful in a view box
+        let number = |value: String| {
+            value
+                .trim_end_matches(|c: char| c.is_alphabetic() || c == '%')
+                .parse::<f32>()
+                .ok()
+        };
+        let width = attribute("width").and_then(number)?;
+        let height = attribute("height").and_then(number)?;
+        Some(format!("0 0 {} {}", width, height))
+    })?;
+
+    Some((view_box, &contents[root_end + 1..body_end]))
+}
+
+fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn base64_encode(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let b0 = chunk[0] as u32;
+        let b1 = chunk.get(1).copied().unwrap_or(0) as u32;
+        let b2 = chunk.get(2).copied().unwrap_or(0) as u32;
+        let triple = (b0 << 16) | (b1 << 8) | b2;
+
+        encoded.push(ALPHABET[(triple >> 18) as usize & 0x3f] as char);
+        encoded.push(ALPHABET[(triple >> 12) as usize & 0x3f] as char);
+        encoded.push(if chunk.len() > 1 {
+            ALPHABET[(triple >> 6) as usize & 0x3f] as char
+        } else {
+            '='
+        });
+        encoded.push(if chunk.len() > 2 {
+            ALPHABET[triple as usize & 0x3f] as char
+        } else {
+            '='
+        });
+    }
+    encoded
+}
@@ -0,0 +1,118 @@
+use chrono::{Datelike, Local};
+use serde::{Deserialize, Serialize};
+use strum_macros::{Display, EnumIter};
+
+use crate::{
+    book_metadata::BookMetadata,
+    dependencies::{Dependency, SingletonFor},
+    photo::Photo,
+    photo_captions::CaptionPattern,
+    photo_manager::PhotoManager,
+};
+
+/// What a template's text region is filled in with once photos are placed in the template
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, EnumIter, Serialize, Deserialize)]
+pub enum TemplateAutofill {
+    Location,
+    #[strum(to_string = "Date Range")]
+    DateRange,
+    Caption,
+}
+
+impl TemplateAutofill {
+    pub fn description(&self) -> &'static str {
+        match self {
+            TemplateAutofill::Location => {
+                "The folders the photos are in, usually named after the place. Falls back to the book's location."
+            }
+            TemplateAutofill::DateRange => "When the photos were taken, from the first to the last",
+            TemplateAutofill::Caption => "The captions of the photos, or their titles",
+        }
+    }
+
+    /// The text for a region showing `photos`, None when they have nothing to fill it with
+    pub fn text(&self, photos: &[&Photo]) -> Option<String> {
+        let text = match self {
+            TemplateAutofill::Location => Self::location(photos),
+            TemplateAutofill::DateRange => Self::date_range(photos),
+            TemplateAutofill::Caption => Self::captions(photos),
+        };
+
+        Some(text).filter(|text| !text.trim().is_empty())
+    }
+
+    fn location(photos: &[&Photo]) -> String {
+        let pattern = CaptionPattern {
+            template: "{folder}".to_string(),
+            ..CaptionPattern::default()
+        };
+
+        let mut folders: Vec<String> = Vec::new();
+        for photo in photos {
+            let folder = pattern.apply(photo, 0);
+            if !folder.is_empty() && !folders.contains(&folder) {
+                folders.push(folder);
+            }
+        }
+
+        if folders.is_empty() {
+            BookMetadata::current().location
+        } else {
+            folders.join(", ")
+        }
+    }
+
+    // "May 3, 2024", "May 3–7, 2024", "May 30 – June 2, 2024" or "December 30, 2023 – January 2, 2024"
+    fn date_range(photos: &[&Photo]) -> String {
+        let dates: Vec<_> = photos
+            .iter()
+            .filter_map(|photo| PhotoManager::capture_date(photo))
+            .map(|date| date.with_timezone(&Local).date_naive())
+            .collect();
+
+        let (Some(first), Some(last)) = (dates.iter().min(), dates.iter().max()) else {
+            return String::new();
+        };
+
+        if first == last {
+            first.format("%B %-d, %Y").to_string()
+        } else if first.year() == last.year() && first.month() == last.month() {
+            format!(
+                "{}–{}, {}",
+                first.format("%B %-d"),
+                last.format("%-d"),
+                last.format("%Y")
+            )
+        } else if first.year() == last.year() {
+            format!(
+                "{} – {}, {}",
+                first.format("%B %-d"),
+                last.format("%B %-d"),
+                last.format("%Y")
+            )
+        } else {
+            format!(
+                "{} – {}",
+                first.format("%B %-d, %Y"),
+                last.format("%B %-d, %Y")
+            )
+        }
+    }
+
+    // One line per photo, photos sharing a caption only add it once
+    fn captions(photos: &[&Photo]) -> String {
+        let mut lines: Vec<String> = Vec::new();
+        Dependency::<PhotoManager>::get().with_lock(|photo_manager| {
+            for photo in photos {
+                let captions = photo_manager.captions(&photo.path);
+                if let Some(text) = captions.caption.or(captions.title) {
+                    if !lines.contains(&text) {
+                        lines.push(text);
+                    }
+                }
+            }
+        });
+
+        lines.join("\n")
+    }
+}
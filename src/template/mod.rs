@@ -1,7 +1,13 @@
+pub mod autofill;
+pub mod pack;
+
 use egui::{Pos2, Vec2};
 use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+use crate::model::{layer_constraints::LayerConstraints, page::Page};
 
-use crate::model::page::Page;
+use self::autofill::TemplateAutofill;
 
 pub const BUILT_IN: Lazy<Vec<Template>> = Lazy::new(|| {
     vec![
@@ -12,6 +18,7 @@ pub const BUILT_IN: Lazy<Vec<Template>> = Lazy::new(|| {
             regions: vec![TemplateRegion {
                 relative_position: Pos2::new(0.0, 0.0),
                 relative_size: Vec2::new(1.0, 1.0),
+                constraints: LayerConstraints::default(),
                 kind: TemplateRegionKind::Image,
             }],
         },
@@ -23,22 +30,27 @@ pub const BUILT_IN: Lazy<Vec<Template>> = Lazy::new(|| {
                 TemplateRegion {
                     relative_position: Pos2::new(0.05, 0.05),
                     relative_size: Vec2::new(0.4, 0.6 * 1.5),
+                    constraints: LayerConstraints::default(),
                     kind: TemplateRegionKind::Image,
                 },
                 TemplateRegion {
                     relative_position: Pos2::new(0.55, 0.1),
                     relative_size: Vec2::new(0.4, 0.1),
+                    constraints: LayerConstraints::default(),
                     kind: TemplateRegionKind::Text {
                         sample_text: "Title".to_string(),
-                        font_size: 150.0
+                        font_size: 150.0,
+                        autofill: Some(TemplateAutofill::Location),
                     },
                 },
                 TemplateRegion {
                     relative_position: Pos2::new(0.55, 0.2),
                     relative_size: Vec2::new(0.4, 0.7),
+                    constraints: LayerConstraints::default(),
                     kind: TemplateRegionKind::Text {
                         sample_text: "Lorem ipsum dolor sit amet, consectetur adipiscing elit. Integer tempor libero eros, vel scelerisque quam fringilla et. Mauris libero augue, tempus vel eros ut, semper finibus arcu. Pellentesque pellentesque sit amet augue a laoreet. Integer eget feugiat ex, vel efficitur ante. Nullam sed mi imperdiet turpis varius scelerisque id eu dolor. Nulla sollicitudin vehicula interdum. Nunc diam libero, ullamcorper at feugiat eget, dapibus in ante.".to_string(),
-                        font_size: 32.0
+                        font_size: 32.0,
+                        autofill: Some(TemplateAutofill::Caption),
                     },
                 },
             ],
@@ -51,11 +63,13 @@ pub const BUILT_IN: Lazy<Vec<Template>> = Lazy::new(|| {
                 TemplateRegion {
                     relative_position: Pos2::new(0.0, 0.0),
                     relative_size: Vec2::new(0.5, 1.0),
+                    constraints: LayerConstraints::default(),
                     kind: TemplateRegionKind::Image,
                 },
                 TemplateRegion {
                     relative_position: Pos2::new(0.5, 0.0),
                     relative_size: Vec2::new(0.5, 1.0),
+                    constraints: LayerConstraints::default(),
                     kind: TemplateRegionKind::Image,
                 },
             ],
@@ -68,16 +82,19 @@ pub const BUILT_IN: Lazy<Vec<Template>> = Lazy::new(|| {
                 TemplateRegion {
                     relative_position: Pos2::new(0.0, 0.0),
                     relative_size: Vec2::new(0.333, 1.0),
+                    constraints: LayerConstraints::default(),
                     kind: TemplateRegionKind::Image,
                 },
                 TemplateRegion {
                     relative_position: Pos2::new(0.333, 0.0),
                     relative_size: Vec2::new(0.333, 1.0),
+                    constraints: LayerConstraints::default(),
                     kind: TemplateRegionKind::Image,
                 },
                 TemplateRegion {
                     relative_position: Pos2::new(0.666, 0.0),
                     relative_size: Vec2::new(0.333, 1.0),
+                    constraints: LayerConstraints::default(),
                     kind: TemplateRegionKind::Image,
                 },
             ],
@@ -85,22 +102,31 @@ pub const BUILT_IN: Lazy<Vec<Template>> = Lazy::new(|| {
     ]
 });
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct Template {
     pub name: String,
     pub page: Page,
     pub regions: Vec<TemplateRegion>,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct TemplateRegion {
     pub relative_position: Pos2,
     pub relative_size: Vec2,
+    // Where layers made from the region go when the page changes size
+    #[serde(default)]
+    pub constraints: LayerConstraints,
     pub kind: TemplateRegionKind,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub enum TemplateRegionKind {
     Image,
-    Text { sample_text: String, font_size: f32 },
+    Text {
+        sample_text: String,
+        font_size: f32,
+        // Filled in from the photos placed in the template, the sample text shows until then
+        #[serde(default)]
+        autofill: Option<TemplateAutofill>,
+    },
 }
@@ -0,0 +1,78 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use super::Template;
+
+pub const TEMPLATE_PACK_EXTENSION: &str = "json";
+
+// Bumped when the format changes in a way older versions can't read
+const TEMPLATE_PACK_VERSION: u32 = 1;
+
+#[derive(Error, Debug)]
+pub enum TemplatePackError {
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+
+    #[error("Serde error: {0}")]
+    SerdeError(#[from] serde_json::Error),
+
+    #[error("The template pack was made by a newer version (format {0})")]
+    UnsupportedVersion(u32),
+
+    #[error("The template pack has no templates")]
+    Empty,
+}
+
+/// A named collection of templates shared as a JSON file. Imported packs are listed under their
+/// name in the templates panel and removed together.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TemplatePack {
+    #[serde(default = "TemplatePack::version")]
+    pub version: u32,
+    pub name: String,
+    pub templates: Vec<Template>,
+}
+
+impl TemplatePack {
+    pub fn new(name: String, templates: Vec<Template>) -> Self {
+        Self {
+            version: TEMPLATE_PACK_VERSION,
+            name,
+            templates,
+        }
+    }
+
+    fn version() -> u32 {
+        TEMPLATE_PACK_VERSION
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), TemplatePackError> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Reads a pack from `path`. Packs without a name are named after the file.
+    pub fn read(path: &Path) -> Result<Self, TemplatePackError> {
+        let contents = std::fs::read_to_string(path)?;
+        let mut pack: Self = serde_json::from_str(&contents)?;
+
+        if pack.version > TEMPLATE_PACK_VERSION {
+            return Err(TemplatePackError::UnsupportedVersion(pack.version));
+        }
+
+        if pack.templates.is_empty() {
+            return Err(TemplatePackError::Empty);
+        }
+
+        if pack.name.trim().is_empty() {
+            pack.name = path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+                .unwrap_or_else(|| "Imported Templates".to_string());
+        }
+
+        Ok(pack)
+    }
+}
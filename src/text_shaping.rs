@@ -0,0 +1,327 @@
+use std::borrow::Cow;
+
+// egui maps each character to a single glyph and lays text out left to right, so text that
+// relies on a shaping engine needs some help before it reaches the painter. Only what can be done
+// with whole characters is supported: Arabic letters are converted to their contextual
+// presentation forms and right to left runs are reordered for display. Reordering is done a line
+// at a time after the text is broken into lines, so lines still start with the logically first
+// words. Scripts that combine glyphs (e.g. Devanagari conjuncts) can't be drawn a character at a
+// time at all, see `complex_script`, they're shaped and drawn by glyph id in `glyph_text`.
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Direction {
+    Ltr,
+    Rtl,
+    Neutral,
+}
+
+struct ArabicForms {
+    isolated: char,
+    final_form: char,
+    // Right joining letters don't have initial or medial forms
+    initial: Option<char>,
+    medial: Option<char>,
+}
+
+fn dual(isolated: u32) -> Option<ArabicForms> {
+    Some(ArabicForms {
+        isolated: char_from(isolated),
+        final_form: char_from(isolated + 1),
+        initial: Some(char_from(isolated + 2)),
+        medial: Some(char_from(isolated + 3)),
+    })
+}
+
+fn right(isolated: u32) -> Option<ArabicForms> {
+    Some(ArabicForms {
+        isolated: char_from(isolated),
+        final_form: char_from(isolated + 1),
+        initial: None,
+        medial: None,
+    })
+}
+
+fn char_from(code_point: u32) -> char {
+    char::from_u32(code_point).unwrap_or(char::REPLACEMENT_CHARACTER)
+}
+
+fn arabic_forms(c: char) -> Option<ArabicForms> {
+    match c {
+        '\u{0622}' => right(0xFE81),
+        '\u{0623}' => right(0xFE83),
+        '\u{0624}' => right(0xFE85),
+        '\u{0625}' => right(0xFE87),
+        '\u{0626}' => dual(0xFE89),
+        '\u{0627}' => right(0xFE8D),
+        '\u{0628}' => dual(0xFE8F),
+        '\u{0629}' => right(0xFE93),
+        '\u{062A}' => dual(0xFE95),
+        '\u{062B}' => dual(0xFE99),
+        '\u{062C}' => dual(0xFE9D),
+        '\u{062D}' => dual(0xFEA1),
+        '\u{062E}' => dual(0xFEA5),
+        '\u{062F}' => right(0xFEA9),
+        '\u{0630}' => right(0xFEAB),
+        '\u{0631}' => right(0xFEAD),
+        '\u{0632}' => right(0xFEAF),
+        '\u{0633}' => dual(0xFEB1),
+        '\u{0634}' => dual(0xFEB5),
+        '\u{0635}' => dual(0xFEB9),
+        '\u{0636}' => dual(0xFEBD),
+        '\u{0637}' => dual(0xFEC1),
+        '\u{0638}' => dual(0xFEC5),
+        '\u{0639}' => dual(0xFEC9),
+        '\u{063A}' => dual(0xFECD),
+        '\u{0641}' => dual(0xFED1),
+        '\u{0642}' => dual(0xFED5),
+        '\u{0643}' => dual(0xFED9),
+        '\u{0644}' => dual(0xFEDD),
+        '\u{0645}' => dual(0xFEE1),
+        '\u{0646}' => dual(0xFEE5),
+        '\u{0647}' => dual(0xFEE9),
+        '\u{0648}' => right(0xFEED),
+        '\u{0649}' => right(0xFEEF),
+        '\u{064A}' => dual(0xFEF1),
+        _ => None,
+    }
+}
+
+// Lam followed by one of the alef variants is drawn as a single ligature, returns (isolated, final)
+fn lam_alef_ligature(alef: char) -> Option<(char, char)> {
+    match alef {
+        '\u{0622}' => Some(('\u{FEF5}', '\u{FEF6}')),
+        '\u{0623}' => Some(('\u{FEF7}', '\u{FEF8}')),
+        '\u{0625}' => Some(('\u{FEF9}', '\u{FEFA}')),
+        '\u{0627}' => Some(('\u{FEFB}', '\u{FEFC}')),
+        _ => None,
+    }
+}
+
+const LAM: char = '\u{0644}';
+const TATWEEL: char = '\u{0640}';
+
+fn is_transparent(c: char) -> bool {
+    matches!(c, '\u{0610}'..='\u{061A}' | '\u{064B}'..='\u{065F}' | '\u{0670}' | '\u{06D6}'..='\u{06ED}')
+}
+
+fn joins_to_previous(c: char) -> bool {
+    c == TATWEEL || arabic_forms(c).is_some()
+}
+
+fn joins_to_next(c: char) -> bool {
+    c == TATWEEL
+        || arabic_forms(c)
+            .map(|forms| forms.initial.is_some())
+            .unwrap_or(false)
+}
+
+fn direction(c: char) -> Direction {
+    match c {
+        '\u{0590}'..='\u{08FF}' | '\u{FB1D}'..='\u{FDFF}' | '\u{FE70}'..='\u{FEFF}' => {
+            Direction::Rtl
+        }
+        c if c.is_alphanumeric() => Direction::Ltr,
+        _ => Direction::Neutral,
+    }
+}
+
+fn mirrored(c: char) -> char {
+    match c {
+        '(' => ')',
+        ')' => '(',
+        '[' => ']',
+        ']' => '[',
+        '{' => '}',
+        '}' => '{',
+        '<' => '>',
+        '>' => '<',
+        '«' => '»',
+        '»' => '«',
+        _ => c,
+    }
+}
+
+/// The name of the first script in `text` whose glyphs need combining, which egui can't draw.
+/// Text with one is drawn by `glyph_text` instead.
+pub fn complex_script(text: &str) -> Option<&'static str> {
+    text.chars().find_map(|c| match c {
+        '\u{0900}'..='\u{097F}' | '\u{A8E0}'..='\u{A8FF}' => Some("Devanagari"),
+        '\u{0980}'..='\u{09FF}' => Some("Bengali"),
+        '\u{0A00}'..='\u{0A7F}' => Some("Gurmukhi"),
+        '\u{0A80}'..='\u{0AFF}' => Some("Gujarati"),
+        '\u{0B00}'..='\u{0B7F}' => Some("Odia"),
+        '\u{0B80}'..='\u{0BFF}' => Some("Tamil"),
+        '\u{0C00}'..='\u{0C7F}' => Some("Telugu"),
+        '\u{0C80}'..='\u{0CFF}' => Some("Kannada"),
+        '\u{0D00}'..='\u{0D7F}' => Some("Malayalam"),
+        '\u{0D80}'..='\u{0DFF}' => Some("Sinhala"),
+        '\u{0F00}'..='\u{0FFF}' => Some("Tibetan"),
+        '\u{1000}'..='\u{109F}' => Some("Myanmar"),
+        '\u{1780}'..='\u{17FF}' => Some("Khmer"),
+        _ => None,
+    })
+}
+
+/// Whether `text` has right to left characters, which means its lines have to be broken before
+/// they're drawn so each can be reordered with `reorder`
+pub fn has_rtl(text: &str) -> bool {
+    text.chars().any(|c| direction(c) == Direction::Rtl)
+}
+
+/// Converts Arabic letters to the forms they take next to their neighbors. The text stays in
+/// logical order so it can still be broken into lines.
+pub fn join(text: &str) -> Cow<str> {
+    if !has_rtl(text) {
+        return Cow::Borrowed(text);
+    }
+
+    Cow::Owned(
+        text.split('\n')
+            .map(|line| join_arabic(line).into_iter().collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n"),
+    )
+}
+
+/// Puts a single line of joined text in display order, left to right
+pub fn reorder(line: &str) -> Cow<str> {
+    if !has_rtl(line) {
+        return Cow::Borrowed(line);
+    }
+
+    Cow::Owned(reorder_line(&line.chars().collect::<Vec<_>>()))
+}
+
+fn join_arabic(line: &str) -> Vec<char> {
+    let chars: Vec<char> = line.chars().collect();
+    let mut output = Vec::with_capacity(chars.len());
+
+    let previous_joining = |index: usize| {
+        chars[..index]
+            .iter()
+            .rev()
+            .find(|c| !is_transparent(**c))
+            .copied()
+    };
+    let next_joining = |index: usize| {
+        chars[index + 1..]
+            .iter()
+            .enumerate()
+            .find(|(_, c)| !is_transparent(**c))
+            .map(|(offset, c)| (index + 1 + offset, *c))
+    };
+
+    let mut index = 0;
+    while index < chars.len() {
+        let c = chars[index];
+
+        let forms = match arabic_forms(c) {
+            Some(forms) => forms,
+            None => {
+                output.push(c);
+                index += 1;
+                continue;
+            }
+        };
+
+        let joins_previous = previous_joining(index).is_some_and(joins_to_next);
+        let next = next_joining(index);
+
+        if c == LAM {
+            if let Some((next_index, (isolated, final_form))) =
+                next.and_then(|(i, next)| lam_alef_ligature(next).map(|forms| (i, forms)))
+            {
+                output.push(if joins_previous { final_form } else { isolated });
+                // Keep any marks between the lam and alef
+                output.extend(&chars[index + 1..next_index]);
+                index = next_index + 1;
+                continue;
+            }
+        }
+
+        let joins_next = forms.initial.is_some() && next.is_some_and(|(_, c)| joins_to_previous(c));
+
+        let shaped = match (joins_previous, joins_next) {
+            (false, false) => forms.isolated,
+            (true, false) => forms.final_form,
+            (false, true) => forms.initial.unwrap_or(forms.isolated),
+            (true, true) => forms.medial.unwrap_or(forms.final_form),
+        };
+
+        output.push(shaped);
+        index += 1;
+    }
+
+    output
+}
+
+// A simplified version of the unicode bidi algorithm. Neutral characters take the direction of the
+// text around them and the paragraph direction is determined by the first strong character.
+fn reorder_line(chars: &[char]) -> String {
+    let base_direction = chars
+        .iter()
+        .map(|c| direction(*c))
+        .find(|direction| *direction != Direction::Neutral)
+        .unwrap_or(Direction::Ltr);
+
+    let strong_directions: Vec<Direction> = chars.iter().map(|c| direction(*c)).collect();
+
+    let resolved: Vec<Direction> = (0..chars.len())
+        .map(|index| {
+            if strong_directions[index] != Direction::Neutral {
+                return strong_directions[index];
+            }
+
+            let before = strong_directions[..index]
+                .iter()
+                .rev()
+                .find(|d| **d != Direction::Neutral)
+                .copied()
+                .unwrap_or(base_direction);
+            let after = strong_directions[index + 1..]
+                .iter()
+                .find(|d| **d != Direction::Neutral)
+                .copied()
+                .unwrap_or(base_direction);
+
+            if before == after {
+                before
+            } else {
+                base_direction
+            }
+        })
+        .collect();
+
+    // Group combining marks with their base character so they stay attached when reversed
+    let mut runs: Vec<(Direction, Vec<Vec<char>>)> = Vec::new();
+    for (c, direction) in chars.iter().zip(resolved) {
+        let is_mark = is_transparent(*c);
+        match runs.last_mut() {
+            Some((_, clusters)) if is_mark && !clusters.is_empty() => {
+                clusters.last_mut().unwrap().push(*c);
+            }
+            Some((run_direction, clusters)) if *run_direction == direction => {
+                clusters.push(vec![*c]);
+            }
+            _ => runs.push((direction, vec![vec![*c]])),
+        }
+    }
+
+    if base_direction == Direction::Rtl {
+        runs.reverse();
+    }
+
+    runs.into_iter()
+        .flat_map(|(direction, mut clusters)| {
+            if direction == Direction::Rtl {
+                clusters.reverse();
+                for cluster in &mut clusters {
+                    if let Some(first) = cluster.first_mut() {
+                        *first = mirrored(*first);
+                    }
+                }
+            }
+            clusters.into_iter().flatten()
+        })
+        .collect()
+}
@@ -0,0 +1,97 @@
+use egui::{Color32, FontId};
+
+use crate::{
+    book_theme::{BookTheme, ThemeColorRole, ThemeFontRole},
+    typography::Typography,
+    widget::{
+        canvas::CanvasState,
+        canvas_info::layers::{
+            CanvasText, LayerContent, TextHorizontalAlignment, TextSpacing, TextVerticalAlignment,
+        },
+    },
+};
+
+/// Named text formatting saved with the project. Text layers remember the style they follow so
+/// redefining it restyles all of them at once, e.g. every caption or every title in the book.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextStylePreset {
+    pub name: String,
+    pub font_size: f32,
+    pub font_id: FontId,
+    pub color: Color32,
+    pub font_role: Option<ThemeFontRole>,
+    pub color_role: Option<ThemeColorRole>,
+    pub horizontal_alignment: TextHorizontalAlignment,
+    pub vertical_alignment: TextVerticalAlignment,
+    pub spacing: TextSpacing,
+    pub typography: Typography,
+}
+
+impl TextStylePreset {
+    pub fn from_text(name: String, text: &CanvasText) -> Self {
+        Self {
+            name,
+            font_size: text.font_size,
+            font_id: text.font_id.clone(),
+            color: text.color,
+            font_role: text.font_role,
+            color_role: text.color_role,
+            horizontal_alignment: text.horizontal_alignment,
+            vertical_alignment: text.vertical_alignment,
+            spacing: text.spacing,
+            typography: text.typography,
+        }
+    }
+
+    /// Formats the text with this style and makes it follow the style from now on. Theme roles
+    /// are resolved against `theme` since the style's own font and color may be from an older one.
+    pub fn apply_to_text(&self, text: &mut CanvasText, theme: &BookTheme) {
+        text.font_size = self.font_size;
+        text.font_id = self.font_id.clone();
+        text.color = self.color;
+        text.font_role = self.font_role;
+        text.color_role = self.color_role;
+        text.horizontal_alignment = self.horizontal_alignment;
+        text.vertical_alignment = self.vertical_alignment;
+        text.spacing = self.spacing;
+        text.typography = self.typography;
+        text.style = Some(self.name.clone());
+
+        theme.apply_to_text(text);
+    }
+
+    /// Whether the text still looks the way the style does, ignoring what the theme resolved
+    pub fn matches(&self, text: &CanvasText) -> bool {
+        text.font_size == self.font_size
+            && (text.font_role.is_some() || text.font_id.family == self.font_id.family)
+            && (text.color_role.is_some() || text.color == self.color)
+            && text.font_role == self.font_role
+            && text.color_role == self.color_role
+            && text.horizontal_alignment == self.horizontal_alignment
+            && text.vertical_alignment == self.vertical_alignment
+            && text.spacing == self.spacing
+            && text.typography == self.typography
+    }
+
+    /// Restyles the text layers on the page that follow this style. Returns true if any changed.
+    pub fn apply_to_page(&self, page: &mut CanvasState, theme: &BookTheme) -> bool {
+        let mut changed = false;
+
+        for layer in page.layers.values_mut() {
+            match &mut layer.content {
+                LayerContent::Text(text) | LayerContent::TemplateText { text, .. } => {
+                    if text.style.as_ref() == Some(&self.name) && !self.matches(text) {
+                        self.apply_to_text(text, theme);
+                        changed = true;
+                    }
+                }
+                LayerContent::Photo(_)
+                | LayerContent::TemplatePhoto { .. }
+                | LayerContent::Decoration(_)
+                | LayerContent::Path(_) => {}
+            }
+        }
+
+        changed
+    }
+}
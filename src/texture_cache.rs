@@ -0,0 +1,243 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use egui::{load::SizedTexture, Context, Id, TextureHandle};
+use log::info;
+
+use crate::dirs::Dirs;
+
+/// The largest texture side the budget is sized for. Adapters that support larger textures
+/// don't get a larger budget, adapters that support less get a smaller one.
+pub const MAX_TEXTURE_SIZE: usize = 8192;
+
+// How many maximum size textures fit in the budget
+const BUDGET_TEXTURES: usize = 8;
+// Textures drawn this recently are never evicted, even over budget, to avoid reloading
+// textures that are still on screen every frame
+const EVICTION_GRACE: Duration = Duration::from_secs(2);
+// Prefetching stops once this much of the budget is in use so prefetched textures never push
+// out the ones on screen
+const PREFETCH_BUDGET_PERCENT: usize = 75;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CachedTextureKind {
+    Photo,
+    Thumbnail,
+    // Created by the photo manager and owned by the context's memory instead of a loader
+    Adjusted,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct CachedTexture {
+    texture: SizedTexture,
+    kind: CachedTextureKind,
+    bytes: usize,
+    last_used: Instant,
+    // Loaded ahead of being needed and not drawn since, these are evicted first
+    prefetched: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TextureMetrics {
+    pub textures: usize,
+    pub thumbnails: usize,
+    pub prefetched: usize,
+    pub bytes: usize,
+    pub budget: usize,
+    pub evictions: usize,
+}
+
+/// Photo textures keyed by uri with a memory budget. When the budget is exceeded the least
+/// recently used photo textures are freed, they're loaded again the next time they're drawn.
+/// Thumbnails are small and used as the fallback while photos load, so they're never evicted.
+#[derive(Debug)]
+pub struct TextureCache {
+    textures: HashMap<String, CachedTexture>,
+    thumbnail_uri_prefix: String,
+    budget: usize,
+    bytes: usize,
+    evictions: usize,
+}
+
+impl TextureCache {
+    pub fn new() -> Self {
+        Self {
+            textures: HashMap::new(),
+            thumbnail_uri_prefix: format!("file://{}", Dirs::Thumbnails.path().display()),
+            budget: Self::budget_for(MAX_TEXTURE_SIZE),
+            bytes: 0,
+            evictions: 0,
+        }
+    }
+
+    /// Sizes the budget for the largest texture the graphics adapter supports
+    pub fn set_max_texture_side(&mut self, max_texture_side: usize) {
+        self.budget = Self::budget_for(max_texture_side);
+        info!(
+            "Texture budget set to {} MiB for a maximum texture side of {}",
+            self.budget / (1024 * 1024),
+            max_texture_side
+        );
+    }
+
+    fn budget_for(max_texture_side: usize) -> usize {
+        let side = max_texture_side.min(MAX_TEXTURE_SIZE);
+        side * side * 4 * BUDGET_TEXTURES
+    }
+
+    /// Returns the texture and marks it as used
+    pub fn get(&mut self, uri: &str) -> Option<SizedTexture> {
+        let cached = self.textures.get_mut(uri)?;
+        cached.last_used = Instant::now();
+        cached.prefetched = false;
+        Some(cached.texture)
+    }
+
+    /// Returns the texture without marking it as used
+    pub fn peek(&self, uri: &str) -> Option<SizedTexture> {
+        self.textures.get(uri).map(|cached| cached.texture)
+    }
+
+    pub fn contains_key(&self, uri: &str) -> bool {
+        self.textures.contains_key(uri)
+    }
+
+    /// Whether there's room left in the budget for textures that aren't needed yet
+    pub fn has_prefetch_room(&self) -> bool {
+        self.bytes < self.budget / 100 * PREFETCH_BUDGET_PERCENT
+    }
+
+    /// Adds a texture and evicts other textures if that puts the cache over budget
+    pub fn insert(&mut self, uri: String, texture: SizedTexture, ctx: &Context) {
+        self.insert_texture(uri, texture, false, ctx);
+    }
+
+    /// Adds a texture that isn't drawn yet. It's evicted before any drawn texture until it's
+    /// drawn. Textures that are already cached are left as they are.
+    pub fn insert_prefetched(&mut self, uri: String, texture: SizedTexture, ctx: &Context) {
+        if !self.textures.contains_key(&uri) {
+            self.insert_texture(uri, texture, true, ctx);
+        }
+    }
+
+    fn insert_texture(
+        &mut self,
+        uri: String,
+        texture: SizedTexture,
+        prefetched: bool,
+        ctx: &Context,
+    ) {
+        let kind = if uri.contains("#adjusted-") {
+            CachedTextureKind::Adjusted
+        } else if uri.starts_with(&self.thumbnail_uri_prefix) {
+            CachedTextureKind::Thumbnail
+        } else {
+            CachedTextureKind::Photo
+        };
+
+        let bytes = texture.size.x as usize * texture.size.y as usize * 4;
+
+        let previous = self.textures.insert(
+            uri,
+            CachedTexture {
+                texture,
+                kind,
+                bytes,
+                last_used: Instant::now(),
+                prefetched,
+            },
+        );
+
+        if let Some(previous) = previous {
+            self.bytes -= previous.bytes;
+        }
+        self.bytes += bytes;
+
+        self.evict_over_budget(ctx);
+    }
+
+    fn evict_over_budget(&mut self, ctx: &Context) {
+        if self.bytes <= self.budget {
+            return;
+        }
+
+        let now = Instant::now();
+        let mut candidates: Vec<(String, bool, Instant)> = self
+            .textures
+            .iter()
+            .filter(|(_, cached)| {
+                cached.kind != CachedTextureKind::Thumbnail
+                    && (cached.prefetched || now.duration_since(cached.last_used) > EVICTION_GRACE)
+            })
+            .map(|(uri, cached)| (uri.clone(), cached.prefetched, cached.last_used))
+            .collect();
+
+        // Prefetched textures first, then the least recently used
+        candidates.sort_by_key(|(_, prefetched, last_used)| (!*prefetched, *last_used));
+
+        let mut evicted = 0;
+        for (uri, _, _) in candidates {
+            if self.bytes <= self.budget {
+                break;
+            }
+            self.remove(&uri, ctx);
+            evicted += 1;
+        }
+
+        if evicted > 0 {
+            self.evictions += evicted;
+            info!(
+                "Evicted {} textures, {} MiB of {} MiB in use",
+                evicted,
+                self.bytes / (1024 * 1024),
+                self.budget / (1024 * 1024)
+            );
+        }
+    }
+
+    /// Removes the texture and frees it
+    pub fn remove(&mut self, uri: &str, ctx: &Context) {
+        let Some(cached) = self.textures.remove(uri) else {
+            return;
+        };
+
+        self.bytes -= cached.bytes;
+
+        match cached.kind {
+            CachedTextureKind::Photo | CachedTextureKind::Thumbnail => ctx.forget_image(uri),
+            CachedTextureKind::Adjusted => {
+                // Dropping the last handle frees the texture
+                ctx.data_mut(|data| data.remove::<TextureHandle>(Id::new(uri)));
+            }
+        }
+    }
+
+    /// Removes and frees every texture, thumbnails included
+    pub fn clear(&mut self, ctx: &Context) {
+        let uris: Vec<String> = self.textures.keys().cloned().collect();
+        for uri in uris {
+            self.remove(&uri, ctx);
+        }
+    }
+
+    pub fn metrics(&self) -> TextureMetrics {
+        TextureMetrics {
+            textures: self.textures.len(),
+            thumbnails: self
+                .textures
+                .values()
+                .filter(|cached| cached.kind == CachedTextureKind::Thumbnail)
+                .count(),
+            prefetched: self
+                .textures
+                .values()
+                .filter(|cached| cached.prefetched)
+                .count(),
+            bytes: self.bytes,
+            budget: self.budget,
+            evictions: self.evictions,
+        }
+    }
+}
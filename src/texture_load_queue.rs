@@ -0,0 +1,261 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+// Widgets ask for the textures they show every frame, a request that isn't repeated for this
+// long belongs to a widget that's gone so it's dropped before it starts
+const STALE_AFTER: Duration = Duration::from_millis(500);
+
+// A texture that failed to load isn't tried again for this long so a broken file isn't read every
+// frame, but a file that was only briefly unreadable still shows up
+const RETRY_FAILED_AFTER: Duration = Duration::from_secs(10);
+
+/// How soon a texture is needed. Loads start highest priority first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum LoadPriority {
+    // Loaded ahead of time in case it's needed, like the photos on the next page
+    Prefetch,
+    // Gallery thumbnails and page previews
+    Thumbnail,
+    // Photos on the canvas or in the viewer
+    Visible,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct QueuedLoad {
+    priority: LoadPriority,
+    requested_at: Instant,
+}
+
+/// Texture loads waiting for one of a few load slots. Asking for a texture that's already
+/// waiting or loading doesn't load it twice, and loads nothing asks for anymore are dropped.
+#[derive(Debug)]
+pub struct TextureLoadQueue {
+    queued: HashMap<String, QueuedLoad>,
+    // With the priority they were started at and when they were last asked for
+    loading: HashMap<String, QueuedLoad>,
+    // When each one failed
+    failed: HashMap<String, Instant>,
+    max_loading: usize,
+}
+
+impl TextureLoadQueue {
+    pub fn new() -> Self {
+        Self::with_max_loading(num_cpus::get().max(2))
+    }
+
+    fn with_max_loading(max_loading: usize) -> Self {
+        Self {
+            queued: HashMap::new(),
+            loading: HashMap::new(),
+            failed: HashMap::new(),
+            max_loading,
+        }
+    }
+
+    /// Asks for `uri` to be loaded. Asking again while it waits or loads keeps it from going
+    /// stale and raises its priority if the new one is higher.
+    pub fn request(&mut self, uri: &str, priority: LoadPriority) {
+        let requested_at = Instant::now();
+
+        if let Some(load) = self.loading.get_mut(uri) {
+            load.requested_at = requested_at;
+            return;
+        }
+        if self.has_failed(uri) {
+            return;
+        }
+        self.failed.remove(uri);
+
+        self.queued
+            .entry(uri.to_string())
+            .and_modify(|load| {
+                load.priority = load.priority.max(priority);
+                load.requested_at = requested_at;
+            })
+            .or_insert(QueuedLoad {
+                priority,
+                requested_at,
+            });
+    }
+
+    /// Takes the loads to start now out of the queue, as many as there are free slots, highest
+    /// priority and most recently requested first
+    pub fn next_loads(&mut self) -> Vec<String> {
+        let now = Instant::now();
+        self.queued
+            .retain(|_, load| now.duration_since(load.requested_at) < STALE_AFTER);
+
+        let free = self.max_loading.saturating_sub(self.loading.len());
+        if free == 0 || self.queued.is_empty() {
+            return Vec::new();
+        }
+
+        let mut queued: Vec<(String, QueuedLoad)> = self
+            .queued
+            .iter()
+            .map(|(uri, load)| (uri.clone(), *load))
+            .collect();
+        queued.sort_by(|(_, a), (_, b)| {
+            b.priority
+                .cmp(&a.priority)
+                .then(b.requested_at.cmp(&a.requested_at))
+        });
+
+        queued
+            .into_iter()
+            .take(free)
+            .map(|(uri, load)| {
+                self.queued.remove(&uri);
+                self.loading.insert(uri.clone(), load);
+                uri
+            })
+            .collect()
+    }
+
+    /// Whether a started load is still asked for. Loads wait for a blocking thread so by the time
+    /// one gets going whatever wanted it may be gone, it's cancelled then.
+    pub fn is_wanted(&self, uri: &str) -> bool {
+        self.loading
+            .get(uri)
+            .is_some_and(|load| load.requested_at.elapsed() < STALE_AFTER)
+    }
+
+    /// Frees the load's slot, returning the priority it was started at. A load nothing asked for
+    /// while it ran comes back as a prefetch so it's the first thing evicted.
+    pub fn finished(&mut self, uri: &str) -> Option<LoadPriority> {
+        let load = self.loading.remove(uri)?;
+        if load.requested_at.elapsed() < STALE_AFTER {
+            Some(load.priority)
+        } else {
+            Some(LoadPriority::Prefetch)
+        }
+    }
+
+    pub fn failed(&mut self, uri: &str) {
+        self.loading.remove(uri);
+        self.failed.insert(uri.to_string(), Instant::now());
+    }
+
+    /// Whether `uri` failed to load recently enough that it isn't tried again yet
+    pub fn has_failed(&self, uri: &str) -> bool {
+        self.failed
+            .get(uri)
+            .is_some_and(|failed_at| failed_at.elapsed() < RETRY_FAILED_AFTER)
+    }
+
+    /// Lets every load that failed be tried again right away, e.g. once missing photos are found
+    pub fn retry_failed(&mut self) {
+        self.failed.clear();
+    }
+
+    pub fn queued_count(&self) -> usize {
+        self.queued.len()
+    }
+
+    pub fn loading_count(&self) -> usize {
+        self.loading.len()
+    }
+
+    pub fn is_requested(&self, uri: &str) -> bool {
+        self.queued.contains_key(uri) || self.loading.contains_key(uri)
+    }
+
+    /// Forgets the waiting loads and the failed ones, loads already started still finish
+    pub fn clear(&mut self) {
+        self.queued.clear();
+        self.failed.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn age(load: &mut QueuedLoad) {
+        load.requested_at -= STALE_AFTER;
+    }
+
+    #[test]
+    fn loads_start_highest_priority_first_up_to_the_free_slots() {
+        let mut queue = TextureLoadQueue::with_max_loading(2);
+        queue.request("prefetch", LoadPriority::Prefetch);
+        queue.request("thumbnail", LoadPriority::Thumbnail);
+        queue.request("visible", LoadPriority::Visible);
+
+        assert_eq!(queue.next_loads(), vec!["visible", "thumbnail"]);
+        assert!(queue.next_loads().is_empty());
+
+        queue.finished("visible");
+        assert_eq!(queue.next_loads(), vec!["prefetch"]);
+    }
+
+    #[test]
+    fn repeat_requests_are_merged_and_raise_the_priority() {
+        let mut queue = TextureLoadQueue::with_max_loading(1);
+        queue.request("a", LoadPriority::Prefetch);
+        queue.request("b", LoadPriority::Thumbnail);
+        queue.request("a", LoadPriority::Visible);
+
+        assert_eq!(queue.queued_count(), 2);
+        assert_eq!(queue.next_loads(), vec!["a"]);
+
+        // Already loading, so asking again doesn't queue it a second time
+        queue.request("a", LoadPriority::Visible);
+        assert_eq!(queue.queued_count(), 1);
+        assert_eq!(queue.finished("a"), Some(LoadPriority::Visible));
+    }
+
+    #[test]
+    fn stale_requests_are_dropped_before_they_start() {
+        let mut queue = TextureLoadQueue::with_max_loading(1);
+        queue.request("gone", LoadPriority::Visible);
+        age(queue.queued.get_mut("gone").unwrap());
+
+        assert!(queue.next_loads().is_empty());
+        assert!(!queue.is_requested("gone"));
+    }
+
+    #[test]
+    fn started_loads_nothing_asks_for_are_cancelled() {
+        let mut queue = TextureLoadQueue::with_max_loading(1);
+        queue.request("a", LoadPriority::Visible);
+        queue.next_loads();
+        assert!(queue.is_wanted("a"));
+
+        age(queue.loading.get_mut("a").unwrap());
+        assert!(!queue.is_wanted("a"));
+        // Kept if it finished anyway, but evicted first
+        assert_eq!(queue.finished("a"), Some(LoadPriority::Prefetch));
+    }
+
+    #[test]
+    fn failed_loads_are_retried_after_a_while() {
+        let mut queue = TextureLoadQueue::with_max_loading(1);
+        queue.request("broken", LoadPriority::Visible);
+        queue.next_loads();
+        queue.failed("broken");
+
+        queue.request("broken", LoadPriority::Visible);
+        assert!(queue.has_failed("broken"));
+        assert!(!queue.is_requested("broken"));
+
+        *queue.failed.get_mut("broken").unwrap() -= RETRY_FAILED_AFTER;
+        queue.request("broken", LoadPriority::Visible);
+        assert!(!queue.has_failed("broken"));
+        assert_eq!(queue.next_loads(), vec!["broken"]);
+    }
+
+    #[test]
+    fn failed_loads_are_retried_when_asked_to() {
+        let mut queue = TextureLoadQueue::with_max_loading(1);
+        queue.request("missing", LoadPriority::Visible);
+        queue.next_loads();
+        queue.failed("missing");
+
+        queue.retry_failed();
+        queue.request("missing", LoadPriority::Visible);
+        assert!(queue.is_requested("missing"));
+    }
+}
@@ -1,7 +1,136 @@
+use egui::{Color32, Context, Stroke, Theme, ThemePreference, Visuals};
+use serde::{Deserialize, Serialize};
+use strum_macros::EnumIter;
+
 pub mod color {
-    use egui::Color32;
+    use egui::{Color32, Visuals};
+
+    pub const MISSPELLED: Color32 = Color32::from_rgb(0xe0, 0x30, 0x30);
+    pub const WARNING: Color32 = Color32::from_rgb(0xf0, 0xb0, 0x30);
+    pub const ERROR: Color32 = Color32::from_rgb(0xe0, 0x50, 0x40);
+
+    /// Outlines whatever is selected or hovered, in the accent color
+    pub fn focused(visuals: &Visuals) -> Color32 {
+        visuals.selection.bg_fill
+    }
+
+    pub fn placeholder(visuals: &Visuals) -> Color32 {
+        if visuals.dark_mode {
+            Color32::from_gray(50)
+        } else {
+            Color32::from_gray(215)
+        }
+    }
+
+    pub fn action_bar(visuals: &Visuals) -> Color32 {
+        if visuals.dark_mode {
+            Color32::from_gray(30)
+        } else {
+            Color32::from_gray(235)
+        }
+    }
+
+    /// Headers and strips around the panels, like the top bar and the page strip
+    pub fn chrome(visuals: &Visuals) -> Color32 {
+        if visuals.dark_mode {
+            Color32::from_gray(40)
+        } else {
+            Color32::from_gray(225)
+        }
+    }
+
+    /// The area around the page on the canvas
+    pub fn canvas_background(visuals: &Visuals) -> Color32 {
+        if visuals.dark_mode {
+            Color32::BLACK
+        } else {
+            Color32::from_gray(190)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, EnumIter)]
+pub enum ThemeMode {
+    System,
+    Light,
+    #[default]
+    Dark,
+}
+
+impl std::fmt::Display for ThemeMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ThemeMode::System => write!(f, "System"),
+            ThemeMode::Light => write!(f, "Light"),
+            ThemeMode::Dark => write!(f, "Dark"),
+        }
+    }
+}
+
+impl ThemeMode {
+    fn preference(&self) -> ThemePreference {
+        match self {
+            ThemeMode::System => ThemePreference::System,
+            ThemeMode::Light => ThemePreference::Light,
+            ThemeMode::Dark => ThemePreference::Dark,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, EnumIter)]
+pub enum AccentColor {
+    #[default]
+    Blue,
+    Teal,
+    Green,
+    Orange,
+    Pink,
+    Purple,
+}
+
+impl std::fmt::Display for AccentColor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AccentColor::Blue => write!(f, "Blue"),
+            AccentColor::Teal => write!(f, "Teal"),
+            AccentColor::Green => write!(f, "Green"),
+            AccentColor::Orange => write!(f, "Orange"),
+            AccentColor::Pink => write!(f, "Pink"),
+            AccentColor::Purple => write!(f, "Purple"),
+        }
+    }
+}
+
+impl AccentColor {
+    pub fn color(&self) -> Color32 {
+        match self {
+            AccentColor::Blue => Color32::from_rgb(0x00, 0x7f, 0xff),
+            AccentColor::Teal => Color32::from_rgb(0x00, 0x9c, 0x9c),
+            AccentColor::Green => Color32::from_rgb(0x2e, 0xa0, 0x43),
+            AccentColor::Orange => Color32::from_rgb(0xe0, 0x70, 0x10),
+            AccentColor::Pink => Color32::from_rgb(0xd6, 0x33, 0x84),
+            AccentColor::Purple => Color32::from_rgb(0x7b, 0x4d, 0xd6),
+        }
+    }
+}
+
+/// Installs the light and dark styles with the accent color and picks which of them is used.
+/// With `ThemeMode::System` egui follows the operating system as it changes.
+pub fn apply(ctx: &Context, mode: ThemeMode, accent: AccentColor) {
+    ctx.set_visuals_of(Theme::Dark, visuals(Theme::Dark, accent));
+    ctx.set_visuals_of(Theme::Light, visuals(Theme::Light, accent));
+    ctx.set_theme(mode.preference());
+}
+
+fn visuals(theme: Theme, accent: AccentColor) -> Visuals {
+    let mut visuals = theme.default_visuals();
+    let accent = accent.color();
+
+    visuals.selection.bg_fill = accent;
+    visuals.selection.stroke = Stroke::new(1.0, Color32::WHITE);
+    visuals.hyperlink_color = accent;
+    visuals.widgets.hovered.bg_stroke.color = accent;
+    visuals.widgets.active.bg_stroke.color = accent;
 
-    pub const FOCUSED: Color32 = Color32::from_rgb(0x00, 0x7f, 0xff);
-    pub const PLACEHOLDER: Color32 = Color32::from_rgb(50, 50, 50);
-    pub const ACTION_BAR: Color32 = Color32::from_rgb(30, 30, 30);
+    visuals
 }
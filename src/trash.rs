@@ -0,0 +1,115 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Duration, Utc};
+use indexmap::indexmap;
+
+use crate::{
+    model::{edit_state::EditablePage, page::Page},
+    widget::{canvas::CanvasState, canvas_info::layers::Layer},
+};
+
+pub type TrashEntryId = u64;
+
+#[derive(Debug, Clone)]
+pub enum TrashedItem {
+    Page(CanvasState),
+    Layer {
+        layer: Layer,
+        // The page the layer was deleted from, used to preview the layer in the trash
+        page: Page,
+        // The canvas of the page it was deleted from, to put it back there. Not kept once the
+        // project is closed.
+        canvas_id: Option<egui::Id>,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct TrashEntry {
+    pub id: TrashEntryId,
+    pub item: TrashedItem,
+    pub deleted_at: DateTime<Utc>,
+}
+
+impl TrashEntry {
+    pub fn new(item: TrashedItem, deleted_at: DateTime<Utc>) -> Self {
+        Self {
+            id: rand::random(),
+            item,
+            deleted_at,
+        }
+    }
+}
+
+pub struct TrashManager {
+    pub entries: Vec<TrashEntry>,
+    // What each entry looks like in the trash, made the first time it's shown
+    previews: HashMap<TrashEntryId, CanvasState>,
+}
+
+impl TrashManager {
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+            previews: HashMap::new(),
+        }
+    }
+
+    pub fn trash_page(&mut self, page: CanvasState) {
+        self.entries
+            .push(TrashEntry::new(TrashedItem::Page(page), Utc::now()));
+    }
+
+    pub fn trash_layers(&mut self, layers: impl IntoIterator<Item = Layer>, page: &CanvasState) {
+        let deleted_at = Utc::now();
+        for mut layer in layers {
+            layer.selected = false;
+            self.entries.push(TrashEntry::new(
+                TrashedItem::Layer {
+                    layer,
+                    page: page.page.value.clone(),
+                    canvas_id: Some(page.canvas_id),
+                },
+                deleted_at,
+            ));
+        }
+    }
+
+    // Removes the entry from the trash so it can be restored by the caller
+    pub fn take(&mut self, id: TrashEntryId) -> Option<TrashEntry> {
+        let index = self.entries.iter().position(|entry| entry.id == id)?;
+        Some(self.entries.remove(index))
+    }
+
+    pub fn empty(&mut self) {
+        self.entries.clear();
+    }
+
+    pub fn purge_older_than(&mut self, days: u32) {
+        let cutoff = Utc::now() - Duration::days(days as i64);
+        self.entries.retain(|entry| entry.deleted_at > cutoff);
+
+        let entries = &self.entries;
+        self.previews
+            .retain(|id, _| entries.iter().any(|entry| entry.id == *id));
+    }
+
+    /// The page to draw as the entry's thumbnail, with widget ids of its own so it doesn't share
+    /// state with the page it came from
+    pub fn preview(&mut self, id: TrashEntryId) -> Option<&mut CanvasState> {
+        let entry = self.entries.iter().find(|entry| entry.id == id)?;
+
+        Some(
+            self.previews
+                .entry(id)
+                .or_insert_with(|| match &entry.item {
+                    TrashedItem::Page(page) => page.clone_with_new_widget_ids(),
+                    TrashedItem::Layer { layer, page, .. } => CanvasState::with_layers(
+                        indexmap! { layer.id => layer.clone() },
+                        EditablePage::new(page.clone()),
+                        None,
+                        vec![layer.id],
+                    ),
+                }),
+        )
+    }
+}
@@ -0,0 +1,475 @@
+use std::collections::VecDeque;
+
+use strum_macros::{Display, EnumIter};
+
+// Line breaking for text layers that goes a little further than egui's own wrapping. Paragraphs
+// are broken into lines here, before egui sees them, so words can be hyphenated and the last
+// line of a paragraph kept from being a lone word. Hyphenation is rule based rather than
+// dictionary based: words are split between syllables found from the language's vowels and the
+// consonant clusters it keeps together, which gets most long words right but not all of them.
+
+pub const NON_BREAKING_SPACE: char = '\u{A0}';
+
+// Words shorter than this are never hyphenated
+const MIN_HYPHENATED_WORD: usize = 6;
+
+// How much narrower each attempt at rebreaking a paragraph with a widow is, as a fraction of the
+// width, and how many attempts are made before giving up
+const WIDOW_STEP: f32 = 0.03;
+const WIDOW_ATTEMPTS: usize = 8;
+
+// Kept free at the end of each line so egui doesn't wrap a line that only just fits again
+const WRAP_SLACK: f32 = 1.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, EnumIter)]
+pub enum HyphenationLanguage {
+    English,
+    German,
+    French,
+    Spanish,
+    Italian,
+    Dutch,
+}
+
+impl HyphenationLanguage {
+    fn vowels(&self) -> &'static str {
+        match self {
+            HyphenationLanguage::English => "aeiouy",
+            HyphenationLanguage::German => "aeiouyäöü",
+            HyphenationLanguage::French => "aeiouyàâéèêëîïôûùüÿœæ",
+            HyphenationLanguage::Spanish => "aeiouáéíóúü",
+            HyphenationLanguage::Italian => "aeiouàèéìíòóùú",
+            HyphenationLanguage::Dutch => "aeiouyëïéèê",
+        }
+    }
+
+    // Consonant clusters that are never split and start the syllable they're in
+    fn clusters(&self) -> &'static [&'static str] {
+        match self {
+            HyphenationLanguage::English => &[
+                "ch", "sh", "th", "ph", "wh", "bl", "br", "cl", "cr", "dr", "fl", "fr", "gl", "gr",
+                "pl", "pr", "tr",
+            ],
+            HyphenationLanguage::German => &["sch", "ch", "ck", "ph", "th"],
+            HyphenationLanguage::French => &[
+                "ch", "gn", "ph", "th", "bl", "br", "cl", "cr", "dr", "fl", "fr", "gl", "gr", "pl",
+                "pr", "tr", "vr",
+            ],
+            HyphenationLanguage::Spanish => &[
+                "ch", "ll", "rr", "bl", "br", "cl", "cr", "dr", "fl", "fr", "gl", "gr", "pl", "pr",
+                "tr",
+            ],
+            HyphenationLanguage::Italian => &[
+                "gn", "sc", "ch", "gh", "sp", "st", "bl", "br", "cl", "cr", "dr", "fl", "fr", "gl",
+                "gr", "pl", "pr", "tr",
+            ],
+            HyphenationLanguage::Dutch => &[
+                "sch", "ch", "bl", "br", "cl", "cr", "dr", "fl", "fr", "gl", "gr", "pl", "pr", "tr",
+            ],
+        }
+    }
+
+    // Consonant clusters that are never split and end the syllable they're in
+    fn codas(&self) -> &'static [&'static str] {
+        match self {
+            HyphenationLanguage::English => &["ck", "ng"],
+            HyphenationLanguage::Dutch => &["ng"],
+            _ => &[],
+        }
+    }
+
+    // The fewest letters left before and after a hyphen
+    fn min_fragment(&self) -> (usize, usize) {
+        match self {
+            HyphenationLanguage::English | HyphenationLanguage::French => (2, 3),
+            _ => (2, 2),
+        }
+    }
+
+    /// The byte offsets in `word` where it can be hyphenated. Words with anything but letters
+    /// in them, apart from punctuation at either end, aren't hyphenated except after a hyphen
+    /// they already have.
+    pub fn hyphenation_points(&self, word: &str) -> Vec<usize> {
+        if word.contains('-') {
+            return word
+                .match_indices('-')
+                .map(|(index, _)| index + 1)
+                .filter(|&index| index < word.len())
+                .collect();
+        }
+
+        let start = word.find(|c: char| c.is_alphabetic()).unwrap_or(word.len());
+        let end = word
+            .char_indices()
+            .rfind(|(_, c)| c.is_alphabetic())
+            .map_or(start, |(index, c)| index + c.len_utf8());
+        let core = &word[start..end];
+
+        if core.is_empty() || !core.chars().all(char::is_alphabetic) {
+            return Vec::new();
+        }
+
+        let letters: Vec<(usize, char)> = core
+            .char_indices()
+            .map(|(index, c)| (start + index, c.to_lowercase().next().unwrap_or(c)))
+            .collect();
+
+        // Acronyms and short words are left alone
+        if letters.len() < MIN_HYPHENATED_WORD || core.chars().all(char::is_uppercase) {
+            return Vec::new();
+        }
+
+        let is_vowel = |c: char| self.vowels().contains(c);
+        let (min_before, min_after) = self.min_fragment();
+        let mut points = Vec::new();
+        let mut index = 0;
+
+        // Skip to the first vowel, consonants before it can't start a new syllable
+        while index < letters.len() && !is_vowel(letters[index].1) {
+            index += 1;
+        }
+
+        while index < letters.len() {
+            while index < letters.len() && is_vowel(letters[index].1) {
+                index += 1;
+            }
+
+            let consonants_start = index;
+            while index < letters.len() && !is_vowel(letters[index].1) {
+                index += 1;
+            }
+
+            // Consonants at the end of the word stay with the last syllable
+            if index >= letters.len() {
+                break;
+            }
+
+            let consonants: String = letters[consonants_start..index]
+                .iter()
+                .map(|(_, c)| *c)
+                .collect();
+            let count = consonants.chars().count();
+
+            // Between two vowels the syllable breaks before the last consonant, or before a
+            // cluster that starts the next syllable
+            let mut split = count.saturating_sub(1);
+            if let Some(cluster) = self
+                .clusters()
+                .iter()
+                .filter(|cluster| consonants.ends_with(*cluster))
+                .max_by_key(|cluster| cluster.len())
+            {
+                split = count - cluster.chars().count();
+            } else if split > 0 {
+                let pair: String = consonants.chars().skip(split - 1).collect();
+                if self.codas().contains(&pair.as_str()) {
+                    split += 1;
+                }
+            }
+
+            let point = consonants_start + split;
+            if count > 0 && point >= min_before && letters.len() - point >= min_after {
+                points.push(letters[point].0);
+            }
+        }
+
+        points
+    }
+}
+
+/// Typographic niceties for multi-line text layers
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Typography {
+    // Words are hyphenated by the rules of the language when set
+    pub hyphenation: Option<HyphenationLanguage>,
+    // Keeps the last line of a paragraph from being a lone word or the end of a hyphenated one
+    pub avoid_widows: bool,
+    // Keeps short words, and numbers, on the same line as the word after them
+    pub tie_short_words: bool,
+}
+
+impl Typography {
+    pub fn breaks_lines(&self) -> bool {
+        self.hyphenation.is_some() || self.avoid_widows
+    }
+
+    /// Breaks `paragraph` into lines no wider than `width`. The first line is `indent` narrower.
+    /// `measure` gives the width of a line of text.
+    pub fn break_paragraph(
+        &self,
+        paragraph: &str,
+        width: f32,
+        indent: f32,
+        measure: &mut impl FnMut(&str) -> f32,
+    ) -> Vec<String> {
+        let lines = self.break_greedy(paragraph, width, indent, measure);
+
+        if !self.avoid_widows || lines.len() < 2 || !is_widow(&lines) {
+            return lines;
+        }
+
+        // Narrowing the paragraph a little pushes more words onto the last line, as long as it
+        // doesn't add a line
+        (1..=WIDOW_ATTEMPTS)
+            .map(|attempt| width * (1.0 - WIDOW_STEP * attempt as f32))
+            .map(|narrower| self.break_greedy(paragraph, narrower, indent, measure))
+            .find(|narrower| narrower.len() == lines.len() && !is_widow(narrower))
+            .unwrap_or(lines)
+    }
+
+    fn break_greedy(
+        &self,
+        paragraph: &str,
+        width: f32,
+        indent: f32,
+        measure: &mut impl FnMut(&str) -> f32,
+    ) -> Vec<String> {
+        let mut words: VecDeque<String> = paragraph
+            .split(' ')
+            .filter(|word| !word.is_empty())
+            .map(str::to_string)
+            .collect();
+        let mut lines: Vec<String> = Vec::new();
+        let mut line = String::new();
+
+        while let Some(word) = words.pop_front() {
+            let available = if lines.is_empty() {
+                width - indent
+            } else {
+                width
+            } - WRAP_SLACK;
+
+            let candidate = if line.is_empty() {
+                word.clone()
+            } else {
+                format!("{} {}", line, word)
+            };
+
+            if measure(&candidate) <= available {
+                line = candidate;
+                continue;
+            }
+
+            // The last word isn't hyphenated when avoiding widows, its end would be one
+            let hyphenate = !(self.avoid_widows && words.is_empty());
+            if let Some((head, tail)) = self
+                .hyphenation
+                .filter(|_| hyphenate)
+                .and_then(|language| hyphenate_to_fit(&line, &word, language, available, measure))
+            {
+                lines.push(head);
+                line.clear();
+                words.push_front(tail);
+                continue;
+            }
+
+            if line.is_empty() {
+                // Too long for a line of its own, egui breaks it when it wraps
+                lines.push(word);
+            } else {
+                lines.push(std::mem::take(&mut line));
+                words.push_front(word);
+            }
+        }
+
+        if !line.is_empty() || lines.is_empty() {
+            lines.push(line);
+        }
+
+        lines
+    }
+}
+
+// The longest start of `word` that fits on `line` with a hyphen, and the rest of the word
+fn hyphenate_to_fit(
+    line: &str,
+    word: &str,
+    language: HyphenationLanguage,
+    available: f32,
+    measure: &mut impl FnMut(&str) -> f32,
+) -> Option<(String, String)> {
+    language
+        .hyphenation_points(word)
+        .into_iter()
+        .rev()
+        .map(|point| {
+            let (start, rest) = word.split_at(point);
+            // Words that already have a hyphen are broken after it without adding another
+            let start = if start.ends_with('-') {
+                start.to_string()
+            } else {
+                format!("{}-", start)
+            };
+            let head = if line.is_empty() {
+                start
+            } else {
+                format!("{} {}", line, start)
+            };
+            (head, rest.to_string())
+        })
+        .find(|(head, _)| measure(head) <= available)
+}
+
+// Whether the last line is a single word, or what's left of one hyphenated on the line before
+fn is_widow(lines: &[String]) -> bool {
+    lines
+        .last()
+        .is_some_and(|line| line.split(' ').filter(|word| !word.is_empty()).count() <= 1)
+}
+
+/// Replaces the spaces after short words and numbers, and before dashes and the punctuation
+/// French sets apart, with non-breaking spaces so they aren't left at the end of a line
+pub fn tie_short_words(text: &str) -> String {
+    text.split('\n')
+        .map(|paragraph| {
+            let words: Vec<&str> = paragraph.split(' ').collect();
+            let mut tied = String::with_capacity(paragraph.len());
+
+            for (index, word) in words.iter().enumerate() {
+                if index > 0 {
+                    let tie = ties_to_next(words[index - 1]) || ties_to_previous(word);
+                    tied.push(if tie { NON_BREAKING_SPACE } else { ' ' });
+                }
+                tied.push_str(word);
+            }
+
+            tied
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn ties_to_next(word: &str) -> bool {
+    let letters = word.chars().filter(|c| c.is_alphabetic()).count();
+    let is_short = letters > 0 && letters <= 2 && word.chars().all(char::is_alphabetic);
+    let is_number = word.starts_with(|c: char| c.is_ascii_digit())
+        && word.ends_with(|c: char| c.is_ascii_digit())
+        && word
+            .chars()
+            .all(|c| c.is_ascii_digit() || c == '.' || c == ',');
+
+    is_short || is_number || word.ends_with('«')
+}
+
+fn ties_to_previous(word: &str) -> bool {
+    word.starts_with(['—', '–', ':', ';', '!', '?', '»'])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn measure(text: &str) -> f32 {
+        text.chars().count() as f32
+    }
+
+    #[test]
+    fn words_are_split_between_syllables() {
+        let english = HyphenationLanguage::English;
+
+        assert_eq!(english.hyphenation_points("picture"), vec![3]);
+        // Clusters start the next syllable and codas end the one before
+        assert_eq!(english.hyphenation_points("mother"), vec![2]);
+        assert_eq!(english.hyphenation_points("jackets"), vec![4]);
+        assert_eq!(
+            HyphenationLanguage::German.hyphenation_points("Tasche"),
+            vec![2]
+        );
+    }
+
+    #[test]
+    fn hyphenation_points_are_byte_offsets() {
+        // "Mäd-chen", the ä takes two bytes
+        assert_eq!(
+            HyphenationLanguage::German.hyphenation_points("Mädchen"),
+            vec![4]
+        );
+        assert_eq!(
+            HyphenationLanguage::English.hyphenation_points("(picture),"),
+            vec![4]
+        );
+    }
+
+    #[test]
+    fn short_words_acronyms_and_non_words_arent_hyphenated() {
+        let english = HyphenationLanguage::English;
+
+        assert!(english.hyphenation_points("house").is_empty());
+        assert!(english.hyphenation_points("UNESCO").is_empty());
+        assert!(english.hyphenation_points("abc123def").is_empty());
+        assert!(english.hyphenation_points("").is_empty());
+        // Too little would be left after the hyphen
+        assert!(english.hyphenation_points("singer").is_empty());
+    }
+
+    #[test]
+    fn words_with_a_hyphen_are_only_broken_after_it() {
+        let english = HyphenationLanguage::English;
+
+        assert_eq!(english.hyphenation_points("well-known"), vec![5]);
+        assert!(english.hyphenation_points("well-").is_empty());
+    }
+
+    #[test]
+    fn lines_are_filled_with_the_start_of_a_hyphenated_word() {
+        let typography = Typography {
+            hyphenation: Some(HyphenationLanguage::English),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            typography.break_paragraph("the picture frame", 11.0, 0.0, &mut measure),
+            vec!["the pic-", "ture frame"]
+        );
+        assert_eq!(
+            Typography::default().break_paragraph("the picture frame", 11.0, 0.0, &mut measure),
+            vec!["the", "picture", "frame"]
+        );
+    }
+
+    #[test]
+    fn the_first_line_is_narrower_by_the_indent() {
+        assert_eq!(
+            Typography::default().break_paragraph("one two three", 10.0, 0.0, &mut measure),
+            vec!["one two", "three"]
+        );
+        assert_eq!(
+            Typography::default().break_paragraph("one two three", 10.0, 4.0, &mut measure),
+            vec!["one", "two three"]
+        );
+    }
+
+    #[test]
+    fn widows_are_avoided_by_narrowing_the_paragraph() {
+        let typography = Typography {
+            avoid_widows: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            Typography::default().break_paragraph("one two three four", 15.0, 0.0, &mut measure),
+            vec!["one two three", "four"]
+        );
+        assert_eq!(
+            typography.break_paragraph("one two three four", 15.0, 0.0, &mut measure),
+            vec!["one two", "three four"]
+        );
+    }
+
+    #[test]
+    fn short_words_and_numbers_are_tied_to_the_next_word() {
+        assert_eq!(
+            tie_short_words("a cat sat on the mat"),
+            "a\u{A0}cat sat on\u{A0}the mat"
+        );
+        assert_eq!(tie_short_words("page 12 of 30"), "page 12\u{A0}of\u{A0}30");
+        assert_eq!(tie_short_words("a b\nc d"), "a\u{A0}b\nc\u{A0}d");
+    }
+
+    #[test]
+    fn dashes_and_french_punctuation_are_tied_to_the_word_before() {
+        assert_eq!(tie_short_words("Quoi ?"), "Quoi\u{A0}?");
+        assert_eq!(tie_short_words("« Oui »"), "«\u{A0}Oui\u{A0}»");
+        assert_eq!(tie_short_words("then — later"), "then\u{A0}— later");
+    }
+}
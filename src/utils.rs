@@ -8,7 +8,7 @@ use eframe::{
     emath::Rot2,
     epaint::{Pos2, Rect, Vec2},
 };
-use egui::{Align, Id, InnerResponse, Layout, Sense, Ui};
+use egui::{Align, Id, InnerResponse, Layout, Response, Sense, Ui};
 
 use crate::{
     cursor_manager::CursorManager,
@@ -25,6 +25,11 @@ pub fn partition_iterator<T>(iter: impl Iterator<Item = T>, partitions: usize) -
     output
 }
 
+/// Quotes the field and doubles any quotes in it so commas and line breaks stay in the field
+pub fn csv_field(text: &str) -> String {
+    format!("\"{}\"", text.replace('"', "\"\""))
+}
+
 pub trait Truncate {
     fn truncate(&self, max_length: usize) -> String;
 }
@@ -309,6 +314,18 @@ impl EguiUiExt for Ui {
     }
 }
 
+pub trait ResponseExt {
+    fn changed_for_history(&self) -> bool;
+}
+
+impl ResponseExt for Response {
+    /// True when the value changed and the change should be recorded in the history. Only the
+    /// final value of a drag is recorded rather than every value along the way.
+    fn changed_for_history(&self) -> bool {
+        self.drag_stopped() || (self.changed() && !self.dragged())
+    }
+}
+
 pub trait ExifDateTimeExt {
     fn into_chrono_date_time(&self) -> Result<chrono::DateTime<Utc>, ParseError>;
 }
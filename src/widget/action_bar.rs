@@ -1,7 +1,7 @@
 use egui::{frame, Button, Color32, Frame, ImageSource, Stroke, Theme, Ui};
 use font_kit::canvas::Canvas;
 
-use crate::{assets::Asset, theme::color::ACTION_BAR, utils::EguiUiExt};
+use crate::{assets::Asset, theme::color, utils::EguiUiExt};
 
 #[derive(Debug, Clone)]
 pub enum ActionItemKind {
@@ -44,7 +44,7 @@ impl<T: Clone> ActionBar<T> {
     pub fn show(&mut self, ui: &mut Ui) -> ActionBarResponse<T> {
         Frame::canvas(ui.style())
             .inner_margin(10.0)
-            .fill(ACTION_BAR)
+            .fill(color::action_bar(ui.visuals()))
             .rounding(8.0)
             .show(ui, |ui| {
                 ui.horizontal_centered(|ui| {
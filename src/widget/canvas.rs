@@ -1,25 +1,65 @@
-use std::sync::Arc;
+use std::{
+    collections::HashSet,
+    hash::{Hash, Hasher},
+    path::PathBuf,
+    sync::Arc,
+};
 
 use eframe::{
     egui::{self, Context, CursorIcon, Sense, Ui},
     emath::Rot2,
-    epaint::{Color32, FontId, Mesh, Pos2, Rect, Shape, Vec2},
+    epaint::{Color32, FontId, Pos2, Rect, Shape, TextShape, Vec2},
+};
+use egui::{
+    Align, Align2, Button, DragAndDrop, Frame, Id, Image, Layout, Mesh, RichText, Stroke,
+    TextureHandle, TextureOptions, UiBuilder,
 };
-use egui::{Align, Button, Frame, Id, Layout, RichText, Stroke, UiBuilder};
+use fxhash::{hash64, FxHasher};
 use indexmap::{indexmap, IndexMap};
 use printpdf::image_crate::flat::SampleLayout;
+use strum::IntoEnumIterator;
 
 use crate::{
+    auto_persisting::AutoPersisting,
+    book_metadata::BookMetadata,
+    book_theme::{ThemeColorRole, ThemeFontRole},
+    config::Config,
     cursor_manager::CursorManager,
     debug::DebugSettings,
+    decoration::{Decoration, DecorationKind},
     dependencies::{Dependency, Singleton, SingletonFor},
+    font_manager::FontManager,
     id::{next_layer_id, next_quick_layout_index, LayerId},
-    model::{edit_state::EditablePage, page::Page, scale_mode::ScaleMode},
+    layer_naming::LayerNaming,
+    model::{
+        canvas_path::CanvasPath,
+        caption_anchor::CaptionAnchor,
+        color_label::ColorLabel,
+        edit_state::EditablePage,
+        fill::{Fill, Gradient},
+        guide::{Guide, GuideOrientation},
+        layer_constraints::LayerConstraints,
+        measurement::Measurement,
+        page::Page,
+        page_comment::PageComment,
+        page_notes::PageNotes,
+        photo_effects::PhotoEffects,
+        photo_orientation::{OrientCommand, PhotoOrientation},
+        scale_mode::{PhotoAlignment, ScaleMode},
+    },
+    page_rescale::{PageRescale, RescaleAnchor},
     photo::{self, Photo},
+    photo_adjustments::PhotoAdjustments,
+    photo_captions::CaptionPattern,
     photo_manager::PhotoManager,
     project_settings::ProjectSettingsManager,
+    render,
+    saliency::SaliencyCache,
     scene::canvas_scene::{CanvasHistoryKind, CanvasHistoryManager},
-    template::{Template, TemplateRegionKind},
+    snippet::Snippet,
+    template::{Template, TemplateRegion, TemplateRegionKind},
+    text_shaping, theme,
+    trash::TrashManager,
     utils::{IdExt, RectExt, Toggle},
 };
 
@@ -29,13 +69,24 @@ use super::{
     canvas_info::{
         layers::{
             CanvasText, Layer, LayerContent, LayerTransformEditState, TextHorizontalAlignment,
-            TextVerticalAlignment,
+            TextOrientation, TextVerticalAlignment,
         },
+        page_fit,
         quick_layout::{self, QuickLayout},
     },
-    canvas_state::{CanvasInteractionMode, CropState},
+    canvas_state::{CanvasInteractionMode, CanvasTool, CropState},
+    crop::Crop,
+    guides::{GuideOverlay, RULER_SIZE},
+    measure_tool::{MeasureTool, MeasurementOverlay},
+    pan_zoom::{PanInertia, PanZoomInput},
+    pen_tool::{PenTool, PenToolResponse},
+    photo_picker::{PhotoPicker, PhotoPickerResponse},
+    photo_tray::DraggedPhoto,
+    placeholder::MissingPhotoPlaceholder,
+    review::{ReviewPins, ReviewState},
+    snippets::DraggedSnippet,
     transformable::{
-        ResizeMode, TransformHandleMode, TransformableState, TransformableWidget,
+        ResizeMode, SnapLines, TransformHandleMode, TransformableState, TransformableWidget,
         TransformableWidgetResponse,
     },
 };
@@ -45,22 +96,216 @@ pub enum CanvasResponse {
     EnterCropMode {
         target_layer: LayerId,
         photo: CanvasPhoto,
+        // Start from a crop suggested from what's in the photo
+        suggest: bool,
     },
 }
 
+// The number of previous crops kept for each photo
+const MAX_CROP_HISTORY: usize = 20;
+
+// How many times wider or taller than the photo a layer has to be before a smart crop is
+// suggested, below this the crop around the current one is close enough
+const SMART_CROP_ASPECT_DIFFERENCE: f32 = 1.3;
+
+// The size of the anchors and handles of the selected path
+const PATH_POINT_SIZE: f32 = 7.0;
+
+// The start and end of a gradient being edited on the page
+const GRADIENT_HANDLE_SIZE: f32 = 12.0;
+
+// How far outside a path's stroke, in screen pixels, still picks it
+const PATH_HIT_SLOP: f32 = 4.0;
+
+// How far the page can be zoomed out and in, a zoom of 1.0 shows each pixel of the page as a point
+pub const MIN_ZOOM: f32 = 0.05;
+pub const MAX_ZOOM: f32 = 8.0;
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct CanvasPhoto {
     pub photo: Photo,
     // Normalized crop rect
     pub crop: Rect,
+    // Previous crops, most recent last. Kept with the photo so earlier crops can be restored
+    // in later crop sessions
+    pub crop_history: Vec<Rect>,
+    pub adjustments: PhotoAdjustments,
+    pub orientation: PhotoOrientation,
+    pub effects: PhotoEffects,
 }
 
 impl CanvasPhoto {
+    pub const UNCROPPED: Rect = Rect::from_min_max(Pos2::ZERO, Pos2::new(1.0, 1.0));
+
     pub fn new(photo: Photo) -> Self {
         Self {
             photo,
-            crop: Rect::from_min_size(Pos2::ZERO, Vec2::splat(1.0)),
+            crop: Self::UNCROPPED,
+            crop_history: Vec::new(),
+            adjustments: PhotoAdjustments::default(),
+            orientation: PhotoOrientation::default(),
+            effects: PhotoEffects::default(),
+        }
+    }
+
+    pub fn set_crop(&mut self, crop: Rect) {
+        if crop == self.crop {
+            return;
+        }
+
+        self.crop_history.push(self.crop);
+        if self.crop_history.len() > MAX_CROP_HISTORY {
+            self.crop_history.remove(0);
         }
+
+        self.crop = crop;
+    }
+
+    pub fn is_cropped(&self) -> bool {
+        self.crop != Self::UNCROPPED
+    }
+
+    /// The uncropped size of the photo as it's shown, with the EXIF and user rotation applied
+    pub fn oriented_size(&self) -> Vec2 {
+        let metadata = &self.photo.metadata;
+        self.orientation.oriented_size(
+            metadata.rotation(),
+            Vec2::new(metadata.width() as f32, metadata.height() as f32),
+        )
+    }
+
+    /// The crop in the space of the photo as it's shown. The stored crop is on the unrotated
+    /// image so it stays put when the photo is rotated or flipped.
+    pub fn display_crop(&self) -> Rect {
+        self.image_crop_to_display(self.crop)
+    }
+
+    pub fn image_crop_to_display(&self, crop: Rect) -> Rect {
+        self.orientation
+            .image_rect_to_display(self.photo.metadata.rotation(), crop)
+    }
+
+    pub fn display_crop_to_image(&self, display_crop: Rect) -> Rect {
+        self.orientation
+            .display_rect_to_image(self.photo.metadata.rotation(), display_crop)
+    }
+
+    /// The aspect ratio of the cropped photo as it's shown
+    pub fn cropped_aspect_ratio(&self) -> f32 {
+        let size = self.oriented_size() * self.display_crop().size();
+        size.x / size.y
+    }
+
+    /// The largest crop with the aspect ratio of `size`, centered on the current crop as far as
+    /// the edges of the photo allow. Used to make the photo cover a rect of that size.
+    pub fn crop_to_fill(&self, size: Vec2) -> Rect {
+        let crop_size = self.fill_crop_size(size);
+        let focus = self.display_crop().center();
+
+        let crop = Rect::from_center_size(
+            Pos2::new(
+                focus.x.clamp(crop_size.x / 2.0, 1.0 - crop_size.x / 2.0),
+                focus.y.clamp(crop_size.y / 2.0, 1.0 - crop_size.y / 2.0),
+            ),
+            crop_size,
+        );
+
+        self.display_crop_to_image(crop)
+    }
+
+    /// Like `crop_to_fill` but placed over the most visually important part of the photo rather
+    /// than the current crop. None until the photo's saliency map has been made in the background,
+    /// asking for it starts making it.
+    pub fn suggested_crop(&self, size: Vec2) -> Option<Rect> {
+        let crop_size = self.fill_crop_size(size);
+
+        // The saliency map is of the unrotated image, a quarter turn swaps the crop's sides
+        let image_crop_size = self
+            .display_crop_to_image(Rect::from_min_size(Pos2::ZERO, crop_size))
+            .size();
+
+        let map = Dependency::<SaliencyCache>::get().with_lock_mut(|cache| cache.map(&self.photo));
+        Some(map?.best_window(image_crop_size))
+    }
+
+    /// Whether smart crops are on and a layer of `size` is shaped differently enough from the
+    /// photo that a suggested crop is worth offering. The suggestion is only ever applied from
+    /// the crop scene, where it can be adjusted first.
+    pub fn suggests_crop(&self, size: Vec2) -> bool {
+        let smart_crop = Dependency::<AutoPersisting<Config>>::get().with_lock(|config| {
+            config
+                .read()
+                .map(|config| config.smart_crop())
+                .unwrap_or(false)
+        });
+
+        let photo_size = self.oriented_size();
+        let photo_aspect_ratio = photo_size.x / photo_size.y;
+        let region_aspect_ratio = size.x / size.y;
+        let difference = (photo_aspect_ratio / region_aspect_ratio)
+            .max(region_aspect_ratio / photo_aspect_ratio);
+
+        smart_crop && difference >= SMART_CROP_ASPECT_DIFFERENCE
+    }
+
+    // The normalized size of the largest crop with the aspect ratio of `size`
+    fn fill_crop_size(&self, size: Vec2) -> Vec2 {
+        let relative_size = size / self.oriented_size();
+        relative_size / relative_size.max_elem()
+    }
+}
+
+// Shaped text painted by the canvas, kept in the layer's memory with the key it was made for
+#[derive(Clone)]
+struct ShapedTextImage {
+    key: u64,
+    // The texture with the size of the text and the padding around it, None if there's no font
+    texture: Option<(TextureHandle, Vec2, f32)>,
+}
+
+// What photos are being picked for, a template region or, without one, new layers on the page
+#[derive(Debug, Clone, Copy)]
+struct PhotoPickerTarget {
+    layer_id: Option<LayerId>,
+    anchor: Pos2,
+}
+
+// How long the distance moved with the arrow keys stays on screen after the last nudge, in seconds
+const NUDGE_FEEDBACK_DURATION: f64 = 1.0;
+
+// The distance the selection has been moved with the arrow keys, added up until the keys have
+// been left alone for a moment
+#[derive(Debug, Clone, Copy)]
+struct NudgeFeedback {
+    // In page pixels
+    delta: Vec2,
+    last_nudge: f64,
+}
+
+impl NudgeFeedback {
+    fn id(canvas_id: egui::Id) -> egui::Id {
+        canvas_id.with("nudge_feedback")
+    }
+
+    fn add(ctx: &Context, canvas_id: egui::Id, delta: Vec2, time: f64) {
+        ctx.data_mut(|data| {
+            let feedback = data
+                .get_temp::<NudgeFeedback>(Self::id(canvas_id))
+                .filter(|feedback| time - feedback.last_nudge < NUDGE_FEEDBACK_DURATION);
+            data.insert_temp(
+                Self::id(canvas_id),
+                NudgeFeedback {
+                    delta: feedback.map_or(Vec2::ZERO, |feedback| feedback.delta) + delta,
+                    last_nudge: time,
+                },
+            );
+        });
+    }
+
+    fn current(ctx: &Context, canvas_id: egui::Id) -> Option<Self> {
+        let time = ctx.input(|input| input.time);
+        ctx.data(|data| data.get_temp::<NudgeFeedback>(Self::id(canvas_id)))
+            .filter(|feedback| time - feedback.last_nudge < NUDGE_FEEDBACK_DURATION)
     }
 }
 
@@ -74,7 +319,25 @@ pub struct CanvasState {
     pub template: Option<Template>,
     pub quick_layout_order: Vec<LayerId>,
     pub last_quick_layout: Option<quick_layout::Layout>,
+    pub quick_layout_parameters: quick_layout::QuickLayoutParameters,
     pub canvas_id: egui::Id,
+    // Review comments pinned to the page, they aren't part of the undo history
+    pub comments: Vec<PageComment>,
+    // Guides layers snap to, they aren't part of the undo history either
+    pub guides: Vec<Guide>,
+    // Distances measured with the measure tool, kept until they're cleared
+    pub measurements: Vec<Measurement>,
+    // Where the page is at, e.g. draft or done, shown in the pages panel
+    pub color_label: Option<ColorLabel>,
+    // Notes and things left to do on the page, not part of the undo history
+    pub notes: PageNotes,
+    pub background: Fill,
+    // Layers matching the search in the layers panel, outlined on the canvas
+    pub search_matches: Vec<LayerId>,
+    // Name of the section or chapter that starts at this page, e.g. "Day 1 – Rome"
+    pub section_start: Option<String>,
+    // Name of the section the page is in, kept up to date by PagesState::update_sections
+    pub section: Option<String>,
     computed_initial_zoom: bool,
 }
 
@@ -97,7 +360,17 @@ impl CanvasState {
             template: None,
             quick_layout_order: Vec::new(),
             last_quick_layout: None,
+            quick_layout_parameters: quick_layout::QuickLayoutParameters::default(),
             canvas_id: Id::random(),
+            comments: Vec::new(),
+            guides: Vec::new(),
+            measurements: Vec::new(),
+            color_label: None,
+            notes: PageNotes::default(),
+            background: Fill::default(),
+            search_matches: Vec::new(),
+            section_start: None,
+            section: None,
             computed_initial_zoom: false,
         }
     }
@@ -117,7 +390,17 @@ impl CanvasState {
             template,
             quick_layout_order: quick_layout_order,
             last_quick_layout: None,
+            quick_layout_parameters: quick_layout::QuickLayoutParameters::default(),
             canvas_id: Id::random(),
+            comments: Vec::new(),
+            guides: Vec::new(),
+            measurements: Vec::new(),
+            color_label: None,
+            notes: PageNotes::default(),
+            background: Fill::default(),
+            search_matches: Vec::new(),
+            section_start: None,
+            section: None,
             computed_initial_zoom: false,
         }
     }
@@ -157,12 +440,14 @@ impl CanvasState {
         let layer = Layer {
             content: LayerContent::Photo(canvas_photo),
             name,
+            auto_named: true,
             visible: true,
             locked: false,
             selected: false,
             id: next_layer_id(),
             transform_edit_state,
             transform_state,
+            constraints: LayerConstraints::default(),
         };
 
         Self {
@@ -174,7 +459,17 @@ impl CanvasState {
             template: None,
             quick_layout_order: vec![layer.id],
             last_quick_layout: None,
+            quick_layout_parameters: quick_layout::QuickLayoutParameters::default(),
             canvas_id: Id::random(),
+            comments: Vec::new(),
+            guides: Vec::new(),
+            measurements: Vec::new(),
+            color_label: None,
+            notes: PageNotes::default(),
+            background: Fill::default(),
+            search_matches: Vec::new(),
+            section_start: None,
+            section: None,
             computed_initial_zoom: false,
         }
     }
@@ -214,40 +509,53 @@ impl CanvasState {
                             region: region.clone(),
                             photo: None,
                             scale_mode: ScaleMode::Fit,
+                            alignment: PhotoAlignment::default(),
                         },
                         name,
+                        auto_named: true,
                         visible: true,
                         locked: false,
                         selected: false,
                         id: next_layer_id(),
                         transform_edit_state,
                         transform_state,
+                        constraints: region.constraints,
                     };
                     layers.insert(layer.id, layer);
                 }
                 TemplateRegionKind::Text {
                     sample_text,
                     font_size,
+                    ..
                 } => {
+                    let mut text = CanvasText::new(
+                        sample_text.clone(),
+                        *font_size,
+                        FontId::default(),
+                        Color32::BLACK,
+                        TextHorizontalAlignment::Left,
+                        TextVerticalAlignment::Top,
+                    );
+                    text.font_role = Some(ThemeFontRole::Body);
+                    text.color_role = Some(ThemeColorRole::Text);
+                    Dependency::<ProjectSettingsManager>::get().with_lock(|manager| {
+                        manager.project_settings.theme.apply_to_text(&mut text)
+                    });
+
                     let layer = Layer {
                         content: LayerContent::TemplateText {
                             region: region.clone(),
-                            text: CanvasText::new(
-                                sample_text.clone(),
-                                *font_size,
-                                FontId::default(),
-                                Color32::BLACK,
-                                TextHorizontalAlignment::Left,
-                                TextVerticalAlignment::Top,
-                            ),
+                            text,
                         },
                         name,
+                        auto_named: true,
                         visible: true,
                         locked: false,
                         selected: false,
                         id: next_layer_id(),
                         transform_edit_state,
                         transform_state,
+                        constraints: region.constraints,
                     };
 
                     layers.insert(layer.id, layer);
@@ -266,7 +574,17 @@ impl CanvasState {
             template: Some(template),
             quick_layout_order: ids,
             last_quick_layout: None,
+            quick_layout_parameters: quick_layout::QuickLayoutParameters::default(),
             canvas_id: Id::random(),
+            comments: Vec::new(),
+            guides: Vec::new(),
+            measurements: Vec::new(),
+            color_label: None,
+            notes: PageNotes::default(),
+            background: Fill::default(),
+            search_matches: Vec::new(),
+            section_start: None,
+            section: None,
             computed_initial_zoom: false,
         }
     }
@@ -301,6 +619,500 @@ impl CanvasState {
             .rect = original_child_b_rect.fit_and_center_within(original_child_a_rect);
     }
 
+    /// Fits the layers, laid out for a page of `previous_size` pixels, to the current page size
+    pub fn rescale_layers(&mut self, previous_size: Vec2, anchor: RescaleAnchor) {
+        PageRescale::new(previous_size, self.page.size_pixels(), anchor).apply(self);
+    }
+
+    /// Moves the layers pinned to the page to where their pins put them on the current page size,
+    /// leaving the rest of the layout as it was. Returns true if any layer moved.
+    pub fn reflow_pinned_layers(&mut self, previous_size: Vec2) -> bool {
+        PageRescale::new(
+            previous_size,
+            self.page.size_pixels(),
+            RescaleAnchor::default(),
+        )
+        .reflow_pinned(self)
+    }
+
+    /// Rotates or flips the selected photos. Free photos keep their center and swap their width
+    /// and height when turned on their side, template photos stay in their region. Returns true
+    /// if any photo changed.
+    pub fn orient_selected_photos(&mut self, command: OrientCommand) -> bool {
+        let mut changed = false;
+
+        for layer in self.selected_layers_iter_mut() {
+            match &mut layer.content {
+                LayerContent::Photo(photo) => {
+                    let exif_rotation = photo.photo.metadata.rotation();
+                    let was_sideways = photo.orientation.is_sideways(exif_rotation);
+                    command.apply(&mut photo.orientation);
+
+                    if was_sideways != photo.orientation.is_sideways(exif_rotation) {
+                        let rect = layer.transform_state.rect;
+                        layer.transform_state.rect = Rect::from_center_size(
+                            rect.center(),
+                            Vec2::new(rect.height(), rect.width()),
+                        );
+                    }
+                    changed = true;
+                }
+                LayerContent::TemplatePhoto {
+                    photo: Some(photo), ..
+                } => {
+                    command.apply(&mut photo.orientation);
+                    changed = true;
+                }
+                LayerContent::TemplatePhoto { photo: None, .. }
+                | LayerContent::Text(_)
+                | LayerContent::TemplateText { .. }
+                | LayerContent::Decoration(_)
+                | LayerContent::Path(_) => {}
+            }
+        }
+
+        changed
+    }
+
+    /// Gives the placed copies of `photo` its orientation override. Free photos keep their center
+    /// and swap their width and height if that turns them on their side.
+    pub fn update_photo_orientation(&mut self, photo: &Photo) {
+        let orientation_override = photo.metadata.orientation_override;
+
+        for layer in self.layers.values_mut() {
+            match &mut layer.content {
+                LayerContent::Photo(canvas_photo) if canvas_photo.photo.path == photo.path => {
+                    let was_sideways = canvas_photo
+                        .orientation
+                        .is_sideways(canvas_photo.photo.metadata.rotation());
+                    canvas_photo.photo.metadata.orientation_override = orientation_override;
+
+                    if was_sideways
+                        != canvas_photo
+                            .orientation
+                            .is_sideways(canvas_photo.photo.metadata.rotation())
+                    {
+                        let rect = layer.transform_state.rect;
+                        layer.transform_state.rect = Rect::from_center_size(
+                            rect.center(),
+                            Vec2::new(rect.height(), rect.width()),
+                        );
+                    }
+                }
+                LayerContent::TemplatePhoto {
+                    photo: Some(canvas_photo),
+                    ..
+                } if canvas_photo.photo.path == photo.path => {
+                    canvas_photo.photo.metadata.orientation_override = orientation_override;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Makes a photo full bleed, covering the page and its bleed. The photo is cropped to the
+    /// shape of the page around its current crop. Returns true if it changed.
+    pub fn fill_page_with_photo(&mut self, layer_id: LayerId) -> bool {
+        let region = Rect::from_min_size(Pos2::ZERO, self.page.size_pixels())
+            .expand(self.page.bleed_pixels());
+
+        let Some(layer) = self.layers.get_mut(&layer_id) else {
+            return false;
+        };
+        let LayerContent::Photo(photo) = &mut layer.content else {
+            return false;
+        };
+
+        let crop = photo.crop_to_fill(region.size());
+        let changed = photo.crop != crop
+            || layer.transform_state.rect != region
+            || layer.transform_state.rotation != 0.0;
+
+        photo.set_crop(crop);
+        layer.transform_state.rect = region;
+        layer.transform_state.rotation = 0.0;
+
+        changed
+    }
+
+    /// Where the page remembers whether the handles of a gradient background are shown
+    pub fn background_handles_id(&self) -> Id {
+        self.canvas_id.with("background_handles")
+    }
+
+    /// Places this page's photos where `source` has its photos, in quick layout order, so a look
+    /// from one page can be repeated on another. Positions are relative to the page so pages of
+    /// different sizes work. If the counts differ the largest of the source's spots are used, or
+    /// split, until there's one per photo. Photos are cropped to the shape of their new spot.
+    /// Returns true if anything moved.
+    pub fn apply_layout_from(&mut self, source: &CanvasState) -> bool {
+        let source_size = source.page.size_pixels();
+        let spots: Vec<(Rect, f32)> = source
+            .photo_layers_in_layout_order()
+            .map(|layer| {
+                let rect = layer.transform_state.rect;
+                (
+                    Rect::from_min_max(
+                        (rect.min.to_vec2() / source_size).to_pos2(),
+                        (rect.max.to_vec2() / source_size).to_pos2(),
+                    ),
+                    layer.transform_state.rotation,
+                )
+            })
+            .collect();
+
+        self.apply_layout_spots(spots)
+    }
+
+    /// Places this page's photos in the photo regions of `template` the same way as
+    /// `apply_layout_from`. Returns true if anything moved.
+    pub fn apply_template_layout(&mut self, template: &Template) -> bool {
+        let spots: Vec<(Rect, f32)> = template
+            .regions
+            .iter()
+            .filter(|region| region.kind == TemplateRegionKind::Image)
+            .map(|region| {
+                (
+                    Rect::from_min_size(region.relative_position, region.relative_size),
+                    0.0,
+                )
+            })
+            .collect();
+
+        self.apply_layout_spots(spots)
+    }
+
+    /// Whether the page has photos a layout from another page or a template can be applied to
+    pub fn has_layout_photos(&self) -> bool {
+        self.photo_layers_in_layout_order().next().is_some()
+    }
+
+    // Places the photos in quick layout order in `spots`, which are relative to the page, with
+    // their rotations
+    fn apply_layout_spots(&mut self, spots: Vec<(Rect, f32)>) -> bool {
+        let targets: Vec<LayerId> = self
+            .photo_layers_in_layout_order()
+            .map(|layer| layer.id)
+            .collect();
+
+        if spots.is_empty() || targets.is_empty() {
+            return false;
+        }
+
+        let spots = reflow_layout_spots(spots, targets.len(), self.quick_layout_parameters.gutter);
+        let page_size = self.page.size_pixels();
+        let mut changed = false;
+
+        for (layer_id, (spot, rotation)) in targets.into_iter().zip(spots) {
+            let rect = Rect::from_min_max(
+                (spot.min.to_vec2() * page_size).to_pos2(),
+                (spot.max.to_vec2() * page_size).to_pos2(),
+            );
+
+            let Some(layer) = self.layers.get_mut(&layer_id) else {
+                continue;
+            };
+            let LayerContent::Photo(photo) = &mut layer.content else {
+                continue;
+            };
+
+            let crop = photo.crop_to_fill(rect.size());
+            changed |= photo.crop != crop
+                || layer.transform_state.rect != rect
+                || layer.transform_state.rotation != rotation;
+
+            photo.set_crop(crop);
+            layer.transform_state.rect = rect;
+            layer.transform_state.rotation = rotation;
+        }
+
+        if changed {
+            self.last_quick_layout = None;
+        }
+
+        changed
+    }
+
+    fn photo_layers_in_layout_order(&self) -> impl Iterator<Item = &Layer> {
+        self.quick_layout_order
+            .iter()
+            .filter_map(|id| self.layers.get(id))
+            .filter(|layer| matches!(layer.content, LayerContent::Photo(_)))
+    }
+
+    /// Centers a photo on the page, as large as it fits inside `margin_mm` from the edges.
+    /// The crop is kept. Returns true if it changed.
+    pub fn fit_photo_to_page(&mut self, layer_id: LayerId, margin_mm: f32) -> bool {
+        let page_size = self.page.size_pixels();
+        let margin = margin_mm * page_size.x / self.page.size_mm().x;
+        let region = Rect::from_min_size(Pos2::ZERO, page_size).shrink(margin);
+
+        let Some(layer) = self.layers.get_mut(&layer_id) else {
+            return false;
+        };
+        let LayerContent::Photo(photo) = &layer.content else {
+            return false;
+        };
+
+        if region.width() <= 0.0 || region.height() <= 0.0 {
+            return false;
+        }
+
+        let photo_size = photo.oriented_size() * photo.display_crop().size();
+        let rect = ScaleMode::Fit.photo_rect(region, photo_size, PhotoAlignment::default());
+        let changed = layer.transform_state.rect != rect || layer.transform_state.rotation != 0.0;
+
+        layer.transform_state.rect = rect;
+        layer.transform_state.rotation = 0.0;
+
+        changed
+    }
+
+    /// Adds a caption below the photo layer that follows it around the page. The text is built
+    /// from `pattern`, see [`CaptionPattern`] for its tokens. The caption is selected so it can be
+    /// edited right away. Returns its layer id.
+    pub fn add_caption(&mut self, photo_layer_id: LayerId, pattern: &str) -> Option<LayerId> {
+        let photo_layer = self.layers.get(&photo_layer_id)?;
+        let LayerContent::Photo(photo) = &photo_layer.content else {
+            return None;
+        };
+
+        let text = CaptionPattern {
+            template: pattern.to_string(),
+            ..Default::default()
+        }
+        .apply(&photo.photo, 0);
+
+        let mut caption = Layer::new_text_layer();
+        caption.name = format!("{} Caption", photo_layer.name);
+        caption.auto_named = false;
+        caption.selected = true;
+        if let LayerContent::Text(canvas_text) = &mut caption.content {
+            caption.transform_state.rect = Rect::from_min_size(
+                Pos2::ZERO,
+                Vec2::new(
+                    photo_layer.transform_state.rect.width(),
+                    canvas_text.font_size * 2.0,
+                ),
+            );
+            canvas_text.text = text;
+            canvas_text.horizontal_alignment = TextHorizontalAlignment::Center;
+            canvas_text.anchor = Some(CaptionAnchor::new(photo_layer_id));
+        }
+
+        for layer in self.layers.values_mut() {
+            layer.selected = false;
+        }
+
+        let caption_id = caption.id;
+        self.layers.insert(caption_id, caption);
+        self.update_anchored_captions();
+
+        Some(caption_id)
+    }
+
+    /// The text each of the template's autofilled text regions gets from the photos placed in the
+    /// template. Regions the photos have nothing for are left out.
+    pub fn template_autofill(&self) -> Vec<(LayerId, String)> {
+        let photos: Vec<&Photo> = self
+            .layers
+            .values()
+            .filter_map(|layer| match &layer.content {
+                LayerContent::TemplatePhoto {
+                    photo: Some(photo), ..
+                } => Some(&photo.photo),
+                _ => None,
+            })
+            .collect();
+
+        if photos.is_empty() {
+            return Vec::new();
+        }
+
+        self.layers
+            .values()
+            .filter_map(|layer| match &layer.content {
+                LayerContent::TemplateText {
+                    region:
+                        TemplateRegion {
+                            kind:
+                                TemplateRegionKind::Text {
+                                    autofill: Some(autofill),
+                                    ..
+                                },
+                            ..
+                        },
+                    ..
+                } => autofill.text(&photos).map(|text| (layer.id, text)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Fills the template's text regions in from its photos. Text typed into a region is kept
+    /// unless `overwrite`, a region still showing its sample text or what `previous` filled in,
+    /// the autofill from before the photos changed, hasn't been typed into. Returns true if any
+    /// text changed.
+    pub fn apply_template_autofill(
+        &mut self,
+        previous: &[(LayerId, String)],
+        overwrite: bool,
+    ) -> bool {
+        let mut changed = false;
+
+        for (layer_id, text) in self.template_autofill() {
+            let Some(LayerContent::TemplateText {
+                region:
+                    TemplateRegion {
+                        kind: TemplateRegionKind::Text { sample_text, .. },
+                        ..
+                    },
+                text: canvas_text,
+            }) = self
+                .layers
+                .get_mut(&layer_id)
+                .map(|layer| &mut layer.content)
+            else {
+                continue;
+            };
+
+            let untouched = canvas_text.text == *sample_text
+                || previous
+                    .iter()
+                    .any(|(id, filled)| *id == layer_id && *filled == canvas_text.text);
+
+            if (overwrite || untouched) && canvas_text.text != text {
+                canvas_text.text = text;
+                changed = true;
+            }
+        }
+
+        changed
+    }
+
+    /// Moves captions back next to their photos. Captions whose photo layer is gone stay where
+    /// they are.
+    pub fn update_anchored_captions(&mut self) {
+        let anchored: Vec<(LayerId, CaptionAnchor)> = self
+            .layers
+            .values()
+            .filter_map(|layer| match &layer.content {
+                LayerContent::Text(text) => text.anchor.map(|anchor| (layer.id, anchor)),
+                _ => None,
+            })
+            .collect();
+
+        for (caption_id, anchor) in anchored {
+            let Some((photo_rect, rotation)) = self
+                .layers
+                .get(&anchor.photo_layer)
+                .map(|layer| (layer.transform_state.rect, layer.transform_state.rotation))
+            else {
+                continue;
+            };
+
+            if let Some(caption) = self.layers.get_mut(&caption_id) {
+                let rect = anchor.rotated_caption_rect(
+                    photo_rect,
+                    rotation,
+                    caption.transform_state.rect.size(),
+                );
+                if caption.transform_state.rect != rect
+                    || caption.transform_state.rotation != rotation
+                {
+                    caption.transform_state.rect = rect;
+                    caption.transform_state.rotation = rotation;
+                    caption
+                        .transform_edit_state
+                        .update(&caption.transform_state);
+                }
+            }
+        }
+    }
+
+    // Hash of where the anchored captions and their photos are and how they're anchored, so a
+    // caption dragged away from its photo is put back too
+    fn anchored_captions_key(&self) -> u64 {
+        let mut hasher = FxHasher::default();
+
+        for layer in self.layers.values() {
+            let LayerContent::Text(CanvasText {
+                anchor: Some(anchor),
+                ..
+            }) = &layer.content
+            else {
+                continue;
+            };
+
+            anchor.photo_layer.hash(&mut hasher);
+            anchor.side.hash(&mut hasher);
+            anchor.gap.to_bits().hash(&mut hasher);
+
+            for layer in std::iter::once(layer).chain(self.layers.get(&anchor.photo_layer)) {
+                let rect = layer.transform_state.rect;
+                layer.id.hash(&mut hasher);
+                for value in [
+                    rect.min.x,
+                    rect.min.y,
+                    rect.max.x,
+                    rect.max.y,
+                    layer.transform_state.rotation,
+                ] {
+                    value.to_bits().hash(&mut hasher);
+                }
+            }
+        }
+
+        hasher.finish()
+    }
+
+    /// Pans so the layer is in the middle of the canvas
+    pub fn center_on_layer(&mut self, layer_id: LayerId) {
+        let Some(layer) = self.layers.get(&layer_id) else {
+            return;
+        };
+
+        let page_center = self.page.size_pixels() / 2.0;
+        self.offset = (page_center - layer.transform_state.rect.center().to_vec2()) * self.zoom;
+    }
+
+    /// Zooms to `zoom`, within the zoom limits, keeping the middle of the canvas where it is
+    pub fn set_zoom(&mut self, zoom: f32) {
+        let zoom = zoom.clamp(MIN_ZOOM, MAX_ZOOM);
+        self.offset *= zoom / self.zoom;
+        self.zoom = zoom;
+        self.computed_initial_zoom = true;
+    }
+
+    /// Zooms so the width of the page fills `canvas_rect`, showing the top of the page
+    pub fn fit_width(&mut self, canvas_rect: Rect) {
+        let page_size = self.page.size_pixels();
+        self.set_zoom(canvas_rect.width() / page_size.x);
+
+        let overflow = (page_size.y * self.zoom - canvas_rect.height()).max(0.0);
+        self.offset = Vec2::new(0.0, overflow / 2.0);
+    }
+
+    /// Zooms so the height of the page fills `canvas_rect`, showing the left of the page
+    pub fn fit_height(&mut self, canvas_rect: Rect) {
+        let page_size = self.page.size_pixels();
+        self.set_zoom(canvas_rect.height() / page_size.y);
+
+        let overflow = (page_size.x * self.zoom - canvas_rect.width()).max(0.0);
+        self.offset = Vec2::new(overflow / 2.0, 0.0);
+    }
+
+    /// Renames the layers that follow the naming rules to match their content
+    pub fn update_layer_names(&mut self, naming: &LayerNaming) {
+        for layer in self.layers.values_mut().filter(|layer| layer.auto_named) {
+            if let Some(name) = naming.name_for(&layer.content) {
+                if layer.name != name {
+                    layer.name = name;
+                }
+            }
+        }
+    }
+
     fn is_layer_selected(&self, layer_id: &LayerId) -> bool {
         self.layers.get(layer_id).unwrap().selected
     }
@@ -315,6 +1127,66 @@ impl CanvasState {
         self.update_quick_layout_order();
     }
 
+    /// Adds the photo centered on `center`, in page pixels, and selects it
+    pub fn add_photo_at(&mut self, photo: Photo, center: Pos2) {
+        let mut layer = Layer::with_photo(photo);
+        layer.transform_state.rect =
+            Rect::from_center_size(center, layer.transform_state.rect.size());
+        layer.transform_edit_state.update(&layer.transform_state);
+
+        for existing in self.layers.values_mut() {
+            existing.selected = false;
+        }
+        layer.selected = true;
+
+        self.layers.insert(layer.id, layer);
+        self.update_quick_layout_order();
+    }
+
+    /// Adds a copy of the snippet's layers centered on `center`, selected in place of the layers
+    /// that were
+    pub fn add_snippet(&mut self, snippet: &Snippet, center: Pos2) {
+        for existing in self.layers.values_mut() {
+            existing.selected = false;
+        }
+
+        for layer in snippet.instantiate(center) {
+            self.layers.insert(layer.id, layer);
+        }
+        self.update_quick_layout_order();
+    }
+
+    pub fn add_decoration(&mut self, decoration: Decoration) {
+        let is_texture = decoration.kind == DecorationKind::Texture;
+        let layer = Layer::with_decoration(decoration, self.page.size_pixels());
+        if is_texture {
+            // Textures are backgrounds so they go below everything else
+            self.layers.shift_insert(0, layer.id, layer);
+        } else {
+            self.layers.insert(layer.id, layer);
+        }
+        self.update_quick_layout_order();
+    }
+
+    /// The paths of every photo shown on the page, including those in template regions
+    pub fn photo_paths(&self) -> impl Iterator<Item = &PathBuf> {
+        self.photo_layers().map(|(_, path)| path)
+    }
+
+    /// The layers showing photos, with the path of the photo each one shows
+    pub fn photo_layers(&self) -> impl Iterator<Item = (LayerId, &PathBuf)> {
+        self.layers
+            .values()
+            .filter_map(|layer| match &layer.content {
+                LayerContent::Photo(canvas_photo)
+                | LayerContent::TemplatePhoto {
+                    photo: Some(canvas_photo),
+                    ..
+                } => Some((layer.id, &canvas_photo.photo.path)),
+                _ => None,
+            })
+    }
+
     pub fn update_quick_layout_order(&mut self) {
         self.quick_layout_order
             .retain(|id| self.layers.contains_key(id));
@@ -327,6 +1199,52 @@ impl CanvasState {
     }
 }
 
+// Makes `count` spots out of a layout's photo spots, which are relative to the page. Extra spots
+// are dropped smallest first and missing ones come from splitting the largest in half along its
+// longer side, `gutter` apart. The spots stay in their original order.
+fn reflow_layout_spots(mut spots: Vec<(Rect, f32)>, count: usize, gutter: f32) -> Vec<(Rect, f32)> {
+    let area = |spot: &(Rect, f32)| spot.0.area();
+
+    if spots.len() > count {
+        let mut by_size: Vec<usize> = (0..spots.len()).collect();
+        by_size.sort_by(|a, b| area(&spots[*b]).total_cmp(&area(&spots[*a])));
+        let kept: HashSet<usize> = by_size.into_iter().take(count).collect();
+
+        spots = spots
+            .into_iter()
+            .enumerate()
+            .filter(|(index, _)| kept.contains(index))
+            .map(|(_, spot)| spot)
+            .collect();
+    }
+
+    while !spots.is_empty() && spots.len() < count {
+        let largest = (0..spots.len())
+            .max_by(|a, b| area(&spots[*a]).total_cmp(&area(&spots[*b])))
+            .unwrap_or_default();
+        let (rect, rotation) = spots[largest];
+
+        let (first, second) = if rect.width() >= rect.height() {
+            let half = ((rect.width() - gutter) / 2.0).max(0.0);
+            (
+                Rect::from_min_size(rect.min, Vec2::new(half, rect.height())),
+                Rect::from_min_max(Pos2::new(rect.max.x - half, rect.min.y), rect.max),
+            )
+        } else {
+            let half = ((rect.height() - gutter) / 2.0).max(0.0);
+            (
+                Rect::from_min_size(rect.min, Vec2::new(rect.width(), half)),
+                Rect::from_min_max(Pos2::new(rect.min.x, rect.max.y - half), rect.max),
+            )
+        };
+
+        spots[largest] = (first, rotation);
+        spots.insert(largest + 1, (second, rotation));
+    }
+
+    spots
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct MultiSelect {
     transformable_state: TransformableState,
@@ -401,19 +1319,30 @@ impl MultiSelect {
             .map(|child| child.id)
             .collect::<Vec<_>>();
 
+        let selection_changed = !added_layers.is_empty() || !removed_layers.is_empty();
+
         for layer_id in removed_layers {
             self.selected_layers.retain(|child| child.id != layer_id);
         }
 
-        let joined_selected_ids: Vec<usize> = selected_layer_ids
-            .iter()
-            .chain(self.selected_layers.iter().map(|child| &child.id))
-            .copied()
-            .collect();
+        // The group's rect is the center the children rotate and scale around, so it's only
+        // refit to the children once they have stopped being transformed
+        let is_transforming =
+            self.transformable_state.active_handle.is_some() || self.transformable_state.is_moving;
+
+        if selection_changed || !is_transforming {
+            let joined_selected_ids: Vec<usize> = selected_layer_ids
+                .iter()
+                .chain(self.selected_layers.iter().map(|child| &child.id))
+                .copied()
+                .collect();
 
-        let new_rect = Self::compute_rect(layers, &joined_selected_ids);
+            self.transformable_state.rect = Self::compute_rect(layers, &joined_selected_ids);
 
-        self.transformable_state.rect = new_rect;
+            // The refit rect already contains the rotated children so the group itself is unrotated
+            self.transformable_state.rotation = 0.0;
+            self.transformable_state.last_frame_rotation = 0.0;
+        }
 
         for layer in added_layers {
             self.selected_layers.push(MultiSelectChild {
@@ -436,8 +1365,10 @@ impl MultiSelect {
         for layer_id in selected_layers {
             let layer = &layers.get(layer_id).unwrap();
 
-            // TODO: we should be rotating the rect here as well, but that messes things up
-            let rect = layer.transform_state.rect;
+            let rect = layer
+                .transform_state
+                .rect
+                .rotate_bb_around_center(layer.transform_state.rotation);
 
             min.x = min.x.min(rect.min.x);
             min.y = min.y.min(rect.min.y);
@@ -456,12 +1387,23 @@ enum ActionBarAction {
     SwapCentersAndBounds(LayerId, LayerId),
     SwapQuickLayoutPosition(LayerId, LayerId),
     Crop(LayerId),
+    SuggestCrop(LayerId),
+    ResetCrop(LayerId),
+    Orient(OrientCommand),
+    FillPage(LayerId),
+    FitPage(LayerId),
 }
 
 pub struct Canvas<'a> {
     pub state: &'a mut CanvasState,
     available_rect: Rect,
     history_manager: &'a mut CanvasHistoryManager,
+    placed_photos: HashSet<PathBuf>,
+    snap_lines: SnapLines,
+    // The topmost layer under the pointer this frame, the only one that takes clicks
+    picked_layer: Option<LayerId>,
+    // Previews draw full resolution photos rather than thumbnails, for exports
+    full_resolution: bool,
 }
 
 impl<'a> Canvas<'a> {
@@ -474,19 +1416,171 @@ impl<'a> Canvas<'a> {
             state,
             available_rect,
             history_manager,
+            placed_photos: HashSet::new(),
+            snap_lines: SnapLines::default(),
+            picked_layer: None,
+            full_resolution: false,
         }
     }
 
+    /// The photos used anywhere in the book, marked in the photo picker
+    pub fn with_placed_photos(mut self, placed_photos: HashSet<PathBuf>) -> Self {
+        self.placed_photos = placed_photos;
+        self
+    }
+
+    /// Draws previews with the full resolution photos loaded into the preview's own context
+    /// instead of falling back to thumbnails. Used by exports, which load every photo first.
+    pub fn with_full_resolution(mut self) -> Self {
+        self.full_resolution = true;
+        self
+    }
+
+    /// Whether the photo picker is open on the page. Callers use this to only gather the photos
+    /// placed in the book while it's shown.
+    pub fn is_photo_picker_open(ctx: &Context, state: &CanvasState) -> bool {
+        ctx.data(|data| {
+            data.get_temp::<PhotoPickerTarget>(state.canvas_id.with("photo_picker"))
+                .is_some()
+        })
+    }
+
     pub fn show(&mut self, ui: &mut Ui) -> Option<CanvasResponse> {
+        self.handle_tool_keys(ui.ctx());
+
         if let Some(response) = self.handle_keys(ui.ctx()) {
             return Some(response);
         }
 
+        let (canvas_response, page_rect) = self.show_page(ui);
+        self.update_anchored_captions(ui.ctx());
+        self.update_layer_names(ui.ctx());
+        self.snap_lines = self.guide_snap_lines();
+        self.picked_layer = self.pick_layer(ui, page_rect);
+
+        // Draw the layers by iterating over the layers and drawing them
+        // We collect the ids into a map to avoid borrowing issues
+        // TODO: Is there a better way?
+        for layer_id in self.state.layers.keys().copied().collect::<Vec<LayerId>>() {
+            if let Some(transform_response) = self.draw_layer(&layer_id, page_rect, ui) {
+                let primary_pointer_pressed = ui.input(|input| input.pointer.primary_pressed());
+                let primary_pointer_released = ui.input(|input| input.pointer.primary_released());
+
+                // If the canvas was clicked but not on a layer then deselect the photo
+                if canvas_response.clicked()
+                    && self.picked_layer.is_none()
+                    && self.is_pointer_on_canvas(ui)
+                    && self.state.is_layer_selected(&layer_id)
+                {
+                    self.deselect_all_photos();
+                } else if transform_response.mouse_down && primary_pointer_pressed {
+                    self.select_photo(&layer_id, ui.ctx());
+                }
+
+                if primary_pointer_released
+                    && (transform_response.ended_moving
+                        || transform_response.ended_resizing
+                        || transform_response.ended_rotating)
+                {
+                    self.history_manager
+                        .save_history(CanvasHistoryKind::Transform, self.state);
+                }
+            }
+        }
+
+        self.draw_multi_select(ui, page_rect);
+        self.draw_search_matches(ui, page_rect);
+        self.show_nudge_feedback(ui, page_rect);
+
+        match CanvasTool::current(ui.ctx()) {
+            CanvasTool::Select => {
+                self.edit_path_points(ui, page_rect);
+                self.edit_gradient_handles(ui, page_rect);
+            }
+            CanvasTool::Pen => {
+                let pen_tool = PenTool::new(self.pen_tool_id(), page_rect, self.state.zoom);
+                if let PenToolResponse::Finished(path) = pen_tool.show(ui, canvas_response.rect) {
+                    self.add_path(path);
+                }
+            }
+            CanvasTool::Measure => {
+                let hovered_layer = self
+                    .picked_layer
+                    .and_then(|layer_id| self.state.layers.get(&layer_id))
+                    .map(|layer| {
+                        let transform_state = &layer.transform_state;
+                        (
+                            layer.id,
+                            transform_state
+                                .rect
+                                .rotate_bb_around_center(transform_state.rotation),
+                        )
+                    });
+
+                let measure_tool = MeasureTool::new(
+                    self.state.canvas_id.with("measure_tool"),
+                    &self.state.page.value,
+                    page_rect,
+                    self.state.zoom,
+                );
+                if let Some(measurement) =
+                    measure_tool.show(ui, canvas_response.rect, hovered_layer)
+                {
+                    self.state.measurements.push(measurement);
+                }
+            }
+        }
+
+        MeasurementOverlay::new(
+            &mut self.state.measurements,
+            &self.state.page.value,
+            page_rect,
+            self.state.zoom,
+            self.state.canvas_id.with("measurements"),
+        )
+        .show(ui, CanvasTool::current(ui.ctx()) == CanvasTool::Measure);
+
+        self.show_guides(ui, canvas_response.rect, page_rect);
+
+        self.show_tools(ui, canvas_response.rect);
+
+        // Double-clicking empty space opens the picker to add photos to the page. Template pages
+        // are filled through their regions instead.
+        if canvas_response.double_clicked()
+            && self.state.template.is_none()
+            && self.is_pointer_on_canvas(ui)
+            && !self.state.layers.values().any(|layer| layer.selected)
+        {
+            if let Some(pointer) = canvas_response.interact_pointer_pos() {
+                self.open_photo_picker(ui.ctx(), None, pointer);
+            }
+        }
+
+        self.show_photo_picker(ui.ctx());
+
+        self.handle_photo_drop(ui, page_rect);
+
+        self.handle_snippet_drop(ui, page_rect);
+
+        // Add action bar at the bottom
+        if self.state.layers.values().any(|layer| layer.selected) {
+            if let Some(response) = self.show_action_bar(ui) {
+                return Some(response);
+            }
+        }
+
+        None
+    }
+
+    // Pans and zooms with the pointer and paints the empty page, returning the canvas response
+    // and where the page is on screen
+    fn show_page(&mut self, ui: &mut Ui) -> (egui::Response, Rect) {
         // Adjust the zoom so that the page fits in the available rect
         if !self.state.computed_initial_zoom {
             let page_size = self.state.page.size_pixels() * 1.1;
             self.state.zoom = (self.available_rect.width() / page_size.x)
-                .min(self.available_rect.height() / page_size.y);
+                .min(self.available_rect.height() / page_size.y)
+                .clamp(MIN_ZOOM, MAX_ZOOM);
             self.state.computed_initial_zoom = true;
         }
 
@@ -497,147 +1591,652 @@ impl<'a> Canvas<'a> {
 
         ui.set_clip_rect(canvas_rect);
 
-        if ui.ctx().pointer_hover_pos().is_some() {
-            if is_pointer_on_canvas {
-                ui.input(|input| {
-                    // if input.raw_scroll_delta.y != 0.0 {
-                    let zoom_factor = if input.raw_scroll_delta.y > 0.0 {
-                        1.1
-                    } else if input.raw_scroll_delta.y < 0.0 {
-                        1.0 / 1.1
-                    } else {
-                        1.0
-                    };
-                    let new_zoom = self.state.zoom * zoom_factor;
+        let mut pan_delta = None;
 
-                    if let Some(pointer_pos) = input.pointer.hover_pos() {
-                        let current_page_rect: Rect = Rect::from_center_size(
-                            canvas_rect.center() + self.state.offset,
-                            self.state.page.size_pixels() * self.state.zoom,
-                        );
-                        let old_pointer_to_page = pointer_pos - current_page_rect.center();
-                        let new_page_rect: Rect = Rect::from_center_size(
-                            canvas_rect.center() + self.state.offset,
-                            self.state.page.size_pixels() * new_zoom,
-                        );
-                        let new_pointer_to_page = pointer_pos - new_page_rect.center();
+        if ui.ctx().pointer_hover_pos().is_some() && is_pointer_on_canvas {
+            let gesture = ui.input(PanZoomInput::read);
 
-                        // Corrected offset calculation
-                        self.state.offset += old_pointer_to_page
-                            - new_pointer_to_page * (new_zoom / self.state.zoom);
+            if gesture.is_zooming() {
+                if let Some(pointer_pos) = ui.input(|input| input.pointer.hover_pos()) {
+                    let new_zoom =
+                        (self.state.zoom * gesture.zoom_factor).clamp(MIN_ZOOM, MAX_ZOOM);
 
-                        self.state.zoom = new_zoom;
-                    }
+                    let current_page_rect: Rect = Rect::from_center_size(
+                        canvas_rect.center() + self.state.offset,
+                        self.state.page.size_pixels() * self.state.zoom,
+                    );
+                    let old_pointer_to_page = pointer_pos - current_page_rect.center();
+                    let new_page_rect: Rect = Rect::from_center_size(
+                        canvas_rect.center() + self.state.offset,
+                        self.state.page.size_pixels() * new_zoom,
+                    );
+                    let new_pointer_to_page = pointer_pos - new_page_rect.center();
+
+                    // Corrected offset calculation
+                    self.state.offset +=
+                        old_pointer_to_page - new_pointer_to_page * (new_zoom / self.state.zoom);
+
+                    self.state.zoom = new_zoom;
+                }
+            }
+
+            if gesture.pan != Vec2::ZERO {
+                self.state.offset += gesture.pan;
+                pan_delta = Some(gesture.pan);
+            }
+
+            if gesture.grabbing {
+                Dependency::<CursorManager>::get().with_lock_mut(|cursor_manager| {
+                    cursor_manager.set_cursor(CursorIcon::Grabbing);
                 });
             }
         }
 
-        let page_rect: Rect = Rect::from_center_size(
-            canvas_rect.center() + self.state.offset,
-            self.state.page.size_pixels() * self.state.zoom,
-        );
-
         ui.input(|input| {
             if input.key_down(egui::Key::Space) && is_pointer_on_canvas {
                 self.state.offset += input.pointer.delta();
+                pan_delta = Some(pan_delta.unwrap_or_default() + input.pointer.delta());
                 Dependency::<CursorManager>::get().with_lock_mut(|cursor_manager| {
                     cursor_manager.set_cursor(CursorIcon::Grabbing);
                 });
-                true
-            } else {
-                false
             }
         });
 
-        ui.painter().rect_filled(canvas_rect, 0.0, Color32::BLACK);
-        ui.painter().rect_filled(page_rect, 0.0, Color32::WHITE);
+        self.state.offset += PanInertia::update(
+            ui.ctx(),
+            self.state.canvas_id.with("pan_inertia"),
+            pan_delta,
+        );
+
+        let page_rect: Rect = Rect::from_center_size(
+            canvas_rect.center() + self.state.offset,
+            self.state.page.size_pixels() * self.state.zoom,
+        );
+
+        ui.painter().rect_filled(
+            canvas_rect,
+            0.0,
+            theme::color::canvas_background(ui.visuals()),
+        );
+        ui.painter()
+            .add(Shape::mesh(self.state.background.rect_mesh(page_rect)));
 
         self.draw_template(ui, page_rect);
 
-        // Draw the layers by iterating over the layers and drawing them
-        // We collect the ids into a map to avoid borrowing issues
-        // TODO: Is there a better way?
-        for layer_id in self.state.layers.keys().copied().collect::<Vec<LayerId>>() {
-            if let Some(transform_response) = self.draw_layer(&layer_id, false, page_rect, ui) {
-                let transform_state = &self.state.layers.get(&layer_id).unwrap().transform_state;
+        (canvas_response, page_rect)
+    }
 
-                let primary_pointer_pressed = ui.input(|input| input.pointer.primary_pressed());
-                let primary_pointer_released = ui.input(|input| input.pointer.primary_released());
+    /// Shows the page without editing so comment pins can be placed on it
+    pub fn show_review(&mut self, ui: &mut Ui, review_state: &mut ReviewState) {
+        let (_, page_rect) = self.show_page(ui);
+        self.paint_page(ui, page_rect);
 
-                // If the canvas was clicked but not on the photo then deselect the photo
-                if canvas_response.clicked()
-                    && !transform_state
-                        .rect
-                        .contains(canvas_response.interact_pointer_pos().unwrap_or(Pos2::ZERO))
-                    && self.is_pointer_on_canvas(ui)
-                    && self.state.is_layer_selected(&layer_id)
-                {
-                    self.deselect_all_photos();
-                } else if transform_response.mouse_down && primary_pointer_pressed {
-                    self.select_photo(&layer_id, ui.ctx());
+        ReviewPins::new(
+            &mut self.state.comments,
+            review_state,
+            page_rect,
+            self.state.canvas_id.with("review"),
+        )
+        .show(ui);
+    }
+
+    pub fn show_preview(&mut self, ui: &mut Ui, rect: Rect) {
+        let zoom = (rect.width() / self.state.page.size_pixels().x)
+            .min(rect.height() / self.state.page.size_pixels().y);
+
+        let page_rect: Rect =
+            Rect::from_center_size(rect.center(), self.state.page.size_pixels() * zoom);
+
+        ui.painter()
+            .add(Shape::mesh(self.state.background.rect_mesh(page_rect)));
+
+        self.paint_page(ui, page_rect);
+    }
+
+    // Paints the layers without interacting with them, the way every export format draws a page
+    fn paint_page(&self, ui: &mut Ui, page_rect: Rect) {
+        let mut target = PainterTarget {
+            ui,
+            zoom: page_rect.width() / self.state.page.size_pixels().x,
+            section: self.state.section.as_deref(),
+            full_resolution: self.full_resolution,
+        };
+
+        render::render_page(self.state, page_rect, &mut target)
+            .unwrap_or_else(|never| match never {});
+    }
+
+    fn show_guides(&mut self, ui: &mut Ui, canvas_rect: Rect, page_rect: Rect) {
+        let project_settings: Singleton<ProjectSettingsManager> = Dependency::get();
+        let mut book_guides =
+            project_settings.with_lock(|manager| manager.project_settings.guides.clone());
+        let original_book_guides = book_guides.clone();
+
+        GuideOverlay::new(
+            &mut self.state.guides,
+            &mut book_guides,
+            &self.state.page.value,
+            canvas_rect,
+            page_rect,
+            self.state.canvas_id.with("guides"),
+        )
+        .show(ui);
+
+        if book_guides != original_book_guides {
+            project_settings.with_lock_mut(|manager| {
+                manager.project_settings.guides = book_guides;
+            });
+        }
+    }
+
+    // The page's guides and those on every page, in the page space layers are positioned in
+    fn guide_snap_lines(&self) -> SnapLines {
+        let book_guides = Dependency::<ProjectSettingsManager>::get()
+            .with_lock(|manager| manager.project_settings.guides.clone());
+
+        let mut snap_lines = SnapLines::default();
+        for guide in self.state.guides.iter().chain(&book_guides) {
+            let position = guide.position_pixels(&self.state.page.value);
+            match guide.orientation {
+                GuideOrientation::Horizontal => snap_lines.y.push(position),
+                GuideOrientation::Vertical => snap_lines.x.push(position),
+            }
+        }
+        snap_lines
+    }
+
+    fn pen_tool_id(&self) -> Id {
+        self.state.canvas_id.with("pen_tool")
+    }
+
+    // Selects the tool, keeping whatever was drawn with the pen when switching away from it
+    fn set_tool(&mut self, ctx: &Context, tool: CanvasTool) {
+        if tool != CanvasTool::Pen {
+            if let PenToolResponse::Finished(path) = PenTool::finish(ctx, self.pen_tool_id()) {
+                self.add_path(path);
+            }
+        }
+
+        tool.set(ctx);
+    }
+
+    // The tool picker in the corner of the canvas, clear of the rulers
+    fn show_tools(&mut self, ui: &mut Ui, canvas_rect: Rect) {
+        let current = CanvasTool::current(ui.ctx());
+        let mut chosen = current;
+
+        let tools_rect = Rect::from_min_size(
+            canvas_rect.min + Vec2::splat(RULER_SIZE + 8.0),
+            Vec2::new(48.0, 120.0),
+        );
+
+        ui.allocate_new_ui(UiBuilder::new().max_rect(tools_rect), |ui| {
+            Frame::popup(ui.style()).inner_margin(4.0).show(ui, |ui| {
+                ui.vertical(|ui| {
+                    for tool in CanvasTool::iter() {
+                        ui.selectable_value(
+                            &mut chosen,
+                            tool,
+                            RichText::new(tool.icon()).size(16.0),
+                        )
+                        .on_hover_text(format!(
+                            "{} ({})",
+                            tool,
+                            tool.shortcut().name()
+                        ));
+                    }
+                });
+            });
+        });
+
+        if chosen != current {
+            self.set_tool(ui.ctx(), chosen);
+        }
+    }
+
+    // Adds a path drawn with the pen as a new layer and selects it
+    fn add_path(&mut self, path: CanvasPath) {
+        let mut layer = Layer::with_path(path);
+        for other in self.state.layers.values_mut() {
+            other.selected = false;
+        }
+        layer.selected = true;
+
+        self.state.layers.insert(layer.id, layer);
+        self.history_manager
+            .save_history(CanvasHistoryKind::AddPath, self.state);
+    }
+
+    // The anchors and handles of the selected path can be dragged. Double-clicking an anchor
+    // switches it between a corner and a smooth curve, its context menu can also delete it.
+    fn edit_path_points(&mut self, ui: &mut Ui, page_rect: Rect) {
+        if self.state.multi_select.is_some() {
+            return;
+        }
+
+        let mut selected = self.state.layers.values().filter(|layer| layer.selected);
+        let (Some(layer), None) = (selected.next(), selected.next()) else {
+            return;
+        };
+        let (LayerContent::Path(path), false) = (&layer.content, layer.locked) else {
+            return;
+        };
+
+        let layer_id = layer.id;
+        let mut path = path.clone();
+        let mut rect = layer.transform_state.rect;
+        let rotation = layer.transform_state.rotation;
+
+        let screen_rect = Rect::from_min_size(
+            page_rect.min + rect.min.to_vec2() * self.state.zoom,
+            rect.size() * self.state.zoom,
+        );
+        let to_screen = CanvasPath::transform(screen_rect, rotation);
+        // Pointer movement on screen as movement in the path's relative space
+        let to_relative = |delta: Vec2| (Rot2::from_angle(-rotation) * delta) / screen_rect.size();
+
+        let id = self.state.canvas_id.with("path_points").with(layer_id);
+        let focused = theme::color::focused(ui.visuals());
+        let break_handles = ui.input(|input| input.modifiers.alt);
+        let can_delete = path.points.len() > 2;
+
+        let mut moved = false;
+        let mut ended = false;
+        let mut toggled = None;
+        let mut deleted = None;
+
+        for (index, point) in path.points.iter_mut().enumerate() {
+            let anchor = to_screen(point.anchor);
+
+            if !point.is_corner() {
+                for is_out in [false, true] {
+                    let handle = if is_out {
+                        point.handle_out
+                    } else {
+                        point.handle_in
+                    };
+                    let handle_pos = to_screen(point.anchor + handle);
+
+                    ui.painter()
+                        .line_segment([anchor, handle_pos], Stroke::new(1.0, focused));
+                    ui.painter()
+                        .circle_filled(handle_pos, PATH_POINT_SIZE / 2.0, focused);
+
+                    let response = ui.interact(
+                        Rect::from_center_size(handle_pos, Vec2::splat(PATH_POINT_SIZE + 4.0)),
+                        id.with((index, is_out)),
+                        Sense::drag(),
+                    );
+
+                    if response.dragged() {
+                        let (dragged, opposite) = if is_out {
+                            (&mut point.handle_out, &mut point.handle_in)
+                        } else {
+                            (&mut point.handle_in, &mut point.handle_out)
+                        };
+                        *dragged = handle + to_relative(response.drag_delta());
+                        // Alt moves the handles separately for a sharp turn through the anchor
+                        if !break_handles {
+                            *opposite = -*dragged;
+                        }
+                        moved = true;
+                    }
+                    ended |= response.drag_stopped();
                 }
+            }
 
-                if primary_pointer_released
-                    && (transform_response.ended_moving
-                        || transform_response.ended_resizing
-                        || transform_response.ended_rotating)
+            let anchor_rect = Rect::from_center_size(anchor, Vec2::splat(PATH_POINT_SIZE));
+            let response = ui.interact(
+                anchor_rect.expand(2.0),
+                id.with(index),
+                Sense::click_and_drag(),
+            );
+
+            ui.painter().rect_filled(
+                anchor_rect,
+                0.0,
+                if response.hovered() || response.dragged() {
+                    focused
+                } else {
+                    Color32::WHITE
+                },
+            );
+            ui.painter()
+                .rect_stroke(anchor_rect, 0.0, Stroke::new(1.0, focused));
+
+            if response.hovered() || response.dragged() {
+                Dependency::<CursorManager>::get().with_lock_mut(|cursor_manager| {
+                    cursor_manager.set_cursor(CursorIcon::Move);
+                });
+            }
+
+            if response.dragged() {
+                point.anchor += to_relative(response.drag_delta());
+                moved = true;
+            }
+            ended |= response.drag_stopped();
+
+            if response.double_clicked() {
+                toggled = Some(index);
+            }
+
+            response.context_menu(|ui| {
+                let toggle_text = if point.is_corner() {
+                    "Make Smooth"
+                } else {
+                    "Make Corner"
+                };
+                if ui.button(toggle_text).clicked() {
+                    toggled = Some(index);
+                    ui.close_menu();
+                }
+                if ui
+                    .add_enabled(can_delete, Button::new("Delete Point"))
+                    .clicked()
                 {
+                    deleted = Some(index);
+                    ui.close_menu();
+                }
+            });
+        }
+
+        if let Some(index) = toggled {
+            path.toggle_smooth(index);
+        }
+        if let Some(index) = deleted {
+            path.points.remove(index);
+        }
+
+        let edited = toggled.is_some() || deleted.is_some();
+
+        if moved || edited {
+            path.fit_rect(&mut rect, rotation);
+            if let Some(layer) = self.state.layers.get_mut(&layer_id) {
+                layer.content = LayerContent::Path(path);
+                layer.transform_state.rect = rect;
+            }
+        }
+
+        if ended || edited {
+            self.history_manager
+                .save_history(CanvasHistoryKind::EditPath, self.state);
+        }
+    }
+
+    // The start and end of the selected path's gradient, or of the page background's while its
+    // handles are switched on, can be dragged on the page
+    fn edit_gradient_handles(&mut self, ui: &mut Ui, page_rect: Rect) {
+        let show_background = ui
+            .data(|data| data.get_temp::<bool>(self.state.background_handles_id()))
+            .unwrap_or_default();
+        if show_background {
+            let mut background = self.state.background.clone();
+            if let Fill::Gradient(gradient) = &mut background {
+                let id = self.state.canvas_id.with("background_gradient");
+                let (moved, ended) = gradient_handles(ui, id, gradient, page_rect, 0.0);
+                if moved {
+                    self.state.background = background;
+                }
+                if ended {
                     self.history_manager
-                        .save_history(CanvasHistoryKind::Transform, self.state);
+                        .save_history(CanvasHistoryKind::Background, self.state);
                 }
             }
         }
 
-        self.draw_multi_select(ui, page_rect);
+        if self.state.multi_select.is_some() {
+            return;
+        }
 
-        // Add action bar at the bottom
-        if self.state.layers.values().any(|layer| layer.selected) {
-            if let Some(response) = self.show_action_bar(ui) {
-                return Some(response);
+        let mut selected = self.state.layers.values().filter(|layer| layer.selected);
+        let (Some(layer), None) = (selected.next(), selected.next()) else {
+            return;
+        };
+        let (LayerContent::Path(path), false) = (&layer.content, layer.locked) else {
+            return;
+        };
+        let Fill::Gradient(gradient) = &path.fill else {
+            return;
+        };
+        if !path.is_filled() {
+            return;
+        }
+
+        let layer_id = layer.id;
+        let mut gradient = gradient.clone();
+        let rect = layer.transform_state.rect;
+        let screen_rect = Rect::from_min_size(
+            page_rect.min + rect.min.to_vec2() * self.state.zoom,
+            rect.size() * self.state.zoom,
+        );
+        let id = self.state.canvas_id.with("path_gradient").with(layer_id);
+
+        let (moved, ended) = gradient_handles(
+            ui,
+            id,
+            &mut gradient,
+            screen_rect,
+            layer.transform_state.rotation,
+        );
+
+        if moved {
+            if let Some(LayerContent::Path(path)) = self
+                .state
+                .layers
+                .get_mut(&layer_id)
+                .map(|layer| &mut layer.content)
+            {
+                path.fill = Fill::Gradient(gradient);
             }
         }
 
-        None
+        if ended {
+            self.history_manager
+                .save_history(CanvasHistoryKind::EditPath, self.state);
+        }
     }
 
-    pub fn show_preview(&mut self, ui: &mut Ui, rect: Rect) {
-        let zoom = (rect.width() / self.state.page.size_pixels().x)
-            .min(rect.height() / self.state.page.size_pixels().y);
+    // Photos dragged from the tray or the gallery are placed where they're dropped. On template
+    // pages they fill the photo region under the pointer instead.
+    fn handle_photo_drop(&mut self, ui: &mut Ui, page_rect: Rect) {
+        if !DragAndDrop::has_payload_of_type::<DraggedPhoto>(ui.ctx())
+            || !self.is_pointer_on_canvas(ui)
+        {
+            return;
+        }
+        let Some(pointer) = ui.ctx().pointer_hover_pos() else {
+            return;
+        };
 
-        let page_rect: Rect =
-            Rect::from_center_size(rect.center(), self.state.page.size_pixels() * zoom);
+        let target_region = self.state.template.is_some().then(|| {
+            self.state
+                .layers
+                .values()
+                .filter(|layer| layer.visible && !layer.locked)
+                .find(|layer| match &layer.content {
+                    LayerContent::TemplatePhoto { region, .. } => {
+                        render::region_rect(page_rect, region).contains(pointer)
+                    }
+                    _ => false,
+                })
+                .map(|layer| layer.id)
+        });
 
-        ui.painter().rect_filled(page_rect, 0.0, Color32::WHITE);
+        let highlight = match target_region {
+            Some(Some(layer_id)) => self
+                .state
+                .layers
+                .get(&layer_id)
+                .and_then(|layer| match &layer.content {
+                    LayerContent::TemplatePhoto { region, .. } => {
+                        Some(render::region_rect(page_rect, region))
+                    }
+                    _ => None,
+                }),
+            Some(None) => None,
+            None => Some(page_rect),
+        };
+        if let Some(highlight) = highlight {
+            ui.painter().rect_stroke(
+                highlight,
+                0.0,
+                Stroke::new(2.0, theme::color::focused(ui.visuals())),
+            );
+        }
 
-        let current_zoom = self.state.zoom;
-        self.state.zoom = zoom;
+        if !ui.input(|input| input.pointer.any_released()) {
+            return;
+        }
+        let Some(dropped) = DragAndDrop::take_payload::<DraggedPhoto>(ui.ctx()) else {
+            return;
+        };
+        let photo = dropped.0.clone();
+
+        match target_region {
+            Some(Some(layer_id)) => {
+                let previous_autofill = self.state.template_autofill();
+                if let Some(Layer {
+                    content:
+                        LayerContent::TemplatePhoto {
+                            photo: canvas_photo,
+                            ..
+                        },
+                    ..
+                }) = self.state.layers.get_mut(&layer_id)
+                {
+                    *canvas_photo = Some(CanvasPhoto::new(photo));
+                }
+                self.state
+                    .apply_template_autofill(&previous_autofill, false);
+            }
+            Some(None) => return,
+            None => {
+                let center = (pointer - page_rect.min) / self.state.zoom;
+                self.state.add_photo_at(photo, center.to_pos2());
+            }
+        }
 
-        for layer_id in self.state.layers.keys().copied().collect::<Vec<LayerId>>() {
-            self.draw_layer(&layer_id, true, page_rect, ui);
+        self.history_manager
+            .save_history(CanvasHistoryKind::AddPhoto, self.state);
+    }
+
+    // Snippets dragged from the snippets panel are added centered where they're dropped
+    fn handle_snippet_drop(&mut self, ui: &mut Ui, page_rect: Rect) {
+        if !DragAndDrop::has_payload_of_type::<DraggedSnippet>(ui.ctx())
+            || !self.is_pointer_on_canvas(ui)
+        {
+            return;
         }
+        let Some(pointer) = ui.ctx().pointer_hover_pos() else {
+            return;
+        };
+
+        ui.painter().rect_stroke(
+            page_rect,
+            0.0,
+            Stroke::new(2.0, theme::color::focused(ui.visuals())),
+        );
+
+        if !ui.input(|input| input.pointer.any_released()) {
+            return;
+        }
+        let Some(dropped) = DragAndDrop::take_payload::<DraggedSnippet>(ui.ctx()) else {
+            return;
+        };
+
+        let center = (pointer - page_rect.min) / self.state.zoom;
+        self.state.add_snippet(&dropped.0, center.to_pos2());
+
+        self.history_manager
+            .save_history(CanvasHistoryKind::AddSnippet, self.state);
+    }
+
+    fn photo_picker_id(&self) -> Id {
+        self.state.canvas_id.with("photo_picker")
+    }
+
+    fn open_photo_picker(&self, ctx: &Context, layer_id: Option<LayerId>, anchor: Pos2) {
+        let target = PhotoPickerTarget { layer_id, anchor };
+        ctx.data_mut(|data| data.insert_temp(self.photo_picker_id(), target));
+    }
+
+    // Shows the photo picker for the template region or empty page area that was double-clicked,
+    // if any
+    fn show_photo_picker(&mut self, ctx: &Context) {
+        let picker_id = self.photo_picker_id();
+        let Some(target) = ctx.data(|data| data.get_temp::<PhotoPickerTarget>(picker_id)) else {
+            return;
+        };
+
+        let placed_on_page: HashSet<PathBuf> = self.state.photo_paths().cloned().collect();
+        let picker_response = PhotoPicker::new(&self.placed_photos, &placed_on_page)
+            .multiple(target.layer_id.is_none())
+            .show(ctx, picker_id.with("popup"), target.anchor);
+
+        match picker_response {
+            PhotoPickerResponse::None => {}
+            PhotoPickerResponse::Picked(photos) => {
+                match target.layer_id {
+                    Some(layer_id) => {
+                        let previous_autofill = self.state.template_autofill();
+                        if let (
+                            Some(Layer {
+                                content:
+                                    LayerContent::TemplatePhoto {
+                                        photo: canvas_photo,
+                                        ..
+                                    },
+                                ..
+                            }),
+                            Some(photo),
+                        ) = (
+                            self.state.layers.get_mut(&layer_id),
+                            photos.into_iter().next(),
+                        ) {
+                            *canvas_photo = Some(CanvasPhoto::new(photo));
+                            self.state
+                                .apply_template_autofill(&previous_autofill, false);
+                            self.history_manager
+                                .save_history(CanvasHistoryKind::AddPhoto, self.state);
+                        }
+                    }
+                    None if !photos.is_empty() => {
+                        for photo in photos {
+                            self.state.add_photo(photo);
+                        }
+
+                        // Lays out the page for however many photos it now has
+                        if let Some(layout) =
+                            quick_layout::Layout::default_for(self.state.quick_layout_order.len())
+                        {
+                            layout.apply(self.state);
+                        }
 
-        self.state.zoom = current_zoom;
+                        self.history_manager
+                            .save_history(CanvasHistoryKind::AddPhoto, self.state);
+                    }
+                    None => {}
+                }
+
+                ctx.data_mut(|data| data.remove::<PhotoPickerTarget>(picker_id));
+            }
+            PhotoPickerResponse::Closed => {
+                ctx.data_mut(|data| data.remove::<PhotoPickerTarget>(picker_id));
+            }
+        }
     }
 
     fn draw_template(&mut self, ui: &mut Ui, page_rect: Rect) {
         if let Some(template) = &self.state.template {
             for region in &template.regions {
-                let region_rect = Rect::from_min_max(
-                    page_rect.min + region.relative_position.to_vec2() * page_rect.size(),
-                    page_rect.min
-                        + region.relative_position.to_vec2() * page_rect.size()
-                        + region.relative_size * page_rect.size(),
-                );
+                let region_rect = render::region_rect(page_rect, region);
 
                 match &region.kind {
                     TemplateRegionKind::Image => {
                         ui.painter()
                             .rect_filled(region_rect, 0.0, Color32::LIGHT_BLUE);
                     }
-                    TemplateRegionKind::Text {
-                        sample_text: _,
-                        font_size: _,
-                    } => {
+                    TemplateRegionKind::Text { .. } => {
                         ui.painter().rect_stroke(
                             region_rect,
                             0.0,
@@ -649,6 +2248,44 @@ impl<'a> Canvas<'a> {
         }
     }
 
+    // Outlines the layers found by the search in the layers panel so they're easy to spot
+    fn draw_search_matches(&self, ui: &mut Ui, page_rect: Rect) {
+        let stroke = Stroke::new(2.0, theme::color::focused(ui.visuals()));
+
+        for layer in self
+            .state
+            .search_matches
+            .iter()
+            .filter_map(|layer_id| self.state.layers.get(layer_id))
+            .filter(|layer| layer.visible)
+        {
+            let (rect, rotation) = match &layer.content {
+                LayerContent::TemplatePhoto { region, .. }
+                | LayerContent::TemplateText { region, .. } => {
+                    (render::region_rect(page_rect, region), 0.0)
+                }
+                _ => (
+                    render::layer_rect(page_rect, layer.transform_state.rect, self.state.zoom),
+                    layer.transform_state.rotation,
+                ),
+            };
+
+            let rect = rect.expand(4.0);
+            let rotation = Rot2::from_angle(rotation);
+            let center = rect.center();
+            let corners = [
+                rect.left_top(),
+                rect.right_top(),
+                rect.right_bottom(),
+                rect.left_bottom(),
+            ]
+            .map(|corner| center + rotation * (corner - center));
+
+            ui.painter()
+                .add(Shape::closed_line(corners.to_vec(), stroke));
+        }
+    }
+
     fn draw_multi_select(&mut self, ui: &mut Ui, rect: Rect) {
         let selected_layer_ids = self
             .state
@@ -683,7 +2320,9 @@ impl<'a> Canvas<'a> {
                     .map(|child| child.id)
                     .collect::<Vec<_>>();
 
-                let transform_response = TransformableWidget::new(&mut transform_state).show(
+                let mut transformable = TransformableWidget::new(&mut transform_state)
+                    .with_snap_lines(self.snap_lines.clone());
+                let transform_response = transformable.show(
                     ui,
                     rect,
                     self.state.zoom,
@@ -693,86 +2332,40 @@ impl<'a> Canvas<'a> {
                         for child_id in child_ids_content {
                             let layer: &mut Layer = self.state.layers.get_mut(&child_id).unwrap();
 
-                            // Compute the relative position of the layer in the group so we can apply transformations
-                            // to each side as they are adjusted at the group level
-                            // This accounts for scaling and translation
+                            // Scale the layer's center and size by how much the group changed so it keeps
+                            // its relative position in the group. This accounts for scaling and translation
                             {
-                                let delta_left =
-                                    transformable_state.rect.left() - pre_transform_rect.left();
-                                let delta_top =
-                                    transformable_state.rect.top() - pre_transform_rect.top();
-                                let delta_right =
-                                    transformable_state.rect.right() - pre_transform_rect.right();
-                                let delta_bottom =
-                                    transformable_state.rect.bottom() - pre_transform_rect.bottom();
-
-                                let relative_top = (pre_transform_rect.top()
-                                    - layer.transform_state.rect.top())
-                                .abs()
-                                    / pre_transform_rect.height();
-
-                                let relative_left = (pre_transform_rect.left()
-                                    - layer.transform_state.rect.left())
-                                .abs()
-                                    / pre_transform_rect.width();
-
-                                let relative_right = (pre_transform_rect.right()
-                                    - layer.transform_state.rect.right())
-                                .abs()
-                                    / pre_transform_rect.width();
-
-                                let relative_bottom = (pre_transform_rect.bottom()
-                                    - layer.transform_state.rect.bottom())
-                                .abs()
-                                    / pre_transform_rect.height();
-
-                                layer
-                                    .transform_state
-                                    .rect
-                                    .set_left(layer.transform_state.rect.left() + delta_left);
-
-                                layer
-                                    .transform_state
-                                    .rect
-                                    .set_top(layer.transform_state.rect.top() + delta_top);
-
-                                layer
-                                    .transform_state
-                                    .rect
-                                    .set_right(layer.transform_state.rect.right() + delta_right);
-
-                                layer
-                                    .transform_state
-                                    .rect
-                                    .set_bottom(layer.transform_state.rect.bottom() + delta_bottom);
-
-                                if relative_top > 0.0 {
-                                    layer.transform_state.rect.set_top(
-                                        transformable_state.rect.top()
-                                            + relative_top * transformable_state.rect.height(),
-                                    );
-                                }
+                                let scale = Vec2::new(
+                                    if pre_transform_rect.width() != 0.0 {
+                                        transformable_state.rect.width()
+                                            / pre_transform_rect.width()
+                                    } else {
+                                        1.0
+                                    },
+                                    if pre_transform_rect.height() != 0.0 {
+                                        transformable_state.rect.height()
+                                            / pre_transform_rect.height()
+                                    } else {
+                                        1.0
+                                    },
+                                );
 
-                                if relative_left > 0.0 {
-                                    layer.transform_state.rect.set_left(
-                                        transformable_state.rect.left()
-                                            + relative_left * transformable_state.rect.width(),
-                                    );
-                                }
+                                let layer_rect = layer.transform_state.rect;
 
-                                if relative_right > 0.0 {
-                                    layer.transform_state.rect.set_right(
-                                        transformable_state.rect.right()
-                                            - relative_right * transformable_state.rect.width(),
-                                    );
-                                }
+                                let center = transformable_state.rect.min
+                                    + (layer_rect.center() - pre_transform_rect.min) * scale;
 
-                                if relative_bottom > 0.0 {
-                                    layer.transform_state.rect.set_bottom(
-                                        transformable_state.rect.bottom()
-                                            - relative_bottom * transformable_state.rect.height(),
-                                    );
-                                }
+                                // Rotated layers are scaled along their own axes so they keep their
+                                // rotation rather than being skewed by the group's axes
+                                let (sin, cos) = layer.transform_state.rotation.sin_cos();
+                                let size = Vec2::new(
+                                    layer_rect.width()
+                                        * Vec2::new(scale.x * cos, scale.y * sin).length(),
+                                    layer_rect.height()
+                                        * Vec2::new(scale.x * sin, scale.y * cos).length(),
+                                );
+
+                                layer.transform_state.rect = Rect::from_center_size(center, size);
                             }
 
                             // Now rotate the layer while maintaining the relative position of the layer in the group
@@ -828,76 +2421,108 @@ impl<'a> Canvas<'a> {
         }
     }
 
+    // The topmost layer under the pointer. Layers are hit by their actual shape so clicking the
+    // empty corner of a rotated photo's bounds picks whatever is under it. Hidden and locked
+    // layers are clicked through.
+    fn pick_layer(&self, ui: &Ui, page_rect: Rect) -> Option<LayerId> {
+        let pointer = ui.ctx().pointer_hover_pos()?;
+        if !self.available_rect.contains(pointer) {
+            return None;
+        }
+
+        self.state
+            .layers
+            .values()
+            .rev()
+            .filter(|layer| layer.visible && !layer.locked)
+            .find(|layer| self.layer_contains(layer, page_rect, pointer))
+            .map(|layer| layer.id)
+    }
+
+    fn layer_contains(&self, layer: &Layer, page_rect: Rect, pos: Pos2) -> bool {
+        let zoom = self.state.zoom;
+        let transform_state = &layer.transform_state;
+
+        match &layer.content {
+            LayerContent::TemplatePhoto { region, .. }
+            | LayerContent::TemplateText { region, .. } => {
+                render::region_rect(page_rect, region).contains(pos)
+            }
+            LayerContent::Path(path) => {
+                // A selected path can be grabbed anywhere in its bounds
+                if layer.selected {
+                    return transform_state.contains(page_rect, zoom, pos);
+                }
+
+                let rect = Rect::from_min_size(
+                    page_rect.min + transform_state.rect.min.to_vec2() * zoom,
+                    transform_state.rect.size() * zoom,
+                );
+                let reach = path.stroke_width * zoom / 2.0 + PATH_HIT_SLOP;
+                path.hit_test(rect, transform_state.rotation, pos, reach)
+            }
+            LayerContent::Photo(_) | LayerContent::Text(_) | LayerContent::Decoration(_) => {
+                transform_state.contains(page_rect, zoom, pos)
+            }
+        }
+    }
+
     fn draw_layer(
         &mut self,
         layer_id: &LayerId,
-        is_preview: bool,
         available_rect: Rect,
         ui: &mut Ui,
     ) -> Option<TransformableWidgetResponse<()>> {
         let layer = &mut self.state.layers.get_mut(layer_id).unwrap().clone();
-        let active = layer.selected && self.state.multi_select.is_none();
+        let active = layer.selected && !layer.locked && self.state.multi_select.is_none();
+        let picked = self.picked_layer == Some(*layer_id);
 
         let layer_response = match &mut layer.content {
             LayerContent::Photo(ref mut photo) => {
                 let transform_response = ui
                     .push_id(
-                        format!(
-                            "{}_{}_CanvasPhoto_{}",
-                            is_preview,
-                            self.state.canvas_id.value(),
-                            layer.id
-                        ),
+                        format!("{}_CanvasPhoto_{}", self.state.canvas_id.value(), layer.id),
                         |ui| {
                             Dependency::<PhotoManager>::get().with_lock_mut(|photo_manager| {
-                                if let Ok(Some(texture)) = photo_manager
-                                    .texture_for_photo_with_thumbail_backup(&photo.photo, ui.ctx())
-                                {
+                                let missing = photo_manager.is_missing(&photo.photo.path);
+
+                                let texture = if missing {
+                                    Ok(None)
+                                } else {
+                                    photo_manager.adjusted_texture_for(
+                                        &photo.photo,
+                                        &photo.adjustments,
+                                        ui.ctx(),
+                                    )
+                                };
+
+                                let texture = match texture {
+                                    Ok(Some(texture)) => Some(texture),
+                                    _ => None,
+                                };
+
+                                if texture.is_some() || missing {
                                     let mut transform_state = layer.transform_state.clone();
 
                                     let transform_response = TransformableWidget::new(
                                     &mut transform_state,
                                 )
+                                .with_snap_lines(self.snap_lines.clone())
+                                .with_pointer_over(picked)
                                 .show(
                                     ui,
                                     available_rect,
                                     self.state.zoom,
-                                    active && !is_preview,
+                                    active,
                                     |ui: &mut Ui, transformed_rect: Rect, _transformable_state| {
-                                        // If the photo is rotated swap the width and height
-                                        let mesh_rect =
-                                            if photo.photo.metadata.rotation().is_horizontal() {
-                                                transformed_rect
-                                            } else {
-                                                Rect::from_center_size(
-                                                    transformed_rect.center(),
-                                                    Vec2::new(
-                                                        transformed_rect.height(),
-                                                        transformed_rect.width(),
-                                                    ),
-                                                )
-                                            };
-
-                                        let painter = ui.painter();
-                                        let mut mesh = Mesh::with_texture(texture.id);
-
-                                        mesh.add_rect_with_uv(mesh_rect, photo.crop, Color32::WHITE);
-
-                                        let mesh_center: Pos2 =
-                                            mesh_rect.min + Vec2::splat(0.5) * mesh_rect.size();
-
-                                        mesh.rotate(
-                                            Rot2::from_angle(
-                                                photo.photo.metadata.rotation().radians(),
-                                            ),
-                                            mesh_center,
+                                        paint_photo(
+                                            ui,
+                                            texture.as_ref().map(|texture| texture.id),
+                                            photo,
+                                            transformed_rect,
+                                            layer.transform_state.rotation,
+                                            self.state.zoom,
                                         );
-                                        mesh.rotate(
-                                            Rot2::from_angle(layer.transform_state.rotation),
-                                            mesh_center,
-                                        );
-
-                                        painter.add(Shape::mesh(mesh));
                                     },
                                 );
 
@@ -929,25 +2554,106 @@ impl<'a> Canvas<'a> {
             LayerContent::Text(text) => {
                 let mut transform_state = layer.transform_state.clone();
 
-                let transform_response: TransformableWidgetResponse<()> =
-                    TransformableWidget::new(&mut transform_state).show(
+                let mut transformable = TransformableWidget::new(&mut transform_state)
+                    .with_snap_lines(self.snap_lines.clone())
+                    .with_pointer_over(picked);
+                let transform_response: TransformableWidgetResponse<()> = transformable.show(
+                    ui,
+                    available_rect,
+                    self.state.zoom,
+                    active,
+                    |ui: &mut Ui, transformed_rect: Rect, _transformable_state| {
+                        Self::draw_text(
+                            ui,
+                            text,
+                            transformed_rect,
+                            self.state.zoom,
+                            self.state.section.as_deref(),
+                        );
+                    },
+                );
+
+                layer.transform_state = transform_state;
+                self.state.layers.insert(*layer_id, layer.clone());
+
+                Some(transform_response)
+            }
+            LayerContent::Decoration(decoration) => {
+                let mut transform_state = layer.transform_state.clone();
+
+                let mut transformable = TransformableWidget::new(&mut transform_state)
+                    .with_snap_lines(self.snap_lines.clone())
+                    .with_pointer_over(picked);
+                let transform_response: TransformableWidgetResponse<()> = transformable.show(
+                    ui,
+                    available_rect,
+                    self.state.zoom,
+                    active,
+                    |ui: &mut Ui, transformed_rect: Rect, _transformable_state| {
+                        paint_decoration(
+                            ui,
+                            decoration,
+                            transformed_rect,
+                            layer.transform_state.rotation,
+                        );
+                    },
+                );
+
+                layer.transform_state = transform_state;
+                self.state.layers.insert(*layer_id, layer.clone());
+
+                Some(transform_response)
+            }
+
+            LayerContent::Path(path) => {
+                let mut transform_state = layer.transform_state.clone();
+                let rotation = layer.transform_state.rotation;
+                let zoom = self.state.zoom;
+
+                let transform_response = if active {
+                    let mut transformable = TransformableWidget::new(&mut transform_state)
+                        .with_snap_lines(self.snap_lines.clone())
+                        .with_pointer_over(picked);
+                    transformable.show(
                         ui,
                         available_rect,
-                        self.state.zoom,
-                        active && !is_preview,
+                        zoom,
+                        true,
                         |ui: &mut Ui, transformed_rect: Rect, _transformable_state| {
-                            Self::draw_text(
-                                ui,
-                                &text.text,
-                                &text.font_id,
-                                transformed_rect,
-                                text.font_size * self.state.zoom,
-                                text.color,
-                                text.horizontal_alignment,
-                                text.vertical_alignment,
-                            );
+                            path.paint(ui.painter(), transformed_rect, rotation, zoom);
                         },
+                    )
+                } else {
+                    let rect = Rect::from_min_size(
+                        available_rect.min + transform_state.rect.min.to_vec2() * zoom,
+                        transform_state.rect.size() * zoom,
                     );
+                    path.paint(ui.painter(), rect, rotation, zoom);
+
+                    // Only the painted part of the path picks it so layers under the rest of its
+                    // bounds can still be clicked
+                    let response = picked.then(|| {
+                        ui.interact(
+                            rect.rotate_bb_around_center(rotation),
+                            transform_state.id,
+                            Sense::click_and_drag(),
+                        )
+                    });
+
+                    TransformableWidgetResponse {
+                        mouse_down: response
+                            .as_ref()
+                            .is_some_and(|response| response.is_pointer_button_down_on()),
+                        ended_moving: false,
+                        ended_resizing: false,
+                        ended_rotating: false,
+                        inner: (),
+                        began_moving: false,
+                        began_resizing: false,
+                        began_rotating: false,
+                        clicked: response.is_some_and(|response| response.clicked()),
+                    }
+                };
 
                 layer.transform_state = transform_state;
                 self.state.layers.insert(*layer_id, layer.clone());
@@ -959,116 +2665,104 @@ impl<'a> Canvas<'a> {
                 region,
                 photo,
                 scale_mode,
+                alignment,
             } => {
-                let rect: Rect = Rect::from_min_max(
-                    available_rect.min + region.relative_position.to_vec2() * available_rect.size(),
-                    available_rect.min
-                        + region.relative_position.to_vec2() * available_rect.size()
-                        + region.relative_size * available_rect.size(),
-                );
+                let rect = render::region_rect(available_rect, region);
+
+                // A photo being panned keeps the pointer even if it moves over another layer
+                let interactive = picked || ui.ctx().is_being_dragged(ui.next_auto_id());
 
                 let response = ui.allocate_rect(
                     rect,
-                    if is_preview {
-                        Sense::focusable_noninteractive()
+                    if !interactive {
+                        Sense::hover()
+                    } else if photo.is_some() {
+                        // Dragging pans the photo inside the region
+                        Sense::click_and_drag()
                     } else {
                         Sense::click()
                     },
                 );
 
-                if let Some(photo) = photo {
-                    Dependency::<PhotoManager>::get().with_lock_mut(|photo_manager| {
-                        if let Ok(Some(texture)) = photo_manager
-                            .texture_for_photo_with_thumbail_backup(&photo.photo, ui.ctx())
-                        {
-                            let photo_size = Vec2::new(
-                                photo.photo.metadata.width() as f32,
-                                photo.photo.metadata.height() as f32,
-                            );
-
-                            // Rotate to match the image rotation so we can calculate the scaled rect correctly
-                            let rotated_rect: Rect =
-                                if photo.photo.metadata.rotation().is_horizontal()
-                                    || photo.photo.metadata.rotation().radians()
-                                        == std::f32::consts::PI
-                                {
-                                    rect
-                                } else {
-                                    Rect::from_center_size(
-                                        rect.center(),
-                                        Vec2::new(rect.height(), rect.width()),
-                                    )
-                                };
-
-                            let scaled_rect = match scale_mode {
-                                ScaleMode::Fit => {
-                                    if photo_size.x > photo_size.y {
-                                        Rect::from_center_size(
-                                            rotated_rect.center(),
-                                            Vec2::new(
-                                                rotated_rect.width(),
-                                                rotated_rect.width() / photo_size.x * photo_size.y,
-                                            ),
-                                        )
-                                    } else {
-                                        Rect::from_center_size(
-                                            rotated_rect.center(),
-                                            Vec2::new(
-                                                rotated_rect.height() / photo_size.y * photo_size.x,
-                                                rotated_rect.height(),
-                                            ),
-                                        )
-                                    }
-                                }
-                                ScaleMode::Fill => {
-                                    if photo_size.x > photo_size.y {
-                                        Rect::from_center_size(
-                                            rotated_rect.center(),
-                                            Vec2::new(
-                                                rotated_rect.height() / photo_size.y * photo_size.x,
-                                                rotated_rect.height(),
-                                            ),
-                                        )
-                                    } else {
-                                        Rect::from_center_size(
-                                            rotated_rect.center(),
-                                            Vec2::new(
-                                                rotated_rect.width(),
-                                                rotated_rect.width() / photo_size.x * photo_size.y,
-                                            ),
-                                        )
-                                    }
-                                }
-                                ScaleMode::Stretch => rotated_rect,
-                            };
-
-                            let current_clip = ui.clip_rect();
-
-                            let clipped_rect = scaled_rect.intersect(current_clip);
-                            ui.set_clip_rect(clipped_rect);
+                if response.double_clicked() {
+                    self.open_photo_picker(
+                        ui.ctx(),
+                        Some(*layer_id),
+                        response.interact_pointer_pos().unwrap_or(rect.center()),
+                    );
+                }
 
-                            let painter = ui.painter();
-                            let mut mesh = Mesh::with_texture(texture.id);
+                if photo.is_none() && response.hovered() {
+                    ui.painter().text(
+                        rect.center(),
+                        Align2::CENTER_CENTER,
+                        "Double-click to choose a photo",
+                        FontId::proportional(14.0),
+                        Color32::DARK_GRAY,
+                    );
+                }
 
-                            mesh.add_rect_with_uv(
-                                scaled_rect.center_within(rect),
-                                Rect::from_min_max(Pos2::new(0.0, 0.0), Pos2 { x: 1.0, y: 1.0 }),
-                                Color32::WHITE,
-                            );
+                let missing = photo.as_ref().is_some_and(|photo| {
+                    Dependency::<PhotoManager>::get()
+                        .with_lock(|photo_manager| photo_manager.is_missing(&photo.photo.path))
+                });
 
-                            let mesh_center: Pos2 =
-                                scaled_rect.min + Vec2::splat(0.5) * scaled_rect.size();
+                if let (true, Some(photo)) = (missing, &photo) {
+                    MissingPhotoPlaceholder::paint(ui.painter(), rect, photo.photo.file_name());
+                } else if let Some(photo) = photo {
+                    let mut photo_rect =
+                        render::template_photo_rect(photo, rect, *scale_mode, *alignment);
+
+                    if response.dragged() {
+                        let panned = alignment.panned(response.drag_delta(), rect, photo_rect);
+                        if panned != *alignment {
+                            *alignment = panned;
+                            photo_rect =
+                                render::template_photo_rect(photo, rect, *scale_mode, *alignment);
+
+                            if let Some(Layer {
+                                content:
+                                    LayerContent::TemplatePhoto {
+                                        alignment: state_alignment,
+                                        ..
+                                    },
+                                ..
+                            }) = self.state.layers.get_mut(layer_id)
+                            {
+                                *state_alignment = panned;
+                            }
+                        }
+                    }
 
-                            mesh.rotate(
-                                Rot2::from_angle(photo.photo.metadata.rotation().radians()),
-                                mesh_center,
-                            );
+                    if response.hovered() || response.dragged() {
+                        Dependency::<CursorManager>::get().with_lock_mut(|cursor_manager| {
+                            cursor_manager.set_cursor(if response.dragged() {
+                                CursorIcon::Grabbing
+                            } else {
+                                CursorIcon::Grab
+                            });
+                        });
+                    }
 
-                            painter.add(Shape::mesh(mesh));
+                    let texture =
+                        Dependency::<PhotoManager>::get().with_lock_mut(|photo_manager| {
+                            photo_manager.adjusted_texture_for(
+                                &photo.photo,
+                                &photo.adjustments,
+                                ui.ctx(),
+                            )
+                        });
 
-                            ui.set_clip_rect(current_clip);
-                        }
-                    });
+                    if let Ok(Some(texture)) = texture {
+                        paint_template_photo(
+                            ui,
+                            texture.id,
+                            photo,
+                            rect,
+                            photo_rect,
+                            self.state.zoom,
+                        );
+                    }
                 }
 
                 if layer.selected {
@@ -1078,7 +2772,7 @@ impl<'a> Canvas<'a> {
 
                 Some(TransformableWidgetResponse {
                     mouse_down: response.is_pointer_button_down_on(),
-                    ended_moving: false,
+                    ended_moving: response.drag_stopped(),
                     ended_resizing: false,
                     ended_rotating: false,
                     inner: (),
@@ -1089,31 +2783,23 @@ impl<'a> Canvas<'a> {
                 })
             }
             LayerContent::TemplateText { region, text } => {
-                let rect = Rect::from_min_max(
-                    available_rect.min + region.relative_position.to_vec2() * available_rect.size(),
-                    available_rect.min
-                        + region.relative_position.to_vec2() * available_rect.size()
-                        + region.relative_size * available_rect.size(),
-                );
+                let rect = render::region_rect(available_rect, region);
 
                 let response = ui.allocate_rect(
                     rect,
-                    if is_preview {
-                        Sense::focusable_noninteractive()
-                    } else {
+                    if picked {
                         Sense::click()
+                    } else {
+                        Sense::hover()
                     },
                 );
 
                 Self::draw_text(
                     ui,
-                    &text.text,
-                    &text.font_id,
+                    text,
                     rect,
-                    text.font_size * self.state.zoom,
-                    text.color,
-                    text.horizontal_alignment,
-                    text.vertical_alignment,
+                    self.state.zoom,
+                    self.state.section.as_deref(),
                 );
 
                 if layer.selected {
@@ -1139,6 +2825,31 @@ impl<'a> Canvas<'a> {
         return layer_response;
     }
 
+    // Layers following the naming rules are only renamed once what they're named after changes
+    // Moves the anchored captions only when a caption, its anchor or its photo has changed
+    fn update_anchored_captions(&mut self, ctx: &Context) {
+        let key_id = self.state.canvas_id.with("anchored_captions_key");
+
+        if ctx.data(|data| data.get_temp::<u64>(key_id)) != Some(self.state.anchored_captions_key())
+        {
+            self.state.update_anchored_captions();
+            // Taken again since moving the captions changes it
+            let key = self.state.anchored_captions_key();
+            ctx.data_mut(|data| data.insert_temp(key_id, key));
+        }
+    }
+
+    fn update_layer_names(&mut self, ctx: &Context) {
+        let naming = LayerNaming::current();
+        let key = naming.names_key(self.state.layers.values());
+        let key_id = self.state.canvas_id.with("layer_names_key");
+
+        if ctx.data(|data| data.get_temp::<u64>(key_id)) != Some(key) {
+            self.state.update_layer_names(&naming);
+            ctx.data_mut(|data| data.insert_temp(key_id, key));
+        }
+    }
+
     fn draw_quick_layout_number(
         &self,
         ui: &mut Ui,
@@ -1175,29 +2886,29 @@ impl<'a> Canvas<'a> {
         }
     }
 
-    fn draw_text(
-        ui: &mut Ui,
-        text: &str,
-        font_id: &FontId,
-        rect: Rect,
-        font_size: f32,
-        color: Color32,
-        horizontal_alignment: TextHorizontalAlignment,
-        vertical_alignment: TextVerticalAlignment,
-    ) {
+    fn draw_text(ui: &mut Ui, text: &CanvasText, rect: Rect, zoom: f32, section: Option<&str>) {
+        if Self::draw_shaped_text(ui, text, rect, zoom, section) {
+            return;
+        }
+
+        if text.orientation.is_sideways() {
+            Self::draw_sideways_text(ui, text, rect, zoom, section);
+            return;
+        }
+
         ui.allocate_ui_at_rect(rect, |ui| {
             ui.style_mut().interaction.selectable_labels = false;
 
             let layout = Layout {
                 main_dir: egui::Direction::TopDown,
                 main_wrap: true,
-                main_align: match vertical_alignment {
+                main_align: match text.vertical_alignment {
                     TextVerticalAlignment::Top => Align::Min,
                     TextVerticalAlignment::Center => Align::Center,
                     TextVerticalAlignment::Bottom => Align::Max,
                 },
                 main_justify: true,
-                cross_align: match horizontal_alignment {
+                cross_align: match text.horizontal_alignment {
                     TextHorizontalAlignment::Left => Align::Min,
                     TextHorizontalAlignment::Center => Align::Center,
                     TextHorizontalAlignment::Right => Align::Max,
@@ -1205,14 +2916,11 @@ impl<'a> Canvas<'a> {
                 cross_justify: false,
             };
 
-            ui.with_layout(layout, |ui| {
-                ui.label(
-                    RichText::new(text)
-                        .color(color)
-                        .family(font_id.family.clone())
-                        .size(font_size),
-                )
-            });
+            let metadata = BookMetadata::current().with_section(section);
+            let job = ui
+                .fonts(|fonts| render::text_layout_job(fonts, text, &metadata, zoom, rect.width()));
+
+            ui.with_layout(layout, |ui| ui.label(job));
 
             // TODO: It seems like there isn't a way to rotate when drawing text with ui.label
             // The following sort of works but it makes laying out t vhe text more difficult because we can't use eguis layout system
@@ -1238,7 +2946,149 @@ impl<'a> Canvas<'a> {
         });
     }
 
+    // Text in scripts egui can't draw is shaped and painted as an image, made again only when the
+    // text or its size changes. Returns false to leave the text to egui when it doesn't need
+    // shaping or there's no font for it.
+    fn draw_shaped_text(
+        ui: &mut Ui,
+        text: &CanvasText,
+        rect: Rect,
+        zoom: f32,
+        section: Option<&str>,
+    ) -> bool {
+        if text_shaping::complex_script(&text.text).is_none() {
+            return false;
+        }
+
+        let Some(definitions) = Dependency::<FontManager>::get()
+            .with_lock(|font_manager| font_manager.font_definitions.clone())
+        else {
+            return false;
+        };
+
+        let metadata = BookMetadata::current().with_section(section);
+        let resolved = render::resolved_text(text, &metadata);
+        let wrap_width = render::text_layout_rect(text, rect).width();
+        let pixels_per_point = ui.ctx().pixels_per_point();
+
+        // The fonts are part of the key since the text is shaped again once its font is loaded
+        let key = hash64(&format!(
+            "{:p} {} {:?} {:?} {:?} {:?} {:?} {:?} {} {} {}",
+            Arc::as_ptr(&definitions),
+            resolved,
+            text.font_id.family,
+            text.color,
+            text.spacing,
+            text.typography,
+            text.horizontal_alignment,
+            text.orientation,
+            text.font_size * zoom,
+            wrap_width,
+            pixels_per_point
+        ));
+
+        let id = ui.id().with("shaped_text");
+        let image = match ui.data(|data| data.get_temp::<ShapedTextImage>(id)) {
+            Some(image) if image.key == key => image,
+            _ => {
+                let texture = render::shaped_text(&definitions, text, &resolved, zoom, wrap_width)
+                    .map(|shaped| {
+                        let texture = ui.ctx().load_texture(
+                            format!("shaped_text_{}", key),
+                            shaped.rasterize(text.color, pixels_per_point),
+                            TextureOptions::LINEAR,
+                        );
+                        (texture, shaped.size, shaped.padding())
+                    });
+
+                let image = ShapedTextImage { key, texture };
+                ui.data_mut(|data| data.insert_temp(id, image.clone()));
+                image
+            }
+        };
+
+        let Some((texture, size, padding)) = image.texture else {
+            return false;
+        };
+
+        let image_rect = render::shaped_text_rect(text, size, rect).expand(padding);
+        let mut mesh = Mesh::with_texture(texture.id());
+        mesh.add_rect_with_uv(image_rect, render::full_uv(), Color32::WHITE);
+        mesh.rotate(Rot2::from_angle(text.orientation.angle()), rect.center());
+        ui.painter().add(Shape::mesh(mesh));
+
+        true
+    }
+
+    // Labels can't be turned so sideways text is painted, vertical text one upright character at
+    // a time
+    fn draw_sideways_text(
+        ui: &mut Ui,
+        text: &CanvasText,
+        rect: Rect,
+        zoom: f32,
+        section: Option<&str>,
+    ) {
+        let metadata = BookMetadata::current().with_section(section);
+        let angle = text.orientation.angle();
+
+        let shapes: Vec<Shape> = ui.fonts(|fonts| {
+            let (galley, pos) = render::sideways_text_galley(fonts, text, &metadata, zoom, rect);
+
+            match text.orientation {
+                TextOrientation::Vertical => render::vertical_text_glyphs(&galley, pos, rect)
+                    .into_iter()
+                    .filter(|(chr, _, _)| !chr.is_whitespace())
+                    .map(|(chr, center, format)| {
+                        let glyph = fonts.layout_no_wrap(
+                            chr.to_string(),
+                            format.font_id.clone(),
+                            format.color,
+                        );
+                        Shape::galley(center - glyph.size() / 2.0, glyph, format.color)
+                    })
+                    .collect(),
+                _ => {
+                    let pos = rect.center() + Rot2::from_angle(angle) * (pos - rect.center());
+                    vec![Shape::Text(
+                        TextShape::new(pos, galley, text.color).with_angle(angle),
+                    )]
+                }
+            }
+        });
+
+        ui.painter().extend(shapes);
+    }
+
+    // Tool shortcuts, and the pen's keys while a path is being drawn
+    fn handle_tool_keys(&mut self, ctx: &Context) {
+        if ctx.wants_keyboard_input() {
+            return;
+        }
+
+        if let PenToolResponse::Finished(path) = PenTool::handle_keys(ctx, self.pen_tool_id()) {
+            self.add_path(path);
+        }
+
+        let chosen = ctx.input(|input| {
+            CanvasTool::iter()
+                .find(|tool| input.modifiers.is_none() && input.key_pressed(tool.shortcut()))
+        });
+
+        if let Some(tool) = chosen {
+            self.set_tool(ctx, tool);
+        }
+    }
+
     fn handle_keys(&mut self, ctx: &Context) -> Option<CanvasResponse> {
+        // Keys belong to the focused widget, like a text field or the layers list
+        if ctx.wants_keyboard_input() {
+            return None;
+        }
+
+        // Recorded after the input has been read, the context can't be changed while it is
+        let mut nudged = None;
+
         ctx.input(|input| {
             // Exit the canvas
             if input.key_pressed(egui::Key::Backspace) && input.modifiers.ctrl {
@@ -1252,6 +3102,20 @@ impl<'a> Canvas<'a> {
 
             // Delete the selected photo
             if input.key_pressed(egui::Key::Delete) {
+                let deleted_layers: Vec<Layer> = self
+                    .state
+                    .layers
+                    .values()
+                    .filter(|layer| layer.selected)
+                    .cloned()
+                    .collect();
+
+                if !deleted_layers.is_empty() {
+                    Dependency::<TrashManager>::get().with_lock_mut(|trash_manager| {
+                        trash_manager.trash_layers(deleted_layers, self.state)
+                    });
+                }
+
                 self.state.layers.retain(|_, layer| !layer.selected);
 
                 // Remove any layers that are in the quick layout order but are no longer in the layers map
@@ -1261,34 +3125,43 @@ impl<'a> Canvas<'a> {
                     .save_history(CanvasHistoryKind::DeletePhoto, self.state);
             }
 
-            // Move the selected photo
+            // Move the selected photo. Ctrl+Arrow switches pages instead.
+            let direction = if input.modifiers.ctrl {
+                Vec2::ZERO
+            } else {
+                [
+                    (egui::Key::ArrowLeft, Vec2::new(-1.0, 0.0)),
+                    (egui::Key::ArrowRight, Vec2::new(1.0, 0.0)),
+                    (egui::Key::ArrowUp, Vec2::new(0.0, -1.0)),
+                    (egui::Key::ArrowDown, Vec2::new(0.0, 1.0)),
+                ]
+                .into_iter()
+                .filter(|(key, _)| input.key_pressed(*key))
+                .fold(Vec2::ZERO, |sum, (_, direction)| sum + direction)
+            };
+            let nudge = if direction == Vec2::ZERO
+                || !self.state.layers.values().any(|layer| layer.selected)
+            {
+                Vec2::ZERO
+            } else {
+                let distance = Dependency::<AutoPersisting<Config>>::get()
+                    .with_lock_mut(|config| config.read().map(|config| config.nudge_distance()))
+                    .unwrap_or_default();
+                let delta = direction
+                    * distance.page_pixels(
+                        &self.state.page.value,
+                        self.state.zoom,
+                        input.modifiers,
+                    );
+                nudged = Some((delta, input.time));
+                delta
+            };
+
             let mut save_transform_history = false;
             for layer in self.state.selected_layers_iter_mut() {
-                // Handle movement via arrow keys
-                {
-                    let distance = if input.modifiers.shift { 10.0 } else { 1.0 };
-
+                if !input.modifiers.ctrl {
                     let transform_state = &mut layer.transform_state;
-
-                    if input.key_pressed(egui::Key::ArrowLeft) {
-                        transform_state.rect =
-                            transform_state.rect.translate(Vec2::new(-distance, 0.0));
-                    }
-
-                    if input.key_pressed(egui::Key::ArrowRight) {
-                        transform_state.rect =
-                            transform_state.rect.translate(Vec2::new(distance, 0.0));
-                    }
-
-                    if input.key_pressed(egui::Key::ArrowUp) {
-                        transform_state.rect =
-                            transform_state.rect.translate(Vec2::new(0.0, -distance));
-                    }
-
-                    if input.key_pressed(egui::Key::ArrowDown) {
-                        transform_state.rect =
-                            transform_state.rect.translate(Vec2::new(0.0, distance));
-                    }
+                    transform_state.rect = transform_state.rect.translate(nudge);
 
                     // Once the arrow key is released then log the history
                     if input.key_released(egui::Key::ArrowLeft)
@@ -1319,6 +3192,26 @@ impl<'a> Canvas<'a> {
                     .save_history(CanvasHistoryKind::Transform, self.state);
             }
 
+            // Rotate and flip the selected photos
+            let orient_command = if input.key_pressed(egui::Key::OpenBracket) {
+                Some(OrientCommand::RotateCcw)
+            } else if input.key_pressed(egui::Key::CloseBracket) {
+                Some(OrientCommand::RotateCw)
+            } else if input.modifiers.shift && input.key_pressed(egui::Key::H) {
+                Some(OrientCommand::FlipHorizontal)
+            } else if input.modifiers.shift && input.key_pressed(egui::Key::V) {
+                Some(OrientCommand::FlipVertical)
+            } else {
+                None
+            };
+
+            if let Some(command) = orient_command {
+                if self.state.orient_selected_photos(command) {
+                    self.history_manager
+                        .save_history(CanvasHistoryKind::Orient, self.state);
+                }
+            }
+
             // Undo/Redo
             if input.key_pressed(egui::Key::Z) && input.modifiers.ctrl {
                 if input.modifiers.shift {
@@ -1331,9 +3224,70 @@ impl<'a> Canvas<'a> {
             None
         });
 
+        if let Some((delta, time)) = nudged {
+            NudgeFeedback::add(ctx, self.state.canvas_id, delta, time);
+        }
+
         None
     }
 
+    // Labels the selection with how far it has been moved with the arrow keys
+    fn show_nudge_feedback(&self, ui: &mut Ui, page_rect: Rect) {
+        let Some(feedback) = NudgeFeedback::current(ui.ctx(), self.state.canvas_id) else {
+            return;
+        };
+
+        let Some(selection_rect) = self
+            .state
+            .layers
+            .values()
+            .filter(|layer| layer.selected)
+            .map(|layer| {
+                let transform_state = &layer.transform_state;
+                render::layer_rect(
+                    page_rect,
+                    transform_state
+                        .rect
+                        .rotate_bb_around_center(transform_state.rotation),
+                    self.state.zoom,
+                )
+            })
+            .reduce(|a, b| a.union(b))
+        else {
+            return;
+        };
+
+        let distance = Dependency::<AutoPersisting<Config>>::get()
+            .with_lock_mut(|config| config.read().map(|config| config.nudge_distance()))
+            .unwrap_or_default();
+        let label = distance.delta_label(feedback.delta, &self.state.page.value, self.state.zoom);
+
+        let painter = ui.painter();
+        let galley = painter.layout_no_wrap(
+            label,
+            FontId::proportional(12.0),
+            ui.visuals().strong_text_color(),
+        );
+        let label_rect = egui::Align2::CENTER_BOTTOM.anchor_size(
+            selection_rect.center_top() - Vec2::new(0.0, 8.0),
+            galley.size(),
+        );
+        painter.rect(
+            label_rect.expand(4.0),
+            4.0,
+            ui.visuals().window_fill,
+            ui.visuals().window_stroke,
+        );
+        painter.galley(label_rect.min, galley, ui.visuals().strong_text_color());
+
+        // Repainted once more to take the label away
+        let shown_for = ui.input(|input| input.time) - feedback.last_nudge;
+        ui.ctx()
+            .request_repaint_after(std::time::Duration::from_secs_f64(
+                (NUDGE_FEEDBACK_DURATION - shown_for).max(0.0),
+            ));
+    }
+
     fn is_pointer_on_canvas(&self, ui: &mut Ui) -> bool {
         self.available_rect.contains(
             ui.input(|input| input.pointer.hover_pos())
@@ -1386,11 +3340,56 @@ impl<'a> Canvas<'a> {
             1 => {
                 let layer_id = selected_layers[0];
                 if let Some(layer) = self.state.layers.get(&layer_id) {
-                    if let LayerContent::Photo(_photo) = &layer.content {
+                    if let LayerContent::Photo(photo) = &layer.content {
                         actions.push(ActionItem {
                             kind: ActionItemKind::Text("Crop".to_string()),
                             action: ActionBarAction::Crop(layer_id),
                         });
+
+                        // Made ahead of time so the suggestion is usually ready when it's opened
+                        if photo.suggests_crop(layer.transform_state.rect.size()) {
+                            Dependency::<SaliencyCache>::get()
+                                .with_lock_mut(|cache| cache.prepare(&photo.photo));
+                            actions.push(ActionItem {
+                                kind: ActionItemKind::Text("Suggested Crop".to_string()),
+                                action: ActionBarAction::SuggestCrop(layer_id),
+                            });
+                        }
+
+                        if photo.is_cropped() {
+                            actions.push(ActionItem {
+                                kind: ActionItemKind::Text("Reset Crop".to_string()),
+                                action: ActionBarAction::ResetCrop(layer_id),
+                            });
+                        }
+
+                        actions.extend_from_slice(&[
+                            ActionItem {
+                                kind: ActionItemKind::Text("Fill Page".to_string()),
+                                action: ActionBarAction::FillPage(layer_id),
+                            },
+                            ActionItem {
+                                kind: ActionItemKind::Text("Fit Page".to_string()),
+                                action: ActionBarAction::FitPage(layer_id),
+                            },
+                        ]);
+                    }
+
+                    if let LayerContent::Photo(_)
+                    | LayerContent::TemplatePhoto { photo: Some(_), .. } = &layer.content
+                    {
+                        actions.extend(
+                            [
+                                OrientCommand::RotateCcw,
+                                OrientCommand::RotateCw,
+                                OrientCommand::FlipHorizontal,
+                                OrientCommand::FlipVertical,
+                            ]
+                            .map(|command| ActionItem {
+                                kind: ActionItemKind::Text(command.to_string()),
+                                action: ActionBarAction::Orient(command),
+                            }),
+                        );
                     }
                 }
             }
@@ -1512,16 +3511,46 @@ impl<'a> Canvas<'a> {
                                 layout.apply(&mut self.state);
                             }
                         }
-                        ActionBarAction::Crop(layer_id) => {
+                        ActionBarAction::Crop(layer_id)
+                        | ActionBarAction::SuggestCrop(layer_id) => {
                             if let Some(layer) = self.state.layers.get(&layer_id) {
                                 if let LayerContent::Photo(photo) = &layer.content {
                                     return Some(CanvasResponse::EnterCropMode {
                                         target_layer: layer_id,
                                         photo: photo.clone(),
+                                        suggest: matches!(action, ActionBarAction::SuggestCrop(_)),
                                     });
                                 }
                             }
                         }
+                        ActionBarAction::ResetCrop(layer_id) => {
+                            Crop::apply_crop(self.state, layer_id, CanvasPhoto::UNCROPPED);
+                            self.history_manager
+                                .save_history(CanvasHistoryKind::Crop, self.state);
+                            return None;
+                        }
+                        ActionBarAction::Orient(command) => {
+                            if self.state.orient_selected_photos(command) {
+                                self.history_manager
+                                    .save_history(CanvasHistoryKind::Orient, self.state);
+                            }
+                            return None;
+                        }
+                        ActionBarAction::FillPage(layer_id) => {
+                            if self.state.fill_page_with_photo(layer_id) {
+                                self.history_manager
+                                    .save_history(CanvasHistoryKind::FillPage, self.state);
+                            }
+                            return None;
+                        }
+                        ActionBarAction::FitPage(layer_id) => {
+                            let margin_mm = page_fit::fit_page_margin_mm();
+                            if self.state.fit_photo_to_page(layer_id, margin_mm) {
+                                self.history_manager
+                                    .save_history(CanvasHistoryKind::FitPage, self.state);
+                            }
+                            return None;
+                        }
                     }
                     self.history_manager
                         .save_history(CanvasHistoryKind::Transform, self.state);
@@ -1533,3 +3562,197 @@ impl<'a> Canvas<'a> {
         None
     }
 }
+
+// Draws the start and end of `gradient` filling `screen_rect` and lets them be dragged. Returns
+// whether they moved and whether a drag ended.
+/// Paints a page with egui for previews of it and for the raster and PDF exports, which are
+/// rasterized from the preview. It's driven by `render::render_page` like the other formats and
+/// paints each layer the same way the canvas does while it's being edited.
+struct PainterTarget<'a> {
+    ui: &'a mut Ui,
+    zoom: f32,
+    section: Option<&'a str>,
+    full_resolution: bool,
+}
+
+impl PainterTarget<'_> {
+    // Whether the photo is missing, and its texture once it's loaded
+    fn texture(&self, photo: &CanvasPhoto) -> (bool, Option<egui::TextureId>) {
+        Dependency::<PhotoManager>::get().with_lock_mut(|photo_manager| {
+            if photo_manager.is_missing(&photo.photo.path) {
+                return (true, None);
+            }
+
+            let texture = if self.full_resolution {
+                PhotoManager::full_resolution_texture_for(
+                    &photo.photo,
+                    &photo.adjustments,
+                    self.ui.ctx(),
+                )
+            } else {
+                photo_manager.preview_texture_for(&photo.photo, &photo.adjustments, self.ui.ctx())
+            };
+
+            (false, texture.ok().flatten().map(|texture| texture.id))
+        })
+    }
+}
+
+impl render::RenderTarget for PainterTarget<'_> {
+    type Error = std::convert::Infallible;
+
+    fn photo(&mut self, photo: &CanvasPhoto, rect: Rect, rotation: f32) -> Result<(), Self::Error> {
+        match self.texture(photo) {
+            (true, _) => paint_photo(self.ui, None, photo, rect, rotation, self.zoom),
+            (false, Some(texture)) => {
+                paint_photo(self.ui, Some(texture), photo, rect, rotation, self.zoom)
+            }
+            // Still loading
+            (false, None) => {}
+        }
+
+        Ok(())
+    }
+
+    fn template_photo(
+        &mut self,
+        photo: &CanvasPhoto,
+        region_rect: Rect,
+        photo_rect: Rect,
+    ) -> Result<(), Self::Error> {
+        match self.texture(photo) {
+            (true, _) => MissingPhotoPlaceholder::paint(
+                self.ui.painter(),
+                region_rect,
+                photo.photo.file_name(),
+            ),
+            (false, Some(texture)) => {
+                paint_template_photo(self.ui, texture, photo, region_rect, photo_rect, self.zoom)
+            }
+            (false, None) => {}
+        }
+
+        Ok(())
+    }
+
+    fn text(&mut self, text: &CanvasText, rect: Rect, _rotation: f32) -> Result<(), Self::Error> {
+        Canvas::draw_text(self.ui, text, rect, self.zoom, self.section);
+        Ok(())
+    }
+
+    fn decoration(
+        &mut self,
+        decoration: &Decoration,
+        rect: Rect,
+        rotation: f32,
+    ) -> Result<(), Self::Error> {
+        paint_decoration(self.ui, decoration, rect, rotation);
+        Ok(())
+    }
+
+    fn path(&mut self, path: &CanvasPath, rect: Rect, rotation: f32) -> Result<(), Self::Error> {
+        path.paint(self.ui.painter(), rect, rotation, self.zoom);
+        Ok(())
+    }
+}
+
+// A photo layer cropped and rotated into `rect`, or a placeholder when there's no texture because
+// the photo is missing
+fn paint_photo(
+    ui: &Ui,
+    texture: Option<egui::TextureId>,
+    photo: &CanvasPhoto,
+    rect: Rect,
+    rotation: f32,
+    zoom: f32,
+) {
+    let Some(texture) = texture else {
+        MissingPhotoPlaceholder::paint(ui.painter(), rect, photo.photo.file_name());
+        return;
+    };
+
+    let mesh = render::photo_mesh(texture, photo, rect, photo.crop, rotation, zoom);
+    ui.painter().add(Shape::mesh(mesh));
+}
+
+// A template photo placed at `photo_rect` and clipped to its region
+fn paint_template_photo(
+    ui: &mut Ui,
+    texture: egui::TextureId,
+    photo: &CanvasPhoto,
+    region_rect: Rect,
+    photo_rect: Rect,
+    zoom: f32,
+) {
+    let current_clip = ui.clip_rect();
+    ui.set_clip_rect(region_rect.intersect(current_clip));
+
+    let mesh = render::photo_mesh(texture, photo, photo_rect, render::full_uv(), 0.0, zoom);
+    ui.painter().add(Shape::mesh(mesh));
+
+    ui.set_clip_rect(current_clip);
+}
+
+// Painting at the transformed size lets SVGs rasterize at the current zoom
+fn paint_decoration(ui: &mut Ui, decoration: &Decoration, rect: Rect, rotation: f32) {
+    Image::new(decoration.uri())
+        .rotate(rotation, Vec2::splat(0.5))
+        .paint_at(ui, rect);
+}
+
+fn gradient_handles(
+    ui: &mut Ui,
+    id: Id,
+    gradient: &mut Gradient,
+    screen_rect: Rect,
+    rotation: f32,
+) -> (bool, bool) {
+    let to_screen = CanvasPath::transform(screen_rect, rotation);
+    let to_relative = |delta: Vec2| (Rot2::from_angle(-rotation) * delta) / screen_rect.size();
+    let focused = theme::color::focused(ui.visuals());
+
+    let start = to_screen(gradient.start);
+    let end = to_screen(gradient.end);
+    ui.painter()
+        .line_segment([start, end], Stroke::new(1.0, focused));
+
+    let mut moved = false;
+    let mut ended = false;
+
+    for (is_end, pos) in [(false, start), (true, end)] {
+        let response = ui.interact(
+            Rect::from_center_size(pos, Vec2::splat(GRADIENT_HANDLE_SIZE + 4.0)),
+            id.with(is_end),
+            Sense::drag(),
+        );
+
+        let color = gradient.color_at(if is_end { 1.0 } else { 0.0 });
+        let highlighted = response.hovered() || response.dragged();
+        ui.painter()
+            .circle_filled(pos, GRADIENT_HANDLE_SIZE / 2.0, color);
+        ui.painter().circle_stroke(
+            pos,
+            GRADIENT_HANDLE_SIZE / 2.0,
+            Stroke::new(if highlighted { 3.0 } else { 2.0 }, focused),
+        );
+
+        if highlighted {
+            Dependency::<CursorManager>::get().with_lock_mut(|cursor_manager| {
+                cursor_manager.set_cursor(CursorIcon::Move);
+            });
+        }
+
+        if response.dragged() {
+            let point = if is_end {
+                &mut gradient.end
+            } else {
+                &mut gradient.start
+            };
+            *point += to_relative(response.drag_delta());
+            moved = true;
+        }
+        ended |= response.drag_stopped();
+    }
+
+    (moved, ended)
+}
@@ -1,7 +1,7 @@
 use std::fmt::{Display, Formatter};
 
 use eframe::egui::{self};
-use egui::{Button, ImageSource, Pos2, RichText, Ui, Vec2};
+use egui::{Button, ImageSource, Pos2, Ui, Vec2};
 
 use strum::IntoEnumIterator;
 use strum_macros::EnumIter;
@@ -96,13 +96,9 @@ impl<'a> AlignmentInfo<'a> {
         ui.vertical(|ui| {
             ui.spacing_mut().item_spacing = Vec2::new(10.0, 5.0);
 
-            ui.label(RichText::new("Alignment").heading());
-
             self.alignment(ui);
 
             self.distribution(ui);
-
-            ui.separator();
         });
     }
 
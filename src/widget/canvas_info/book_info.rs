@@ -0,0 +1,91 @@
+use eframe::egui::{self};
+use egui::{Grid, RichText, TextEdit, Vec2};
+use strum::IntoEnumIterator;
+
+use crate::{
+    book_metadata::TextVariable,
+    book_theme::ThemeColorRole,
+    dependencies::{Dependency, SingletonFor},
+    project_settings::ProjectSettingsManager,
+};
+
+const SWATCH_SIZE: f32 = 16.0;
+
+/// Project wide settings that affect every page. The details are edited in place while the theme
+/// is only summarized, changing it goes through Project Settings > Book Theme which applies it to
+/// the pages.
+pub struct BookInfo;
+
+impl BookInfo {
+    pub fn show_details(ui: &mut egui::Ui) {
+        Dependency::<ProjectSettingsManager>::get().with_lock_mut(|manager| {
+            Grid::new("book_info_details")
+                .num_columns(2)
+                .spacing([10.0, 5.0])
+                .show(ui, |ui| {
+                    for variable in TextVariable::iter().filter(TextVariable::is_book_wide) {
+                        ui.label(format!("{}:", variable));
+                        ui.add(
+                            TextEdit::singleline(
+                                manager.project_settings.metadata.value_mut(variable),
+                            )
+                            .hint_text(variable.token()),
+                        );
+                        ui.end_row();
+                    }
+                });
+        });
+    }
+
+    pub fn show_theme(ui: &mut egui::Ui) {
+        let theme = Dependency::<ProjectSettingsManager>::get()
+            .with_lock(|manager| manager.project_settings.theme.clone());
+
+        ui.vertical(|ui| {
+            ui.spacing_mut().item_spacing = Vec2::new(10.0, 5.0);
+
+            ui.label(RichText::new(&theme.name).strong());
+
+            ui.horizontal(|ui| {
+                for role in ThemeColorRole::iter() {
+                    let (swatch, response) =
+                        ui.allocate_exact_size(Vec2::splat(SWATCH_SIZE), egui::Sense::hover());
+                    ui.painter().rect_filled(swatch, 2.0, theme.color(role));
+                    response.on_hover_text(role.to_string());
+                }
+            });
+
+            ui.label(RichText::new("Change the theme in Project Settings > Book Theme").weak());
+        });
+    }
+
+    pub fn show_text_styles(ui: &mut egui::Ui) {
+        Dependency::<ProjectSettingsManager>::get().with_lock_mut(|manager| {
+            let text_styles = &mut manager.project_settings.text_styles;
+
+            if text_styles.is_empty() {
+                ui.label(RichText::new("Create styles from a text layer's Style menu").weak());
+                return;
+            }
+
+            // Layers following a deleted style keep their formatting
+            let mut deleted = None;
+            Grid::new("book_info_text_styles")
+                .num_columns(2)
+                .spacing([10.0, 5.0])
+                .show(ui, |ui| {
+                    for (index, style) in text_styles.iter().enumerate() {
+                        ui.label(format!("{} ({} pt)", style.name, style.font_size));
+                        if ui.small_button("Delete").clicked() {
+                            deleted = Some(index);
+                        }
+                        ui.end_row();
+                    }
+                });
+
+            if let Some(index) = deleted {
+                text_styles.remove(index);
+            }
+        });
+    }
+}
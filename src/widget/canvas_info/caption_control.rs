@@ -0,0 +1,113 @@
+use eframe::egui::{self};
+use egui::{ComboBox, DragValue, TextEdit, Vec2};
+use strum::IntoEnumIterator;
+
+use crate::{
+    model::caption_anchor::{CaptionAnchor, CaptionSide},
+    photo_captions::CaptionPattern,
+};
+
+const DEFAULT_CAPTION_PATTERN: &str = "{date}";
+
+pub enum CaptionControlResponse {
+    None,
+    // Add a caption to the photo built from the pattern
+    Add(String),
+    Changed,
+    Detach,
+}
+
+/// Adds captions to photo layers and edits how an attached caption sits next to its photo
+pub struct CaptionControl;
+
+impl CaptionControl {
+    pub fn show_for_photo(ui: &mut egui::Ui) -> CaptionControlResponse {
+        let pattern_id = ui.id().with("caption_pattern");
+        let mut pattern: String = ui
+            .data(|data| data.get_temp(pattern_id))
+            .unwrap_or_else(|| DEFAULT_CAPTION_PATTERN.to_string());
+
+        let mut response = CaptionControlResponse::None;
+
+        ui.vertical(|ui| {
+            ui.spacing_mut().item_spacing = Vec2::new(10.0, 5.0);
+
+            ui.horizontal(|ui| {
+                ui.label("Text:");
+                ui.add(TextEdit::singleline(&mut pattern).desired_width(120.0));
+
+                ui.menu_button("{ }", |ui| {
+                    for token in CaptionPattern::TOKENS {
+                        if ui.button(token).clicked() {
+                            pattern.push_str(token);
+                            ui.close_menu();
+                        }
+                    }
+                })
+                .response
+                .on_hover_text("Insert a value from the photo");
+            });
+
+            if ui
+                .button("Add Caption")
+                .on_hover_text("Add a text layer below the photo that moves with it")
+                .clicked()
+            {
+                response = CaptionControlResponse::Add(pattern.clone());
+            }
+        });
+
+        ui.data_mut(|data| data.insert_temp(pattern_id, pattern));
+
+        response
+    }
+
+    pub fn show_for_caption(
+        ui: &mut egui::Ui,
+        anchor: &mut CaptionAnchor,
+    ) -> CaptionControlResponse {
+        let mut response = CaptionControlResponse::None;
+
+        ui.vertical(|ui| {
+            ui.spacing_mut().item_spacing = Vec2::new(10.0, 5.0);
+
+            let mut side = anchor.side;
+            ComboBox::from_label("Side")
+                .selected_text(side.to_string())
+                .show_ui(ui, |ui| {
+                    for option in CaptionSide::iter() {
+                        ui.selectable_value(&mut side, option, option.to_string());
+                    }
+                });
+            if side != anchor.side {
+                anchor.side = side;
+                response = CaptionControlResponse::Changed;
+            }
+
+            ui.horizontal(|ui| {
+                ui.label("Gap:");
+                if ui
+                    .add(
+                        DragValue::new(&mut anchor.gap)
+                            .range(-500.0..=500.0)
+                            .speed(0.5)
+                            .suffix(" px"),
+                    )
+                    .changed()
+                {
+                    response = CaptionControlResponse::Changed;
+                }
+            });
+
+            if ui
+                .button("Detach")
+                .on_hover_text("Stop moving the caption with its photo")
+                .clicked()
+            {
+                response = CaptionControlResponse::Detach;
+            }
+        });
+
+        response
+    }
+}
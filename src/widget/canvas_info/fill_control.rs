@@ -0,0 +1,238 @@
+use eframe::egui::{self};
+use egui::{
+    Button, Color32, ComboBox, CursorIcon, DragValue, Id, Pos2, Rect, Sense, Shape, Stroke, Vec2,
+};
+use strum::IntoEnumIterator;
+
+use crate::{
+    cursor_manager::CursorManager,
+    dependencies::{Dependency, SingletonFor},
+    model::fill::{Fill, Gradient, GradientKind},
+    theme,
+    utils::ResponseExt,
+};
+
+const BAR_HEIGHT: f32 = 18.0;
+
+const STOP_MARKER_SIZE: f32 = 10.0;
+
+/// Edits a solid or gradient fill. Gradients get a bar showing the blend with a marker for each
+/// stop, clicking the bar adds a stop and the markers can be dragged along it.
+pub struct FillControl<'a> {
+    fill: &'a mut Fill,
+    id: Id,
+}
+
+impl<'a> FillControl<'a> {
+    pub fn new(fill: &'a mut Fill, id: Id) -> Self {
+        Self { fill, id }
+    }
+
+    /// Returns true when the fill has changed enough to be recorded in the history
+    pub fn show(&mut self, ui: &mut egui::Ui) -> bool {
+        let mut changed = false;
+
+        ui.vertical(|ui| {
+            ui.spacing_mut().item_spacing = Vec2::new(10.0, 5.0);
+
+            ui.horizontal(|ui| {
+                changed |= self.kind_ui(ui);
+
+                if let Fill::Solid(color) = &mut *self.fill {
+                    changed |= ui.color_edit_button_srgba(color).changed();
+                }
+            });
+
+            if let Fill::Gradient(gradient) = &mut *self.fill {
+                changed |= Self::gradient_ui(ui, self.id, gradient);
+            }
+        });
+
+        changed
+    }
+
+    fn kind_ui(&mut self, ui: &mut egui::Ui) -> bool {
+        let current = match &*self.fill {
+            Fill::Solid(_) => None,
+            Fill::Gradient(gradient) => Some(gradient.kind),
+        };
+        let mut selected = current;
+
+        ComboBox::from_id_salt(self.id.with("kind"))
+            .selected_text(match current {
+                Some(kind) => kind.to_string(),
+                None => "Solid".to_string(),
+            })
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut selected, None, "Solid");
+                for kind in GradientKind::iter() {
+                    ui.selectable_value(&mut selected, Some(kind), kind.to_string());
+                }
+            });
+
+        if selected == current {
+            return false;
+        }
+
+        *self.fill = match (selected, &*self.fill) {
+            (None, fill) => Fill::Solid(fill.average_color()),
+            (Some(kind), Fill::Gradient(gradient)) => {
+                // Keeps the colors, the points move to where the new kind starts out
+                let defaults = Gradient::new(kind, Color32::WHITE, Color32::BLACK);
+                Fill::Gradient(Gradient {
+                    kind,
+                    stops: gradient.stops.clone(),
+                    ..defaults
+                })
+            }
+            (Some(kind), Fill::Solid(color)) => {
+                let to = if *color == Color32::WHITE {
+                    Color32::BLACK
+                } else {
+                    Color32::WHITE
+                };
+                Fill::Gradient(Gradient::new(kind, *color, to))
+            }
+        };
+
+        true
+    }
+
+    fn gradient_ui(ui: &mut egui::Ui, id: Id, gradient: &mut Gradient) -> bool {
+        let mut changed = false;
+
+        let (bar_rect, bar_response) = ui.allocate_exact_size(
+            Vec2::new(ui.available_width(), BAR_HEIGHT + STOP_MARKER_SIZE),
+            Sense::click(),
+        );
+        let bar_rect = Rect::from_min_size(bar_rect.min, Vec2::new(bar_rect.width(), BAR_HEIGHT));
+
+        // The bar always runs left to right whatever the gradient's kind and direction
+        let preview = Fill::Gradient(Gradient {
+            kind: GradientKind::Linear,
+            stops: gradient.stops.clone(),
+            start: Pos2::new(0.0, 0.5),
+            end: Pos2::new(1.0, 0.5),
+        });
+        ui.painter().add(Shape::mesh(preview.rect_mesh(bar_rect)));
+        ui.painter().rect_stroke(
+            bar_rect,
+            2.0,
+            Stroke::new(1.0, ui.visuals().widgets.noninteractive.bg_stroke.color),
+        );
+
+        if bar_response.clicked() {
+            if let Some(pointer) = bar_response.interact_pointer_pos() {
+                gradient.insert_stop((pointer.x - bar_rect.left()) / bar_rect.width());
+                changed = true;
+            }
+        }
+        bar_response.on_hover_text("Click to add a stop");
+
+        let focused = theme::color::focused(ui.visuals());
+        let count = gradient.stops.len();
+        for index in 0..count {
+            let (min, max) = Self::stop_range(gradient, index);
+
+            let stop = &mut gradient.stops[index];
+            let x = bar_rect.left() + stop.offset * bar_rect.width();
+            let tip = Pos2::new(x, bar_rect.bottom());
+            let marker_rect = Rect::from_center_size(
+                Pos2::new(x, bar_rect.bottom() + STOP_MARKER_SIZE / 2.0),
+                Vec2::splat(STOP_MARKER_SIZE),
+            );
+
+            let response = ui.interact(marker_rect, id.with(("stop", index)), Sense::drag());
+            if response.dragged() {
+                stop.offset =
+                    (stop.offset + response.drag_delta().x / bar_rect.width()).clamp(min, max);
+            }
+            changed |= response.drag_stopped();
+
+            if response.hovered() || response.dragged() {
+                Dependency::<CursorManager>::get().with_lock_mut(|cursor_manager| {
+                    cursor_manager.set_cursor(CursorIcon::ResizeHorizontal);
+                });
+            }
+
+            let outline = if response.hovered() || response.dragged() {
+                focused
+            } else {
+                ui.visuals().text_color()
+            };
+            ui.painter().add(Shape::convex_polygon(
+                vec![
+                    tip,
+                    Pos2::new(marker_rect.right(), marker_rect.bottom()),
+                    Pos2::new(marker_rect.left(), marker_rect.bottom()),
+                ],
+                stop.color,
+                Stroke::new(1.0, outline),
+            ));
+        }
+
+        let mut removed = None;
+        for index in 0..count {
+            let (min, max) = Self::stop_range(gradient, index);
+
+            let stop = &mut gradient.stops[index];
+            ui.horizontal(|ui| {
+                changed |= ui.color_edit_button_srgba(&mut stop.color).changed();
+
+                let mut percent = stop.offset * 100.0;
+                let response = ui.add(
+                    DragValue::new(&mut percent)
+                        .range(min * 100.0..=max * 100.0)
+                        .speed(0.5)
+                        .max_decimals(1)
+                        .suffix("%"),
+                );
+                stop.offset = percent / 100.0;
+                changed |= response.changed_for_history();
+
+                // A gradient needs a color at either end
+                if ui
+                    .add_enabled(count > 2, Button::new("🗑").small())
+                    .on_hover_text("Remove Stop")
+                    .clicked()
+                {
+                    removed = Some(index);
+                }
+            });
+        }
+
+        if let Some(index) = removed {
+            gradient.stops.remove(index);
+            changed = true;
+        }
+
+        if ui
+            .button("Reverse")
+            .on_hover_text("Swap the start and end colors")
+            .clicked()
+        {
+            gradient.stops.reverse();
+            for stop in &mut gradient.stops {
+                stop.offset = 1.0 - stop.offset;
+            }
+            changed = true;
+        }
+
+        changed
+    }
+
+    // Stops can't pass their neighbors so they stay in order
+    fn stop_range(gradient: &Gradient, index: usize) -> (f32, f32) {
+        let min = index
+            .checked_sub(1)
+            .and_then(|previous| gradient.stops.get(previous))
+            .map(|previous| previous.offset)
+            .unwrap_or(0.0);
+        let max = gradient
+            .stops
+            .get(index + 1)
+            .map(|next| next.offset)
+            .unwrap_or(1.0);
+        (min, max)
+    }
+}
@@ -1,22 +1,32 @@
 use eframe::egui::{self};
-use egui::RichText;
+use egui::{DragValue, ProgressBar, RichText};
 
 use egui_extras::{Column, TableBuilder};
-use strum::IntoEnumIterator;
 
 use crate::{
+    auto_persisting::AutoPersisting,
+    config::{Config, ConfigModification, DEFAULT_HISTORY_LENGTH, DEFAULT_HISTORY_MEMORY_LIMIT_MB},
+    dependencies::{Dependency, Singleton, SingletonFor},
     scene::canvas_scene::{CanvasHistoryKind, CanvasHistoryManager},
     utils::EguiUiExt,
+    widget::canvas::CanvasState,
 };
 
 #[derive(Debug, PartialEq)]
 pub struct HistoryInfoState<'a> {
     history_manager: &'a mut CanvasHistoryManager,
+    canvas_state: &'a mut CanvasState,
 }
 
-impl HistoryInfoState<'_> {
-    pub fn new(history_manager: &mut CanvasHistoryManager) -> HistoryInfoState {
-        HistoryInfoState { history_manager }
+impl<'a> HistoryInfoState<'a> {
+    pub fn new(
+        history_manager: &'a mut CanvasHistoryManager,
+        canvas_state: &'a mut CanvasState,
+    ) -> HistoryInfoState<'a> {
+        HistoryInfoState {
+            history_manager,
+            canvas_state,
+        }
     }
 }
 
@@ -34,65 +44,159 @@ impl<'a> HistoryInfo<'a> {
         ui.vertical(|ui| {
             ui.style_mut().spacing.text_edit_width = 80.0;
 
-            ui.label(RichText::new("History").heading());
+            self.memory_limit_ui(ui);
 
-            let history: Vec<&CanvasHistoryKind> = self
+            self.length_ui(ui);
+
+            ui.separator();
+
+            // Newest first
+            let history: Vec<CanvasHistoryKind> = self
                 .state
                 .history_manager
                 .stack
                 .history
                 .iter()
-                .map(|(kind, _)| kind)
+                .map(|(kind, _)| kind.clone())
                 .rev()
                 .collect();
 
+            if history.is_empty() {
+                ui.label(RichText::new("Nothing to undo yet").weak());
+                return;
+            }
+
+            let current = self.state.history_manager.stack.index;
             let available_width = ui.available_width();
+            let mut jump_to = None;
 
             TableBuilder::new(ui)
                 .column(Column::exact(available_width))
                 .striped(true)
                 .body(|body| {
-                    body.rows(
-                        20.0,
-                        self.state.history_manager.stack.history.len(),
-                        |mut row| {
-                            let index = row.index();
-                            let history_kind = history[index];
-                            row.col(|ui| {
-                                if ui
-                                    .clickable(|ui| {
-                                        ui.set_width(available_width);
-                                        ui.horizontal(|ui| {
-                                            ui.label(format!("{}", index));
-                                            ui.label(
-                                                RichText::new(history_kind.to_string())
-                                                    .monospace()
-                                                    .color(
-                                                        if (history.len() - 1) - index
-                                                            == self
-                                                                .state
-                                                                .history_manager
-                                                                .stack
-                                                                .index
-                                                        {
-                                                            egui::Color32::GREEN
-                                                        } else {
-                                                            egui::Color32::WHITE
-                                                        },
-                                                    ),
-                                            );
-                                        })
+                    body.rows(20.0, history.len(), |mut row| {
+                        let row_index = row.index();
+                        let step = (history.len() - 1) - row_index;
+                        let history_kind = &history[row_index];
+
+                        row.col(|ui| {
+                            let step_text =
+                                RichText::new(format!("{:>3}", step + 1)).monospace().weak();
+                            let mut name_text = RichText::new(history_kind.to_string());
+                            if step == current {
+                                name_text = name_text
+                                    .strong()
+                                    .color(ui.visuals().selection.stroke.color);
+                            } else if step > current {
+                                // Undone, redo or click to bring it back
+                                name_text = name_text.weak().italics();
+                            }
+
+                            let response = ui
+                                .clickable(|ui| {
+                                    ui.set_width(available_width);
+                                    ui.horizontal(|ui| {
+                                        ui.label(step_text);
+                                        ui.label(name_text);
                                     })
-                                    .response
-                                    .clicked()
-                                {
-                                    self.state.history_manager.stack.index =
-                                        (history.len() - 1) - index;
-                                }
-                            });
-                        },
-                    );
+                                })
+                                .response;
+
+                            if response.clicked() && step != current {
+                                jump_to = Some(step);
+                            }
+                        });
+                    });
                 });
+
+            if let Some(step) = jump_to {
+                self.state
+                    .history_manager
+                    .jump_to(step, self.state.canvas_state);
+            }
         });
     }
+
+    fn length_ui(&mut self, ui: &mut egui::Ui) {
+        let config: Singleton<AutoPersisting<Config>> = Dependency::get();
+        let saved_length = config.with_lock(|config| {
+            config
+                .read()
+                .map(|config| config.history_length())
+                .unwrap_or(DEFAULT_HISTORY_LENGTH)
+        });
+
+        // Saved, and used to drop history, once the drag ends or the field loses focus
+        let length_id = ui.id().with("history_length");
+        let mut length = ui
+            .data(|data| data.get_temp::<u32>(length_id))
+            .unwrap_or(saved_length);
+
+        ui.horizontal(|ui| {
+            ui.label(format!(
+                "Steps: {} of",
+                self.state.history_manager.stack.history.len()
+            ));
+
+            let length_response = ui
+                .add(DragValue::new(&mut length).range(10..=1000))
+                .on_hover_text("The oldest steps are dropped when there are more than this");
+            if length_response.changed() {
+                ui.data_mut(|data| data.insert_temp(length_id, length));
+            }
+            if length_response.drag_stopped() || length_response.lost_focus() {
+                ui.data_mut(|data| data.remove::<u32>(length_id));
+                config.with_lock_mut(|config| {
+                    if let Err(err) = config.modify(ConfigModification::SetHistoryLength(length)) {
+                        log::error!("Failed to update history length: {:?}", err);
+                    }
+                });
+            }
+        });
+    }
+
+    fn memory_limit_ui(&mut self, ui: &mut egui::Ui) {
+        let config: Singleton<AutoPersisting<Config>> = Dependency::get();
+        let saved_limit_mb = config.with_lock(|config| {
+            config
+                .read()
+                .map(|config| config.history_memory_limit_mb())
+                .unwrap_or(DEFAULT_HISTORY_MEMORY_LIMIT_MB)
+        });
+
+        // Saved, and used to drop history, once the drag ends or the field loses focus
+        let limit_id = ui.id().with("history_memory_limit");
+        let mut limit_mb = ui
+            .data(|data| data.get_temp::<u32>(limit_id))
+            .unwrap_or(saved_limit_mb);
+
+        let memory_mb = self.state.history_manager.approximate_memory() as f32 / (1024.0 * 1024.0);
+
+        ui.horizontal(|ui| {
+            ui.label(format!("Memory: {:.1} MiB of", memory_mb));
+
+            let limit = ui
+                .add(
+                    DragValue::new(&mut limit_mb)
+                        .range(16..=4096)
+                        .suffix(" MiB"),
+                )
+                .on_hover_text("The oldest history is dropped when the limit is reached");
+            if limit.changed() {
+                ui.data_mut(|data| data.insert_temp(limit_id, limit_mb));
+            }
+            if limit.drag_stopped() || limit.lost_focus() {
+                ui.data_mut(|data| data.remove::<u32>(limit_id));
+                config.with_lock_mut(|config| {
+                    if let Err(err) =
+                        config.modify(ConfigModification::SetHistoryMemoryLimit(limit_mb))
+                    {
+                        log::error!("Failed to update history memory limit: {:?}", err);
+                    }
+                });
+            }
+        });
+
+        ui.add(ProgressBar::new((memory_mb / limit_mb as f32).clamp(0.0, 1.0)).desired_height(4.0));
+    }
 }
@@ -1,19 +1,32 @@
-use std::{hash::Hasher, sync::Arc};
+use std::{f32::consts::FRAC_PI_2, hash::Hasher, sync::Arc};
 
 use eframe::epaint::Color32;
-use egui::{CursorIcon, FontId, Id, Image, Pos2, Rect, Vec2};
+use egui::{
+    text::{Fonts, LayoutJob, TextFormat},
+    CursorIcon, FontId, Id, Image, Pos2, Rect, Vec2,
+};
 use indexmap::IndexMap;
 use strum_macros::{Display, EnumIter};
 
 use crate::{
+    auto_persisting::AutoPersisting,
+    book_theme::{ThemeColorRole, ThemeFontRole},
+    config::{Config, ConfigModification},
     cursor_manager::CursorManager,
+    decoration::{Decoration, DecorationKind},
     dependencies::{Dependency, Singleton, SingletonFor},
     history::HistoricallyEqual,
     id::{next_layer_id, next_quick_layout_index, LayerId},
-    model::{self, editable_value::EditableValue},
+    layer_naming::LayerNaming,
+    model::{
+        self, canvas_path::CanvasPath, caption_anchor::CaptionAnchor,
+        editable_value::EditableValue, layer_constraints::LayerConstraints,
+    },
     photo::Photo,
     photo_manager::PhotoManager,
+    project_settings::ProjectSettingsManager,
     template::TemplateRegion,
+    typography::Typography,
     utils::{IdExt, Toggle},
     widget::{
         canvas::CanvasPhoto,
@@ -87,6 +100,137 @@ pub enum TextVerticalAlignment {
     Bottom,
 }
 
+/// Which way the lines of a text layer run. Sideways text is laid out along the layer's height
+/// so wrapping and alignment follow the lines rather than the page.
+#[derive(Debug, Clone, PartialEq, Display, EnumIter, Copy, Default)]
+pub enum TextOrientation {
+    #[default]
+    Horizontal,
+    // Reads from top to bottom, the way spines are printed in English
+    #[strum(to_string = "Rotated 90°")]
+    Rotated90,
+    // Reads from bottom to top, for captions up the side of a photo
+    #[strum(to_string = "Rotated 270°")]
+    Rotated270,
+    // Upright characters in columns from right to left, for Chinese, Japanese and Korean
+    Vertical,
+}
+
+impl TextOrientation {
+    /// The clockwise turn of the lines from horizontal in radians. Vertical columns run like
+    /// lines turned 90° but their characters are drawn upright.
+    pub fn angle(&self) -> f32 {
+        match self {
+            TextOrientation::Horizontal => 0.0,
+            TextOrientation::Rotated90 | TextOrientation::Vertical => FRAC_PI_2,
+            TextOrientation::Rotated270 => -FRAC_PI_2,
+        }
+    }
+
+    pub fn is_sideways(&self) -> bool {
+        *self != TextOrientation::Horizontal
+    }
+}
+
+/// Typographic spacing for a text layer. Everything except the line height is in ems so
+/// spacing stays proportional when the font size changes or the page is rescaled.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TextSpacing {
+    // Multiplier of the font's natural line height
+    pub line_height: f32,
+    pub letter_spacing: f32,
+    pub paragraph_spacing: f32,
+    pub first_line_indent: f32,
+}
+
+impl Default for TextSpacing {
+    fn default() -> Self {
+        Self {
+            line_height: 1.0,
+            letter_spacing: 0.0,
+            paragraph_spacing: 0.0,
+            first_line_indent: 0.0,
+        }
+    }
+}
+
+impl TextSpacing {
+    /// Lays out `text` with the spacing applied. Each line of `text` is treated as a paragraph.
+    pub fn layout_job(
+        &self,
+        fonts: &Fonts,
+        text: &str,
+        font_id: FontId,
+        color: Color32,
+    ) -> LayoutJob {
+        let paragraphs: Vec<Vec<String>> = text
+            .split('\n')
+            .map(|paragraph| vec![paragraph.to_string()])
+            .collect();
+        self.layout_lines(fonts, &paragraphs, font_id, color)
+    }
+
+    /// Lays out paragraphs that have already been broken into lines with the spacing applied
+    pub fn layout_lines(
+        &self,
+        fonts: &Fonts,
+        paragraphs: &[Vec<String>],
+        font_id: FontId,
+        color: Color32,
+    ) -> LayoutJob {
+        let font_size = font_id.size;
+        let format = self.text_format(fonts, font_id.clone(), color);
+
+        // Paragraph spacing is an empty row between paragraphs with the spacing as its height
+        let paragraph_spacer = TextFormat {
+            line_height: Some(self.paragraph_spacing * font_size),
+            ..TextFormat::simple(font_id, color)
+        };
+
+        let mut job = LayoutJob::default();
+
+        for (index, lines) in paragraphs.iter().enumerate() {
+            if index > 0 {
+                job.append("\n", 0.0, format.clone());
+
+                if self.paragraph_spacing > 0.0 {
+                    job.append("\n", 0.0, paragraph_spacer.clone());
+                }
+            }
+
+            for (line_index, line) in lines.iter().enumerate() {
+                if line_index > 0 {
+                    job.append("\n", 0.0, format.clone());
+                }
+
+                // Only the paragraph's first line is indented
+                let indent = if line_index == 0 {
+                    self.first_line_indent * font_size
+                } else {
+                    0.0
+                };
+
+                if !line.is_empty() {
+                    job.append(line, indent, format.clone());
+                }
+            }
+        }
+
+        job
+    }
+
+    /// The format text is laid out in with the line height and letter spacing applied
+    pub fn text_format(&self, fonts: &Fonts, font_id: FontId, color: Color32) -> TextFormat {
+        let row_height = fonts.row_height(&font_id);
+
+        TextFormat {
+            line_height: (self.line_height != 1.0).then_some(row_height * self.line_height),
+            extra_letter_spacing: self.letter_spacing * font_id.size,
+            ..TextFormat::simple(font_id, color)
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct CanvasText {
     pub text: String,
@@ -96,6 +240,16 @@ pub struct CanvasText {
     pub edit_state: CanvasTextEditState,
     pub horizontal_alignment: TextHorizontalAlignment,
     pub vertical_alignment: TextVerticalAlignment,
+    // When set the font or color follows the book theme instead of the literal value above
+    pub font_role: Option<ThemeFontRole>,
+    pub color_role: Option<ThemeColorRole>,
+    pub spacing: TextSpacing,
+    // The name of the project text style the layer follows
+    pub style: Option<String>,
+    // Set for captions that follow a photo layer around the page
+    pub anchor: Option<CaptionAnchor>,
+    pub typography: Typography,
+    pub orientation: TextOrientation,
 }
 
 impl CanvasText {
@@ -115,6 +269,13 @@ impl CanvasText {
             color,
             horizontal_alignment,
             vertical_alignment,
+            font_role: None,
+            color_role: None,
+            spacing: TextSpacing::default(),
+            style: None,
+            anchor: None,
+            typography: Typography::default(),
+            orientation: TextOrientation::default(),
         }
     }
 }
@@ -127,11 +288,14 @@ pub enum LayerContent {
         region: TemplateRegion,
         photo: Option<CanvasPhoto>,
         scale_mode: model::scale_mode::ScaleMode,
+        alignment: model::scale_mode::PhotoAlignment,
     },
     TemplateText {
         region: TemplateRegion,
         text: CanvasText,
     },
+    Decoration(Decoration),
+    Path(CanvasPath),
 }
 
 impl LayerContent {
@@ -153,12 +317,16 @@ impl LayerContent {
 pub struct Layer {
     pub content: LayerContent,
     pub name: String,
+    // Whether the name follows the layer naming rules, until the layer is renamed
+    pub auto_named: bool,
     pub visible: bool,
     pub locked: bool,
     pub selected: bool,
     pub id: LayerId,
     pub transform_edit_state: LayerTransformEditState,
     pub transform_state: TransformableState,
+    // Where the layer goes when the page changes size
+    pub constraints: LayerConstraints,
 }
 
 impl Layer {
@@ -190,17 +358,91 @@ impl Layer {
         Self {
             content: LayerContent::Photo(canvas_photo),
             name,
+            auto_named: true,
             visible: true,
             locked: false,
             selected: false,
             id: next_layer_id(),
             transform_edit_state,
             transform_state,
+            constraints: LayerConstraints::default(),
+        }
+    }
+
+    pub fn with_decoration(decoration: Decoration, page_size: Vec2) -> Self {
+        let name = decoration.name();
+
+        // Textures cover the page, everything else is placed in the middle at a third of the page width
+        let rect = match decoration.kind {
+            DecorationKind::Texture => Rect::from_min_size(Pos2::ZERO, page_size),
+            DecorationKind::Sticker | DecorationKind::Ornament => {
+                let size = decoration.size();
+                let width = page_size.x / 3.0;
+                Rect::from_center_size(
+                    (page_size / 2.0).to_pos2(),
+                    Vec2::new(width, width * size.y / size.x),
+                )
+            }
+        };
+
+        let transform_state = TransformableState {
+            rect,
+            active_handle: None,
+            is_moving: false,
+            handle_mode: TransformHandleMode::default(),
+            rotation: 0.0,
+            last_frame_rotation: 0.0,
+            change_in_rotation: None,
+            id: Id::random(),
+        };
+        let transform_edit_state = LayerTransformEditState::from(&transform_state);
+        Self {
+            content: LayerContent::Decoration(decoration),
+            name,
+            auto_named: false,
+            visible: true,
+            locked: false,
+            selected: false,
+            id: next_layer_id(),
+            transform_edit_state,
+            transform_state,
+            constraints: LayerConstraints::default(),
+        }
+    }
+
+    /// A layer for a path drawn with the pen tool, with its points in page coordinates
+    pub fn with_path(mut path: CanvasPath) -> Self {
+        let mut rect = Rect::from_min_size(Pos2::ZERO, Vec2::splat(1.0));
+        path.fit_rect(&mut rect, 0.0);
+        let name = if path.closed { "Shape" } else { "Path" }.to_string();
+
+        let transform_state = TransformableState {
+            rect,
+            active_handle: None,
+            is_moving: false,
+            handle_mode: TransformHandleMode::default(),
+            rotation: 0.0,
+            last_frame_rotation: 0.0,
+            change_in_rotation: None,
+            id: Id::random(),
+        };
+        let transform_edit_state = LayerTransformEditState::from(&transform_state);
+        Self {
+            content: LayerContent::Path(path),
+            name,
+            auto_named: false,
+            visible: true,
+            locked: false,
+            selected: false,
+            id: next_layer_id(),
+            transform_edit_state,
+            transform_state,
+            constraints: LayerConstraints::default(),
         }
     }
 
     pub fn new_text_layer() -> Self {
-        let text = CanvasText::new(
+        let mut text = CanvasText::new(
             "New Text Layer".to_string(),
             20.0,
             FontId::default(),
@@ -208,6 +450,11 @@ impl Layer {
             TextHorizontalAlignment::Left,
             TextVerticalAlignment::Top,
         );
+        text.font_role = Some(ThemeFontRole::Body);
+        text.color_role = Some(ThemeColorRole::Text);
+        Dependency::<ProjectSettingsManager>::get()
+            .with_lock(|manager| manager.project_settings.theme.apply_to_text(&mut text));
+
         let transform_state = TransformableState {
             rect: Rect::from_min_size(Pos2::ZERO, Vec2::new(100.0, 100.0)),
             active_handle: None,
@@ -222,12 +469,14 @@ impl Layer {
         Self {
             content: LayerContent::Text(text),
             name: "New Text Layer".to_string(),
+            auto_named: true,
             visible: true,
             locked: false,
             selected: false,
             id: next_layer_id(),
             transform_edit_state,
             transform_state,
+            constraints: LayerConstraints::default(),
         }
     }
 }
@@ -237,6 +486,10 @@ impl HistoricallyEqual for Layer {
         let layer_content_equal = match (&self.content, &other.content) {
             (LayerContent::Photo(photo), LayerContent::Photo(other_photo)) => {
                 photo.photo == other_photo.photo
+                    && photo.crop == other_photo.crop
+                    && photo.adjustments == other_photo.adjustments
+                    && photo.orientation == other_photo.orientation
+                    && photo.effects == other_photo.effects
             }
             (LayerContent::Text(text), LayerContent::Text(other_text)) => {
                 text.text == other_text.text
@@ -245,29 +498,55 @@ impl HistoricallyEqual for Layer {
                     && text.color == other_text.color
                     && text.horizontal_alignment == other_text.horizontal_alignment
                     && text.vertical_alignment == other_text.vertical_alignment
+                    && text.font_role == other_text.font_role
+                    && text.color_role == other_text.color_role
+                    && text.spacing == other_text.spacing
+                    && text.typography == other_text.typography
+                    && text.orientation == other_text.orientation
+            }
+            (LayerContent::Decoration(decoration), LayerContent::Decoration(other_decoration)) => {
+                decoration == other_decoration
             }
+            (LayerContent::Path(path), LayerContent::Path(other_path)) => path == other_path,
             _ => false,
         };
 
         layer_content_equal
             && self.name == other.name
+            && self.auto_named == other.auto_named
             && self.visible == other.visible
             && self.locked == other.locked
             && self.selected == other.selected
             && self.id == other.id
             && self.transform_state == other.transform_state
+            && self.constraints == other.constraints
     }
 }
 
 pub enum LayersResponse {
     SelectedLayer(LayerId),
+    // The first search match was picked, the canvas should bring it into view
+    JumpTo(LayerId),
+    Reordered,
+    Renamed,
     None,
 }
 
+#[derive(Debug, Clone, Default)]
+struct LayersPanelState {
+    filter: String,
+    // The layer shift selections extend from
+    anchor: Option<LayerId>,
+    // The layer the arrow keys move from
+    cursor: Option<LayerId>,
+    renaming: Option<(LayerId, String)>,
+}
+
 #[derive(Debug)]
 pub struct Layers<'a> {
     layers: &'a mut IndexMap<LayerId, Layer>,
     photo_manager: Singleton<PhotoManager>,
+    search_matches: Option<&'a mut Vec<LayerId>>,
 }
 
 impl Hash for Layer {
@@ -281,21 +560,84 @@ impl<'a> Layers<'a> {
         Self {
             layers,
             photo_manager: Dependency::get(),
+            search_matches: None,
         }
     }
 
+    /// Keeps `search_matches` up to date with the layers the search finds, empty when there's
+    /// no search
+    pub fn with_search_matches(mut self, search_matches: &'a mut Vec<LayerId>) -> Self {
+        self.search_matches = Some(search_matches);
+        self
+    }
+
     pub fn show(&mut self, ui: &mut eframe::egui::Ui) -> LayersResponse {
-        let mut selected_layer_id = None;
+        let panel_id = Id::new("layers_panel");
+        let list_id = panel_id.with("list");
+
+        let mut state = ui
+            .ctx()
+            .data(|data| data.get_temp::<LayersPanelState>(panel_id))
+            .unwrap_or_default();
+
+        let mut clicked_layer_id = None;
+        let mut double_clicked_layer_id = None;
         let mut from = None;
         let mut to = None;
+        let mut rename_finished = None;
+
+        let filter_response = ui
+            .horizontal(|ui| {
+                let response = ui.add(
+                    egui::TextEdit::singleline(&mut state.filter)
+                        .hint_text("Search layers")
+                        .desired_width((ui.available_width() - 70.0).max(50.0)),
+                );
+
+                ui.menu_button("Naming", Self::naming_menu)
+                    .response
+                    .on_hover_text("How new photo and text layers are named");
 
-        ui.vertical(|ui| {
-            let (_response, dropped_payload) =
+                response
+            })
+            .inner;
+
+        let filter = state.filter.to_lowercase();
+
+        // Layers are listed top to bottom, the reverse of the draw order
+        let visible_ids: Vec<LayerId> = self
+            .layers
+            .values()
+            .rev()
+            .filter(|layer| filter.is_empty() || Self::matches_search(layer, &filter))
+            .map(|layer| layer.id)
+            .collect();
+
+        if let Some(search_matches) = &mut self.search_matches {
+            **search_matches = if filter.is_empty() {
+                Vec::new()
+            } else {
+                visible_ids.clone()
+            };
+        }
+
+        // Enter in the search jumps to the topmost match
+        let jump_to = (filter_response.lost_focus()
+            && ui.input(|input| input.key_pressed(egui::Key::Enter)))
+        .then(|| visible_ids.first().copied())
+        .flatten();
+
+        let list_response = ui.vertical(|ui| {
+            let (_response, _dropped_payload) =
                 ui.dnd_drop_zone::<usize, ()>(egui::Frame::none(), |ui| {
                     for (idx, (layer_id, layer)) in self.layers.iter().rev().enumerate() {
+                        if !visible_ids.contains(layer_id) {
+                            continue;
+                        }
+
                         let item_id = Id::new(("layer_list", idx));
 
-                        let row = ui.horizontal(|ui| {
+                        ui.horizontal(|ui| {
                             ui.set_height(60.0);
 
                             if layer.selected {
@@ -307,84 +649,72 @@ impl<'a> Layers<'a> {
                                 );
                             }
 
-                            let response = ui.dnd_drag_source(item_id, idx, |ui| {
-                                match &layer.content {
-                                    LayerContent::Photo(canvas_photo) => {
-                                        let texture_id =
-                                            self.photo_manager.with_lock_mut(|photo_manager| {
-                                                photo_manager.thumbnail_texture_for(
-                                                    &canvas_photo.photo,
-                                                    ui.ctx(),
-                                                )
-                                            });
+                            let renaming = match &mut state.renaming {
+                                Some((renaming_id, name)) if renaming_id == layer_id => Some(name),
+                                _ => None,
+                            };
 
-                                        let image_size =
-                                            Vec2::from(canvas_photo.photo.size_with_max_size(50.0));
-
-                                        match texture_id {
-                                            Ok(Some(texture_id)) => {
-                                                let image = Image::from_texture(texture_id)
-                                                    .rotate(
-                                                        canvas_photo
-                                                            .photo
-                                                            .metadata
-                                                            .rotation()
-                                                            .radians(),
-                                                        Vec2::splat(0.5),
-                                                    )
-                                                    .fit_to_exact_size(image_size);
-                                                ui.add_sized(Vec2::new(70.0, 50.0), image);
-                                            }
-                                            _ => {
-                                                ui.add_sized(
-                                                    Vec2::new(70.0, 50.0),
-                                                    RectPlaceholder::new(image_size, Color32::GRAY),
-                                                );
-                                            }
-                                        };
-                                    }
-                                    LayerContent::Text(_) => {
-                                        ui.label("Text");
-                                    }
-                                    LayerContent::TemplatePhoto { .. } => {
-                                        ui.label("Template Photo");
+                            match renaming {
+                                Some(name) => {
+                                    self.layer_preview(ui, layer);
+
+                                    let response = ui.add(
+                                        egui::TextEdit::singleline(name)
+                                            .hint_text("Automatic name"),
+                                    );
+                                    if !response.has_focus() && !response.lost_focus() {
+                                        response.request_focus();
                                     }
-                                    LayerContent::TemplateText { .. } => {
-                                        ui.label("Template Text");
+
+                                    if response.lost_focus() {
+                                        let cancelled =
+                                            ui.input(|input| input.key_pressed(egui::Key::Escape));
+                                        rename_finished = Some(!cancelled);
                                     }
                                 }
-
-                                ui.label(&layer.name);
-                            });
-
-                            if let (Some(pointer), Some(hovered_idx)) = (
-                                ui.input(|i| i.pointer.interact_pos()),
-                                response.response.dnd_hover_payload::<usize>(),
-                            ) {
-                                let rect = ui.max_rect();
-                                let stroke = egui::Stroke::new(1.0, Color32::WHITE);
-
-                                // Calculate line position once
-                                let line_y = if *hovered_idx == idx {
-                                    None
-                                } else if pointer.y < rect.center().y {
-                                    Some(rect.top())
-                                } else {
-                                    Some(rect.bottom())
-                                };
-
-                                if let Some(line_y) = line_y {
-                                    // Draw single line and update target index
-                                    ui.painter().hline(rect.x_range(), line_y, stroke);
-                                    to = Some(if line_y == rect.bottom() {
-                                        idx + 1
-                                    } else {
-                                        idx
-                                    });
+                                // Reordering a filtered list would be ambiguous
+                                None if !filter.is_empty() => {
+                                    self.layer_preview(ui, layer);
+                                    ui.label(&layer.name);
                                 }
+                                None => {
+                                    let response = ui.dnd_drag_source(item_id, idx, |ui| {
+                                        self.layer_preview(ui, layer);
+                                        ui.label(&layer.name);
+                                    });
+
+                                    if let (Some(pointer), Some(hovered_idx)) = (
+                                        ui.input(|i| i.pointer.interact_pos()),
+                                        response.response.dnd_hover_payload::<usize>(),
+                                    ) {
+                                        let rect = ui.max_rect();
+                                        let stroke = egui::Stroke::new(1.0, Color32::WHITE);
+
+                                        // Calculate line position once
+                                        let line_y = if *hovered_idx == idx {
+                                            None
+                                        } else if pointer.y < rect.center().y {
+                                            Some(rect.top())
+                                        } else {
+                                            Some(rect.bottom())
+                                        };
+
+                                        if let Some(line_y) = line_y {
+                                            // Draw single line and update target index
+                                            ui.painter().hline(rect.x_range(), line_y, stroke);
+                                            to = Some(if line_y == rect.bottom() {
+                                                idx + 1
+                                            } else {
+                                                idx
+                                            });
+                                        }
 
-                                if let Some(dragged_idx) = response.response.dnd_release_payload() {
-                                    from = Some(*dragged_idx);
+                                        if let Some(dragged_idx) =
+                                            response.response.dnd_release_payload()
+                                        {
+                                            from = Some(*dragged_idx);
+                                        }
+                                    }
                                 }
                             }
 
@@ -394,61 +724,383 @@ impl<'a> Layers<'a> {
                                         cursor_manager.set_cursor(CursorIcon::PointingHand);
                                     },
                                 );
-                            }
 
-                            if ui.input(|i| i.pointer.primary_clicked())
-                                && ui.rect_contains_pointer(ui.max_rect())
-                            {
-                                selected_layer_id = Some(*layer_id);
+                                if ui.input(|i| {
+                                    i.pointer
+                                        .button_double_clicked(egui::PointerButton::Primary)
+                                }) {
+                                    double_clicked_layer_id = Some(*layer_id);
+                                } else if ui.input(|i| i.pointer.primary_clicked()) {
+                                    clicked_layer_id = Some(*layer_id);
+                                }
                             }
                         });
 
                         ui.separator();
                     }
+
+                    if visible_ids.is_empty() && !filter.is_empty() {
+                        ui.label("No matching layers");
+                    }
                 });
         });
 
-        if let (Some(from_idx), Some(to_idx)) = (from, to) {
-            // We draw the layers in reverse, but the from and to indices are not reversed, so we reverse to do the
-            // the swap and then reverse again before assigning back to self.layers.
+        // The list takes keyboard focus when clicked so arrow keys move the selection instead
+        // of the selected layers
+        let list_response = ui.interact(
+            list_response.response.rect,
+            list_id,
+            egui::Sense::focusable_noninteractive(),
+        );
 
-            let mut layers = self
-                .layers
-                .clone()
-                .into_iter()
-                .rev()
-                .collect::<IndexMap<_, _>>();
+        let mut response = LayersResponse::None;
+        let mut selected_layer_id = None;
 
-            let (from_key, from_layer) = layers.get_index(from_idx).unwrap();
-            let (from_key, from_layer) = (from_key.clone(), from_layer.clone());
+        if let Some(clicked_layer_id) = clicked_layer_id {
+            list_response.request_focus();
+            let modifiers = ui.input(|input| input.modifiers);
+            self.select(clicked_layer_id, modifiers, &visible_ids, &mut state);
+            selected_layer_id = Some(clicked_layer_id);
+        }
 
-            if to_idx < self.layers.len() {
-                layers.shift_insert(to_idx, from_key, from_layer.clone());
-            } else {
-                layers.shift_remove(&from_key);
-                layers.insert(from_key, from_layer.clone());
+        if let Some(double_clicked_layer_id) = double_clicked_layer_id {
+            self.start_rename(double_clicked_layer_id, &mut state);
+        }
+
+        // Clicking anywhere else, like the canvas, hands the keys back
+        if list_response.has_focus()
+            && ui.input(|input| input.pointer.any_pressed())
+            && !ui.rect_contains_pointer(list_response.rect)
+        {
+            list_response.surrender_focus();
+        }
+
+        if list_response.has_focus() && state.renaming.is_none() {
+            ui.memory_mut(|memory| {
+                memory.set_focus_lock_filter(
+                    list_id,
+                    egui::EventFilter {
+                        vertical_arrows: true,
+                        horizontal_arrows: false,
+                        tab: false,
+                        escape: true,
+                    },
+                )
+            });
+
+            if let Some(layer_id) = self.handle_keys(ui, &visible_ids, &mut state) {
+                selected_layer_id = Some(layer_id);
             }
 
-            *self.layers = layers.into_iter().rev().collect::<IndexMap<_, _>>();
+            // Typing while the list is focused filters it
+            ui.input(|input| {
+                for event in &input.events {
+                    if let egui::Event::Text(text) = event {
+                        state.filter.push_str(text);
+                    }
+                }
+
+                if input.key_pressed(egui::Key::Backspace) {
+                    state.filter.pop();
+                }
+            });
+
+            if ui.input(|input| input.key_pressed(egui::Key::Escape)) {
+                if state.filter.is_empty() {
+                    list_response.surrender_focus();
+                } else {
+                    state.filter.clear();
+                }
+            }
         }
 
         if let Some(selected_layer_id) = selected_layer_id {
-            if ui.ctx().input(|input| input.modifiers.ctrl) {
-                self.layers
-                    .get_mut(&selected_layer_id)
-                    .unwrap()
-                    .selected
-                    .toggle();
+            response = LayersResponse::SelectedLayer(selected_layer_id);
+        }
+
+        if let Some(layer_id) = jump_to {
+            self.select(layer_id, egui::Modifiers::NONE, &visible_ids, &mut state);
+            response = LayersResponse::JumpTo(layer_id);
+        }
+
+        if let Some(commit) = rename_finished {
+            if let Some((layer_id, name)) = state.renaming.take() {
+                let name = name.trim();
+                if let Some(layer) = self.layers.get_mut(&layer_id) {
+                    // Clearing the name hands it back to the naming rules
+                    if commit && name.is_empty() && !layer.auto_named {
+                        layer.auto_named = true;
+                        if let Some(name) = LayerNaming::current().name_for(&layer.content) {
+                            layer.name = name;
+                        }
+                        response = LayersResponse::Renamed;
+                    } else if commit && !name.is_empty() && name != layer.name {
+                        layer.name = name.to_string();
+                        layer.auto_named = false;
+                        response = LayersResponse::Renamed;
+                    }
+                }
+            }
+
+            list_response.request_focus();
+        }
+
+        if let (Some(from_idx), Some(to_idx)) = (from, to) {
+            if self.reorder(from_idx, to_idx) {
+                response = LayersResponse::Reordered;
+            }
+        }
+
+        ui.ctx().data_mut(|data| data.insert_temp(panel_id, state));
+
+        response
+    }
+
+    // Layers are found by name, text layers by their text as well
+    fn matches_search(layer: &Layer, search: &str) -> bool {
+        if layer.name.to_lowercase().contains(search) {
+            return true;
+        }
+
+        match &layer.content {
+            LayerContent::Text(text) | LayerContent::TemplateText { text, .. } => {
+                text.text.to_lowercase().contains(search)
+            }
+            _ => false,
+        }
+    }
+
+    fn naming_menu(ui: &mut egui::Ui) {
+        // Edits are kept here until they're applied
+        let naming_id = Id::new("layer_naming_rules");
+        let mut naming: LayerNaming = ui
+            .data(|data| data.get_temp(naming_id))
+            .unwrap_or_else(LayerNaming::current);
+
+        egui::Grid::new("layer_naming_grid")
+            .num_columns(2)
+            .spacing([10.0, 5.0])
+            .show(ui, |ui| {
+                ui.label("Photos:");
+                ui.add(egui::TextEdit::singleline(&mut naming.photo).desired_width(160.0))
+                    .on_hover_text(LayerNaming::PHOTO_TOKENS.join(" "));
+                ui.end_row();
+
+                ui.label("Text:");
+                ui.add(egui::TextEdit::singleline(&mut naming.text).desired_width(160.0))
+                    .on_hover_text(LayerNaming::TEXT_TOKENS.join(" "));
+                ui.end_row();
+            });
+
+        ui.label(
+            egui::RichText::new(format!(
+                "{} and {} are replaced for photos, {} for text",
+                LayerNaming::PHOTO_TOKENS[0],
+                LayerNaming::PHOTO_TOKENS[1],
+                LayerNaming::TEXT_TOKENS[0]
+            ))
+            .weak(),
+        );
+
+        let mut applied = false;
+        ui.horizontal(|ui| {
+            if ui.button("Apply").clicked() {
+                applied = true;
+                Dependency::<AutoPersisting<Config>>::get().with_lock_mut(|config| {
+                    if let Err(err) =
+                        config.modify(ConfigModification::SetLayerNaming(naming.clone()))
+                    {
+                        log::error!("Failed to update layer naming: {:?}", err);
+                    }
+                });
+                ui.close_menu();
+            }
+
+            if ui.button("Reset").clicked() {
+                naming = LayerNaming::default();
+            }
+        });
+
+        ui.data_mut(|data| {
+            if applied {
+                data.remove::<LayerNaming>(naming_id);
             } else {
-                for (_, layer) in self.layers.iter_mut() {
-                    layer.selected = layer.id == selected_layer_id;
+                data.insert_temp(naming_id, naming);
+            }
+        });
+    }
+
+    fn layer_preview(&self, ui: &mut egui::Ui, layer: &Layer) {
+        match &layer.content {
+            LayerContent::Photo(canvas_photo) => {
+                let texture_id = self.photo_manager.with_lock_mut(|photo_manager| {
+                    photo_manager.thumbnail_texture_for(&canvas_photo.photo, ui.ctx())
+                });
+
+                let image_size = Vec2::from(canvas_photo.photo.size_with_max_size(50.0));
+
+                match texture_id {
+                    Ok(Some(texture_id)) => {
+                        let image = Image::from_texture(texture_id)
+                            .uv(canvas_photo.photo.metadata.rotation().mirror_uv())
+                            .rotate(
+                                canvas_photo
+                                    .orientation
+                                    .radians(canvas_photo.photo.metadata.rotation()),
+                                Vec2::splat(0.5),
+                            )
+                            .fit_to_exact_size(image_size);
+                        ui.add_sized(Vec2::new(70.0, 50.0), image);
+                    }
+                    _ => {
+                        ui.add_sized(
+                            Vec2::new(70.0, 50.0),
+                            RectPlaceholder::new(image_size, Color32::GRAY),
+                        );
+                    }
+                };
+            }
+            LayerContent::Text(_) => {
+                ui.label("Text");
+            }
+            LayerContent::TemplatePhoto { .. } => {
+                ui.label("Template Photo");
+            }
+            LayerContent::TemplateText { .. } => {
+                ui.label("Template Text");
+            }
+            LayerContent::Decoration(decoration) => {
+                ui.add_sized(
+                    Vec2::new(70.0, 50.0),
+                    Image::new(decoration.uri()).fit_to_exact_size(Vec2::new(70.0, 50.0)),
+                );
+            }
+            LayerContent::Path(path) => {
+                ui.label(if path.closed { "Shape" } else { "Path" });
+            }
+        }
+    }
+
+    // Ctrl toggles the layer, shift selects every visible layer between the anchor and the layer
+    fn select(
+        &mut self,
+        layer_id: LayerId,
+        modifiers: egui::Modifiers,
+        visible_ids: &[LayerId],
+        state: &mut LayersPanelState,
+    ) {
+        let anchor_idx = state
+            .anchor
+            .and_then(|anchor| visible_ids.iter().position(|id| *id == anchor));
+        let layer_idx = visible_ids.iter().position(|id| *id == layer_id);
+
+        state.cursor = Some(layer_id);
+
+        match (anchor_idx, layer_idx) {
+            (Some(anchor_idx), Some(layer_idx)) if modifiers.shift => {
+                let range = &visible_ids[anchor_idx.min(layer_idx)..=anchor_idx.max(layer_idx)];
+                for layer in self.layers.values_mut() {
+                    let in_range = range.contains(&layer.id);
+                    layer.selected = in_range || (modifiers.command && layer.selected);
+                }
+            }
+            _ if modifiers.command => {
+                if let Some(layer) = self.layers.get_mut(&layer_id) {
+                    layer.selected.toggle();
+                }
+                state.anchor = Some(layer_id);
+            }
+            _ => {
+                for layer in self.layers.values_mut() {
+                    layer.selected = layer.id == layer_id;
                 }
+                state.anchor = Some(layer_id);
             }
         }
+    }
+
+    fn start_rename(&mut self, layer_id: LayerId, state: &mut LayersPanelState) {
+        if let Some(layer) = self.layers.get(&layer_id) {
+            state.renaming = Some((layer_id, layer.name.clone()));
+        }
+    }
 
-        match selected_layer_id {
-            Some(selected_layer_id) => LayersResponse::SelectedLayer(selected_layer_id),
-            None => LayersResponse::None,
+    // Returns the layer the selection moved to, if any
+    fn handle_keys(
+        &mut self,
+        ui: &mut egui::Ui,
+        visible_ids: &[LayerId],
+        state: &mut LayersPanelState,
+    ) -> Option<LayerId> {
+        if visible_ids.is_empty() {
+            return None;
+        }
+
+        let (modifiers, target) = ui.input(|input| {
+            let current = state
+                .cursor
+                .and_then(|cursor| visible_ids.iter().position(|id| *id == cursor));
+
+            let target = if input.key_pressed(egui::Key::ArrowDown) {
+                Some(current.map_or(0, |idx| (idx + 1).min(visible_ids.len() - 1)))
+            } else if input.key_pressed(egui::Key::ArrowUp) {
+                Some(current.map_or(0, |idx| idx.saturating_sub(1)))
+            } else if input.key_pressed(egui::Key::Home) {
+                Some(0)
+            } else if input.key_pressed(egui::Key::End) {
+                Some(visible_ids.len() - 1)
+            } else {
+                None
+            };
+
+            (input.modifiers, target)
+        });
+
+        if ui.input(|input| input.modifiers.command && input.key_pressed(egui::Key::A)) {
+            for layer in self.layers.values_mut() {
+                layer.selected = visible_ids.contains(&layer.id);
+            }
+            return visible_ids.first().copied();
         }
+
+        if ui.input(|input| input.key_pressed(egui::Key::F2)) {
+            if let Some(cursor) = state.cursor {
+                self.start_rename(cursor, state);
+            }
+            return None;
+        }
+
+        let target_id = visible_ids[target?];
+
+        // Plain arrows move the selection, shift extends it
+        let modifiers = egui::Modifiers {
+            shift: modifiers.shift,
+            ..Default::default()
+        };
+        self.select(target_id, modifiers, visible_ids, state);
+
+        Some(target_id)
+    }
+
+    // `from` and `to` are positions in the list, which is in reverse draw order
+    fn reorder(&mut self, from: usize, to: usize) -> bool {
+        let mut ids: Vec<LayerId> = self.layers.keys().rev().copied().collect();
+
+        if from >= ids.len() || to == from || to == from + 1 {
+            return false;
+        }
+
+        let id = ids.remove(from);
+        let to = if to > from { to - 1 } else { to };
+        ids.insert(to.min(ids.len()), id);
+
+        let mut layers = std::mem::take(self.layers);
+        *self.layers = ids
+            .into_iter()
+            .rev()
+            .filter_map(|id| layers.shift_remove(&id).map(|layer| (id, layer)))
+            .collect();
+
+        true
     }
 }
@@ -1,9 +1,20 @@
 pub mod alignment;
+pub mod book_info;
+pub mod caption_control;
+pub mod fill_control;
 pub mod history_info;
 pub mod layers;
+pub mod page_fit;
 pub mod page_info;
+pub mod page_notes;
 pub mod panel;
+pub mod path_control;
+pub mod photo_adjustments;
+pub mod photo_effects;
+pub mod photo_version;
+pub mod pin_control;
 pub mod quick_layout;
 pub mod scale_mode;
 pub mod text_control;
+pub mod tool_options;
 pub mod transform_control;
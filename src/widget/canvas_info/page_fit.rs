@@ -0,0 +1,109 @@
+use eframe::egui::{self};
+use egui::{DragValue, Vec2};
+
+use crate::{
+    auto_persisting::AutoPersisting,
+    config::{Config, ConfigModification, DEFAULT_FIT_PAGE_MARGIN_MM},
+    dependencies::{Dependency, Singleton, SingletonFor},
+    project_settings::ProjectSettingsManager,
+};
+
+pub enum PageFitResponse {
+    None,
+    Fill,
+    Fit { margin_mm: f32 },
+}
+
+/// Buttons for the two most common ways of placing a photo, full bleed or fitted inside a margin
+pub struct PageFit;
+
+impl PageFit {
+    pub fn show(ui: &mut egui::Ui) -> PageFitResponse {
+        let saved_margin_mm = fit_page_margin_mm();
+
+        // The margin being dragged isn't saved until the drag ends
+        let margin_id = ui.id().with("fit_page_margin");
+        let mut margin_mm = ui
+            .data(|data| data.get_temp::<f32>(margin_id))
+            .unwrap_or(saved_margin_mm);
+
+        let mut response = PageFitResponse::None;
+
+        ui.vertical(|ui| {
+            ui.spacing_mut().item_spacing = Vec2::new(10.0, 5.0);
+
+            ui.horizontal(|ui| {
+                if ui
+                    .button("Fill Page")
+                    .on_hover_text("Cover the page and its bleed, cropping the photo to fit")
+                    .clicked()
+                {
+                    response = PageFitResponse::Fill;
+                }
+
+                if ui
+                    .button("Fit Page")
+                    .on_hover_text("Center the whole photo on the page inside the margin")
+                    .clicked()
+                {
+                    response = PageFitResponse::Fit { margin_mm };
+                }
+
+                ui.label("Margin:");
+                let drag = ui.add(
+                    DragValue::new(&mut margin_mm)
+                        .range(0.0..=100.0)
+                        .speed(0.5)
+                        .suffix(" mm"),
+                );
+
+                if drag.dragged() {
+                    ui.data_mut(|data| data.insert_temp(margin_id, margin_mm));
+                } else if drag.drag_stopped() || drag.changed() {
+                    ui.data_mut(|data| data.remove::<f32>(margin_id));
+                    set_fit_page_margin_mm(margin_mm);
+                }
+            });
+        });
+
+        response
+    }
+}
+
+/// The margin Fit Page leaves around photos, the project's own if it has one and otherwise the one
+/// from the config
+pub fn fit_page_margin_mm() -> f32 {
+    Dependency::<ProjectSettingsManager>::get()
+        .with_lock(|settings| settings.project_settings.fit_page_margin_mm)
+        .unwrap_or_else(|| {
+            Dependency::<AutoPersisting<Config>>::get().with_lock(|config| {
+                config
+                    .read()
+                    .map(|config| config.fit_page_margin_mm())
+                    .unwrap_or(DEFAULT_FIT_PAGE_MARGIN_MM)
+            })
+        })
+}
+
+// Changes the margin where it came from so projects with their own margin keep it to themselves
+fn set_fit_page_margin_mm(margin_mm: f32) {
+    let margin_mm = margin_mm.max(0.0);
+    let in_project = Dependency::<ProjectSettingsManager>::get().with_lock_mut(|settings| {
+        match &mut settings.project_settings.fit_page_margin_mm {
+            Some(project_margin_mm) => {
+                *project_margin_mm = margin_mm;
+                true
+            }
+            None => false,
+        }
+    });
+
+    if !in_project {
+        let config: Singleton<AutoPersisting<Config>> = Dependency::get();
+        config.with_lock_mut(|config| {
+            if let Err(err) = config.modify(ConfigModification::SetFitPageMargin(margin_mm)) {
+                log::error!("Failed to update fit page margin: {:?}", err);
+            }
+        });
+    }
+}
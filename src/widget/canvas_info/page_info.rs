@@ -1,12 +1,14 @@
 use eframe::egui::{self};
-use egui::{ComboBox, RichText, Vec2};
+use egui::{Button, ComboBox, Id, RichText, TextEdit, Vec2};
+use log::error;
 use strum::IntoEnumIterator;
 
 use crate::{
-    model::{
-        edit_state::EditablePage,
-        unit::{PageSizePreset, Unit},
-    },
+    auto_persisting::AutoPersisting,
+    config::{Config, ConfigModification},
+    dependencies::{Dependency, Singleton, SingletonFor},
+    model::{edit_state::EditablePage, page_preset::PagePreset, unit::Unit},
+    project_settings::ProjectSettingsManager,
     utils::EditableValueTextEdit,
 };
 
@@ -21,6 +23,13 @@ impl PageInfoState<'_> {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PageInfoResponse {
+    // The page size changed, the layers are still laid out for the previous size
+    SizeChanged { previous_size_pixels: Vec2 },
+    None,
+}
+
 #[derive(Debug, PartialEq)]
 pub struct PageInfo<'a> {
     pub state: &'a mut PageInfoState<'a>,
@@ -31,38 +40,22 @@ impl<'a> PageInfo<'a> {
         PageInfo { state }
     }
 
-    pub fn show(&mut self, ui: &mut egui::Ui) {
+    pub fn show(&mut self, ui: &mut egui::Ui) -> PageInfoResponse {
         self.state.page.update();
 
+        let previous_size_pixels = self.state.page.size_pixels();
+
         ui.vertical(|ui| {
             ui.style_mut().spacing.text_edit_width = 80.0;
 
-            ui.label(RichText::new("Document Info").heading());
-
             ui.horizontal(|ui| {
-                let page = &mut self.state.page;
-
-                ComboBox::from_label("Preset Size")
-                    .selected_text("Select size preset...")
-                    .show_ui(ui, |ui| {
-                        for preset in PageSizePreset::iter() {
-                            if ui.selectable_label(false, preset.to_string()).clicked() {
-                                if let Some((width, height)) = preset.dimensions() {
-                                    page.set_size(Vec2::new(width, height));
-                                    page.edit_state.width.begin_editing();
-                                    page.edit_state.height.begin_editing();
-
-                                    *page.edit_state.width.editable_value() = width.to_string();
-                                    *page.edit_state.height.editable_value() = height.to_string();
-
-                                    page.edit_state.width.end_editing();
-                                    page.edit_state.height.end_editing();
-                                }
-                            }
-                        }
-                    });
+                if let Some(preset) = self.presets_ui(ui) {
+                    self.apply_preset(&preset);
+                }
             });
 
+            self.save_preset_ui(ui);
+
             ui.separator();
 
             ui.horizontal(|ui| {
@@ -82,6 +75,22 @@ impl<'a> PageInfo<'a> {
                 page.set_size(Vec2::new(width, new_height));
             });
 
+            ui.horizontal(|ui| {
+                let page = &mut self.state.page;
+
+                ui.label("PPI:");
+
+                let new_ppi = ui.text_edit_editable_value_singleline(&mut page.edit_state.ppi);
+                if new_ppi > 0 {
+                    page.set_ppi(new_ppi);
+                }
+
+                ui.label("Bleed:");
+
+                let new_bleed = ui.text_edit_editable_value_singleline(&mut page.edit_state.bleed);
+                page.set_bleed(new_bleed);
+            });
+
             ui.separator();
 
             ui.horizontal(|ui| {
@@ -103,5 +112,174 @@ impl<'a> PageInfo<'a> {
             });
             ui.separator();
         });
+
+        // Converting units can round the size slightly, that isn't a change
+        if (self.state.page.size_pixels() - previous_size_pixels).length() > 0.5 {
+            PageInfoResponse::SizeChanged {
+                previous_size_pixels,
+            }
+        } else {
+            PageInfoResponse::None
+        }
+    }
+
+    // Returns the preset picked from the list, if any
+    fn presets_ui(&mut self, ui: &mut egui::Ui) -> Option<PagePreset> {
+        let config: Singleton<AutoPersisting<Config>> = Dependency::get();
+
+        let (user_presets, recent_sizes) = config.with_lock_mut(|config| {
+            config
+                .read()
+                .map(|config| {
+                    (
+                        config.page_presets().to_vec(),
+                        config.recent_page_sizes().to_vec(),
+                    )
+                })
+                .unwrap_or_default()
+        });
+
+        let project_default = Dependency::<ProjectSettingsManager>::get().with_lock(|settings| {
+            settings
+                .project_settings
+                .default_page
+                .as_ref()
+                .map(|page| PagePreset {
+                    name: "Project Default".to_string(),
+                    ..PagePreset::from_page(page)
+                })
+        });
+
+        let builtin_presets = PagePreset::builtin();
+
+        let page = &self.state.page.value;
+        let selected_text = project_default
+            .iter()
+            .chain(user_presets.iter())
+            .chain(builtin_presets.iter())
+            .find(|preset| preset.matches(page))
+            .map(|preset| preset.name.clone())
+            .unwrap_or_else(|| "Custom".to_string());
+
+        let mut picked = None;
+
+        ComboBox::from_label("Preset Size")
+            .selected_text(selected_text)
+            .width(180.0)
+            .show_ui(ui, |ui| {
+                if let Some(project_default) = &project_default {
+                    if ui
+                        .selectable_label(project_default.matches(page), &project_default.name)
+                        .on_hover_text(PagePreset::size_label(
+                            project_default.size,
+                            project_default.unit,
+                        ))
+                        .clicked()
+                    {
+                        picked = Some(project_default.clone());
+                    }
+                }
+
+                ui.label(RichText::new("Built-in").weak());
+                for preset in &builtin_presets {
+                    if ui
+                        .selectable_label(preset.matches(page), &preset.name)
+                        .clicked()
+                    {
+                        picked = Some(preset.clone());
+                    }
+                }
+
+                if !user_presets.is_empty() {
+                    ui.label(RichText::new("My Presets").weak());
+                    for preset in &user_presets {
+                        ui.horizontal(|ui| {
+                            if ui
+                                .selectable_label(preset.matches(page), &preset.name)
+                                .on_hover_text(PagePreset::size_label(preset.size, preset.unit))
+                                .clicked()
+                            {
+                                picked = Some(preset.clone());
+                            }
+
+                            if ui
+                                .small_button("🗑")
+                                .on_hover_text("Delete preset")
+                                .clicked()
+                            {
+                                config.with_lock_mut(|config| {
+                                    if let Err(err) = config.modify(
+                                        ConfigModification::DeletePagePreset(preset.name.clone()),
+                                    ) {
+                                        error!("Failed to delete page preset: {:?}", err);
+                                    }
+                                });
+                            }
+                        });
+                    }
+                }
+
+                if !recent_sizes.is_empty() {
+                    ui.label(RichText::new("Recent").weak());
+                    for size in &recent_sizes {
+                        if ui
+                            .selectable_label(false, &size.name)
+                            .on_hover_text(PagePreset::size_label(size.size, size.unit))
+                            .clicked()
+                        {
+                            picked = Some(size.clone());
+                        }
+                    }
+                }
+            });
+
+        picked
+    }
+
+    fn save_preset_ui(&mut self, ui: &mut egui::Ui) {
+        let name_id = Id::new("page_preset_name");
+        let mut name = ui.data_mut(|data| data.get_temp::<String>(name_id).unwrap_or_default());
+
+        ui.horizontal(|ui| {
+            ui.add(
+                TextEdit::singleline(&mut name)
+                    .hint_text("Preset name")
+                    .desired_width(120.0),
+            );
+
+            let trimmed = name.trim().to_string();
+            if ui
+                .add_enabled(!trimmed.is_empty(), Button::new("Save Preset"))
+                .on_hover_text("Save the current size, PPI and bleed as a preset")
+                .clicked()
+            {
+                let preset = PagePreset {
+                    name: trimmed,
+                    ..PagePreset::from_page(&self.state.page.value)
+                };
+                Dependency::<AutoPersisting<Config>>::get().with_lock_mut(|config| {
+                    if let Err(err) = config.modify(ConfigModification::SavePagePreset(preset)) {
+                        error!("Failed to save page preset: {:?}", err);
+                    }
+                });
+                name.clear();
+            }
+        });
+
+        ui.data_mut(|data| data.insert_temp(name_id, name));
+    }
+
+    fn apply_preset(&mut self, preset: &PagePreset) {
+        self.state.page.value = preset.page();
+        self.state.page.update();
+
+        Dependency::<AutoPersisting<Config>>::get().with_lock_mut(|config| {
+            if let Err(err) = config.modify(ConfigModification::AddRecentPageSize(PagePreset {
+                name: PagePreset::size_label(preset.size, preset.unit),
+                ..preset.clone()
+            })) {
+                error!("Failed to save recent page size: {:?}", err);
+            }
+        });
     }
 }
@@ -0,0 +1,127 @@
+use eframe::egui::{self};
+use egui::{Id, Key, RichText, TextEdit, Vec2};
+
+use crate::{
+    id::PageId,
+    model::page_notes::{ChecklistItem, PageNotes},
+};
+
+// Offered when adding an item since most pages need the same few checks
+const COMMON_TASKS: [&str; 4] = [
+    "Confirm names",
+    "Replace blurry photo",
+    "Check spelling",
+    "Check dates",
+];
+
+/// Notes and a checklist for the page being edited
+pub struct PageNotesControl<'a> {
+    page_id: PageId,
+    notes: &'a mut PageNotes,
+}
+
+impl<'a> PageNotesControl<'a> {
+    pub fn new(page_id: PageId, notes: &'a mut PageNotes) -> Self {
+        Self { page_id, notes }
+    }
+
+    pub fn show(&mut self, ui: &mut egui::Ui) {
+        ui.vertical(|ui| {
+            ui.spacing_mut().item_spacing = Vec2::new(10.0, 5.0);
+
+            ui.add(
+                TextEdit::multiline(&mut self.notes.text)
+                    .hint_text("Notes for this page")
+                    .desired_rows(3)
+                    .desired_width(f32::INFINITY),
+            );
+
+            ui.add_space(5.0);
+
+            let open = self.notes.open_task_count();
+            ui.label(
+                RichText::new(match (self.notes.checklist.len(), open) {
+                    (0, _) => "Checklist".to_string(),
+                    (_, 0) => "Checklist, all done".to_string(),
+                    (_, open) => format!("Checklist, {} open", open),
+                })
+                .strong(),
+            );
+
+            let mut removed = None;
+            for (index, item) in self.notes.checklist.iter_mut().enumerate() {
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut item.done, "");
+
+                    let text_color = if item.done {
+                        ui.visuals().weak_text_color()
+                    } else {
+                        ui.visuals().text_color()
+                    };
+                    ui.add(
+                        TextEdit::singleline(&mut item.text)
+                            .text_color(text_color)
+                            .desired_width(ui.available_width() - 30.0),
+                    );
+
+                    if ui.small_button("🗑").on_hover_text("Remove").clicked() {
+                        removed = Some(index);
+                    }
+                });
+            }
+
+            if let Some(index) = removed {
+                self.notes.checklist.remove(index);
+            }
+
+            self.add_item_ui(ui);
+
+            if self.notes.checklist.iter().any(|item| item.done)
+                && ui.button("Clear Done").clicked()
+            {
+                self.notes.checklist.retain(|item| !item.done);
+            }
+        });
+    }
+
+    fn add_item_ui(&mut self, ui: &mut egui::Ui) {
+        // The item being typed isn't part of the page until it's added, so each
+        // page keeps its own draft
+        let new_item_id = Id::new("page_notes_new_item").with(self.page_id);
+        let mut new_item = ui
+            .data(|data| data.get_temp::<String>(new_item_id))
+            .unwrap_or_default();
+
+        ui.horizontal(|ui| {
+            let response = ui.add(
+                TextEdit::singleline(&mut new_item)
+                    .hint_text("Add an item")
+                    .desired_width(ui.available_width() - 80.0),
+            );
+            let submitted = response.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter));
+
+            if (ui.button("Add").clicked() || submitted) && !new_item.trim().is_empty() {
+                self.notes
+                    .checklist
+                    .push(ChecklistItem::new(new_item.trim()));
+                new_item.clear();
+                if submitted {
+                    response.request_focus();
+                }
+            }
+
+            ui.menu_button("⏷", |ui| {
+                for task in COMMON_TASKS {
+                    if ui.button(task).clicked() {
+                        self.notes.checklist.push(ChecklistItem::new(task));
+                        ui.close_menu();
+                    }
+                }
+            })
+            .response
+            .on_hover_text("Common checks");
+        });
+
+        ui.data_mut(|data| data.insert_temp(new_item_id, new_item));
+    }
+}
@@ -1,108 +1,540 @@
 use eframe::egui::{self};
-use egui::InnerResponse;
+use egui::{CollapsingHeader, ComboBox, Id, InnerResponse, RichText, ScrollArea, Vec2};
+use strum::IntoEnumIterator;
+use strum_macros::{Display, EnumIter};
 
 use crate::{
+    history::HistoricallyEqual,
+    id::PageId,
+    model::fill::Fill,
+    page_rescale::RescaleAnchor,
     scene::canvas_scene::{CanvasHistoryKind, CanvasHistoryManager},
+    template::TemplateRegionKind,
     widget::{
         canvas::CanvasState,
         canvas_info::{
             alignment::{AlignmentInfo, AlignmentInfoState},
-            page_info::{PageInfo, PageInfoState},
+            page_info::{PageInfo, PageInfoResponse, PageInfoState},
         },
+        canvas_state::CanvasTool,
+        segment_control::SegmentControl,
     },
 };
 
 use super::{
+    book_info::BookInfo,
+    caption_control::{CaptionControl, CaptionControlResponse},
+    fill_control::FillControl,
     history_info::{HistoryInfo, HistoryInfoState},
     layers::{Layer, LayerContent, Layers, LayersResponse},
+    page_fit::{PageFit, PageFitResponse},
+    page_notes::PageNotesControl,
+    path_control::{PathControl, PathControlState},
+    photo_adjustments::{PhotoAdjustmentsControl, PhotoAdjustmentsState},
+    photo_effects::{PhotoEffectsControl, PhotoEffectsState},
+    photo_version::{PhotoVersion, PhotoVersionState},
+    pin_control::PinControl,
     scale_mode::{ScaleMode, ScaleModeState},
-    text_control::{TextControl, TextControlState},
+    text_control::{TextControl, TextControlResponse, TextControlState},
+    tool_options::ToolOptions,
     transform_control::{TransformControl, TransformControlState},
 };
 
+// A page size change the user hasn't decided whether to rescale the layers for yet
+#[derive(Debug, Clone, Copy)]
+struct RescalePrompt {
+    // The size the layers are laid out for. Kept from the first change if the size changes again
+    // before the prompt is answered.
+    previous_size_pixels: Vec2,
+    anchor: RescaleAnchor,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Display, EnumIter)]
+pub enum InfoTab {
+    #[default]
+    Layer,
+    Page,
+    Tool,
+    Book,
+}
+
+/// The open tab and collapsed sections of the info panel. Saved with the project so the panel is
+/// laid out the same way when it's opened again.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct InfoPanelState {
+    pub tab: InfoTab,
+    // Sections start open so only the collapsed ones are remembered
+    pub collapsed_sections: Vec<String>,
+    // The tool in use last frame and the tab that was open before it was picked. Neither is saved.
+    last_tool: CanvasTool,
+    tab_before_tool: Option<InfoTab>,
+}
+
+impl InfoPanelState {
+    pub fn new(tab: InfoTab, collapsed_sections: Vec<String>) -> Self {
+        Self {
+            tab,
+            collapsed_sections,
+            ..Default::default()
+        }
+    }
+
+    // Shows the tool options while a tool other than select is picked and goes back to the
+    // previous tab when it's put down
+    fn follow_tool(&mut self, tool: CanvasTool) {
+        if tool == self.last_tool {
+            return;
+        }
+        self.last_tool = tool;
+
+        if tool == CanvasTool::default() {
+            if let Some(tab) = self.tab_before_tool.take() {
+                if self.tab == InfoTab::Tool {
+                    self.tab = tab;
+                }
+            }
+        } else if self.tab != InfoTab::Tool {
+            self.tab_before_tool = Some(self.tab);
+            self.tab = InfoTab::Tool;
+        }
+    }
+
+    // A collapsible part of a tab. Returns what `add_contents` returned if the section is open.
+    fn section<R>(
+        &mut self,
+        ui: &mut egui::Ui,
+        title: &str,
+        add_contents: impl FnOnce(&mut egui::Ui) -> R,
+    ) -> Option<R> {
+        let open = !self
+            .collapsed_sections
+            .iter()
+            .any(|section| section == title);
+
+        let response = CollapsingHeader::new(RichText::new(title).strong())
+            .id_salt(("info_panel_section", title))
+            .open(Some(open))
+            .show(ui, add_contents);
+
+        if response.header_response.clicked() {
+            if open {
+                self.collapsed_sections.push(title.to_string());
+            } else {
+                self.collapsed_sections.retain(|section| section != title);
+            }
+        }
+
+        response.body_returned
+    }
+}
+
 pub struct CanvasInfoResponse {
     pub history: Option<CanvasHistoryKind>,
+    // A text style that was redefined and needs to be applied to every page
+    pub restyle: Option<String>,
 }
 
 #[derive(Debug, PartialEq)]
 pub struct CanvasInfo<'a> {
+    pub page_id: PageId,
     pub canvas_state: &'a mut CanvasState,
     pub history_manager: &'a mut CanvasHistoryManager,
+    pub panel_state: &'a mut InfoPanelState,
 }
 
 impl<'a> CanvasInfo<'a> {
     pub fn show(&mut self, ui: &mut egui::Ui) -> InnerResponse<CanvasInfoResponse> {
         let mut history = None;
+        let mut restyle = None;
+
+        let tool = CanvasTool::current(ui.ctx());
+        self.panel_state.follow_tool(tool);
 
         let response = ui.allocate_ui(ui.available_size(), |ui| {
             ui.vertical(|ui| {
-                PageInfo::new(&mut PageInfoState::new(&mut self.canvas_state.page)).show(ui);
+                let tabs: Vec<(InfoTab, String)> =
+                    InfoTab::iter().map(|tab| (tab, tab.to_string())).collect();
+                ui.add(SegmentControl::new(&tabs, &mut self.panel_state.tab));
 
-                AlignmentInfo::new(&mut AlignmentInfoState::new(
-                    self.canvas_state.page.size_pixels(),
-                    self.canvas_state
-                        .layers
-                        .iter_mut()
-                        .filter(|(_, layer)| layer.selected)
-                        .map(|(_, layer)| layer)
-                        .collect(),
-                ))
-                .show(ui);
+                ui.separator();
 
-                // TODO: Handle multi select
-                let selected_layer = self
-                    .canvas_state
+                ScrollArea::vertical()
+                    .auto_shrink([false, false])
+                    .show(ui, |ui| {
+                        let tab = self.panel_state.tab;
+                        match tab {
+                            InfoTab::Layer => self.layer_tab(ui, &mut history, &mut restyle),
+                            InfoTab::Page => self.page_tab(ui, &mut history),
+                            InfoTab::Tool => self.tool_tab(ui, tool),
+                            InfoTab::Book => self.book_tab(ui),
+                        }
+                    });
+            })
+        });
+
+        InnerResponse::new(CanvasInfoResponse { history, restyle }, response.response)
+    }
+
+    fn layer_tab(
+        &mut self,
+        ui: &mut egui::Ui,
+        history: &mut Option<CanvasHistoryKind>,
+        restyle: &mut Option<String>,
+    ) {
+        self.panel_state.section(ui, "Alignment", |ui| {
+            AlignmentInfo::new(&mut AlignmentInfoState::new(
+                self.canvas_state.page.size_pixels(),
+                self.canvas_state
                     .layers
                     .iter_mut()
-                    .filter(|x| x.1.selected)
+                    .filter(|(_, layer)| layer.selected)
                     .map(|(_, layer)| layer)
-                    .next();
-
-                if let Some(layer) = selected_layer {
-                    if let LayerContent::TemplatePhoto {
-                        region: _,
-                        photo: _,
-                        scale_mode,
-                    } = &mut layer.content
-                    {
-                        ui.separator();
+                    .collect(),
+            ))
+            .show(ui);
+        });
 
-                        ScaleMode::new(&mut ScaleModeState::new(scale_mode)).show(ui);
+        let mut page_fit = None;
+        let mut add_caption = None;
+
+        // TODO: Handle multi select
+        let selected_layer = self
+            .canvas_state
+            .layers
+            .iter_mut()
+            .filter(|x| x.1.selected)
+            .map(|(_, layer)| layer)
+            .next();
+
+        if let Some(layer) = selected_layer {
+            if let LayerContent::Photo(photo)
+            | LayerContent::TemplatePhoto {
+                photo: Some(photo), ..
+            } = &mut layer.content
+            {
+                self.panel_state.section(ui, "Photo", |ui| {
+                    if PhotoVersion::new(PhotoVersionState::new(photo)).show(ui) {
+                        *history = Some(CanvasHistoryKind::Version);
                     }
 
-                    {
-                        TransformControl::new(TransformControlState::new(layer)).show(ui);
+                    if PhotoAdjustmentsControl::new(PhotoAdjustmentsState::new(photo)).show(ui) {
+                        *history = Some(CanvasHistoryKind::Adjust);
+                    }
+                });
+            }
+
+            if let LayerContent::Photo(photo) = &mut layer.content {
+                self.panel_state.section(ui, "Effects", |ui| {
+                    if PhotoEffectsControl::new(PhotoEffectsState::new(photo)).show(ui) {
+                        *history = Some(CanvasHistoryKind::Effects);
+                    }
+                });
+            }
 
-                        ui.separator();
+            if let LayerContent::Photo(_) = layer.content {
+                // Applied once the layer is no longer borrowed, it needs the page
+                let layer_id = layer.id;
+                page_fit = self
+                    .panel_state
+                    .section(ui, "Page Fit", |ui| (layer_id, PageFit::show(ui)));
 
-                        if layer.content.is_text() {
-                            TextControl::new(TextControlState::new(layer)).show(ui);
-                            ui.separator();
+                add_caption = self
+                    .panel_state
+                    .section(ui, "Caption", |ui| {
+                        match CaptionControl::show_for_photo(ui) {
+                            CaptionControlResponse::Add(pattern) => Some((layer_id, pattern)),
+                            _ => None,
                         }
+                    })
+                    .flatten();
+            }
+
+            if let LayerContent::Text(text) = &mut layer.content {
+                if let Some(anchor) = &mut text.anchor {
+                    match self.panel_state.section(ui, "Caption", |ui| {
+                        CaptionControl::show_for_caption(ui, anchor)
+                    }) {
+                        Some(CaptionControlResponse::Changed) => {
+                            *history = Some(CanvasHistoryKind::EditText);
+                        }
+                        Some(CaptionControlResponse::Detach) => {
+                            text.anchor = None;
+                            *history = Some(CanvasHistoryKind::EditText);
+                        }
+                        Some(CaptionControlResponse::Add(_))
+                        | Some(CaptionControlResponse::None)
+                        | None => {}
                     }
                 }
+            }
 
-                ui.separator();
+            if let LayerContent::TemplatePhoto {
+                scale_mode,
+                alignment,
+                ..
+            } = &mut layer.content
+            {
+                self.panel_state.section(ui, "Scale Mode", |ui| {
+                    if ScaleMode::new(&mut ScaleModeState::new(scale_mode, alignment)).show(ui) {
+                        *history = Some(CanvasHistoryKind::ScaleMode);
+                    }
+                });
+            }
+
+            self.panel_state.section(ui, "Transform", |ui| {
+                TransformControl::new(TransformControlState::new(layer)).show(ui);
+            });
+
+            self.panel_state.section(ui, "Pin to Page", |ui| {
+                if PinControl::show(ui, &mut layer.constraints) {
+                    *history = Some(CanvasHistoryKind::PinLayer);
+                }
+            });
+
+            if layer.content.is_text() {
+                self.panel_state.section(ui, "Text", |ui| {
+                    let before = layer.clone();
+                    if let TextControlResponse::RedefineStyle(name) =
+                        TextControl::new(TextControlState::new(layer)).show(ui)
+                    {
+                        *restyle = Some(name);
+                    }
+                    if !layer.historically_equal_to(&before) {
+                        *history = Some(CanvasHistoryKind::EditText);
+                    }
+                });
+            }
 
-                match Layers::new(&mut self.canvas_state.layers).show(ui) {
-                    LayersResponse::SelectedLayer(_) => {
-                        history = Some(CanvasHistoryKind::SelectLayer)
+            if let LayerContent::Path(_) = layer.content {
+                self.panel_state.section(ui, "Path", |ui| {
+                    if PathControl::new(PathControlState::new(layer)).show(ui) {
+                        *history = Some(CanvasHistoryKind::EditPath);
                     }
-                    LayersResponse::None => {}
+                });
+            }
+        } else {
+            ui.label(RichText::new("Select a layer to edit it").weak());
+        }
+
+        match page_fit {
+            Some((layer_id, PageFitResponse::Fill)) => {
+                if self.canvas_state.fill_page_with_photo(layer_id) {
+                    *history = Some(CanvasHistoryKind::FillPage);
+                }
+            }
+            Some((layer_id, PageFitResponse::Fit { margin_mm })) => {
+                if self.canvas_state.fit_photo_to_page(layer_id, margin_mm) {
+                    *history = Some(CanvasHistoryKind::FitPage);
                 }
+            }
+            Some((_, PageFitResponse::None)) | None => {}
+        }
+
+        if let Some((layer_id, pattern)) = add_caption {
+            if self.canvas_state.add_caption(layer_id, &pattern).is_some() {
+                *history = Some(CanvasHistoryKind::AddCaption);
+            }
+        }
+
+        self.panel_state.section(ui, "Layers", |ui| {
+            let layers_response = Layers::new(&mut self.canvas_state.layers)
+                .with_search_matches(&mut self.canvas_state.search_matches)
+                .show(ui);
 
-                if ui.button("Add Text").clicked() {
-                    let layer = Layer::new_text_layer();
-                    self.canvas_state.layers.insert(layer.id, layer);
-                    history = Some(CanvasHistoryKind::AddText);
+            match layers_response {
+                LayersResponse::SelectedLayer(_) => *history = Some(CanvasHistoryKind::SelectLayer),
+                LayersResponse::JumpTo(layer_id) => {
+                    self.canvas_state.center_on_layer(layer_id);
+                    *history = Some(CanvasHistoryKind::SelectLayer);
                 }
+                LayersResponse::Reordered => *history = Some(CanvasHistoryKind::ReorderLayers),
+                LayersResponse::Renamed => *history = Some(CanvasHistoryKind::RenameLayer),
+                LayersResponse::None => {}
+            }
 
-                ui.separator();
+            if ui.button("Add Text").clicked() {
+                let layer = Layer::new_text_layer();
+                self.canvas_state.layers.insert(layer.id, layer);
+                *history = Some(CanvasHistoryKind::AddText);
+            }
+        });
+    }
+
+    fn page_tab(&mut self, ui: &mut egui::Ui, history: &mut Option<CanvasHistoryKind>) {
+        let page_info = self.panel_state.section(ui, "Document", |ui| {
+            PageInfo::new(&mut PageInfoState::new(&mut self.canvas_state.page)).show(ui)
+        });
+
+        if let Some(PageInfoResponse::SizeChanged {
+            previous_size_pixels,
+        }) = page_info
+        {
+            *history = Some(CanvasHistoryKind::PageSize);
+            if !self.canvas_state.layers.is_empty() {
+                let prompt_id = self.rescale_prompt_id();
+                ui.data_mut(|data| {
+                    data.get_temp_mut_or(
+                        prompt_id,
+                        RescalePrompt {
+                            previous_size_pixels,
+                            anchor: RescaleAnchor::default(),
+                        },
+                    );
+                });
+            }
+        }
 
-                HistoryInfo::new(&mut HistoryInfoState::new(self.history_manager)).show(ui);
+        if self.rescale_prompt_ui(ui) {
+            *history = Some(CanvasHistoryKind::RescaleLayers);
+        }
+
+        if PhotoAdjustmentsControl::finish_auto_enhance_page(ui.ctx(), self.canvas_state) {
+            *history = Some(CanvasHistoryKind::Adjust);
+        }
+
+        self.panel_state.section(ui, "Photos", |ui| {
+            if PhotoAdjustmentsControl::is_auto_enhancing_page(ui.ctx(), self.canvas_state) {
+                ui.horizontal(|ui| {
+                    ui.spinner();
+                    ui.label("Enhancing photos...");
+                });
+            } else if ui
+                .button("Auto Enhance Page")
+                .on_hover_text("Auto enhance every photo on this page")
+                .clicked()
+            {
+                PhotoAdjustmentsControl::start_auto_enhance_page(ui.ctx(), self.canvas_state);
+            }
+        });
+
+        let has_autofill = self.canvas_state.template.as_ref().is_some_and(|template| {
+            template.regions.iter().any(|region| {
+                matches!(
+                    region.kind,
+                    TemplateRegionKind::Text {
+                        autofill: Some(_),
+                        ..
+                    }
+                )
             })
         });
 
-        InnerResponse::new(CanvasInfoResponse { history }, response.response)
+        if has_autofill {
+            self.panel_state.section(ui, "Template Text", |ui| {
+                if ui
+                    .button("Fill From Photos")
+                    .on_hover_text(
+                        "Fill the template's text in from its photos, replacing text typed into it",
+                    )
+                    .clicked()
+                    && self.canvas_state.apply_template_autofill(&[], true)
+                {
+                    *history = Some(CanvasHistoryKind::AutofillText);
+                }
+            });
+        }
+
+        self.panel_state.section(ui, "Background", |ui| {
+            let handles_id = self.canvas_state.background_handles_id();
+            let fill_id = self.canvas_state.canvas_id.with("background_fill");
+            if FillControl::new(&mut self.canvas_state.background, fill_id).show(ui) {
+                *history = Some(CanvasHistoryKind::Background);
+            }
+
+            if matches!(self.canvas_state.background, Fill::Gradient(_)) {
+                let mut show_handles = ui
+                    .data(|data| data.get_temp::<bool>(handles_id))
+                    .unwrap_or_default();
+                ui.checkbox(&mut show_handles, "Show Handles on Page")
+                    .on_hover_text("Drag the start and end of the gradient on the page");
+                ui.data_mut(|data| data.insert_temp(handles_id, show_handles));
+            }
+        });
+
+        self.panel_state.section(ui, "Notes", |ui| {
+            PageNotesControl::new(self.page_id, &mut self.canvas_state.notes).show(ui);
+        });
+
+        self.panel_state.section(ui, "History", |ui| {
+            HistoryInfo::new(&mut HistoryInfoState::new(
+                self.history_manager,
+                self.canvas_state,
+            ))
+            .show(ui);
+        });
+    }
+
+    fn tool_tab(&mut self, ui: &mut egui::Ui, tool: CanvasTool) {
+        let title = format!("{} {}", tool.icon(), tool);
+        self.panel_state.section(ui, &title, |ui| {
+            ToolOptions::show(ui, tool, self.canvas_state)
+        });
+    }
+
+    fn book_tab(&mut self, ui: &mut egui::Ui) {
+        self.panel_state
+            .section(ui, "Details", BookInfo::show_details);
+        self.panel_state.section(ui, "Theme", BookInfo::show_theme);
+        self.panel_state
+            .section(ui, "Text Styles", BookInfo::show_text_styles);
+    }
+
+    fn rescale_prompt_id(&self) -> Id {
+        self.canvas_state.canvas_id.with("rescale_prompt")
+    }
+
+    // Offers to fit the layers to the page after its size changed. Returns true if the layers
+    // were rescaled.
+    fn rescale_prompt_ui(&mut self, ui: &mut egui::Ui) -> bool {
+        let prompt_id = self.rescale_prompt_id();
+        let Some(mut prompt) = ui.data(|data| data.get_temp::<RescalePrompt>(prompt_id)) else {
+            return false;
+        };
+
+        // Undoing the size change makes the prompt moot
+        if (self.canvas_state.page.size_pixels() - prompt.previous_size_pixels).length() <= 0.5 {
+            ui.data_mut(|data| data.remove::<RescalePrompt>(prompt_id));
+            return false;
+        }
+
+        let mut rescaled = false;
+        let mut dismissed = false;
+
+        ui.group(|ui| {
+            ui.label(RichText::new("The page size changed. Rescale the layers to fit?").strong());
+
+            ComboBox::from_label("Anchor")
+                .selected_text(prompt.anchor.to_string())
+                .show_ui(ui, |ui| {
+                    for anchor in RescaleAnchor::iter() {
+                        ui.selectable_value(&mut prompt.anchor, anchor, anchor.to_string());
+                    }
+                });
+
+            ui.horizontal(|ui| {
+                if ui.button("Rescale Layers").clicked() {
+                    self.canvas_state
+                        .rescale_layers(prompt.previous_size_pixels, prompt.anchor);
+                    rescaled = true;
+                    dismissed = true;
+                }
+                if ui.button("Keep Layout").clicked() {
+                    rescaled = self
+                        .canvas_state
+                        .reflow_pinned_layers(prompt.previous_size_pixels);
+                    dismissed = true;
+                }
+            });
+        });
+
+        ui.data_mut(|data| {
+            if dismissed {
+                data.remove::<RescalePrompt>(prompt_id);
+            } else {
+                data.insert_temp(prompt_id, prompt);
+            }
+        });
+
+        rescaled
     }
 }
@@ -0,0 +1,96 @@
+use eframe::egui::{self};
+use egui::{DragValue, Grid, Id, RichText, Vec2};
+
+use crate::utils::ResponseExt;
+
+use super::{
+    fill_control::FillControl,
+    layers::{Layer, LayerContent},
+};
+
+pub struct PathControlState<'a> {
+    layer: &'a mut Layer,
+}
+
+impl<'a> PathControlState<'a> {
+    pub fn new(layer: &'a mut Layer) -> Self {
+        Self { layer }
+    }
+}
+
+pub struct PathControl<'a> {
+    state: PathControlState<'a>,
+}
+
+impl<'a> PathControl<'a> {
+    pub fn new(state: PathControlState<'a>) -> Self {
+        Self { state }
+    }
+
+    /// Returns true when the path has changed enough to be recorded in the history
+    pub fn show(&mut self, ui: &mut egui::Ui) -> bool {
+        let layer = &mut *self.state.layer;
+        let LayerContent::Path(path) = &mut layer.content else {
+            return false;
+        };
+
+        let mut changed = false;
+
+        ui.vertical(|ui| {
+            ui.spacing_mut().item_spacing = Vec2::new(10.0, 5.0);
+
+            Grid::new("path_control")
+                .num_columns(2)
+                .spacing([10.0, 5.0])
+                .show(ui, |ui| {
+                    ui.label("Closed:");
+                    if ui
+                        .checkbox(&mut path.closed, "")
+                        .on_hover_text("Closed paths are filled")
+                        .changed()
+                    {
+                        // Closing adds a segment back to the start which can reach outside the rect
+                        path.fit_rect(
+                            &mut layer.transform_state.rect,
+                            layer.transform_state.rotation,
+                        );
+                        changed = true;
+                    }
+                    ui.end_row();
+
+                    ui.label("Fill:");
+                    ui.add_enabled_ui(path.closed, |ui| {
+                        changed |=
+                            FillControl::new(&mut path.fill, Id::new("path_fill").with(layer.id))
+                                .show(ui);
+                    });
+                    ui.end_row();
+
+                    ui.label("Stroke:");
+                    changed |= ui.color_edit_button_srgba(&mut path.stroke_color).changed();
+                    ui.end_row();
+
+                    ui.label("Stroke Width:");
+                    let response = ui.add(
+                        DragValue::new(&mut path.stroke_width)
+                            .range(0.0..=500.0)
+                            .speed(0.5)
+                            .suffix(" px"),
+                    );
+                    changed |= response.changed_for_history();
+                    ui.end_row();
+                });
+
+            ui.label(
+                RichText::new(
+                    "Drag the points to reshape the path. Double-click a point to make it a \
+                     corner or a curve, hold Alt to move a handle on its own. Gradients have \
+                     handles on the page for their start and end.",
+                )
+                .weak(),
+            );
+        });
+
+        changed
+    }
+}
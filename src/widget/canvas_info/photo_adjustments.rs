@@ -0,0 +1,197 @@
+use std::{
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+use eframe::egui::{self};
+use egui::{Context, Id, RichText, Slider, Vec2};
+use tokio::task::spawn_blocking;
+
+use crate::{
+    error_sink::ErrorSink,
+    id::LayerId,
+    photo::Photo,
+    photo_adjustments::{AdjustmentError, PhotoAdjustments},
+    utils::ResponseExt,
+    widget::canvas::{CanvasPhoto, CanvasState},
+};
+
+use super::layers::LayerContent;
+
+// The adjustments worked out for each photo on a page, with the path of the photo they're for
+type AutoEnhanceResults = Vec<(LayerId, PathBuf, Result<PhotoAdjustments, AdjustmentError>)>;
+
+// Auto enhancing a page that's still running, kept in the context's temp data under the page's
+// canvas id. Written by the blocking task once every photo is done.
+#[derive(Clone, Default)]
+struct AutoEnhanceTask(Arc<Mutex<Option<AutoEnhanceResults>>>);
+
+impl AutoEnhanceTask {
+    fn id(canvas_state: &CanvasState) -> Id {
+        canvas_state.canvas_id.with("auto_enhance_page")
+    }
+}
+
+pub struct PhotoAdjustmentsState<'a> {
+    photo: &'a mut CanvasPhoto,
+}
+
+impl<'a> PhotoAdjustmentsState<'a> {
+    pub fn new(photo: &'a mut CanvasPhoto) -> Self {
+        Self { photo }
+    }
+}
+
+pub struct PhotoAdjustmentsControl<'a> {
+    state: PhotoAdjustmentsState<'a>,
+}
+
+impl<'a> PhotoAdjustmentsControl<'a> {
+    pub fn new(state: PhotoAdjustmentsState<'a>) -> Self {
+        Self { state }
+    }
+
+    /// Returns true when the adjustments have changed enough to be recorded in the history
+    pub fn show(&mut self, ui: &mut egui::Ui) -> bool {
+        let mut changed = false;
+
+        ui.vertical(|ui| {
+            ui.spacing_mut().item_spacing = Vec2::new(10.0, 5.0);
+
+            ui.label(RichText::new("Adjustments").strong());
+
+            ui.horizontal(|ui| {
+                if ui
+                    .button("Auto Enhance")
+                    .on_hover_text("Set levels and white balance from the photo's histogram")
+                    .clicked()
+                {
+                    let adjustments = PhotoAdjustments::auto(&self.state.photo.photo);
+                    changed |= Self::apply_auto(self.state.photo, adjustments);
+                }
+
+                if ui
+                    .add_enabled(
+                        self.state.photo.adjustments != PhotoAdjustments::default(),
+                        egui::Button::new("Reset"),
+                    )
+                    .clicked()
+                {
+                    self.state.photo.adjustments = PhotoAdjustments::default();
+                    changed = true;
+                }
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("Strength:");
+                let response = ui.add(
+                    Slider::new(&mut self.state.photo.adjustments.strength, 0.0..=1.0)
+                        .fixed_decimals(2),
+                );
+                changed |= response.changed_for_history();
+            });
+        });
+
+        changed
+    }
+
+    /// Starts auto enhancing every photo on the page. Photos without a thumbnail are read at full
+    /// size, so it runs on the blocking pool and is picked up by `finish_auto_enhance_page`.
+    pub fn start_auto_enhance_page(ctx: &Context, canvas_state: &CanvasState) {
+        let photos: Vec<(LayerId, Photo)> = canvas_state
+            .layers
+            .values()
+            .filter_map(|layer| match &layer.content {
+                LayerContent::Photo(photo)
+                | LayerContent::TemplatePhoto {
+                    photo: Some(photo), ..
+                } => Some((layer.id, photo.photo.clone())),
+                LayerContent::TemplatePhoto { photo: None, .. }
+                | LayerContent::Text(_)
+                | LayerContent::TemplateText { .. }
+                | LayerContent::Decoration(_)
+                | LayerContent::Path(_) => None,
+            })
+            .collect();
+
+        let task = AutoEnhanceTask::default();
+        ctx.data_mut(|data| data.insert_temp(AutoEnhanceTask::id(canvas_state), task.clone()));
+
+        let ctx = ctx.clone();
+        spawn_blocking(move || {
+            let results = photos
+                .into_iter()
+                .map(|(layer_id, photo)| {
+                    let adjustments = PhotoAdjustments::auto(&photo);
+                    (layer_id, photo.path, adjustments)
+                })
+                .collect();
+
+            *task.0.lock().unwrap() = Some(results);
+            ctx.request_repaint();
+        });
+    }
+
+    pub fn is_auto_enhancing_page(ctx: &Context, canvas_state: &CanvasState) -> bool {
+        ctx.data(|data| {
+            data.get_temp::<AutoEnhanceTask>(AutoEnhanceTask::id(canvas_state))
+                .is_some()
+        })
+    }
+
+    /// Applies a finished auto enhance to the page's photos that are still there. Returns true if
+    /// any photo changed.
+    pub fn finish_auto_enhance_page(ctx: &Context, canvas_state: &mut CanvasState) -> bool {
+        let id = AutoEnhanceTask::id(canvas_state);
+        let Some(task) = ctx.data(|data| data.get_temp::<AutoEnhanceTask>(id)) else {
+            return false;
+        };
+        let Some(results) = task.0.lock().unwrap().take() else {
+            return false;
+        };
+        ctx.data_mut(|data| data.remove::<AutoEnhanceTask>(id));
+
+        let mut changed = false;
+        for (layer_id, path, adjustments) in results {
+            let Some(layer) = canvas_state.layers.get_mut(&layer_id) else {
+                continue;
+            };
+            match &mut layer.content {
+                LayerContent::Photo(photo)
+                | LayerContent::TemplatePhoto {
+                    photo: Some(photo), ..
+                } if photo.photo.path == path => {
+                    changed |= Self::apply_auto(photo, adjustments);
+                }
+                _ => {}
+            }
+        }
+
+        changed
+    }
+
+    fn apply_auto(
+        photo: &mut CanvasPhoto,
+        adjustments: Result<PhotoAdjustments, AdjustmentError>,
+    ) -> bool {
+        match adjustments {
+            Ok(adjustments) => {
+                // Keep the strength the user picked so re-running doesn't undo it
+                let adjustments = PhotoAdjustments {
+                    strength: photo.adjustments.strength,
+                    ..adjustments
+                };
+                let changed = photo.adjustments != adjustments;
+                photo.adjustments = adjustments;
+                changed
+            }
+            Err(err) => {
+                ErrorSink::report(
+                    format!("Couldn't auto enhance {}", photo.photo.file_name()),
+                    err,
+                );
+                false
+            }
+        }
+    }
+}
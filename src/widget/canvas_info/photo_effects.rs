@@ -0,0 +1,76 @@
+use eframe::egui::{self};
+use egui::{Slider, Vec2};
+
+use crate::{model::photo_effects::PhotoEffects, utils::ResponseExt, widget::canvas::CanvasPhoto};
+
+pub struct PhotoEffectsState<'a> {
+    photo: &'a mut CanvasPhoto,
+}
+
+impl<'a> PhotoEffectsState<'a> {
+    pub fn new(photo: &'a mut CanvasPhoto) -> Self {
+        Self { photo }
+    }
+}
+
+pub struct PhotoEffectsControl<'a> {
+    state: PhotoEffectsState<'a>,
+}
+
+impl<'a> PhotoEffectsControl<'a> {
+    pub fn new(state: PhotoEffectsState<'a>) -> Self {
+        Self { state }
+    }
+
+    /// Returns true when the effects have changed enough to be recorded in the history
+    pub fn show(&mut self, ui: &mut egui::Ui) -> bool {
+        let mut changed = false;
+        let effects = &mut self.state.photo.effects;
+
+        ui.vertical(|ui| {
+            ui.spacing_mut().item_spacing = Vec2::new(10.0, 5.0);
+
+            ui.horizontal(|ui| {
+                ui.label("Corner Radius:");
+                changed |= ui
+                    .add(
+                        Slider::new(&mut effects.corner_radius, 0.0..=500.0)
+                            .suffix(" px")
+                            .fixed_decimals(0),
+                    )
+                    .changed_for_history();
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("Vignette:");
+                changed |= ui
+                    .add(Slider::new(&mut effects.vignette_strength, 0.0..=1.0).fixed_decimals(2))
+                    .changed_for_history();
+            });
+
+            ui.add_enabled_ui(effects.vignette_strength > 0.0, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Feather:");
+                    changed |= ui
+                        .add(
+                            Slider::new(&mut effects.vignette_feather, 0.0..=1.0).fixed_decimals(2),
+                        )
+                        .changed_for_history();
+                });
+            });
+
+            if ui
+                .add_enabled(
+                    *effects != PhotoEffects::default(),
+                    egui::Button::new("Reset"),
+                )
+                .clicked()
+            {
+                *effects = PhotoEffects::default();
+                changed = true;
+            }
+        });
+
+        changed
+    }
+}
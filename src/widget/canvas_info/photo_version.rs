@@ -0,0 +1,78 @@
+use eframe::egui::{self};
+use egui::{ComboBox, RichText, Vec2};
+
+use crate::{
+    dependencies::{Dependency, SingletonFor},
+    photo_adjustments::PhotoAdjustments,
+    photo_manager::PhotoManager,
+    widget::canvas::CanvasPhoto,
+};
+
+pub struct PhotoVersionState<'a> {
+    photo: &'a mut CanvasPhoto,
+}
+
+impl<'a> PhotoVersionState<'a> {
+    pub fn new(photo: &'a mut CanvasPhoto) -> Self {
+        Self { photo }
+    }
+}
+
+/// Switches which version of a stacked photo a layer shows
+pub struct PhotoVersion<'a> {
+    state: PhotoVersionState<'a>,
+}
+
+impl<'a> PhotoVersion<'a> {
+    pub fn new(state: PhotoVersionState<'a>) -> Self {
+        Self { state }
+    }
+
+    /// Returns true if the layer now references a different version
+    pub fn show(&mut self, ui: &mut egui::Ui) -> bool {
+        let versions = Dependency::<PhotoManager>::get()
+            .with_lock(|photo_manager| photo_manager.stack_versions(&self.state.photo.photo.path));
+
+        if versions.len() < 2 {
+            return false;
+        }
+
+        let mut selected = self.state.photo.photo.path.clone();
+
+        ui.vertical(|ui| {
+            ui.spacing_mut().item_spacing = Vec2::new(10.0, 5.0);
+
+            ui.label(RichText::new("Version").strong());
+
+            ComboBox::from_id_salt("photo_layer_version")
+                .selected_text(self.state.photo.photo.file_name())
+                .show_ui(ui, |ui| {
+                    for version in &versions {
+                        ui.selectable_value(
+                            &mut selected,
+                            version.path.clone(),
+                            version.file_name(),
+                        );
+                    }
+                });
+        });
+
+        if selected == self.state.photo.photo.path {
+            return false;
+        }
+
+        let Some(version) = versions
+            .into_iter()
+            .find(|version| version.path == selected)
+        else {
+            return false;
+        };
+
+        // The crop is normalized so it carries over, the adjustments were made for the other
+        // version's colors so they don't
+        self.state.photo.photo = version;
+        self.state.photo.adjustments = PhotoAdjustments::default();
+
+        true
+    }
+}
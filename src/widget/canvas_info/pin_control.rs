@@ -0,0 +1,43 @@
+use eframe::egui::{self};
+use egui::{ComboBox, Grid, RichText};
+use strum::IntoEnumIterator;
+
+use crate::model::layer_constraints::{LayerConstraints, Pin};
+
+/// Pins the layer to the edges or center of the page so it reflows predictably when the page size
+/// or orientation changes
+pub struct PinControl;
+
+impl PinControl {
+    /// Returns true if the constraints changed
+    pub fn show(ui: &mut egui::Ui, constraints: &mut LayerConstraints) -> bool {
+        let before = *constraints;
+
+        Grid::new("layer_pins")
+            .num_columns(2)
+            .spacing([10.0, 5.0])
+            .show(ui, |ui| {
+                ui.label("Horizontal:");
+                Self::pin_combo(ui, "horizontal_pin", &mut constraints.horizontal, true);
+                ui.end_row();
+
+                ui.label("Vertical:");
+                Self::pin_combo(ui, "vertical_pin", &mut constraints.vertical, false);
+                ui.end_row();
+            });
+
+        ui.label(RichText::new("Pinned layers keep their place when the page size changes").weak());
+
+        *constraints != before
+    }
+
+    fn pin_combo(ui: &mut egui::Ui, id: &str, pin: &mut Pin, horizontal: bool) {
+        ComboBox::from_id_salt(id)
+            .selected_text(pin.name(horizontal))
+            .show_ui(ui, |ui| {
+                for option in Pin::iter() {
+                    ui.selectable_value(pin, option, option.name(horizontal));
+                }
+            });
+    }
+}
@@ -1,23 +1,24 @@
+use std::ops::RangeInclusive;
+
 use eframe::egui::{self};
-use egui::{Pos2, Rect, Sense, Vec2};
+use egui::{Grid, Pos2, Rect, Sense, Slider, Stroke, Vec2};
 
 use egui_extras::Column;
 use indexmap::IndexMap;
-use strum::IntoEnumIterator;
 
 use crate::{
     id::LayerId,
     model::page::Page,
     scene::canvas_scene::{CanvasHistoryKind, CanvasHistoryManager},
+    theme,
     utils::EguiUiExt,
-    widget::{
-        canvas::{Canvas, CanvasState},
-        spacer::Spacer,
-    },
+    widget::canvas::{Canvas, CanvasState},
 };
 
 use super::layers::Layer;
 
+const MIN_PREVIEW_WIDTH: f32 = 150.0;
+
 #[derive(Debug, Clone, Copy)]
 struct QuickLayoutRegion {
     absolute_rect: Rect,
@@ -29,6 +30,48 @@ enum QuickLayoutFillMode {
     Margin(f32),
 }
 
+/// What every quick layout of a page is made with. Spacing is a fraction of the page size. They
+/// stay with the page so it's laid out the same way again when it's reflowed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct QuickLayoutParameters {
+    // Space between photos
+    pub gutter: f32,
+    // Space between the photos and the edge of the page
+    pub margin: f32,
+    // Shapes grids, above 0 gives more columns and below 0 more rows
+    pub bias: f32,
+    // Whether the margin starts at the page's safe margin instead of its edge
+    pub respect_safe_margin: bool,
+}
+
+impl Default for QuickLayoutParameters {
+    fn default() -> Self {
+        Self {
+            gutter: 0.02,
+            margin: 0.02,
+            bias: 0.0,
+            respect_safe_margin: false,
+        }
+    }
+}
+
+impl QuickLayoutParameters {
+    // The margin along each side as a fraction of the page's width and height
+    fn outer_margin(&self, page: &Page) -> Vec2 {
+        let mut margin = Vec2::splat(self.margin);
+        if self.respect_safe_margin {
+            margin += Vec2::splat(page.safe_margin_pixels()) / page.size_pixels();
+        }
+        margin
+    }
+
+    // Without a bias grids are as close to square as they can be
+    fn columns(&self, n: usize) -> usize {
+        let columns = ((n as f32).sqrt() * 2f32.powf(self.bias)).ceil() as usize;
+        columns.clamp(1, n.max(1))
+    }
+}
+
 #[derive(Debug, PartialEq)]
 pub struct QuickLayoutState<'a> {
     canvas_state: &'a mut CanvasState,
@@ -58,25 +101,132 @@ pub enum Layout {
 }
 
 impl Layout {
+    /// The layout used when photos are placed on a page without the user picking one
+    pub fn default_for(n: usize) -> Option<Layout> {
+        match n {
+            0 => None,
+            1 => Some(Layout::GridLayout { n, padding: 0.05 }),
+            _ => Some(Layout::CenteredWeightedGridLayout { n, padding: 0.02 }),
+        }
+    }
+
+    /// The layouts offered for a page with `n` layers
+    pub fn available(n: usize) -> Vec<Layout> {
+        if n == 0 {
+            return vec![];
+        }
+
+        let mut layouts: Vec<Layout> = vec![];
+
+        if n == 1 {
+            // Grid serves as a centering layout for a single photo
+            layouts.push(Layout::GridLayout { n, padding: 0.0 });
+            layouts.push(Layout::GridLayout { n, padding: 0.05 });
+            layouts.push(Layout::GridLayout { n, padding: 0.1 });
+            layouts.push(Layout::GridLayout { n, padding: 0.2 });
+            layouts.push(Layout::GridLayout { n, padding: 0.3 });
+        } else if n == 2 {
+            layouts.push(Layout::CenteredWeightedGridLayout { n, padding: 0.02 });
+            layouts.push(Layout::HighlightLayout { padding: 0.2 });
+            layouts.push(Layout::HighlightLayout { padding: 0.1 });
+            layouts.push(Layout::VerticalStackLayout);
+            layouts.push(Layout::HorizontalStackLayout);
+        } else if n >= 3 {
+            layouts.push(Layout::CenteredWeightedGridLayout { n, padding: 0.0 });
+            layouts.push(Layout::CenteredWeightedGridLayout { n, padding: 0.02 });
+            layouts.push(Layout::CenteredWeightedGridLayout { n, padding: 0.1 });
+
+            layouts.push(Layout::HighlightLayout { padding: 0.0 });
+            layouts.push(Layout::HighlightLayout { padding: 0.1 });
+            layouts.push(Layout::VerticalStackLayout);
+            layouts.push(Layout::HorizontalStackLayout);
+            layouts.push(Layout::ZigzagLayout);
+
+            layouts.push(Layout::GridLayout { n, padding: 0.0 });
+            layouts.push(Layout::GridLayout { n, padding: 0.025 });
+            layouts.push(Layout::GridLayout { n, padding: 0.05 });
+            layouts.push(Layout::GridLayout { n, padding: 0.1 });
+        }
+
+        layouts
+    }
+
+    /// This kind of layout made for a page with `n` layers, None if it doesn't work with that many
+    pub fn for_count(&self, n: usize) -> Option<Layout> {
+        let (layout, min_count) = match *self {
+            Layout::GridLayout { padding, .. } => (Layout::GridLayout { n, padding }, 1),
+            Layout::CenteredWeightedGridLayout { padding, .. } => {
+                (Layout::CenteredWeightedGridLayout { n, padding }, 2)
+            }
+            Layout::HighlightLayout { .. }
+            | Layout::VerticalStackLayout
+            | Layout::HorizontalStackLayout => (*self, 2),
+            Layout::ZigzagLayout => (*self, 3),
+        };
+
+        (n >= min_count).then_some(layout)
+    }
+
+    pub fn name(&self) -> String {
+        let (name, padding) = match self {
+            Layout::GridLayout { padding, .. } => ("Grid", *padding),
+            Layout::CenteredWeightedGridLayout { padding, .. } => ("Centered Grid", *padding),
+            Layout::HighlightLayout { padding } => ("Highlight", *padding),
+            Layout::VerticalStackLayout => ("Vertical Stack", 0.0),
+            Layout::HorizontalStackLayout => ("Horizontal Stack", 0.0),
+            Layout::ZigzagLayout => ("Zigzag", 0.0),
+        };
+
+        if padding > 0.0 {
+            // Rounded to a tenth of a percent, padding like 2.5% isn't a whole number
+            format!("{}, {}% Padding", name, (padding * 1000.0).round() / 10.0)
+        } else {
+            name.to_string()
+        }
+    }
+
+    /// Lays out the page's photos. A saved layout made for a different number of photos, after
+    /// photos were added or removed, is remade for the current count, or forgotten if this kind
+    /// of layout doesn't work with that many.
     pub fn apply(&self, canvas_state: &mut CanvasState) {
+        let Some(layout) = self.for_count(canvas_state.quick_layout_order.len()) else {
+            canvas_state.last_quick_layout = None;
+            return;
+        };
+
+        if layout != *self {
+            if canvas_state.last_quick_layout == Some(*self) {
+                canvas_state.last_quick_layout = Some(layout);
+            }
+            return layout.apply(canvas_state);
+        }
+
+        let parameters = canvas_state.quick_layout_parameters;
+        let gutter = parameters.gutter;
+        let margin = parameters.outer_margin(&canvas_state.page.value);
+        // Fraction of the page available inside the outer margin
+        let inner_size = Vec2::splat(1.0) - 2.0 * margin;
+
         let regions = match self {
             Layout::GridLayout { n, padding } => {
-                let grid_size = (*n as f32).sqrt().ceil() as usize;
-                let cell_size = 1.0 / grid_size as f32;
+                let columns = parameters.columns(*n);
+                let rows = n.div_ceil(columns);
+                let cell_size = ((inner_size.x - gutter * (columns - 1) as f32) / columns as f32)
+                    .min((inner_size.y - gutter * (rows - 1) as f32) / rows as f32);
                 canvas_state
                     .quick_layout_order
                     .iter()
                     .enumerate()
                     .map(|(index, layer_id)| {
                         let layer = canvas_state.layers.get(layer_id).unwrap();
-                        let row = index / grid_size;
-                        let col = index % grid_size;
+                        let row = index / columns;
+                        let col = index % columns;
                         let rect = Rect::from_min_size(
                             Pos2::new(
-                                col as f32 * cell_size + padding,
-                                row as f32 * cell_size + padding,
+                                margin.x + col as f32 * (cell_size + gutter) + padding,
+                                margin.y + row as f32 * (cell_size + gutter) + padding,
                             ),
-                            Vec2::new(cell_size - 2.0 * padding, cell_size - 2.0 * padding),
+                            Vec2::splat((cell_size - 2.0 * padding).max(0.0)),
                         );
                         QuickLayoutRegion {
                             absolute_rect: QuickLayout::fractional_rect_for_layer_in_page(
@@ -90,25 +240,24 @@ impl Layout {
                     .collect::<Vec<_>>()
             }
             Layout::CenteredWeightedGridLayout { n, padding } => {
-                let grid_size = (*n as f32).sqrt().ceil() as usize;
-                let fixed_spacing = 0.02; // 2% of page size between images
-                let rows = ((n + grid_size - 1) / grid_size) as f32;
+                let grid_size = parameters.columns(*n);
+                let rows = n.div_ceil(grid_size) as f32;
 
                 // Calculate cell sizes based on both width and height constraints
-                let inner_width = 1.0 - (2.0 * padding);
-                let inner_height = 1.0 - (2.0 * padding);
+                let inner_width = inner_size.x - (2.0 * padding);
+                let inner_height = inner_size.y - (2.0 * padding);
 
                 let width_based_cell_size =
-                    (inner_width - (fixed_spacing * (grid_size - 1) as f32)) / grid_size as f32;
-                let height_based_cell_size = (inner_height - (fixed_spacing * (rows - 1.0))) / rows;
+                    (inner_width - (gutter * (grid_size - 1) as f32)) / grid_size as f32;
+                let height_based_cell_size = (inner_height - (gutter * (rows - 1.0))) / rows;
 
                 // Use the smaller cell size to maintain equal spacing
                 let cell_size = width_based_cell_size.min(height_based_cell_size);
 
                 // Recalculate total dimensions with final cell size
                 let total_width =
-                    (cell_size * grid_size as f32) + (fixed_spacing * (grid_size - 1) as f32);
-                let total_height = (cell_size * rows) + (fixed_spacing * (rows - 1.0));
+                    (cell_size * grid_size as f32) + (gutter * (grid_size - 1) as f32);
+                let total_height = (cell_size * rows) + (gutter * (rows - 1.0));
 
                 // Center the grid
                 let x_offset = (1.0 - total_width) / 2.0;
@@ -123,8 +272,8 @@ impl Layout {
                         let row = index / grid_size;
                         let col = index % grid_size;
 
-                        let x = x_offset + (col as f32 * (cell_size + fixed_spacing));
-                        let y = y_offset + (row as f32 * (cell_size + fixed_spacing));
+                        let x = x_offset + (col as f32 * (cell_size + gutter));
+                        let y = y_offset + (row as f32 * (cell_size + gutter));
 
                         let rect =
                             Rect::from_min_size(Pos2::new(x, y), Vec2::new(cell_size, cell_size));
@@ -144,6 +293,7 @@ impl Layout {
                 let n = canvas_state.quick_layout_order.len();
                 let mut regions = vec![];
                 let highlight_layer_index = 0;
+                let highlight_width = inner_size.x * 0.6 - gutter / 2.0;
 
                 let highlight_region = QuickLayoutRegion {
                     absolute_rect: QuickLayout::fractional_rect_for_layer_in_page(
@@ -152,7 +302,10 @@ impl Layout {
                             .get(&canvas_state.quick_layout_order[highlight_layer_index])
                             .unwrap(),
                         &canvas_state.page.value,
-                        Rect::from_min_size(Pos2::ZERO, Vec2::new(0.6, 1.0)),
+                        Rect::from_min_size(
+                            margin.to_pos2(),
+                            Vec2::new(highlight_width, inner_size.y),
+                        ),
                         QuickLayoutFillMode::Margin(*padding),
                     ),
                 };
@@ -162,7 +315,10 @@ impl Layout {
                 let min_y = highlight_rect.min.y / &canvas_state.page.value.size_pixels().y;
                 let max_y = highlight_rect.max.y / &canvas_state.page.value.size_pixels().y;
 
-                let photo_height = (max_y - min_y) / (n - 1) as f32;
+                let side_x = margin.x + highlight_width + gutter;
+                let side_width = inner_size.x - highlight_width - gutter;
+                let photo_height = ((max_y - min_y) - gutter * n.saturating_sub(2) as f32).max(0.0)
+                    / n.saturating_sub(1).max(1) as f32;
                 let mut non_highlight_count = 0;
 
                 for (i, layer_id) in canvas_state.quick_layout_order.iter().enumerate() {
@@ -175,8 +331,11 @@ impl Layout {
                             canvas_state.layers.get(layer_id).unwrap(),
                             &canvas_state.page.value,
                             Rect::from_min_size(
-                                Pos2::new(0.6, min_y + non_highlight_count as f32 * photo_height),
-                                Vec2::new(0.4, photo_height),
+                                Pos2::new(
+                                    side_x,
+                                    min_y + non_highlight_count as f32 * (photo_height + gutter),
+                                ),
+                                Vec2::new(side_width, photo_height),
                             ),
                             QuickLayoutFillMode::Margin(*padding),
                         ),
@@ -189,8 +348,7 @@ impl Layout {
             }
             Layout::VerticalStackLayout => {
                 let n = canvas_state.quick_layout_order.len();
-                let margin = 0.02;
-                let available_height = 1.0 - margin * (n as f32 + 1.0);
+                let available_height = inner_size.y - gutter * (n as f32 - 1.0);
                 let cell_height = available_height / n as f32;
                 canvas_state
                     .quick_layout_order
@@ -198,14 +356,14 @@ impl Layout {
                     .enumerate()
                     .map(|(i, layer_id)| {
                         let layer = canvas_state.layers.get(layer_id).unwrap();
-                        let y = margin * (i as f32 + 1.0) + cell_height * i as f32;
+                        let y = margin.y + (cell_height + gutter) * i as f32;
                         QuickLayoutRegion {
                             absolute_rect: QuickLayout::fractional_rect_for_layer_in_page(
                                 layer,
                                 &canvas_state.page.value,
                                 Rect::from_min_size(
-                                    Pos2::new(margin, y),
-                                    Vec2::new(1.0 - 2.0 * margin, cell_height),
+                                    Pos2::new(margin.x, y),
+                                    Vec2::new(inner_size.x, cell_height),
                                 ),
                                 QuickLayoutFillMode::Fill,
                             ),
@@ -215,8 +373,7 @@ impl Layout {
             }
             Layout::HorizontalStackLayout => {
                 let n = canvas_state.quick_layout_order.len();
-                let margin = 0.02;
-                let available_width = 1.0 - margin * (n as f32 + 1.0);
+                let available_width = inner_size.x - gutter * (n as f32 - 1.0);
                 let cell_width = available_width / n as f32;
                 canvas_state
                     .quick_layout_order
@@ -224,14 +381,14 @@ impl Layout {
                     .enumerate()
                     .map(|(i, layer_id)| {
                         let layer = canvas_state.layers.get(layer_id).unwrap();
-                        let x = margin * (i as f32 + 1.0) + cell_width * i as f32;
+                        let x = margin.x + (cell_width + gutter) * i as f32;
                         QuickLayoutRegion {
                             absolute_rect: QuickLayout::fractional_rect_for_layer_in_page(
                                 layer,
                                 &canvas_state.page.value,
                                 Rect::from_min_size(
-                                    Pos2::new(x, margin),
-                                    Vec2::new(cell_width, 1.0 - 2.0 * margin),
+                                    Pos2::new(x, margin.y),
+                                    Vec2::new(cell_width, inner_size.y),
                                 ),
                                 QuickLayoutFillMode::Fill,
                             ),
@@ -240,7 +397,8 @@ impl Layout {
                     .collect::<Vec<_>>()
             }
             Layout::ZigzagLayout => {
-                let size = 0.3;
+                // Positions are relative to the area inside the margin
+                let size = inner_size * 0.3;
                 let x_positions = [0.1, 0.6];
                 canvas_state
                     .quick_layout_order
@@ -248,13 +406,13 @@ impl Layout {
                     .enumerate()
                     .map(|(i, layer_id)| {
                         let layer = canvas_state.layers.get(layer_id).unwrap();
-                        let x = x_positions[i % 2];
-                        let y = 0.1 + 0.2 * i as f32;
+                        let x = margin.x + x_positions[i % 2] * inner_size.x;
+                        let y = margin.y + (0.1 + 0.2 * i as f32) * inner_size.y;
                         QuickLayoutRegion {
                             absolute_rect: QuickLayout::fractional_rect_for_layer_in_page(
                                 layer,
                                 &canvas_state.page.value,
-                                Rect::from_min_size(Pos2::new(x, y), Vec2::new(size, size)),
+                                Rect::from_min_size(Pos2::new(x, y), size),
                                 QuickLayoutFillMode::Fill,
                             ),
                         }
@@ -301,45 +459,46 @@ impl<'a> QuickLayout<'a> {
             return;
         }
 
+        self.parameters_ui(ui);
+
+        ui.separator();
+
         ui.set_clip_rect(ui.available_rect_before_wrap());
 
-        let available_width = ui.available_width();
+        let available_width = ui.available_width() - 10.0;
         let available_height = ui.available_height();
-        let column_width: f32 = ui.available_width();
-        let row_height = column_width;
-        let num_columns: usize = (available_width / column_width).floor() as usize;
+        let item_spacing = ui.spacing().item_spacing.x;
 
-        let spacer_width = (available_width
-            - ((column_width + ui.spacing().item_spacing.x) * num_columns as f32)
-            - 10.0
-            - ui.spacing().item_spacing.x)
-            .max(0.0);
+        let num_columns =
+            ((available_width / (MIN_PREVIEW_WIDTH + item_spacing)).floor() as usize).max(1);
+        let column_width = available_width / num_columns as f32 - item_spacing;
+        // Match the page shape so the previews don't have empty space around them
+        let row_height =
+            (column_width / self.state.canvas_state.page.aspect_ratio()).min(column_width * 1.5);
 
-        let num_rows = available_layouts.len();
+        let num_rows = available_layouts.len().div_ceil(num_columns);
 
         let mut selected_layout: Option<Layout> = None;
 
         egui_extras::TableBuilder::new(ui)
             .min_scrolled_height(available_height)
             .columns(Column::exact(column_width), num_columns)
-            .column(Column::exact(spacer_width))
             .body(|body| {
                 body.rows(row_height, num_rows, |mut row| {
-                    let offest = row.index() * num_columns;
+                    let offset = row.index() * num_columns;
                     for i in 0..num_columns {
-                        if offest + i >= num_rows {
+                        let Some(layout) = available_layouts.get(offset + i) else {
                             break;
-                        }
-
-                        let _index = offest + i;
-                        let layout = available_layouts.get(offest + i).unwrap();
+                        };
 
                         let mut canvas_state = self.state.canvas_state.clone_with_new_widget_ids();
 
                         layout.apply(&mut canvas_state);
 
+                        let is_current = self.state.canvas_state.last_quick_layout == Some(*layout);
+
                         row.col(|ui| {
-                            let page_rect = ui.max_rect().shrink2(Vec2::new(20.0, 0.0));
+                            let page_rect = ui.max_rect().shrink(5.0);
                             Canvas::new(
                                 &mut canvas_state,
                                 page_rect,
@@ -349,15 +508,25 @@ impl<'a> QuickLayout<'a> {
 
                             let click_response = ui.allocate_rect(page_rect, Sense::click());
 
+                            if is_current {
+                                ui.painter().rect_stroke(
+                                    page_rect,
+                                    4.0,
+                                    Stroke::new(3.0, theme::color::focused(ui.visuals())),
+                                );
+                            } else if click_response.hovered() {
+                                ui.painter().rect_stroke(
+                                    page_rect,
+                                    4.0,
+                                    Stroke::new(1.0, ui.visuals().widgets.hovered.fg_stroke.color),
+                                );
+                            }
+
                             if click_response.clicked() {
-                                selected_layout = Some(layout.clone());
+                                selected_layout = Some(*layout);
                             }
                         });
                     }
-
-                    row.col(|ui| {
-                        ui.add(Spacer::new(spacer_width, row_height));
-                    });
                 })
             });
 
@@ -370,46 +539,78 @@ impl<'a> QuickLayout<'a> {
         }
     }
 
-    fn available_layouts(&self) -> Vec<Layout> {
-        let n = self.state.canvas_state.quick_layout_order.len();
-
-        if n == 0 {
-            return vec![];
-        }
-
-        let mut layouts: Vec<Layout> = vec![];
+    // The previews follow the parameters as they're adjusted and so does the page if it's using
+    // one of the layouts
+    fn parameters_ui(&mut self, ui: &mut egui::Ui) {
+        let percent = |value: f64, _: RangeInclusive<usize>| format!("{:.0}%", value * 100.0);
+        // Columns grow by a factor of 2^bias, shown as how many more columns or rows there are
+        let bias_text = |value: f64, _: RangeInclusive<usize>| {
+            let more = (2f64.powf(value.abs()) - 1.0) * 100.0;
+            match value {
+                value if value > 0.0 => format!("{:.0}% more columns", more),
+                value if value < 0.0 => format!("{:.0}% more rows", more),
+                _ => "Even".to_string(),
+            }
+        };
 
-        if n == 1 {
-            // Grid serves as a centering layout for a single photo
-            layouts.push(Layout::GridLayout { n, padding: 0.0 });
-            layouts.push(Layout::GridLayout { n, padding: 0.05 });
-            layouts.push(Layout::GridLayout { n, padding: 0.1 });
-            layouts.push(Layout::GridLayout { n, padding: 0.2 });
-            layouts.push(Layout::GridLayout { n, padding: 0.3 });
-        } else if n == 2 {
-            layouts.push(Layout::CenteredWeightedGridLayout { n, padding: 0.02 });
-            layouts.push(Layout::HighlightLayout { padding: 0.2 });
-            layouts.push(Layout::HighlightLayout { padding: 0.1 });
-            layouts.push(Layout::VerticalStackLayout);
-            layouts.push(Layout::HorizontalStackLayout);
-        } else if n >= 3 {
-            layouts.push(Layout::CenteredWeightedGridLayout { n, padding: 0.0 });
-            layouts.push(Layout::CenteredWeightedGridLayout { n, padding: 0.02 });
-            layouts.push(Layout::CenteredWeightedGridLayout { n, padding: 0.1 });
+        let parameters = &mut self.state.canvas_state.quick_layout_parameters;
+
+        let response = Grid::new("quick_layout_parameters")
+            .num_columns(2)
+            .spacing([20.0, 5.0])
+            .show(ui, |ui| {
+                ui.label("Gutter:");
+                let gutter = ui.add(
+                    Slider::new(&mut parameters.gutter, 0.0..=0.1).custom_formatter(percent),
+                );
+                ui.end_row();
+
+                ui.label("Margin:");
+                let margin = ui.add(
+                    Slider::new(&mut parameters.margin, 0.0..=0.15).custom_formatter(percent),
+                );
+                ui.end_row();
+
+                ui.label("Grid Shape:");
+                let bias = ui
+                    .add(
+                        Slider::new(&mut parameters.bias, -1.0..=1.0)
+                            .step_by(0.1)
+                            .custom_formatter(bias_text),
+                    )
+                    .on_hover_text("Favor more columns or more rows in grid layouts");
+                ui.end_row();
+
+                ui.label("");
+                let safe_margin = ui
+                    .checkbox(&mut parameters.respect_safe_margin, "Inside Safe Margin")
+                    .on_hover_text(
+                        "Start the margin at the page's safe margin so photos stay clear of the trim",
+                    );
+                ui.end_row();
+
+                gutter | margin | bias | safe_margin
+            })
+            .inner;
+
+        let Some(layout) = self.state.canvas_state.last_quick_layout else {
+            return;
+        };
 
-            layouts.push(Layout::HighlightLayout { padding: 0.0 });
-            layouts.push(Layout::HighlightLayout { padding: 0.1 });
-            layouts.push(Layout::VerticalStackLayout);
-            layouts.push(Layout::HorizontalStackLayout);
-            layouts.push(Layout::ZigzagLayout);
+        if response.changed() {
+            layout.apply(self.state.canvas_state);
+        }
 
-            layouts.push(Layout::GridLayout { n, padding: 0.0 });
-            layouts.push(Layout::GridLayout { n, padding: 0.025 });
-            layouts.push(Layout::GridLayout { n, padding: 0.05 });
-            layouts.push(Layout::GridLayout { n, padding: 0.1 });
+        // Dragging a slider is one change
+        if (response.changed() && !response.dragged()) || response.drag_stopped() {
+            self.state
+                .history_manager
+                .save_history(CanvasHistoryKind::QuickLayout, self.state.canvas_state);
         }
+    }
 
-        layouts
+    fn available_layouts(&self) -> Vec<Layout> {
+        Layout::available(self.state.canvas_state.quick_layout_order.len())
     }
 
     fn fractional_rect_for_layer_in_page(
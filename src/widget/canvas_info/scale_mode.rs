@@ -3,16 +3,30 @@ use egui::Vec2;
 
 use strum::IntoEnumIterator;
 
-use crate::model;
+use crate::model::{self, scale_mode::PhotoAlignment};
+
+// The alignment presets shown in a 3x3 grid, by row
+const ALIGNMENT_PRESETS: [[(f32, f32, &str); 3]; 3] = [
+    [(0.0, 0.0, "↖"), (0.5, 0.0, "⬆"), (1.0, 0.0, "↗")],
+    [(0.0, 0.5, "⬅"), (0.5, 0.5, "⏺"), (1.0, 0.5, "➡")],
+    [(0.0, 1.0, "↙"), (0.5, 1.0, "⬇"), (1.0, 1.0, "↘")],
+];
 
 #[derive(Debug, PartialEq)]
 pub struct ScaleModeState<'a> {
     scale_mode: &'a mut model::scale_mode::ScaleMode,
+    alignment: &'a mut PhotoAlignment,
 }
 
-impl ScaleModeState<'_> {
-    pub fn new(scale_mode: &mut model::scale_mode::ScaleMode) -> ScaleModeState<'_> {
-        ScaleModeState { scale_mode }
+impl<'a> ScaleModeState<'a> {
+    pub fn new(
+        scale_mode: &'a mut model::scale_mode::ScaleMode,
+        alignment: &'a mut PhotoAlignment,
+    ) -> ScaleModeState<'a> {
+        ScaleModeState {
+            scale_mode,
+            alignment,
+        }
     }
 }
 
@@ -26,23 +40,53 @@ impl<'a> ScaleMode<'a> {
         ScaleMode { state }
     }
 
-    pub fn show(&mut self, ui: &mut egui::Ui) {
+    /// Returns true if the scale mode or alignment changed
+    pub fn show(&mut self, ui: &mut egui::Ui) -> bool {
+        let mut changed = false;
+
         ui.vertical(|ui| {
             ui.spacing_mut().item_spacing = Vec2::new(10.0, 5.0);
 
-            ui.heading("Scale Mode");
-
             ui.horizontal(|ui| {
                 for scale_mode in model::scale_mode::ScaleMode::iter() {
                     let is_selected = *self.state.scale_mode == scale_mode;
                     if ui
                         .selectable_label(is_selected, format!("{}", scale_mode))
                         .clicked()
+                        && !is_selected
                     {
                         *self.state.scale_mode = scale_mode;
+                        changed = true;
                     }
                 }
             });
+
+            ui.label("Alignment")
+                .on_hover_text("The part of the photo kept in view. Drag the photo to fine tune.");
+
+            ui.spacing_mut().item_spacing = Vec2::splat(2.0);
+
+            for row in ALIGNMENT_PRESETS {
+                ui.horizontal(|ui| {
+                    for (x, y, icon) in row {
+                        let preset = PhotoAlignment::new(x, y);
+                        let is_selected = *self.state.alignment == preset;
+                        if ui
+                            .add_sized(
+                                Vec2::splat(24.0),
+                                egui::SelectableLabel::new(is_selected, icon),
+                            )
+                            .clicked()
+                            && !is_selected
+                        {
+                            *self.state.alignment = preset;
+                            changed = true;
+                        }
+                    }
+                });
+            }
         });
+
+        changed
     }
 }
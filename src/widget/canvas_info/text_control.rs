@@ -1,18 +1,41 @@
+use std::{collections::HashSet, ops::Range};
+
 use eframe::{
     egui::{self, RichText, Ui},
-    epaint::{FontId, Vec2},
+    epaint::{FontFamily, FontId, Rect, Vec2},
+};
+use egui::{
+    text::{LayoutJob, TextFormat},
+    ComboBox, DragValue, Grid, Stroke, TextEdit, TextStyle,
 };
-use egui::ComboBox;
 use strum::IntoEnumIterator;
 
-use crate::utils::EditableValueTextEdit;
+use crate::{
+    book_metadata::TextVariable,
+    book_theme::{ThemeColorRole, ThemeFontRole},
+    dependencies::{Dependency, Singleton, SingletonFor},
+    font_manager::FontManager,
+    glyph_text,
+    project_settings::ProjectSettingsManager,
+    spell_check::SpellChecker,
+    text_style::TextStylePreset,
+    theme,
+    typography::{HyphenationLanguage, Typography},
+    utils::EditableValueTextEdit,
+};
 
 use super::layers::{
-    Layer,
-    LayerContent::{Photo, TemplatePhoto, TemplateText, Text},
-    TextHorizontalAlignment, TextVerticalAlignment,
+    CanvasText, Layer,
+    LayerContent::{Decoration, Path, Photo, TemplatePhoto, TemplateText, Text},
+    TextHorizontalAlignment, TextOrientation, TextSpacing, TextVerticalAlignment,
 };
 
+pub enum TextControlResponse {
+    None,
+    // The named style was redefined from the layer, the layers following it need restyling
+    RedefineStyle(String),
+}
+
 pub struct TextControlState<'a> {
     layer: &'a mut Layer,
 }
@@ -32,10 +55,12 @@ impl<'a> TextControl<'a> {
         Self { state }
     }
 
-    pub fn show(&mut self, ui: &mut Ui) {
+    pub fn show(&mut self, ui: &mut Ui) -> TextControlResponse {
+        let mut response = TextControlResponse::None;
+
         let _response: egui::InnerResponse<()> =
             ui.allocate_ui(ui.available_size(), |ui| match self.state.layer.content {
-                Photo(_) | TemplatePhoto { .. } => {
+                Photo(_) | TemplatePhoto { .. } | Decoration(_) | Path(_) => {
                     ui.label("No text layer selected");
                 }
                 Text(ref mut text_content)
@@ -49,7 +74,15 @@ impl<'a> TextControl<'a> {
                         ui.spacing_mut().item_spacing = Vec2::new(10.0, 5.0);
                         ui.style_mut().spacing.text_edit_width = 80.0;
 
-                        ui.label(RichText::new("Text").heading());
+                        ui.horizontal(|ui| {
+                            let text = &mut self.state.layer.content;
+                            match text {
+                                Text(text) | TemplateText { region: _, text } => {
+                                    response = Self::style_controls(ui, text);
+                                }
+                                _ => (),
+                            }
+                        });
 
                         ui.horizontal(|ui| {
                             let text = &mut self.state.layer.content;
@@ -57,13 +90,36 @@ impl<'a> TextControl<'a> {
                                 Text(text) | TemplateText { region: _, text } => {
                                     let mut new_text = text.text.clone();
                                     ui.label("Text:");
-                                    ui.text_edit_singleline(&mut new_text);
+                                    Self::spell_checked_text_edit(ui, &mut new_text);
+                                    Self::variable_menu(ui, &mut new_text);
                                     text.text = new_text;
                                 }
                                 _ => (),
                             }
                         });
 
+                        if let Text(text) | TemplateText { text, .. } = &self.state.layer.content {
+                            let missing_script = Dependency::<FontManager>::get()
+                                .with_lock(|font_manager| font_manager.font_definitions.clone())
+                                .and_then(|definitions| {
+                                    glyph_text::missing_script(
+                                        &definitions,
+                                        &text.font_id.family,
+                                        &text.text,
+                                    )
+                                });
+
+                            if let Some(script) = missing_script {
+                                ui.label(
+                                    RichText::new(format!(
+                                        "No installed font has {script} letters, install one \
+                                         such as Noto Sans {script}"
+                                    ))
+                                    .color(theme::color::WARNING),
+                                );
+                            }
+                        }
+
                         ui.horizontal(|ui| {
                             let text = &mut self.state.layer.content;
                             match text {
@@ -85,26 +141,63 @@ impl<'a> TextControl<'a> {
                                 Text(text) | TemplateText { region: _, text } => {
                                     ui.label("Font Family:");
 
+                                    let previous_font_id = text.font_id.clone();
+
                                     ComboBox::from_label("Font Family")
                                         .selected_text(format!("{}", text.font_id.family))
                                         .show_ui(ui, |ui| {
-                                            let fonts = ui.ctx().fonts(|fonts| {
-                                                fonts
-                                                    .families()
-                                                    .iter()
-                                                    .map(|family| FontId::new(20.0, family.clone()))
-                                                    .collect::<Vec<FontId>>()
-                                            });
-
-                                            for font_id in &fonts {
+                                            let fonts = Self::font_choices(ui);
+
+                                            for (font_id, loaded) in &fonts {
+                                                let mut label =
+                                                    RichText::new(font_id.family.to_string());
+                                                if *loaded {
+                                                    label = label.font(font_id.clone());
+                                                }
                                                 ui.selectable_value(
                                                     &mut text.font_id,
                                                     font_id.clone(),
-                                                    RichText::new(font_id.family.to_string())
-                                                        .font(font_id.clone()),
+                                                    label,
                                                 );
                                             }
                                         });
+
+                                    // Picking a specific font detaches the layer from the theme
+                                    if text.font_id != previous_font_id {
+                                        text.font_role = None;
+                                    }
+                                }
+                                _ => (),
+                            }
+                        });
+
+                        ui.horizontal(|ui| {
+                            let text = &mut self.state.layer.content;
+                            match text {
+                                Text(text) | TemplateText { region: _, text } => {
+                                    let mut font_role = text.font_role;
+
+                                    ComboBox::from_label("Theme Font")
+                                        .selected_text(Self::role_name(font_role))
+                                        .show_ui(ui, |ui| {
+                                            ui.selectable_value(
+                                                &mut font_role,
+                                                None,
+                                                Self::role_name::<ThemeFontRole>(None),
+                                            );
+                                            for role in ThemeFontRole::iter() {
+                                                ui.selectable_value(
+                                                    &mut font_role,
+                                                    Some(role),
+                                                    role.to_string(),
+                                                );
+                                            }
+                                        });
+
+                                    if font_role != text.font_role {
+                                        text.font_role = font_role;
+                                        Self::apply_theme(text);
+                                    }
                                 }
                                 _ => (),
                             }
@@ -116,7 +209,41 @@ impl<'a> TextControl<'a> {
                                 Text(text) | TemplateText { region: _, text } => {
                                     ui.label("Color:");
 
-                                    ui.color_edit_button_srgba(&mut text.color);
+                                    if ui.color_edit_button_srgba(&mut text.color).changed() {
+                                        text.color_role = None;
+                                    }
+                                }
+                                _ => (),
+                            }
+                        });
+
+                        ui.horizontal(|ui| {
+                            let text = &mut self.state.layer.content;
+                            match text {
+                                Text(text) | TemplateText { region: _, text } => {
+                                    let mut color_role = text.color_role;
+
+                                    ComboBox::from_label("Theme Color")
+                                        .selected_text(Self::role_name(color_role))
+                                        .show_ui(ui, |ui| {
+                                            ui.selectable_value(
+                                                &mut color_role,
+                                                None,
+                                                Self::role_name::<ThemeColorRole>(None),
+                                            );
+                                            for role in ThemeColorRole::iter() {
+                                                ui.selectable_value(
+                                                    &mut color_role,
+                                                    Some(role),
+                                                    role.to_string(),
+                                                );
+                                            }
+                                        });
+
+                                    if color_role != text.color_role {
+                                        text.color_role = color_role;
+                                        Self::apply_theme(text);
+                                    }
                                 }
                                 _ => (),
                             }
@@ -169,8 +296,365 @@ impl<'a> TextControl<'a> {
                                 _ => (),
                             }
                         });
+
+                        ui.horizontal(|ui| {
+                            Self::orientation_control(ui, self.state.layer);
+                        });
+
+                        ui.label(RichText::new("Spacing").strong());
+
+                        let text = &mut self.state.layer.content;
+                        match text {
+                            Text(text) | TemplateText { region: _, text } => {
+                                Self::spacing_controls(ui, &mut text.spacing);
+                            }
+                            _ => (),
+                        }
+
+                        ui.label(RichText::new("Typography").strong());
+
+                        let text = &mut self.state.layer.content;
+                        match text {
+                            Text(text) | TemplateText { region: _, text } => {
+                                Self::typography_controls(ui, &mut text.typography);
+                            }
+                            _ => (),
+                        }
                     });
                 }
             });
+
+        response
+    }
+
+    // Turning free text between horizontal and sideways swaps the layer's width and height so the
+    // lines keep the length they had. Template text stays in its region.
+    fn orientation_control(ui: &mut Ui, layer: &mut Layer) {
+        let (Text(text) | TemplateText { text, .. }) = &mut layer.content else {
+            return;
+        };
+
+        let mut orientation = text.orientation;
+
+        ComboBox::from_label("Orientation")
+            .selected_text(orientation.to_string())
+            .show_ui(ui, |ui| {
+                for option in TextOrientation::iter() {
+                    let hint = match option {
+                        TextOrientation::Horizontal => "Lines run across the layer",
+                        TextOrientation::Rotated90 => "Reads top to bottom, for book spines",
+                        TextOrientation::Rotated270 => {
+                            "Reads bottom to top, for captions up the side of a photo"
+                        }
+                        TextOrientation::Vertical => {
+                            "Upright characters in columns from right to left, for CJK text"
+                        }
+                    };
+
+                    ui.selectable_value(&mut orientation, option, option.to_string())
+                        .on_hover_text(hint);
+                }
+            });
+
+        if orientation == text.orientation {
+            return;
+        }
+
+        let swap = orientation.is_sideways() != text.orientation.is_sideways();
+        text.orientation = orientation;
+
+        if swap && matches!(layer.content, Text(_)) {
+            let rect = layer.transform_state.rect;
+            layer.transform_state.rect =
+                Rect::from_center_size(rect.center(), Vec2::new(rect.height(), rect.width()));
+            layer.transform_edit_state.update(&layer.transform_state);
+        }
+    }
+
+    // Picks the project text style the layer follows and creates or redefines styles from it
+    fn style_controls(ui: &mut Ui, text: &mut CanvasText) -> TextControlResponse {
+        let mut response = TextControlResponse::None;
+
+        let project_settings: Singleton<ProjectSettingsManager> = Dependency::get();
+        let (styles, theme) = project_settings.with_lock(|manager| {
+            (
+                manager.project_settings.text_styles.clone(),
+                manager.project_settings.theme.clone(),
+            )
+        });
+
+        let current = text
+            .style
+            .as_ref()
+            .and_then(|name| styles.iter().find(|style| &style.name == name));
+        let mut selected = current.map(|style| style.name.clone());
+
+        ComboBox::from_label("Style")
+            .selected_text(selected.as_deref().unwrap_or("None"))
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut selected, None, "None");
+                for style in &styles {
+                    ui.selectable_value(
+                        &mut selected,
+                        Some(style.name.clone()),
+                        style.name.as_str(),
+                    );
+                }
+            });
+
+        if selected.as_ref() != current.map(|style| &style.name) {
+            match selected
+                .as_ref()
+                .and_then(|name| styles.iter().find(|style| &style.name == name))
+            {
+                Some(style) => style.apply_to_text(text, &theme),
+                None => text.style = None,
+            }
+        } else if let Some(style) = current.filter(|style| !style.matches(text)) {
+            ui.label(RichText::new("Modified").weak());
+
+            if ui
+                .button("Redefine")
+                .on_hover_text(
+                    "Update the style to match this text and restyle every layer using it",
+                )
+                .clicked()
+            {
+                project_settings.with_lock_mut(|manager| {
+                    manager
+                        .project_settings
+                        .save_text_style(TextStylePreset::from_text(style.name.clone(), text));
+                });
+                response = TextControlResponse::RedefineStyle(style.name.clone());
+            }
+        }
+
+        ui.menu_button("New Style", |ui| {
+            let name_id = ui.id().with("new_text_style_name");
+            let mut name: String = ui.data(|data| data.get_temp(name_id)).unwrap_or_default();
+
+            ui.add(TextEdit::singleline(&mut name).hint_text("Style name"));
+
+            let trimmed = name.trim();
+            let exists = styles.iter().any(|style| style.name == trimmed);
+            let create = ui
+                .add_enabled(!trimmed.is_empty() && !exists, egui::Button::new("Create"))
+                .on_disabled_hover_text(if exists {
+                    "A style with this name already exists"
+                } else {
+                    "Name the style"
+                })
+                .clicked();
+
+            if create {
+                let style = TextStylePreset::from_text(trimmed.to_string(), text);
+                text.style = Some(style.name.clone());
+                project_settings
+                    .with_lock_mut(|manager| manager.project_settings.save_text_style(style));
+                ui.data_mut(|data| data.remove::<String>(name_id));
+                ui.close_menu();
+            } else {
+                ui.data_mut(|data| data.insert_temp(name_id, name));
+            }
+        });
+
+        response
+    }
+
+    fn spacing_controls(ui: &mut Ui, spacing: &mut TextSpacing) {
+        Grid::new("text_spacing")
+            .num_columns(2)
+            .spacing([10.0, 5.0])
+            .show(ui, |ui| {
+                ui.label("Line Height:");
+                ui.add(
+                    DragValue::new(&mut spacing.line_height)
+                        .range(0.5..=3.0)
+                        .speed(0.01)
+                        .suffix("×"),
+                );
+                ui.end_row();
+
+                ui.label("Letter Spacing:");
+                ui.add(
+                    DragValue::new(&mut spacing.letter_spacing)
+                        .range(-0.2..=1.0)
+                        .speed(0.005)
+                        .suffix(" em"),
+                );
+                ui.end_row();
+
+                ui.label("Paragraph Spacing:");
+                ui.add(
+                    DragValue::new(&mut spacing.paragraph_spacing)
+                        .range(0.0..=5.0)
+                        .speed(0.01)
+                        .suffix(" em"),
+                );
+                ui.end_row();
+
+                ui.label("First Line Indent:");
+                ui.add(
+                    DragValue::new(&mut spacing.first_line_indent)
+                        .range(0.0..=10.0)
+                        .speed(0.05)
+                        .suffix(" em"),
+                );
+                ui.end_row();
+            });
+    }
+
+    // Every installed family along with whether egui has it yet. Families that haven't been
+    // loaded are listed in the default font, egui panics on families it doesn't know.
+    fn font_choices(ui: &Ui) -> Vec<(FontId, bool)> {
+        let loaded = ui.ctx().fonts(|fonts| fonts.families());
+        let installed =
+            Dependency::<FontManager>::get().with_lock(|font_manager| font_manager.family_names());
+
+        [FontFamily::Proportional, FontFamily::Monospace]
+            .into_iter()
+            .chain(
+                installed
+                    .into_iter()
+                    .map(|name| FontFamily::Name(name.into())),
+            )
+            .map(|family| {
+                let is_loaded = loaded.contains(&family);
+                (FontId::new(20.0, family), is_loaded)
+            })
+            .collect()
+    }
+
+    fn typography_controls(ui: &mut Ui, typography: &mut Typography) {
+        ComboBox::from_label("Hyphenation")
+            .selected_text(
+                typography
+                    .hyphenation
+                    .map_or("Off".to_string(), |language| language.to_string()),
+            )
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut typography.hyphenation, None, "Off");
+                for language in HyphenationLanguage::iter() {
+                    ui.selectable_value(
+                        &mut typography.hyphenation,
+                        Some(language),
+                        language.to_string(),
+                    );
+                }
+            });
+
+        ui.checkbox(&mut typography.avoid_widows, "Avoid Widows")
+            .on_hover_text(
+                "Rebreak paragraphs so their last line isn't a single word or the end of a \
+                 hyphenated word",
+            );
+
+        ui.checkbox(&mut typography.tie_short_words, "Keep Short Words Together")
+            .on_hover_text(
+                "Use non-breaking spaces after short words and numbers so they aren't left at \
+                 the end of a line",
+            );
+    }
+
+    fn role_name<T: ToString>(role: Option<T>) -> String {
+        match role {
+            Some(role) => role.to_string(),
+            None => "Custom".to_string(),
+        }
+    }
+
+    fn apply_theme(text: &mut CanvasText) {
+        Dependency::<ProjectSettingsManager>::get()
+            .with_lock(|manager| manager.project_settings.theme.apply_to_text(text));
+    }
+
+    // Adds a book metadata variable to the end of the text
+    fn variable_menu(ui: &mut Ui, text: &mut String) {
+        ui.menu_button("{ }", |ui| {
+            for variable in TextVariable::iter() {
+                if ui.button(variable.to_string()).clicked() {
+                    text.push_str(variable.token());
+                    ui.close_menu();
+                }
+            }
+        })
+        .response
+        .on_hover_text(
+            "Insert a variable set in Project Settings > Book Details, or the page's section from the Outline",
+        );
+    }
+
+    fn spell_checked_text_edit(ui: &mut Ui, text: &mut String) {
+        let spell_checker: Singleton<SpellChecker> = Dependency::get();
+
+        let mut layouter = |ui: &Ui, string: &str, wrap_width: f32| {
+            let font_id = TextStyle::Body.resolve(ui.style());
+            let color = ui.visuals().text_color();
+            let misspelled_format = TextFormat {
+                underline: Stroke::new(1.0, theme::color::MISSPELLED),
+                ..TextFormat::simple(font_id.clone(), color)
+            };
+
+            let misspelled =
+                spell_checker.with_lock_mut(|checker| checker.misspelled_ranges(string));
+
+            let mut job = LayoutJob::default();
+            let mut cursor = 0;
+            for range in misspelled {
+                job.append(
+                    &string[cursor..range.start],
+                    0.0,
+                    TextFormat::simple(font_id.clone(), color),
+                );
+                job.append(&string[range.clone()], 0.0, misspelled_format.clone());
+                cursor = range.end;
+            }
+            job.append(&string[cursor..], 0.0, TextFormat::simple(font_id, color));
+            job.wrap.max_width = wrap_width;
+
+            ui.fonts(|fonts| fonts.layout_job(job))
+        };
+
+        let response = ui.add(TextEdit::singleline(text).layouter(&mut layouter));
+
+        let mut misspelled: Vec<(Range<usize>, String)> = spell_checker
+            .with_lock_mut(|checker| checker.misspelled_ranges(text))
+            .into_iter()
+            .map(|range| (range.clone(), text[range].to_string()))
+            .collect();
+        // A word misspelled more than once is listed once, wherever it is in the text
+        let mut listed = HashSet::new();
+        misspelled.retain(|(_, word)| listed.insert(word.clone()));
+
+        if misspelled.is_empty() {
+            return;
+        }
+
+        response.context_menu(|ui| {
+            for (range, word) in misspelled {
+                ui.menu_button(&word, |ui| {
+                    let suggestions =
+                        spell_checker.with_lock_mut(|checker| checker.suggestions(&word, 5));
+
+                    if suggestions.is_empty() {
+                        ui.label("No suggestions");
+                    }
+
+                    for suggestion in suggestions {
+                        if ui.button(&suggestion).clicked() {
+                            text.replace_range(range.clone(), &suggestion);
+                            ui.close_menu();
+                        }
+                    }
+
+                    ui.separator();
+
+                    if ui.button("Add to Dictionary").clicked() {
+                        SpellChecker::add_to_custom_dictionary(&word);
+                        ui.close_menu();
+                    }
+                });
+            }
+        });
     }
 }
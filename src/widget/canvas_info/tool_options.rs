@@ -0,0 +1,136 @@
+use eframe::egui::{self};
+use egui::{Button, ComboBox, DragValue, Grid, RichText, Vec2};
+use strum::IntoEnumIterator;
+
+use crate::{
+    model::unit::Unit,
+    widget::{
+        canvas::CanvasState, canvas_state::CanvasTool, measure_tool::MeasureToolOptions,
+        pen_tool::PenToolOptions,
+    },
+};
+
+/// Settings of the chosen canvas tool
+pub struct ToolOptions;
+
+impl ToolOptions {
+    pub fn show(ui: &mut egui::Ui, tool: CanvasTool, canvas_state: &mut CanvasState) {
+        ui.vertical(|ui| {
+            ui.spacing_mut().item_spacing = Vec2::new(10.0, 5.0);
+
+            match tool {
+                CanvasTool::Select => {
+                    ui.label(
+                        RichText::new(
+                            "Click a layer to select it, drag to move it. Press P to draw paths \
+                             with the pen or M to measure.",
+                        )
+                        .weak(),
+                    );
+                }
+                CanvasTool::Pen => Self::pen_options(ui),
+                CanvasTool::Measure => Self::measure_options(ui, canvas_state),
+            }
+        });
+    }
+
+    fn pen_options(ui: &mut egui::Ui) {
+        let mut options = PenToolOptions::current(ui.ctx());
+
+        Grid::new("pen_tool_options")
+            .num_columns(2)
+            .spacing([10.0, 5.0])
+            .show(ui, |ui| {
+                ui.label("Fill:");
+                ui.color_edit_button_srgba(&mut options.fill)
+                    .on_hover_text("Only closed paths are filled");
+                ui.end_row();
+
+                ui.label("Stroke:");
+                ui.color_edit_button_srgba(&mut options.stroke_color);
+                ui.end_row();
+
+                ui.label("Stroke Width:");
+                ui.add(
+                    DragValue::new(&mut options.stroke_width)
+                        .range(0.0..=500.0)
+                        .speed(0.5)
+                        .suffix(" px"),
+                );
+                ui.end_row();
+            });
+
+        options.set(ui.ctx());
+
+        ui.label(
+            RichText::new(
+                "New paths start with these. Click the first point to close a path, \
+                 double-click or press Enter to leave it open. Press V to go back to selecting.",
+            )
+            .weak(),
+        );
+    }
+
+    fn measure_options(ui: &mut egui::Ui, canvas_state: &mut CanvasState) {
+        let mut options = MeasureToolOptions::current(ui.ctx());
+        let page = &canvas_state.page.value;
+
+        ui.horizontal(|ui| {
+            ui.label("Units:");
+            ComboBox::from_id_salt("measure_tool_unit")
+                .selected_text(match options.unit {
+                    Some(unit) => unit.to_string(),
+                    None => format!("Page ({})", page.unit()),
+                })
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut options.unit, None, format!("Page ({})", page.unit()));
+                    for unit in Unit::iter() {
+                        ui.selectable_value(&mut options.unit, Some(unit), unit.to_string());
+                    }
+                });
+        });
+
+        options.set(ui.ctx());
+
+        let unit = options.unit(page);
+        let mut removed = None;
+
+        Grid::new("measurements")
+            .num_columns(3)
+            .spacing([10.0, 5.0])
+            .show(ui, |ui| {
+                for (index, measurement) in canvas_state.measurements.iter().enumerate() {
+                    ui.label(format!("{}.", index + 1));
+                    ui.label(measurement.label(page, unit))
+                        .on_hover_text(measurement.extent_label(page, unit));
+                    if ui.small_button("Remove").clicked() {
+                        removed = Some(index);
+                    }
+                    ui.end_row();
+                }
+            });
+
+        if let Some(index) = removed {
+            canvas_state.measurements.remove(index);
+        }
+
+        if ui
+            .add_enabled(
+                !canvas_state.measurements.is_empty(),
+                Button::new("Clear Measurements"),
+            )
+            .clicked()
+        {
+            canvas_state.measurements.clear();
+        }
+
+        ui.label(
+            RichText::new(
+                "Drag between two points to measure them, or from one layer to another to \
+                 measure the gap between them. Hold Shift to measure straight across or down. \
+                 Measurements stay on the page until they're cleared and are never exported.",
+            )
+            .weak(),
+        );
+    }
+}
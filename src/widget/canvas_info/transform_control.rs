@@ -41,7 +41,7 @@ impl<'a> TransformControl<'a> {
                 ui.spacing_mut().item_spacing = Vec2::new(10.0, 5.0);
                 ui.style_mut().spacing.text_edit_width = 80.0;
 
-                ui.label(RichText::new("Position").heading());
+                ui.label(RichText::new("Position").strong());
 
                 ui.horizontal(|ui| {
                     ui.label("x:");
@@ -77,7 +77,7 @@ impl<'a> TransformControl<'a> {
 
                 ui.separator();
 
-                ui.label(RichText::new("Size").heading());
+                ui.label(RichText::new("Size").strong());
 
                 ui.horizontal(|ui| {
                     ui.label("Width:");
@@ -99,7 +99,7 @@ impl<'a> TransformControl<'a> {
 
                 ui.separator();
 
-                ui.label(RichText::new("Rotation").heading());
+                ui.label(RichText::new("Rotation").strong());
 
                 ui.horizontal(|ui| {
                     ui.label("Degrees:");
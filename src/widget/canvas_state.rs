@@ -1,6 +1,7 @@
 use crate::id::LayerId;
-use eframe::egui::{Id, Rect};
 use crate::widget::transformable::TransformableState;
+use eframe::egui::{Context, Id, Key, Rect};
+use strum_macros::{Display, EnumIter};
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum CanvasInteractionMode {
@@ -13,4 +14,51 @@ pub struct CropState {
     pub target_layer: LayerId,
     pub transform_state: TransformableState,
     pub photo_rect: Rect,
+    // Name entered for saving the current crop as a preset
+    pub preset_name: String,
+    // Mirrors the golden spiral overlay horizontally and vertically
+    pub spiral_flip: [bool; 2],
+    // Waiting on the photo's saliency map to move the crop handles to a suggested crop
+    pub suggesting: bool,
+}
+
+/// What dragging on the page does. The tool is shared by every page so it stays chosen when
+/// switching pages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Display, EnumIter)]
+pub enum CanvasTool {
+    #[default]
+    Select,
+    Pen,
+    Measure,
+}
+
+impl CanvasTool {
+    fn id() -> Id {
+        Id::new("canvas_tool")
+    }
+
+    pub fn current(ctx: &Context) -> Self {
+        ctx.data(|data| data.get_temp(Self::id()))
+            .unwrap_or_default()
+    }
+
+    pub fn set(self, ctx: &Context) {
+        ctx.data_mut(|data| data.insert_temp(Self::id(), self));
+    }
+
+    pub fn icon(&self) -> &'static str {
+        match self {
+            CanvasTool::Select => "↖",
+            CanvasTool::Pen => "✒",
+            CanvasTool::Measure => "📏",
+        }
+    }
+
+    pub fn shortcut(&self) -> Key {
+        match self {
+            CanvasTool::Select => Key::V,
+            CanvasTool::Pen => Key::P,
+            CanvasTool::Measure => Key::M,
+        }
+    }
 }
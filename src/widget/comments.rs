@@ -0,0 +1,162 @@
+use eframe::egui;
+use egui::{Label, Layout, RichText, ScrollArea, Sense};
+use log::{error, info};
+
+use crate::{
+    modal::{basic::BasicModal, manager::ModalManager},
+    review_report::ReviewReport,
+};
+
+use super::{pages::PagesState, review::ReviewState};
+
+/// Lists the review comments of every page in the book
+pub struct Comments<'a> {
+    pages_state: &'a mut PagesState,
+    review: &'a mut ReviewState,
+}
+
+impl<'a> Comments<'a> {
+    pub fn new(pages_state: &'a mut PagesState, review: &'a mut ReviewState) -> Self {
+        Self {
+            pages_state,
+            review,
+        }
+    }
+
+    pub fn show(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            if ui
+                .selectable_label(self.review.enabled, "Review Mode")
+                .on_hover_text("Click the page to pin a comment")
+                .clicked()
+            {
+                self.review.enabled = !self.review.enabled;
+                self.review.selected_comment = None;
+            }
+
+            ui.checkbox(&mut self.review.show_resolved, "Show Resolved");
+
+            ui.with_layout(Layout::right_to_left(egui::Align::Center), |ui| {
+                if ui.button("Export...").clicked() {
+                    self.export();
+                }
+            });
+        });
+
+        ui.separator();
+
+        let mut select = None;
+
+        ScrollArea::vertical()
+            .auto_shrink([false, false])
+            .show(ui, |ui| {
+                let mut any_shown = false;
+
+                for (page_index, (page_id, page)) in self.pages_state.pages.iter_mut().enumerate() {
+                    let shown = page
+                        .comments
+                        .iter()
+                        .any(|comment| self.review.show_resolved || !comment.resolved);
+                    if !shown {
+                        continue;
+                    }
+                    any_shown = true;
+
+                    ui.label(RichText::new(format!("Page {}", page_index + 1)).strong());
+
+                    for (index, comment) in page.comments.iter_mut().enumerate() {
+                        if comment.resolved && !self.review.show_resolved {
+                            continue;
+                        }
+
+                        ui.horizontal(|ui| {
+                            ui.checkbox(&mut comment.resolved, "")
+                                .on_hover_text("Resolved");
+
+                            let text = if comment.text.is_empty() {
+                                RichText::new("No comment yet").weak()
+                            } else if comment.resolved {
+                                RichText::new(&comment.text).strikethrough()
+                            } else {
+                                RichText::new(&comment.text)
+                            };
+
+                            let selected = self.review.selected_comment == Some(comment.id);
+                            let label = ui.add(
+                                Label::new(RichText::new(format!("{}.", index + 1)).strong())
+                                    .selectable(false),
+                            );
+                            let response = ui
+                                .add(Label::new(text).truncate().sense(Sense::click()))
+                                .on_hover_text(comment.created_label());
+
+                            if selected {
+                                ui.painter().rect_stroke(
+                                    label.rect.union(response.rect).expand(2.0),
+                                    2.0,
+                                    ui.visuals().selection.stroke,
+                                );
+                            }
+
+                            if response.clicked() {
+                                select = Some((*page_id, comment.id));
+                            }
+                        });
+                    }
+
+                    ui.add_space(6.0);
+                }
+
+                if !any_shown {
+                    ui.centered_and_justified(|ui| {
+                        ui.label("No comments");
+                    });
+                }
+            });
+
+        // Jumps to the page and opens the comment there
+        if let Some((page_id, comment_id)) = select {
+            self.pages_state.selected_page = page_id;
+            self.review.enabled = true;
+            self.review.selected_comment = Some(comment_id);
+        }
+    }
+
+    fn export(&self) {
+        let report = ReviewReport::new(&self.pages_state.pages, self.review.show_resolved);
+        if report.is_empty() {
+            ModalManager::push(BasicModal::new(
+                "Export Comments",
+                "There are no comments to export",
+                "OK",
+            ));
+            return;
+        }
+
+        let path = match native_dialog::FileDialog::new()
+            .set_filename("comments.csv")
+            .add_filter("CSV", &["csv"])
+            .add_filter("Text", &["txt"])
+            .show_save_single_file()
+        {
+            Ok(Some(path)) => path,
+            Ok(None) => {
+                info!("No comments export path selected");
+                return;
+            }
+            Err(err) => {
+                error!("Error opening comments export dialog: {:?}", err);
+                return;
+            }
+        };
+
+        if let Err(err) = report.write(&path) {
+            error!("Failed to export comments: {:?}", err);
+            ModalManager::push(BasicModal::new(
+                "Export Comments",
+                format!("Failed to export comments: {}", err),
+                "OK",
+            ));
+        }
+    }
+}
@@ -1,21 +1,37 @@
 use std::thread::current;
+use std::time::Duration;
 
-use eframe::egui::{self, CursorIcon, Pos2, Rect, Sense, Stroke, Ui, Vec2};
-use eframe::emath::Rot2;
+use eframe::egui::{self, Align2, CursorIcon, FontId, Pos2, Rect, Sense, Stroke, Ui, Vec2};
 use eframe::epaint::{Color32, Mesh, Shape};
-use egui::UiBuilder;
+use egui::{Button, TextEdit, UiBuilder};
+use log::error;
+use serde::{Deserialize, Serialize};
+use strum::IntoEnumIterator;
 
+use crate::auto_persisting::AutoPersisting;
+use crate::config::{Config, ConfigModification};
 use crate::dependencies::{Dependency, Singleton, SingletonFor};
+use crate::id::LayerId;
 use crate::photo_manager::PhotoManager;
+use crate::saliency::SaliencyCache;
 use crate::scene::canvas_scene::{CanvasHistoryKind, CanvasHistoryManager};
 use crate::utils::RectExt;
 use crate::widget::action_bar::{ActionBar, ActionBarResponse, ActionItem, ActionItemKind};
 use crate::widget::auto_center::AutoCenter;
-use crate::widget::canvas::CanvasState;
+use crate::widget::canvas::{CanvasPhoto, CanvasState};
 use crate::widget::canvas_info::layers::LayerContent;
 use crate::widget::canvas_state::{CanvasInteractionMode, CropState};
+use crate::widget::crop_overlay::{self, CropOverlay};
 use crate::widget::transformable::{ResizeMode, TransformHandleMode, TransformableWidget};
 
+/// A named crop that can be applied to any photo. The crop is normalized so it applies to
+/// photos of any size.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CropPreset {
+    pub name: String,
+    pub crop: Rect,
+}
+
 #[derive(Debug, Clone, PartialEq, Copy)]
 pub enum CropResponse {
     Exit,
@@ -60,18 +76,12 @@ impl<'a> Crop<'a> {
                 let painter: &egui::Painter = ui.painter();
                 let mut mesh: Mesh = Mesh::with_texture(texture.id);
 
-                let mesh_rect =
-                    if photo.photo.metadata.width() != photo.photo.metadata.rotated_width() {
-                        Rect::from_center_size(
-                            self.crop_state.photo_rect.center(),
-                            Vec2::new(
-                                self.crop_state.photo_rect.height(),
-                                self.crop_state.photo_rect.width(),
-                            ),
-                        )
-                    } else {
-                        self.crop_state.photo_rect
-                    };
+                // The photo is shown as it's oriented on the page so the crop handles are in
+                // the same space as the layer
+                let exif_rotation = photo.photo.metadata.rotation();
+                let mesh_rect = photo
+                    .orientation
+                    .mesh_rect(exif_rotation, self.crop_state.photo_rect);
 
                 mesh.add_rect_with_uv(
                     mesh_rect,
@@ -82,21 +92,42 @@ impl<'a> Crop<'a> {
                 let mesh_center: Pos2 = self.crop_state.photo_rect.min
                     + Vec2::splat(0.5) * self.crop_state.photo_rect.size();
 
-                mesh.rotate(
-                    Rot2::from_angle(photo.photo.metadata.rotation().radians()),
-                    mesh_center,
-                );
+                photo
+                    .orientation
+                    .orient_mesh(exif_rotation, &mut mesh, mesh_center);
 
                 painter.add(Shape::mesh(mesh));
 
-                let transform_response =
-                    TransformableWidget::new(&mut self.crop_state.transform_state).show(
-                        ui,
-                        mesh_rect,
-                        1.0,
-                        true,
-                        |_ui: &mut Ui, _transformed_rect: Rect, _transformable_state| {},
-                    );
+                let overlays = Self::overlays();
+                let spiral_flip = self.crop_state.spiral_flip;
+
+                let transform_response = TransformableWidget::new(
+                    &mut self.crop_state.transform_state,
+                )
+                .show(
+                    ui,
+                    self.crop_state.photo_rect,
+                    1.0,
+                    true,
+                    |ui: &mut Ui, crop_rect: Rect, transformable_state| {
+                        crop_overlay::paint_overlays(
+                            ui.painter(),
+                            crop_rect,
+                            transformable_state.rotation,
+                            &overlays,
+                            spiral_flip,
+                        );
+
+                        // Rotating the crop is how a photo is straightened
+                        if matches!(transformable_state.handle_mode, TransformHandleMode::Rotate) {
+                            crop_overlay::paint_level(
+                                ui.painter(),
+                                crop_rect,
+                                transformable_state.rotation,
+                            );
+                        }
+                    },
+                );
 
                 if transform_response.ended_moving
                     || transform_response.ended_resizing
@@ -108,6 +139,26 @@ impl<'a> Crop<'a> {
             }
         }
 
+        self.show_menus(ui);
+
+        if self.crop_state.suggesting {
+            self.suggest_crop();
+        }
+
+        if self.crop_state.suggesting {
+            ui.painter().text(
+                Pos2::new(
+                    self.available_rect.center().x,
+                    self.available_rect.max.y - 80.0,
+                ),
+                Align2::CENTER_CENTER,
+                "Analyzing photo…",
+                FontId::proportional(14.0),
+                Color32::WHITE,
+            );
+            ui.ctx().request_repaint_after(Duration::from_millis(100));
+        }
+
         if self.show_action_bar(ui) {
             return CropResponse::Exit;
         }
@@ -115,6 +166,267 @@ impl<'a> Crop<'a> {
         CropResponse::None
     }
 
+    /// Converts a normalized crop into a rect relative to the top left of `photo_rect`, which is
+    /// the space the crop handles are edited in
+    pub fn crop_to_local_rect(crop: Rect, photo_rect: Rect) -> Rect {
+        Rect::from_min_size(
+            (crop.min.to_vec2() * photo_rect.size()).to_pos2(),
+            crop.size() * photo_rect.size(),
+        )
+    }
+
+    // The crop currently shown, on the unrotated image like the photo's stored crop
+    fn current_crop(&self) -> Rect {
+        let display_crop = self.current_display_crop();
+        self.target_photo()
+            .map(|photo| photo.display_crop_to_image(display_crop))
+            .unwrap_or(display_crop)
+    }
+
+    fn set_current_crop(&mut self, crop: Rect) {
+        let display_crop = self
+            .target_photo()
+            .map(|photo| photo.image_crop_to_display(crop))
+            .unwrap_or(crop);
+        self.set_current_display_crop(display_crop);
+    }
+
+    // The normalized crop currently shown by the crop handles, in the space of the photo as it's
+    // shown. Presets are saved in this space so they apply the same way to any photo.
+    fn current_display_crop(&self) -> Rect {
+        let photo_rect = self.crop_state.photo_rect;
+        let world_transform_rect = self
+            .crop_state
+            .transform_state
+            .rect
+            .to_world_space(photo_rect);
+
+        let intersection = world_transform_rect.intersect(photo_rect);
+
+        Rect::from_min_size(
+            ((intersection.min - photo_rect.min) / photo_rect.size()).to_pos2(),
+            intersection.size() / photo_rect.size(),
+        )
+    }
+
+    fn set_current_display_crop(&mut self, crop: Rect) {
+        self.crop_state.transform_state.rect =
+            Self::crop_to_local_rect(crop, self.crop_state.photo_rect);
+    }
+
+    fn target_photo(&self) -> Option<&CanvasPhoto> {
+        match &self
+            .state
+            .layers
+            .get(&self.crop_state.target_layer)?
+            .content
+        {
+            LayerContent::Photo(photo) => Some(photo),
+            _ => None,
+        }
+    }
+
+    // The crop before the one currently shown, stepping further back each time it's applied
+    fn previous_crop(&self) -> Option<Rect> {
+        let crop_history = &self.target_photo()?.crop_history;
+        let current_crop = self.current_crop();
+
+        let current_index = crop_history
+            .iter()
+            .rposition(|crop| crops_match(*crop, current_crop))
+            .unwrap_or(crop_history.len());
+
+        current_index
+            .checked_sub(1)
+            .and_then(|index| crop_history.get(index))
+            .copied()
+    }
+
+    // Moves the crop handles over the most visually important part of the photo, in the shape of
+    // the layer so the photo keeps its place on the page. The suggestion can be adjusted before
+    // it's applied. Until the photo's saliency map is made the crop scene keeps asking.
+    fn suggest_crop(&mut self) {
+        let Some(layer) = self.state.layers.get(&self.crop_state.target_layer) else {
+            self.crop_state.suggesting = false;
+            return;
+        };
+        let LayerContent::Photo(photo) = &layer.content else {
+            self.crop_state.suggesting = false;
+            return;
+        };
+
+        match photo.suggested_crop(layer.transform_state.rect.size()) {
+            Some(crop) => {
+                self.crop_state.suggesting = false;
+                self.set_current_crop(crop);
+            }
+            None => {
+                // Without a thumbnail, or if the map couldn't be made, there's nothing to wait on
+                self.crop_state.suggesting = Dependency::<SaliencyCache>::get()
+                    .with_lock(|cache| cache.is_making(&photo.photo));
+                if !self.crop_state.suggesting {
+                    error!(
+                        "No saliency map to suggest a crop for {:?}",
+                        photo.photo.path
+                    );
+                }
+            }
+        }
+    }
+
+    /// Applies a crop to a photo layer, resizing the layer to match the aspect ratio of the crop
+    pub fn apply_crop(state: &mut CanvasState, layer_id: LayerId, crop: Rect) {
+        if let Some(layer) = state.layers.get_mut(&layer_id) {
+            if let LayerContent::Photo(photo) = &mut layer.content {
+                photo.set_crop(crop);
+
+                let crop_aspect_ratio = photo.cropped_aspect_ratio();
+                let mut transform_rect = layer.transform_state.rect;
+                let rect_center = transform_rect.center();
+
+                let old_w = transform_rect.width();
+                let old_h = transform_rect.height();
+                let old_ar = old_w / old_h;
+
+                if old_ar < crop_aspect_ratio {
+                    // Keep width, shrink height
+                    let new_h = old_w / crop_aspect_ratio;
+                    transform_rect = Rect::from_center_size(rect_center, Vec2::new(old_w, new_h));
+                } else {
+                    // Keep height, shrink width
+                    let new_w = old_h * crop_aspect_ratio;
+                    transform_rect = Rect::from_center_size(rect_center, Vec2::new(new_w, old_h));
+                }
+
+                layer.transform_state.rect = transform_rect;
+            }
+        }
+    }
+
+    fn overlays() -> Vec<CropOverlay> {
+        Dependency::<AutoPersisting<Config>>::get().with_lock_mut(|config| {
+            config
+                .read()
+                .map(|config| config.crop_overlays())
+                .unwrap_or_default()
+        })
+    }
+
+    fn show_menus(&mut self, ui: &mut Ui) {
+        let menu_rect = Rect::from_min_size(
+            ui.max_rect().left_top() + Vec2::splat(10.0),
+            Vec2::new(300.0, 30.0),
+        );
+
+        ui.allocate_new_ui(UiBuilder::new().max_rect(menu_rect), |ui| {
+            ui.horizontal(|ui| {
+                self.show_presets_menu(ui);
+                self.show_overlays_menu(ui);
+            });
+        });
+    }
+
+    fn show_overlays_menu(&mut self, ui: &mut Ui) {
+        let mut overlays = Self::overlays();
+        let original_overlays = overlays.clone();
+
+        ui.menu_button("Overlays", |ui| {
+            for overlay in CropOverlay::iter() {
+                let mut shown = overlays.contains(&overlay);
+                if ui.checkbox(&mut shown, overlay.to_string()).changed() {
+                    if shown {
+                        overlays.push(overlay);
+                    } else {
+                        overlays.retain(|existing| *existing != overlay);
+                    }
+                }
+            }
+
+            if overlays.contains(&CropOverlay::GoldenSpiral) {
+                ui.separator();
+                ui.checkbox(
+                    &mut self.crop_state.spiral_flip[0],
+                    "Flip Spiral Horizontally",
+                );
+                ui.checkbox(
+                    &mut self.crop_state.spiral_flip[1],
+                    "Flip Spiral Vertically",
+                );
+            }
+        });
+
+        if overlays != original_overlays {
+            Dependency::<AutoPersisting<Config>>::get().with_lock_mut(|config| {
+                if let Err(err) = config.modify(ConfigModification::SetCropOverlays(overlays)) {
+                    error!("Failed to save crop overlays: {:?}", err);
+                }
+            });
+        }
+    }
+
+    fn show_presets_menu(&mut self, ui: &mut Ui) {
+        let config: Singleton<AutoPersisting<Config>> = Dependency::get();
+        let presets = config.with_lock_mut(|config| {
+            config
+                .read()
+                .map(|config| config.crop_presets().to_vec())
+                .unwrap_or_default()
+        });
+
+        ui.menu_button("Presets", |ui| {
+            if presets.is_empty() {
+                ui.label("No saved presets");
+            }
+
+            for preset in &presets {
+                ui.horizontal(|ui| {
+                    if ui.button(&preset.name).clicked() {
+                        self.set_current_display_crop(preset.crop);
+                        ui.close_menu();
+                    }
+
+                    if ui.small_button("Delete").clicked() {
+                        config.with_lock_mut(|config| {
+                            if let Err(err) = config
+                                .modify(ConfigModification::DeleteCropPreset(preset.name.clone()))
+                            {
+                                error!("Failed to delete crop preset: {:?}", err);
+                            }
+                        });
+                    }
+                });
+            }
+
+            ui.separator();
+
+            ui.horizontal(|ui| {
+                ui.add(
+                    TextEdit::singleline(&mut self.crop_state.preset_name)
+                        .hint_text("Preset name")
+                        .desired_width(120.0),
+                );
+
+                let name = self.crop_state.preset_name.trim().to_string();
+                if ui
+                    .add_enabled(!name.is_empty(), Button::new("Save"))
+                    .clicked()
+                {
+                    let preset = CropPreset {
+                        name,
+                        crop: self.current_display_crop(),
+                    };
+                    config.with_lock_mut(|config| {
+                        if let Err(err) = config.modify(ConfigModification::SaveCropPreset(preset))
+                        {
+                            error!("Failed to save crop preset: {:?}", err);
+                        }
+                    });
+                    self.crop_state.preset_name.clear();
+                }
+            });
+        });
+    }
+
     fn show_action_bar(&mut self, ui: &mut Ui) -> bool {
         let bar_height = 40.0;
         let bar_margin_bottom = 40.0;
@@ -127,17 +439,33 @@ impl<'a> Crop<'a> {
             Vec2::new(ui.max_rect().width(), bar_height),
         );
 
-        let actions = vec![
+        let mut actions = vec![
             ActionItem {
                 kind: ActionItemKind::Text("Apply".to_string()),
                 action: "apply",
             },
             ActionItem {
-                kind: ActionItemKind::Text("Cancel".to_string()),
-                action: "cancel",
+                kind: ActionItemKind::Text("Reset".to_string()),
+                action: "reset",
+            },
+            ActionItem {
+                kind: ActionItemKind::Text("Suggest Crop".to_string()),
+                action: "suggest",
             },
         ];
 
+        if self.previous_crop().is_some() {
+            actions.push(ActionItem {
+                kind: ActionItemKind::Text("Previous Crop".to_string()),
+                action: "previous",
+            });
+        }
+
+        actions.push(ActionItem {
+            kind: ActionItemKind::Text("Cancel".to_string()),
+            action: "cancel",
+        });
+
         match ui
             .allocate_new_ui(UiBuilder::new().max_rect(bar_rect), |ui| {
                 AutoCenter::new("crop_action_bar")
@@ -151,62 +479,26 @@ impl<'a> Crop<'a> {
         {
             ActionBarResponse::Clicked(action) => match action {
                 "apply" => {
-                    // Update the target layer's crop rect
-                    if let Some(layer) = self.state.layers.get_mut(&self.crop_state.target_layer) {
-                        if let LayerContent::Photo(photo) = &mut layer.content {
-
-                            let world_transform_rect = self
-                                .crop_state
-                                .transform_state
-                                .rect
-                                .to_world_space(self.crop_state.photo_rect);
-
-                            let intersection =
-                                world_transform_rect.intersect(self.crop_state.photo_rect);
-
-                            let normalized_intersection = Rect::from_min_size(
-                                Pos2::new(
-                                    (intersection.min - self.crop_state.photo_rect.min).x
-                                        / self.crop_state.photo_rect.size().x,
-                                    (intersection.min - self.crop_state.photo_rect.min).y
-                                        / self.crop_state.photo_rect.size().y,
-                                ),
-                                Vec2::new(
-                                    intersection.size().x / self.crop_state.photo_rect.size().x,
-                                    intersection.size().y / self.crop_state.photo_rect.size().y,
-                                ),
-                            );
-                            if let Some(layer) =
-                                self.state.layers.get_mut(&self.crop_state.target_layer)
-                            {
-                                if let LayerContent::Photo(photo) = &mut layer.content {                                   
-                                    photo.crop = normalized_intersection;
-
-                                    let crop_aspect_ratio = normalized_intersection.width() / normalized_intersection.height();
-                                    let mut transform_rect = layer.transform_state.rect;
-                                    let rect_center = transform_rect.center();
-
-                                    let old_w = transform_rect.width();
-                                    let old_h = transform_rect.height();
-                                    let old_ar = old_w / old_h;
-
-                                    if old_ar < crop_aspect_ratio {
-                                        // Keep width, shrink height
-                                        let new_h = old_w / crop_aspect_ratio;
-                                        transform_rect = Rect::from_center_size(rect_center, Vec2::new(old_w, new_h));
-                                    } else {
-                                        // Keep height, shrink width
-                                        let new_w = old_h * crop_aspect_ratio;
-                                        transform_rect = Rect::from_center_size(rect_center, Vec2::new(new_w, old_h));
-                                    }
-
-                                    layer.transform_state.rect = transform_rect;
-                                }
-                            }
-                        }
-                    }
+                    let crop = self.current_crop();
+                    Self::apply_crop(self.state, self.crop_state.target_layer, crop);
+                    self.history_manager
+                        .save_history(CanvasHistoryKind::Crop, self.state);
                     true
                 }
+                "reset" => {
+                    self.set_current_display_crop(CanvasPhoto::UNCROPPED);
+                    false
+                }
+                "previous" => {
+                    if let Some(crop) = self.previous_crop() {
+                        self.set_current_crop(crop);
+                    }
+                    false
+                }
+                "suggest" => {
+                    self.suggest_crop();
+                    false
+                }
                 "cancel" => true,
                 _ => false,
             },
@@ -214,3 +506,9 @@ impl<'a> Crop<'a> {
         }
     }
 }
+
+// Crops go through a round trip to screen space while being edited so they won't match exactly
+fn crops_match(a: Rect, b: Rect) -> bool {
+    const TOLERANCE: f32 = 0.001;
+    (a.min - b.min).length() < TOLERANCE && (a.max - b.max).length() < TOLERANCE
+}
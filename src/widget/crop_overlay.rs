@@ -0,0 +1,251 @@
+use std::f32::consts::{FRAC_PI_2, PI};
+
+use eframe::egui::{
+    emath::Rot2, Align2, Color32, FontId, Painter, Pos2, Rect, Shape, Stroke, Vec2,
+};
+use serde::{Deserialize, Serialize};
+use strum_macros::{Display, EnumIter};
+
+// 1 / φ², where the smaller part of a golden section starts
+const GOLDEN_SECTION: f32 = 0.381_966;
+
+// Quarter turns of the golden spiral drawn, smaller ones are too small to see
+const SPIRAL_TURNS: usize = 10;
+const SPIRAL_ARC_POINTS: usize = 16;
+
+// Within this many degrees of level the level indicator shows the crop as level
+const LEVEL_TOLERANCE: f32 = 0.1;
+
+/// Composition guides drawn over the crop while it's being edited
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Display, EnumIter)]
+pub enum CropOverlay {
+    #[strum(to_string = "Rule of Thirds")]
+    RuleOfThirds,
+    #[strum(to_string = "Golden Ratio")]
+    GoldenRatio,
+    #[strum(to_string = "Golden Spiral")]
+    GoldenSpiral,
+    Diagonals,
+    #[strum(to_string = "Center Cross")]
+    CenterCross,
+}
+
+/// Paints `overlays` over `rect`, the crop before it's rotated by `rotation` around its center.
+/// `spiral_flip` mirrors the golden spiral horizontally and vertically so it can wind into any
+/// corner.
+pub fn paint_overlays(
+    painter: &Painter,
+    rect: Rect,
+    rotation: f32,
+    overlays: &[CropOverlay],
+    spiral_flip: [bool; 2],
+) {
+    let size = rect.size();
+    // Points are given relative to the crop, 0 to 1 across it
+    let to_screen = |point: Pos2| {
+        rect.center() + Rot2::from_angle(rotation) * ((point.to_vec2() - Vec2::splat(0.5)) * size)
+    };
+
+    let mut lines: Vec<Vec<Pos2>> = Vec::new();
+
+    for overlay in overlays {
+        match overlay {
+            CropOverlay::RuleOfThirds => {
+                for split in [1.0 / 3.0, 2.0 / 3.0] {
+                    lines.push(vec![Pos2::new(split, 0.0), Pos2::new(split, 1.0)]);
+                    lines.push(vec![Pos2::new(0.0, split), Pos2::new(1.0, split)]);
+                }
+            }
+            CropOverlay::GoldenRatio => {
+                for split in [GOLDEN_SECTION, 1.0 - GOLDEN_SECTION] {
+                    lines.push(vec![Pos2::new(split, 0.0), Pos2::new(split, 1.0)]);
+                    lines.push(vec![Pos2::new(0.0, split), Pos2::new(1.0, split)]);
+                }
+            }
+            CropOverlay::Diagonals => {
+                lines.push(vec![Pos2::new(0.0, 0.0), Pos2::new(1.0, 1.0)]);
+                lines.push(vec![Pos2::new(1.0, 0.0), Pos2::new(0.0, 1.0)]);
+            }
+            CropOverlay::CenterCross => {
+                // The arms are the same length on screen whatever the crop's shape
+                let arm = Vec2::splat(0.05 * size.min_elem()) / size;
+                lines.push(vec![
+                    Pos2::new(0.5 - arm.x, 0.5),
+                    Pos2::new(0.5 + arm.x, 0.5),
+                ]);
+                lines.push(vec![
+                    Pos2::new(0.5, 0.5 - arm.y),
+                    Pos2::new(0.5, 0.5 + arm.y),
+                ]);
+            }
+            CropOverlay::GoldenSpiral => {
+                let flip = |point: Pos2| {
+                    // Portrait crops get the spiral turned on its side so it follows the long side
+                    let point = if size.y > size.x {
+                        Pos2::new(point.y, point.x)
+                    } else {
+                        point
+                    };
+                    let mirror = |value: f32, flipped: bool| {
+                        if flipped {
+                            1.0 - value
+                        } else {
+                            value
+                        }
+                    };
+                    Pos2::new(
+                        mirror(point.x, spiral_flip[0]),
+                        mirror(point.y, spiral_flip[1]),
+                    )
+                };
+
+                let (arc, squares) = golden_spiral();
+                lines.push(arc.into_iter().map(&flip).collect());
+                for [from, to] in squares {
+                    lines.push(vec![flip(from), flip(to)]);
+                }
+            }
+        }
+    }
+
+    // A dark line under a light one so the guides show up on any photo
+    for (width, color) in [
+        (2.0, Color32::from_black_alpha(90)),
+        (1.0, Color32::from_white_alpha(170)),
+    ] {
+        for line in &lines {
+            painter.add(Shape::line(
+                line.iter().copied().map(to_screen).collect(),
+                Stroke::new(width, color),
+            ));
+        }
+    }
+}
+
+/// Shows how far `rotation` is from level with the crop's horizon against a level line, and the
+/// angle, while the crop is straightened
+pub fn paint_level(painter: &Painter, rect: Rect, rotation: f32) {
+    // The nearest quarter turn counts as level so crops turned on their side can be leveled too
+    let tilt = rotation - (rotation / FRAC_PI_2).round() * FRAC_PI_2;
+    let degrees = tilt.to_degrees();
+    let level = degrees.abs() < LEVEL_TOLERANCE;
+
+    let color = if level {
+        Color32::from_rgb(80, 220, 120)
+    } else {
+        Color32::from_rgb(250, 200, 60)
+    };
+
+    let center = rect.center();
+    let half_width = rect.width().max(rect.height()) / 2.0;
+
+    painter.extend(Shape::dashed_line(
+        &[
+            center - Vec2::new(half_width, 0.0),
+            center + Vec2::new(half_width, 0.0),
+        ],
+        Stroke::new(1.0, Color32::from_white_alpha(200)),
+        6.0,
+        4.0,
+    ));
+
+    let horizon = Rot2::from_angle(tilt) * Vec2::new(half_width, 0.0);
+    painter.line_segment(
+        [center - horizon, center + horizon],
+        Stroke::new(2.0, color),
+    );
+
+    let label = if level {
+        "Level".to_string()
+    } else {
+        format!("{:+.1}°", degrees)
+    };
+    let label_pos = center - Vec2::new(0.0, 16.0);
+    let galley = painter.layout_no_wrap(label, FontId::proportional(14.0), color);
+    let label_rect = Align2::CENTER_BOTTOM.anchor_size(label_pos, galley.size());
+    painter.rect_filled(label_rect.expand(4.0), 4.0, Color32::from_black_alpha(160));
+    painter.galley(label_rect.min, galley, color);
+}
+
+// The arc of a golden spiral and the lines dividing it into squares, over a landscape rect 0 to
+// 1 across. The spiral starts at the bottom left corner and winds clockwise into the rect.
+fn golden_spiral() -> (Vec<Pos2>, Vec<[Pos2; 2]>) {
+    let phi = (1.0 + 5f32.sqrt()) / 2.0;
+
+    // Worked out on a golden rect, φ wide and 1 high, then squashed to fit
+    let mut remaining = Rect::from_min_size(Pos2::ZERO, Vec2::new(phi, 1.0));
+    let mut arc = Vec::new();
+    let mut squares = Vec::new();
+
+    for turn in 0..SPIRAL_TURNS {
+        let Rect { min, max } = remaining;
+
+        // A square is cut from each side in turn, the arc runs through it around the corner
+        // it shares with what's left
+        let (square, center, start, end) = match turn % 4 {
+            0 => {
+                let side = remaining.height();
+                remaining.min.x += side;
+                (
+                    Rect::from_min_size(min, Vec2::splat(side)),
+                    Pos2::new(min.x + side, max.y),
+                    PI,
+                    1.5 * PI,
+                )
+            }
+            1 => {
+                let side = remaining.width();
+                remaining.min.y += side;
+                (
+                    Rect::from_min_size(min, Vec2::splat(side)),
+                    Pos2::new(min.x, min.y + side),
+                    1.5 * PI,
+                    2.0 * PI,
+                )
+            }
+            2 => {
+                let side = remaining.height();
+                remaining.max.x -= side;
+                (
+                    Rect::from_min_max(Pos2::new(max.x - side, min.y), max),
+                    Pos2::new(max.x - side, min.y),
+                    0.0,
+                    FRAC_PI_2,
+                )
+            }
+            _ => {
+                let side = remaining.width();
+                remaining.max.y -= side;
+                (
+                    Rect::from_min_max(Pos2::new(min.x, max.y - side), max),
+                    Pos2::new(max.x, max.y - side),
+                    FRAC_PI_2,
+                    PI,
+                )
+            }
+        };
+
+        let radius = square.width();
+        for step in 0..=SPIRAL_ARC_POINTS {
+            let angle = start + (end - start) * step as f32 / SPIRAL_ARC_POINTS as f32;
+            arc.push(center + Vec2::angled(angle) * radius);
+        }
+
+        // The side of the square that borders the rest of the spiral
+        squares.push(match turn % 4 {
+            0 => [Pos2::new(square.max.x, min.y), square.max],
+            1 => [Pos2::new(min.x, square.max.y), square.max],
+            2 => [square.min, Pos2::new(square.min.x, max.y)],
+            _ => [square.min, Pos2::new(max.x, square.min.y)],
+        });
+    }
+
+    let squash = |point: Pos2| Pos2::new(point.x / phi, point.y);
+    (
+        arc.into_iter().map(squash).collect(),
+        squares
+            .into_iter()
+            .map(|[from, to]| [squash(from), squash(to)])
+            .collect(),
+    )
+}
@@ -0,0 +1,117 @@
+use eframe::egui;
+use egui::{CollapsingHeader, Image, Layout, Sense, Stroke, Vec2};
+use strum::IntoEnumIterator;
+
+use crate::{
+    decoration::{Decoration, DecorationKind},
+    dependencies::{Dependency, SingletonFor},
+    project_settings::ProjectSettingsManager,
+    theme,
+};
+
+const THUMBNAIL_SIZE: f32 = 80.0;
+
+pub enum DecorationsResponse {
+    None,
+    Place(Decoration),
+}
+
+pub struct Decorations {}
+
+impl Decorations {
+    pub fn show(ui: &mut egui::Ui) -> DecorationsResponse {
+        let mut response = DecorationsResponse::None;
+        let mut removed = None;
+
+        ui.horizontal(|ui| {
+            ui.menu_button("Import", |ui| {
+                for kind in DecorationKind::iter() {
+                    if ui.button(kind.to_string()).clicked() {
+                        Decoration::import(kind);
+                        ui.close_menu();
+                    }
+                }
+            });
+        });
+
+        ui.separator();
+
+        let decorations = Dependency::<ProjectSettingsManager>::get()
+            .with_lock(|manager| manager.project_settings.decorations.clone());
+
+        if decorations.is_empty() {
+            ui.centered_and_justified(|ui| {
+                ui.label("Import stickers, ornaments or textures to use them on your pages");
+            });
+            return response;
+        }
+
+        egui::ScrollArea::vertical()
+            .auto_shrink([false, false])
+            .show(ui, |ui| {
+                for kind in DecorationKind::iter() {
+                    let decorations_of_kind: Vec<&Decoration> = decorations
+                        .iter()
+                        .filter(|decoration| decoration.kind == kind)
+                        .collect();
+
+                    if decorations_of_kind.is_empty() {
+                        continue;
+                    }
+
+                    CollapsingHeader::new(kind.to_string())
+                        .default_open(true)
+                        .show(ui, |ui| {
+                            ui.with_layout(
+                                Layout::left_to_right(egui::Align::Min).with_main_wrap(true),
+                                |ui| {
+                                    for decoration in decorations_of_kind {
+                                        let thumbnail_response = ui.add_sized(
+                                            Vec2::splat(THUMBNAIL_SIZE),
+                                            Image::new(decoration.uri())
+                                                .max_size(Vec2::splat(THUMBNAIL_SIZE - 8.0))
+                                                .sense(Sense::click()),
+                                        );
+
+                                        if thumbnail_response.hovered() {
+                                            ui.painter().rect_stroke(
+                                                thumbnail_response.rect,
+                                                4.0,
+                                                Stroke::new(
+                                                    2.0,
+                                                    theme::color::focused(ui.visuals()),
+                                                ),
+                                            );
+                                        }
+
+                                        let thumbnail_response =
+                                            thumbnail_response.on_hover_text(format!(
+                                                "{}\nClick to add to the page",
+                                                decoration.name()
+                                            ));
+
+                                        if thumbnail_response.clicked() {
+                                            response =
+                                                DecorationsResponse::Place(decoration.clone());
+                                        }
+
+                                        thumbnail_response.context_menu(|ui| {
+                                            if ui.button("Remove from Project").clicked() {
+                                                removed = Some(decoration.clone());
+                                                ui.close_menu();
+                                            }
+                                        });
+                                    }
+                                },
+                            );
+                        });
+                }
+            });
+
+        if let Some(decoration) = removed {
+            Decoration::remove(&decoration);
+        }
+
+        response
+    }
+}
@@ -0,0 +1,56 @@
+use eframe::egui;
+use egui::{Grid, Layout, RichText, ScrollArea};
+
+use crate::debug::{DiagnosticsReport, FRAME_SAMPLES};
+
+/// Frame timings, memory use and background work for tracking down slowdowns. Everything shown
+/// can be copied as text to attach to a bug report.
+pub struct DiagnosticsPanel {
+    report: DiagnosticsReport,
+}
+
+impl DiagnosticsPanel {
+    pub fn new(report: DiagnosticsReport) -> Self {
+        Self { report }
+    }
+
+    pub fn show(&self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label(
+                RichText::new(format!("Averaged over the last {} frames", FRAME_SAMPLES)).weak(),
+            );
+            ui.with_layout(Layout::right_to_left(egui::Align::Center), |ui| {
+                if ui.button("Copy Report").clicked() {
+                    ui.ctx().copy_text(self.report.text());
+                }
+            });
+        });
+
+        ui.separator();
+
+        ScrollArea::vertical()
+            .auto_shrink([false, false])
+            .show(ui, |ui| {
+                for (heading, rows) in self.report.sections() {
+                    ui.label(RichText::new(heading).strong());
+
+                    Grid::new(("diagnostics", heading))
+                        .num_columns(2)
+                        .spacing([20.0, 4.0])
+                        .striped(true)
+                        .show(ui, |ui| {
+                            for (name, value) in rows {
+                                ui.label(name);
+                                ui.label(RichText::new(value).monospace());
+                                ui.end_row();
+                            }
+                        });
+
+                    ui.add_space(8.0);
+                }
+            });
+
+        // Keep the numbers moving while the panel is open
+        ui.ctx().request_repaint();
+    }
+}
@@ -2,7 +2,7 @@ use eframe::{
     egui::{load::SizedTexture, Image, Response, Sense, Ui, Widget},
     epaint::{Color32, Vec2},
 };
-use egui::{Spinner, Stroke};
+use egui::{Align2, FontId, Rect, Spinner, Stroke};
 use log::error;
 
 use crate::{
@@ -16,6 +16,12 @@ pub struct GalleryImage {
     photo: Photo,
     texture: anyhow::Result<Option<SizedTexture>>,
     selected: bool,
+    // Number of versions in the photo's stack
+    stack_size: usize,
+    missing: bool,
+    panorama: bool,
+    // Whether the photo can be dragged out, e.g. onto the canvas or the tray
+    draggable: bool,
 }
 
 impl GalleryImage {
@@ -28,8 +34,32 @@ impl GalleryImage {
             photo,
             texture,
             selected,
+            stack_size: 1,
+            missing: false,
+            panorama: false,
+            draggable: false,
         }
     }
+
+    pub fn stack_size(mut self, stack_size: usize) -> Self {
+        self.stack_size = stack_size;
+        self
+    }
+
+    pub fn missing(mut self, missing: bool) -> Self {
+        self.missing = missing;
+        self
+    }
+
+    pub fn panorama(mut self, panorama: bool) -> Self {
+        self.panorama = panorama;
+        self
+    }
+
+    pub fn draggable(mut self, draggable: bool) -> Self {
+        self.draggable = draggable;
+        self
+    }
 }
 
 impl Widget for GalleryImage {
@@ -52,7 +82,12 @@ impl Widget for GalleryImage {
                     ),
                 };
 
-                let (rect, response) = ui.allocate_exact_size(size, Sense::click());
+                let sense = if self.draggable {
+                    Sense::click_and_drag()
+                } else {
+                    Sense::click()
+                };
+                let (rect, response) = ui.allocate_exact_size(size, sense);
 
                 ui.allocate_ui_at_rect(rect, |ui| {
                     ui.spacing_mut().item_spacing = Vec2::splat(0.0);
@@ -60,11 +95,67 @@ impl Widget for GalleryImage {
                     ui.painter()
                         .rect_filled(ui.max_rect(), 6.0, Color32::from_rgb(15, 15, 15));
 
+                    if self.stack_size > 1 {
+                        let badge_rect = Rect::from_min_size(
+                            ui.max_rect().right_top() + Vec2::new(-40.0, 6.0),
+                            Vec2::new(34.0, 20.0),
+                        );
+                        ui.painter()
+                            .rect_filled(badge_rect, 4.0, Color32::from_gray(60));
+                        ui.painter().text(
+                            badge_rect.center(),
+                            Align2::CENTER_CENTER,
+                            format!("⧉ {}", self.stack_size),
+                            FontId::proportional(12.0),
+                            Color32::WHITE,
+                        );
+                    }
+
+                    if self.panorama {
+                        let badge_rect = Rect::from_min_size(
+                            ui.max_rect().left_top() + Vec2::new(6.0, 6.0),
+                            Vec2::new(44.0, 20.0),
+                        );
+                        ui.painter()
+                            .rect_filled(badge_rect, 4.0, Color32::from_gray(60));
+                        ui.painter().text(
+                            badge_rect.center(),
+                            Align2::CENTER_CENTER,
+                            "Pano",
+                            FontId::proportional(12.0),
+                            Color32::WHITE,
+                        );
+                    }
+
+                    if self.missing {
+                        let badge_rect = Rect::from_min_size(
+                            ui.max_rect().left_bottom() + Vec2::new(6.0, -26.0),
+                            Vec2::new(60.0, 20.0),
+                        );
+                        ui.painter()
+                            .rect_filled(badge_rect, 4.0, Color32::from_rgb(200, 60, 60));
+                        ui.painter().text(
+                            badge_rect.center(),
+                            Align2::CENTER_CENTER,
+                            "Missing",
+                            FontId::proportional(12.0),
+                            Color32::WHITE,
+                        );
+                    }
+
+                    if let Some(color_label) = self.photo.color_label {
+                        color_label.paint_dot(
+                            ui.painter(),
+                            ui.max_rect().right_bottom() + Vec2::new(-14.0, -14.0),
+                            6.0,
+                        );
+                    }
+
                     if self.selected {
                         ui.painter().rect_stroke(
                             ui.max_rect(),
                             4.0,
-                            Stroke::new(3.0, theme::color::FOCUSED),
+                            Stroke::new(3.0, theme::color::focused(ui.visuals())),
                         );
                     }
 
@@ -130,6 +221,7 @@ impl Widget for GalleryImage {
                                     let rotation = self.photo.metadata.rotation();
                                     ui.add(
                                         Image::from_texture(texture)
+                                            .uv(rotation.mirror_uv())
                                             .rotate(rotation.radians(), Vec2::splat(0.5))
                                             .fit_to_exact_size(scaled_image_size),
                                     );
@@ -137,11 +229,14 @@ impl Widget for GalleryImage {
                                 Ok(None) => {
                                     let response = RectPlaceholder::new(
                                         rotated_scaled_image_size,
-                                        color::PLACEHOLDER,
+                                        color::placeholder(ui.visuals()),
                                     )
                                     .ui(ui);
 
-                                    ui.put(response.rect, Spinner::new());
+                                    // A missing photo won't finish loading
+                                    if !self.missing {
+                                        ui.put(response.rect, Spinner::new());
+                                    }
                                 }
                                 Err(err) => {
                                     // Show red square for error for now
@@ -0,0 +1,305 @@
+use eframe::egui::{self};
+use egui::{Event, EventFilter, Id, Key, Rect, Sense, Ui};
+
+// Letters typed within this many seconds of each other are one prefix
+const TYPE_AHEAD_TIMEOUT: f64 = 1.0;
+
+/// What a key pressed in a focused grid asks for
+pub enum GridKeysResponse {
+    // Move the cursor to the item at this index
+    MoveTo(usize),
+    Open(usize),
+    // Toggle the quick preview of the item under the cursor
+    Preview,
+    // Jump to the first item whose name starts with this, in lowercase
+    Find(String),
+}
+
+#[derive(Debug, Clone, Default)]
+struct TypeAhead {
+    prefix: String,
+    last_typed: f64,
+}
+
+/// Keyboard navigation for `count` items laid out `columns` to a row, left to right and top to
+/// bottom. The grid takes the keyboard when one of its items is clicked, like the layers list,
+/// and gives it back when anything else is clicked.
+pub struct GridKeys {
+    id: Id,
+    count: usize,
+    columns: usize,
+    // The row each item is on when rows aren't all full, like grids split into groups
+    rows: Option<Vec<usize>>,
+    // How many rows Page Up and Page Down move by
+    page_rows: usize,
+}
+
+impl GridKeys {
+    pub fn new(id: Id, count: usize, columns: usize) -> Self {
+        Self {
+            id,
+            count,
+            columns: columns.max(1),
+            rows: None,
+            page_rows: 1,
+        }
+    }
+
+    /// Puts item `i` on row `rows[i]` instead of filling each row in turn. Rows must be in order
+    /// but can skip numbers, like the rows of group titles.
+    pub fn rows(mut self, rows: Vec<usize>) -> Self {
+        self.rows = Some(rows);
+        self
+    }
+
+    pub fn page_rows(mut self, page_rows: usize) -> Self {
+        self.page_rows = page_rows.max(1);
+        self
+    }
+
+    pub fn has_focus(&self, ui: &Ui) -> bool {
+        ui.memory(|memory| memory.has_focus(self.id))
+    }
+
+    pub fn surrender_focus(&self, ui: &Ui) {
+        ui.memory_mut(|memory| memory.surrender_focus(self.id));
+    }
+
+    /// Makes `rect`, where the grid is shown, take the keyboard when `clicked`
+    pub fn update_focus(&self, ui: &Ui, rect: Rect, clicked: bool) {
+        let response = ui.interact(rect, self.id, Sense::focusable_noninteractive());
+
+        if clicked {
+            response.request_focus();
+        }
+
+        if response.has_focus()
+            && ui.input(|input| input.pointer.any_pressed())
+            && !clicked
+            && !ui.rect_contains_pointer(rect)
+        {
+            response.surrender_focus();
+        }
+
+        if response.has_focus() {
+            ui.memory_mut(|memory| {
+                memory.set_focus_lock_filter(
+                    self.id,
+                    EventFilter {
+                        vertical_arrows: true,
+                        horizontal_arrows: true,
+                        tab: false,
+                        escape: false,
+                    },
+                )
+            });
+        }
+    }
+
+    /// Arrows move by an item or a row, Home and End go to either end and Page Up and Page Down
+    /// move by a screen. Enter opens the item under the cursor and Space previews it. Anything
+    /// else typed is collected into a prefix to find an item by.
+    pub fn handle(&self, ui: &Ui, cursor: Option<usize>) -> Option<GridKeysResponse> {
+        if self.count == 0 || !self.has_focus(ui) {
+            return None;
+        }
+
+        let last = self.count - 1;
+        let page = self.page_rows as isize;
+
+        let (target, typed) = ui.input(|input| {
+            if input.modifiers.command || input.modifiers.alt {
+                return (None, None);
+            }
+
+            let target = match cursor {
+                _ if input.key_pressed(Key::Home) => Some(0),
+                _ if input.key_pressed(Key::End) => Some(last),
+                None if [
+                    Key::ArrowLeft,
+                    Key::ArrowRight,
+                    Key::ArrowUp,
+                    Key::ArrowDown,
+                    Key::PageUp,
+                    Key::PageDown,
+                ]
+                .iter()
+                .any(|key| input.key_pressed(*key)) =>
+                {
+                    Some(0)
+                }
+                None => None,
+                Some(cursor) if input.key_pressed(Key::ArrowLeft) => Some(cursor.saturating_sub(1)),
+                Some(cursor) if input.key_pressed(Key::ArrowRight) => Some((cursor + 1).min(last)),
+                Some(cursor) if input.key_pressed(Key::ArrowUp) => Some(self.move_rows(cursor, -1)),
+                Some(cursor) if input.key_pressed(Key::ArrowDown) => {
+                    Some(self.move_rows(cursor, 1))
+                }
+                Some(cursor) if input.key_pressed(Key::PageUp) => {
+                    Some(self.move_rows(cursor, -page))
+                }
+                Some(cursor) if input.key_pressed(Key::PageDown) => {
+                    Some(self.move_rows(cursor, page))
+                }
+                Some(_) => None,
+            };
+
+            let typed: String = input
+                .events
+                .iter()
+                .filter_map(|event| match event {
+                    Event::Text(text) => Some(text.as_str()),
+                    _ => None,
+                })
+                .collect::<String>()
+                .chars()
+                // Space previews instead
+                .filter(|c| !c.is_whitespace())
+                .collect();
+
+            (target, (!typed.is_empty()).then_some((typed, input.time)))
+        });
+
+        if let Some(target) = target {
+            return Some(GridKeysResponse::MoveTo(target));
+        }
+
+        if let Some(cursor) = cursor {
+            if ui.input(|input| input.key_pressed(Key::Enter)) {
+                return Some(GridKeysResponse::Open(cursor));
+            }
+
+            if ui.input(|input| input.key_pressed(Key::Space)) {
+                return Some(GridKeysResponse::Preview);
+            }
+        }
+
+        let (typed, time) = typed?;
+        let prefix = ui.data_mut(|data| {
+            let type_ahead = data.get_temp_mut_or_default::<TypeAhead>(self.id.with("type_ahead"));
+            if time - type_ahead.last_typed > TYPE_AHEAD_TIMEOUT {
+                type_ahead.prefix.clear();
+            }
+            type_ahead.prefix.push_str(&typed.to_lowercase());
+            type_ahead.last_typed = time;
+            type_ahead.prefix.clone()
+        });
+
+        Some(GridKeysResponse::Find(prefix))
+    }
+
+    // The item `rows` rows above, when negative, or below `cursor` in the same column, or the last
+    // item of a shorter row. Moving stops at the top and bottom rows.
+    fn move_rows(&self, cursor: usize, rows: isize) -> usize {
+        let mut target = cursor;
+
+        for _ in 0..rows.unsigned_abs() {
+            let start = self.row_start(target);
+            let column = target - start;
+
+            if rows < 0 {
+                if start == 0 {
+                    break;
+                }
+                target = (self.row_start(start - 1) + column).min(start - 1);
+            } else {
+                let end = self.row_end(target);
+                if end == self.count {
+                    break;
+                }
+                target = (end + column).min(self.row_end(end) - 1);
+            }
+        }
+
+        target
+    }
+
+    fn row_of(&self, index: usize) -> usize {
+        match &self.rows {
+            Some(rows) => rows[index],
+            None => index / self.columns,
+        }
+    }
+
+    // The first item on the same row as `index`
+    fn row_start(&self, index: usize) -> usize {
+        let row = self.row_of(index);
+        (0..index)
+            .rev()
+            .take_while(|other| self.row_of(*other) == row)
+            .last()
+            .unwrap_or(index)
+    }
+
+    // One past the last item on the same row as `index`
+    fn row_end(&self, index: usize) -> usize {
+        let row = self.row_of(index);
+        (index + 1..self.count)
+            .find(|other| self.row_of(*other) != row)
+            .unwrap_or(self.count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 7 items, 3 to a row: 0 1 2 / 3 4 5 / 6
+    fn full_rows() -> GridKeys {
+        GridKeys::new(Id::new("grid"), 7, 3)
+    }
+
+    // Two groups with a title row between them: 0 1 2 / 3 / (title) / 4 5 6
+    fn grouped_rows() -> GridKeys {
+        GridKeys::new(Id::new("grid"), 7, 3).rows(vec![0, 0, 0, 1, 3, 3, 3])
+    }
+
+    #[test]
+    fn row_start_and_end() {
+        let grid = full_rows();
+        assert_eq!(grid.row_start(4), 3);
+        assert_eq!(grid.row_end(4), 6);
+        assert_eq!(grid.row_start(6), 6);
+        assert_eq!(grid.row_end(6), 7);
+
+        let grid = grouped_rows();
+        assert_eq!(grid.row_start(3), 3);
+        assert_eq!(grid.row_end(3), 4);
+        assert_eq!(grid.row_start(6), 4);
+        assert_eq!(grid.row_end(0), 3);
+    }
+
+    #[test]
+    fn moves_down_and_up_in_the_same_column() {
+        let grid = full_rows();
+        assert_eq!(grid.move_rows(1, 1), 4);
+        assert_eq!(grid.move_rows(4, -1), 1);
+        assert_eq!(grid.move_rows(2, 2), 6);
+    }
+
+    #[test]
+    fn shorter_rows_take_their_last_item() {
+        let grid = full_rows();
+        assert_eq!(grid.move_rows(5, 1), 6);
+
+        let grid = grouped_rows();
+        assert_eq!(grid.move_rows(2, 1), 3);
+        assert_eq!(grid.move_rows(6, -1), 3);
+    }
+
+    #[test]
+    fn skipped_rows_are_moved_over() {
+        let grid = grouped_rows();
+        assert_eq!(grid.move_rows(3, 1), 4);
+        assert_eq!(grid.move_rows(4, -1), 3);
+    }
+
+    #[test]
+    fn stops_at_the_top_and_bottom() {
+        let grid = full_rows();
+        assert_eq!(grid.move_rows(1, -1), 1);
+        assert_eq!(grid.move_rows(6, 1), 6);
+        assert_eq!(grid.move_rows(4, 10), 6);
+        assert_eq!(grid.move_rows(4, -10), 1);
+    }
+}
@@ -0,0 +1,182 @@
+use eframe::egui;
+use egui::{DragValue, Grid, RichText, ScrollArea};
+use strum::IntoEnumIterator;
+
+use crate::{
+    dependencies::{Dependency, Singleton, SingletonFor},
+    id::GuideId,
+    model::{
+        guide::{Guide, GuideOrientation},
+        page::Page,
+        unit::Unit,
+    },
+    project_settings::ProjectSettingsManager,
+};
+
+use super::canvas::CanvasState;
+
+enum GuideAction {
+    Delete(GuideId),
+    MoveToBook(GuideId),
+    MoveToPage(GuideId),
+}
+
+/// Lists the guides on the page and those shared by every page, for placing them exactly
+pub struct GuideManager<'a> {
+    page: &'a mut CanvasState,
+}
+
+impl<'a> GuideManager<'a> {
+    pub fn new(page: &'a mut CanvasState) -> Self {
+        Self { page }
+    }
+
+    pub fn show(&mut self, ui: &mut egui::Ui) {
+        let project_settings: Singleton<ProjectSettingsManager> = Dependency::get();
+        let mut book_guides =
+            project_settings.with_lock(|manager| manager.project_settings.guides.clone());
+        let original_book_guides = book_guides.clone();
+
+        ui.horizontal(|ui| {
+            for orientation in GuideOrientation::iter() {
+                if ui.button(format!("Add {}", orientation)).clicked() {
+                    self.page.guides.push(Guide::new(orientation, 0.5));
+                }
+            }
+
+            if ui
+                .button("Clear All")
+                .on_hover_text("Removes the guides on this page and those on every page")
+                .clicked()
+            {
+                self.page.guides.clear();
+                book_guides.clear();
+            }
+        });
+
+        ui.label(
+            RichText::new("Drag from a ruler to add a guide, drop it off the page to remove it")
+                .weak(),
+        );
+
+        ui.separator();
+
+        let page = self.page.page.value.clone();
+        let mut actions = Vec::new();
+
+        ScrollArea::vertical()
+            .auto_shrink([false, false])
+            .show(ui, |ui| {
+                ui.label(RichText::new("This Page").strong());
+                Self::guides_ui(
+                    ui,
+                    "page_guides",
+                    &mut self.page.guides,
+                    &page,
+                    false,
+                    &mut actions,
+                );
+
+                ui.add_space(6.0);
+
+                ui.label(RichText::new("All Pages").strong());
+                Self::guides_ui(
+                    ui,
+                    "book_guides",
+                    &mut book_guides,
+                    &page,
+                    true,
+                    &mut actions,
+                );
+            });
+
+        for action in actions {
+            match action {
+                GuideAction::Delete(id) => {
+                    self.page.guides.retain(|guide| guide.id != id);
+                    book_guides.retain(|guide| guide.id != id);
+                }
+                GuideAction::MoveToBook(id) => {
+                    if let Some(index) = self.page.guides.iter().position(|guide| guide.id == id) {
+                        book_guides.push(self.page.guides.remove(index));
+                    }
+                }
+                GuideAction::MoveToPage(id) => {
+                    if let Some(index) = book_guides.iter().position(|guide| guide.id == id) {
+                        self.page.guides.push(book_guides.remove(index));
+                    }
+                }
+            }
+        }
+
+        if book_guides != original_book_guides {
+            project_settings.with_lock_mut(|manager| {
+                manager.project_settings.guides = book_guides;
+            });
+        }
+    }
+
+    fn guides_ui(
+        ui: &mut egui::Ui,
+        id: &str,
+        guides: &mut [Guide],
+        page: &Page,
+        is_book: bool,
+        actions: &mut Vec<GuideAction>,
+    ) {
+        if guides.is_empty() {
+            ui.label(RichText::new("No guides").weak());
+            return;
+        }
+
+        Grid::new(id).num_columns(5).striped(true).show(ui, |ui| {
+            for guide in guides.iter_mut() {
+                ui.label(guide.orientation.to_string());
+
+                let mut position = guide.position_in_unit(page);
+                let response = ui.add_enabled(
+                    !guide.locked,
+                    DragValue::new(&mut position)
+                        .speed(0.01)
+                        .max_decimals(3)
+                        .suffix(unit_suffix(page.unit())),
+                );
+                if response.changed() {
+                    guide.set_position_in_unit(page, position);
+                }
+
+                ui.checkbox(&mut guide.locked, "Locked");
+
+                if is_book {
+                    if ui
+                        .button("This Page")
+                        .on_hover_text("Only show the guide on this page")
+                        .clicked()
+                    {
+                        actions.push(GuideAction::MoveToPage(guide.id));
+                    }
+                } else if ui
+                    .button("All Pages")
+                    .on_hover_text("Show the guide on every page")
+                    .clicked()
+                {
+                    actions.push(GuideAction::MoveToBook(guide.id));
+                }
+
+                if ui.button("Delete").clicked() {
+                    actions.push(GuideAction::Delete(guide.id));
+                }
+
+                ui.end_row();
+            }
+        });
+    }
+}
+
+fn unit_suffix(unit: Unit) -> &'static str {
+    match unit {
+        Unit::Pixels => " px",
+        Unit::Inches => " in",
+        Unit::Centimeters => " cm",
+    }
+}
@@ -0,0 +1,330 @@
+use eframe::egui;
+use egui::{Align2, Color32, CursorIcon, FontId, Id, Pos2, Rect, Sense, Stroke, Vec2};
+
+use crate::{
+    cursor_manager::CursorManager,
+    dependencies::{Dependency, SingletonFor},
+    id::GuideId,
+    model::{
+        guide::{Guide, GuideOrientation},
+        page::Page,
+        unit::Unit,
+    },
+    theme,
+};
+
+pub const RULER_SIZE: f32 = 18.0;
+
+const PAGE_GUIDE_COLOR: Color32 = Color32::from_rgb(0x00, 0xbc, 0xd4);
+const BOOK_GUIDE_COLOR: Color32 = Color32::from_rgb(0xe0, 0x40, 0xa0);
+
+// How far either side of a guide it can be grabbed, in screen points
+const GRAB_DISTANCE: f32 = 3.0;
+
+// The closest ruler ticks are allowed to get, in screen points
+const MIN_TICK_SPACING: f32 = 8.0;
+
+/// Rulers along the top and left of the canvas and the guides on the page. Dragging out of a
+/// ruler adds a guide to the page, dragging a guide moves it and dropping it off the page
+/// removes it.
+pub struct GuideOverlay<'a> {
+    page_guides: &'a mut Vec<Guide>,
+    book_guides: &'a mut Vec<Guide>,
+    page: &'a Page,
+    canvas_rect: Rect,
+    page_rect: Rect,
+    id: Id,
+}
+
+impl<'a> GuideOverlay<'a> {
+    pub fn new(
+        page_guides: &'a mut Vec<Guide>,
+        book_guides: &'a mut Vec<Guide>,
+        page: &'a Page,
+        canvas_rect: Rect,
+        page_rect: Rect,
+        id: Id,
+    ) -> Self {
+        Self {
+            page_guides,
+            book_guides,
+            page,
+            canvas_rect,
+            page_rect,
+            id,
+        }
+    }
+
+    pub fn show(&mut self, ui: &mut egui::Ui) {
+        let top_ruler = Rect::from_min_max(
+            self.canvas_rect.left_top() + Vec2::new(RULER_SIZE, 0.0),
+            Pos2::new(
+                self.canvas_rect.right(),
+                self.canvas_rect.top() + RULER_SIZE,
+            ),
+        );
+        let left_ruler = Rect::from_min_max(
+            self.canvas_rect.left_top() + Vec2::new(0.0, RULER_SIZE),
+            Pos2::new(
+                self.canvas_rect.left() + RULER_SIZE,
+                self.canvas_rect.bottom(),
+            ),
+        );
+
+        self.paint_guides(ui, self.page_guides.iter(), PAGE_GUIDE_COLOR);
+        self.paint_guides(ui, self.book_guides.iter(), BOOK_GUIDE_COLOR);
+
+        let page_rect = self.page_rect;
+        let canvas_rect = self.canvas_rect;
+        for guides in [&mut *self.page_guides, &mut *self.book_guides] {
+            let mut removed = None;
+
+            for guide in guides.iter_mut().filter(|guide| !guide.locked) {
+                let response = ui.interact(
+                    guide_rect(guide, page_rect, canvas_rect).expand(GRAB_DISTANCE),
+                    self.id.with(("guide", guide.id)),
+                    Sense::drag(),
+                );
+
+                if response.hovered() || response.dragged() {
+                    set_cursor(guide.orientation);
+                }
+
+                if response.dragged() {
+                    if let Some(pointer) = response.interact_pointer_pos() {
+                        guide.position = relative_position(guide.orientation, page_rect, pointer);
+                    }
+                }
+
+                if response.drag_stopped() && is_off_page(guide) {
+                    removed = Some(guide.id);
+                }
+            }
+
+            if let Some(removed) = removed {
+                guides.retain(|guide| guide.id != removed);
+            }
+        }
+
+        self.ruler_ui(ui, top_ruler, GuideOrientation::Horizontal);
+        self.ruler_ui(ui, left_ruler, GuideOrientation::Vertical);
+
+        // The corner where the rulers meet
+        ui.painter().rect_filled(
+            Rect::from_min_size(self.canvas_rect.left_top(), Vec2::splat(RULER_SIZE)),
+            0.0,
+            theme::color::chrome(ui.visuals()),
+        );
+    }
+
+    fn paint_guides<'g>(
+        &self,
+        ui: &egui::Ui,
+        guides: impl Iterator<Item = &'g Guide>,
+        color: Color32,
+    ) {
+        for guide in guides {
+            let rect = guide_rect(guide, self.page_rect, self.canvas_rect);
+            let color = if guide.locked {
+                color.gamma_multiply(0.5)
+            } else {
+                color
+            };
+
+            let points = match guide.orientation {
+                GuideOrientation::Horizontal => [rect.left_center(), rect.right_center()],
+                GuideOrientation::Vertical => [rect.center_top(), rect.center_bottom()],
+            };
+            ui.painter().line_segment(points, Stroke::new(1.0, color));
+        }
+    }
+
+    // A horizontal ruler adds horizontal guides, which are dragged down from the top of the canvas
+    fn ruler_ui(&mut self, ui: &mut egui::Ui, rect: Rect, orientation: GuideOrientation) {
+        self.paint_ruler(ui, rect, orientation);
+
+        let response = ui.interact(rect, self.id.with(("ruler", orientation)), Sense::drag());
+        let new_guide_id = self.id.with(("new_guide", orientation));
+
+        if response.hovered() || response.dragged() {
+            set_cursor(orientation);
+        }
+
+        let Some(pointer) = response.interact_pointer_pos() else {
+            return;
+        };
+
+        if response.drag_started() {
+            let guide = Guide::new(
+                orientation,
+                relative_position(orientation, self.page_rect, pointer),
+            );
+            ui.data_mut(|data| data.insert_temp(new_guide_id, guide.id));
+            self.page_guides.push(guide);
+        }
+
+        let Some(guide_id) = ui.data(|data| data.get_temp::<GuideId>(new_guide_id)) else {
+            return;
+        };
+
+        if response.dragged() {
+            if let Some(guide) = self
+                .page_guides
+                .iter_mut()
+                .find(|guide| guide.id == guide_id)
+            {
+                guide.position = relative_position(orientation, self.page_rect, pointer);
+            }
+        }
+
+        if response.drag_stopped() {
+            ui.data_mut(|data| data.remove::<GuideId>(new_guide_id));
+            self.page_guides
+                .retain(|guide| guide.id != guide_id || !is_off_page(guide));
+        }
+    }
+
+    fn paint_ruler(&self, ui: &egui::Ui, rect: Rect, orientation: GuideOrientation) {
+        let painter = ui.painter();
+        let text_color = ui.visuals().weak_text_color();
+        painter.rect_filled(rect, 0.0, theme::color::chrome(ui.visuals()));
+
+        let page_length = match orientation {
+            GuideOrientation::Horizontal => self.page.size().x,
+            GuideOrientation::Vertical => self.page.size().y,
+        };
+        let (start, end, screen_length) = match orientation {
+            GuideOrientation::Horizontal => (rect.left(), rect.right(), self.page_rect.width()),
+            GuideOrientation::Vertical => (rect.top(), rect.bottom(), self.page_rect.height()),
+        };
+        let origin = match orientation {
+            GuideOrientation::Horizontal => self.page_rect.left(),
+            GuideOrientation::Vertical => self.page_rect.top(),
+        };
+
+        if page_length <= 0.0 || screen_length <= 0.0 {
+            return;
+        }
+
+        let points_per_unit = screen_length / page_length;
+        let mut step = base_tick_step(self.page.unit());
+        while step * points_per_unit < MIN_TICK_SPACING {
+            step *= 2.0;
+        }
+
+        let first = ((start - origin) / points_per_unit / step).floor() as i64;
+        let last = ((end - origin) / points_per_unit / step).ceil() as i64;
+
+        for index in first..=last {
+            let value = index as f32 * step;
+            let screen = origin + value * points_per_unit;
+            if screen < start || screen > end {
+                continue;
+            }
+
+            // Every fourth tick is numbered
+            let major = index % 4 == 0;
+            let tick_length = if major {
+                RULER_SIZE * 0.6
+            } else {
+                RULER_SIZE * 0.3
+            };
+
+            match orientation {
+                GuideOrientation::Horizontal => {
+                    painter.line_segment(
+                        [
+                            Pos2::new(screen, rect.bottom() - tick_length),
+                            Pos2::new(screen, rect.bottom()),
+                        ],
+                        Stroke::new(1.0, text_color),
+                    );
+                    if major {
+                        painter.text(
+                            Pos2::new(screen + 2.0, rect.top()),
+                            Align2::LEFT_TOP,
+                            format_tick(value),
+                            FontId::proportional(9.0),
+                            text_color,
+                        );
+                    }
+                }
+                GuideOrientation::Vertical => {
+                    painter.line_segment(
+                        [
+                            Pos2::new(rect.right() - tick_length, screen),
+                            Pos2::new(rect.right(), screen),
+                        ],
+                        Stroke::new(1.0, text_color),
+                    );
+                    if major {
+                        painter.text(
+                            Pos2::new(rect.left() + 1.0, screen + 2.0),
+                            Align2::LEFT_TOP,
+                            format_tick(value),
+                            FontId::proportional(9.0),
+                            text_color,
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
+
+// A thin rect along the guide spanning the whole canvas
+fn guide_rect(guide: &Guide, page_rect: Rect, canvas_rect: Rect) -> Rect {
+    match guide.orientation {
+        GuideOrientation::Horizontal => {
+            let y = page_rect.top() + guide.position * page_rect.height();
+            Rect::from_min_max(
+                Pos2::new(canvas_rect.left(), y),
+                Pos2::new(canvas_rect.right(), y),
+            )
+        }
+        GuideOrientation::Vertical => {
+            let x = page_rect.left() + guide.position * page_rect.width();
+            Rect::from_min_max(
+                Pos2::new(x, canvas_rect.top()),
+                Pos2::new(x, canvas_rect.bottom()),
+            )
+        }
+    }
+}
+
+fn relative_position(orientation: GuideOrientation, page_rect: Rect, pointer: Pos2) -> f32 {
+    match orientation {
+        GuideOrientation::Horizontal => (pointer.y - page_rect.top()) / page_rect.height(),
+        GuideOrientation::Vertical => (pointer.x - page_rect.left()) / page_rect.width(),
+    }
+}
+
+fn is_off_page(guide: &Guide) -> bool {
+    !(0.0..=1.0).contains(&guide.position)
+}
+
+fn set_cursor(orientation: GuideOrientation) {
+    Dependency::<CursorManager>::get().with_lock_mut(|cursor_manager| {
+        cursor_manager.set_cursor(match orientation {
+            GuideOrientation::Horizontal => CursorIcon::ResizeRow,
+            GuideOrientation::Vertical => CursorIcon::ResizeColumn,
+        });
+    });
+}
+
+// The smallest gap between ticks, in the page's unit, before they're spread out for the zoom
+fn base_tick_step(unit: Unit) -> f32 {
+    match unit {
+        Unit::Pixels => 10.0,
+        Unit::Inches => 0.125,
+        Unit::Centimeters => 0.25,
+    }
+}
+
+fn format_tick(value: f32) -> String {
+    if value.fract() == 0.0 {
+        format!("{}", value as i64)
+    } else {
+        format!("{:.1}", value)
+    }
+}
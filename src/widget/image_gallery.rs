@@ -1,27 +1,53 @@
-use std::{collections::HashSet, path::PathBuf};
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+};
 
 use eframe::{
     egui::{Key, Ui},
     epaint::Vec2,
 };
 
-use egui::{Color32, Image, Layout, Slider};
+use egui::{
+    Align2, Area, Button, Color32, ComboBox, FontId, Id, Image, Layout, Order, Rect, RichText,
+    Sense, Slider, TextEdit,
+};
 use egui_extras::Column;
+use strum::IntoEnumIterator;
 
 use crate::{
     assets::Asset,
+    auto_persisting::AutoPersisting,
+    config::{Config, ConfigModification},
     dependencies::{Dependency, Singleton, SingletonFor},
+    modal::{manager::ModalManager, photo_filter::PhotoFilterModal},
+    model::color_label::ColorLabel,
+    panorama::PanoramaPlacement,
     photo::Photo,
-    photo_manager::PhotoManager,
+    photo_manager::{PhotoManager, PhotosGrouping, PhotosSortField, SortDirection},
+    theme,
     utils::EguiUiExt,
 };
 
-use super::{gallery_image::GalleryImage, spacer::Spacer};
+use super::{
+    gallery_image::GalleryImage,
+    grid_keys::{GridKeys, GridKeysResponse},
+    pages::{PagesState, PhotoPlacement},
+    photo_tray::DraggedPhoto,
+    spacer::Spacer,
+};
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct ImageGalleryState {
     pub selected_images: HashSet<PathBuf>,
     pub scale: f32,
+    // Where each photo is placed in the book, kept up to date by the edit scene
+    pub placements: HashMap<PathBuf, Vec<PhotoPlacement>>,
+    placements_key: Option<u64>,
+    // The photo the arrow keys move from
+    pub cursor: Option<PathBuf>,
+    // Whether the photo under the cursor is shown over the gallery, toggled with Space
+    pub quick_preview: bool,
 }
 
 impl Default for ImageGalleryState {
@@ -29,6 +55,22 @@ impl Default for ImageGalleryState {
         Self {
             selected_images: HashSet::new(),
             scale: 1.0,
+            placements: HashMap::new(),
+            placements_key: None,
+            cursor: None,
+            quick_preview: false,
+        }
+    }
+}
+
+impl ImageGalleryState {
+    /// Works out where photos are placed in `pages` again if any were placed, removed or moved
+    /// since the last time
+    pub fn update_placements(&mut self, pages: &PagesState) {
+        let key = pages.placements_key();
+        if self.placements_key != Some(key) {
+            self.placements = pages.photo_placements();
+            self.placements_key = Some(key);
         }
     }
 }
@@ -41,6 +83,9 @@ pub struct ImageGallery<'a> {
 pub enum ImageGalleryResponse {
     SelectPhotoPrimaryAction(Photo),
     SelectPhotoSecondaryAction(Photo),
+    PlacePanorama(Photo, PanoramaPlacement),
+    // Show the page the photo is placed on with its layer selected
+    GoToPlacement(PhotoPlacement),
 }
 
 impl<'a> ImageGallery<'a> {
@@ -48,6 +93,7 @@ impl<'a> ImageGallery<'a> {
         let mut response = None;
         let photo_manager: Singleton<PhotoManager> = Dependency::get();
         let selected_images = &mut state.selected_images;
+        let placements = &state.placements;
 
         let has_photos = photo_manager.with_lock(|photo_manager| !photo_manager.photos.is_empty());
 
@@ -55,8 +101,13 @@ impl<'a> ImageGallery<'a> {
             ui.vertical(|ui| {
                 if ui.input(|input| input.key_down(Key::Escape)) {
                     selected_images.clear();
+                    state.quick_preview = false;
                 }
 
+                Self::color_label_shortcuts(ui, &photo_manager, selected_images);
+
+                Self::search_bar(ui, &photo_manager);
+
                 let spacing = 10.0;
 
                 let bottom_bar_height = 50.0;
@@ -118,104 +169,241 @@ impl<'a> ImageGallery<'a> {
 
                     let heights: Vec<f32> = row_metadatas.iter().map(|x| x.height).collect();
 
-                    egui_extras::TableBuilder::new(ui)
+                    // Every shown photo in order, with the row of the table it's on
+                    let photo_rows: Vec<(&Photo, usize)> = row_metadatas
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, metadata)| !metadata.is_title)
+                        .flat_map(|(row_index, metadata)| {
+                            grouped_photos
+                                .get(&metadata.section)
+                                .unwrap()
+                                .values()
+                                .skip(metadata.row_index_in_section * num_columns)
+                                .take(num_columns)
+                                .map(move |photo| (photo, row_index))
+                        })
+                        .collect();
+
+                    // Groups start on a new row so the rows aren't all full
+                    let grid_keys =
+                        GridKeys::new(Id::new("image_gallery_keys"), photo_rows.len(), num_columns)
+                            .rows(photo_rows.iter().map(|(_, row_index)| *row_index).collect())
+                            .page_rows((table_size.y / (row_height + spacing)) as usize);
+
+                    let cursor = state.cursor.as_ref().and_then(|cursor| {
+                        photo_rows.iter().position(|(photo, _)| &photo.path == cursor)
+                    });
+
+                    // The number keys label photos when some are selected
+                    let labeling =
+                        !selected_images.is_empty() && ColorLabel::pressed(ui.ctx()).is_some();
+
+                    let mut scroll_to_row = None;
+                    let mut move_cursor = |index: usize| {
+                        let (photo, row_index) = photo_rows[index];
+                        selected_images.clear();
+                        selected_images.insert(photo.path.clone());
+                        state.cursor = Some(photo.path.clone());
+                        scroll_to_row = Some(row_index);
+                    };
+
+                    match grid_keys.handle(ui, cursor) {
+                        Some(GridKeysResponse::MoveTo(index)) => move_cursor(index),
+                        Some(GridKeysResponse::Open(index)) => {
+                            response = Some(ImageGalleryResponse::SelectPhotoPrimaryAction(
+                                photo_rows[index].0.clone(),
+                            ));
+                        }
+                        Some(GridKeysResponse::Preview) => {
+                            state.quick_preview = !state.quick_preview;
+                        }
+                        Some(GridKeysResponse::Find(_)) if labeling => {}
+                        Some(GridKeysResponse::Find(prefix)) => {
+                            if let Some(index) = photo_rows.iter().position(|(photo, _)| {
+                                photo.file_name().to_lowercase().starts_with(&prefix)
+                            }) {
+                                move_cursor(index);
+                            }
+                        }
+                        None => {}
+                    }
+
+                    let preview_photo = state
+                        .cursor
+                        .as_ref()
+                        .filter(|_| state.quick_preview && grid_keys.has_focus(ui))
+                        .and_then(|cursor| {
+                            photo_rows
+                                .iter()
+                                .find(|(photo, _)| &photo.path == cursor)
+                                .map(|(photo, _)| (*photo).clone())
+                        });
+
+                    let table_rect = ui.max_rect();
+                    let mut clicked_photo = false;
+
+                    let mut table = egui_extras::TableBuilder::new(ui)
                         .min_scrolled_height(table_size.y)
                         .auto_shrink(false)
                         .columns(Column::exact(column_width), num_columns)
-                        .column(Column::exact(spacer_width))
-                        .body(|body| {
-                            body.heterogeneous_rows(heights.into_iter(), |mut row| {
-                                let row_index = row.index();
-                                let metadata = &row_metadatas[row_index];
-                                let offest = metadata.row_index_in_section * num_columns;
-
-                                let group = grouped_photos.get(&metadata.section).unwrap();
-
-                                if metadata.is_title {
-                                    row.col(|ui| {
-                                        ui.vertical(|ui| {
-                                            ui.add_space(10.0);
-                                            ui.heading(metadata.section.clone());
-                                        });
+                        .column(Column::exact(spacer_width));
+
+                    if let Some(row_index) = scroll_to_row {
+                        table = table.scroll_to_row(row_index, None);
+                    }
+
+                    table.body(|body| {
+                        body.heterogeneous_rows(heights.into_iter(), |mut row| {
+                            let row_index = row.index();
+                            let metadata = &row_metadatas[row_index];
+                            let offest = metadata.row_index_in_section * num_columns;
+
+                            let group = grouped_photos.get(&metadata.section).unwrap();
+
+                            if metadata.is_title {
+                                row.col(|ui| {
+                                    ui.vertical(|ui| {
+                                        ui.add_space(10.0);
+                                        ui.heading(metadata.section.clone());
                                     });
-                                } else {
-                                    for i in 0..num_columns {
-                                        if offest + i >= group.len() {
-                                            break;
-                                        }
-
-                                        row.col(|ui: &mut Ui| {
-                                            let photo = &group[offest + i];
-                                            photo_manager.with_lock_mut(|photo_manager| {
-                                                let image = GalleryImage::new(
-                                                    photo.clone(),
-                                                    photo_manager
-                                                        .thumbnail_texture_for(photo, ui.ctx()),
-                                                    selected_images.contains(&photo.path),
-                                                );
+                                });
+                            } else {
+                                for i in 0..num_columns {
+                                    if offest + i >= group.len() {
+                                        break;
+                                    }
 
-                                                let image_response = ui.add(image);
-
-                                                if image_response.clicked() {
-                                                    let ctrl_held =
-                                                        ui.input(|input| input.modifiers.ctrl);
-                                                    if ctrl_held {
-                                                        if selected_images.contains(&photo.path) {
-                                                            selected_images.remove(&photo.path);
-                                                        } else {
-                                                            selected_images
-                                                                .insert(photo.path.clone());
-                                                        }
+                                    row.col(|ui: &mut Ui| {
+                                        let photo = &group[offest + i];
+                                        photo_manager.with_lock_mut(|photo_manager| {
+                                            let image = GalleryImage::new(
+                                                photo.clone(),
+                                                photo_manager
+                                                    .thumbnail_texture_for(photo, ui.ctx()),
+                                                selected_images.contains(&photo.path),
+                                            )
+                                            .stack_size(
+                                                photo_manager.stack_size(&photo.path),
+                                            )
+                                            .missing(photo_manager.is_missing(&photo.path))
+                                            .panorama(photo.is_panorama())
+                                            .draggable(true);
+
+                                            let placements = placements
+                                                .get(&photo.path)
+                                                .map(Vec::as_slice)
+                                                .unwrap_or_default();
+
+                                            let mut image_response = ui.add(image);
+                                            image_response.dnd_set_drag_payload(
+                                                DraggedPhoto(photo.clone()),
+                                            );
+
+                                            if !placements.is_empty() {
+                                                image_response = image_response.on_hover_text(
+                                                    Self::placements_label(placements),
+                                                );
+                                            }
+
+                                            if image_response.clicked() {
+                                                clicked_photo = true;
+                                                state.cursor = Some(photo.path.clone());
+                                                let ctrl_held =
+                                                    ui.input(|input| input.modifiers.ctrl);
+                                                if ctrl_held {
+                                                    if selected_images.contains(&photo.path) {
+                                                        selected_images.remove(&photo.path);
                                                     } else {
-                                                        selected_images.clear();
-                                                        selected_images.insert(photo.path.clone());
+                                                        selected_images
+                                                            .insert(photo.path.clone());
                                                     }
+                                                } else {
+                                                    selected_images.clear();
+                                                    selected_images.insert(photo.path.clone());
                                                 }
-
-                                                if image_response.double_clicked() {
-                                                    response = Some(
-                                                        ImageGalleryResponse::SelectPhotoPrimaryAction(
-                                                            photo.clone(),
-                                                        ),
-                                                    );
-                                                } else if image_response.secondary_clicked() {
-                                                    response =
-                                                        Some(ImageGalleryResponse::SelectPhotoSecondaryAction(
-                                                            photo.clone(),
-                                                        ));
-                                                }
-                                            });
+                                            }
+
+                                            // Right clicking a panorama or a photo placed in
+                                            // the book opens a menu instead of the secondary
+                                            // action
+                                            let has_menu =
+                                                photo.is_panorama() || !placements.is_empty();
+                                            if has_menu {
+                                                image_response.context_menu(|ui| {
+                                                    if let Some(menu_response) =
+                                                        Self::context_menu_ui(
+                                                            ui, photo, placements,
+                                                        )
+                                                    {
+                                                        response = Some(menu_response);
+                                                        ui.close_menu();
+                                                    }
+                                                });
+                                            }
+
+                                            if image_response.double_clicked() {
+                                                response = Some(
+                                                    ImageGalleryResponse::SelectPhotoPrimaryAction(
+                                                        photo.clone(),
+                                                    ),
+                                                );
+                                            } else if image_response.secondary_clicked()
+                                                && !has_menu
+                                            {
+                                                response =
+                                                    Some(ImageGalleryResponse::SelectPhotoSecondaryAction(
+                                                        photo.clone(),
+                                                    ));
+                                            }
                                         });
-                                    }
-
-                                    row.col(|ui| {
-                                        ui.add(Spacer::new(spacer_width, row_height));
                                     });
                                 }
-                            });
-                        })
+
+                                row.col(|ui| {
+                                    ui.add(Spacer::new(spacer_width, row_height));
+                                });
+                            }
+                        });
+                    });
+
+                    grid_keys.update_focus(ui, table_rect, clicked_photo);
+
+                    match preview_photo {
+                        Some(photo) => {
+                            if Self::quick_preview_ui(ui, table_rect, &photo, &photo_manager) {
+                                state.quick_preview = false;
+                            }
+                        }
+                        None => state.quick_preview = false,
+                    }
                 });
                 ui.painter().rect_filled(
                     ui.available_rect_before_wrap(),
                     0.0,
-                    Color32::from_gray(40),
+                    theme::color::chrome(ui.visuals()),
                 );
 
-                ui.with_layout(Layout::right_to_left(egui::Align::Center), |ui| {
+                ui.horizontal_centered(|ui| {
                     ui.add_space(20.0);
-                    ui.add(
-                        Image::from(Asset::larger())
-                            .tint(Color32::WHITE)
-                            .maintain_aspect_ratio(true)
-                            .fit_to_exact_size(Vec2::splat(20.0)),
-                    );
-                    ui.add(Slider::new(&mut state.scale, 0.5..=1.5).show_value(true));
-                    ui.add(
-                        Image::from(Asset::smaller())
-                            .tint(Color32::WHITE)
-                            .maintain_aspect_ratio(true)
-                            .fit_to_exact_size(Vec2::splat(20.0)),
-                    );
+                    Self::sort_and_group_controls(ui, &photo_manager);
+
+                    ui.with_layout(Layout::right_to_left(egui::Align::Center), |ui| {
+                        ui.add_space(20.0);
+                        ui.add(
+                            Image::from(Asset::larger())
+                                .tint(Color32::WHITE)
+                                .maintain_aspect_ratio(true)
+                                .fit_to_exact_size(Vec2::splat(20.0)),
+                        );
+                        ui.add(Slider::new(&mut state.scale, 0.5..=1.5).show_value(true));
+                        ui.add(
+                            Image::from(Asset::smaller())
+                                .tint(Color32::WHITE)
+                                .maintain_aspect_ratio(true)
+                                .fit_to_exact_size(Vec2::splat(20.0)),
+                        );
+                    });
                 });
             });
         } else {
@@ -224,4 +412,346 @@ impl<'a> ImageGallery<'a> {
 
         response
     }
+
+    // Shows the photo as large as it fits over the gallery, returns whether it was clicked away
+    fn quick_preview_ui(
+        ui: &mut Ui,
+        rect: Rect,
+        photo: &Photo,
+        photo_manager: &Singleton<PhotoManager>,
+    ) -> bool {
+        let texture = photo_manager.with_lock_mut(|photo_manager| {
+            photo_manager.texture_for_photo_with_thumbail_backup(photo, ui.ctx())
+        });
+
+        Area::new(Id::new("image_gallery_quick_preview"))
+            .order(Order::Foreground)
+            .fixed_pos(rect.min)
+            .show(ui.ctx(), |ui| {
+                let (rect, response) = ui.allocate_exact_size(rect.size(), Sense::click());
+                ui.painter()
+                    .rect_filled(rect, 0.0, Color32::from_black_alpha(220));
+
+                if let Ok(Some(texture)) = texture {
+                    photo.paint_fitted(ui, texture, rect.shrink(40.0));
+                }
+
+                ui.painter().text(
+                    rect.center_bottom() - Vec2::new(0.0, 12.0),
+                    Align2::CENTER_BOTTOM,
+                    photo.file_name(),
+                    FontId::proportional(14.0),
+                    Color32::WHITE,
+                );
+
+                response.clicked()
+            })
+            .inner
+    }
+
+    // "Placed on page 3" or "Placed on pages 3, 7", a photo placed twice on a page counts once
+    fn placements_label(placements: &[PhotoPlacement]) -> String {
+        let mut pages: Vec<usize> = placements
+            .iter()
+            .map(|placement| placement.page_index + 1)
+            .collect();
+        pages.dedup();
+
+        let pages: Vec<String> = pages.iter().map(usize::to_string).collect();
+        if pages.len() == 1 {
+            format!("Placed on page {}", pages[0])
+        } else {
+            format!("Placed on pages {}", pages.join(", "))
+        }
+    }
+
+    fn context_menu_ui(
+        ui: &mut Ui,
+        photo: &Photo,
+        placements: &[PhotoPlacement],
+    ) -> Option<ImageGalleryResponse> {
+        let mut response = None;
+
+        // The menu takes the place of the secondary action so it's offered first
+        if ui.button("Open").clicked() {
+            response = Some(ImageGalleryResponse::SelectPhotoSecondaryAction(
+                photo.clone(),
+            ));
+        }
+
+        if photo.is_panorama() {
+            ui.separator();
+            for placement in PanoramaPlacement::iter() {
+                if ui.button(placement.to_string()).clicked() {
+                    response = Some(ImageGalleryResponse::PlacePanorama(
+                        photo.clone(),
+                        placement,
+                    ));
+                }
+            }
+        }
+
+        if !placements.is_empty() {
+            ui.separator();
+        }
+
+        for placement in placements {
+            if ui
+                .button(format!("Go to Page {}", placement.page_index + 1))
+                .clicked()
+            {
+                response = Some(ImageGalleryResponse::GoToPlacement(*placement));
+            }
+        }
+
+        response
+    }
+
+    // Pressing a label's key labels the selected photos, or clears the label if they all have it
+    fn color_label_shortcuts(
+        ui: &mut Ui,
+        photo_manager: &Singleton<PhotoManager>,
+        selected_images: &HashSet<PathBuf>,
+    ) {
+        if selected_images.is_empty() {
+            return;
+        }
+
+        let Some(color_label) = ColorLabel::pressed(ui.ctx()) else {
+            return;
+        };
+
+        photo_manager.with_lock_mut(|photo_manager| {
+            let all_labeled = selected_images.iter().all(|path| {
+                photo_manager
+                    .photos
+                    .get(path)
+                    .is_some_and(|photo| photo.color_label == Some(color_label))
+            });
+
+            photo_manager.set_color_label(
+                selected_images,
+                if all_labeled { None } else { Some(color_label) },
+            );
+        });
+    }
+
+    fn search_bar(ui: &mut Ui, photo_manager: &Singleton<PhotoManager>) {
+        let (mut text, error, shown_photos, total_photos) =
+            photo_manager.with_lock(|photo_manager| {
+                (
+                    photo_manager.search_text().to_string(),
+                    photo_manager.search_error().map(|error| error.to_string()),
+                    photo_manager
+                        .grouped_photos()
+                        .values()
+                        .map(|group| group.len())
+                        .sum::<usize>(),
+                    photo_manager.photos.len(),
+                )
+            });
+
+        ui.horizontal(|ui| {
+            ui.add_space(10.0);
+
+            let response = ui.add(
+                TextEdit::singleline(&mut text)
+                    .hint_text("Search, e.g. rating>=maybe AND date:2023-06..2023-07 AND orientation:landscape AND tag:beach")
+                    .desired_width((ui.available_width() - 260.0).max(100.0)),
+            );
+
+            if response.changed() {
+                photo_manager.with_lock_mut(|photo_manager| photo_manager.set_search(text.clone()));
+            }
+
+            ui.menu_button("Saved", |ui| {
+                if Self::saved_searches_ui(ui, photo_manager, &text) {
+                    ui.close_menu();
+                }
+            });
+
+            ui.menu_button("Label", |ui| {
+                Self::color_label_filter_menu(ui, photo_manager, &text);
+            });
+
+            if ui
+                .button("Camera")
+                .on_hover_text("Filter by camera, lens, ISO, aperture and focal length")
+                .clicked()
+            {
+                ModalManager::push(PhotoFilterModal::new());
+            }
+
+            if shown_photos != total_photos {
+                ui.label(format!("{} of {} photos", shown_photos, total_photos));
+            }
+        });
+
+        if let Some(error) = error {
+            ui.horizontal(|ui| {
+                ui.add_space(10.0);
+                ui.label(RichText::new(error).color(theme::color::WARNING));
+            });
+        }
+    }
+
+    // Adds a label term to the search so it narrows down whatever is already being searched for
+    fn color_label_filter_menu(
+        ui: &mut Ui,
+        photo_manager: &Singleton<PhotoManager>,
+        current: &str,
+    ) {
+        let terms = ColorLabel::iter()
+            .map(|label| (Some(label), label.to_string()))
+            .chain([(None, "No Label".to_string())]);
+
+        for (label, name) in terms {
+            let term = format!(
+                "label:{}",
+                label.map_or("none".to_string(), |label| label.to_string().to_lowercase())
+            );
+
+            let response = ui.horizontal(|ui| {
+                let (rect, _) = ui.allocate_exact_size(Vec2::splat(12.0), Sense::hover());
+                if let Some(label) = label {
+                    label.paint_dot(ui.painter(), rect.center(), 5.0);
+                }
+                ui.button(name)
+            });
+
+            if response.inner.clicked() {
+                let search = match current.trim() {
+                    "" => term,
+                    current => format!("{} {}", current, term),
+                };
+                photo_manager.with_lock_mut(|photo_manager| photo_manager.set_search(search));
+                ui.close_menu();
+            }
+        }
+    }
+
+    /// Lists the saved searches to pick from and saves `current` under a new name. Returns true
+    /// when one of them was picked and is now being searched for.
+    pub fn saved_searches_ui(
+        ui: &mut Ui,
+        photo_manager: &Singleton<PhotoManager>,
+        current: &str,
+    ) -> bool {
+        let config: Singleton<AutoPersisting<Config>> = Dependency::get();
+        let saved_searches = config.with_lock_mut(|config| {
+            config
+                .read()
+                .map(|config| config.saved_searches())
+                .unwrap_or_default()
+        });
+
+        if saved_searches.is_empty() {
+            ui.label("No saved searches");
+        }
+
+        let mut deleted = None;
+        let mut picked = false;
+
+        for (name, query) in &saved_searches {
+            ui.horizontal(|ui| {
+                if ui.button(name).on_hover_text(query).clicked() {
+                    photo_manager
+                        .with_lock_mut(|photo_manager| photo_manager.set_search(query.clone()));
+                    picked = true;
+                }
+
+                if ui.small_button("🗑").on_hover_text("Delete").clicked() {
+                    deleted = Some(name.clone());
+                }
+            });
+        }
+
+        if let Some(name) = deleted {
+            config.with_lock_mut(|config| {
+                let _ = config.modify(ConfigModification::DeleteSearch(name));
+            });
+        }
+
+        ui.separator();
+
+        let name_id = ui.id().with("saved_search_name");
+        let mut name: String = ui.data(|data| data.get_temp(name_id)).unwrap_or_default();
+
+        ui.add_enabled_ui(!current.trim().is_empty(), |ui| {
+            ui.horizontal(|ui| {
+                ui.add(
+                    TextEdit::singleline(&mut name)
+                        .hint_text("Name")
+                        .desired_width(120.0),
+                );
+
+                if ui
+                    .add_enabled(!name.trim().is_empty(), Button::new("Save Search"))
+                    .clicked()
+                {
+                    config.with_lock_mut(|config| {
+                        let _ = config.modify(ConfigModification::SaveSearch(
+                            name.trim().to_string(),
+                            current.to_string(),
+                        ));
+                    });
+                    name.clear();
+                }
+            });
+        });
+
+        ui.data_mut(|data| data.insert_temp(name_id, name));
+
+        picked
+    }
+
+    fn sort_and_group_controls(ui: &mut Ui, photo_manager: &Singleton<PhotoManager>) {
+        let (mut sort, mut grouping) = photo_manager.with_lock(|photo_manager| {
+            (photo_manager.photos_sort(), photo_manager.photo_grouping())
+        });
+        let current_sort = sort;
+        let current_grouping = grouping;
+
+        ui.label("Sort:");
+        ComboBox::from_id_salt("gallery_sort_field")
+            .selected_text(sort.field.to_string())
+            .show_ui(ui, |ui| {
+                for field in PhotosSortField::iter() {
+                    ui.selectable_value(&mut sort.field, field, field.to_string());
+                }
+            });
+
+        let direction_text = match sort.direction {
+            SortDirection::Ascending => "⬆",
+            SortDirection::Descending => "⬇",
+        };
+        if ui
+            .button(direction_text)
+            .on_hover_text(format!("{:?}", sort.direction))
+            .clicked()
+        {
+            sort.direction = sort.direction.toggled();
+        }
+
+        ui.add_space(10.0);
+
+        ui.label("Group:");
+        ComboBox::from_id_salt("gallery_grouping")
+            .selected_text(grouping.to_string())
+            .show_ui(ui, |ui| {
+                for option in PhotosGrouping::iter() {
+                    ui.selectable_value(&mut grouping, option, option.to_string());
+                }
+            });
+
+        if sort != current_sort {
+            photo_manager.with_lock_mut(|photo_manager| photo_manager.sort_photos_by(sort));
+        }
+
+        if grouping != current_grouping {
+            photo_manager.with_lock_mut(|photo_manager| {
+                photo_manager.group_photos_by(grouping);
+            });
+        }
+    }
 }
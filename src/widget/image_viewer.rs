@@ -1,24 +1,41 @@
+use std::f32::consts::TAU;
+
 use eframe::{
-    egui::{self, Image, Key, Response, Sense, Widget},
+    egui::{self, Image, Key, Painter, PointerButton, Response, Sense, Widget},
     emath::Rot2,
-    epaint::{Color32, Mesh, Pos2, Rect, Shape, Vec2},
+    epaint::{Color32, FontId, Mesh, Pos2, Rect, Shape, Stroke, TextureId, Vec2, Vertex},
 };
 
 use crate::{
     dependencies::{Dependency, Singleton, SingletonFor},
     photo::{
         MaxPhotoDimension::{Height, Width},
-        Photo,
+        Photo, PhotoMetadataFieldLabel,
     },
     photo_manager::PhotoManager,
     theme::color,
     utils::RectExt,
 };
 
+use super::pan_zoom::{PanInertia, PanZoomInput};
+
+const LOUPE_RADIUS: f32 = 90.0;
+const LOUPE_SEGMENTS: u32 = 48;
+// The loupe always magnifies at least this much, even when the photo is already shown at 100%
+const LOUPE_MIN_MAGNIFICATION: f32 = 2.0;
+
+const OVERLAY_MARGIN: f32 = 12.0;
+const OVERLAY_PADDING: f32 = 8.0;
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct ImageViewerState {
     pub scale: f32,
     pub offset: Vec2,
+    // Shows one pixel of the photo per screen pixel. This is kept when moving to another photo
+    // so the same detail can be compared between shots.
+    pub actual_size: bool,
+    pub show_loupe: bool,
+    pub show_exif: bool,
 }
 
 impl Default for ImageViewerState {
@@ -26,6 +43,20 @@ impl Default for ImageViewerState {
         Self {
             scale: 1.0,
             offset: Vec2::ZERO,
+            actual_size: false,
+            show_loupe: false,
+            show_exif: false,
+        }
+    }
+}
+
+impl ImageViewerState {
+    /// Switches between showing the photo at 100% and fitting it in the viewer
+    pub fn toggle_actual_size(&mut self) {
+        self.actual_size = !self.actual_size;
+        if !self.actual_size {
+            self.scale = 1.0;
+            self.offset = Vec2::ZERO;
         }
     }
 }
@@ -34,6 +65,10 @@ pub enum Request {
     Exit,
     Previous,
     Next,
+    // Pins the photo for an A/B comparison, or unpins it
+    PinCompare,
+    // Switches between the pinned photo and the one being viewed
+    SwapCompare,
 }
 
 pub struct ImageViewerResponse {
@@ -44,6 +79,7 @@ pub struct ImageViewerResponse {
 pub struct ImageViewer<'a> {
     photo: &'a Photo,
     state: &'a mut ImageViewerState,
+    compare_label: Option<String>,
     photo_manager: Singleton<PhotoManager>,
 }
 
@@ -52,11 +88,35 @@ impl<'a> ImageViewer<'a> {
         Self {
             photo,
             state,
+            compare_label: None,
             photo_manager: Dependency::<PhotoManager>::get(),
         }
     }
 
+    /// Labels the photo as one side of an A/B comparison
+    pub fn compare_label(mut self, label: impl Into<String>) -> Self {
+        self.compare_label = Some(label.into());
+        self
+    }
+
     pub fn show(self, ui: &mut eframe::egui::Ui) -> ImageViewerResponse {
+        // The toggles are read before drawing so they show up in this frame
+        if !ui.ctx().wants_keyboard_input() {
+            let (actual_size, loupe, exif) = ui.input(|input| {
+                (
+                    input.key_pressed(Key::Z),
+                    input.key_pressed(Key::L),
+                    input.key_pressed(Key::I),
+                )
+            });
+
+            if actual_size {
+                self.state.toggle_actual_size();
+            }
+            self.state.show_loupe ^= loupe;
+            self.state.show_exif ^= exif;
+        }
+
         let response = self.ui(ui);
 
         let mut viewer_response = ImageViewerResponse {
@@ -70,6 +130,12 @@ impl<'a> ImageViewer<'a> {
             viewer_response.request = Some(Request::Previous);
         } else if ui.input(|input| input.key_pressed(Key::ArrowRight)) {
             viewer_response.request = Some(Request::Next);
+        } else if !ui.ctx().wants_keyboard_input() {
+            if ui.input(|input| input.key_pressed(Key::C)) {
+                viewer_response.request = Some(Request::PinCompare);
+            } else if ui.input(|input| input.key_pressed(Key::X)) {
+                viewer_response.request = Some(Request::SwapCompare);
+            }
         }
 
         viewer_response
@@ -85,6 +151,99 @@ impl<'a> ImageViewer<'a> {
 
         new_rect
     }
+
+    // Draws a circle at `pos` showing the photo under it magnified
+    fn paint_loupe(
+        &self,
+        painter: &Painter,
+        texture_id: TextureId,
+        image_rect: Rect,
+        pos: Pos2,
+        magnification: f32,
+    ) {
+        let rotation = Rot2::from_angle(self.photo.metadata.rotation().radians());
+        let mirrored = self.photo.metadata.rotation().is_mirrored();
+        let center = image_rect.center();
+
+        // The photo's mesh is rotated after its uvs are set so undo the rotation to find them
+        let uv_at = |screen_pos: Pos2| {
+            let unrotated = center + rotation.inverse() * (screen_pos - center);
+            let uv = ((unrotated - image_rect.min) / image_rect.size()).to_pos2();
+            if mirrored {
+                Pos2::new(1.0 - uv.x, uv.y)
+            } else {
+                uv
+            }
+        };
+
+        let mut mesh = Mesh::with_texture(texture_id);
+        mesh.vertices.push(Vertex {
+            pos,
+            uv: uv_at(pos),
+            color: Color32::WHITE,
+        });
+
+        for segment in 0..LOUPE_SEGMENTS {
+            let direction = Vec2::angled(segment as f32 / LOUPE_SEGMENTS as f32 * TAU);
+            mesh.vertices.push(Vertex {
+                pos: pos + direction * LOUPE_RADIUS,
+                uv: uv_at(pos + direction * LOUPE_RADIUS / magnification),
+                color: Color32::WHITE,
+            });
+            mesh.add_triangle(0, segment + 1, (segment + 1) % LOUPE_SEGMENTS + 1);
+        }
+
+        painter.circle_filled(pos, LOUPE_RADIUS, Color32::BLACK);
+        painter.add(Shape::mesh(mesh));
+        painter.circle_stroke(pos, LOUPE_RADIUS, Stroke::new(2.0, Color32::WHITE));
+    }
+
+    // The shooting details in the bottom left corner, for comparing shots while culling
+    fn paint_exif(&self, painter: &Painter, rect: Rect) {
+        let metadata = &self.photo.metadata;
+
+        let mut lines: Vec<String> = [
+            PhotoMetadataFieldLabel::Camera,
+            PhotoMetadataFieldLabel::FocalLength,
+            PhotoMetadataFieldLabel::Aperture,
+            PhotoMetadataFieldLabel::ShutterSpeed,
+            PhotoMetadataFieldLabel::ISO,
+            PhotoMetadataFieldLabel::DateTime,
+        ]
+        .into_iter()
+        .filter_map(|label| metadata.get(label).map(|value| format!("{label}: {value}")))
+        .collect();
+
+        lines.insert(0, self.photo.file_name().to_string());
+        lines.push(format!(
+            "{} x {}",
+            metadata.rotated_width(),
+            metadata.rotated_height()
+        ));
+
+        let galley = painter.layout(
+            lines.join("\n"),
+            FontId::proportional(13.0),
+            Color32::WHITE,
+            (rect.width() - (OVERLAY_MARGIN + OVERLAY_PADDING) * 2.0).max(0.0),
+        );
+
+        let position = Pos2::new(
+            rect.left() + OVERLAY_MARGIN + OVERLAY_PADDING,
+            rect.bottom() - OVERLAY_MARGIN - OVERLAY_PADDING - galley.size().y,
+        );
+        Self::paint_overlay(painter, Rect::from_min_size(position, galley.size()));
+        painter.galley(position, galley, Color32::WHITE);
+    }
+
+    // A dark backing so text stays readable over the photo
+    fn paint_overlay(painter: &Painter, content_rect: Rect) {
+        painter.rect_filled(
+            content_rect.expand(OVERLAY_PADDING),
+            4.0,
+            Color32::from_black_alpha(160),
+        );
+    }
 }
 
 impl<'a> Widget for ImageViewer<'a> {
@@ -153,43 +312,73 @@ impl<'a> Widget for ImageViewer<'a> {
             }
         };
 
+        // The scale, relative to fitting the photo in the viewer, that shows it at 100%
+        let actual_scale = Some(
+            self.photo.metadata.rotated_width() as f32
+                / (image_rect.width() * ui.ctx().pixels_per_point()),
+        )
+        .filter(|scale| scale.is_finite() && *scale > 0.0);
+
         image_rect = Self::translate_from_center(self.state.offset, image_rect, rect);
 
-        ui.input(|i| {
-            if i.pointer.hover_pos().is_some() && i.raw_scroll_delta.y != 0.0 {
-                let mouse_pos = i.pointer.hover_pos().unwrap();
+        let gesture = ui.input(PanZoomInput::read);
+        let hover_pos = ui.input(|i| i.pointer.hover_pos());
 
-                let rel_mouse_pos_before = image_rect.center() - mouse_pos;
+        if response.double_clicked() {
+            self.state.toggle_actual_size();
+        }
 
-                let mut scale_delta = 1.0;
+        // Zooming by hand leaves 100%, otherwise the scale follows the photo and the viewer's size
+        let zoom = match hover_pos.filter(|_| gesture.is_zooming()) {
+            Some(mouse_pos) => {
+                self.state.actual_size = false;
+                Some((mouse_pos, gesture.zoom_factor))
+            }
+            None => actual_scale
+                .filter(|actual_scale| {
+                    self.state.actual_size && (actual_scale - self.state.scale).abs() > f32::EPSILON
+                })
+                .map(|actual_scale| {
+                    let anchor = hover_pos
+                        .filter(|pos| rect.contains(*pos))
+                        .unwrap_or(rect.center());
+                    (anchor, actual_scale / self.state.scale)
+                }),
+        };
 
-                if i.raw_scroll_delta.y > 0.0 {
-                    scale_delta = 1.1;
-                } else if i.raw_scroll_delta.y < 0.0 {
-                    scale_delta = 0.9;
-                }
+        if let Some((mouse_pos, scale_delta)) = zoom {
+            let rel_mouse_pos_before = image_rect.center() - mouse_pos;
 
-                self.state.scale *= scale_delta;
+            self.state.scale *= scale_delta;
 
-                let scaled_width_diff = image_rect.width() * self.state.scale - image_rect.width();
-                let scaled_height_diff =
-                    image_rect.height() * self.state.scale - image_rect.height();
+            let scaled_width_diff = image_rect.width() * self.state.scale - image_rect.width();
+            let scaled_height_diff = image_rect.height() * self.state.scale - image_rect.height();
 
-                image_rect = image_rect
-                    .expand2(Vec2::new(scaled_width_diff * 0.5, scaled_height_diff * 0.5));
+            image_rect =
+                image_rect.expand2(Vec2::new(scaled_width_diff * 0.5, scaled_height_diff * 0.5));
 
-                let rel_mouse_pos_after = rel_mouse_pos_before * scale_delta;
+            let rel_mouse_pos_after = rel_mouse_pos_before * scale_delta;
 
-                self.state.offset += rel_mouse_pos_after - rel_mouse_pos_before;
-            } else {
-                let scaled_width_diff = image_rect.width() * self.state.scale - image_rect.width();
-                let scaled_height_diff =
-                    image_rect.height() * self.state.scale - image_rect.height();
+            self.state.offset += rel_mouse_pos_after - rel_mouse_pos_before;
+        } else {
+            let scaled_width_diff = image_rect.width() * self.state.scale - image_rect.width();
+            let scaled_height_diff = image_rect.height() * self.state.scale - image_rect.height();
 
-                image_rect = image_rect
-                    .expand2(Vec2::new(scaled_width_diff * 0.5, scaled_height_diff * 0.5));
-            }
-        });
+            image_rect =
+                image_rect.expand2(Vec2::new(scaled_width_diff * 0.5, scaled_height_diff * 0.5));
+        }
+
+        // Two finger trackpad scrolling and middle mouse dragging pan the same way dragging does
+        let mut pan_delta = None;
+        if hover_pos.is_some() && gesture.pan != Vec2::ZERO {
+            pan_delta = Some(gesture.pan);
+        }
+        if response.dragged_by(PointerButton::Primary) {
+            pan_delta = Some(pan_delta.unwrap_or_default() + response.drag_delta());
+        }
+
+        let pan = pan_delta.unwrap_or_default()
+            + PanInertia::update(ui.ctx(), response.id.with("pan_inertia"), pan_delta);
 
         image_rect = Self::translate_from_center(self.state.offset, image_rect, rect);
 
@@ -197,7 +386,7 @@ impl<'a> Widget for ImageViewer<'a> {
 
         // Adjust image_rect so it always fills rect, or is centered in rect
         if image_rect.width() >= rect.width() {
-            self.state.offset.x += response.drag_delta().x;
+            self.state.offset.x += pan.x;
             image_rect = Self::translate_from_center(self.state.offset, image_rect, rect);
 
             if image_rect.right() < rect.right() {
@@ -214,7 +403,7 @@ impl<'a> Widget for ImageViewer<'a> {
         }
 
         if image_rect.height() >= rect.height() {
-            self.state.offset.y += response.drag_delta().y;
+            self.state.offset.y += pan.y;
             image_rect = Self::translate_from_center(self.state.offset, image_rect, rect);
 
             if image_rect.bottom() < rect.bottom() {
@@ -250,14 +439,15 @@ impl<'a> Widget for ImageViewer<'a> {
             );
         }
 
+        let painter = ui.painter().with_clip_rect(rect);
+
         match self
             .photo_manager
             .with_lock_mut(|photo_manager| photo_manager.texture_for(self.photo, ui.ctx()))
         {
             Ok(Some(texture)) => {
-                let uv = Rect::from_min_max(Pos2::new(0.0, 0.0), Pos2 { x: 1.0, y: 1.0 });
+                let uv = self.photo.metadata.rotation().mirror_uv();
 
-                let painter = ui.painter().with_clip_rect(rect);
                 let mut mesh = Mesh::with_texture(texture.id);
                 mesh.add_rect_with_uv(image_rect, uv, Color32::WHITE);
                 mesh.rotate(
@@ -265,12 +455,28 @@ impl<'a> Widget for ImageViewer<'a> {
                     image_rect.min + Vec2::splat(0.5) * image_rect.size(),
                 );
                 painter.add(Shape::mesh(mesh));
+
+                let shown_rect =
+                    image_rect.rotate_bb_around_center(self.photo.metadata.rotation().radians());
+                let loupe_pos =
+                    hover_pos.filter(|pos| rect.contains(*pos) && shown_rect.contains(*pos));
+
+                if let (true, Some(loupe_pos)) = (self.state.show_loupe, loupe_pos) {
+                    // The loupe shows the photo at 100% unless it's already zoomed in further
+                    let magnification = actual_scale
+                        .map(|actual_scale| actual_scale / self.state.scale)
+                        .unwrap_or_default()
+                        .max(LOUPE_MIN_MAGNIFICATION);
+
+                    self.paint_loupe(&painter, texture.id, image_rect, loupe_pos, magnification);
+                }
             }
             Ok(None) => match self.photo_manager.with_lock_mut(|photo_manager| {
                 photo_manager.thumbnail_texture_for(self.photo, ui.ctx())
             }) {
                 Ok(Some(texture)) => {
                     Image::from_texture(texture)
+                        .uv(self.photo.metadata.rotation().mirror_uv())
                         .rotate(self.photo.metadata.rotation().radians(), Vec2::splat(0.5))
                         .paint_at(ui, image_rect);
 
@@ -283,7 +489,7 @@ impl<'a> Widget for ImageViewer<'a> {
                         image_rect
                             .rotate_bb_around_center(self.photo.metadata.rotation().radians()),
                         0.0,
-                        color::PLACEHOLDER,
+                        color::placeholder(ui.visuals()),
                     );
                 }
             },
@@ -298,6 +504,21 @@ impl<'a> Widget for ImageViewer<'a> {
             }
         }
 
+        if let Some(compare_label) = &self.compare_label {
+            let galley = painter.layout_no_wrap(
+                compare_label.clone(),
+                FontId::proportional(16.0),
+                Color32::WHITE,
+            );
+            let position = rect.left_top() + Vec2::splat(OVERLAY_MARGIN + OVERLAY_PADDING);
+            Self::paint_overlay(&painter, Rect::from_min_size(position, galley.size()));
+            painter.galley(position, galley, Color32::WHITE);
+        }
+
+        if self.state.show_exif {
+            self.paint_exif(&painter, rect);
+        }
+
         response
     }
 }
@@ -0,0 +1,278 @@
+use eframe::egui::{
+    Align2, Color32, Context, CursorIcon, FontId, Id, Painter, Pos2, Rect, Sense, Stroke, Ui, Vec2,
+};
+
+use crate::{
+    cursor_manager::CursorManager,
+    dependencies::{Dependency, SingletonFor},
+    id::LayerId,
+    model::{measurement::Measurement, page::Page, unit::Unit},
+    theme,
+};
+
+const MEASUREMENT_COLOR: Color32 = Color32::from_rgb(0xff, 0x8f, 0x00);
+
+// Length of the ticks across the ends of a measurement, in screen points
+const TICK_SIZE: f32 = 10.0;
+
+// Drags shorter than this on screen are taken as clicks and don't add a measurement
+const MIN_DRAG_LENGTH: f32 = 4.0;
+
+/// How measurements are labeled, set from the tool options while the measure tool is chosen
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct MeasureToolOptions {
+    // The page's own unit when not set
+    pub unit: Option<Unit>,
+}
+
+impl MeasureToolOptions {
+    fn id() -> Id {
+        Id::new("measure_tool_options")
+    }
+
+    pub fn current(ctx: &Context) -> Self {
+        ctx.data(|data| data.get_temp(Self::id()))
+            .unwrap_or_default()
+    }
+
+    pub fn set(self, ctx: &Context) {
+        ctx.data_mut(|data| data.insert_temp(Self::id(), self));
+    }
+
+    pub fn unit(&self, page: &Page) -> Unit {
+        self.unit.unwrap_or(page.unit())
+    }
+}
+
+// A measurement being dragged out
+#[derive(Debug, Clone, Copy)]
+struct MeasureDrag {
+    // In page pixels
+    start: Pos2,
+    // The layer the drag started on and its bounds in page pixels
+    start_layer: Option<(LayerId, Rect)>,
+}
+
+/// Measures the page by dragging between two points. Dragging from one layer to another
+/// measures the gap between them instead, and holding shift keeps the line horizontal or
+/// vertical.
+pub struct MeasureTool<'a> {
+    id: Id,
+    page: &'a Page,
+    page_rect: Rect,
+    zoom: f32,
+}
+
+impl<'a> MeasureTool<'a> {
+    pub fn new(id: Id, page: &'a Page, page_rect: Rect, zoom: f32) -> Self {
+        Self {
+            id,
+            page,
+            page_rect,
+            zoom,
+        }
+    }
+
+    /// `hovered_layer` is the layer under the pointer and its bounds in page pixels. Returns the
+    /// measurement once the drag ends.
+    pub fn show(
+        &self,
+        ui: &mut Ui,
+        canvas_rect: Rect,
+        hovered_layer: Option<(LayerId, Rect)>,
+    ) -> Option<Measurement> {
+        let response = ui.interact(canvas_rect, self.id.with("input"), Sense::drag());
+        let to_page = |pos: Pos2| ((pos - self.page_rect.min) / self.zoom).to_pos2();
+
+        if response.hovered() || response.dragged() {
+            Dependency::<CursorManager>::get().with_lock_mut(|cursor_manager| {
+                cursor_manager.set_cursor(CursorIcon::Crosshair);
+            });
+        }
+
+        let mut drag = ui.data(|data| data.get_temp::<MeasureDrag>(self.id));
+
+        if response.drag_started() {
+            if let Some(pointer) = response.interact_pointer_pos() {
+                drag = Some(MeasureDrag {
+                    start: to_page(pointer),
+                    start_layer: hovered_layer,
+                });
+            }
+        }
+
+        let drag = drag?;
+        let constrain = ui.input(|input| input.modifiers.shift);
+        let measurement = response
+            .interact_pointer_pos()
+            .or(ui.ctx().pointer_latest_pos())
+            .map(|pointer| Self::measure(drag, to_page(pointer), hovered_layer, constrain));
+
+        // Released, or the drag was taken over by something else
+        if !response.dragged() {
+            ui.data_mut(|data| data.remove::<MeasureDrag>(self.id));
+            return measurement
+                .filter(|measurement| measurement.length() * self.zoom >= MIN_DRAG_LENGTH);
+        }
+
+        ui.data_mut(|data| data.insert_temp(self.id, drag));
+
+        if let Some(measurement) = measurement {
+            self.paint_preview(ui, drag, measurement, hovered_layer);
+        }
+
+        None
+    }
+
+    fn measure(
+        drag: MeasureDrag,
+        end: Pos2,
+        hovered_layer: Option<(LayerId, Rect)>,
+        constrain: bool,
+    ) -> Measurement {
+        match (drag.start_layer, hovered_layer) {
+            (Some((from, from_rect)), Some((to, to_rect))) if from != to => {
+                Measurement::between_rects(from_rect, to_rect)
+            }
+            _ => {
+                let delta = end - drag.start;
+                let end = match constrain {
+                    true if delta.x.abs() >= delta.y.abs() => Pos2::new(end.x, drag.start.y),
+                    true => Pos2::new(drag.start.x, end.y),
+                    false => end,
+                };
+                Measurement::new(drag.start, end)
+            }
+        }
+    }
+
+    fn paint_preview(
+        &self,
+        ui: &Ui,
+        drag: MeasureDrag,
+        measurement: Measurement,
+        hovered_layer: Option<(LayerId, Rect)>,
+    ) {
+        let focused = theme::color::focused(ui.visuals());
+        let to_screen = |rect: Rect| {
+            Rect::from_min_max(
+                self.page_rect.min + rect.min.to_vec2() * self.zoom,
+                self.page_rect.min + rect.max.to_vec2() * self.zoom,
+            )
+        };
+
+        // Outline the layers being measured between
+        if let (Some((from, from_rect)), Some((to, to_rect))) = (drag.start_layer, hovered_layer) {
+            if from != to {
+                for rect in [from_rect, to_rect] {
+                    ui.painter()
+                        .rect_stroke(to_screen(rect), 0.0, Stroke::new(1.0, focused));
+                }
+            }
+        }
+
+        let unit = MeasureToolOptions::current(ui.ctx()).unit(self.page);
+        paint_measurement(
+            ui.painter(),
+            &measurement,
+            &measurement.label(self.page, unit),
+            self.page_rect,
+            self.zoom,
+        );
+    }
+}
+
+/// The measurements left on the page. While the measure tool is chosen a measurement's label
+/// shows how far across and down it goes and can be right-clicked to remove it.
+pub struct MeasurementOverlay<'a> {
+    measurements: &'a mut Vec<Measurement>,
+    page: &'a Page,
+    page_rect: Rect,
+    zoom: f32,
+    id: Id,
+}
+
+impl<'a> MeasurementOverlay<'a> {
+    pub fn new(
+        measurements: &'a mut Vec<Measurement>,
+        page: &'a Page,
+        page_rect: Rect,
+        zoom: f32,
+        id: Id,
+    ) -> Self {
+        Self {
+            measurements,
+            page,
+            page_rect,
+            zoom,
+            id,
+        }
+    }
+
+    pub fn show(&mut self, ui: &mut Ui, editable: bool) {
+        let unit = MeasureToolOptions::current(ui.ctx()).unit(self.page);
+        let mut removed = None;
+
+        for (index, measurement) in self.measurements.iter().enumerate() {
+            let label_rect = paint_measurement(
+                ui.painter(),
+                measurement,
+                &measurement.label(self.page, unit),
+                self.page_rect,
+                self.zoom,
+            );
+
+            if !editable {
+                continue;
+            }
+
+            ui.interact(label_rect, self.id.with(index), Sense::click())
+                .on_hover_text(measurement.extent_label(self.page, unit))
+                .context_menu(|ui| {
+                    if ui.button("Remove Measurement").clicked() {
+                        removed = Some(index);
+                        ui.close_menu();
+                    }
+                });
+        }
+
+        if let Some(index) = removed {
+            self.measurements.remove(index);
+        }
+    }
+}
+
+// Draws the line with ticks across its ends and the label in the middle. Returns where the
+// label was drawn.
+fn paint_measurement(
+    painter: &Painter,
+    measurement: &Measurement,
+    label: &str,
+    page_rect: Rect,
+    zoom: f32,
+) -> Rect {
+    let start = page_rect.min + measurement.start.to_vec2() * zoom;
+    let end = page_rect.min + measurement.end.to_vec2() * zoom;
+    let stroke = Stroke::new(1.5, MEASUREMENT_COLOR);
+
+    painter.line_segment([start, end], stroke);
+
+    let direction = (end - start).normalized();
+    if direction != Vec2::ZERO {
+        let tick = direction.rot90() * TICK_SIZE / 2.0;
+        for point in [start, end] {
+            painter.line_segment([point - tick, point + tick], stroke);
+        }
+    }
+
+    let galley = painter.layout_no_wrap(
+        label.to_string(),
+        FontId::proportional(12.0),
+        Color32::WHITE,
+    );
+    let label_rect = Align2::CENTER_CENTER.anchor_size(start + (end - start) / 2.0, galley.size());
+    painter.rect_filled(label_rect.expand(3.0), 3.0, MEASUREMENT_COLOR);
+    painter.galley(label_rect.min, galley, Color32::WHITE);
+
+    label_rect.expand(3.0)
+}
@@ -1,6 +1,10 @@
 pub mod canvas;
 pub mod canvas_info;
+pub mod comments;
+pub mod decorations;
 pub mod gallery_image;
+pub mod guide_manager;
+pub mod guides;
 pub mod image_gallery;
 pub mod image_viewer;
 pub mod pages;
@@ -10,7 +14,23 @@ pub mod segment_control;
 pub mod spacer;
 pub mod templates;
 pub mod transformable;
+pub mod trash;
 pub mod crop;
+pub mod crop_overlay;
 pub mod canvas_state;
 pub mod action_bar;
-pub mod auto_center;
\ No newline at end of file
+pub mod auto_center;
+pub mod pan_zoom;
+pub mod photo_picker;
+pub mod page_strip;
+pub mod review;
+pub mod smart_collections;
+pub mod pen_tool;
+pub mod measure_tool;
+pub mod photo_tray;
+pub mod outline;
+pub mod zoom_bar;
+pub mod grid_keys;
+pub mod statistics;
+pub mod snippets;
+pub mod diagnostics;
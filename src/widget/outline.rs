@@ -0,0 +1,401 @@
+use std::ops::Range;
+
+use indexmap::IndexMap;
+
+use eframe::egui;
+use egui::{
+    collapsing_header::CollapsingState, Button, Color32, FontId, Id, Layout, Pos2, Rect, Response,
+    RichText, ScrollArea, Stroke, TextEdit,
+};
+
+use crate::{
+    book_metadata::TextVariable,
+    book_theme::{ThemeColorRole, ThemeFontRole},
+    dependencies::{Dependency, SingletonFor},
+    id::{next_page_id, PageId},
+    project_settings::ProjectSettingsManager,
+    scene::canvas_scene::{CanvasHistoryKind, CanvasHistoryManager},
+    theme,
+};
+
+use super::{
+    canvas::CanvasState,
+    canvas_info::layers::{
+        CanvasText, Layer, LayerContent, LayerTransformEditState, TextHorizontalAlignment,
+        TextVerticalAlignment,
+    },
+    pages::PagesState,
+};
+
+// Title pages show the section name centered in the page, inside this fraction of it
+const TITLE_MARGIN: f32 = 0.1;
+// Font size of the section name as a fraction of the page height
+const TITLE_FONT_SCALE: f32 = 0.06;
+
+// Payload of a page dragged in the outline, kept apart from pages dragged in the pages panel
+struct DraggedPage(PageId);
+
+// Where a dragged page goes, see PagesState::move_page
+#[derive(Debug, Clone, Copy)]
+struct PageDrop {
+    index: usize,
+    starts_section: bool,
+}
+
+enum SectionAction {
+    AddTitlePage(usize),
+    Rename(usize, String),
+    Remove(usize),
+}
+
+// What happened in the outline this frame, applied once the sections have been shown
+#[derive(Default)]
+struct OutlineEvents {
+    dragged: Option<PageId>,
+    drop: Option<PageDrop>,
+    action: Option<SectionAction>,
+    select: Option<PageId>,
+}
+
+/// The pages of the book grouped into named sections or chapters. A section runs from the page
+/// that starts it to the next section, pages before the first section aren't in one.
+pub struct Outline<'a> {
+    state: &'a mut PagesState,
+    history_manager: &'a mut CanvasHistoryManager,
+}
+
+impl<'a> Outline<'a> {
+    pub fn new(state: &'a mut PagesState, history_manager: &'a mut CanvasHistoryManager) -> Self {
+        Self {
+            state,
+            history_manager,
+        }
+    }
+
+    pub fn show(&mut self, ui: &mut egui::Ui) {
+        self.state.update_sections();
+
+        let mut events = OutlineEvents::default();
+
+        let bottom_bar_height = 40.0;
+        let mut list_size = ui.available_size();
+        list_size.y -= bottom_bar_height;
+
+        ui.allocate_ui(list_size, |ui| {
+            ScrollArea::vertical()
+                .auto_shrink([false, false])
+                .show(ui, |ui| {
+                    if self.state.pages.is_empty() {
+                        ui.centered_and_justified(|ui| {
+                            ui.label("No pages");
+                        });
+                        return;
+                    }
+
+                    // Positions of the first page of each group, the pages before the first
+                    // section are a group of their own
+                    let mut starts: Vec<usize> = self
+                        .state
+                        .pages
+                        .values()
+                        .enumerate()
+                        .filter(|(_, page)| page.section_start.is_some())
+                        .map(|(index, _)| index)
+                        .collect();
+                    if starts.first() != Some(&0) {
+                        starts.insert(0, 0);
+                    }
+
+                    for (group, &start) in starts.iter().enumerate() {
+                        let end = starts
+                            .get(group + 1)
+                            .copied()
+                            .unwrap_or(self.state.pages.len());
+                        self.section_ui(ui, start..end, &mut events);
+                    }
+                });
+        });
+
+        if let (Some(page_id), Some(drop)) = (events.dragged, events.drop) {
+            let before = self.state.pages.clone();
+            self.state
+                .move_page(page_id, drop.index, drop.starts_section);
+            self.save_history(CanvasHistoryKind::MovePage, &before);
+        }
+
+        match events.action {
+            Some(SectionAction::AddTitlePage(start)) => self.add_title_page(start),
+            Some(SectionAction::Rename(start, name)) => {
+                let before = self.state.pages.clone();
+                if let Some((_, page)) = self.state.pages.get_index_mut(start) {
+                    page.section_start = Some(name);
+                }
+                self.save_history(CanvasHistoryKind::RenameSection, &before);
+            }
+            Some(SectionAction::Remove(start)) => {
+                let before = self.state.pages.clone();
+                if let Some((_, page)) = self.state.pages.get_index_mut(start) {
+                    page.section_start = None;
+                }
+                self.save_history(CanvasHistoryKind::RemoveSection, &before);
+            }
+            None => {}
+        }
+
+        if let Some(page_id) = events.select {
+            self.state.selected_pages.clear();
+            self.state.selected_page = page_id;
+        }
+
+        ui.painter().rect_filled(
+            ui.available_rect_before_wrap(),
+            0.0,
+            theme::color::chrome(ui.visuals()),
+        );
+
+        ui.with_layout(Layout::right_to_left(egui::Align::Center), |ui| {
+            ui.add_space(10.0);
+
+            let can_start = self
+                .state
+                .pages
+                .get(&self.state.selected_page)
+                .is_some_and(|page| page.section_start.is_none());
+
+            if ui
+                .add_enabled(can_start, Button::new("Start Section"))
+                .on_hover_text("Start a new section at the selected page")
+                .clicked()
+            {
+                let count = self
+                    .state
+                    .pages
+                    .values()
+                    .filter(|page| page.section_start.is_some())
+                    .count();
+                let before = self.state.pages.clone();
+                if let Some(page) = self.state.pages.get_mut(&self.state.selected_page) {
+                    page.section_start = Some(format!("Section {}", count + 1));
+                }
+                self.save_history(CanvasHistoryKind::StartSection, &before);
+            }
+        });
+
+        self.state.update_sections();
+    }
+
+    // Saves a step for a change to the sections, `before` being the pages as they were before it
+    fn save_history(&mut self, kind: CanvasHistoryKind, before: &IndexMap<PageId, CanvasState>) {
+        self.state.update_sections();
+        self.history_manager.save_book_history(
+            kind,
+            self.state.selected_page,
+            before,
+            &self.state.pages,
+        );
+    }
+
+    // The section's name and the pages in it, `pages` are their positions in the book
+    fn section_ui(&mut self, ui: &mut egui::Ui, pages: Range<usize>, events: &mut OutlineEvents) {
+        let start = pages.start;
+        let (start_id, start_page) = self.state.pages.get_index(start).unwrap();
+        let named = start_page.section_start.is_some();
+        let header_id = if named {
+            Id::new(("outline_section", *start_id))
+        } else {
+            Id::new("outline_no_section")
+        };
+
+        let (_, header, _) = CollapsingState::load_with_default_open(ui.ctx(), header_id, true)
+            .show_header(ui, |ui| {
+                match &start_page.section_start {
+                    Some(name) => {
+                        // The name being typed is kept aside and saved when done, so renaming
+                        // is a single step in the history
+                        let edit_id = header_id.with("name");
+                        let mut edited = ui
+                            .data(|data| data.get_temp::<String>(edit_id))
+                            .unwrap_or_else(|| name.clone());
+
+                        let response = ui.add(
+                            TextEdit::singleline(&mut edited)
+                                .hint_text("Section name")
+                                .desired_width(160.0),
+                        );
+
+                        if response.lost_focus() {
+                            ui.data_mut(|data| data.remove::<String>(edit_id));
+                            if edited != *name {
+                                events.action = Some(SectionAction::Rename(start, edited));
+                            }
+                        } else if response.has_focus() {
+                            ui.data_mut(|data| data.insert_temp(edit_id, edited));
+                        }
+                    }
+                    None => {
+                        ui.label(RichText::new("No Section").weak());
+                    }
+                }
+
+                ui.label(
+                    RichText::new(match pages.len() {
+                        1 => "1 page".to_string(),
+                        count => format!("{} pages", count),
+                    })
+                    .weak(),
+                );
+
+                if named {
+                    ui.menu_button("…", |ui| {
+                        if ui
+                            .button("Add Title Page")
+                            .on_hover_text(
+                                "Add a page showing the section name before its first page",
+                            )
+                            .clicked()
+                        {
+                            events.action = Some(SectionAction::AddTitlePage(start));
+                            ui.close_menu();
+                        }
+
+                        if ui
+                            .button("Remove Section")
+                            .on_hover_text("Keep the pages, they join the section before")
+                            .clicked()
+                        {
+                            events.action = Some(SectionAction::Remove(start));
+                            ui.close_menu();
+                        }
+                    });
+                }
+            })
+            .body(|ui| {
+                for index in pages.clone() {
+                    let (page_id, page) = self.state.pages.get_index(index).unwrap();
+                    let selected = self.state.selected_page == *page_id;
+
+                    let response = ui.dnd_drag_source(
+                        Id::new(("outline_page", *page_id)),
+                        DraggedPage(*page_id),
+                        |ui| {
+                            ui.horizontal(|ui| {
+                                let response =
+                                    ui.selectable_label(selected, format!("Page {}", index + 1));
+
+                                if let Some(color_label) = page.color_label {
+                                    ui.label(RichText::new("⏺").color(color_label.color()));
+                                }
+
+                                response
+                            })
+                            .inner
+                        },
+                    );
+
+                    if response.inner.clicked() {
+                        events.select = Some(*page_id);
+                    }
+
+                    // Dropping above the first page of a section puts the page in that
+                    // section, not at the end of the one before
+                    if let Some(drop) =
+                        Self::drop_target(ui, &response.response, index, named && index == start)
+                    {
+                        events.drop = Some(drop);
+                        if let Some(dragged) =
+                            response.response.dnd_release_payload::<DraggedPage>()
+                        {
+                            events.dragged = Some(dragged.0);
+                        }
+                    }
+                }
+            });
+
+        // Pages dropped on a section's name go first in it, so pages can be moved into collapsed
+        // sections too
+        if header.response.dnd_hover_payload::<DraggedPage>().is_some() {
+            ui.painter().rect_stroke(
+                header.response.rect.expand(2.0),
+                2.0,
+                Stroke::new(2.0, theme::color::focused(ui.visuals())),
+            );
+            events.drop = Some(PageDrop {
+                index: start,
+                starts_section: named,
+            });
+            if let Some(dragged) = header.response.dnd_release_payload::<DraggedPage>() {
+                events.dragged = Some(dragged.0);
+            }
+        }
+    }
+
+    // Where a page dropped on the row of the page at `index` goes, with a line showing it
+    fn drop_target(
+        ui: &egui::Ui,
+        response: &Response,
+        index: usize,
+        first_in_section: bool,
+    ) -> Option<PageDrop> {
+        let pointer = ui.input(|i| i.pointer.interact_pos())?;
+        response.dnd_hover_payload::<DraggedPage>()?;
+
+        let rect = response.rect;
+        let stroke = Stroke::new(2.0, Color32::WHITE);
+        if pointer.y < rect.center().y {
+            ui.painter().hline(rect.x_range(), rect.top(), stroke);
+            Some(PageDrop {
+                index,
+                starts_section: first_in_section,
+            })
+        } else {
+            ui.painter().hline(rect.x_range(), rect.bottom(), stroke);
+            Some(PageDrop {
+                index: index + 1,
+                starts_section: false,
+            })
+        }
+    }
+
+    // Puts a page showing the section's name in front of the section starting at `start`, it
+    // becomes the section's first page
+    fn add_title_page(&mut self, start: usize) {
+        let Some((_, first_page)) = self.state.pages.get_index_mut(start) else {
+            return;
+        };
+
+        let mut page = CanvasState::new();
+        page.section_start = first_page.section_start.take();
+
+        let page_size = page.page.value.size_pixels();
+        let margin = page_size * TITLE_MARGIN;
+
+        let mut layer = Layer::new_text_layer();
+        layer.name = "Section Title".to_string();
+        layer.transform_state.rect = Rect::from_min_max(
+            Pos2::new(margin.x, margin.y),
+            Pos2::new(page_size.x - margin.x, page_size.y - margin.y),
+        );
+        layer.transform_edit_state = LayerTransformEditState::from(&layer.transform_state);
+
+        let mut text = CanvasText::new(
+            TextVariable::Section.token().to_string(),
+            page_size.y * TITLE_FONT_SCALE,
+            FontId::default(),
+            Color32::BLACK,
+            TextHorizontalAlignment::Center,
+            TextVerticalAlignment::Center,
+        );
+        text.font_role = Some(ThemeFontRole::Heading);
+        text.color_role = Some(ThemeColorRole::Text);
+        Dependency::<ProjectSettingsManager>::get()
+            .with_lock(|manager| manager.project_settings.theme.apply_to_text(&mut text));
+        layer.content = LayerContent::Text(text);
+        page.layers.insert(layer.id, layer);
+
+        let page_id = next_page_id();
+        self.state.pages.shift_insert(start, page_id, page);
+        self.state.selected_pages.clear();
+        self.state.selected_page = page_id;
+        self.state.update_sections();
+    }
+}
@@ -0,0 +1,99 @@
+use eframe::egui::{self};
+use egui::{Rect, ScrollArea, Sense, Stroke, UiBuilder, Vec2};
+
+use crate::{id::PageId, scene::canvas_scene::CanvasHistoryManager, theme};
+
+use super::{
+    canvas::Canvas,
+    pages::{PagesResponse, PagesState},
+};
+
+pub const PAGE_STRIP_HEIGHT: f32 = 80.0;
+
+const THUMBNAIL_PADDING: f32 = 8.0;
+
+/// A single row of page thumbnails shown below the canvas for switching pages while editing
+pub struct PageStrip<'a> {
+    state: &'a mut PagesState,
+}
+
+impl<'a> PageStrip<'a> {
+    pub fn new(state: &'a mut PagesState) -> PageStrip<'a> {
+        PageStrip { state }
+    }
+
+    pub fn show(&mut self, ui: &mut egui::Ui, rect: Rect) -> PagesResponse {
+        ui.painter()
+            .rect_filled(rect, 0.0, theme::color::chrome(ui.visuals()));
+
+        let thumbnail_height = rect.height() - 2.0 * THUMBNAIL_PADDING;
+
+        // Only scroll when the selection changes so the strip can still be scrolled by hand
+        let last_selected_id = ui.id().with("page_strip_last_selected");
+        let last_selected = ui.data(|data| data.get_temp::<PageId>(last_selected_id));
+        let scroll_to_selected = last_selected != Some(self.state.selected_page);
+        ui.data_mut(|data| data.insert_temp(last_selected_id, self.state.selected_page));
+
+        let mut clicked_page = None;
+
+        ui.allocate_new_ui(UiBuilder::new().max_rect(rect), |ui| {
+            ScrollArea::horizontal()
+                .id_salt("page_strip")
+                .auto_shrink(false)
+                .show(ui, |ui| {
+                    ui.horizontal_centered(|ui| {
+                        ui.spacing_mut().item_spacing.x = THUMBNAIL_PADDING;
+                        ui.add_space(THUMBNAIL_PADDING);
+
+                        for (index, (page_id, page)) in self.state.pages.iter().enumerate() {
+                            let page_size = page.page.size_pixels();
+                            let thumbnail_size = Vec2::new(
+                                thumbnail_height * page_size.x / page_size.y,
+                                thumbnail_height,
+                            );
+
+                            let (thumbnail_rect, response) =
+                                ui.allocate_exact_size(thumbnail_size, Sense::click());
+
+                            if ui.is_rect_visible(thumbnail_rect) {
+                                let mut page = page.clone_with_new_widget_ids();
+                                Canvas::new(
+                                    &mut page,
+                                    thumbnail_rect,
+                                    &mut CanvasHistoryManager::preview(),
+                                )
+                                .show_preview(ui, thumbnail_rect);
+                            }
+
+                            let selected = *page_id == self.state.selected_page;
+                            if selected {
+                                ui.painter().rect_stroke(
+                                    thumbnail_rect.expand(2.0),
+                                    2.0,
+                                    Stroke::new(2.0, theme::color::focused(ui.visuals())),
+                                );
+
+                                if scroll_to_selected {
+                                    response.scroll_to_me(None);
+                                }
+                            }
+
+                            if response
+                                .on_hover_text(format!("Page {}", index + 1))
+                                .clicked()
+                            {
+                                clicked_page = Some(*page_id);
+                            }
+                        }
+                    });
+                });
+        });
+
+        if let Some(page) = clicked_page {
+            self.state.selected_page = page;
+            PagesResponse::SelectPage
+        } else {
+            PagesResponse::None
+        }
+    }
+}
@@ -1,24 +1,67 @@
+use std::{
+    collections::{HashMap, HashSet},
+    hash::{Hash, Hasher},
+    path::PathBuf,
+};
+
 use eframe::egui::{self};
-use egui::{Button, Color32, Layout, Sense, Stroke, Vec2};
+use egui::{Area, Button, Color32, ComboBox, Layout, Order, Rect, RichText, Sense, Stroke, Vec2};
+use fxhash::FxHasher;
+use strum::IntoEnumIterator;
 
 use egui_extras::Column;
 use indexmap::IndexMap;
 
 use crate::{
     assets::Asset,
-    id::{next_page_id, PageId},
+    dependencies::{Dependency, Singleton, SingletonFor},
+    id::{next_page_id, LayerId, PageId},
+    modal::{export_changes::ExportChangesModal, manager::ModalManager},
+    model::{color_label::ColorLabel, page_notes::PageNotes},
+    project_settings::ProjectSettingsManager,
     scene::canvas_scene::{CanvasHistory, CanvasHistoryManager},
+    template::Template,
     theme,
+    trash::TrashManager,
 };
 
 use super::{
     canvas::{Canvas, CanvasState},
+    canvas_info::quick_layout::Layout as QuickLayout,
+    grid_keys::{GridKeys, GridKeysResponse},
     spacer::Spacer,
+    templates::TemplatesState,
 };
 
 pub enum PagesResponse {
     None,
     SelectPage,
+    // Lay out the photos of `to` like the photos of `from`
+    ApplyLayout {
+        from: PageId,
+        to: PageId,
+    },
+    // Reflow the photos of each of `pages` with `layout`
+    LayOutPages {
+        pages: Vec<PageId>,
+        layout: PageLayoutChoice,
+    },
+}
+
+/// A layout picked for several pages at once
+#[derive(Debug, Clone, PartialEq)]
+pub enum PageLayoutChoice {
+    Quick(QuickLayout),
+    Template(Template),
+}
+
+/// A photo layer on a page of the book
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PhotoPlacement {
+    pub page: PageId,
+    // Position of the page in the book when the placement was found
+    pub page_index: usize,
+    pub layer: LayerId,
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -27,6 +70,15 @@ pub struct PagesState {
     pub pages: IndexMap<PageId, CanvasState>,
 
     pub selected_page: PageId,
+
+    // Pages picked with a Ctrl/Cmd click on top of the selected page, e.g. to export only them
+    pub selected_pages: HashSet<PageId>,
+
+    // Only pages with this label are listed
+    pub label_filter: Option<ColorLabel>,
+
+    // Whether the selected page is shown over the list, toggled with Space
+    pub quick_preview: bool,
 }
 
 impl PagesState {
@@ -34,8 +86,188 @@ impl PagesState {
         PagesState {
             pages,
             selected_page,
+            selected_pages: HashSet::new(),
+            label_filter: None,
+            quick_preview: false,
+        }
+    }
+
+    /// Positions in the book of the selected page and the pages picked alongside it, in order
+    pub fn selected_page_indices(&self) -> Vec<usize> {
+        self.pages
+            .keys()
+            .enumerate()
+            .filter(|(_, id)| **id == self.selected_page || self.selected_pages.contains(id))
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// The pages a layout picked from `page`'s menu goes to, every selected page in book order if
+    /// `page` is one of them, otherwise just `page`
+    pub fn layout_targets(&self, page: PageId) -> Vec<PageId> {
+        if page != self.selected_page && !self.selected_pages.contains(&page) {
+            return vec![page];
+        }
+
+        self.selected_page_indices()
+            .into_iter()
+            .filter_map(|index| self.pages.get_index(index).map(|(id, _)| *id))
+            .collect()
+    }
+
+    /// The paths of every photo used anywhere in the book
+    pub fn placed_photos(&self) -> HashSet<PathBuf> {
+        self.pages
+            .values()
+            .flat_map(|page| page.photo_paths().cloned())
+            .collect()
+    }
+
+    /// How many times each photo is used in the book
+    pub fn photo_usage(&self) -> HashMap<PathBuf, usize> {
+        self.photo_placements()
+            .into_iter()
+            .map(|(path, placements)| (path, placements.len()))
+            .collect()
+    }
+
+    /// Where each photo is used in the book, in page order
+    pub fn photo_placements(&self) -> HashMap<PathBuf, Vec<PhotoPlacement>> {
+        let mut placements: HashMap<PathBuf, Vec<PhotoPlacement>> = HashMap::new();
+        for (page_index, (page_id, page)) in self.pages.iter().enumerate() {
+            for (layer_id, path) in page.photo_layers() {
+                placements
+                    .entry(path.clone())
+                    .or_default()
+                    .push(PhotoPlacement {
+                        page: *page_id,
+                        page_index,
+                        layer: layer_id,
+                    });
+            }
+        }
+        placements
+    }
+
+    /// Changes whenever a photo is placed, removed or its page moves, so `photo_placements` only
+    /// needs working out again when it does
+    pub fn placements_key(&self) -> u64 {
+        let mut hasher = FxHasher::default();
+        for (page_id, page) in &self.pages {
+            page_id.hash(&mut hasher);
+            for (layer_id, path) in page.photo_layers() {
+                layer_id.hash(&mut hasher);
+                path.hash(&mut hasher);
+            }
+        }
+        hasher.finish()
+    }
+
+    /// How many checklist items are left to do across the book
+    pub fn open_task_count(&self) -> usize {
+        self.pages
+            .values()
+            .map(|page| page.notes.open_task_count())
+            .sum()
+    }
+
+    /// Moves the selection `offset` pages forward or backward, stopping at the first and last page.
+    /// Returns true if a different page was selected.
+    pub fn select_relative(&mut self, offset: isize) -> bool {
+        let Some(index) = self.pages.get_index_of(&self.selected_page) else {
+            return false;
+        };
+
+        let new_index = index
+            .saturating_add_signed(offset)
+            .min(self.pages.len().saturating_sub(1));
+
+        match self.pages.get_index(new_index) {
+            Some((page_id, _)) if new_index != index => {
+                self.selected_page = *page_id;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Gives every page the name of the section it's in, the last section started at or before it
+    pub fn update_sections(&mut self) {
+        let mut section = None;
+        for page in self.pages.values_mut() {
+            if page.section_start.is_some() {
+                section = page.section_start.clone();
+            }
+            if page.section != section {
+                page.section = section.clone();
+            }
         }
     }
+
+    /// Puts the pages in `order` back in that order, in the places they take up now. Pages that
+    /// aren't in it, like those added since, stay where they are.
+    pub fn restore_page_order(&mut self, order: &[PageId]) {
+        let mut listed = order
+            .iter()
+            .filter(|page_id| self.pages.contains_key(*page_id));
+        let page_ids: Vec<PageId> = self
+            .pages
+            .keys()
+            .map(|page_id| {
+                if order.contains(page_id) {
+                    *listed.next().unwrap()
+                } else {
+                    *page_id
+                }
+            })
+            .collect();
+
+        let mut pages = std::mem::take(&mut self.pages);
+        self.pages = page_ids
+            .into_iter()
+            .filter_map(|page_id| pages.swap_remove(&page_id).map(|page| (page_id, page)))
+            .collect();
+        self.update_sections();
+    }
+
+    /// Moves a page to `index` in the book. The section the page started, if any, stays where it
+    /// is and starts at the following page instead. With `starts_section` the page takes over the
+    /// section of the page it's put in front of, becoming its first page rather than the last page
+    /// of the section before.
+    pub fn move_page(&mut self, page_id: PageId, index: usize, starts_section: bool) {
+        let Some(from_index) = self.pages.get_index_of(&page_id) else {
+            return;
+        };
+
+        if let Some(name) = self.pages[from_index].section_start.take() {
+            if let Some((_, next)) = self.pages.get_index_mut(from_index + 1) {
+                if next.section_start.is_none() {
+                    next.section_start = Some(name);
+                }
+            }
+        }
+
+        let page = self.pages.shift_remove_index(from_index).unwrap().1;
+        let index = if index > from_index { index - 1 } else { index };
+
+        if index < self.pages.len() {
+            self.pages.shift_insert(index, page_id, page);
+        } else {
+            self.pages.insert(page_id, page);
+        }
+
+        if starts_section {
+            let name = self
+                .pages
+                .get_index_mut(index + 1)
+                .and_then(|(_, next)| next.section_start.take());
+            if let Some(name) = name {
+                self.pages[index].section_start = Some(name);
+            }
+        }
+
+        self.update_sections();
+    }
 }
 
 #[derive(Debug, PartialEq)]
@@ -63,11 +295,36 @@ impl<'a> Pages<'a> {
             - ui.spacing().item_spacing.x)
             .max(0.0);
 
-        let num_rows = self.state.pages.len().div_ceil(num_columns.max(1));
+        // Positions in the book of the pages that are listed
+        let visible_pages: Vec<usize> = self
+            .state
+            .pages
+            .values()
+            .enumerate()
+            .filter(|(_, page)| {
+                self.state
+                    .label_filter
+                    .map_or(true, |label| page.color_label == Some(label))
+            })
+            .map(|(index, _)| index)
+            .collect();
+
+        let num_rows = visible_pages.len().div_ceil(num_columns.max(1));
 
         let mut clicked_page = None;
         let mut from = None;
         let mut to = None;
+        let mut apply_layout = None;
+        let mut lay_out_pages = None;
+        let mut label_change = None;
+        let mut jump_to_page = None;
+
+        let page_ids: Vec<PageId> = self.state.pages.keys().copied().collect();
+
+        let project_settings: Singleton<ProjectSettingsManager> = Dependency::get();
+        // Nothing is marked as changed before the book is first exported
+        let has_exported =
+            project_settings.with_lock(|manager| manager.project_settings.has_exported());
 
         ui.set_clip_rect(ui.available_rect_before_wrap());
 
@@ -76,111 +333,256 @@ impl<'a> Pages<'a> {
         let mut table_size = ui.available_size() - Vec2::splat(10.0);
         table_size.y -= bottom_bar_height;
 
+        // The arrow keys move the selection, Enter hands the keys back to the canvas to edit the
+        // page and typing a number or the start of a section's name goes to it
+        let grid_keys = GridKeys::new(
+            egui::Id::new("pages_keys"),
+            visible_pages.len(),
+            num_columns,
+        )
+        .page_rows((table_size.y / (row_height + 10.0)) as usize);
+
+        let cursor = visible_pages
+            .iter()
+            .position(|index| page_ids[*index] == self.state.selected_page);
+
+        let target = match grid_keys.handle(ui, cursor) {
+            Some(GridKeysResponse::MoveTo(position)) => Some(position),
+            Some(GridKeysResponse::Open(_)) => {
+                grid_keys.surrender_focus(ui);
+                None
+            }
+            Some(GridKeysResponse::Preview) => {
+                self.state.quick_preview = !self.state.quick_preview;
+                None
+            }
+            Some(GridKeysResponse::Find(prefix)) => match prefix.parse::<usize>() {
+                Ok(number) => visible_pages.iter().position(|index| index + 1 == number),
+                Err(_) => visible_pages.iter().position(|index| {
+                    self.state.pages[*index]
+                        .section
+                        .as_ref()
+                        .is_some_and(|section| section.to_lowercase().starts_with(&prefix))
+                }),
+            },
+            None => None,
+        };
+        let keyed_page = target.map(|position| page_ids[visible_pages[position]]);
+
         ui.allocate_ui(table_size, |ui| {
-            egui_extras::TableBuilder::new(ui)
+            let table_rect = ui.max_rect();
+
+            let mut table = egui_extras::TableBuilder::new(ui)
                 .min_scrolled_height(table_size.y)
                 .drag_to_scroll(false)
                 .auto_shrink(false)
                 .columns(Column::exact(column_width), num_columns)
-                .column(Column::exact(spacer_width))
-                .body(|body| {
-                    body.rows(row_height, num_rows, |mut row| {
-                        let offset = row.index() * num_columns;
-                        for i in 0..num_columns {
-                            if offset + i >= self.state.pages.len() {
-                                break;
-                            }
-
-                            let index: usize = offset + i;
-                            let id: usize = *self.state.pages.get_index(index).unwrap().0;
-                            let page = &mut self
-                                .state
-                                .pages
-                                .get_index_mut(index)
-                                .unwrap()
-                                .1
-                                .clone_with_new_widget_ids();
-
-                            row.col(|ui| {
-                                let item_id = egui::Id::new(("page_list", index));
-
-                                ui.vertical(|ui| {
-                                    ui.add_space(10.0);
-
-                                    let response = ui.dnd_drag_source(item_id, index, |ui| {
-                                        ui.horizontal(|ui| {
-                                            ui.add_space(10.0);
-                                            ui.label(format!("Page {}", index + 1));
-                                        });
+                .column(Column::exact(spacer_width));
 
-                                        let mut page_rect = ui.max_rect().shrink(10.0);
-                                        page_rect.min.y += 30.0;
+            if let Some(position) = target {
+                table = table.scroll_to_row(position / num_columns.max(1), None);
+            }
 
-                                        Canvas::new(
-                                            page,
-                                            page_rect,
-                                            &mut CanvasHistoryManager::preview(),
-                                        )
-                                        .show_preview(ui, page_rect);
-                                    });
+            table.body(|body| {
+                body.rows(row_height, num_rows, |mut row| {
+                    let offset = row.index() * num_columns;
+                    for i in 0..num_columns {
+                        if offset + i >= visible_pages.len() {
+                            break;
+                        }
 
-                                    let page_rect = ui.max_rect().shrink(10.0);
-
-                                    if let (Some(pointer), Some(hovered_idx)) = (
-                                        ui.input(|i| i.pointer.interact_pos()),
-                                        response.response.dnd_hover_payload::<usize>(),
-                                    ) {
-                                        if *hovered_idx != index {
-                                            let stroke = egui::Stroke::new(2.0, Color32::WHITE);
-                                            if pointer.y < page_rect.center().y {
-                                                ui.painter().hline(
-                                                    page_rect.x_range(),
-                                                    page_rect.top(),
-                                                    stroke,
-                                                );
-                                                to = Some(index);
-                                            } else {
-                                                ui.painter().hline(
-                                                    page_rect.x_range(),
-                                                    page_rect.bottom(),
-                                                    stroke,
-                                                );
-                                                to = Some(index + 1);
-                                            }
+                        let index: usize = visible_pages[offset + i];
+                        let id: usize = *self.state.pages.get_index(index).unwrap().0;
+                        let changed_since_export = has_exported
+                            && project_settings.with_lock(|manager| {
+                                manager
+                                    .project_settings
+                                    .changed_since_export(index, &self.state.pages[index])
+                            });
+                        let page = &mut self
+                            .state
+                            .pages
+                            .get_index_mut(index)
+                            .unwrap()
+                            .1
+                            .clone_with_new_widget_ids();
+
+                        row.col(|ui| {
+                            let item_id = egui::Id::new(("page_list", index));
+
+                            ui.vertical(|ui| {
+                                ui.add_space(10.0);
+
+                                let response = ui.dnd_drag_source(item_id, index, |ui| {
+                                    ui.horizontal(|ui| {
+                                        ui.add_space(10.0);
+                                        ui.label(format!("Page {}", index + 1));
+
+                                        if let Some(color_label) = page.color_label {
+                                            let (rect, _) = ui.allocate_exact_size(
+                                                Vec2::splat(12.0),
+                                                Sense::hover(),
+                                            );
+                                            color_label.paint_dot(ui.painter(), rect.center(), 5.0);
+                                        }
+
+                                        Self::notes_indicator(ui, &page.notes);
+
+                                        if changed_since_export {
+                                            ui.label(
+                                                RichText::new("✏").color(theme::color::WARNING),
+                                            )
+                                            .on_hover_text("Changed since the last export");
                                         }
+                                    });
+
+                                    let mut page_rect = ui.max_rect().shrink(10.0);
+                                    page_rect.min.y += 30.0;
+
+                                    Canvas::new(
+                                        page,
+                                        page_rect,
+                                        &mut CanvasHistoryManager::preview(),
+                                    )
+                                    .show_preview(ui, page_rect);
+                                });
 
-                                        if let Some(dragged_idx) =
-                                            response.response.dnd_release_payload()
-                                        {
-                                            from = Some(*dragged_idx);
+                                let page_rect = ui.max_rect().shrink(10.0);
+
+                                if let (Some(pointer), Some(hovered_idx)) = (
+                                    ui.input(|i| i.pointer.interact_pos()),
+                                    response.response.dnd_hover_payload::<usize>(),
+                                ) {
+                                    if *hovered_idx != index {
+                                        let stroke = egui::Stroke::new(2.0, Color32::WHITE);
+                                        if pointer.y < page_rect.center().y {
+                                            ui.painter().hline(
+                                                page_rect.x_range(),
+                                                page_rect.top(),
+                                                stroke,
+                                            );
+                                            to = Some(index);
+                                        } else {
+                                            ui.painter().hline(
+                                                page_rect.x_range(),
+                                                page_rect.bottom(),
+                                                stroke,
+                                            );
+                                            to = Some(index + 1);
                                         }
                                     }
 
-                                    if ui.input(|i| i.pointer.primary_clicked())
-                                        && ui.rect_contains_pointer(page_rect)
+                                    if let Some(dragged_idx) =
+                                        response.response.dnd_release_payload()
                                     {
-                                        clicked_page = Some(id);
+                                        from = Some(*dragged_idx);
                                     }
+                                }
+
+                                if ui.input(|i| i.pointer.primary_clicked())
+                                    && ui.rect_contains_pointer(page_rect)
+                                {
+                                    clicked_page = Some((id, ui.input(|i| i.modifiers.command)));
+                                }
+
+                                // Only senses clicks so dragging still reorders the page
+                                ui.interact(page_rect, item_id.with("menu"), Sense::click())
+                                    .context_menu(|ui| {
+                                        ui.menu_button("Label", |ui| {
+                                            if ui.button("None").clicked() {
+                                                label_change = Some((id, None));
+                                                ui.close_menu();
+                                            }
+                                            for color_label in ColorLabel::iter() {
+                                                if ui
+                                                    .button(
+                                                        RichText::new(format!("⏺ {}", color_label))
+                                                            .color(color_label.color()),
+                                                    )
+                                                    .clicked()
+                                                {
+                                                    label_change = Some((id, Some(color_label)));
+                                                    ui.close_menu();
+                                                }
+                                            }
+                                        });
 
-                                    if self.state.selected_page == id {
-                                        // ui.set_clip_rect(ui.max_rect().expand(10.0));
-                                        ui.painter().rect_stroke(
-                                            page_rect.expand(3.0),
-                                            4.0,
-                                            Stroke::new(3.0, theme::color::FOCUSED),
-                                        );
-                                    }
-                                });
-                            });
-                        }
+                                        ui.menu_button("Apply Layout To", |ui| {
+                                            for (target_index, target) in
+                                                page_ids.iter().enumerate()
+                                            {
+                                                if *target != id
+                                                    && ui
+                                                        .button(format!(
+                                                            "Page {}",
+                                                            target_index + 1
+                                                        ))
+                                                        .clicked()
+                                                {
+                                                    apply_layout = Some((id, *target));
+                                                    ui.close_menu();
+                                                }
+                                            }
+                                        });
 
-                        row.col(|ui| {
-                            ui.add(Spacer::new(spacer_width, row_height));
+                                        let targets = self.state.layout_targets(id);
+                                        let title = if targets.len() > 1 {
+                                            format!("Lay Out {} Selected Pages", targets.len())
+                                        } else {
+                                            "Lay Out".to_string()
+                                        };
+                                        ui.menu_button(title, |ui| {
+                                            if let Some(layout) = Self::layout_choice_menu(ui) {
+                                                lay_out_pages = Some((targets, layout));
+                                                ui.close_menu();
+                                            }
+                                        });
+                                    });
+
+                                if self.state.selected_page == id {
+                                    // ui.set_clip_rect(ui.max_rect().expand(10.0));
+                                    ui.painter().rect_stroke(
+                                        page_rect.expand(3.0),
+                                        4.0,
+                                        Stroke::new(3.0, theme::color::focused(ui.visuals())),
+                                    );
+                                } else if self.state.selected_pages.contains(&id) {
+                                    ui.painter().rect_stroke(
+                                        page_rect.expand(3.0),
+                                        4.0,
+                                        Stroke::new(1.5, theme::color::focused(ui.visuals())),
+                                    );
+                                }
+                            });
                         });
-                    })
-                });
+                    }
+
+                    row.col(|ui| {
+                        ui.add(Spacer::new(spacer_width, row_height));
+                    });
+                })
+            });
+
+            grid_keys.update_focus(ui, table_rect, clicked_page.is_some());
+
+            if self.state.quick_preview && grid_keys.has_focus(ui) {
+                let page_id = keyed_page.unwrap_or(self.state.selected_page);
+                if let Some(page) = self.state.pages.get(&page_id) {
+                    if Self::quick_preview_ui(ui, table_rect, page) {
+                        self.state.quick_preview = false;
+                    }
+                }
+            } else {
+                self.state.quick_preview = false;
+            }
         });
 
+        if let Some((page_id, color_label)) = label_change {
+            if let Some(page) = self.state.pages.get_mut(&page_id) {
+                page.color_label = color_label;
+            }
+        }
+
         // Handle reordering
         if let (Some(from_idx), Some(to_idx)) = (from, to) {
             if from_idx != to_idx {
@@ -197,8 +599,11 @@ impl<'a> Pages<'a> {
             }
         }
 
-        ui.painter()
-            .rect_filled(ui.available_rect_before_wrap(), 0.0, Color32::from_gray(40));
+        ui.painter().rect_filled(
+            ui.available_rect_before_wrap(),
+            0.0,
+            theme::color::chrome(ui.visuals()),
+        );
 
         ui.with_layout(Layout::right_to_left(egui::Align::Center), |ui| {
             ui.add_space(20.0);
@@ -219,7 +624,10 @@ impl<'a> Pages<'a> {
                     .clicked()
                 {
                     if let Some(index) = self.state.pages.get_index_of(&self.state.selected_page) {
-                        self.state.pages.shift_remove_index(index);
+                        if let Some((_, page)) = self.state.pages.shift_remove_index(index) {
+                            Dependency::<TrashManager>::get()
+                                .with_lock_mut(|trash_manager| trash_manager.trash_page(page));
+                        }
                         // Select the previous page, or the first page if we deleted the first one
                         self.state.selected_page = *self
                             .state
@@ -230,13 +638,182 @@ impl<'a> Pages<'a> {
                     }
                 }
             }
+
+            ui.add_space(10.0);
+            Self::label_filter(ui, &mut self.state.label_filter);
+
+            jump_to_page = Self::open_tasks_menu(ui, self.state);
+
+            if has_exported
+                && ui
+                    .button("Review Changes")
+                    .on_hover_text("Compare the pages changed since the last export with how they were exported")
+                    .clicked()
+            {
+                ModalManager::push(ExportChangesModal::new(self.state.pages.values()));
+            }
         });
 
-        if let Some(page) = clicked_page {
+        if let Some((from, to)) = apply_layout {
+            PagesResponse::ApplyLayout { from, to }
+        } else if let Some((pages, layout)) = lay_out_pages {
+            PagesResponse::LayOutPages { pages, layout }
+        } else if let Some(page) = jump_to_page.or(keyed_page) {
+            self.state.selected_pages.clear();
             self.state.selected_page = page;
             PagesResponse::SelectPage
+        } else if let Some((page, toggle)) = clicked_page {
+            if toggle {
+                // Ctrl/Cmd clicking a picked page takes it out of the selection again
+                if !self.state.selected_pages.remove(&page) {
+                    self.state.selected_pages.insert(page);
+                }
+                PagesResponse::None
+            } else {
+                self.state.selected_pages.clear();
+                self.state.selected_page = page;
+                PagesResponse::SelectPage
+            }
         } else {
             PagesResponse::None
         }
     }
+
+    // Shows the page as large as it fits over the list, returns whether it was clicked away
+    fn quick_preview_ui(ui: &mut egui::Ui, rect: Rect, page: &CanvasState) -> bool {
+        let mut page = page.clone_with_new_widget_ids();
+
+        Area::new(egui::Id::new("pages_quick_preview"))
+            .order(Order::Foreground)
+            .fixed_pos(rect.min)
+            .show(ui.ctx(), |ui| {
+                let (rect, response) = ui.allocate_exact_size(rect.size(), Sense::click());
+                ui.painter()
+                    .rect_filled(rect, 0.0, Color32::from_black_alpha(220));
+
+                let page_rect = rect.shrink(40.0);
+                Canvas::new(&mut page, page_rect, &mut CanvasHistoryManager::preview())
+                    .show_preview(ui, page_rect);
+
+                response.clicked()
+            })
+            .inner
+    }
+
+    // Shows how many checklist items are open on the page, or that it has notes, with the notes
+    // and items on hover
+    fn notes_indicator(ui: &mut egui::Ui, notes: &PageNotes) {
+        if notes.is_empty() {
+            return;
+        }
+
+        let open = notes.open_task_count();
+        let indicator = if open > 0 {
+            RichText::new(format!("☐ {}", open)).color(theme::color::WARNING)
+        } else {
+            RichText::new("🗒").weak()
+        };
+
+        ui.label(indicator).on_hover_ui(|ui| {
+            if !notes.text.trim().is_empty() {
+                ui.label(notes.text.trim());
+            }
+            for item in &notes.checklist {
+                ui.label(format!(
+                    "{} {}",
+                    if item.done { "☑" } else { "☐" },
+                    item.text
+                ));
+            }
+        });
+    }
+
+    // The quick layouts and templates pages can be laid out with. Returns the one picked.
+    fn layout_choice_menu(ui: &mut egui::Ui) -> Option<PageLayoutChoice> {
+        let mut choice = None;
+
+        ui.menu_button("Quick Layout", |ui| {
+            // Each layout is made for the photo count of the page it's applied to
+            for layout in QuickLayout::available(3) {
+                if ui.button(layout.name()).clicked() {
+                    choice = Some(PageLayoutChoice::Quick(layout));
+                }
+            }
+        });
+
+        ui.menu_button("Template", |ui| {
+            for template in TemplatesState::new().templates {
+                if ui.button(&template.name).clicked() {
+                    choice = Some(PageLayoutChoice::Template(template));
+                }
+            }
+        });
+
+        choice
+    }
+
+    // Every open checklist item in the book by page. Items can be checked off here, clicking a
+    // page goes to it. Returns the page to go to.
+    fn open_tasks_menu(ui: &mut egui::Ui, state: &mut PagesState) -> Option<PageId> {
+        let open = state.open_task_count();
+        let mut jump_to_page = None;
+
+        let mut title = RichText::new(format!("☐ {} Open Tasks", open));
+        if open > 0 {
+            title = title.color(theme::color::WARNING);
+        }
+
+        ui.menu_button(title, |ui| {
+            if open == 0 {
+                ui.label("Nothing left to do");
+                return;
+            }
+
+            for (index, (page_id, page)) in state.pages.iter_mut().enumerate() {
+                if page.notes.open_task_count() == 0 {
+                    continue;
+                }
+
+                if ui
+                    .button(RichText::new(format!("Page {}", index + 1)).strong())
+                    .clicked()
+                {
+                    jump_to_page = Some(*page_id);
+                    ui.close_menu();
+                }
+
+                for item in page.notes.checklist.iter_mut().filter(|item| !item.done) {
+                    ui.checkbox(&mut item.done, item.text.as_str());
+                }
+
+                ui.add_space(5.0);
+            }
+        })
+        .response
+        .on_hover_text("Checklist items left on the pages, check them before ordering the book");
+
+        jump_to_page
+    }
+
+    fn label_filter(ui: &mut egui::Ui, label_filter: &mut Option<ColorLabel>) {
+        ComboBox::from_id_salt("pages_label_filter")
+            .selected_text(match label_filter {
+                Some(color_label) => {
+                    RichText::new(format!("⏺ {}", color_label)).color(color_label.color())
+                }
+                None => RichText::new("All Pages"),
+            })
+            .show_ui(ui, |ui| {
+                ui.selectable_value(label_filter, None, "All Pages");
+                for color_label in ColorLabel::iter() {
+                    ui.selectable_value(
+                        label_filter,
+                        Some(color_label),
+                        RichText::new(format!("⏺ {}", color_label)).color(color_label.color()),
+                    );
+                }
+            })
+            .response
+            .on_hover_text("Only show pages with this label");
+    }
 }
@@ -0,0 +1,204 @@
+use eframe::egui::{Context, Event, Id, InputState, MouseWheelUnit, Vec2};
+use serde::{Deserialize, Serialize};
+use strum_macros::EnumIter;
+
+use crate::{
+    auto_persisting::AutoPersisting,
+    config::Config,
+    dependencies::{Dependency, SingletonFor},
+};
+
+const WHEEL_ZOOM_STEP: f32 = 1.1;
+
+// How far one notch of a mouse wheel scrolls when it pans instead of zooming
+const WHEEL_SCROLL_POINTS: f32 = 50.0;
+
+// Trackpad scrolling is continuous so it zooms by this many points per doubling
+const SMOOTH_ZOOM_POINTS: f32 = 200.0;
+
+pub const MIN_ZOOM_SENSITIVITY: f32 = 0.25;
+pub const MAX_ZOOM_SENSITIVITY: f32 = 4.0;
+
+// Inertial panning stops once it drops below this speed, in points per second
+const MIN_INERTIA_SPEED: f32 = 20.0;
+
+// Higher values bring inertial panning to a stop sooner
+const INERTIA_FRICTION: f32 = 6.0;
+
+/// What turning the mouse wheel does. Holding ctrl does the other one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, EnumIter)]
+pub enum WheelAction {
+    #[default]
+    Zoom,
+    Scroll,
+}
+
+impl std::fmt::Display for WheelAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WheelAction::Zoom => write!(f, "Zoom"),
+            WheelAction::Scroll => write!(f, "Scroll"),
+        }
+    }
+}
+
+/// How the mouse and trackpad move the canvas and the viewer, from the config
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PanZoomSettings {
+    pub wheel_action: WheelAction,
+    // Multiplies how far each wheel step or trackpad scroll zooms
+    pub zoom_sensitivity: f32,
+    pub invert_zoom: bool,
+    pub middle_mouse_pan: bool,
+}
+
+impl Default for PanZoomSettings {
+    fn default() -> Self {
+        Self {
+            wheel_action: WheelAction::default(),
+            zoom_sensitivity: 1.0,
+            invert_zoom: false,
+            middle_mouse_pan: true,
+        }
+    }
+}
+
+impl PanZoomSettings {
+    pub fn current() -> Self {
+        Dependency::<AutoPersisting<Config>>::get().with_lock_mut(|config| {
+            config
+                .read()
+                .map(|config| config.pan_zoom_settings())
+                .unwrap_or_default()
+        })
+    }
+}
+
+/// The zoom and pan requested this frame by the mouse wheel, trackpad gestures or middle mouse
+/// dragging
+pub struct PanZoomInput {
+    pub zoom_factor: f32,
+    pub pan: Vec2,
+    // The view is being dragged with the middle mouse button
+    pub grabbing: bool,
+}
+
+impl PanZoomInput {
+    pub fn read(input: &InputState) -> Self {
+        Self::read_with(input, &PanZoomSettings::current())
+    }
+
+    pub fn read_with(input: &InputState, settings: &PanZoomSettings) -> Self {
+        let mut zoom_factor = 1.0;
+        let mut pan = Vec2::ZERO;
+
+        for event in &input.events {
+            match event {
+                // Pinch gestures always zoom
+                Event::Zoom(factor) => zoom_factor *= factor,
+                Event::MouseWheel {
+                    unit,
+                    delta,
+                    modifiers,
+                } => {
+                    let swapped = modifiers.ctrl || modifiers.command;
+
+                    match unit {
+                        // Mouse wheels scroll by lines, they zoom in steps
+                        MouseWheelUnit::Line | MouseWheelUnit::Page => {
+                            let zooms = match settings.wheel_action {
+                                WheelAction::Zoom => !swapped,
+                                WheelAction::Scroll => swapped,
+                            };
+
+                            if !zooms {
+                                pan += *delta * WHEEL_SCROLL_POINTS;
+                            } else if delta.y != 0.0 {
+                                let step = WHEEL_ZOOM_STEP.powf(settings.zoom_sensitivity);
+                                zoom_factor *= if (delta.y > 0.0) != settings.invert_zoom {
+                                    step
+                                } else {
+                                    1.0 / step
+                                };
+                            }
+                        }
+                        // Trackpads scroll by points, two finger scrolling pans and ctrl turns
+                        // it into a smooth zoom, which is also how some platforms send pinches
+                        MouseWheelUnit::Point => {
+                            if swapped {
+                                let direction = if settings.invert_zoom { -1.0 } else { 1.0 };
+                                zoom_factor *= (delta.y * direction * settings.zoom_sensitivity
+                                    / SMOOTH_ZOOM_POINTS)
+                                    .exp2();
+                            } else {
+                                pan += *delta;
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let grabbing = settings.middle_mouse_pan && input.pointer.middle_down();
+        if grabbing {
+            pan += input.pointer.delta();
+        }
+
+        Self {
+            zoom_factor,
+            pan,
+            grabbing,
+        }
+    }
+
+    pub fn is_zooming(&self) -> bool {
+        self.zoom_factor != 1.0
+    }
+}
+
+/// Keeps a pan moving for a moment after a drag ends. The velocity is kept in egui's temporary
+/// memory so views don't need to carry it in their state.
+pub struct PanInertia;
+
+impl PanInertia {
+    /// Call once per frame with the drag applied this frame, or `None` when the user isn't
+    /// dragging. Returns the extra offset to apply from inertia.
+    pub fn update(ctx: &Context, id: Id, drag: Option<Vec2>) -> Vec2 {
+        let dt = ctx.input(|input| input.stable_dt).max(f32::EPSILON);
+        let velocity: Vec2 = ctx.data(|data| data.get_temp(id)).unwrap_or_default();
+
+        match drag {
+            Some(delta) => {
+                // Averaged with the previous frame so one uneven frame doesn't decide the throw
+                let velocity = velocity * 0.5 + (delta / dt) * 0.5;
+                ctx.data_mut(|data| data.insert_temp(id, velocity));
+                Vec2::ZERO
+            }
+            None => {
+                if velocity.length() < MIN_INERTIA_SPEED || !Self::enabled() {
+                    if velocity != Vec2::ZERO {
+                        ctx.data_mut(|data| data.remove::<Vec2>(id));
+                    }
+                    return Vec2::ZERO;
+                }
+
+                let offset = velocity * dt;
+                let velocity = velocity * (-INERTIA_FRICTION * dt).exp();
+                ctx.data_mut(|data| data.insert_temp(id, velocity));
+                ctx.request_repaint();
+
+                offset
+            }
+        }
+    }
+
+    fn enabled() -> bool {
+        Dependency::<AutoPersisting<Config>>::get().with_lock_mut(|config| {
+            config
+                .read()
+                .map(|config| config.pan_inertia())
+                .unwrap_or(true)
+        })
+    }
+}
@@ -0,0 +1,254 @@
+use eframe::egui::{
+    Color32, Context, CursorIcon, Id, Key, Modifiers, Pos2, Rect, Sense, Stroke, Ui, Vec2,
+};
+
+use crate::{
+    cursor_manager::CursorManager,
+    dependencies::{Dependency, SingletonFor},
+    model::{
+        canvas_path::{CanvasPath, PathPoint},
+        fill::Fill,
+    },
+    theme,
+};
+
+// How close to the first anchor, in screen pixels, a click closes the path
+const CLOSE_DISTANCE: f32 = 8.0;
+
+const ANCHOR_SIZE: f32 = 7.0;
+
+/// The style new paths start with, set from the tool options while the pen is chosen
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PenToolOptions {
+    pub fill: Color32,
+    pub stroke_color: Color32,
+    // In page pixels
+    pub stroke_width: f32,
+}
+
+impl Default for PenToolOptions {
+    fn default() -> Self {
+        let path = CanvasPath::new(Vec::new(), false);
+        Self {
+            fill: path.fill.average_color(),
+            stroke_color: path.stroke_color,
+            stroke_width: path.stroke_width,
+        }
+    }
+}
+
+impl PenToolOptions {
+    fn id() -> Id {
+        Id::new("pen_tool_options")
+    }
+
+    pub fn current(ctx: &Context) -> Self {
+        ctx.data(|data| data.get_temp(Self::id()))
+            .unwrap_or_default()
+    }
+
+    pub fn set(self, ctx: &Context) {
+        ctx.data_mut(|data| data.insert_temp(Self::id(), self));
+    }
+
+    pub fn new_path(&self) -> CanvasPath {
+        CanvasPath {
+            fill: Fill::Solid(self.fill),
+            stroke_color: self.stroke_color,
+            stroke_width: self.stroke_width,
+            ..CanvasPath::new(Vec::new(), false)
+        }
+    }
+}
+
+pub enum PenToolResponse {
+    None,
+    Finished(CanvasPath),
+}
+
+/// Draws bezier paths on the page. Clicking adds a corner and dragging pulls out handles to curve
+/// the path through the anchor. Clicking the first anchor closes the path into a filled shape,
+/// double-clicking or Enter leaves it open.
+pub struct PenTool {
+    id: Id,
+    page_rect: Rect,
+    zoom: f32,
+}
+
+impl PenTool {
+    pub fn new(id: Id, page_rect: Rect, zoom: f32) -> Self {
+        Self {
+            id,
+            page_rect,
+            zoom,
+        }
+    }
+
+    /// Enter and Escape finish the path and Backspace takes back the last point. These are read
+    /// before the canvas's own keys so they don't deselect or delete layers while drawing.
+    pub fn handle_keys(ctx: &Context, id: Id) -> PenToolResponse {
+        let Some(mut path) = ctx.data(|data| data.get_temp::<CanvasPath>(id)) else {
+            return PenToolResponse::None;
+        };
+
+        let (finish, remove_point) = ctx.input_mut(|input| {
+            (
+                input.consume_key(Modifiers::NONE, Key::Enter)
+                    | input.consume_key(Modifiers::NONE, Key::Escape),
+                input.consume_key(Modifiers::NONE, Key::Backspace),
+            )
+        });
+
+        if finish {
+            return Self::finish(ctx, id);
+        }
+
+        if remove_point {
+            path.points.pop();
+            ctx.data_mut(|data| {
+                if path.points.is_empty() {
+                    data.remove::<CanvasPath>(id);
+                } else {
+                    data.insert_temp(id, path);
+                }
+            });
+        }
+
+        PenToolResponse::None
+    }
+
+    /// Ends the path being drawn. A single point isn't enough for a path so it's dropped.
+    pub fn finish(ctx: &Context, id: Id) -> PenToolResponse {
+        let path = ctx.data_mut(|data| {
+            let path = data.get_temp::<CanvasPath>(id);
+            data.remove::<CanvasPath>(id);
+            path
+        });
+
+        match path {
+            Some(path) if path.points.len() > 1 => PenToolResponse::Finished(path),
+            _ => PenToolResponse::None,
+        }
+    }
+
+    pub fn show(&self, ui: &mut Ui, canvas_rect: Rect) -> PenToolResponse {
+        let response = ui.interact(canvas_rect, self.id.with("input"), Sense::click_and_drag());
+        let mut path = ui.data(|data| data.get_temp::<CanvasPath>(self.id));
+        let mut result = PenToolResponse::None;
+
+        let to_page = |pos: Pos2| ((pos - self.page_rect.min) / self.zoom).to_pos2();
+        // The path's points are in page pixels while it's drawn
+        let page_to_screen = Rect::from_min_size(self.page_rect.min, Vec2::splat(self.zoom));
+        let to_screen = CanvasPath::transform(page_to_screen, 0.0);
+
+        let pointer = response.hover_pos().or(response.interact_pointer_pos());
+        let is_over_start = |path: &CanvasPath, pointer: Pos2| {
+            path.points.len() > 2
+                && to_screen(path.points[0].anchor).distance(pointer) <= CLOSE_DISTANCE
+        };
+
+        if response.hovered() {
+            Dependency::<CursorManager>::get().with_lock_mut(|cursor_manager| {
+                cursor_manager.set_cursor(CursorIcon::Crosshair);
+            });
+        }
+
+        let pressed = response.is_pointer_button_down_on()
+            && ui.input(|input| input.pointer.primary_pressed());
+
+        if let (true, Some(pointer)) = (pressed, pointer) {
+            match path.take() {
+                Some(mut closing) if is_over_start(&closing, pointer) => {
+                    closing.closed = true;
+                    result = PenToolResponse::Finished(closing);
+                }
+                drawing => {
+                    let mut drawing =
+                        drawing.unwrap_or_else(|| PenToolOptions::current(ui.ctx()).new_path());
+                    drawing.points.push(PathPoint::corner(to_page(pointer)));
+                    path = Some(drawing);
+                }
+            }
+        }
+
+        // Dragging after placing an anchor pulls out handles either side of it
+        if let (true, Some(drawing), Some(pointer)) = (response.dragged(), &mut path, pointer) {
+            if let Some(point) = drawing.points.last_mut() {
+                point.handle_out = to_page(pointer) - point.anchor;
+                point.handle_in = -point.handle_out;
+            }
+        }
+
+        // The second click of a double-click added a point of its own
+        if response.double_clicked() {
+            if let Some(mut drawing) = path.take() {
+                drawing.points.pop();
+                ui.data_mut(|data| data.insert_temp(self.id, drawing));
+                return Self::finish(ui.ctx(), self.id);
+            }
+        }
+
+        match &path {
+            Some(drawing) => {
+                self.paint(ui, drawing, pointer, &to_screen, &is_over_start);
+                ui.data_mut(|data| data.insert_temp(self.id, drawing.clone()));
+            }
+            None => ui.data_mut(|data| data.remove::<CanvasPath>(self.id)),
+        }
+
+        result
+    }
+
+    fn paint(
+        &self,
+        ui: &Ui,
+        path: &CanvasPath,
+        pointer: Option<Pos2>,
+        to_screen: &impl Fn(Pos2) -> Pos2,
+        is_over_start: &impl Fn(&CanvasPath, Pos2) -> bool,
+    ) {
+        let painter = ui.painter();
+        let focused = theme::color::focused(ui.visuals());
+
+        path.paint(
+            painter,
+            Rect::from_min_size(self.page_rect.min, Vec2::splat(self.zoom)),
+            0.0,
+            self.zoom,
+        );
+
+        let Some(last) = path.points.last() else {
+            return;
+        };
+
+        // Where the next segment will go
+        if let Some(pointer) = pointer {
+            painter.line_segment([to_screen(last.anchor), pointer], Stroke::new(1.0, focused));
+        }
+
+        if !last.is_corner() {
+            for handle in [last.handle_in, last.handle_out] {
+                let handle_pos = to_screen(last.anchor + handle);
+                painter.line_segment(
+                    [to_screen(last.anchor), handle_pos],
+                    Stroke::new(1.0, focused),
+                );
+                painter.circle_filled(handle_pos, ANCHOR_SIZE / 2.0, focused);
+            }
+        }
+
+        let closes = pointer.is_some_and(|pointer| is_over_start(path, pointer));
+
+        for (index, point) in path.points.iter().enumerate() {
+            let anchor_rect =
+                Rect::from_center_size(to_screen(point.anchor), Vec2::splat(ANCHOR_SIZE));
+            let highlighted = index == 0 && closes;
+            painter.rect_filled(
+                anchor_rect,
+                0.0,
+                if highlighted { focused } else { Color32::WHITE },
+            );
+            painter.rect_stroke(anchor_rect, 0.0, Stroke::new(1.0, focused));
+        }
+    }
+}
@@ -1,8 +1,16 @@
+use std::path::PathBuf;
+
 use eframe::egui::{Grid, Widget};
-use egui::{Key, Ui};
+use egui::{ComboBox, Key, RichText, TextEdit, Ui};
 use strum::IntoEnumIterator;
 
-use crate::photo::{PhotoMetadataField, PhotoRating, SaveOnDropPhoto};
+use crate::{
+    dependencies::{Dependency, Singleton, SingletonFor},
+    model::color_label::ColorLabel,
+    photo::{PhotoMetadataField, PhotoRating, PhotoRotation, SaveOnDropPhoto},
+    photo_captions::CaptionTarget,
+    photo_manager::PhotoManager,
+};
 
 use super::{segment_control::SegmentControl, spacer::Spacer};
 
@@ -17,6 +25,106 @@ impl<'a> PhotoInfo<'a> {
 }
 
 impl<'a> PhotoInfo<'a> {
+    // Lets the primary version of the photo's stack be picked, nothing is shown for photos that
+    // aren't part of a stack
+    fn stack_versions(ui: &mut Ui, path: &PathBuf) {
+        let photo_manager: Singleton<PhotoManager> = Dependency::get();
+        let (versions, primary) = photo_manager.with_lock(|photo_manager| {
+            (
+                photo_manager.stack_versions(path),
+                photo_manager.stack_primary(path),
+            )
+        });
+
+        if versions.len() < 2 {
+            return;
+        }
+
+        let Some(mut primary) = primary else {
+            return;
+        };
+
+        ui.label("Primary Version");
+
+        let file_name = |path: &PathBuf| {
+            path.file_name()
+                .map(|file_name| file_name.to_string_lossy().to_string())
+                .unwrap_or_default()
+        };
+
+        let mut changed = false;
+        ComboBox::from_id_salt("photo_info_stack_primary")
+            .selected_text(file_name(&primary))
+            .show_ui(ui, |ui| {
+                for version in &versions {
+                    changed |= ui
+                        .selectable_value(&mut primary, version.path.clone(), version.file_name())
+                        .changed();
+                }
+            });
+
+        if changed {
+            photo_manager.with_lock_mut(|photo_manager| photo_manager.set_stack_primary(primary));
+        }
+
+        Spacer::new(ui.available_width(), 1.0).ui(ui);
+        ui.end_row();
+    }
+
+    // Title and caption rows, edited text is saved straight to the photo manager
+    fn captions(ui: &mut Ui, path: &PathBuf) {
+        let photo_manager: Singleton<PhotoManager> = Dependency::get();
+        let captions = photo_manager.with_lock(|photo_manager| photo_manager.captions(path));
+
+        for target in CaptionTarget::iter() {
+            ui.label(target.to_string());
+
+            let mut text = captions.get(target).unwrap_or_default().to_string();
+            if ui
+                .add(TextEdit::singleline(&mut text).hint_text(format!("No {}", target)))
+                .changed()
+            {
+                photo_manager.with_lock_mut(|photo_manager| {
+                    photo_manager.set_caption(path, target, Some(text));
+                });
+            }
+
+            Spacer::new(ui.available_width(), 1.0).ui(ui);
+            ui.end_row();
+        }
+    }
+
+    // Overrides the EXIF orientation for photos where it's wrong or missing. Placed copies of the
+    // photo pick it up so it's used on the canvas and in exports too.
+    fn orientation(&mut self, ui: &mut Ui) {
+        ui.label("Orientation");
+
+        let exif_rotation = self.photo.metadata.exif_rotation();
+        let from_exif = format!("From EXIF ({})", exif_rotation);
+        let mut orientation_override = self.photo.metadata.orientation_override;
+
+        ComboBox::from_id_salt("photo_info_orientation")
+            .selected_text(match orientation_override {
+                Some(rotation) => rotation.to_string(),
+                None => from_exif.clone(),
+            })
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut orientation_override, None, from_exif);
+                for rotation in PhotoRotation::iter() {
+                    ui.selectable_value(
+                        &mut orientation_override,
+                        Some(rotation),
+                        rotation.to_string(),
+                    );
+                }
+            });
+
+        self.photo.metadata.orientation_override = orientation_override;
+
+        Spacer::new(ui.available_width(), 1.0).ui(ui);
+        ui.end_row();
+    }
+
     pub fn show(&mut self, ui: &mut Ui) {
         ui.allocate_ui(ui.available_size(), |ui: &mut egui::Ui| {
             Grid::new("photo_info_grid")
@@ -37,6 +145,30 @@ impl<'a> PhotoInfo<'a> {
 
                     ui.end_row();
 
+                    ui.label("Label");
+                    ui.horizontal(|ui| {
+                        ui.selectable_value(&mut self.photo.color_label, None, "None");
+                        for color_label in ColorLabel::iter() {
+                            ui.selectable_value(
+                                &mut self.photo.color_label,
+                                Some(color_label),
+                                RichText::new("⏺").color(color_label.color()),
+                            )
+                            .on_hover_text(format!(
+                                "({}) {}",
+                                color_label.key().name(),
+                                color_label
+                            ));
+                        }
+                    });
+                    ui.end_row();
+
+                    Self::stack_versions(ui, &self.photo.path);
+
+                    self.orientation(ui);
+
+                    Self::captions(ui, &self.photo.path);
+
                     for (label, value) in self.photo.metadata.iter() {
                         ui.label(format!("{}", label));
                         ui.label(format!("{}", value));
@@ -51,6 +183,11 @@ impl<'a> PhotoInfo<'a> {
                 });
         });
 
+        // Typing a number into the title or caption shouldn't change the rating
+        if ui.ctx().memory(|memory| memory.focused().is_some()) {
+            return;
+        }
+
         ui.ctx().input(|input| {
             if input.key_down(Key::Num1) {
                 self.photo.rating = PhotoRating::Yes;
@@ -0,0 +1,260 @@
+use std::{collections::HashSet, path::PathBuf};
+
+use eframe::egui::{
+    self, Align2, Area, Color32, FontId, Frame, Id, Key, Layout, Order, Pos2, Rect, RichText,
+    ScrollArea, TextEdit, Vec2,
+};
+use strum::IntoEnumIterator;
+
+use crate::{
+    dependencies::{Dependency, Singleton, SingletonFor},
+    photo::{Photo, PhotoRating},
+    photo_manager::PhotoManager,
+    theme,
+};
+
+use super::gallery_image::GalleryImage;
+
+const THUMBNAIL_SIZE: f32 = 128.0;
+const PICKER_WIDTH: f32 = 560.0;
+const PICKER_MAX_HEIGHT: f32 = 420.0;
+
+#[derive(Debug, Clone, Default)]
+struct PhotoPickerState {
+    rating: Option<PhotoRating>,
+    // In the order they were chosen, which is the order they're placed in
+    selected: Vec<PathBuf>,
+}
+
+pub enum PhotoPickerResponse {
+    None,
+    Picked(Vec<Photo>),
+    Closed,
+}
+
+/// A popup for choosing photos without going through the gallery. Photos are shown with the
+/// gallery's search, sorting and grouping and can be narrowed down further by rating. Photos
+/// already placed in the book or on the current page are marked.
+pub struct PhotoPicker<'a> {
+    placed_in_book: &'a HashSet<PathBuf>,
+    placed_on_page: &'a HashSet<PathBuf>,
+    allow_multiple: bool,
+}
+
+impl<'a> PhotoPicker<'a> {
+    pub fn new(placed_in_book: &'a HashSet<PathBuf>, placed_on_page: &'a HashSet<PathBuf>) -> Self {
+        Self {
+            placed_in_book,
+            placed_on_page,
+            allow_multiple: false,
+        }
+    }
+
+    /// Lets several photos be chosen with ctrl-click and inserted together
+    pub fn multiple(mut self, allow_multiple: bool) -> Self {
+        self.allow_multiple = allow_multiple;
+        self
+    }
+
+    pub fn show(&self, ctx: &egui::Context, id: Id, anchor: Pos2) -> PhotoPickerResponse {
+        let stored_state: Option<PhotoPickerState> = ctx.data(|data| data.get_temp(id));
+        // The click that opened the picker shouldn't also close it
+        let is_first_frame = stored_state.is_none();
+        let mut state = stored_state.unwrap_or_default();
+        let mut response = PhotoPickerResponse::None;
+
+        let photo_manager: Singleton<PhotoManager> = Dependency::get();
+        let (grouped_photos, mut search, search_error) = photo_manager.with_lock(|photo_manager| {
+            (
+                photo_manager.grouped_photos().clone(),
+                photo_manager.search_text().to_string(),
+                photo_manager.search_error().map(|error| error.to_string()),
+            )
+        });
+
+        let area_response = Area::new(id)
+            .order(Order::Foreground)
+            .fixed_pos(anchor)
+            .constrain(true)
+            .show(ctx, |ui| {
+                Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.set_width(PICKER_WIDTH);
+
+                    ui.horizontal(|ui| {
+                        // Shares the gallery's search so both show the same photos
+                        let search_response = ui.add(
+                            TextEdit::singleline(&mut search)
+                                .hint_text("Search")
+                                .desired_width(200.0),
+                        );
+                        if is_first_frame {
+                            search_response.request_focus();
+                        }
+                        if search_response.changed() {
+                            photo_manager.with_lock_mut(|photo_manager| {
+                                photo_manager.set_search(search.clone())
+                            });
+                        }
+
+                        ui.separator();
+
+                        ui.selectable_value(&mut state.rating, None, "All");
+                        for rating in PhotoRating::iter() {
+                            ui.selectable_value(
+                                &mut state.rating,
+                                Some(rating),
+                                rating.to_string(),
+                            );
+                        }
+                    });
+
+                    if let Some(error) = &search_error {
+                        ui.label(RichText::new(error).color(theme::color::WARNING));
+                    }
+
+                    ui.separator();
+
+                    ScrollArea::vertical()
+                        .max_height(PICKER_MAX_HEIGHT)
+                        .auto_shrink([false, true])
+                        .show(ui, |ui| {
+                            let mut has_matches = false;
+
+                            for (title, group) in &grouped_photos {
+                                let photos: Vec<&Photo> = group
+                                    .values()
+                                    .filter(|photo| {
+                                        state.rating.map_or(true, |rating| photo.rating == rating)
+                                    })
+                                    .collect();
+
+                                if photos.is_empty() {
+                                    continue;
+                                }
+
+                                has_matches = true;
+
+                                ui.label(RichText::new(title).strong());
+                                ui.horizontal_wrapped(|ui| {
+                                    for photo in photos {
+                                        let texture =
+                                            photo_manager.with_lock_mut(|photo_manager| {
+                                                photo_manager.thumbnail_texture_for(photo, ui.ctx())
+                                            });
+
+                                        let selected = state.selected.contains(&photo.path);
+                                        let image_response = ui
+                                            .allocate_ui(Vec2::splat(THUMBNAIL_SIZE), |ui| {
+                                                ui.add(GalleryImage::new(
+                                                    photo.clone(),
+                                                    texture,
+                                                    selected,
+                                                ))
+                                            })
+                                            .inner;
+
+                                        self.paint_placed_badge(ui, image_response.rect, photo);
+
+                                        if image_response.clicked() {
+                                            let toggle = self.allow_multiple
+                                                && ui.input(|input| input.modifiers.command);
+
+                                            if toggle {
+                                                if selected {
+                                                    state
+                                                        .selected
+                                                        .retain(|path| *path != photo.path);
+                                                } else {
+                                                    state.selected.push(photo.path.clone());
+                                                }
+                                            } else {
+                                                response =
+                                                    PhotoPickerResponse::Picked(
+                                                        vec![photo.clone()],
+                                                    );
+                                            }
+                                        }
+                                    }
+                                });
+                            }
+
+                            if !has_matches {
+                                ui.label("No photos match");
+                            }
+                        });
+
+                    if self.allow_multiple {
+                        ui.separator();
+                        ui.horizontal(|ui| {
+                            if state.selected.is_empty() {
+                                ui.label(RichText::new("Ctrl-click to choose several").weak());
+                                return;
+                            }
+
+                            ui.with_layout(Layout::right_to_left(egui::Align::Center), |ui| {
+                                let insert_label = match state.selected.len() {
+                                    1 => "Insert 1 Photo".to_string(),
+                                    count => format!("Insert {} Photos", count),
+                                };
+                                if ui.button(insert_label).clicked() {
+                                    let photos: Vec<Photo> =
+                                        photo_manager.with_lock(|photo_manager| {
+                                            state
+                                                .selected
+                                                .iter()
+                                                .filter_map(|path| photo_manager.photos.get(path))
+                                                .cloned()
+                                                .collect()
+                                        });
+                                    response = PhotoPickerResponse::Picked(photos);
+                                }
+
+                                if ui.button("Clear").clicked() {
+                                    state.selected.clear();
+                                }
+                            });
+                        });
+                    }
+                });
+            });
+
+        if matches!(response, PhotoPickerResponse::None)
+            && !is_first_frame
+            && (ctx.input(|input| input.key_pressed(Key::Escape))
+                || area_response.response.clicked_elsewhere())
+        {
+            response = PhotoPickerResponse::Closed;
+        }
+
+        match response {
+            PhotoPickerResponse::None => ctx.data_mut(|data| data.insert_temp(id, state)),
+            _ => ctx.data_mut(|data| data.remove::<PhotoPickerState>(id)),
+        }
+
+        response
+    }
+
+    // Marks photos that are already used so the same photo isn't placed twice by accident
+    fn paint_placed_badge(&self, ui: &egui::Ui, rect: Rect, photo: &Photo) {
+        let (label, fill) = if self.placed_on_page.contains(&photo.path) {
+            ("On Page", theme::color::focused(ui.visuals()))
+        } else if self.placed_in_book.contains(&photo.path) {
+            ("In Book", Color32::from_gray(60))
+        } else {
+            return;
+        };
+
+        let badge_rect = Rect::from_min_size(
+            rect.right_top() + Vec2::new(-66.0, 6.0),
+            Vec2::new(60.0, 20.0),
+        );
+        ui.painter().rect_filled(badge_rect, 4.0, fill);
+        ui.painter().text(
+            badge_rect.center(),
+            Align2::CENTER_CENTER,
+            label,
+            FontId::proportional(12.0),
+            Color32::WHITE,
+        );
+    }
+}
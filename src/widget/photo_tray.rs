@@ -0,0 +1,232 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+};
+
+use eframe::egui;
+use egui::{Align2, Button, Color32, FontId, Frame, Layout, Rect, ScrollArea, Sense, Stroke, Vec2};
+
+use crate::{
+    dependencies::{Dependency, Singleton, SingletonFor},
+    photo::Photo,
+    photo_manager::PhotoManager,
+    project_settings::ProjectSettingsManager,
+    theme,
+    utils::Truncate,
+};
+
+const THUMBNAIL_SIZE: f32 = 96.0;
+
+/// Carries a photo being dragged from the gallery or the tray so the canvas can place it where
+/// it's dropped
+#[derive(Debug, Clone)]
+pub struct DraggedPhoto(pub Photo);
+
+/// A strip of candidate photos set aside while laying out the book. Photos are dragged in from
+/// the gallery and out onto the canvas, and show how often they've been placed.
+pub struct PhotoTray<'a> {
+    // How many times each photo is placed in the book
+    usage: &'a HashMap<PathBuf, usize>,
+    gallery_selection: &'a HashSet<PathBuf>,
+}
+
+impl<'a> PhotoTray<'a> {
+    pub fn new(
+        usage: &'a HashMap<PathBuf, usize>,
+        gallery_selection: &'a HashSet<PathBuf>,
+    ) -> Self {
+        Self {
+            usage,
+            gallery_selection,
+        }
+    }
+
+    pub fn show(&self, ui: &mut egui::Ui) {
+        let photo_manager: Singleton<PhotoManager> = Dependency::get();
+        let project_settings: Singleton<ProjectSettingsManager> = Dependency::get();
+
+        let tray = project_settings.with_lock(|manager| manager.project_settings.tray.clone());
+
+        let bottom_bar_height = 40.0;
+        let mut tray_size = ui.available_size();
+        tray_size.y = (tray_size.y - bottom_bar_height).max(THUMBNAIL_SIZE);
+
+        let mut removed = None;
+
+        let dropped = ui
+            .allocate_ui(tray_size, |ui| {
+                ui.dnd_drop_zone::<DraggedPhoto, _>(Frame::none().inner_margin(5.0), |ui| {
+                    ui.set_min_size(ui.available_size());
+
+                    if tray.is_empty() {
+                        ui.centered_and_justified(|ui| {
+                            ui.label("Drag photos here from the gallery to keep them at hand");
+                        });
+                        return;
+                    }
+
+                    ScrollArea::vertical()
+                        .auto_shrink([false, false])
+                        .show(ui, |ui| {
+                            ui.horizontal_wrapped(|ui| {
+                                for path in &tray {
+                                    let photo = photo_manager.with_lock(|photo_manager| {
+                                        photo_manager.photos.get(path).cloned()
+                                    });
+
+                                    let response = match photo {
+                                        Some(photo) => {
+                                            let id = ui.id().with(("tray_photo", path));
+                                            ui.dnd_drag_source(
+                                                id,
+                                                DraggedPhoto(photo.clone()),
+                                                |ui| self.thumbnail(ui, &photo, &photo_manager),
+                                            )
+                                            .inner
+                                        }
+                                        None => Self::missing_thumbnail(ui, path),
+                                    };
+
+                                    response.context_menu(|ui| {
+                                        if ui.button("Remove from Tray").clicked() {
+                                            removed = Some(path.clone());
+                                            ui.close_menu();
+                                        }
+                                    });
+                                }
+                            });
+                        });
+                })
+                .1
+            })
+            .inner;
+
+        ui.with_layout(Layout::right_to_left(egui::Align::Center), |ui| {
+            ui.add_space(10.0);
+
+            if ui
+                .add_enabled(!tray.is_empty(), Button::new("Clear"))
+                .clicked()
+            {
+                project_settings.with_lock_mut(|manager| manager.project_settings.tray.clear());
+            }
+
+            let any_placed = tray.iter().any(|path| self.usage.contains_key(path));
+            if ui
+                .add_enabled(any_placed, Button::new("Remove Placed"))
+                .on_hover_text("Take out the photos that are already in the book")
+                .clicked()
+            {
+                project_settings.with_lock_mut(|manager| {
+                    manager
+                        .project_settings
+                        .tray
+                        .retain(|path| !self.usage.contains_key(path))
+                });
+            }
+
+            let selection_count = self.gallery_selection.len();
+            if ui
+                .add_enabled(
+                    selection_count > 0,
+                    Button::new(format!("Add {} Selected", selection_count)),
+                )
+                .on_hover_text("Add the photos selected in the gallery")
+                .clicked()
+            {
+                let mut selection: Vec<PathBuf> = self.gallery_selection.iter().cloned().collect();
+                selection.sort();
+                project_settings.with_lock_mut(|manager| {
+                    manager.project_settings.add_to_tray(selection);
+                });
+            }
+        });
+
+        if let Some(dropped) = dropped {
+            project_settings.with_lock_mut(|manager| {
+                manager
+                    .project_settings
+                    .add_to_tray([dropped.0.path.clone()]);
+            });
+        }
+
+        if let Some(removed) = removed {
+            project_settings.with_lock_mut(|manager| {
+                manager
+                    .project_settings
+                    .tray
+                    .retain(|path| *path != removed);
+            });
+        }
+    }
+
+    fn thumbnail(
+        &self,
+        ui: &mut egui::Ui,
+        photo: &Photo,
+        photo_manager: &Singleton<PhotoManager>,
+    ) -> egui::Response {
+        let (rect, response) = ui.allocate_exact_size(Vec2::splat(THUMBNAIL_SIZE), Sense::click());
+        ui.painter()
+            .rect_filled(rect, 4.0, theme::color::placeholder(ui.visuals()));
+
+        let texture = photo_manager
+            .with_lock_mut(|photo_manager| photo_manager.thumbnail_texture_for(photo, ui.ctx()));
+
+        if let Ok(Some(texture)) = texture {
+            photo.paint_fitted(ui, texture, rect.shrink(4.0));
+        }
+
+        if let Some(count) = self.usage.get(&photo.path) {
+            let badge_rect = Rect::from_min_size(
+                rect.right_top() + Vec2::new(-34.0, 4.0),
+                Vec2::new(30.0, 18.0),
+            );
+            ui.painter()
+                .rect_filled(badge_rect, 4.0, theme::color::focused(ui.visuals()));
+            ui.painter().text(
+                badge_rect.center(),
+                Align2::CENTER_CENTER,
+                format!("✔ {}", count),
+                FontId::proportional(11.0),
+                Color32::WHITE,
+            );
+        }
+
+        if response.hovered() {
+            ui.painter().rect_stroke(
+                rect,
+                4.0,
+                Stroke::new(2.0, theme::color::focused(ui.visuals())),
+            );
+        }
+
+        response.on_hover_text(match self.usage.get(&photo.path) {
+            Some(count) => format!(
+                "{}\nPlaced {} time{}",
+                photo.file_name(),
+                count,
+                if *count == 1 { "" } else { "s" }
+            ),
+            None => format!("{}\nNot placed yet", photo.file_name()),
+        })
+    }
+
+    // Photos removed from the project since they were put in the tray
+    fn missing_thumbnail(ui: &mut egui::Ui, path: &PathBuf) -> egui::Response {
+        let (rect, response) = ui.allocate_exact_size(Vec2::splat(THUMBNAIL_SIZE), Sense::click());
+        ui.painter()
+            .rect_filled(rect, 4.0, theme::color::placeholder(ui.visuals()));
+        ui.painter().text(
+            rect.center(),
+            Align2::CENTER_CENTER,
+            path.file_name()
+                .map(|file_name| file_name.to_string_lossy().truncate(14))
+                .unwrap_or_default(),
+            FontId::proportional(11.0),
+            ui.visuals().weak_text_color(),
+        );
+
+        response.on_hover_text("Not in the project anymore")
+    }
+}
@@ -1,4 +1,5 @@
 use eframe::egui::{Color32, Response, Sense, Ui, Vec2, Widget};
+use egui::{Align2, FontId, Painter, Pos2, Rect, Stroke};
 
 pub struct RectPlaceholder {
     size: Vec2,
@@ -18,3 +19,50 @@ impl Widget for RectPlaceholder {
         response
     }
 }
+
+const MISSING_HATCH_SPACING: f32 = 12.0;
+const MISSING_COLOR: Color32 = Color32::from_rgb(200, 60, 60);
+
+/// Drawn in place of a photo whose file can't be found, so it isn't silently left blank
+pub struct MissingPhotoPlaceholder;
+
+impl MissingPhotoPlaceholder {
+    pub fn paint(painter: &Painter, rect: Rect, file_name: &str) {
+        let painter = painter.with_clip_rect(rect.intersect(painter.clip_rect()));
+
+        painter.rect_filled(rect, 0.0, Color32::from_gray(235));
+
+        // Diagonal hatch across the whole rect
+        let stroke = Stroke::new(1.0, MISSING_COLOR.gamma_multiply(0.5));
+        let mut offset = 0.0;
+        while offset < rect.width() + rect.height() {
+            painter.line_segment(
+                [
+                    Pos2::new(rect.left() + offset, rect.top()),
+                    Pos2::new(rect.left() + offset - rect.height(), rect.bottom()),
+                ],
+                stroke,
+            );
+            offset += MISSING_HATCH_SPACING;
+        }
+
+        painter.rect_stroke(rect.shrink(1.0), 0.0, Stroke::new(2.0, MISSING_COLOR));
+
+        let font_size = (rect.height() / 10.0).clamp(8.0, 16.0);
+
+        painter.text(
+            rect.center(),
+            Align2::CENTER_BOTTOM,
+            file_name,
+            FontId::proportional(font_size),
+            Color32::BLACK,
+        );
+        painter.text(
+            rect.center(),
+            Align2::CENTER_TOP,
+            "Missing",
+            FontId::proportional(font_size),
+            MISSING_COLOR,
+        );
+    }
+}
@@ -0,0 +1,222 @@
+use eframe::egui;
+use egui::{
+    Align2, Area, Color32, FontId, Frame, Id, Key, Order, Pos2, Rect, Sense, Stroke, TextEdit, Vec2,
+};
+
+use crate::{id::CommentId, model::page_comment::PageComment};
+
+const PIN_RADIUS: f32 = 11.0;
+
+const OPEN_PIN_COLOR: Color32 = Color32::from_rgb(235, 130, 40);
+const RESOLVED_PIN_COLOR: Color32 = Color32::from_gray(140);
+
+/// Review mode replaces editing on the canvas with placing and answering comment pins
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ReviewState {
+    pub enabled: bool,
+    pub selected_comment: Option<CommentId>,
+    pub show_resolved: bool,
+}
+
+pub struct ReviewPins<'a> {
+    comments: &'a mut Vec<PageComment>,
+    state: &'a mut ReviewState,
+    page_rect: Rect,
+    id: Id,
+}
+
+impl<'a> ReviewPins<'a> {
+    pub fn new(
+        comments: &'a mut Vec<PageComment>,
+        state: &'a mut ReviewState,
+        page_rect: Rect,
+        id: Id,
+    ) -> Self {
+        Self {
+            comments,
+            state,
+            page_rect,
+            id,
+        }
+    }
+
+    /// Clicking the page adds a pin, clicking a pin opens it and dragging a pin moves it
+    pub fn show(&mut self, ui: &mut egui::Ui) {
+        let page_rect = self.page_rect;
+
+        // Registered before the pins so the pins get clicks on top of the page
+        let page_response = ui.interact(self.page_rect, self.id.with("page"), Sense::click());
+
+        for number in 0..self.comments.len() {
+            let comment = &mut self.comments[number];
+            if comment.resolved
+                && !self.state.show_resolved
+                && self.state.selected_comment != Some(comment.id)
+            {
+                continue;
+            }
+
+            let center = page_position(page_rect, comment.position);
+            let response = ui.interact(
+                Rect::from_center_size(center, Vec2::splat(PIN_RADIUS * 2.0)),
+                self.id.with(("pin", comment.id)),
+                Sense::click_and_drag(),
+            );
+
+            if response.dragged() {
+                comment.position = (comment.position + response.drag_delta() / page_rect.size())
+                    .clamp(Pos2::ZERO, Pos2::new(1.0, 1.0));
+            }
+
+            if response.clicked() || response.drag_started() {
+                self.state.selected_comment = Some(comment.id);
+            }
+
+            let selected = self.state.selected_comment == Some(comment.id);
+            Self::paint_pin(
+                ui,
+                page_position(page_rect, comment.position),
+                number + 1,
+                comment,
+                selected,
+            );
+
+            response.on_hover_text(if comment.text.is_empty() {
+                "No comment yet".to_string()
+            } else {
+                comment.text.clone()
+            });
+        }
+
+        if page_response.clicked() {
+            if self.state.selected_comment.is_some() {
+                self.close_selected();
+            } else if let Some(pointer) = page_response.interact_pointer_pos() {
+                let comment =
+                    PageComment::new(((pointer - page_rect.min) / page_rect.size()).to_pos2());
+                self.state.selected_comment = Some(comment.id);
+                self.comments.push(comment);
+            }
+        }
+
+        self.show_editor(ui);
+    }
+
+    fn paint_pin(
+        ui: &egui::Ui,
+        center: Pos2,
+        number: usize,
+        comment: &PageComment,
+        selected: bool,
+    ) {
+        let painter = ui.painter();
+        let fill = if comment.resolved {
+            RESOLVED_PIN_COLOR
+        } else {
+            OPEN_PIN_COLOR
+        };
+
+        painter.circle_filled(
+            center + Vec2::new(1.0, 2.0),
+            PIN_RADIUS,
+            Color32::from_black_alpha(80),
+        );
+        painter.circle(
+            center,
+            PIN_RADIUS,
+            fill,
+            Stroke::new(if selected { 3.0 } else { 1.5 }, Color32::WHITE),
+        );
+        painter.text(
+            center,
+            Align2::CENTER_CENTER,
+            number.to_string(),
+            FontId::proportional(12.0),
+            Color32::WHITE,
+        );
+    }
+
+    // Deselects the open comment, throwing it away if nothing was written in it
+    fn close_selected(&mut self) {
+        if let Some(selected) = self.state.selected_comment.take() {
+            self.comments
+                .retain(|comment| comment.id != selected || !comment.text.trim().is_empty());
+        }
+    }
+
+    fn show_editor(&mut self, ui: &mut egui::Ui) {
+        let Some(selected) = self.state.selected_comment else {
+            return;
+        };
+
+        let Some(index) = self
+            .comments
+            .iter()
+            .position(|comment| comment.id == selected)
+        else {
+            self.state.selected_comment = None;
+            return;
+        };
+
+        let anchor = page_position(self.page_rect, self.comments[index].position)
+            + Vec2::new(PIN_RADIUS + 6.0, -PIN_RADIUS);
+
+        let mut close = false;
+        let mut delete = false;
+
+        Area::new(self.id.with("comment_editor"))
+            .order(Order::Foreground)
+            .fixed_pos(anchor)
+            .show(ui.ctx(), |ui| {
+                Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.set_width(240.0);
+
+                    let comment = &mut self.comments[index];
+                    ui.label(format!(
+                        "Comment {} · {}",
+                        index + 1,
+                        comment.created_label()
+                    ));
+
+                    let text_response = ui.add(
+                        TextEdit::multiline(&mut comment.text)
+                            .hint_text("Write a comment")
+                            .desired_rows(3)
+                            .desired_width(f32::INFINITY),
+                    );
+
+                    // New comments are typed into straight away
+                    if comment.text.is_empty() && !text_response.has_focus() {
+                        text_response.request_focus();
+                    }
+
+                    ui.horizontal(|ui| {
+                        ui.checkbox(&mut comment.resolved, "Resolved");
+
+                        if ui.button("Delete").clicked() {
+                            delete = true;
+                        }
+
+                        if ui.button("Done").clicked() {
+                            close = true;
+                        }
+                    });
+                });
+            });
+
+        if ui.input(|input| input.key_pressed(Key::Escape)) {
+            close = true;
+        }
+
+        if delete {
+            self.comments.remove(index);
+            self.state.selected_comment = None;
+        } else if close {
+            self.close_selected();
+        }
+    }
+}
+
+fn page_position(page_rect: Rect, position: Pos2) -> Pos2 {
+    page_rect.min + position.to_vec2() * page_rect.size()
+}
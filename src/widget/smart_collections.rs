@@ -0,0 +1,124 @@
+use eframe::egui::{self, RichText, ScrollArea};
+use log::error;
+
+use crate::{
+    auto_persisting::AutoPersisting,
+    config::{Config, ConfigModification},
+    dependencies::{Dependency, Singleton, SingletonFor},
+    modal::{manager::ModalManager, smart_collection::SmartCollectionModal},
+    photo_manager::PhotoManager,
+    theme,
+};
+
+/// The gallery sidebar listing the whole library and the smart collections. Choosing a
+/// collection narrows the gallery to the photos matching its query.
+pub struct SmartCollections;
+
+impl SmartCollections {
+    pub fn show(ui: &mut egui::Ui) {
+        let config: Singleton<AutoPersisting<Config>> = Dependency::get();
+        let photo_manager: Singleton<PhotoManager> = Dependency::get();
+
+        let collections = config.with_lock_mut(|config| {
+            config
+                .read()
+                .map(|config| config.smart_collections().to_vec())
+                .unwrap_or_default()
+        });
+        let (active, error, search_text) = photo_manager.with_lock(|photo_manager| {
+            (
+                photo_manager.active_collection().map(str::to_string),
+                photo_manager
+                    .collection_error()
+                    .map(|error| error.to_string()),
+                photo_manager.search_text().to_string(),
+            )
+        });
+
+        // The collection may have been deleted or renamed elsewhere
+        if let Some(name) = &active {
+            if !collections
+                .iter()
+                .any(|collection| collection.name == *name)
+            {
+                photo_manager.with_lock_mut(|photo_manager| photo_manager.set_collection(None));
+            }
+        }
+
+        ScrollArea::vertical()
+            .auto_shrink([false, false])
+            .show(ui, |ui| {
+                ui.label(RichText::new("Library").strong());
+                if ui
+                    .selectable_label(active.is_none(), "All Photos")
+                    .clicked()
+                {
+                    photo_manager.with_lock_mut(|photo_manager| photo_manager.set_collection(None));
+                }
+
+                ui.add_space(10.0);
+
+                ui.horizontal(|ui| {
+                    ui.label(RichText::new("Smart Collections").strong());
+                    if ui
+                        .small_button("+")
+                        .on_hover_text("New smart collection from the current search")
+                        .clicked()
+                    {
+                        ModalManager::push(SmartCollectionModal::new(search_text.clone()));
+                    }
+                });
+
+                if collections.is_empty() {
+                    ui.label(RichText::new("Save a search as a collection with +").weak());
+                }
+
+                let mut deleted = None;
+
+                for collection in &collections {
+                    let is_active = active.as_deref() == Some(collection.name.as_str());
+                    let response = ui
+                        .selectable_label(is_active, &collection.name)
+                        .on_hover_text(&collection.query);
+
+                    if response.clicked() && !is_active {
+                        photo_manager.with_lock_mut(|photo_manager| {
+                            photo_manager.set_collection(Some(collection))
+                        });
+                    }
+
+                    response.context_menu(|ui| {
+                        if ui.button("Edit...").clicked() {
+                            ModalManager::push(SmartCollectionModal::edit(collection.clone()));
+                            ui.close_menu();
+                        }
+
+                        if ui.button("Delete").clicked() {
+                            deleted = Some(collection.name.clone());
+                            ui.close_menu();
+                        }
+                    });
+                }
+
+                if let Some(error) = &error {
+                    ui.add_space(6.0);
+                    ui.label(RichText::new(error).color(theme::color::WARNING));
+                }
+
+                if let Some(name) = deleted {
+                    if active.as_ref() == Some(&name) {
+                        photo_manager
+                            .with_lock_mut(|photo_manager| photo_manager.set_collection(None));
+                    }
+
+                    config.with_lock_mut(|config| {
+                        if let Err(err) =
+                            config.modify(ConfigModification::DeleteSmartCollection(name))
+                        {
+                            error!("Failed to delete smart collection: {:?}", err);
+                        }
+                    });
+                }
+            });
+    }
+}
@@ -0,0 +1,175 @@
+use eframe::egui;
+use egui::{Button, Layout, Rect, RichText, ScrollArea, Sense, TextEdit, Vec2};
+use indexmap::IndexMap;
+
+use crate::{
+    dependencies::{Dependency, Singleton, SingletonFor},
+    model::edit_state::EditablePage,
+    scene::canvas_scene::CanvasHistoryManager,
+    snippet::{Snippet, SnippetId, SnippetManager},
+};
+
+use super::canvas::{Canvas, CanvasState};
+
+const THUMBNAIL_SIZE: f32 = 96.0;
+
+/// A snippet being dragged onto a page
+#[derive(Debug, Clone)]
+pub struct DraggedSnippet(pub Snippet);
+
+pub enum SnippetsResponse {
+    None,
+    // Add the snippet to the middle of the selected page
+    Add(Snippet),
+}
+
+/// Lists the project's snippets, which are dragged onto a page to add them. The selected layers
+/// of the selected page can be saved as a new one.
+pub struct Snippets<'a> {
+    selected_page: Option<&'a CanvasState>,
+}
+
+impl<'a> Snippets<'a> {
+    pub fn new(selected_page: Option<&'a CanvasState>) -> Self {
+        Self { selected_page }
+    }
+
+    pub fn show(&mut self, ui: &mut egui::Ui) -> SnippetsResponse {
+        let snippet_manager: Singleton<SnippetManager> = Dependency::get();
+
+        self.save_ui(ui, &snippet_manager);
+
+        ui.separator();
+
+        let mut response = SnippetsResponse::None;
+        let mut deleted: Option<SnippetId> = None;
+
+        ScrollArea::vertical()
+            .auto_shrink([false, false])
+            .show(ui, |ui| {
+                snippet_manager.with_lock(|snippet_manager| {
+                    if snippet_manager.snippets.is_empty() {
+                        ui.centered_and_justified(|ui| {
+                            ui.label("Select layers and save them to reuse them on other pages");
+                        });
+                        return;
+                    }
+
+                    for snippet in &snippet_manager.snippets {
+                        ui.horizontal(|ui| {
+                            let drag_id = egui::Id::new(("snippet", snippet.id));
+                            ui.dnd_drag_source(drag_id, DraggedSnippet(snippet.clone()), |ui| {
+                                let (thumbnail_rect, _) = ui.allocate_exact_size(
+                                    Vec2::splat(THUMBNAIL_SIZE),
+                                    Sense::hover(),
+                                );
+                                Self::show_thumbnail(ui, snippet, thumbnail_rect);
+                            })
+                            .response
+                            .on_hover_text("Drag onto a page to add it");
+
+                            ui.vertical(|ui| {
+                                ui.label(RichText::new(&snippet.name).strong());
+                                ui.label(match snippet.layers.len() {
+                                    1 => "1 layer".to_string(),
+                                    count => format!("{} layers", count),
+                                });
+
+                                ui.horizontal(|ui| {
+                                    if ui
+                                        .add_enabled(
+                                            self.selected_page.is_some(),
+                                            Button::new("Add to Page"),
+                                        )
+                                        .clicked()
+                                    {
+                                        response = SnippetsResponse::Add(snippet.clone());
+                                    }
+
+                                    if ui.small_button("🗑").on_hover_text("Delete").clicked() {
+                                        deleted = Some(snippet.id);
+                                    }
+                                });
+                            });
+                        });
+                        ui.separator();
+                    }
+                });
+            });
+
+        if let Some(id) = deleted {
+            snippet_manager.with_lock_mut(|snippet_manager| snippet_manager.delete(id));
+        }
+
+        response
+    }
+
+    fn save_ui(&self, ui: &mut egui::Ui, snippet_manager: &Singleton<SnippetManager>) {
+        let selected_layers: Vec<_> = self
+            .selected_page
+            .map(|page| {
+                page.layers
+                    .values()
+                    .filter(|layer| layer.selected && Snippet::can_save(layer))
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let name_id = ui.id().with("snippet_name");
+        let mut name: String = ui.data(|data| data.get_temp(name_id)).unwrap_or_default();
+
+        ui.horizontal(|ui| {
+            ui.add(
+                TextEdit::singleline(&mut name)
+                    .hint_text("Name")
+                    .desired_width((ui.available_width() - 110.0).max(60.0)),
+            );
+
+            ui.with_layout(Layout::right_to_left(egui::Align::Center), |ui| {
+                let can_save = !selected_layers.is_empty() && !name.trim().is_empty();
+                if ui
+                    .add_enabled(can_save, Button::new("Save Selection"))
+                    .on_disabled_hover_text(
+                        "Select layers and name the snippet, template regions can't be saved",
+                    )
+                    .clicked()
+                {
+                    let page = &self.selected_page.unwrap().page.value;
+                    if let Some(snippet) =
+                        Snippet::new(name.trim().to_string(), selected_layers, page)
+                    {
+                        snippet_manager
+                            .with_lock_mut(|snippet_manager| snippet_manager.add(snippet));
+                        name.clear();
+                    }
+                }
+            });
+        });
+
+        ui.data_mut(|data| data.insert_temp(name_id, name));
+    }
+
+    fn show_thumbnail(ui: &mut egui::Ui, snippet: &Snippet, rect: Rect) {
+        let layers: IndexMap<_, _> = snippet
+            .layers
+            .iter()
+            .map(|layer| (layer.id, layer.clone()))
+            .collect();
+        let layer_ids = layers.keys().copied().collect();
+
+        let mut preview_state = CanvasState::with_layers(
+            layers,
+            EditablePage::new(snippet.page.clone()),
+            None,
+            layer_ids,
+        );
+
+        Canvas::new(
+            &mut preview_state,
+            rect,
+            &mut CanvasHistoryManager::preview(),
+        )
+        .show_preview(ui, rect);
+    }
+}
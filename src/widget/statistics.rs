@@ -0,0 +1,186 @@
+use eframe::egui;
+use egui::{DragValue, Grid, Layout, RichText, ScrollArea};
+use log::{error, info};
+use strum::IntoEnumIterator;
+
+use crate::{
+    auto_persisting::AutoPersisting,
+    book_statistics::BookStatistics,
+    book_vendor::{BookVendor, VendorPricing},
+    config::{Config, ConfigModification},
+    dependencies::{Dependency, Singleton, SingletonFor},
+    modal::{basic::BasicModal, manager::ModalManager},
+    theme,
+};
+
+use super::pages::PagesState;
+
+/// Counts of what's in the book and what printing it would cost, worked out again every frame so
+/// they follow pages as they're added
+pub struct Statistics<'a> {
+    pages_state: &'a PagesState,
+}
+
+impl<'a> Statistics<'a> {
+    pub fn new(pages_state: &'a PagesState) -> Self {
+        Self { pages_state }
+    }
+
+    pub fn show(&mut self, ui: &mut egui::Ui) {
+        let config: Singleton<AutoPersisting<Config>> = Dependency::get();
+        let saved_pricing = config.with_lock(|config| {
+            config
+                .read()
+                .map(|config| config.print_pricing())
+                .unwrap_or_else(|_| {
+                    BookVendor::iter()
+                        .map(|vendor| vendor.default_pricing())
+                        .collect()
+                })
+        });
+
+        // Prices being edited are only saved once the drag ends or the field loses focus
+        let pricing_id = ui.id().with("print_pricing");
+        let pricing = ui
+            .data(|data| data.get_temp::<Vec<VendorPricing>>(pricing_id))
+            .unwrap_or_else(|| saved_pricing.clone());
+
+        let statistics = BookStatistics::new(&self.pages_state.pages, &pricing);
+
+        ui.horizontal(|ui| {
+            ui.heading("Book");
+            ui.with_layout(Layout::right_to_left(egui::Align::Center), |ui| {
+                if ui.button("Export...").clicked() {
+                    Self::export(&statistics);
+                }
+            });
+        });
+
+        ui.separator();
+
+        ScrollArea::vertical()
+            .auto_shrink([false, false])
+            .show(ui, |ui| {
+                Grid::new("book_statistics")
+                    .num_columns(2)
+                    .spacing([20.0, 4.0])
+                    .show(ui, |ui| {
+                        for (name, value) in [
+                            ("Pages", statistics.page_count),
+                            ("Photos Placed", statistics.photo_count),
+                            ("Different Photos", statistics.unique_photo_count),
+                            ("Text Layers", statistics.text_count),
+                            ("Empty Pages", statistics.empty_page_count),
+                        ] {
+                            ui.label(name);
+                            ui.label(value.to_string());
+                            ui.end_row();
+                        }
+                    });
+
+                ui.add_space(10.0);
+                ui.heading("Print Cost");
+                ui.label(
+                    RichText::new(
+                        "The base price covers the vendor's fewest pages, set both to what your \
+                         book's size, paper and cover cost",
+                    )
+                    .weak(),
+                );
+                ui.add_space(4.0);
+
+                let mut edited_pricing = pricing.clone();
+                let mut editing_ended = false;
+
+                Grid::new("book_print_cost")
+                    .num_columns(4)
+                    .spacing([12.0, 4.0])
+                    .show(ui, |ui| {
+                        ui.label(RichText::new("Vendor").strong());
+                        ui.label(RichText::new("Base").strong());
+                        ui.label(RichText::new("Per Extra Page").strong());
+                        ui.label(RichText::new("Estimate").strong());
+                        ui.end_row();
+
+                        for (edited, estimate) in
+                            edited_pricing.iter_mut().zip(&statistics.estimates)
+                        {
+                            ui.label(edited.vendor.to_string());
+                            let base_price = ui.add(
+                                DragValue::new(&mut edited.base_price)
+                                    .range(0.0..=10_000.0)
+                                    .speed(0.5)
+                                    .max_decimals(2),
+                            );
+                            let price_per_extra_page = ui.add(
+                                DragValue::new(&mut edited.price_per_extra_page)
+                                    .range(0.0..=100.0)
+                                    .speed(0.05)
+                                    .max_decimals(2),
+                            );
+                            ui.label(format!("{:.2}", estimate.cost));
+                            ui.end_row();
+
+                            for response in [base_price, price_per_extra_page] {
+                                editing_ended |= response.drag_stopped() || response.lost_focus();
+                            }
+                        }
+                    });
+
+                for warning in statistics
+                    .estimates
+                    .iter()
+                    .filter_map(|estimate| estimate.warning())
+                {
+                    ui.label(RichText::new(warning).color(theme::color::WARNING));
+                }
+
+                if editing_ended {
+                    ui.data_mut(|data| data.remove::<Vec<VendorPricing>>(pricing_id));
+                    for edited in edited_pricing
+                        .into_iter()
+                        .filter(|edited| !saved_pricing.contains(edited))
+                    {
+                        Self::set_pricing(&config, edited);
+                    }
+                } else if edited_pricing != pricing {
+                    ui.data_mut(|data| data.insert_temp(pricing_id, edited_pricing));
+                }
+            });
+    }
+
+    fn set_pricing(config: &Singleton<AutoPersisting<Config>>, pricing: VendorPricing) {
+        config.with_lock_mut(|config| {
+            if let Err(err) = config.modify(ConfigModification::SetPrintPricing(pricing)) {
+                error!("Failed to update print pricing: {:?}", err);
+            }
+        });
+    }
+
+    fn export(statistics: &BookStatistics) {
+        let path = match native_dialog::FileDialog::new()
+            .set_filename("book statistics.csv")
+            .add_filter("CSV", &["csv"])
+            .show_save_single_file()
+        {
+            Ok(Some(path)) => path,
+            Ok(None) => {
+                info!("No statistics export path selected");
+                return;
+            }
+            Err(err) => {
+                error!("Error opening statistics export dialog: {:?}", err);
+                return;
+            }
+        };
+
+        if let Err(err) = statistics.write(&path) {
+            error!("Failed to export statistics: {:?}", err);
+            ModalManager::push(BasicModal::new(
+                "Export Statistics",
+                format!("Failed to export statistics: {}", err),
+                "OK",
+            ));
+        }
+    }
+}
@@ -1,26 +1,80 @@
 use eframe::egui;
-use egui::{Color32, FontId, Pos2, Rect, RichText, Sense, Stroke, Vec2};
+use egui::{Color32, FontId, Layout, Pos2, Rect, RichText, ScrollArea, Sense, Stroke, Vec2};
+use log::{error, info};
 
-use egui_extras::Column;
+use crate::{
+    auto_persisting::AutoPersisting,
+    config::{Config, ConfigModification},
+    dependencies::{Dependency, Singleton, SingletonFor},
+    modal::{
+        basic::BasicModal, manager::ModalManager, template_pack_import::TemplatePackImportModal,
+    },
+    template::{
+        self,
+        pack::{TemplatePack, TEMPLATE_PACK_EXTENSION},
+        Template,
+    },
+};
 
-use crate::template::{self, Template};
-
-use super::spacer::Spacer;
+const PREVIEW_SIZE: Vec2 = Vec2::splat(256.0);
 
 pub enum TemplatesResponse {
     None,
     SelectTemplate(Template),
+    NewTemplate,
+    EditTemplate(Template),
 }
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct TemplatesState {
     pub templates: Vec<Template>,
+    // The templates made in the template editor, also in `templates` after the built in ones
+    user_templates: Vec<Template>,
+    // Imported packs, their templates are at the end of `templates`
+    packs: Vec<TemplatePack>,
 }
 
 impl TemplatesState {
     pub fn new() -> TemplatesState {
+        let (user_templates, packs) = Self::saved_templates();
         TemplatesState {
-            templates: template::BUILT_IN.clone(),
+            templates: Self::all_templates(&user_templates, &packs),
+            user_templates,
+            packs,
+        }
+    }
+
+    fn saved_templates() -> (Vec<Template>, Vec<TemplatePack>) {
+        let config: Singleton<AutoPersisting<Config>> = Dependency::get();
+        config.with_lock_mut(|config| {
+            config
+                .read()
+                .map(|config| {
+                    (
+                        config.templates().to_vec(),
+                        config.template_packs().to_vec(),
+                    )
+                })
+                .unwrap_or_default()
+        })
+    }
+
+    fn all_templates(user_templates: &[Template], packs: &[TemplatePack]) -> Vec<Template> {
+        template::BUILT_IN
+            .iter()
+            .chain(user_templates.iter())
+            .chain(packs.iter().flat_map(|pack| pack.templates.iter()))
+            .cloned()
+            .collect()
+    }
+
+    // Picks up templates saved, imported or deleted since the list was built
+    fn sync_user_templates(&mut self) {
+        let (user_templates, packs) = Self::saved_templates();
+        if user_templates != self.user_templates || packs != self.packs {
+            self.templates = Self::all_templates(&user_templates, &packs);
+            self.user_templates = user_templates;
+            self.packs = packs;
         }
     }
 }
@@ -36,61 +90,227 @@ impl<'a> Templates<'a> {
     }
 
     pub fn show(&mut self, ui: &mut egui::Ui) -> TemplatesResponse {
-        ui.spacing_mut().item_spacing = Vec2::splat(10.0);
+        self.state.sync_user_templates();
+
+        let mut response = TemplatesResponse::None;
+
+        ui.horizontal(|ui| {
+            if ui
+                .button("New Template")
+                .on_hover_text("Design a template in the template editor")
+                .clicked()
+            {
+                response = TemplatesResponse::NewTemplate;
+            }
 
-        let window_width = ui.available_width();
-        let window_height = ui.available_height();
-        let column_width = 256.0;
-        let row_height = 256.0;
-        let num_columns: usize = (window_width / column_width).floor() as usize;
+            if ui
+                .button("Import Pack...")
+                .on_hover_text("Add the templates of a shared template pack")
+                .clicked()
+            {
+                Self::import_pack();
+            }
 
-        //let padding_size = num_columns as f32 * 10.0;
-        let spacer_width = (window_width
-            - ((column_width + ui.spacing().item_spacing.x) * num_columns as f32)
-            - 10.0
-            - ui.spacing().item_spacing.x)
-            .max(0.0);
+            if ui
+                .add_enabled(
+                    !self.state.user_templates.is_empty(),
+                    egui::Button::new("Export My Templates..."),
+                )
+                .on_hover_text("Save the templates made here as a pack to share")
+                .clicked()
+            {
+                Self::export_pack(&self.state.user_templates);
+            }
+        });
 
-        let num_rows = self.state.templates.len().div_ceil(num_columns);
+        ui.spacing_mut().item_spacing = Vec2::splat(10.0);
 
         let mut clicked_template = None;
+        let mut deleted_template = None;
+        let mut removed_pack = None;
 
-        egui_extras::TableBuilder::new(ui)
-            .min_scrolled_height(window_height)
-            .columns(Column::exact(column_width), num_columns)
-            .column(Column::exact(spacer_width))
-            .body(|body| {
-                body.rows(row_height, num_rows, |mut row| {
-                    let offest = row.index() * num_columns;
-                    for i in 0..num_columns {
-                        if offest + i >= self.state.templates.len() {
-                            break;
-                        }
-
-                        let template = self.state.templates.get(offest + i).unwrap();
-
-                        if row
-                            .col(|ui| {
-                                TemplatePreview::show(ui, template);
-                            })
-                            .1
-                            .interact(Sense::click())
-                            .double_clicked()
-                        {
-                            clicked_template = Some(template.clone());
-                        }
-                    }
+        ScrollArea::vertical()
+            .auto_shrink([false, false])
+            .show(ui, |ui| {
+                ui.heading("Built In");
+                clicked_template = Self::template_grid(ui, &template::BUILT_IN, |_, _| {})
+                    .or(clicked_template.take());
+
+                // Built in and imported templates can't be changed
+                if !self.state.user_templates.is_empty() {
+                    ui.heading("My Templates");
+                    clicked_template =
+                        Self::template_grid(ui, &self.state.user_templates, |ui, template| {
+                            if ui.button("Edit").clicked() {
+                                response = TemplatesResponse::EditTemplate(template.clone());
+                                ui.close_menu();
+                            }
+
+                            if ui.button("Delete").clicked() {
+                                deleted_template = Some(template.name.clone());
+                                ui.close_menu();
+                            }
+                        })
+                        .or(clicked_template.take());
+                }
 
-                    row.col(|ui| {
-                        ui.add(Spacer::new(spacer_width, row_height));
+                for pack in &self.state.packs {
+                    ui.horizontal(|ui| {
+                        ui.heading(&pack.name);
+                        ui.with_layout(Layout::right_to_left(egui::Align::Center), |ui| {
+                            if ui.button("Remove Pack").clicked() {
+                                removed_pack = Some(pack.name.clone());
+                            }
+                        });
                     });
-                })
+                    clicked_template = Self::template_grid(ui, &pack.templates, |_, _| {})
+                        .or(clicked_template.take());
+                }
+            });
+
+        let config: Singleton<AutoPersisting<Config>> = Dependency::get();
+
+        if let Some(name) = deleted_template {
+            config.with_lock_mut(|config| {
+                if let Err(err) = config.modify(ConfigModification::DeleteTemplate(name)) {
+                    error!("Failed to delete template: {:?}", err);
+                }
+            });
+        }
+
+        if let Some(name) = removed_pack {
+            config.with_lock_mut(|config| {
+                if let Err(err) = config.modify(ConfigModification::DeleteTemplatePack(name)) {
+                    error!("Failed to remove template pack: {:?}", err);
+                }
             });
+        }
 
         if let Some(template) = clicked_template {
             TemplatesResponse::SelectTemplate(template)
         } else {
-            TemplatesResponse::None
+            response
+        }
+    }
+
+    // Previews wrapped to the width of the panel. Returns the template that was double clicked.
+    fn template_grid(
+        ui: &mut egui::Ui,
+        templates: &[Template],
+        mut context_menu: impl FnMut(&mut egui::Ui, &Template),
+    ) -> Option<Template> {
+        let mut clicked_template = None;
+
+        ui.horizontal_wrapped(|ui| {
+            for template in templates {
+                let template_response = ui
+                    .allocate_ui(PREVIEW_SIZE, |ui| {
+                        ui.set_min_size(PREVIEW_SIZE);
+                        TemplatePreview::show(ui, template);
+                    })
+                    .response
+                    .interact(Sense::click());
+
+                if template_response.double_clicked() {
+                    clicked_template = Some(template.clone());
+                }
+
+                template_response.context_menu(|ui| context_menu(ui, template));
+            }
+        });
+
+        clicked_template
+    }
+
+    fn import_pack() {
+        let path = match native_dialog::FileDialog::new()
+            .add_filter("Template Pack", &[TEMPLATE_PACK_EXTENSION])
+            .show_open_single_file()
+        {
+            Ok(Some(path)) => path,
+            Ok(None) => {
+                info!("No template pack selected");
+                return;
+            }
+            Err(err) => {
+                error!("Error opening template pack import dialog: {:?}", err);
+                return;
+            }
+        };
+
+        let pack = match TemplatePack::read(&path) {
+            Ok(pack) => pack,
+            Err(err) => {
+                error!("Failed to read template pack: {:?}", err);
+                ModalManager::push(BasicModal::new(
+                    "Import Template Pack",
+                    format!("Failed to import the template pack: {}", err),
+                    "OK",
+                ));
+                return;
+            }
+        };
+
+        let (_, packs) = TemplatesState::saved_templates();
+
+        // Importing the same pack again changes nothing
+        if packs
+            .iter()
+            .any(|existing| existing.templates == pack.templates)
+        {
+            ModalManager::push(BasicModal::new(
+                "Import Template Pack",
+                format!(
+                    "The templates in \"{}\" have already been imported.",
+                    pack.name
+                ),
+                "OK",
+            ));
+            return;
+        }
+
+        if packs.iter().any(|existing| existing.name == pack.name) {
+            ModalManager::push(TemplatePackImportModal::new(pack));
+            return;
+        }
+
+        Dependency::<AutoPersisting<Config>>::get().with_lock_mut(|config| {
+            if let Err(err) = config.modify(ConfigModification::AddTemplatePack(pack)) {
+                error!("Failed to save template pack: {:?}", err);
+            }
+        });
+    }
+
+    // The pack is named after the file it's saved as
+    fn export_pack(templates: &[Template]) {
+        let path = match native_dialog::FileDialog::new()
+            .set_filename(&format!("My Templates.{}", TEMPLATE_PACK_EXTENSION))
+            .add_filter("Template Pack", &[TEMPLATE_PACK_EXTENSION])
+            .show_save_single_file()
+        {
+            Ok(Some(path)) => path,
+            Ok(None) => {
+                info!("No template pack export path selected");
+                return;
+            }
+            Err(err) => {
+                error!("Error opening template pack export dialog: {:?}", err);
+                return;
+            }
+        };
+
+        let name = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_else(|| "My Templates".to_string());
+
+        if let Err(err) = TemplatePack::new(name, templates.to_vec()).write(&path) {
+            error!("Failed to export template pack: {:?}", err);
+            ModalManager::push(BasicModal::new(
+                "Export Template Pack",
+                format!("Failed to export the template pack: {}", err),
+                "OK",
+            ));
         }
     }
 }
@@ -135,6 +355,7 @@ impl TemplatePreview {
                     template::TemplateRegionKind::Text {
                         sample_text,
                         font_size,
+                        ..
                     } => {
                         ui.painter().rect_stroke(
                             region_rect,
@@ -1,5 +1,6 @@
 use eframe::{
     egui::{self, Button, CursorIcon, Image, Response, Sense, Ui},
+    emath::Rot2,
     epaint::{Color32, Pos2, Rect, Stroke, Vec2},
 };
 use egui::Id;
@@ -70,6 +71,19 @@ pub struct TransformableState {
 }
 
 impl TransformableState {
+    /// Whether `pos` is inside the rect as it's drawn, rotation included, rather than just inside
+    /// its bounding box
+    pub fn contains(&self, container_rect: Rect, scale: f32, pos: Pos2) -> bool {
+        let rect = Rect::from_min_size(
+            container_rect.left_top() + (self.rect.left_top() * scale).to_vec2(),
+            self.rect.size() * scale,
+        );
+
+        // Undo the rotation around the center so the point can be checked against the plain rect
+        let local = Rot2::from_angle(-self.rotation) * (pos - rect.center());
+        local.x.abs() <= rect.width() / 2.0 && local.y.abs() <= rect.height() / 2.0
+    }
+
     pub fn to_local_space(&self, parent: &TransformableState) -> Self {
         let mut new_rect = self.rect;
         new_rect.set_center(parent.rect.center() - self.rect.center().to_vec2());
@@ -87,8 +101,82 @@ impl TransformableState {
     }
 }
 
+/// Positions, in the same space as the transformed rect, that its edges and center snap to
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SnapLines {
+    pub x: Vec<f32>,
+    pub y: Vec<f32>,
+}
+
+impl SnapLines {
+    // How close, in screen points, an edge has to be to a line to snap to it
+    const SNAP_DISTANCE: f32 = 6.0;
+
+    pub fn is_empty(&self) -> bool {
+        self.x.is_empty() && self.y.is_empty()
+    }
+
+    // The smallest offset that puts one of the candidates on a line, if one is close enough
+    fn offset(lines: &[f32], candidates: &[f32], threshold: f32) -> Option<f32> {
+        lines
+            .iter()
+            .flat_map(|line| candidates.iter().map(move |candidate| line - candidate))
+            .filter(|offset| offset.abs() <= threshold)
+            .min_by(|a, b| a.abs().total_cmp(&b.abs()))
+    }
+
+    fn snap_move(&self, rect: Rect, threshold: f32) -> Rect {
+        let offset = Vec2::new(
+            Self::offset(
+                &self.x,
+                &[rect.left(), rect.center().x, rect.right()],
+                threshold,
+            )
+            .unwrap_or(0.0),
+            Self::offset(
+                &self.y,
+                &[rect.top(), rect.center().y, rect.bottom()],
+                threshold,
+            )
+            .unwrap_or(0.0),
+        );
+        rect.translate(offset)
+    }
+
+    // Only the edges the handle moves are snapped
+    fn snap_edges(&self, mut rect: Rect, handle: TransformHandle, threshold: f32) -> Rect {
+        let (left, right, top, bottom) = match handle {
+            TransformHandle::TopLeft => (true, false, true, false),
+            TransformHandle::TopRight => (false, true, true, false),
+            TransformHandle::BottomLeft => (true, false, false, true),
+            TransformHandle::BottomRight => (false, true, false, true),
+            TransformHandle::MiddleTop => (false, false, true, false),
+            TransformHandle::MiddleBottom => (false, false, false, true),
+            TransformHandle::MiddleLeft => (true, false, false, false),
+            TransformHandle::MiddleRight => (false, true, false, false),
+        };
+
+        if left {
+            rect.min.x += Self::offset(&self.x, &[rect.min.x], threshold).unwrap_or(0.0);
+        }
+        if right {
+            rect.max.x += Self::offset(&self.x, &[rect.max.x], threshold).unwrap_or(0.0);
+        }
+        if top {
+            rect.min.y += Self::offset(&self.y, &[rect.min.y], threshold).unwrap_or(0.0);
+        }
+        if bottom {
+            rect.max.y += Self::offset(&self.y, &[rect.max.y], threshold).unwrap_or(0.0);
+        }
+
+        rect
+    }
+}
+
 pub struct TransformableWidget<'a> {
     pub state: &'a mut TransformableState,
+    snap_lines: SnapLines,
+    pointer_over: bool,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -113,7 +201,26 @@ impl<'a> TransformableWidget<'a> {
     const HANDLE_SIZE: Vec2 = Vec2::splat(10.0);
 
     pub fn new(state: &'a mut TransformableState) -> Self {
-        Self { state }
+        Self {
+            state,
+            snap_lines: SnapLines::default(),
+            pointer_over: true,
+        }
+    }
+
+    /// Whether the pointer is over the content itself. When it's only over the bounding box,
+    /// like the empty corners of a rotated rect, clicks go through to whatever is underneath.
+    /// Handles of an active widget and drags that already started are always kept.
+    pub fn with_pointer_over(mut self, pointer_over: bool) -> Self {
+        self.pointer_over = pointer_over;
+        self
+    }
+
+    /// Snaps the rect to the lines while it's moved or freely resized. Rotated rects aren't
+    /// snapped.
+    pub fn with_snap_lines(mut self, snap_lines: SnapLines) -> Self {
+        self.snap_lines = snap_lines;
+        self
     }
 
     pub fn show<R>(
@@ -144,21 +251,6 @@ impl<'a> TransformableWidget<'a> {
         let rotated_inner_content_rect =
             pre_rotated_inner_content_rect.rotate_bb_around_center(self.state.rotation);
 
-        let mut response = if active {
-            // Draw the mode selector above the inner content
-            let mode_selector_response =
-                self.draw_handle_mode_selector(ui, rotated_inner_content_rect.center_top());
-
-            ui.allocate_rect(rotated_inner_content_rect, Sense::click_and_drag())
-                .union(mode_selector_response)
-        } else {
-            ui.allocate_rect(rotated_inner_content_rect, Sense::click_and_drag())
-        };
-
-        response.id = self.state.id;
-
-        let rect = response.rect;
-
         let middle_point = |p1: Pos2, p2: Pos2| p1 + (p2 - p1) / 2.0;
 
         let handles = [
@@ -208,13 +300,54 @@ impl<'a> TransformableWidget<'a> {
             ),
         ];
 
+        let pointer_on_handle = active
+            && ui.ctx().pointer_hover_pos().is_some_and(|pos| {
+                handles.iter().any(|(_, handle_pos)| {
+                    Rect::from_min_size(*handle_pos, Self::HANDLE_SIZE).contains(pos)
+                })
+            });
+
+        let sense = if self.pointer_over
+            || pointer_on_handle
+            || self.state.is_moving
+            || self.state.active_handle.is_some()
+            || ui.ctx().is_being_dragged(self.state.id)
+        {
+            Sense::click_and_drag()
+        } else {
+            Sense::hover()
+        };
+
+        let mut response = if active {
+            // Draw the mode selector above the inner content
+            let mode_selector_response =
+                self.draw_handle_mode_selector(ui, rotated_inner_content_rect.center_top());
+
+            ui.allocate_rect(rotated_inner_content_rect, sense)
+                .union(mode_selector_response)
+        } else {
+            ui.allocate_rect(rotated_inner_content_rect, sense)
+        };
+
+        response.id = self.state.id;
+
+        let rect = response.rect;
+
         // Interact with an expanded rect to include the handles which are partially outside the rect
         let interact_response: Response = ui.interact(
             rotated_inner_content_rect.expand(Self::HANDLE_SIZE.x / 2.0),
             response.id,
-            Sense::click_and_drag(),
+            sense,
         );
 
+        // While snapping, drags are applied to where the rect would be without snapping so it can
+        // be pulled away from a line again
+        let snap_id = self.state.id.with("snap");
+        let previous_snap = ui.data(|data| data.get_temp::<(Rect, Rect)>(snap_id));
+        if let Some((unsnapped_rect, _)) = previous_snap {
+            self.state.rect = unsnapped_rect;
+        }
+
         if active {
             for (handle, rotated_handle_pos) in &handles {
                 let handle_rect: Rect = Rect::from_min_size(*rotated_handle_pos, Self::HANDLE_SIZE);
@@ -478,6 +611,8 @@ impl<'a> TransformableWidget<'a> {
             self.state.change_in_rotation = None;
         }
 
+        self.snap(ui, active, global_scale, previous_snap);
+
         let inner_response = add_contents(ui, pre_rotated_inner_content_rect, self.state);
 
         if active {
@@ -506,6 +641,47 @@ impl<'a> TransformableWidget<'a> {
         }
     }
 
+    fn snap(
+        &mut self,
+        ui: &Ui,
+        active: bool,
+        global_scale: f32,
+        previous_snap: Option<(Rect, Rect)>,
+    ) {
+        let snap_id = self.state.id.with("snap");
+
+        let modifiers_pressed = ui.input(|input| input.modifiers.shift || input.modifiers.alt);
+        let free_resize_handle = match (self.state.handle_mode, self.state.active_handle) {
+            (TransformHandleMode::Resize(ResizeMode::Free), Some(handle)) if !modifiers_pressed => {
+                Some(handle)
+            }
+            _ => None,
+        };
+
+        let snapping = active
+            && !self.snap_lines.is_empty()
+            && self.state.rotation == 0.0
+            && (self.state.is_moving || free_resize_handle.is_some());
+
+        if snapping {
+            let threshold = SnapLines::SNAP_DISTANCE / global_scale;
+            let unsnapped_rect = self.state.rect;
+            let snapped_rect = match free_resize_handle {
+                Some(handle) => self
+                    .snap_lines
+                    .snap_edges(unsnapped_rect, handle, threshold),
+                None => self.snap_lines.snap_move(unsnapped_rect, threshold),
+            };
+
+            self.state.rect = snapped_rect;
+            ui.data_mut(|data| data.insert_temp(snap_id, (unsnapped_rect, snapped_rect)));
+        } else if let Some((_, snapped_rect)) = previous_snap {
+            // The drag ended, so the rect stays where it was last snapped to
+            self.state.rect = snapped_rect;
+            ui.data_mut(|data| data.remove::<(Rect, Rect)>(snap_id));
+        }
+    }
+
     fn update_cursor(
         &self,
         ui: &mut Ui,
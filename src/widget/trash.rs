@@ -0,0 +1,154 @@
+use chrono::{DateTime, Local, Utc};
+use eframe::egui;
+use egui::{Button, DragValue, Layout, Rect, RichText, ScrollArea, Sense, Vec2};
+
+use crate::{
+    auto_persisting::AutoPersisting,
+    config::{Config, ConfigModification, DEFAULT_TRASH_RETENTION_DAYS},
+    dependencies::{Dependency, Singleton, SingletonFor},
+    scene::canvas_scene::CanvasHistoryManager,
+    trash::{TrashEntry, TrashEntryId, TrashManager, TrashedItem},
+};
+
+use super::canvas::Canvas;
+
+const THUMBNAIL_SIZE: f32 = 96.0;
+
+pub enum TrashResponse {
+    None,
+    Restore(TrashEntry),
+}
+
+pub struct Trash {}
+
+impl Trash {
+    pub fn show(ui: &mut egui::Ui) -> TrashResponse {
+        let trash_manager: Singleton<TrashManager> = Dependency::get();
+        let config: Singleton<AutoPersisting<Config>> = Dependency::get();
+
+        let saved_retention_days = config.with_lock_mut(|config| {
+            config
+                .read()
+                .map(|config| config.trash_retention_days())
+                .unwrap_or(DEFAULT_TRASH_RETENTION_DAYS)
+        });
+
+        trash_manager.with_lock_mut(|trash_manager| {
+            trash_manager.purge_older_than(saved_retention_days);
+        });
+
+        // The retention being edited isn't saved, or used to purge the trash, until the drag ends
+        // or the field loses focus
+        let retention_id = ui.id().with("trash_retention_days");
+        let mut retention_days = ui
+            .data(|data| data.get_temp::<u32>(retention_id))
+            .unwrap_or(saved_retention_days);
+
+        let mut restore_id = None;
+
+        let bottom_bar_height = 40.0;
+        let mut list_size = ui.available_size();
+        list_size.y -= bottom_bar_height;
+
+        ui.allocate_ui(list_size, |ui| {
+            ScrollArea::vertical()
+                .auto_shrink([false, false])
+                .show(ui, |ui| {
+                    trash_manager.with_lock_mut(|trash_manager| {
+                        if trash_manager.entries.is_empty() {
+                            ui.centered_and_justified(|ui| {
+                                ui.label("Trash is empty");
+                            });
+                            return;
+                        }
+
+                        // Most recently deleted first. The previews are kept in the trash manager
+                        // so only what's needed to list the entries is copied.
+                        let entries: Vec<(TrashEntryId, String, DateTime<Utc>)> = trash_manager
+                            .entries
+                            .iter()
+                            .rev()
+                            .map(|entry| {
+                                let title = match &entry.item {
+                                    TrashedItem::Page(_) => "Page".to_string(),
+                                    TrashedItem::Layer { layer, .. } => layer.name.clone(),
+                                };
+                                (entry.id, title, entry.deleted_at)
+                            })
+                            .collect();
+
+                        for (id, title, deleted_at) in entries {
+                            ui.horizontal(|ui| {
+                                let (thumbnail_rect, _) = ui.allocate_exact_size(
+                                    Vec2::splat(THUMBNAIL_SIZE),
+                                    Sense::hover(),
+                                );
+                                Self::show_thumbnail(ui, trash_manager, id, thumbnail_rect);
+
+                                ui.vertical(|ui| {
+                                    ui.label(RichText::new(title).strong());
+                                    ui.label(format!(
+                                        "Deleted {}",
+                                        deleted_at.with_timezone(&Local).format("%b %-d, %Y %H:%M")
+                                    ));
+
+                                    if ui.button("Restore").clicked() {
+                                        restore_id = Some(id);
+                                    }
+                                });
+                            });
+                            ui.separator();
+                        }
+                    });
+                });
+        });
+
+        ui.with_layout(Layout::right_to_left(egui::Align::Center), |ui| {
+            ui.add_space(10.0);
+
+            let is_empty =
+                trash_manager.with_lock(|trash_manager| trash_manager.entries.is_empty());
+            if ui
+                .add_enabled(!is_empty, Button::new("Empty Trash"))
+                .clicked()
+            {
+                trash_manager.with_lock_mut(|trash_manager| trash_manager.empty());
+            }
+
+            let retention = ui.add(DragValue::new(&mut retention_days).range(1..=365));
+            if retention.changed() {
+                ui.data_mut(|data| data.insert_temp(retention_id, retention_days));
+            }
+            if retention.drag_stopped() || retention.lost_focus() {
+                ui.data_mut(|data| data.remove::<u32>(retention_id));
+                config.with_lock_mut(|config| {
+                    if let Err(err) =
+                        config.modify(ConfigModification::SetTrashRetentionDays(retention_days))
+                    {
+                        log::error!("Failed to update trash retention: {:?}", err);
+                    }
+                });
+            }
+            ui.label("Keep deleted items for (days):");
+        });
+
+        match restore_id.and_then(|id| trash_manager.with_lock_mut(|trash| trash.take(id))) {
+            Some(entry) => TrashResponse::Restore(entry),
+            None => TrashResponse::None,
+        }
+    }
+
+    fn show_thumbnail(
+        ui: &mut egui::Ui,
+        trash_manager: &mut TrashManager,
+        id: TrashEntryId,
+        rect: Rect,
+    ) {
+        let Some(preview_state) = trash_manager.preview(id) else {
+            return;
+        };
+
+        Canvas::new(preview_state, rect, &mut CanvasHistoryManager::preview())
+            .show_preview(ui, rect);
+    }
+}
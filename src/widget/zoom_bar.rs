@@ -0,0 +1,79 @@
+use eframe::egui::{self};
+use egui::{DragValue, Layout, Rect, UiBuilder};
+
+use crate::theme;
+
+use super::canvas::{CanvasState, MAX_ZOOM, MIN_ZOOM};
+
+pub const ZOOM_BAR_HEIGHT: f32 = 28.0;
+
+// Zooms one click away in the zoom bar, 100% shows each pixel of the page as a point
+const ZOOM_PRESETS: [f32; 4] = [0.25, 0.5, 1.0, 2.0];
+
+/// The zoom controls shown above the canvas. They change the page's zoom directly so they stay in
+/// step with zooming by the mouse wheel or trackpad.
+pub struct ZoomBar<'a> {
+    state: &'a mut CanvasState,
+    // Where the page is shown, used to fit the page to it
+    canvas_rect: Rect,
+}
+
+impl<'a> ZoomBar<'a> {
+    pub fn new(state: &'a mut CanvasState, canvas_rect: Rect) -> ZoomBar<'a> {
+        ZoomBar { state, canvas_rect }
+    }
+
+    pub fn show(&mut self, ui: &mut egui::Ui, rect: Rect) {
+        ui.painter()
+            .rect_filled(rect, 0.0, theme::color::chrome(ui.visuals()));
+
+        ui.allocate_new_ui(
+            UiBuilder::new()
+                .max_rect(rect.shrink2(egui::vec2(8.0, 0.0)))
+                .layout(Layout::right_to_left(egui::Align::Center)),
+            |ui| {
+                let mut percent = self.state.zoom * 100.0;
+                if ui
+                    .add(
+                        DragValue::new(&mut percent)
+                            .range(MIN_ZOOM * 100.0..=MAX_ZOOM * 100.0)
+                            .max_decimals(0)
+                            .suffix("%"),
+                    )
+                    .on_hover_text("Drag or click to type a zoom")
+                    .changed()
+                {
+                    self.state.set_zoom(percent / 100.0);
+                }
+
+                for preset in ZOOM_PRESETS.iter().rev() {
+                    let selected = (self.state.zoom - preset).abs() < 0.001;
+                    if ui
+                        .selectable_label(selected, format!("{}%", preset * 100.0))
+                        .clicked()
+                    {
+                        self.state.set_zoom(*preset);
+                    }
+                }
+
+                ui.separator();
+
+                if ui
+                    .button("Fit Height")
+                    .on_hover_text("Zoom so the page's height fills the canvas")
+                    .clicked()
+                {
+                    self.state.fit_height(self.canvas_rect);
+                }
+
+                if ui
+                    .button("Fit Width")
+                    .on_hover_text("Zoom so the page's width fills the canvas")
+                    .clicked()
+                {
+                    self.state.fit_width(self.canvas_rect);
+                }
+            },
+        );
+    }
+}